@@ -1,12 +1,13 @@
 use clap::{AppSettings, Parser};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 #[allow(deprecated)]
-use std::env::home_dir;
+use std::{env::home_dir, path::PathBuf};
 
 use error::Error;
 use util::*;
 
 pub mod error;
+mod memory;
 pub mod util;
 
 /// Clap argument parser for the cache subcommand
@@ -52,6 +53,20 @@ pub struct Cache<T> {
     pub expiry: u64,
 }
 
+/// The directory cached objects are stored in, which is `~/.bifrost/cache` unless the
+/// `HEIMDALL_CACHE_DIR` environment variable is set.
+#[allow(deprecated)]
+pub fn cache_dir() -> Result<PathBuf, Error> {
+    if let Some(cache_dir) = std::env::var_os("HEIMDALL_CACHE_DIR") {
+        return Ok(PathBuf::from(cache_dir))
+    }
+
+    let home = home_dir().ok_or(Error::Generic(
+        "failed to get home directory. does your os support `std::env::home_dir()`?".to_string(),
+    ))?;
+    Ok(home.join(".bifrost").join("cache"))
+}
+
 /// Clear the cache, removing all objects
 ///
 /// ```
@@ -69,12 +84,9 @@ pub struct Cache<T> {
 /// /// assert that the cache no longer contains the key
 /// assert!(!keys("*").expect("!").contains(&"clear_cache_key".to_string()));
 /// ```
-#[allow(deprecated)]
 pub fn clear_cache() -> Result<(), Error> {
-    let home = home_dir().ok_or(Error::Generic(
-        "failed to get home directory. does your os support `std::env::home_dir()`?".to_string(),
-    ))?;
-    let cache_dir = home.join(".bifrost").join("cache");
+    let cache_dir = cache_dir()?;
+    memory::clear();
 
     for entry in cache_dir
         .read_dir()
@@ -107,12 +119,8 @@ pub fn clear_cache() -> Result<(), Error> {
 /// /// assert that the cache does not contain a non-existent key
 /// assert!(!exists("non_existent_key").expect("!"));
 /// ```
-#[allow(deprecated)]
 pub fn exists(key: &str) -> Result<bool, Error> {
-    let home = home_dir().ok_or(Error::Generic(
-        "failed to get home directory. does your os support `std::env::home_dir()`?".to_string(),
-    ))?;
-    let cache_dir = home.join(".bifrost").join("cache");
+    let cache_dir = cache_dir()?;
     let cache_file = cache_dir.join(format!("{key}.bin"));
 
     Ok(cache_file.exists())
@@ -135,12 +143,8 @@ pub fn exists(key: &str) -> Result<bool, Error> {
 /// /// assert that the cache contains the key
 /// assert!(keys("keys_*").expect("!").contains(&"keys_key".to_string()));
 /// ```
-#[allow(deprecated)]
 pub fn keys(pattern: &str) -> Result<Vec<String>, Error> {
-    let home = home_dir().ok_or(Error::Generic(
-        "failed to get home directory. does your os support `std::env::home_dir()`?".to_string(),
-    ))?;
-    let cache_dir = home.join(".bifrost").join("cache");
+    let cache_dir = cache_dir()?;
     let mut keys = Vec::new();
 
     // remove wildcard
@@ -159,6 +163,10 @@ pub fn keys(pattern: &str) -> Result<Vec<String>, Error> {
             .to_str()
             .ok_or(Error::Generic("failed to convert path to string".to_string()))?
             .to_string();
+        // skip objects which are still being written
+        if key.ends_with(".tmp") {
+            continue
+        }
         if pattern.is_empty() || key.contains(&pattern) {
             keys.push(key.replace(".bin", ""));
         }
@@ -186,13 +194,10 @@ pub fn keys(pattern: &str) -> Result<Vec<String>, Error> {
 /// /// assert that the cache does not contain the key
 /// assert!(!keys("*").expect("!").contains(&"delete_cache_key".to_string()));
 /// ```
-#[allow(deprecated)]
 pub fn delete_cache(key: &str) -> Result<(), Error> {
-    let home = home_dir().ok_or(Error::Generic(
-        "failed to get home directory. does your os support `std::env::home_dir()`?".to_string(),
-    ))?;
-    let cache_dir = home.join(".bifrost").join("cache");
+    let cache_dir = cache_dir()?;
     let cache_file = cache_dir.join(format!("{key}.bin"));
+    memory::remove(key);
 
     if cache_file.exists() {
         std::fs::remove_file(cache_file)
@@ -202,7 +207,8 @@ pub fn delete_cache(key: &str) -> Result<(), Error> {
     Ok(())
 }
 
-/// Read a cached object
+/// Read a cached object. Objects read or stored by this process are kept in memory, so they can be
+/// shared between threads without reading them from disk again.
 ///
 /// ```
 /// use heimdall_cache::{store_cache, read_cache};
@@ -213,28 +219,31 @@ pub fn delete_cache(key: &str) -> Result<(), Error> {
 /// /// read the cached object
 /// assert_eq!(read_cache::<String>("read_cache_key").expect("!").expect("!"), "value");
 /// ```
-#[allow(deprecated)]
 pub fn read_cache<T>(key: &str) -> Result<Option<T>, Error>
 where
     T: 'static + DeserializeOwned, {
-    let home = home_dir().ok_or(Error::Generic(
-        "failed to get home directory. does your os support `std::env::home_dir()`?".to_string(),
-    ))?;
-    let cache_dir = home.join(".bifrost").join("cache");
-    let cache_file = cache_dir.join(format!("{key}.bin"));
-
-    let binary_string = match read_file(
-        cache_file
-            .to_str()
-            .ok_or(Error::Generic("failed to convert path to string".to_string()))?,
-    ) {
-        Ok(s) => s,
-        Err(_) => return Ok(None),
+    let binary_vec = match memory::get(key) {
+        Some(binary_vec) => binary_vec,
+        None => {
+            let cache_dir = cache_dir()?;
+            let cache_file = cache_dir.join(format!("{key}.bin"));
+
+            let binary_string = match read_file(
+                cache_file
+                    .to_str()
+                    .ok_or(Error::Generic("failed to convert path to string".to_string()))?,
+            ) {
+                Ok(s) => s,
+                Err(_) => return Ok(None),
+            };
+
+            let binary_vec = decode_hex(&binary_string)
+                .map_err(|e| Error::Generic(format!("failed to decode hex: {:?}", e)))?;
+            memory::insert(key, binary_vec.clone());
+            binary_vec
+        }
     };
 
-    let binary_vec = decode_hex(&binary_string)
-        .map_err(|e| Error::Generic(format!("failed to decode hex: {:?}", e)))?;
-
     let cache: Cache<T> = bincode::deserialize::<Cache<T>>(&binary_vec)
         .map_err(|e| Error::Generic(format!("failed to deserialize cache object: {:?}", e)))?;
 
@@ -264,14 +273,10 @@ where
 /// /// add a value to the cache with an expiry time of 1 day
 /// store_cache("store_cache_key2", "value", Some(60 * 60 * 24));
 /// ```
-#[allow(deprecated)]
 pub fn store_cache<T>(key: &str, value: T, expiry: Option<u64>) -> Result<(), Error>
 where
     T: Serialize, {
-    let home = home_dir().ok_or(Error::Generic(
-        "failed to get home directory. does your os support `std::env::home_dir()`?".to_string(),
-    ))?;
-    let cache_dir = home.join(".bifrost").join("cache");
+    let cache_dir = cache_dir()?;
     let cache_file = cache_dir.join(format!("{key}.bin"));
 
     // expire in 90 days
@@ -286,19 +291,19 @@ where
     let cache = Cache { value, expiry };
     let encoded: Vec<u8> = bincode::serialize(&cache)
         .map_err(|e| Error::Generic(format!("failed to serialize cache object: {:?}", e)))?;
-    let binary_string = encode_hex(encoded);
-    write_file(
+    let binary_string = encode_hex(encoded.clone());
+    write_file_atomic(
         cache_file
             .to_str()
             .ok_or(Error::Generic("failed to convert path to string".to_string()))?,
         &binary_string,
     )?;
+    memory::insert(key, encoded);
 
     Ok(())
 }
 
 /// Cache subcommand handler
-#[allow(deprecated)]
 pub fn cache(args: CacheArgs) -> Result<(), Error> {
     match args.sub {
         Subcommands::Clean(_) => {
@@ -314,11 +319,7 @@ pub fn cache(args: CacheArgs) -> Result<(), Error> {
             }
         }
         Subcommands::Size(_) => {
-            let home = home_dir().ok_or(Error::Generic(
-                "failed to get home directory. does your os support `std::env::home_dir()`?"
-                    .to_string(),
-            ))?;
-            let cache_dir = home.join(".bifrost").join("cache");
+            let cache_dir = cache_dir()?;
             let mut size = 0;
 
             for entry in cache_dir
@@ -431,6 +432,28 @@ mod tests {
             .all(|key| { keys("*").unwrap().contains(&key.to_string()) }));
     }
 
+    #[test]
+    fn test_concurrent_store_and_read() {
+        let handles = (0..8u8)
+            .map(|i| {
+                std::thread::spawn(move || {
+                    for _ in 0..16 {
+                        store_cache("concurrent_key", vec![i; 256], None).unwrap();
+                        let value: Vec<u8> = read_cache("concurrent_key").unwrap().unwrap();
+
+                        // a value is never torn between writers
+                        assert!(value.len() == 256 && value.iter().all(|v| *v == value[0]));
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        delete_cache("concurrent_key");
+    }
+
     #[test]
     fn test_exists() {
         assert!(!exists("does_not_exist").unwrap());
@@ -0,0 +1,33 @@
+use std::{collections::BTreeMap, sync::RwLock};
+
+/// The most objects kept in memory. Once full, the in-memory cache is emptied, and objects are
+/// read from disk again as they're needed.
+const MAX_ENTRIES: usize = 16_384;
+
+/// Serialized cache objects which this process has read or stored, shared between threads.
+static MEMORY_CACHE: RwLock<BTreeMap<String, Vec<u8>>> = RwLock::new(BTreeMap::new());
+
+pub(crate) fn get(key: &str) -> Option<Vec<u8>> {
+    MEMORY_CACHE.read().ok().and_then(|entries| entries.get(key).cloned())
+}
+
+pub(crate) fn insert(key: &str, encoded: Vec<u8>) {
+    if let Ok(mut entries) = MEMORY_CACHE.write() {
+        if entries.len() >= MAX_ENTRIES && !entries.contains_key(key) {
+            entries.clear();
+        }
+        entries.insert(key.to_string(), encoded);
+    }
+}
+
+pub(crate) fn remove(key: &str) {
+    if let Ok(mut entries) = MEMORY_CACHE.write() {
+        entries.remove(key);
+    }
+}
+
+pub(crate) fn clear() {
+    if let Ok(mut entries) = MEMORY_CACHE.write() {
+        entries.clear();
+    }
+}
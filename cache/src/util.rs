@@ -5,6 +5,7 @@ use std::{
     num::ParseIntError,
     path::Path,
     process::Command,
+    sync::atomic::{AtomicUsize, Ordering},
 };
 
 use crate::error::Error;
@@ -96,6 +97,30 @@ pub fn write_file(path_str: &str, contents: &str) -> Result<(), Error> {
     Ok(())
 }
 
+/// Write contents to a file on the disc, replacing it in a single step. Concurrent readers see
+/// either the old contents or the new contents, never a partially written file.
+///
+/// ```no_run
+/// use heimdall_cache::util::write_file_atomic;
+///
+/// let result = write_file_atomic("/tmp/test.txt", "Hello, World!");
+/// assert!(result.is_ok());
+/// ```
+pub fn write_file_atomic(path_str: &str, contents: &str) -> Result<(), Error> {
+    static TEMP_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let temp_path = format!(
+        "{path_str}.{}.{}.tmp",
+        std::process::id(),
+        TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+    );
+    write_file(&temp_path, contents)?;
+    std::fs::rename(&temp_path, path_str).map_err(|e| {
+        let _ = std::fs::remove_file(&temp_path);
+        Error::IOError(e)
+    })
+}
+
 /// Read contents from a file on the disc
 ///
 /// ```no_run
@@ -3,8 +3,15 @@ pub(crate) mod output;
 
 use backtrace::Backtrace;
 use error::Error;
-use output::{build_output_path, print_with_less};
-use std::{io, panic};
+use output::{
+    build_output_path, print_output_line, print_with_less, write_output, write_output_lines,
+};
+use std::{
+    io::{self, IsTerminal},
+    panic,
+    path::Path,
+    time::Duration,
+};
 
 use clap::{Parser, Subcommand};
 use colored::Colorize;
@@ -13,35 +20,154 @@ use crossterm::{
     execute,
     terminal::{disable_raw_mode, LeaveAlternateScreen},
 };
+use futures::StreamExt;
 
 use heimdall_cache::{cache, CacheArgs};
-use heimdall_common::utils::{
-    io::{
-        file::{write_file, write_lines_to_file},
-        logging::Logger,
+use heimdall_common::{
+    ether::{
+        artifact::Artifact,
+        batch::{set_rpc_batch_size, set_rpc_concurrency},
+        endpoints::{set_signature_endpoints, set_signature_rate_limit, SignatureEndpoint},
+        explorer::set_explorer_api_key,
+        providers::set_rpc_retries,
+    },
+    utils::{
+        io::{
+            address::{set_address_format, AddressFormat},
+            crash::{set_crash_command, CrashReport},
+            exit::{
+                failure_status, record_failure, report_error, reset_status, set_json_errors,
+                success_status, ExitStatus,
+            },
+            input::read_input,
+            logging::Logger,
+            messages::load_messages,
+            redaction::{register_secret, set_redaction, Redaction},
+            watch::TargetWatcher,
+        },
+        offline::{is_offline, set_offline},
+        version::{current_version, remote_version},
     },
-    version::{current_version, remote_version},
 };
 use heimdall_config::{config, get_config, ConfigArgs};
 use heimdall_core::{
-    cfg::{cfg, output::build_cfg, CFGArgs},
+    address::{address, AddressArgs},
+    asm::{asm, AsmArgs},
+    calls::{calls, output::build_markdown as build_calls_markdown, CallsArgs},
+    cfg::{
+        cfg,
+        output::{build_cfg, build_cfg_json},
+        CFGArgs,
+    },
+    collide::{collide, CollideArgs},
+    debug::{debug, DebugArgs},
     decode::{decode, DecodeArgs},
-    decompile::{decompile, out::abi::ABIStructure, DecompilerArgs},
+    decompile::{decompile, out::abi::ABIStructure, stream::decompile_stream, DecompilerArgs},
+    diff::{diff, DiffArgs},
     disassemble::{disassemble, DisassemblerArgs},
     dump::{dump, DumpArgs},
+    evaluate::{evaluate, output::build_markdown as build_evaluate_markdown, EvaluateArgs},
+    fingerprint::{fingerprint, FingerprintArgs},
     inspect::{inspect, InspectArgs},
-    snapshot::{snapshot, util::csv::generate_csv, SnapshotArgs},
+    interface::{interface, InterfaceArgs},
+    labels::{labels, LabelsArgs},
+    layout::{layout, output::build_markdown as build_layout_markdown, LayoutArgs},
+    lineage::{lineage, output::build_markdown, LineageArgs},
+    locate::{locate, LocateArgs},
+    map::{
+        map,
+        output::{build_dot as build_map_dot, build_mermaid},
+        MapArgs,
+    },
+    metrics::{metrics, MetricsArgs},
+    minimize::{minimize, MinimizeArgs},
+    predeploy::{predeploy, PredeployArgs},
+    query::{query, QueryArgs},
+    roles::{output::build_dot, roles, RolesArgs},
+    scan::{scan, ScanArgs},
+    simulate::{simulate, SimulateArgs},
+    snapshot::{diff::SnapshotSummary, snapshot, util::csv::generate_csv, SnapshotArgs},
+    stats::{stats, StatsArgs},
+    storage_layout::{output::build_table, storage_layout, StorageLayoutArgs},
+    usage::{output::build_markdown as build_usage_markdown, usage, UsageArgs},
 };
 use tui::{backend::CrosstermBackend, Terminal};
 
-#[derive(Debug, Parser)]
+#[derive(Debug, Clone, Parser)]
 #[clap(name = "heimdall", author = "Jonathan Becker <jonathan@jbecker.dev>", version)]
 pub struct Arguments {
     #[clap(subcommand)]
     pub sub: Subcommands,
+
+    /// A comma separated list of signature providers to resolve selectors with, in order of
+    /// preference: `etherscan` and `sourcify` for the target's verified ABI, and `openchain` and
+    /// `4byte` for signature databases. Databases may be given a URL, e.g.
+    /// `openchain=https://api.openchain.xyz/signature-database/v1/lookup`. Overrides the
+    /// configured endpoints.
+    #[clap(
+        long = "signature-endpoints",
+        global = true,
+        default_value = "",
+        hide_default_value = true
+    )]
+    pub signature_endpoints: String,
+
+    /// The most requests made to the signature databases per second, or `0` for no limit.
+    /// Overrides the configured rate limit.
+    #[clap(long = "signature-rate-limit", global = true)]
+    pub signature_rate_limit: Option<u32>,
+
+    /// The most queries sent to the RPC provider in a single JSON-RPC batch, or `1` for providers
+    /// which don't accept batches. Overrides the configured batch size.
+    #[clap(long = "rpc-batch-size", global = true)]
+    pub rpc_batch_size: Option<usize>,
+
+    /// The most JSON-RPC batches sent to the RPC provider at once. Overrides the configured
+    /// concurrency.
+    #[clap(long = "rpc-concurrency", global = true)]
+    pub rpc_concurrency: Option<usize>,
+
+    /// The number of times a failed request to the RPC provider is retried, with jittered
+    /// exponential backoff. Several providers may be given to `--rpc-url` separated by commas,
+    /// and failed requests rotate between them. Overrides the configured retries.
+    #[clap(long = "rpc-retries", global = true)]
+    pub rpc_retries: Option<u32>,
+
+    /// Never access the network. Lookups fall back to the local cache, and operations which need
+    /// an RPC provider or remote API fail.
+    #[clap(long, global = true)]
+    pub offline: bool,
+
+    /// A comma separated list of details to redact from output, so it can be shared: `urls`,
+    /// `keys`, `paths`, `annotations`, and `bytecode`, which is replaced by its hash, or `all`.
+    #[clap(long, global = true, default_value = "", hide_default_value = true)]
+    pub redact: String,
+
+    /// When a command fails, write a JSON error report with its exit status to stderr.
+    #[clap(long = "json-errors", global = true)]
+    pub json_errors: bool,
+
+    /// How addresses are written in every output: `checksum` (EIP-55), `lowercase`, or `short`,
+    /// which keeps only their first and last four digits.
+    #[clap(long = "address-format", global = true, default_value = "checksum")]
+    pub address_format: String,
+
+    /// A TOML file of messages to write reports with instead of their English defaults, i.e.
+    /// `governance.title = "Gouvernance"`.
+    #[clap(long, global = true, default_value = "", hide_default_value = true)]
+    pub messages: String,
+
+    /// Re-run the command whenever its target file changes, until interrupted. Rewrites which
+    /// don't change the target, such as recompiling an artifact whose bytecode is the same, are
+    /// ignored.
+    #[clap(long, global = true)]
+    pub watch: bool,
 }
 
-#[derive(Debug, Subcommand)]
+/// How often a watched target is checked for changes.
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Subcommand)]
 #[clap(
     about = "Heimdall is an advanced Ethereum smart contract toolkit for forensic and heuristic analysis.",
     after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki"
@@ -81,6 +207,240 @@ pub enum Subcommands {
     consumption, storage accesses, event emissions, and more"
     )]
     Snapshot(SnapshotArgs),
+
+    #[clap(
+        name = "minimize",
+        about = "Shrink bytecode which crashes or mis-decompiles down to a minimal reproducer"
+    )]
+    Minimize(MinimizeArgs),
+
+    #[clap(name = "debug", about = "Step through EVM bytecode one instruction at a time")]
+    Debug(DebugArgs),
+
+    #[clap(
+        name = "simulate",
+        about = "Simulate a call to EVM bytecode and report the resulting state"
+    )]
+    Simulate(SimulateArgs),
+
+    #[clap(name = "predeploy", about = "Predict what a contract-creation transaction will deploy")]
+    Predeploy(PredeployArgs),
+
+    #[clap(
+        name = "collide",
+        about = "Search for function signatures which hash to a given selector"
+    )]
+    Collide(CollideArgs),
+
+    #[clap(
+        name = "address",
+        about = "Compute the address a contract is deployed to with CREATE or CREATE2"
+    )]
+    Address(AddressArgs),
+
+    #[clap(
+        name = "interface",
+        about = "Generate a Solidity interface or typed bindings for EVM bytecode"
+    )]
+    Interface(InterfaceArgs),
+
+    #[clap(
+        name = "metrics",
+        about = "Report instruction, control flow, and stack metrics for EVM bytecode"
+    )]
+    Metrics(MetricsArgs),
+
+    #[clap(
+        name = "fingerprint",
+        about = "Fingerprint functions and match them against a corpus of contracts"
+    )]
+    Fingerprint(FingerprintArgs),
+
+    #[clap(name = "scan", about = "Scan bytecode for signatures of known vulnerabilities")]
+    Scan(ScanArgs),
+
+    #[clap(
+        name = "stats",
+        about = "Report selector frequencies, clusters, and unresolved selectors across a corpus"
+    )]
+    Stats(StatsArgs),
+
+    #[clap(name = "diff", about = "Compare two bytecodes' functions, basic blocks, and constants")]
+    Diff(DiffArgs),
+
+    #[clap(
+        name = "query",
+        about = "Query the functions, calls, storage, and guards recovered from a contract"
+    )]
+    Query(QueryArgs),
+
+    #[clap(
+        name = "asm",
+        about = "Assemble mnemonics into bytecode, or patch them into a basic block of a bytecode"
+    )]
+    Asm(AsmArgs),
+
+    #[clap(
+        name = "labels",
+        about = "Manage the labels used to enrich addresses, selectors, and storage slots in output"
+    )]
+    Labels(LabelsArgs),
+
+    #[clap(
+        name = "layout",
+        about = "Recover a contract's storage layout, and check a proxy's layout against its implementation"
+    )]
+    Layout(LayoutArgs),
+
+    #[clap(
+        name = "storage-layout",
+        about = "Reconstruct a contract's state variables as a solc-style storage layout"
+    )]
+    StorageLayout(StorageLayoutArgs),
+
+    #[clap(
+        name = "roles",
+        about = "Recover an AccessControl contract's roles, their admins, and the functions they gate"
+    )]
+    Roles(RolesArgs),
+
+    #[clap(
+        name = "calls",
+        about = "List the external calls each function makes, and who controls where they go"
+    )]
+    Calls(CallsArgs),
+
+    #[clap(
+        name = "map",
+        about = "Map the system of contracts reachable from a contract, and how they call each other"
+    )]
+    Map(MapArgs),
+
+    #[clap(
+        name = "lineage",
+        about = "Trace who deployed a contract, what else they deployed, and a proxy's past implementations"
+    )]
+    Lineage(LineageArgs),
+
+    #[clap(
+        name = "usage",
+        about = "Report how a contract's functions are called in practice, from its transaction history"
+    )]
+    Usage(UsageArgs),
+
+    #[clap(
+        name = "evaluate",
+        alias = "eval",
+        about = "Evaluate the decompiler against compiled contracts, using their artifacts as ground truth"
+    )]
+    Evaluate(EvaluateArgs),
+
+    #[clap(
+        name = "locate",
+        about = "Find the decompiled function, statement, and expression at a program counter"
+    )]
+    Locate(LocateArgs),
+}
+
+impl Subcommands {
+    /// The subcommand's target, i.e. the bytecode, calldata, or address it operates on, if it
+    /// has one.
+    fn target_mut(&mut self) -> Option<&mut String> {
+        match self {
+            Subcommands::Disassemble(cmd) => Some(&mut cmd.target),
+            Subcommands::Decompile(cmd) => Some(&mut cmd.target),
+            Subcommands::CFG(cmd) => Some(&mut cmd.target),
+            Subcommands::Decode(cmd) => Some(&mut cmd.target),
+            Subcommands::Dump(cmd) => Some(&mut cmd.target),
+            Subcommands::Inspect(cmd) => Some(&mut cmd.target),
+            Subcommands::Snapshot(cmd) => Some(&mut cmd.target),
+            Subcommands::Minimize(cmd) => Some(&mut cmd.target),
+            Subcommands::Debug(cmd) => Some(&mut cmd.target),
+            Subcommands::Simulate(cmd) => Some(&mut cmd.target),
+            Subcommands::Predeploy(cmd) => Some(&mut cmd.target),
+            Subcommands::Interface(cmd) => Some(&mut cmd.target),
+            Subcommands::Metrics(cmd) => Some(&mut cmd.target),
+            Subcommands::Fingerprint(cmd) => Some(&mut cmd.target),
+            Subcommands::Scan(cmd) => Some(&mut cmd.target),
+            Subcommands::Layout(cmd) => Some(&mut cmd.target),
+            Subcommands::StorageLayout(cmd) => Some(&mut cmd.target),
+            Subcommands::Roles(cmd) => Some(&mut cmd.target),
+            Subcommands::Calls(cmd) => Some(&mut cmd.target),
+            Subcommands::Map(cmd) => Some(&mut cmd.target),
+            Subcommands::Lineage(cmd) => Some(&mut cmd.target),
+            Subcommands::Usage(cmd) => Some(&mut cmd.target),
+            Subcommands::Locate(cmd) => Some(&mut cmd.target),
+            Subcommands::Config(_) |
+            Subcommands::Cache(_) |
+            Subcommands::Collide(_) |
+            Subcommands::Address(_) |
+            Subcommands::Labels(_) |
+            Subcommands::Stats(_) |
+            Subcommands::Diff(_) |
+            Subcommands::Query(_) |
+            Subcommands::Asm(_) |
+            Subcommands::Evaluate(_) => None,
+        }
+    }
+
+    /// The secrets passed to the subcommand, such as RPC URLs and API keys, which are redacted
+    /// from output along with those in the configuration.
+    fn secrets(&self) -> Vec<&str> {
+        match self {
+            Subcommands::Disassemble(cmd) => vec![&cmd.rpc_url],
+            Subcommands::Decompile(cmd) => vec![&cmd.rpc_url],
+            Subcommands::CFG(cmd) => vec![&cmd.rpc_url],
+            Subcommands::Dump(cmd) => vec![&cmd.rpc_url, &cmd.transpose_api_key],
+            Subcommands::Snapshot(cmd) => vec![&cmd.rpc_url],
+            Subcommands::Minimize(cmd) => vec![&cmd.rpc_url],
+            Subcommands::Debug(cmd) => vec![&cmd.rpc_url],
+            Subcommands::Simulate(cmd) => vec![&cmd.rpc_url],
+            Subcommands::Predeploy(cmd) => vec![&cmd.rpc_url],
+            Subcommands::Interface(cmd) => vec![&cmd.rpc_url],
+            Subcommands::Metrics(cmd) => vec![&cmd.rpc_url],
+            Subcommands::Fingerprint(cmd) => vec![&cmd.rpc_url],
+            Subcommands::Scan(cmd) => vec![&cmd.rpc_url],
+            Subcommands::Diff(cmd) => vec![&cmd.rpc_url],
+            Subcommands::Query(cmd) => vec![&cmd.rpc_url],
+            Subcommands::Asm(cmd) => vec![&cmd.rpc_url],
+            Subcommands::Layout(cmd) => vec![&cmd.rpc_url],
+            Subcommands::StorageLayout(cmd) => vec![&cmd.rpc_url],
+            Subcommands::Roles(cmd) => vec![&cmd.rpc_url],
+            Subcommands::Calls(cmd) => vec![&cmd.rpc_url],
+            Subcommands::Map(cmd) => vec![&cmd.rpc_url],
+            Subcommands::Lineage(cmd) => vec![&cmd.rpc_url],
+            Subcommands::Usage(cmd) => vec![&cmd.rpc_url],
+            Subcommands::Locate(cmd) => vec![&cmd.rpc_url],
+            Subcommands::Decode(cmd) => vec![&cmd.rpc_url, &cmd.openai_api_key],
+            Subcommands::Inspect(cmd) => vec![&cmd.rpc_url, &cmd.transpose_api_key],
+            Subcommands::Config(_) |
+            Subcommands::Cache(_) |
+            Subcommands::Collide(_) |
+            Subcommands::Address(_) |
+            Subcommands::Labels(_) |
+            Subcommands::Stats(_) |
+            Subcommands::Evaluate(_) => Vec::new(),
+        }
+    }
+}
+
+/// Offer to write a redacted crash report to the current directory. When stdin isn't
+/// interactive, don't write one.
+fn offer_crash_report(logger: &Logger, message: &str, frames: Vec<String>) {
+    let interactive = io::stdin().is_terminal();
+    let selection = logger.option(
+        "warn",
+        "would you like to write a crash report?",
+        vec!["yes".to_string(), "no".to_string()],
+        Some(if interactive { 0 } else { 1 }),
+        !interactive,
+    );
+    if selection == 0 {
+        match CrashReport::new(message, frames).write(".") {
+            Ok(path) => logger.info(&format!("wrote crash report to '{path}' .")),
+            Err(e) => logger.error(&format!("failed to write crash report: {e}")),
+        }
+    }
 }
 
 #[tokio::main]
@@ -107,9 +467,206 @@ async fn main() -> Result<(), Error> {
             panic_info.to_string().bright_white().on_bright_red().bold(),
         ));
         logger.fatal(&format!("Stack Trace:\n\n{backtrace:#?}"));
+        report_error(ExitStatus::InternalError, &panic_info.to_string());
+
+        offer_crash_report(&logger, &panic_info.to_string(), heimdall_frames(&backtrace));
     }));
 
+    set_crash_command(match &args.sub {
+        Subcommands::Disassemble(_) => "disassemble",
+        Subcommands::Decompile(_) => "decompile",
+        Subcommands::CFG(_) => "cfg",
+        Subcommands::Decode(_) => "decode",
+        Subcommands::Config(_) => "config",
+        Subcommands::Cache(_) => "cache",
+        Subcommands::Dump(_) => "dump",
+        Subcommands::Inspect(_) => "inspect",
+        Subcommands::Snapshot(_) => "snapshot",
+        Subcommands::Minimize(_) => "minimize",
+        Subcommands::Debug(_) => "debug",
+        Subcommands::Simulate(_) => "simulate",
+        Subcommands::Predeploy(_) => "predeploy",
+        Subcommands::Collide(_) => "collide",
+        Subcommands::Address(_) => "address",
+        Subcommands::Interface(_) => "interface",
+        Subcommands::Metrics(_) => "metrics",
+        Subcommands::Fingerprint(_) => "fingerprint",
+        Subcommands::Scan(_) => "scan",
+        Subcommands::Stats(_) => "stats",
+        Subcommands::Diff(_) => "diff",
+        Subcommands::Query(_) => "query",
+        Subcommands::Asm(_) => "asm",
+        Subcommands::Labels(_) => "labels",
+        Subcommands::Layout(_) => "layout",
+        Subcommands::StorageLayout(_) => "storage-layout",
+        Subcommands::Roles(_) => "roles",
+        Subcommands::Calls(_) => "calls",
+        Subcommands::Map(_) => "map",
+        Subcommands::Lineage(_) => "lineage",
+        Subcommands::Usage(_) => "usage",
+        Subcommands::Evaluate(_) => "evaluate",
+        Subcommands::Locate(_) => "locate",
+    });
+
+    set_offline(args.offline);
+    set_json_errors(args.json_errors);
+
+    let result = match args.watch {
+        true => watch(args).await,
+        false => run(args).await,
+    };
+    if let Err(e) = result {
+        let (logger, _) = Logger::new("TRACE");
+        logger.error(&e.to_string());
+
+        let status = failure_status();
+        report_error(status, &e.to_string());
+
+        // only internal errors are worth a crash report
+        if status != ExitStatus::InternalError {
+            std::process::exit(status.code());
+        }
+
+        offer_crash_report(&logger, &e.to_string(), Vec::new());
+        return Err(e)
+    }
+
+    // check if the version is up to date
+    if !is_offline() {
+        check_version().await;
+    }
+
+    // a partial analysis still writes output, but is reported through the exit code
+    let status = success_status();
+    if status != ExitStatus::Success {
+        std::process::exit(status.code());
+    }
+
+    Ok(())
+}
+
+/// Lets the user know if a newer version of heimdall is available.
+async fn check_version() {
+    let remote_version = remote_version().await;
+    let current_version = current_version();
+
+    if remote_version.gt(&current_version) {
+        let (logger, _) = Logger::new("TRACE");
+        println!();
+        logger.info("great news! An update is available!");
+        logger
+            .info(&format!("you can update now by running: `bifrost --version {remote_version}`"));
+    }
+}
+
+/// Returns the frames of the given backtrace which belong to heimdall, as strings.
+fn heimdall_frames(backtrace: &Backtrace) -> Vec<String> {
+    backtrace
+        .frames()
+        .iter()
+        .flat_map(|frame| frame.symbols())
+        .filter_map(|symbol| symbol.name().map(|name| name.to_string()))
+        .filter(|name| name.contains("heimdall"))
+        .collect()
+}
+
+/// Re-runs the parsed subcommand whenever its target file changes. Each run gets its own copy of
+/// the arguments, since they're consumed by [`run`], and a failed run is reported without ending
+/// the watch. Resolved signatures are cached, so runs after the first only resolve new selectors.
+async fn watch(mut args: Arguments) -> Result<(), Error> {
+    let (logger, _) = Logger::new("TRACE");
+
+    let path = match args.sub.target_mut() {
+        Some(target) if Path::new(target).is_file() => target.clone(),
+        _ => {
+            record_failure(ExitStatus::InvalidArguments);
+            return Err(Error::Generic("--watch requires the target to be a file.".to_string()))
+        }
+    };
+
+    let mut watcher = TargetWatcher::new(&path);
+    logger.info(&format!("watching '{path}' for changes."));
+    loop {
+        if watcher.poll().map_err(|e| Error::Generic(e.to_string()))? {
+            // each run reads the target afresh, and is reported on its own
+            reset_status();
+            if let Err(e) = run(args.clone()).await {
+                logger.error(&e.to_string());
+            }
+            logger.info(&format!("waiting for '{path}' to change."));
+        }
+        tokio::time::sleep(WATCH_INTERVAL).await;
+    }
+}
+
+/// Dispatches the parsed subcommand.
+async fn run(mut args: Arguments) -> Result<(), Error> {
     let configuration = get_config();
+
+    // a compiler artifact given as the target is also ground truth for the decompiler
+    if let Subcommands::Decompile(cmd) = &mut args.sub {
+        if cmd.artifact.is_empty() && Artifact::read(&cmd.target).is_ok() {
+            cmd.artifact = cmd.target.clone();
+        }
+    }
+
+    // targets may be given inline, as a file, or as `-` to read them from stdin, so heimdall can
+    // be piped into
+    if let Some(target) = args.sub.target_mut() {
+        *target = read_input(target).map_err(|e| {
+            record_failure(ExitStatus::InvalidArguments);
+            Error::Generic(format!("failed to read target: {}", e))
+        })?;
+    }
+
+    // resolve selectors with the endpoints given on the command line, or the configured ones
+    set_signature_endpoints(if args.signature_endpoints.is_empty() {
+        configuration.signature_endpoints.clone()
+    } else {
+        SignatureEndpoint::parse_list(&args.signature_endpoints).map_err(|e| {
+            record_failure(ExitStatus::InvalidArguments);
+            Error::Generic(format!("invalid signature endpoints: {}", e))
+        })?
+    });
+    set_signature_rate_limit(
+        args.signature_rate_limit.unwrap_or(configuration.signature_rate_limit),
+    );
+    set_rpc_batch_size(args.rpc_batch_size.unwrap_or(configuration.rpc_batch_size));
+    set_rpc_concurrency(args.rpc_concurrency.unwrap_or(configuration.rpc_concurrency));
+    set_rpc_retries(args.rpc_retries.unwrap_or(configuration.rpc_retries));
+    set_explorer_api_key(&configuration.etherscan_api_key);
+
+    set_address_format(AddressFormat::parse(&args.address_format).map_err(|e| {
+        record_failure(ExitStatus::InvalidArguments);
+        Error::Generic(format!("invalid address format: {}", e))
+    })?);
+
+    if !args.messages.is_empty() {
+        load_messages(&args.messages).map_err(|e| {
+            record_failure(ExitStatus::InvalidArguments);
+            Error::Generic(format!("invalid message catalog: {}", e))
+        })?;
+    }
+
+    // redact secrets and environment details from output
+    set_redaction(Redaction::parse(&args.redact).map_err(|e| {
+        record_failure(ExitStatus::InvalidArguments);
+        Error::Generic(format!("invalid redaction: {}", e))
+    })?);
+    for secret in [
+        &configuration.rpc_url,
+        &configuration.local_rpc_url,
+        &configuration.etherscan_api_key,
+        &configuration.transpose_api_key,
+        &configuration.openai_api_key,
+    ]
+    .into_iter()
+    .map(String::as_str)
+    .chain(args.sub.secrets())
+    {
+        register_secret(secret);
+    }
+
     match args.sub {
         Subcommands::Disassemble(mut cmd) => {
             // if the user has not specified a rpc url, use the default
@@ -118,7 +675,10 @@ async fn main() -> Result<(), Error> {
             }
 
             // if the user has passed an output filename, override the default filename
-            let mut filename: String = "disassembled.asm".to_string();
+            let mut filename = match cmd.format.as_str() {
+                "json" => "disassembled.json".to_string(),
+                _ => "disassembled.asm".to_string(),
+            };
             let given_name = cmd.name.as_str();
 
             if !given_name.is_empty() {
@@ -141,7 +701,7 @@ async fn main() -> Result<(), Error> {
                             Error::Generic(format!("failed to build output path: {}", e))
                         })?;
 
-                write_file(&output_path, &assembly);
+                write_output(&output_path, &assembly);
             }
         }
 
@@ -154,13 +714,29 @@ async fn main() -> Result<(), Error> {
             // if the user has passed an output filename, override the default filename
             let mut abi_filename: String = "abi.json".to_string();
             let mut decompiled_output_filename: String = "decompiled".to_string();
+            let mut evaluation_filename: String = "evaluation.json".to_string();
+            let mut interface_filename: String = "interface.sol".to_string();
 
             let given_name = cmd.name.as_str();
 
             if !given_name.is_empty() {
                 abi_filename = format!("{}-{}", given_name, abi_filename);
+                interface_filename = format!("{}-{}", given_name, interface_filename);
                 decompiled_output_filename =
                     format!("{}-{}", given_name, decompiled_output_filename);
+                evaluation_filename = format!("{}-{}", given_name, evaluation_filename);
+            }
+
+            // print each function as it's analyzed, rather than writing the output at the end
+            if cmd.stream {
+                let mut functions = Box::pin(decompile_stream(cmd));
+                while let Some(function) = functions.next().await {
+                    let function = function.map_err(|e| {
+                        Error::Generic(format!("failed to decompile bytecode: {}", e))
+                    })?;
+                    print_output_line(&serde_json::to_string(&function)?);
+                }
+                return Ok(())
             }
 
             let result = decompile(cmd.clone())
@@ -177,6 +753,15 @@ async fn main() -> Result<(), Error> {
                 if let Some(source) = &result.source {
                     output_str.push_str(&format!("Source:\n\n{}\n", source));
                 }
+                if let Some(interface) = &result.interface {
+                    output_str.push_str(&format!("Interface:\n\n{}\n", interface));
+                }
+                if let Some(verified) = &result.verified {
+                    for (path, content) in &verified.sources {
+                        output_str
+                            .push_str(&format!("Verified Source ({}):\n\n{}\n", path, content));
+                    }
+                }
 
                 print_with_less(&output_str).await.map_err(|e| {
                     Error::Generic(format!("failed to print decompiled bytecode: {}", e))
@@ -191,7 +776,7 @@ async fn main() -> Result<(), Error> {
                                 Error::Generic(format!("failed to build output path: {}", e))
                             })?;
 
-                    write_file(
+                    write_output(
                         &output_path,
                         &format!(
                             "[{}]",
@@ -239,7 +824,55 @@ async fn main() -> Result<(), Error> {
                             Error::Generic(format!("failed to build output path: {}", e))
                         })?
                     };
-                    write_file(&output_path, source);
+                    write_output(&output_path, source);
+                }
+
+                // write the contract interface
+                if let Some(interface) = &result.interface {
+                    let output_path = build_output_path(
+                        &cmd.output,
+                        &cmd.target,
+                        &cmd.rpc_url,
+                        &interface_filename,
+                    )
+                    .await
+                    .map_err(|e| Error::Generic(format!("failed to build output path: {}", e)))?;
+                    write_output(&output_path, interface);
+                }
+
+                // write the evaluation against the target's artifact
+                if let Some(evaluation) = &result.evaluation {
+                    let output_path = build_output_path(
+                        &cmd.output,
+                        &cmd.target,
+                        &cmd.rpc_url,
+                        &evaluation_filename,
+                    )
+                    .await
+                    .map_err(|e| Error::Generic(format!("failed to build output path: {}", e)))?;
+                    write_output(&output_path, &serde_json::to_string_pretty(evaluation)?);
+                }
+
+                // write the verified source files, keeping their relative paths
+                if let Some(verified) = &result.verified {
+                    for (path, content) in &verified.sources {
+                        let relative_path = path
+                            .split('/')
+                            .filter(|part| !part.is_empty() && *part != "." && *part != "..")
+                            .collect::<Vec<&str>>()
+                            .join("/");
+                        let output_path = build_output_path(
+                            &cmd.output,
+                            &cmd.target,
+                            &cmd.rpc_url,
+                            &format!("verified/{}", relative_path),
+                        )
+                        .await
+                        .map_err(|e| {
+                            Error::Generic(format!("failed to build output path: {}", e))
+                        })?;
+                        write_output(&output_path, content);
+                    }
                 }
             }
         }
@@ -269,20 +902,24 @@ async fn main() -> Result<(), Error> {
                 cmd.rpc_url = configuration.rpc_url;
             }
 
+            let cfg = cfg(cmd.clone())
+                .await
+                .map_err(|e| Error::Generic(format!("failed to generate cfg: {}", e)))?;
+            let (output, extension) = match cmd.format.as_str() {
+                "json" => (serde_json::to_string_pretty(&build_cfg_json(&cfg))?, "json"),
+                _ => (build_cfg(&cfg, &cmd), "dot"),
+            };
+
             // if the user has passed an output filename, override the default filename
-            let mut filename = "cfg.dot".to_string();
+            let mut filename = format!("cfg.{}", extension);
             let given_name = cmd.name.as_str();
 
             if !given_name.is_empty() {
                 filename = format!("{}-{}", given_name, filename);
             }
-            let cfg = cfg(cmd.clone())
-                .await
-                .map_err(|e| Error::Generic(format!("failed to generate cfg: {}", e)))?;
-            let stringified_dot = build_cfg(&cfg, &cmd);
 
             if cmd.output == "print" {
-                print_with_less(&stringified_dot)
+                print_with_less(&output)
                     .await
                     .map_err(|e| Error::Generic(format!("failed to print cfg: {}", e)))?;
             } else {
@@ -292,7 +929,7 @@ async fn main() -> Result<(), Error> {
                         .map_err(|e| {
                             Error::Generic(format!("failed to build output path: {}", e))
                         })?;
-                write_file(&output_path, &stringified_dot);
+                write_output(&output_path, &output);
             }
         }
 
@@ -343,7 +980,7 @@ async fn main() -> Result<(), Error> {
                             Error::Generic(format!("failed to build output path: {}", e))
                         })?;
 
-                write_lines_to_file(&output_path, lines);
+                write_output_lines(&output_path, lines);
             }
         }
 
@@ -355,10 +992,20 @@ async fn main() -> Result<(), Error> {
 
             // if the user has passed an output filename, override the default filename
             let mut filename = "snapshot.csv".to_string();
+            let mut summary_filename = "snapshot.json".to_string();
+            let mut diff_filename = "snapshot-diff.json".to_string();
+            let mut governance_filename = "governance.md".to_string();
+            let mut funds_filename = "funds.md".to_string();
+            let mut reverts_filename = "reverts.md".to_string();
             let given_name = cmd.name.as_str();
 
             if !given_name.is_empty() {
                 filename = format!("{}-{}", given_name, filename);
+                summary_filename = format!("{}-{}", given_name, summary_filename);
+                diff_filename = format!("{}-{}", given_name, diff_filename);
+                governance_filename = format!("{}-{}", given_name, governance_filename);
+                funds_filename = format!("{}-{}", given_name, funds_filename);
+                reverts_filename = format!("{}-{}", given_name, reverts_filename);
             }
 
             let snapshot_result = snapshot(cmd.clone())
@@ -369,9 +1016,21 @@ async fn main() -> Result<(), Error> {
                 &snapshot_result.resolved_errors,
                 &snapshot_result.resolved_events,
             );
+            let summary = SnapshotSummary::from_snapshots(&snapshot_result.snapshots);
 
             if cmd.output == "print" {
-                print_with_less(&csv_lines.join("\n"))
+                let mut output_str = csv_lines.join("\n");
+                output_str.push_str(&format!("\n\n{}", summary.governance.to_markdown()));
+                output_str.push_str(&format!("\n\n{}", summary.reverts.to_markdown()));
+                if let Some(funds) = &snapshot_result.funds {
+                    output_str.push_str(&format!("\n\n{}", funds.to_markdown()));
+                }
+                if let Some(diff) = &snapshot_result.diff {
+                    output_str
+                        .push_str(&format!("\n\nDiff:\n\n{}", serde_json::to_string_pretty(diff)?));
+                }
+
+                print_with_less(&output_str)
                     .await
                     .map_err(|e| Error::Generic(format!("failed to print snapshot: {}", e)))?;
             } else {
@@ -382,7 +1041,52 @@ async fn main() -> Result<(), Error> {
                             Error::Generic(format!("failed to build output path: {}", e))
                         })?;
 
-                write_lines_to_file(&output_path, csv_lines);
+                write_output_lines(&output_path, csv_lines);
+
+                // the summary can be passed to a later run with `--diff`
+                let summary_path =
+                    build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, &summary_filename)
+                        .await
+                        .map_err(|e| {
+                            Error::Generic(format!("failed to build output path: {}", e))
+                        })?;
+                write_output(&summary_path, &serde_json::to_string_pretty(&summary)?);
+
+                let governance_path =
+                    build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, &governance_filename)
+                        .await
+                        .map_err(|e| {
+                            Error::Generic(format!("failed to build output path: {}", e))
+                        })?;
+                write_output(&governance_path, &summary.governance.to_markdown());
+
+                let reverts_path =
+                    build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, &reverts_filename)
+                        .await
+                        .map_err(|e| {
+                            Error::Generic(format!("failed to build output path: {}", e))
+                        })?;
+                write_output(&reverts_path, &summary.reverts.to_markdown());
+
+                if let Some(funds) = &snapshot_result.funds {
+                    let funds_path =
+                        build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, &funds_filename)
+                            .await
+                            .map_err(|e| {
+                                Error::Generic(format!("failed to build output path: {}", e))
+                            })?;
+                    write_output(&funds_path, &funds.to_markdown());
+                }
+
+                if let Some(diff) = &snapshot_result.diff {
+                    let diff_path =
+                        build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, &diff_filename)
+                            .await
+                            .map_err(|e| {
+                                Error::Generic(format!("failed to build output path: {}", e))
+                            })?;
+                    write_output(&diff_path, &serde_json::to_string_pretty(diff)?);
+                }
             }
         }
 
@@ -434,29 +1138,851 @@ async fn main() -> Result<(), Error> {
                             Error::Generic(format!("failed to build output path: {}", e))
                         })?;
 
-                write_file(&output_path, &serde_json::to_string_pretty(&decoded_trace)?);
+                write_output(&output_path, &serde_json::to_string_pretty(&decoded_trace)?);
             }
         }
 
-        Subcommands::Config(cmd) => {
-            config(cmd);
+        Subcommands::Minimize(mut cmd) => {
+            // if the user has not specified a rpc url, use the default
+            if cmd.rpc_url.as_str() == "" {
+                cmd.rpc_url = configuration.rpc_url;
+            }
+
+            // if the user has passed an output filename, override the default filename
+            let mut filename = "minimized.bin".to_string();
+            let given_name = cmd.name.as_str();
+
+            if !given_name.is_empty() {
+                filename = format!("{}-{}", given_name, filename);
+            }
+
+            let result = minimize(cmd.clone())
+                .await
+                .map_err(|e| Error::Generic(format!("failed to minimize bytecode: {}", e)))?;
+
+            if cmd.output == "print" {
+                print_with_less(&result.bytecode).await.map_err(|e| {
+                    Error::Generic(format!("failed to print minimized bytecode: {}", e))
+                })?;
+            } else {
+                let output_path =
+                    build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, &filename)
+                        .await
+                        .map_err(|e| {
+                            Error::Generic(format!("failed to build output path: {}", e))
+                        })?;
+
+                write_output(&output_path, &result.bytecode);
+            }
         }
 
-        Subcommands::Cache(cmd) => {
-            _ = cache(cmd);
+        Subcommands::Debug(mut cmd) => {
+            // if the user has not specified a rpc url, use the default
+            if cmd.rpc_url.as_str() == "" {
+                cmd.rpc_url = configuration.rpc_url;
+            }
+
+            debug(cmd)
+                .await
+                .map_err(|e| Error::Generic(format!("failed to debug bytecode: {}", e)))?;
         }
-    }
 
-    // check if the version is up to date
-    let remote_version = remote_version().await;
-    let current_version = current_version();
+        Subcommands::Simulate(mut cmd) => {
+            // if the user has not specified a rpc url, use the default
+            if cmd.rpc_url.as_str() == "" {
+                cmd.rpc_url = configuration.rpc_url;
+            }
 
-    if remote_version.gt(&current_version) {
-        let (logger, _) = Logger::new("TRACE");
-        println!();
-        logger.info("great news! An update is available!");
-        logger
-            .info(&format!("you can update now by running: `bifrost --version {remote_version}`"));
+            // if the user has passed an output filename, override the default filename
+            let mut filename = "simulation.json".to_string();
+            let given_name = cmd.name.as_str();
+
+            if !given_name.is_empty() {
+                filename = format!("{}-{}", given_name, filename);
+            }
+
+            let result = simulate(cmd.clone())
+                .await
+                .map_err(|e| Error::Generic(format!("failed to simulate call: {}", e)))?;
+
+            if cmd.output == "print" {
+                print_with_less(&serde_json::to_string_pretty(&result)?)
+                    .await
+                    .map_err(|e| Error::Generic(format!("failed to print simulation: {}", e)))?;
+            } else {
+                let output_path =
+                    build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, &filename)
+                        .await
+                        .map_err(|e| {
+                            Error::Generic(format!("failed to build output path: {}", e))
+                        })?;
+
+                write_output(&output_path, &serde_json::to_string_pretty(&result)?);
+
+                // the access list is also written on its own, so it can be attached to a
+                // transaction directly
+                let output_path = build_output_path(
+                    &cmd.output,
+                    &cmd.target,
+                    &cmd.rpc_url,
+                    &filename.replace("simulation.json", "access_list.json"),
+                )
+                .await
+                .map_err(|e| Error::Generic(format!("failed to build output path: {}", e)))?;
+
+                write_output(&output_path, &serde_json::to_string_pretty(&result.access_list)?);
+            }
+
+            // failed assertions aren't a crash, so they have their own exit code
+            if !result.failed_assertions.is_empty() {
+                record_failure(ExitStatus::AssertionFailure);
+                return Err(Error::Generic(format!(
+                    "{} assertions failed.",
+                    result.failed_assertions.len()
+                )))
+            }
+        }
+
+        Subcommands::Predeploy(mut cmd) => {
+            // if the user has not specified a rpc url, use the default
+            if cmd.rpc_url.as_str() == "" {
+                cmd.rpc_url = configuration.rpc_url;
+            }
+
+            // if the user has passed an output filename, override the default filename
+            let mut filename = "predeploy.json".to_string();
+            let given_name = cmd.name.as_str();
+
+            if !given_name.is_empty() {
+                filename = format!("{}-{}", given_name, filename);
+            }
+
+            let result = predeploy(cmd.clone())
+                .await
+                .map_err(|e| Error::Generic(format!("failed to predict deployment: {}", e)))?;
+
+            if cmd.output == "print" {
+                print_with_less(&serde_json::to_string_pretty(&result)?)
+                    .await
+                    .map_err(|e| Error::Generic(format!("failed to print prediction: {}", e)))?;
+            } else {
+                let output_path =
+                    build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, &filename)
+                        .await
+                        .map_err(|e| {
+                            Error::Generic(format!("failed to build output path: {}", e))
+                        })?;
+
+                write_output(&output_path, &serde_json::to_string_pretty(&result)?);
+            }
+        }
+
+        Subcommands::Collide(cmd) => {
+            // if the user has passed an output filename, override the default filename
+            let mut filename = "collisions.json".to_string();
+            let given_name = cmd.name.as_str();
+
+            if !given_name.is_empty() {
+                filename = format!("{}-{}", given_name, filename);
+            }
+
+            let result = collide(cmd.clone())
+                .await
+                .map_err(|e| Error::Generic(format!("failed to search for collisions: {}", e)))?;
+
+            if cmd.output == "print" {
+                print_with_less(&serde_json::to_string_pretty(&result)?)
+                    .await
+                    .map_err(|e| Error::Generic(format!("failed to print collisions: {}", e)))?;
+            } else {
+                let output_path = build_output_path(&cmd.output, &cmd.selector, "", &filename)
+                    .await
+                    .map_err(|e| Error::Generic(format!("failed to build output path: {}", e)))?;
+
+                write_output(&output_path, &serde_json::to_string_pretty(&result)?);
+            }
+        }
+
+        Subcommands::Address(cmd) => {
+            // if the user has passed an output filename, override the default filename
+            let mut filename = "address.json".to_string();
+            let given_name = cmd.name.as_str();
+
+            if !given_name.is_empty() {
+                filename = format!("{}-{}", given_name, filename);
+            }
+
+            let result = address(cmd.clone())
+                .await
+                .map_err(|e| Error::Generic(format!("failed to compute address: {}", e)))?;
+
+            if cmd.output == "print" {
+                print_with_less(&serde_json::to_string_pretty(&result)?)
+                    .await
+                    .map_err(|e| Error::Generic(format!("failed to print address: {}", e)))?;
+            } else {
+                let output_path = build_output_path(&cmd.output, &cmd.deployer, "", &filename)
+                    .await
+                    .map_err(|e| Error::Generic(format!("failed to build output path: {}", e)))?;
+
+                write_output(&output_path, &serde_json::to_string_pretty(&result)?);
+            }
+        }
+
+        Subcommands::Interface(mut cmd) => {
+            // if the user has not specified a rpc url, use the default
+            if cmd.rpc_url.as_str() == "" {
+                cmd.rpc_url = configuration.rpc_url;
+            }
+
+            let result = interface(cmd.clone())
+                .await
+                .map_err(|e| Error::Generic(format!("failed to generate interface: {}", e)))?;
+
+            // if the user has passed an output filename, override the default filename
+            let mut filename = format!("interface.{}", result.extension);
+            let given_name = cmd.name.as_str();
+
+            if !given_name.is_empty() {
+                filename = format!("{}-{}", given_name, filename);
+            }
+
+            if cmd.output == "print" {
+                print_with_less(&result.source)
+                    .await
+                    .map_err(|e| Error::Generic(format!("failed to print interface: {}", e)))?;
+            } else {
+                let output_path =
+                    build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, &filename)
+                        .await
+                        .map_err(|e| {
+                            Error::Generic(format!("failed to build output path: {}", e))
+                        })?;
+
+                write_output(&output_path, &result.source);
+            }
+        }
+
+        Subcommands::Metrics(mut cmd) => {
+            // if the user has not specified a rpc url, use the default
+            if cmd.rpc_url.as_str() == "" {
+                cmd.rpc_url = configuration.rpc_url;
+            }
+
+            // if the user has passed an output filename, override the default filename
+            let mut filename = "metrics.json".to_string();
+            let given_name = cmd.name.as_str();
+
+            if !given_name.is_empty() {
+                filename = format!("{}-{}", given_name, filename);
+            }
+
+            let result = metrics(cmd.clone())
+                .await
+                .map_err(|e| Error::Generic(format!("failed to measure bytecode: {}", e)))?;
+
+            if cmd.output == "print" {
+                print_with_less(&serde_json::to_string_pretty(&result)?)
+                    .await
+                    .map_err(|e| Error::Generic(format!("failed to print metrics: {}", e)))?;
+            } else {
+                let output_path =
+                    build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, &filename)
+                        .await
+                        .map_err(|e| {
+                            Error::Generic(format!("failed to build output path: {}", e))
+                        })?;
+
+                write_output(&output_path, &serde_json::to_string_pretty(&result)?);
+            }
+        }
+
+        Subcommands::Fingerprint(mut cmd) => {
+            // if the user has not specified a rpc url, use the default
+            if cmd.rpc_url.as_str() == "" {
+                cmd.rpc_url = configuration.rpc_url;
+            }
+
+            // if the user has passed an output filename, override the default filename
+            let mut filename = "fingerprints.json".to_string();
+            let given_name = cmd.name.as_str();
+
+            if !given_name.is_empty() {
+                filename = format!("{}-{}", given_name, filename);
+            }
+
+            let result = fingerprint(cmd.clone())
+                .await
+                .map_err(|e| Error::Generic(format!("failed to fingerprint bytecode: {}", e)))?;
+
+            if cmd.output == "print" {
+                print_with_less(&serde_json::to_string_pretty(&result)?)
+                    .await
+                    .map_err(|e| Error::Generic(format!("failed to print fingerprints: {}", e)))?;
+            } else {
+                let output_path =
+                    build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, &filename)
+                        .await
+                        .map_err(|e| {
+                            Error::Generic(format!("failed to build output path: {}", e))
+                        })?;
+
+                write_output(&output_path, &serde_json::to_string_pretty(&result)?);
+            }
+        }
+
+        Subcommands::Scan(mut cmd) => {
+            // if the user has not specified a rpc url, use the default
+            if cmd.rpc_url.as_str() == "" {
+                cmd.rpc_url = configuration.rpc_url;
+            }
+
+            // if the user has passed an output filename, override the default filename
+            let mut filename = "findings.json".to_string();
+            let given_name = cmd.name.as_str();
+
+            if !given_name.is_empty() {
+                filename = format!("{}-{}", given_name, filename);
+            }
+
+            let result = scan(cmd.clone())
+                .await
+                .map_err(|e| Error::Generic(format!("failed to scan bytecode: {}", e)))?;
+
+            if cmd.output == "print" {
+                print_with_less(&serde_json::to_string_pretty(&result)?)
+                    .await
+                    .map_err(|e| Error::Generic(format!("failed to print findings: {}", e)))?;
+            } else {
+                let output_path =
+                    build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, &filename)
+                        .await
+                        .map_err(|e| {
+                            Error::Generic(format!("failed to build output path: {}", e))
+                        })?;
+
+                write_output(&output_path, &serde_json::to_string_pretty(&result)?);
+            }
+        }
+
+        Subcommands::Stats(cmd) => {
+            // if the user has passed an output filename, override the default filename
+            let mut filename = "stats.json".to_string();
+            let given_name = cmd.name.as_str();
+
+            if !given_name.is_empty() {
+                filename = format!("{}-{}", given_name, filename);
+            }
+
+            let result = stats(cmd.clone())
+                .await
+                .map_err(|e| Error::Generic(format!("failed to collect statistics: {}", e)))?;
+
+            if cmd.output == "print" {
+                print_with_less(&serde_json::to_string_pretty(&result)?)
+                    .await
+                    .map_err(|e| Error::Generic(format!("failed to print statistics: {}", e)))?;
+            } else {
+                let output_path = build_output_path(&cmd.output, &cmd.target, "", &filename)
+                    .await
+                    .map_err(|e| Error::Generic(format!("failed to build output path: {}", e)))?;
+
+                write_output(&output_path, &serde_json::to_string_pretty(&result)?);
+            }
+        }
+
+        Subcommands::Diff(mut cmd) => {
+            // if the user has not specified a rpc url, use the default
+            if cmd.rpc_url.as_str() == "" {
+                cmd.rpc_url = configuration.rpc_url;
+            }
+
+            // both targets may be given inline or as a file, like any other target
+            for target in [&mut cmd.old_target, &mut cmd.new_target] {
+                *target = read_input(target).map_err(|e| {
+                    record_failure(ExitStatus::InvalidArguments);
+                    Error::Generic(format!("failed to read target: {}", e))
+                })?;
+            }
+
+            // if the user has passed an output filename, override the default filename
+            let mut filename = "diff.json".to_string();
+            let given_name = cmd.name.as_str();
+
+            if !given_name.is_empty() {
+                filename = format!("{}-{}", given_name, filename);
+            }
+
+            let result = diff(cmd.clone())
+                .await
+                .map_err(|e| Error::Generic(format!("failed to diff bytecode: {}", e)))?;
+
+            if cmd.output == "print" {
+                print_with_less(&serde_json::to_string_pretty(&result)?)
+                    .await
+                    .map_err(|e| Error::Generic(format!("failed to print diff: {}", e)))?;
+            } else {
+                let output_path =
+                    build_output_path(&cmd.output, &cmd.new_target, &cmd.rpc_url, &filename)
+                        .await
+                        .map_err(|e| {
+                            Error::Generic(format!("failed to build output path: {}", e))
+                        })?;
+
+                write_output(&output_path, &serde_json::to_string_pretty(&result)?);
+            }
+        }
+
+        Subcommands::Query(mut cmd) => {
+            // if the user has not specified a rpc url, use the default
+            if cmd.rpc_url.as_str() == "" {
+                cmd.rpc_url = configuration.rpc_url;
+            }
+
+            // a stored snapshot is read as-is, rather than as a single target
+            if !cmd.from_snapshot {
+                cmd.target = read_input(&cmd.target).map_err(|e| {
+                    record_failure(ExitStatus::InvalidArguments);
+                    Error::Generic(format!("failed to read target: {}", e))
+                })?;
+            }
+
+            // if the user has passed an output filename, override the default filename
+            let mut filename = "query.json".to_string();
+            let given_name = cmd.name.as_str();
+
+            if !given_name.is_empty() {
+                filename = format!("{}-{}", given_name, filename);
+            }
+
+            let result = query(cmd.clone())
+                .await
+                .map_err(|e| Error::Generic(format!("failed to query contract: {}", e)))?;
+
+            if cmd.output == "print" {
+                print_with_less(&serde_json::to_string_pretty(&result)?)
+                    .await
+                    .map_err(|e| Error::Generic(format!("failed to print query: {}", e)))?;
+            } else {
+                let output_path =
+                    build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, &filename)
+                        .await
+                        .map_err(|e| {
+                            Error::Generic(format!("failed to build output path: {}", e))
+                        })?;
+
+                write_output(&output_path, &serde_json::to_string_pretty(&result)?);
+            }
+        }
+
+        Subcommands::Asm(mut cmd) => {
+            // if the user has not specified a rpc url, use the default
+            if cmd.rpc_url.as_str() == "" {
+                cmd.rpc_url = configuration.rpc_url;
+            }
+
+            // a patched bytecode may be given inline or as a file, like any other target
+            if !cmd.patch.is_empty() {
+                cmd.patch = read_input(&cmd.patch).map_err(|e| {
+                    record_failure(ExitStatus::InvalidArguments);
+                    Error::Generic(format!("failed to read bytecode to patch: {}", e))
+                })?;
+            }
+
+            // if the user has passed an output filename, override the default filename
+            let mut filename = match cmd.patch.is_empty() {
+                true => "assembled.bin".to_string(),
+                false => "patched.bin".to_string(),
+            };
+            let given_name = cmd.name.as_str();
+
+            if !given_name.is_empty() {
+                filename = format!("{}-{}", given_name, filename);
+            }
+
+            let result = asm(cmd.clone())
+                .await
+                .map_err(|e| Error::Generic(format!("failed to assemble bytecode: {}", e)))?;
+
+            if cmd.output == "print" {
+                print_with_less(&result.bytecode).await.map_err(|e| {
+                    Error::Generic(format!("failed to print assembled bytecode: {}", e))
+                })?;
+            } else {
+                let output_path = build_output_path(
+                    &cmd.output,
+                    match cmd.patch.is_empty() {
+                        true => &cmd.target,
+                        false => &cmd.patch,
+                    },
+                    &cmd.rpc_url,
+                    &filename,
+                )
+                .await
+                .map_err(|e| Error::Generic(format!("failed to build output path: {}", e)))?;
+
+                write_output(&output_path, &result.bytecode);
+            }
+        }
+
+        Subcommands::Config(cmd) => {
+            config(cmd);
+        }
+
+        Subcommands::Cache(cmd) => {
+            _ = cache(cmd);
+        }
+
+        Subcommands::Labels(cmd) => {
+            labels(cmd).map_err(|e| Error::Generic(format!("failed to manage labels: {}", e)))?;
+        }
+
+        Subcommands::Layout(mut cmd) => {
+            // if the user has not specified a rpc url, use the default
+            if cmd.rpc_url.as_str() == "" {
+                cmd.rpc_url = configuration.rpc_url;
+            }
+
+            let result = layout(cmd.clone())
+                .await
+                .map_err(|e| Error::Generic(format!("failed to recover storage layout: {}", e)))?;
+            let (output, extension) = match cmd.format.as_str() {
+                "markdown" => (build_layout_markdown(&result), "md"),
+                _ => (serde_json::to_string_pretty(&result)?, "json"),
+            };
+
+            // if the user has passed an output filename, override the default filename
+            let mut filename = format!("layout.{}", extension);
+            let given_name = cmd.name.as_str();
+
+            if !given_name.is_empty() {
+                filename = format!("{}-{}", given_name, filename);
+            }
+
+            if cmd.output == "print" {
+                print_with_less(&output)
+                    .await
+                    .map_err(|e| Error::Generic(format!("failed to print layout: {}", e)))?;
+            } else {
+                let output_path =
+                    build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, &filename)
+                        .await
+                        .map_err(|e| {
+                            Error::Generic(format!("failed to build output path: {}", e))
+                        })?;
+
+                write_output(&output_path, &output);
+            }
+        }
+
+        Subcommands::StorageLayout(mut cmd) => {
+            // if the user has not specified a rpc url, use the default
+            if cmd.rpc_url.as_str() == "" {
+                cmd.rpc_url = configuration.rpc_url;
+            }
+
+            let result = storage_layout(cmd.clone()).await.map_err(|e| {
+                Error::Generic(format!("failed to reconstruct storage layout: {}", e))
+            })?;
+            let json = serde_json::to_string_pretty(&result)?;
+            let table = build_table(&result);
+
+            // if the user has passed an output filename, override the default filename
+            let mut json_filename = String::from("storage-layout.json");
+            let mut table_filename = String::from("storage-layout.md");
+            let given_name = cmd.name.as_str();
+
+            if !given_name.is_empty() {
+                json_filename = format!("{}-{}", given_name, json_filename);
+                table_filename = format!("{}-{}", given_name, table_filename);
+            }
+
+            if cmd.output == "print" {
+                print_with_less(&format!("{table}\n\n{json}")).await.map_err(|e| {
+                    Error::Generic(format!("failed to print storage layout: {}", e))
+                })?;
+            } else {
+                for (filename, output) in [(json_filename, json), (table_filename, table)] {
+                    let output_path =
+                        build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, &filename)
+                            .await
+                            .map_err(|e| {
+                                Error::Generic(format!("failed to build output path: {}", e))
+                            })?;
+
+                    write_output(&output_path, &output);
+                }
+            }
+        }
+
+        Subcommands::Locate(mut cmd) => {
+            // if the user has not specified a rpc url, use the default
+            if cmd.rpc_url.as_str() == "" {
+                cmd.rpc_url = configuration.rpc_url;
+            }
+
+            // if the user has passed an output filename, override the default filename
+            let mut filename = "locate.json".to_string();
+            let given_name = cmd.name.as_str();
+
+            if !given_name.is_empty() {
+                filename = format!("{}-{}", given_name, filename);
+            }
+
+            let result = locate(cmd.clone())
+                .await
+                .map_err(|e| Error::Generic(format!("failed to locate pc: {}", e)))?;
+
+            if cmd.output == "print" {
+                print_with_less(&serde_json::to_string_pretty(&result)?)
+                    .await
+                    .map_err(|e| Error::Generic(format!("failed to print location: {}", e)))?;
+            } else {
+                let output_path =
+                    build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, &filename)
+                        .await
+                        .map_err(|e| {
+                            Error::Generic(format!("failed to build output path: {}", e))
+                        })?;
+
+                write_output(&output_path, &serde_json::to_string_pretty(&result)?);
+            }
+        }
+
+        Subcommands::Roles(mut cmd) => {
+            // if the user has not specified a rpc url, use the default
+            if cmd.rpc_url.as_str() == "" {
+                cmd.rpc_url = configuration.rpc_url;
+            }
+
+            let result = roles(cmd.clone())
+                .await
+                .map_err(|e| Error::Generic(format!("failed to recover roles: {}", e)))?;
+            let output = match cmd.format.as_str() {
+                "dot" => build_dot(&result),
+                _ => serde_json::to_string_pretty(&result)?,
+            };
+
+            // if the user has passed an output filename, override the default filename
+            let mut filename = format!("roles.{}", cmd.format);
+            let given_name = cmd.name.as_str();
+
+            if !given_name.is_empty() {
+                filename = format!("{}-{}", given_name, filename);
+            }
+
+            if cmd.output == "print" {
+                print_with_less(&output)
+                    .await
+                    .map_err(|e| Error::Generic(format!("failed to print roles: {}", e)))?;
+            } else {
+                let output_path =
+                    build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, &filename)
+                        .await
+                        .map_err(|e| {
+                            Error::Generic(format!("failed to build output path: {}", e))
+                        })?;
+
+                write_output(&output_path, &output);
+            }
+        }
+
+        Subcommands::Calls(mut cmd) => {
+            // if the user has not specified a rpc url, use the default
+            if cmd.rpc_url.as_str() == "" {
+                cmd.rpc_url = configuration.rpc_url;
+            }
+
+            let result = calls(cmd.clone())
+                .await
+                .map_err(|e| Error::Generic(format!("failed to list calls: {}", e)))?;
+            let (output, extension) = match cmd.format.as_str() {
+                "markdown" => (build_calls_markdown(&result), "md"),
+                _ => (serde_json::to_string_pretty(&result)?, "json"),
+            };
+
+            // if the user has passed an output filename, override the default filename
+            let mut filename = format!("calls.{}", extension);
+            let given_name = cmd.name.as_str();
+
+            if !given_name.is_empty() {
+                filename = format!("{}-{}", given_name, filename);
+            }
+
+            if cmd.output == "print" {
+                print_with_less(&output)
+                    .await
+                    .map_err(|e| Error::Generic(format!("failed to print calls: {}", e)))?;
+            } else {
+                let output_path =
+                    build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, &filename)
+                        .await
+                        .map_err(|e| {
+                            Error::Generic(format!("failed to build output path: {}", e))
+                        })?;
+
+                write_output(&output_path, &output);
+            }
+        }
+
+        Subcommands::Map(mut cmd) => {
+            // if the user has not specified a rpc url, use the default
+            if cmd.rpc_url.as_str() == "" {
+                cmd.rpc_url = configuration.rpc_url;
+            }
+
+            let result = map(cmd.clone())
+                .await
+                .map_err(|e| Error::Generic(format!("failed to map contracts: {}", e)))?;
+            let (output, extension) = match cmd.format.as_str() {
+                "dot" => (build_map_dot(&result), "dot"),
+                "mermaid" => (build_mermaid(&result), "mmd"),
+                _ => (serde_json::to_string_pretty(&result)?, "json"),
+            };
+
+            // if the user has passed an output filename, override the default filename
+            let mut filename = format!("map.{}", extension);
+            let given_name = cmd.name.as_str();
+
+            if !given_name.is_empty() {
+                filename = format!("{}-{}", given_name, filename);
+            }
+
+            if cmd.output == "print" {
+                print_with_less(&output)
+                    .await
+                    .map_err(|e| Error::Generic(format!("failed to print map: {}", e)))?;
+            } else {
+                let output_path =
+                    build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, &filename)
+                        .await
+                        .map_err(|e| {
+                            Error::Generic(format!("failed to build output path: {}", e))
+                        })?;
+
+                write_output(&output_path, &output);
+            }
+        }
+
+        Subcommands::Lineage(mut cmd) => {
+            // if the user has not specified a rpc url, use the default
+            if cmd.rpc_url.as_str() == "" {
+                cmd.rpc_url = configuration.rpc_url;
+            }
+
+            let result = lineage(cmd.clone())
+                .await
+                .map_err(|e| Error::Generic(format!("failed to trace lineage: {}", e)))?;
+            let (output, extension) = match cmd.format.as_str() {
+                "markdown" => (build_markdown(&result), "md"),
+                _ => (serde_json::to_string_pretty(&result)?, "json"),
+            };
+
+            // if the user has passed an output filename, override the default filename
+            let mut filename = format!("lineage.{}", extension);
+            let given_name = cmd.name.as_str();
+
+            if !given_name.is_empty() {
+                filename = format!("{}-{}", given_name, filename);
+            }
+
+            if cmd.output == "print" {
+                print_with_less(&output)
+                    .await
+                    .map_err(|e| Error::Generic(format!("failed to print lineage: {}", e)))?;
+            } else {
+                let output_path =
+                    build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, &filename)
+                        .await
+                        .map_err(|e| {
+                            Error::Generic(format!("failed to build output path: {}", e))
+                        })?;
+
+                write_output(&output_path, &output);
+            }
+        }
+
+        Subcommands::Usage(mut cmd) => {
+            // if the user has not specified a rpc url, use the default
+            if cmd.rpc_url.as_str() == "" {
+                cmd.rpc_url = configuration.rpc_url;
+            }
+
+            let result = usage(cmd.clone())
+                .await
+                .map_err(|e| Error::Generic(format!("failed to report usage: {}", e)))?;
+            let (output, extension) = match cmd.format.as_str() {
+                "markdown" => (build_usage_markdown(&result), "md"),
+                _ => (serde_json::to_string_pretty(&result)?, "json"),
+            };
+
+            // if the user has passed an output filename, override the default filename
+            let mut filename = format!("usage.{}", extension);
+            let given_name = cmd.name.as_str();
+
+            if !given_name.is_empty() {
+                filename = format!("{}-{}", given_name, filename);
+            }
+
+            if cmd.output == "print" {
+                print_with_less(&output)
+                    .await
+                    .map_err(|e| Error::Generic(format!("failed to print usage: {}", e)))?;
+            } else {
+                let output_path =
+                    build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, &filename)
+                        .await
+                        .map_err(|e| {
+                            Error::Generic(format!("failed to build output path: {}", e))
+                        })?;
+
+                write_output(&output_path, &output);
+            }
+        }
+
+        Subcommands::Evaluate(cmd) => {
+            let result = evaluate(cmd.clone())
+                .await
+                .map_err(|e| Error::Generic(format!("failed to evaluate decompiler: {}", e)))?;
+            let (output, extension) = match cmd.format.as_str() {
+                "markdown" => (build_evaluate_markdown(&result), "md"),
+                _ => (serde_json::to_string_pretty(&result)?, "json"),
+            };
+
+            // if the user has passed an output filename, override the default filename
+            let mut filename = format!("evaluation.{}", extension);
+            let given_name = cmd.name.as_str();
+
+            if !given_name.is_empty() {
+                filename = format!("{}-{}", given_name, filename);
+            }
+
+            if cmd.output == "print" {
+                print_with_less(&output)
+                    .await
+                    .map_err(|e| Error::Generic(format!("failed to print evaluation: {}", e)))?;
+            } else {
+                let output_path = build_output_path(&cmd.output, &cmd.target, "", &filename)
+                    .await
+                    .map_err(|e| Error::Generic(format!("failed to build output path: {}", e)))?;
+
+                write_output(&output_path, &output);
+
+                // the metrics alone, for comparing runs before and after a change
+                let mut metrics_filename = "metrics.json".to_string();
+                if !given_name.is_empty() {
+                    metrics_filename = format!("{}-{}", given_name, metrics_filename);
+                }
+                let output_path =
+                    build_output_path(&cmd.output, &cmd.target, "", &metrics_filename)
+                        .await
+                        .map_err(|e| {
+                            Error::Generic(format!("failed to build output path: {}", e))
+                        })?;
+
+                write_output(&output_path, &serde_json::to_string_pretty(&result.metrics)?);
+            }
+        }
     }
 
     Ok(())
@@ -3,6 +3,11 @@ use std::{env, io::Write};
 use heimdall_common::{
     constants::{ADDRESS_REGEX, TRANSACTION_HASH_REGEX},
     ether::rpc,
+    utils::io::{
+        address::format_addresses,
+        file::{write_file, write_lines_to_file},
+        redaction::redact_report,
+    },
 };
 
 use crate::error::Error;
@@ -47,7 +52,12 @@ pub async fn build_output_path(
     Ok(format!("{}/{}", output, filename))
 }
 
-/// pass the input to the `less` command
+/// format the report's addresses as requested, then apply any redaction
+fn finalize_report(report: &str) -> String {
+    redact_report(&format_addresses(report))
+}
+
+/// pass the input to the `less` command, after applying any redaction
 pub async fn print_with_less(input: &str) -> Result<(), Error> {
     let mut child =
         std::process::Command::new("less").stdin(std::process::Stdio::piped()).spawn()?;
@@ -56,12 +66,27 @@ pub async fn print_with_less(input: &str) -> Result<(), Error> {
         .stdin
         .as_mut()
         .ok_or_else(|| Error::Generic("unable to get stdin for less".to_string()))?;
-    stdin.write_all(input.as_bytes())?;
+    stdin.write_all(finalize_report(input).as_bytes())?;
 
     child.wait()?;
     Ok(())
 }
 
+/// write the output to the given path, after applying any redaction
+pub fn write_output(path: &str, contents: &str) {
+    write_file(path, &finalize_report(contents));
+}
+
+/// write the output lines to the given path, after applying any redaction
+pub fn write_output_lines(path: &str, lines: Vec<String>) {
+    write_lines_to_file(path, lines.iter().map(|line| finalize_report(line)).collect());
+}
+
+/// print a single line of output to stdout, after applying any redaction
+pub fn print_output_line(line: &str) {
+    println!("{}", finalize_report(line));
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -32,4 +32,16 @@ lazy_static! {
 
     /// The following regex is used to find storage accesses
     pub static ref STORAGE_REGEX: Regex = Regex::new(r"storage\[\(?[0-9x]*\]").unwrap();
+
+    /// The following regex is used to find URLs, such as RPC providers
+    pub static ref URL_REGEX: Regex = Regex::new(r"[a-zA-Z][a-zA-Z0-9+.-]*://[^\s'\x22]+").unwrap();
+
+    /// The following regex is used to find runs of hex long enough to be bytecode, rather than
+    /// a word or hash
+    pub static ref LONG_HEX_REGEX: Regex = Regex::new(r"(0x)?[0-9a-fA-F]{130,}").unwrap();
+
+    /// The following regex is used to find addresses in text, which aren't part of a longer
+    /// word or hex string
+    pub static ref EMBEDDED_ADDRESS_REGEX: Regex =
+        Regex::new(r"(?<![0-9a-zA-Z_])0x[0-9a-fA-F]{40}(?![0-9a-zA-Z_])").unwrap();
 }
@@ -0,0 +1,384 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use ethers::{
+    abi::{Abi, StateMutability},
+    types::U256,
+};
+use serde_json::Value;
+
+use crate::{
+    error::Error,
+    ether::signatures::{ResolvedError, ResolvedFunction, ResolvedLog},
+    utils::strings::encode_hex,
+};
+
+/// A function in an artifact's ABI.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArtifactFunction {
+    pub resolved: ResolvedFunction,
+    pub outputs: Vec<String>,
+    /// One of `pure`, `view`, `nonpayable`, or `payable`
+    pub state_mutability: String,
+}
+
+/// A state variable in an artifact's storage layout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageVariable {
+    pub label: String,
+    pub slot: U256,
+    /// The byte offset of the variable within its slot, for packed variables
+    pub offset: u64,
+    /// The number of bytes the variable occupies, which spans several slots for a struct or a
+    /// fixed-size array
+    pub size: u64,
+    /// The variable's type, e.g. `uint256` or `mapping(address => uint256)`
+    pub type_name: String,
+    /// How the variable is stored: `inplace`, `mapping`, `dynamic_array`, or `bytes`
+    pub encoding: String,
+}
+
+/// A contract compiled by foundry, hardhat, or solc, which is ground truth for what heimdall
+/// recovers from its bytecode.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Artifact {
+    pub name: Option<String>,
+    /// The deployed bytecode, without a `0x` prefix
+    pub bytecode: String,
+    /// The ABI's functions, by selector without a `0x` prefix
+    pub functions: HashMap<String, ArtifactFunction>,
+    /// The ABI's custom errors, by selector
+    pub errors: HashMap<U256, ResolvedError>,
+    /// The ABI's non-anonymous events, by topic
+    pub events: HashMap<U256, ResolvedLog>,
+    /// The storage layout, which foundry only includes with `extra_output = ["storageLayout"]`
+    pub storage: Option<Vec<StorageVariable>>,
+}
+
+impl Artifact {
+    /// Parse a foundry, hardhat, or solc artifact, which must include both an ABI and bytecode.
+    pub fn parse(contents: &str) -> Result<Artifact, Error> {
+        let artifact: Value = serde_json::from_str(contents)
+            .map_err(|e| Error::Generic(format!("failed to parse artifact: {e}")))?;
+        Self::from_value(&artifact)
+    }
+
+    /// Parse every contract in a compiler output, which is either a single artifact, solc's
+    /// standard JSON output, or the output of `solc --combined-json`. Contracts without bytecode,
+    /// such as interfaces, are skipped.
+    pub fn parse_all(contents: &str) -> Result<Vec<Artifact>, Error> {
+        let output: Value = serde_json::from_str(contents)
+            .map_err(|e| Error::Generic(format!("failed to parse artifact: {e}")))?;
+        let contracts = match output["contracts"].as_object() {
+            Some(contracts) => contracts,
+            None => return Ok(vec![Self::from_value(&output)?]),
+        };
+
+        let mut artifacts = Vec::new();
+        for (key, entry) in contracts {
+            // `--combined-json` keys each contract by `path:name`, while standard JSON nests them
+            // by path, then by name
+            let entries = match entry.get("abi") {
+                Some(_) => vec![(key.rsplit(':').next().unwrap_or(key), entry)],
+                None => entry
+                    .as_object()
+                    .map(|entries| entries.iter().map(|(name, c)| (name.as_str(), c)).collect())
+                    .unwrap_or_default(),
+            };
+
+            for (name, contract) in entries {
+                if let Ok(mut artifact) = Self::from_value(contract) {
+                    artifact.name = Some(name.to_string());
+                    artifacts.push(artifact);
+                }
+            }
+        }
+
+        artifacts.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(artifacts)
+    }
+
+    fn from_value(artifact: &Value) -> Result<Artifact, Error> {
+        // `--combined-json` from older versions of solc includes the ABI and storage layout as
+        // JSON strings
+        let abi: Abi = match artifact.get("abi") {
+            Some(Value::String(abi)) => serde_json::from_str(abi)
+                .map_err(|e| Error::Generic(format!("failed to parse artifact ABI: {e}")))?,
+            Some(abi) => serde_json::from_value(abi.clone())
+                .map_err(|e| Error::Generic(format!("failed to parse artifact ABI: {e}")))?,
+            None => return Err(Error::Generic(String::from("artifact doesn't include an ABI"))),
+        };
+        let storage = match artifact.get("storageLayout").or_else(|| artifact.get("storage-layout"))
+        {
+            Some(Value::String(layout)) => {
+                serde_json::from_str(layout).ok().as_ref().and_then(parse_storage_layout)
+            }
+            Some(layout) => parse_storage_layout(layout),
+            None => None,
+        };
+        let bytecode = artifact_bytecode(artifact)
+            .ok_or_else(|| Error::Generic(String::from("artifact doesn't include bytecode")))?
+            .trim_start_matches("0x")
+            .to_string();
+
+        let functions = abi
+            .functions()
+            .map(|function| {
+                let inputs = function
+                    .inputs
+                    .iter()
+                    .map(|input| input.kind.to_string())
+                    .collect::<Vec<String>>();
+                (
+                    encode_hex(function.short_signature().to_vec()),
+                    ArtifactFunction {
+                        resolved: ResolvedFunction {
+                            name: function.name.clone(),
+                            signature: format!("{}({})", function.name, inputs.join(",")),
+                            inputs,
+                            decoded_inputs: None,
+                        },
+                        outputs: function
+                            .outputs
+                            .iter()
+                            .map(|output| output.kind.to_string())
+                            .collect(),
+                        state_mutability: String::from(match function.state_mutability {
+                            StateMutability::Pure => "pure",
+                            StateMutability::View => "view",
+                            StateMutability::NonPayable => "nonpayable",
+                            StateMutability::Payable => "payable",
+                        }),
+                    },
+                )
+            })
+            .collect();
+
+        let errors = abi
+            .errors()
+            .map(|error| {
+                let inputs =
+                    error.inputs.iter().map(|input| input.kind.to_string()).collect::<Vec<_>>();
+                (
+                    U256::from(&error.signature().as_bytes()[..4]),
+                    ResolvedError {
+                        name: error.name.clone(),
+                        signature: format!("{}({})", error.name, inputs.join(",")),
+                        inputs,
+                    },
+                )
+            })
+            .collect();
+
+        let events = abi
+            .events()
+            .filter(|event| !event.anonymous)
+            .map(|event| {
+                let inputs =
+                    event.inputs.iter().map(|input| input.kind.to_string()).collect::<Vec<_>>();
+                (
+                    U256::from(event.signature().as_bytes()),
+                    ResolvedLog {
+                        name: event.name.clone(),
+                        signature: format!("{}({})", event.name, inputs.join(",")),
+                        inputs,
+                    },
+                )
+            })
+            .collect();
+
+        Ok(Artifact { name: artifact_name(artifact), bytecode, functions, errors, events, storage })
+    }
+
+    /// Read and parse the artifact at the given path.
+    pub fn read(path: &str) -> Result<Artifact, Error> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| Error::IOError(format!("failed to read artifact '{path}': {e}")))?;
+        Self::parse(&contents)
+    }
+
+    /// Read and parse every contract in the compiler output at the given path.
+    pub fn read_all(path: &str) -> Result<Vec<Artifact>, Error> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| Error::IOError(format!("failed to read artifact '{path}': {e}")))?;
+        Self::parse_all(&contents)
+    }
+}
+
+/// The bytecode of a compiler artifact, preferring the deployed bytecode to the creation code.
+/// Hardhat and `solc --combined-json` store each as a string, while foundry and solc's standard
+/// JSON wrap them in an object, under `evm` for solc.
+pub(crate) fn artifact_bytecode(artifact: &Value) -> Option<&str> {
+    [artifact, &artifact["evm"]]
+        .into_iter()
+        .flat_map(|artifact| {
+            ["deployedBytecode", "bin-runtime", "bytecode", "bin"].map(|key| &artifact[key])
+        })
+        .filter_map(|bytecode| bytecode["object"].as_str().or_else(|| bytecode.as_str()))
+        .find(|bytecode| !bytecode.trim_start_matches("0x").is_empty())
+}
+
+/// The contract's name, which hardhat stores directly, and foundry and solc store as the
+/// compilation target in the metadata.
+fn artifact_name(artifact: &Value) -> Option<String> {
+    if let Some(name) = artifact["contractName"].as_str() {
+        return Some(name.to_string())
+    }
+
+    // solc's metadata is a JSON string, while foundry's is already parsed
+    let metadata = match &artifact["metadata"] {
+        Value::String(metadata) => serde_json::from_str(metadata).ok()?,
+        metadata => metadata.clone(),
+    };
+    metadata["settings"]["compilationTarget"]
+        .as_object()?
+        .values()
+        .next()?
+        .as_str()
+        .map(|name| name.to_string())
+}
+
+/// Parse a solc storage layout, i.e. `{"storage": [..], "types": {..}}`.
+fn parse_storage_layout(layout: &Value) -> Option<Vec<StorageVariable>> {
+    let types = &layout["types"];
+    layout["storage"]
+        .as_array()?
+        .iter()
+        .map(|variable| {
+            let type_id = variable["type"].as_str()?;
+            Some(StorageVariable {
+                label: variable["label"].as_str()?.to_string(),
+                slot: U256::from_dec_str(variable["slot"].as_str()?).ok()?,
+                offset: variable["offset"].as_u64().unwrap_or(0),
+                size: types[type_id]["numberOfBytes"]
+                    .as_str()
+                    .and_then(|size| size.parse().ok())
+                    .unwrap_or(32),
+                type_name: types[type_id]["label"].as_str().unwrap_or(type_id).to_string(),
+                encoding: types[type_id]["encoding"].as_str().unwrap_or("inplace").to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Find every artifact under the given directory, skipping foundry's and hardhat's build info and
+/// hardhat's debug files, which aren't artifacts. Artifacts are ordered by path.
+pub fn find_artifacts(path: &Path) -> Vec<PathBuf> {
+    let mut artifacts = Vec::new();
+    let mut directories = vec![path.to_path_buf()];
+    while let Some(directory) = directories.pop() {
+        let entries = match fs::read_dir(&directory) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+
+        for path in entries.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+            let name = path.file_name().map(|name| name.to_string_lossy().to_string());
+            match name.as_deref() {
+                Some("build-info") => {}
+                Some(name) if path.is_dir() && !name.starts_with('.') => directories.push(path),
+                Some(name) if name.ends_with(".json") && !name.ends_with(".dbg.json") => {
+                    artifacts.push(path)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    artifacts.sort();
+    artifacts
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ether::artifact::*;
+
+    const FOUNDRY_ARTIFACT: &str = r#"{
+        "abi": [
+            {"type": "function", "name": "transfer", "stateMutability": "nonpayable",
+             "inputs": [{"name": "to", "type": "address"}, {"name": "amount", "type": "uint256"}],
+             "outputs": [{"name": "", "type": "bool"}]},
+            {"type": "event", "name": "Transfer", "anonymous": false,
+             "inputs": [{"name": "from", "type": "address", "indexed": true},
+                        {"name": "to", "type": "address", "indexed": true},
+                        {"name": "value", "type": "uint256", "indexed": false}]},
+            {"type": "error", "name": "Unauthorized", "inputs": []}
+        ],
+        "bytecode": {"object": "0x6001"},
+        "deployedBytecode": {"object": "0x6002"},
+        "storageLayout": {
+            "storage": [
+                {"label": "owner", "slot": "0", "offset": 0, "type": "t_address"},
+                {"label": "balances", "slot": "1", "offset": 0, "type": "t_mapping(t_address,t_uint256)"}
+            ],
+            "types": {
+                "t_address": {"encoding": "inplace", "label": "address", "numberOfBytes": "20"},
+                "t_mapping(t_address,t_uint256)": {"encoding": "mapping", "label": "mapping(address => uint256)"}
+            }
+        },
+        "metadata": {"settings": {"compilationTarget": {"src/Token.sol": "Token"}}}
+    }"#;
+
+    #[test]
+    fn test_parse_foundry_artifact() {
+        let artifact = Artifact::parse(FOUNDRY_ARTIFACT).unwrap();
+        assert_eq!(artifact.name.as_deref(), Some("Token"));
+        assert_eq!(artifact.bytecode, "6002");
+
+        let transfer = &artifact.functions["a9059cbb"];
+        assert_eq!(transfer.resolved.signature, "transfer(address,uint256)");
+        assert_eq!(transfer.outputs, vec![String::from("bool")]);
+        assert_eq!(transfer.state_mutability, "nonpayable");
+
+        assert_eq!(artifact.errors[&U256::from(0x82b42900u64)].name, "Unauthorized");
+        assert_eq!(artifact.events.len(), 1);
+
+        let storage = artifact.storage.unwrap();
+        assert_eq!(storage[0].size, 20);
+        assert_eq!(storage[1].label, "balances");
+        assert_eq!(storage[1].slot, U256::one());
+        assert_eq!(storage[1].encoding, "mapping");
+    }
+
+    #[test]
+    fn test_parse_hardhat_artifact() {
+        let artifact = Artifact::parse(
+            r#"{"contractName": "Token", "abi": [], "bytecode": "0x6001", "deployedBytecode": "0x6002"}"#,
+        )
+        .unwrap();
+        assert_eq!(artifact.name.as_deref(), Some("Token"));
+        assert_eq!(artifact.bytecode, "6002");
+        assert!(artifact.storage.is_none());
+
+        // a JSON file without an ABI isn't an artifact
+        assert!(Artifact::parse(r#"{"bytecode": "0x6001"}"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_solc_outputs() {
+        let standard_json = r#"{"contracts": {"src/Token.sol": {
+            "IERC20": {"abi": [], "evm": {"deployedBytecode": {"object": ""}}},
+            "Token": {"abi": [], "evm": {"deployedBytecode": {"object": "6002"}},
+                      "storageLayout": {"storage": [], "types": null}}
+        }}}"#;
+        let artifacts = Artifact::parse_all(standard_json).unwrap();
+        assert_eq!(artifacts.len(), 1);
+        assert_eq!(artifacts[0].name.as_deref(), Some("Token"));
+        assert_eq!(artifacts[0].bytecode, "6002");
+        assert_eq!(artifacts[0].storage, Some(Vec::new()));
+
+        let combined_json = r#"{"contracts": {
+            "src/Token.sol:Token": {"abi": "[]", "bin": "6001", "bin-runtime": "6002"},
+            "src/Vault.sol:Vault": {"abi": [], "bin": "6003", "bin-runtime": "6004"}
+        }}"#;
+        let artifacts = Artifact::parse_all(combined_json).unwrap();
+        assert_eq!(
+            artifacts.iter().map(|a| a.bytecode.as_str()).collect::<Vec<_>>(),
+            vec!["6002", "6004"]
+        );
+        assert_eq!(artifacts[1].name.as_deref(), Some("Vault"));
+    }
+}
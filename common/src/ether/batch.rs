@@ -0,0 +1,341 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use ethers::types::{Bytes, H256, U256};
+use futures::{stream, StreamExt};
+use heimdall_cache::{read_cache, store_cache};
+use reqwest::Client;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    debug_max,
+    error::Error,
+    ether::{
+        providers::{record_provider_failure, rpc_backoff, select_provider},
+        rpc::{chain_id, network_error},
+    },
+    utils::offline::is_offline,
+};
+
+/// The default number of queries sent in each JSON-RPC batch.
+pub const DEFAULT_RPC_BATCH_SIZE: usize = 100;
+
+/// The default number of JSON-RPC batches in flight at once.
+pub const DEFAULT_RPC_CONCURRENCY: usize = 4;
+
+static RPC_BATCH_SIZE: AtomicUsize = AtomicUsize::new(DEFAULT_RPC_BATCH_SIZE);
+static RPC_CONCURRENCY: AtomicUsize = AtomicUsize::new(DEFAULT_RPC_CONCURRENCY);
+
+/// A single query in a batch, and the key its result is cached under. The keys are the ones
+/// used by [`crate::ether::rpc`], so batched and single reads share a cache.
+struct Query {
+    cache_key: String,
+    method: &'static str,
+    params: Value,
+}
+
+/// The default batch size, for configurations which don't set one.
+pub fn default_rpc_batch_size() -> usize {
+    DEFAULT_RPC_BATCH_SIZE
+}
+
+/// The default concurrency, for configurations which don't set one.
+pub fn default_rpc_concurrency() -> usize {
+    DEFAULT_RPC_CONCURRENCY
+}
+
+/// Set the most queries sent to the RPC provider in a single JSON-RPC batch. `1` sends each query
+/// on its own, for providers which don't accept batches.
+pub fn set_rpc_batch_size(batch_size: usize) {
+    RPC_BATCH_SIZE.store(batch_size.max(1), Ordering::SeqCst);
+}
+
+/// Set the most JSON-RPC batches sent to the RPC provider at once.
+pub fn set_rpc_concurrency(concurrency: usize) {
+    RPC_CONCURRENCY.store(concurrency.max(1), Ordering::SeqCst);
+}
+
+/// Get the values of many storage slots of the provided contract address as of the given block,
+/// in the order they're given. Cached slots are never re-fetched.
+///
+/// ```no_run
+/// use ethers::types::H256;
+/// use heimdall_common::ether::batch::get_storage_batch;
+///
+/// // let values = get_storage_batch("0x0", &[H256::zero()], 18_000_000, "https://eth.llamarpc.com").await;
+/// // assert!(values.is_ok());
+/// ```
+pub async fn get_storage_batch(
+    contract_address: &str,
+    slots: &[H256],
+    block_number: u64,
+    rpc_url: &str,
+) -> Result<Vec<H256>, Error> {
+    let chain_id = chain_id(rpc_url).await.unwrap_or(1);
+    let queries = slots
+        .iter()
+        .map(|slot| Query {
+            cache_key: format!(
+                "storage.{}.{}.{:?}.{}",
+                &chain_id, &contract_address, &slot, &block_number
+            ),
+            method: "eth_getStorageAt",
+            params: json!([contract_address, slot, format!("{block_number:#x}")]),
+        })
+        .collect::<Vec<_>>();
+
+    cached_batch(rpc_url, queries, |result| serde_json::from_value::<H256>(result).ok()).await
+}
+
+/// Get the bytecode of many addresses as of the given block, or the latest block if none is
+/// given, in the order they're given. Cached bytecode is never re-fetched.
+///
+/// ```no_run
+/// use heimdall_common::ether::batch::get_code_batch;
+///
+/// // let bytecode = get_code_batch(&["0x0".to_string()], None, "https://eth.llamarpc.com").await;
+/// // assert!(bytecode.is_ok());
+/// ```
+pub async fn get_code_batch(
+    addresses: &[String],
+    block_number: Option<u64>,
+    rpc_url: &str,
+) -> Result<Vec<String>, Error> {
+    let chain_id = chain_id(rpc_url).await.unwrap_or(1);
+    let cache_suffix = block_number.map(|block| format!(".{block}")).unwrap_or_default();
+    let block_tag = block_number.map(|block| format!("{block:#x}")).unwrap_or("latest".into());
+    let queries = addresses
+        .iter()
+        .map(|address| Query {
+            cache_key: format!("contract.{}.{}{}", &chain_id, &address, &cache_suffix),
+            method: "eth_getCode",
+            params: json!([address, block_tag]),
+        })
+        .collect::<Vec<_>>();
+
+    cached_batch(rpc_url, queries, |result| {
+        serde_json::from_value::<Bytes>(result)
+            .ok()
+            .map(|bytecode| bytecode.to_string().replacen("0x", "", 1))
+    })
+    .await
+}
+
+/// Get the ether balance of many addresses at the given block, in the order they're given.
+/// Cached balances are never re-fetched.
+///
+/// ```no_run
+/// use heimdall_common::ether::batch::get_balance_batch;
+///
+/// // let balances = get_balance_batch(&["0x0".to_string()], 18_000_000, "https://eth.llamarpc.com").await;
+/// // assert!(balances.is_ok());
+/// ```
+pub async fn get_balance_batch(
+    addresses: &[String],
+    block_number: u64,
+    rpc_url: &str,
+) -> Result<Vec<U256>, Error> {
+    let chain_id = chain_id(rpc_url).await.unwrap_or(1);
+    let queries = addresses
+        .iter()
+        .map(|address| Query {
+            cache_key: format!("balance.{}.{}.{}", &chain_id, &address, &block_number),
+            method: "eth_getBalance",
+            params: json!([address, format!("{block_number:#x}")]),
+        })
+        .collect::<Vec<_>>();
+
+    cached_batch(rpc_url, queries, |result| serde_json::from_value::<U256>(result).ok()).await
+}
+
+/// Resolve each query from the cache, and fetch the rest from the RPC provider in batches of the
+/// configured size, with the configured number of batches in flight at once.
+async fn cached_batch<T, F>(rpc_url: &str, queries: Vec<Query>, parse: F) -> Result<Vec<T>, Error>
+where
+    T: 'static + Serialize + DeserializeOwned,
+    F: Fn(Value) -> Option<T>, {
+    let mut results = queries
+        .iter()
+        .map(|query| read_cache::<T>(&query.cache_key).ok().flatten())
+        .collect::<Vec<_>>();
+    let misses = (0..queries.len()).filter(|i| results[*i].is_none()).collect::<Vec<_>>();
+    debug_max!("{} of {} queries are cached .", queries.len() - misses.len(), queries.len());
+
+    if !misses.is_empty() {
+        // make sure the RPC provider isn't empty
+        if rpc_url.is_empty() {
+            return Err(network_error(
+                "reading on-chain data requires an RPC provider. Use `heimdall --help` for more information."
+                    .to_string(),
+            ))
+        }
+
+        // offline mode never reaches the RPC provider
+        if is_offline() {
+            return Err(network_error(
+                "reading on-chain data requires network access, which is disabled by `--offline` ."
+                    .to_string(),
+            ))
+        }
+
+        let batch_size = RPC_BATCH_SIZE.load(Ordering::SeqCst);
+        let concurrency = RPC_CONCURRENCY.load(Ordering::SeqCst);
+        let client = Client::new();
+        let batches = stream::iter(misses.chunks(batch_size))
+            .map(|indices| {
+                let batch = indices.iter().map(|i| &queries[*i]).collect::<Vec<_>>();
+                let client = &client;
+                async move { (indices, send_batch(client, rpc_url, &batch).await) }
+            })
+            .buffer_unordered(concurrency)
+            .collect::<Vec<_>>()
+            .await;
+
+        for (indices, responses) in batches {
+            for (i, response) in indices.iter().zip(responses?) {
+                let query = &queries[*i];
+                let value = parse(response).ok_or_else(|| {
+                    network_error(format!(
+                        "invalid response to '{}' with params {}",
+                        query.method, query.params
+                    ))
+                })?;
+
+                // cache the results
+                store_cache(&query.cache_key, &value, None).map_err(|e| {
+                    Error::Generic(format!("failed to cache '{}': {}", query.cache_key, e))
+                })?;
+                results[*i] = Some(value);
+            }
+        }
+    }
+
+    Ok(results.into_iter().flatten().collect())
+}
+
+/// Send a single JSON-RPC batch, returning each query's result in the order the queries were
+/// given. Batches which the provider fails to answer are retried, rotating between providers if
+/// several are given.
+async fn send_batch(client: &Client, rpc_url: &str, batch: &[&Query]) -> Result<Vec<Value>, Error> {
+    let body = Value::Array(
+        batch
+            .iter()
+            .enumerate()
+            .map(|(id, query)| {
+                json!({ "jsonrpc": "2.0", "id": id, "method": query.method, "params": query.params })
+            })
+            .collect(),
+    )
+    .to_string();
+
+    backoff::future::retry(rpc_backoff(), || async {
+        // rotate away from providers which keep failing
+        let provider_url = select_provider(rpc_url);
+        debug_max!("sending a batch of {} queries to '{}' .", batch.len(), provider_url);
+
+        let text = match client
+            .post(&provider_url)
+            .header("Content-Type", "application/json")
+            .body(body.clone())
+            .send()
+            .await
+        {
+            Ok(response) => response.text().await,
+            Err(e) => Err(e),
+        };
+        let responses = text
+            .map_err(|e| e.to_string())
+            .and_then(|text| {
+                serde_json::from_str::<Value>(&text)
+                    .map_err(|_| format!("invalid response: {text}"))
+            })
+            .map_err(|e| {
+                record_provider_failure(&provider_url);
+                backoff::Error::transient(e)
+            })?;
+
+        // errors in the batch, such as a provider without archive data, won't go away by
+        // retrying
+        order_responses(responses, batch.len()).map_err(backoff::Error::permanent)
+    })
+    .await
+    .map_err(|e| network_error(format!("failed to send batch to RPC provider: {e}")))
+}
+
+/// Match the responses to a batch with the queries that were sent, by their ids. Providers may
+/// answer the queries of a batch in any order.
+fn order_responses(responses: Value, len: usize) -> Result<Vec<Value>, String> {
+    let responses = match responses {
+        Value::Array(responses) => responses,
+
+        // a provider which doesn't support batches answers with a single error
+        response => {
+            return Err(match response.get("error") {
+                Some(error) => format!("batch rejected: {error}"),
+                None => format!("invalid response: {response}"),
+            })
+        }
+    };
+
+    let mut results = vec![None; len];
+    for response in responses {
+        if let Some(error) = response.get("error") {
+            return Err(format!("query failed: {error}"))
+        }
+        let id = response.get("id").and_then(Value::as_u64).map(|id| id as usize);
+        match (id, response.get("result")) {
+            (Some(id), Some(result)) if id < len => results[id] = Some(result.clone()),
+            _ => return Err(format!("invalid response: {response}")),
+        }
+    }
+
+    results.into_iter().collect::<Option<Vec<_>>>().ok_or("missing responses in batch".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use heimdall_cache::delete_cache;
+
+    use super::*;
+
+    #[test]
+    fn test_order_responses_out_of_order() {
+        let responses = json!([
+            { "jsonrpc": "2.0", "id": 1, "result": "0x02" },
+            { "jsonrpc": "2.0", "id": 0, "result": "0x01" },
+        ]);
+
+        assert_eq!(order_responses(responses, 2), Ok(vec![json!("0x01"), json!("0x02")]));
+    }
+
+    #[test]
+    fn test_order_responses_errors() {
+        let missing = json!([{ "jsonrpc": "2.0", "id": 0, "result": "0x01" }]);
+        let failed = json!([
+            { "jsonrpc": "2.0", "id": 0, "result": "0x01" },
+            { "jsonrpc": "2.0", "id": 1, "error": { "code": -32000, "message": "missing trie node" } },
+        ]);
+        let rejected = json!({ "jsonrpc": "2.0", "id": null, "error": { "code": -32600 } });
+
+        assert!(order_responses(missing, 2).is_err());
+        assert!(order_responses(failed, 2).is_err());
+        assert!(order_responses(rejected, 1).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_cached_batch_skips_provider() {
+        let slot = H256::from_low_u64_be(0x6865696d64616c6c);
+        let value = H256::from_low_u64_be(1);
+        let key = format!("storage.1.0x0.{:?}.1", slot);
+
+        // the entry is written to a temporary cache, rather than the user's
+        std::env::set_var("HEIMDALL_CACHE_DIR", std::env::temp_dir().join("heimdall-test-cache"));
+        store_cache(&key, value, None).unwrap();
+
+        // nothing is fetched, so the batch succeeds without an RPC provider
+        let values = get_storage_batch("0x0", &[slot, slot], 1, "").await;
+        delete_cache(&key).unwrap();
+        std::env::remove_var("HEIMDALL_CACHE_DIR");
+        assert_eq!(values.unwrap(), vec![value, value]);
+    }
+}
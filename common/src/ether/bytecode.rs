@@ -1,12 +1,67 @@
-use super::rpc::get_code;
+use super::{evm::core::opcodes::Opcode, rpc::get_code};
 use crate::{
     constants::{ADDRESS_REGEX, BYTECODE_REGEX},
     error::Error,
-    utils::io::logging::Logger,
+    utils::io::{
+        crash::set_crash_bytecode,
+        exit::{record_failure, ExitStatus},
+        input::normalize_input,
+        logging::Logger,
+    },
 };
-use std::fs;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, fs, ops::Range};
+
+/// The prefix of an EIP-7702 delegation designator, which is followed by the delegate's address.
+const DELEGATION_PREFIX: &str = "ef0100";
+
+/// The fewest printable bytes in a row which are classified as an embedded string.
+const MIN_STRING_LENGTH: usize = 8;
+
+/// The fewest jump targets in a row which are classified as a jump table.
+const MIN_JUMP_TABLE_ENTRIES: usize = 2;
 
 pub async fn get_bytecode_from_target(target: &str, rpc_url: &str) -> Result<String, Error> {
+    let mut bytecode = _get_bytecode_from_target(target, rpc_url).await?;
+
+    // an EOA which delegates to a contract runs the contract's code, so analyze that instead.
+    // designators aren't followed any further, as the EVM doesn't follow them either
+    if let Some(delegate) = get_delegation_target(&bytecode) {
+        let (logger, _) = Logger::new("");
+        logger.info(&format!("'{target}' is an EOA which delegates to {delegate} (EIP-7702)."));
+        bytecode = get_code(&delegate, rpc_url).await.map_err(|e| {
+            Error::Generic(format!("failed to fetch delegated bytecode from RPC provider: {}", e))
+        })?;
+    }
+
+    // only the hash of the bytecode is kept for crash reports
+    set_crash_bytecode(&bytecode);
+
+    Ok(bytecode)
+}
+
+/// The address the target delegates its code to under EIP-7702, if it's an EOA with a delegation
+/// designator.
+pub async fn get_delegation_from_target(
+    target: &str,
+    rpc_url: &str,
+) -> Result<Option<String>, Error> {
+    Ok(get_delegation_target(&_get_bytecode_from_target(target, rpc_url).await?))
+}
+
+/// The delegate's address if the bytecode is an EIP-7702 delegation designator, i.e.
+/// `0xef0100 . address`.
+pub fn get_delegation_target(bytecode: &str) -> Option<String> {
+    let bytecode = bytecode.strip_prefix("0x").unwrap_or(bytecode).to_lowercase();
+    match bytecode.strip_prefix(DELEGATION_PREFIX) {
+        Some(address) if address.len() == 40 && address.chars().all(|c| c.is_ascii_hexdigit()) => {
+            Some(format!("0x{address}"))
+        }
+        _ => None,
+    }
+}
+
+async fn _get_bytecode_from_target(target: &str, rpc_url: &str) -> Result<String, Error> {
     let (logger, _) = Logger::new("");
 
     if ADDRESS_REGEX
@@ -29,25 +84,276 @@ pub async fn get_bytecode_from_target(target: &str, rpc_url: &str) -> Result<Str
             Ok(contents) => {
                 logger.debug(&format!("reading bytecode from '{}'", &target));
 
-                let _contents = contents.replace('\n', "");
+                let _contents = normalize_input(&contents);
                 if BYTECODE_REGEX
                     .is_match(&_contents)
                     .map_err(|e| Error::Generic(format!("failed to match bytecode regex: {}", e)))? &&
-                    _contents.len() % 2 == 0
+                    _contents.len().is_multiple_of(2)
                 {
                     Ok(_contents.replacen("0x", "", 1))
                 } else {
-                    logger.error(&format!("file '{}' doesn't contain valid bytecode.", &target));
-                    std::process::exit(1)
+                    record_failure(ExitStatus::UnsupportedBytecode);
+                    Err(Error::Generic(format!(
+                        "file '{}' doesn't contain valid bytecode.",
+                        &target
+                    )))
                 }
             }
             Err(_) => {
-                logger.error(&format!("failed to open file '{}' .", &target));
-                std::process::exit(1)
+                // the target isn't an address, bytecode, or a readable file
+                record_failure(ExitStatus::InvalidArguments);
+                Err(Error::Generic(format!("failed to open file '{}' .", &target)))
+            }
+        }
+    }
+}
+
+/// How a contract's bytes are split between executable code, trailing data, and compiler
+/// metadata, in that order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BytecodeSections {
+    /// The length of the executable code, starting at offset 0
+    pub code: usize,
+    /// The length of the data which follows the code, such as Vyper's data section or padding
+    pub data: usize,
+    /// The length of the CBOR-encoded metadata at the end of the bytecode
+    pub metadata: usize,
+}
+
+impl BytecodeSections {
+    /// The offset the data section begins at.
+    pub fn data_start(&self) -> usize {
+        self.code
+    }
+
+    /// The offset the metadata begins at.
+    pub fn metadata_start(&self) -> usize {
+        self.code + self.data
+    }
+}
+
+/// The length of the CBOR-encoded metadata at the end of the bytecode, including its two byte
+/// length suffix, or 0 if there isn't any or it's malformed.
+pub fn metadata_length(bytecode: &[u8]) -> usize {
+    if bytecode.len() < 2 {
+        return 0
+    }
+
+    let length =
+        u16::from_be_bytes([bytecode[bytecode.len() - 2], bytecode[bytecode.len() - 1]]) as usize;
+    match bytecode.len().checked_sub(length + 2) {
+        // metadata is always a CBOR map
+        Some(start) if length > 0 && (0xa1..=0xa5).contains(&bytecode[start]) => length + 2,
+        _ => 0,
+    }
+}
+
+/// Split the bytecode into code, data, and metadata. Code after the last `JUMPDEST` can only be
+/// reached by falling through to it, so anything after the first halting instruction which
+/// follows it is unreachable, and is treated as data rather than disassembled.
+pub fn split_sections(bytecode: &[u8]) -> BytecodeSections {
+    let metadata = metadata_length(bytecode);
+    let end = bytecode.len() - metadata;
+
+    let mut code_end = None;
+    let mut program_counter = 0;
+    while program_counter < end {
+        let operation = Opcode::new(bytecode[program_counter]);
+        let byte_count = operation
+            .name
+            .strip_prefix("PUSH")
+            .and_then(|count| count.parse::<usize>().ok())
+            .unwrap_or(0);
+        let next = (program_counter + 1 + byte_count).min(end);
+
+        match operation.name {
+            "JUMPDEST" => code_end = None,
+            "STOP" | "JUMP" | "RETURN" | "REVERT" | "INVALID" | "SELFDESTRUCT" => {
+                code_end = code_end.or(Some(next))
+            }
+            _ => {}
+        }
+        program_counter = next;
+    }
+
+    let code = code_end.unwrap_or(end);
+    BytecodeSections { code, data: end - code, metadata }
+}
+
+/// What a region of a contract's bytes holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RegionKind {
+    /// Reachable opcodes
+    Code,
+    /// The bytes pushed by a PUSH in reachable code
+    PushData,
+    /// Bytes which can't be executed, and weren't recognized as anything else
+    Data,
+    /// A run of printable ASCII which can't be executed
+    String,
+    /// A run of big-endian, two byte offsets which are each a `JUMPDEST`
+    JumpTable,
+    /// The CBOR-encoded compiler metadata at the end of the bytecode
+    Metadata,
+}
+
+impl RegionKind {
+    /// The name the region is disassembled as, e.g. `JUMPTABLE`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            RegionKind::Code => "CODE",
+            RegionKind::PushData => "PUSHDATA",
+            RegionKind::Data => "DATA",
+            RegionKind::String => "STRING",
+            RegionKind::JumpTable => "JUMPTABLE",
+            RegionKind::Metadata => "METADATA",
+        }
+    }
+}
+
+/// A run of bytes which all hold the same kind of thing.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Region {
+    pub kind: RegionKind,
+    pub offsets: Range<usize>,
+}
+
+/// Classify every byte of the bytecode as code, pushed bytes, data, an embedded string, a jump
+/// table, or metadata. Adjacent bytes of the same kind are merged into one region, and the regions
+/// are in order.
+///
+/// Besides the data section, code between a halting instruction and the next `JUMPDEST` can't be
+/// reached, so it's classified as data too, as is a PUSH which runs past the end of the code.
+///
+/// ```
+/// use heimdall_common::ether::bytecode::{classify_regions, RegionKind};
+///
+/// // PUSH1 0x01 STOP, then "revert: too low" which is never executed
+/// let mut bytecode = vec![0x60, 0x01, 0x00];
+/// bytecode.extend(b"revert: too low");
+///
+/// let regions = classify_regions(&bytecode);
+/// assert_eq!(regions[0].kind, RegionKind::Code);
+/// assert_eq!(regions[1].kind, RegionKind::PushData);
+/// assert_eq!(regions[3].kind, RegionKind::String);
+/// assert_eq!(regions[3].offsets, 3..18);
+/// ```
+pub fn classify_regions(bytecode: &[u8]) -> Vec<Region> {
+    let sections = split_sections(bytecode);
+
+    // every JUMPDEST is needed before data can be checked for jump tables
+    let mut jumpdests = HashSet::new();
+    let mut program_counter = 0;
+    while program_counter < sections.code {
+        let operation = Opcode::new(bytecode[program_counter]);
+        if operation.name == "JUMPDEST" {
+            jumpdests.insert(program_counter);
+        }
+        program_counter += 1 + push_size(&operation);
+    }
+
+    let mut regions = Vec::new();
+    let mut unreachable_start = None;
+    let mut program_counter = 0;
+    while program_counter < sections.code {
+        let operation = Opcode::new(bytecode[program_counter]);
+        let next = program_counter + 1 + push_size(&operation);
+
+        if operation.name == "JUMPDEST" {
+            if let Some(start) = unreachable_start.take() {
+                classify_data(bytecode, start..program_counter, &jumpdests, &mut regions);
             }
         }
+        if next > sections.code {
+            unreachable_start = unreachable_start.or(Some(program_counter));
+            break
+        }
+
+        if unreachable_start.is_none() {
+            push_region(&mut regions, RegionKind::Code, program_counter..program_counter + 1);
+            push_region(&mut regions, RegionKind::PushData, program_counter + 1..next);
+
+            if matches!(
+                operation.name,
+                "STOP" | "JUMP" | "RETURN" | "REVERT" | "INVALID" | "SELFDESTRUCT"
+            ) {
+                unreachable_start = Some(next);
+            }
+        }
+        program_counter = next;
+    }
+
+    let data_start = unreachable_start.unwrap_or(sections.code).min(sections.code);
+    classify_data(bytecode, data_start..sections.metadata_start(), &jumpdests, &mut regions);
+    push_region(
+        &mut regions,
+        RegionKind::Metadata,
+        sections.metadata_start()..sections.metadata_start() + sections.metadata,
+    );
+
+    regions
+}
+
+/// The number of bytes an opcode pushes.
+fn push_size(operation: &Opcode) -> usize {
+    operation.name.strip_prefix("PUSH").and_then(|count| count.parse::<usize>().ok()).unwrap_or(0)
+}
+
+/// Add a region, merging it into the last one if they're adjacent and of the same kind. Empty
+/// regions are ignored.
+fn push_region(regions: &mut Vec<Region>, kind: RegionKind, offsets: Range<usize>) {
+    if offsets.is_empty() {
+        return
+    }
+
+    match regions.last_mut() {
+        Some(last) if last.kind == kind && last.offsets.end == offsets.start => {
+            last.offsets.end = offsets.end
+        }
+        _ => regions.push(Region { kind, offsets }),
     }
 }
+
+/// Split bytes which can't be executed into jump tables, embedded strings, and plain data.
+fn classify_data(
+    bytecode: &[u8],
+    offsets: Range<usize>,
+    jumpdests: &HashSet<usize>,
+    regions: &mut Vec<Region>,
+) {
+    let mut offset = offsets.start;
+    while offset < offsets.end {
+        let entries = (offset..)
+            .step_by(2)
+            .take_while(|entry| {
+                entry + 2 <= offsets.end &&
+                    jumpdests.contains(
+                        &(u16::from_be_bytes([bytecode[*entry], bytecode[entry + 1]]) as usize),
+                    )
+            })
+            .count();
+        if entries >= MIN_JUMP_TABLE_ENTRIES {
+            push_region(regions, RegionKind::JumpTable, offset..offset + 2 * entries);
+            offset += 2 * entries;
+            continue
+        }
+
+        let printable = bytecode[offset..offsets.end]
+            .iter()
+            .take_while(|byte| byte.is_ascii_graphic() || **byte == b' ')
+            .count();
+        if printable >= MIN_STRING_LENGTH {
+            push_region(regions, RegionKind::String, offset..offset + printable);
+            offset += printable;
+            continue
+        }
+
+        push_region(regions, RegionKind::Data, offset..offset + 1);
+        offset += 1;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -91,4 +397,90 @@ mod tests {
 
         fs::remove_file(file_path).unwrap();
     }
+
+    #[test]
+    fn test_metadata_length() {
+        // a2 64 'ipfs' 58 22 <34 bytes> 64 'solc' 43 <3 bytes> 00 33
+        let mut bytecode = vec![0x60, 0x00, 0xfe, 0xa2, 0x64];
+        bytecode.extend(b"ipfs");
+        bytecode.extend([0x58, 0x22]);
+        bytecode.extend([0u8; 34]);
+        bytecode.push(0x64);
+        bytecode.extend(b"solc");
+        bytecode.extend([0x43, 0x00, 0x08, 0x13, 0x00, 0x33]);
+
+        assert_eq!(metadata_length(&bytecode), 0x35);
+        assert_eq!(metadata_length(&[0x60, 0x00, 0x00]), 0);
+    }
+
+    #[test]
+    fn test_split_sections_trailing_data() {
+        // JUMPDEST PUSH1 0x00 JUMP, followed by data which looks like a truncated PUSH32
+        let bytecode = [0x5b, 0x60, 0x00, 0x56, 0x7f, 0x01, 0x02];
+
+        let sections = split_sections(&bytecode);
+        assert_eq!(sections, BytecodeSections { code: 4, data: 3, metadata: 0 });
+        assert_eq!(sections.metadata_start(), 7);
+    }
+
+    #[test]
+    fn test_split_sections_without_metadata() {
+        // PUSH1 0x00 JUMPI JUMPDEST STOP, with stripped metadata
+        let bytecode = [0x60, 0x00, 0x57, 0x5b, 0x00];
+        assert_eq!(split_sections(&bytecode), BytecodeSections { code: 5, data: 0, metadata: 0 });
+
+        // malformed metadata is kept as data, since it can't be reached
+        let bytecode = [0x5b, 0x00, 0xa2, 0x64, 0x69, 0x70, 0xff, 0xff];
+        assert_eq!(split_sections(&bytecode), BytecodeSections { code: 2, data: 6, metadata: 0 });
+    }
+
+    #[test]
+    fn test_classify_regions_unreachable_code() {
+        // PUSH1 0x06 JUMP, two unreachable bytes, JUMPDEST STOP, then a jump table of 0x0006 twice
+        let bytecode = [0x60, 0x06, 0x56, 0x01, 0x02, 0x00, 0x5b, 0x00, 0x00, 0x06, 0x00, 0x06];
+        let regions = classify_regions(&bytecode)
+            .into_iter()
+            .map(|region| (region.kind, region.offsets))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            regions,
+            vec![
+                (RegionKind::Code, 0..1),
+                (RegionKind::PushData, 1..2),
+                (RegionKind::Code, 2..3),
+                (RegionKind::Data, 3..6),
+                (RegionKind::Code, 6..8),
+                (RegionKind::JumpTable, 8..12),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_classify_regions_truncated_push() {
+        // JUMPDEST, then a PUSH2 missing a byte, then metadata
+        let bytecode = [0x5b, 0x61, 0x01, 0xa1, 0x00, 0x00, 0x02];
+        let regions = classify_regions(&bytecode)
+            .into_iter()
+            .map(|region| (region.kind, region.offsets))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            regions,
+            vec![(RegionKind::Code, 0..1), (RegionKind::Data, 1..3), (RegionKind::Metadata, 3..7)]
+        );
+    }
+
+    #[test]
+    fn test_get_delegation_target() {
+        assert_eq!(
+            get_delegation_target("0xef010063c0c19a282a1b52b07dd5a65b58948a07dae32b"),
+            Some(String::from("0x63c0c19a282a1b52b07dd5a65b58948a07dae32b"))
+        );
+
+        // a truncated designator, or code which merely starts with the prefix, isn't a delegation
+        assert_eq!(get_delegation_target("ef010063c0c19a282a1b52b07dd5a65b58948a07dae3"), None);
+        assert_eq!(get_delegation_target("ef010063c0c19a282a1b52b07dd5a65b58948a07dae32b00"), None);
+        assert_eq!(get_delegation_target("6080604052"), None);
+    }
 }
@@ -0,0 +1,239 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use crate::{
+    error::Error,
+    utils::strings::{decode_hex, encode_hex},
+};
+
+/// Where a contract's bytecode is stored in a [`Corpus`].
+#[derive(Debug, Clone)]
+enum Location {
+    /// A file containing a single contract's bytecode
+    File(PathBuf),
+    /// A line of a corpus file
+    Line { name: String, offset: u64, length: usize },
+}
+
+/// A single contract read from a [`Corpus`]. Its bytecode is only decoded when it's used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorpusEntry {
+    /// The file name, or the label given on the contract's line
+    pub name: String,
+    raw: Vec<u8>,
+}
+
+impl CorpusEntry {
+    /// The contract's bytecode, as a hex string without the `0x` prefix. Files containing raw
+    /// bytes rather than hex are encoded.
+    pub fn bytecode(&self) -> String {
+        match std::str::from_utf8(&self.raw) {
+            Ok(text) if is_hex(text.trim()) => text.trim().trim_start_matches("0x").to_lowercase(),
+            _ => encode_hex(self.raw.clone()),
+        }
+    }
+
+    /// The contract's bytecode, as bytes.
+    pub fn bytes(&self) -> Result<Vec<u8>, Error> {
+        decode_hex(&self.bytecode())
+            .map_err(|e| Error::Generic(format!("invalid bytecode in '{}': {e}", self.name)))
+    }
+}
+
+fn is_hex(text: &str) -> bool {
+    let text = text.strip_prefix("0x").unwrap_or(text);
+    !text.is_empty() && text.len().is_multiple_of(2) && text.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Split a corpus line into its label, if any, and its bytecode. Lines are either bytecode, or a
+/// label and bytecode separated by a comma or whitespace, i.e. `0xabc..,0x6080..`.
+fn split_line(line: &str) -> (Option<&str>, &str) {
+    match line.trim().rsplit_once(|c: char| c == ',' || c.is_whitespace()) {
+        Some((name, bytecode)) => (Some(name.trim()), bytecode),
+        None => (None, line.trim()),
+    }
+}
+
+/// A collection of contracts' bytecode, which is read from disk as each contract is needed, so
+/// corpora of millions of contracts can be scanned without holding them in memory. \
+/// \
+/// A corpus is either a directory with one file per contract, containing hex or raw bytecode,
+/// or a file with one contract per line. Only the name and location of each contract are kept in
+/// memory.
+#[derive(Debug)]
+pub struct Corpus {
+    path: PathBuf,
+    locations: Vec<Location>,
+    file: Mutex<Option<File>>,
+}
+
+impl Corpus {
+    /// Open the corpus at the given path, indexing where each contract is stored.
+    pub fn open(path: &str) -> Result<Corpus, Error> {
+        let path = PathBuf::from(path);
+        let locations = match path.is_dir() {
+            true => Self::index_directory(&path)?,
+            false => Self::index_file(&path)?,
+        };
+
+        Ok(Corpus { path, locations, file: Mutex::new(None) })
+    }
+
+    fn index_directory(path: &Path) -> Result<Vec<Location>, Error> {
+        let mut files = std::fs::read_dir(path)
+            .map_err(|e| Error::Generic(format!("failed to read corpus directory: {e}")))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.is_file() &&
+                    !path
+                        .file_name()
+                        .map(|name| name.to_string_lossy().starts_with('.'))
+                        .unwrap_or(true)
+            })
+            .collect::<Vec<PathBuf>>();
+        files.sort();
+
+        Ok(files.into_iter().map(Location::File).collect())
+    }
+
+    fn index_file(path: &Path) -> Result<Vec<Location>, Error> {
+        let file = File::open(path).map_err(|e| {
+            Error::Generic(format!("failed to open corpus '{}': {e}", path.display()))
+        })?;
+        let mut reader = BufReader::new(file);
+
+        let mut locations = Vec::new();
+        let mut line = Vec::new();
+        let mut offset = 0u64;
+        let mut line_number = 0usize;
+        loop {
+            line.clear();
+            let read = reader
+                .read_until(b'\n', &mut line)
+                .map_err(|e| Error::Generic(format!("failed to read corpus: {e}")))?;
+            if read == 0 {
+                break
+            }
+            line_number += 1;
+
+            let text = String::from_utf8_lossy(&line);
+            if !text.trim().is_empty() && !text.trim_start().starts_with('#') {
+                let name = match split_line(&text) {
+                    (Some(name), _) => name.to_string(),
+                    (None, _) => format!("line {line_number}"),
+                };
+                locations.push(Location::Line { name, offset, length: read });
+            }
+            offset += read as u64;
+        }
+
+        Ok(locations)
+    }
+
+    pub fn len(&self) -> usize {
+        self.locations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.locations.is_empty()
+    }
+
+    /// Read the contract at the given index.
+    pub fn get(&self, index: usize) -> Result<CorpusEntry, Error> {
+        match self.locations.get(index) {
+            Some(Location::File(path)) => Ok(CorpusEntry {
+                name: path
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                raw: std::fs::read(path).map_err(|e| {
+                    Error::Generic(format!("failed to read '{}': {e}", path.display()))
+                })?,
+            }),
+            Some(Location::Line { name, offset, length }) => {
+                let mut file = self
+                    .file
+                    .lock()
+                    .map_err(|_| Error::Generic("corpus file lock poisoned".to_string()))?;
+                if file.is_none() {
+                    *file = Some(
+                        File::open(&self.path)
+                            .map_err(|e| Error::Generic(format!("failed to open corpus: {e}")))?,
+                    );
+                }
+                let file = file.as_mut().expect("corpus file was just opened");
+
+                let mut line = vec![0u8; *length];
+                file.seek(SeekFrom::Start(*offset))
+                    .and_then(|_| file.read_exact(&mut line))
+                    .map_err(|e| Error::Generic(format!("failed to read corpus: {e}")))?;
+
+                let text = String::from_utf8_lossy(&line);
+                Ok(CorpusEntry { name: name.clone(), raw: split_line(&text).1.as_bytes().to_vec() })
+            }
+            None => Err(Error::Generic(format!(
+                "corpus has {} contracts, no contract at index {index}",
+                self.len()
+            ))),
+        }
+    }
+
+    /// Read each contract in the corpus, in order.
+    ///
+    /// ```
+    /// use heimdall_common::ether::corpus::Corpus;
+    ///
+    /// std::fs::write("/tmp/heimdall-corpus.txt", "weth,0x6080\n0x6001\n").unwrap();
+    ///
+    /// let corpus = Corpus::open("/tmp/heimdall-corpus.txt").unwrap();
+    /// let names = corpus.iter().map(|entry| entry.unwrap().name).collect::<Vec<_>>();
+    /// assert_eq!(names, vec!["weth", "line 2"]);
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = Result<CorpusEntry, Error>> + '_ {
+        (0..self.len()).map(|index| self.get(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ether::corpus::*;
+
+    #[test]
+    fn test_corpus_directory() {
+        let directory = std::env::temp_dir().join("heimdall-corpus-directory");
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("a.hex"), "0x6080604052\n").unwrap();
+        std::fs::write(directory.join("b.bin"), [0x60u8, 0x01]).unwrap();
+
+        let corpus = Corpus::open(&directory.to_string_lossy()).unwrap();
+        assert_eq!(corpus.len(), 2);
+        assert_eq!(corpus.get(0).unwrap().bytecode(), "6080604052");
+        assert_eq!(corpus.get(1).unwrap().name, "b");
+        assert_eq!(corpus.get(1).unwrap().bytecode(), "6001");
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_corpus_file_random_access() {
+        let path = std::env::temp_dir().join("heimdall-corpus-file.txt");
+        std::fs::write(&path, "# comment\n0xabc 0x6080\n\n0xdef,6001\n0x5b00\n").unwrap();
+
+        let corpus = Corpus::open(&path.to_string_lossy()).unwrap();
+        assert_eq!(corpus.len(), 3);
+
+        let entry = corpus.get(1).unwrap();
+        assert_eq!(entry.name, "0xdef");
+        assert_eq!(entry.bytes().unwrap(), vec![0x60, 0x01]);
+        assert_eq!(corpus.get(2).unwrap().name, "line 5");
+        assert_eq!(corpus.get(0).unwrap().bytecode(), "6080");
+        assert!(corpus.get(3).is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,43 @@
+use ethers::{
+    types::H160,
+    utils::{get_contract_address, get_create2_address_from_hash, keccak256},
+};
+
+/// The address a contract is deployed to with `CREATE`, which depends only on the deployer and
+/// its nonce at the time.
+///
+/// ```
+/// use ethers::types::H160;
+/// use heimdall_common::ether::create::create_address;
+///
+/// let deployer: H160 = "0x6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0".parse().unwrap();
+/// assert_eq!(
+///     create_address(deployer, 0),
+///     "0xcd234a471b72ba2f1ccf0a70fcaba648a5eecd8d".parse::<H160>().unwrap()
+/// );
+/// ```
+pub fn create_address(deployer: H160, nonce: u64) -> H160 {
+    get_contract_address(deployer, nonce)
+}
+
+/// The address a contract is deployed to with `CREATE2`, which depends on the deployer, the salt,
+/// and the hash of the init code, i.e. the creation code followed by any constructor arguments.
+///
+/// ```
+/// use ethers::types::H160;
+/// use heimdall_common::ether::create::{create2_address, init_code_hash};
+///
+/// // from EIP-1014's examples
+/// assert_eq!(
+///     create2_address(H160::zero(), [0u8; 32], init_code_hash(&[0x00])),
+///     "0x4d1a2e2bb4f88f0250f26ffff098b0b30b26bf38".parse::<H160>().unwrap()
+/// );
+/// ```
+pub fn create2_address(deployer: H160, salt: [u8; 32], init_code_hash: [u8; 32]) -> H160 {
+    get_create2_address_from_hash(deployer, salt, init_code_hash)
+}
+
+/// The hash of the init code which `CREATE2` addresses are derived from.
+pub fn init_code_hash(init_code: &[u8]) -> [u8; 32] {
+    keccak256(init_code)
+}
@@ -0,0 +1,631 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Mutex, RwLock,
+    },
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::time::sleep as async_sleep;
+
+use crate::{
+    debug_max,
+    error::Error,
+    ether::signatures::{SignatureProvider, VerifiedAbi},
+    utils::{http::get_json_from_url, offline::is_offline},
+};
+
+/// The most selectors looked up in a single request to an endpoint which supports bulk lookups.
+const MAX_BULK_LOOKUP: usize = 100;
+
+/// The most requests made to signature databases per second, unless configured otherwise.
+pub const DEFAULT_SIGNATURE_RATE_LIMIT: u32 = 10;
+
+static SIGNATURE_RATE_LIMIT: AtomicU32 = AtomicU32::new(DEFAULT_SIGNATURE_RATE_LIMIT);
+
+lazy_static! {
+    static ref SIGNATURE_ENDPOINTS: RwLock<Vec<SignatureEndpoint>> =
+        RwLock::new(default_signature_endpoints());
+    static ref UNHEALTHY_ENDPOINTS: RwLock<HashSet<String>> = RwLock::new(HashSet::new());
+    static ref NEXT_REQUEST: Mutex<Instant> = Mutex::new(Instant::now());
+    static ref SIGNATURE_TARGET: RwLock<Option<(String, String)>> = RwLock::new(None);
+    static ref VERIFIED_ABIS: RwLock<HashMap<String, Option<VerifiedAbi>>> =
+        RwLock::new(HashMap::new());
+}
+
+/// The API a [`SignatureEndpoint`] speaks. Self-hosted mirrors use the flavor of the database
+/// they mirror.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureFlavor {
+    /// The openchain.xyz signature database, e.g. `https://api.openchain.xyz/signature-database/v1/lookup`
+    Openchain,
+    /// The 4byte.directory API, e.g. `https://www.4byte.directory/api/v1`
+    #[serde(rename = "4byte")]
+    FourByte,
+    /// The verified ABI of the contract being analyzed, from the block explorer for its chain.
+    /// The URL is unused, since the explorer is picked by chain
+    Etherscan,
+    /// The verified ABI of the contract being analyzed, from a Sourcify server, e.g.
+    /// `https://sourcify.dev/server`
+    Sourcify,
+}
+
+/// A remote signature database used to resolve selectors.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignatureEndpoint {
+    pub url: String,
+    pub flavor: SignatureFlavor,
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+/// The endpoints used when none are configured. Verified ABIs are preferred, since they're ground
+/// truth for the contract they were verified for.
+pub fn default_signature_endpoints() -> Vec<SignatureEndpoint> {
+    vec![
+        SignatureEndpoint { url: String::new(), flavor: SignatureFlavor::Etherscan, enabled: true },
+        SignatureEndpoint {
+            url: String::from("https://sourcify.dev/server"),
+            flavor: SignatureFlavor::Sourcify,
+            enabled: true,
+        },
+        SignatureEndpoint {
+            url: String::from("https://api.openchain.xyz/signature-database/v1/lookup"),
+            flavor: SignatureFlavor::Openchain,
+            enabled: true,
+        },
+        SignatureEndpoint {
+            url: String::from("https://www.4byte.directory/api/v1"),
+            flavor: SignatureFlavor::FourByte,
+            enabled: false,
+        },
+    ]
+}
+
+impl SignatureEndpoint {
+    /// Parse an endpoint from `<FLAVOR>=<URL>`, a bare flavor which uses its default URL, or a
+    /// bare URL which is assumed to be an openchain mirror. A leading `!` disables the endpoint.
+    ///
+    /// ```
+    /// use heimdall_common::ether::endpoints::{SignatureEndpoint, SignatureFlavor};
+    ///
+    /// let endpoint = SignatureEndpoint::parse("!4byte=http://localhost:8000/api/v1").unwrap();
+    /// assert_eq!(endpoint.flavor, SignatureFlavor::FourByte);
+    /// assert!(!endpoint.enabled);
+    ///
+    /// let endpoint = SignatureEndpoint::parse("sourcify").unwrap();
+    /// assert_eq!(endpoint.url, "https://sourcify.dev/server");
+    /// ```
+    pub fn parse(value: &str) -> Result<SignatureEndpoint, Error> {
+        let (enabled, value) = match value.trim().strip_prefix('!') {
+            Some(value) => (false, value),
+            None => (true, value.trim()),
+        };
+
+        // a bare flavor uses the default endpoint of that flavor
+        if let Some(default) =
+            default_signature_endpoints().into_iter().find(|endpoint| endpoint.name() == value)
+        {
+            return Ok(SignatureEndpoint { enabled, ..default })
+        }
+
+        let (flavor, url) = match value.split_once('=') {
+            Some(("openchain", url)) => (SignatureFlavor::Openchain, url),
+            Some(("4byte", url)) => (SignatureFlavor::FourByte, url),
+            Some(("etherscan", url)) => (SignatureFlavor::Etherscan, url),
+            Some(("sourcify", url)) => (SignatureFlavor::Sourcify, url),
+            Some((flavor, _)) if !flavor.contains('/') => {
+                return Err(Error::Generic(format!("unknown signature endpoint flavor '{flavor}'")))
+            }
+            _ => (SignatureFlavor::Openchain, value),
+        };
+
+        Ok(SignatureEndpoint { url: url.trim_end_matches('/').to_string(), flavor, enabled })
+    }
+
+    /// Parse a comma separated list of endpoints, in order of preference.
+    pub fn parse_list(value: &str) -> Result<Vec<SignatureEndpoint>, Error> {
+        value.split(',').filter(|item| !item.trim().is_empty()).map(Self::parse).collect()
+    }
+
+    /// The name of the endpoint's flavor, as it's written in endpoint lists.
+    pub fn name(&self) -> &'static str {
+        match self.flavor {
+            SignatureFlavor::Openchain => "openchain",
+            SignatureFlavor::FourByte => "4byte",
+            SignatureFlavor::Etherscan => "etherscan",
+            SignatureFlavor::Sourcify => "sourcify",
+        }
+    }
+
+    /// Whether the endpoint is a signature database, rather than a source of verified ABIs. Only a
+    /// signature database can tell that a selector is unknown, since a verified ABI only covers
+    /// the contract it was verified for.
+    fn is_signature_database(&self) -> bool {
+        matches!(self.flavor, SignatureFlavor::Openchain | SignatureFlavor::FourByte)
+    }
+
+    /// The URL to look up the given selector at, if the endpoint is a signature database. `kind`
+    /// is either `function` or `event`.
+    fn lookup_url(&self, kind: &str, selector: &str) -> Option<String> {
+        match self.flavor {
+            SignatureFlavor::Openchain => {
+                Some(format!("{}?filter=true&{}=0x{}", self.url, kind, selector))
+            }
+            SignatureFlavor::FourByte => Some(format!(
+                "{}/{}/?hex_signature=0x{}",
+                self.url,
+                if kind == "event" { "event-signatures" } else { "signatures" },
+                selector
+            )),
+            SignatureFlavor::Etherscan | SignatureFlavor::Sourcify => None,
+        }
+    }
+
+    /// The verified ABI of the contract being analyzed, if the endpoint serves verified ABIs and
+    /// the contract is verified there. It's only fetched once per session.
+    async fn verified_abi(&self) -> Option<VerifiedAbi> {
+        let (address, rpc_url) = SIGNATURE_TARGET.read().ok()?.clone()?;
+        let key = format!("{}.{}.{}", self.name(), self.url, address);
+        if let Some(abi) = VERIFIED_ABIS.read().ok().and_then(|abis| abis.get(&key).cloned()) {
+            return abi
+        }
+
+        let abi = match self.flavor {
+            SignatureFlavor::Etherscan => VerifiedAbi::from_explorer(&address, &rpc_url).await,
+            SignatureFlavor::Sourcify => {
+                VerifiedAbi::from_sourcify(&self.url, &address, &rpc_url).await
+            }
+            _ => None,
+        };
+        if let Ok(mut abis) = VERIFIED_ABIS.write() {
+            abis.insert(key, abi.clone());
+        }
+        abi
+    }
+
+    /// The URL to look up all of the given selectors at, if the endpoint supports bulk lookups.
+    fn bulk_lookup_url(&self, kind: &str, selectors: &[String]) -> Option<String> {
+        match self.flavor {
+            SignatureFlavor::Openchain => Some(format!(
+                "{}?filter=true&{}={}",
+                self.url,
+                kind,
+                selectors
+                    .iter()
+                    .map(|selector| format!("0x{selector}"))
+                    .collect::<Vec<String>>()
+                    .join(",")
+            )),
+            _ => None,
+        }
+    }
+
+    /// Extract the text signatures of each selector from the endpoint's response to a bulk
+    /// lookup. Selectors the endpoint didn't return signatures for have none.
+    fn parse_bulk_response(
+        &self,
+        response: &Value,
+        kind: &str,
+        selectors: &[String],
+    ) -> Option<HashMap<String, Vec<String>>> {
+        response.get("result")?.get(kind)?;
+
+        Some(
+            selectors
+                .iter()
+                .map(|selector| {
+                    (
+                        selector.clone(),
+                        self.parse_response(response, kind, selector).unwrap_or_default(),
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    /// Extract the text signatures from the endpoint's response.
+    fn parse_response(&self, response: &Value, kind: &str, selector: &str) -> Option<Vec<String>> {
+        let (results, field) = match self.flavor {
+            SignatureFlavor::Openchain => (
+                response
+                    .get("result")
+                    .and_then(|result| result.get(kind))
+                    .and_then(|results| results.get(format!("0x{selector}"))),
+                "name",
+            ),
+            SignatureFlavor::FourByte => (response.get("results"), "text_signature"),
+            SignatureFlavor::Etherscan | SignatureFlavor::Sourcify => return None,
+        };
+
+        results.and_then(|results| results.as_array()).map(|results| {
+            results
+                .iter()
+                .filter_map(|result| result.get(field).and_then(|name| name.as_str()))
+                .map(|name| name.to_string())
+                .collect()
+        })
+    }
+
+    /// Skip this endpoint for the rest of the session.
+    fn mark_unhealthy(&self) {
+        if let Ok(mut unhealthy) = UNHEALTHY_ENDPOINTS.write() {
+            unhealthy.insert(self.url.clone());
+        }
+    }
+}
+
+/// Set the endpoints used to resolve selectors, in order of preference.
+pub fn set_signature_endpoints(endpoints: Vec<SignatureEndpoint>) {
+    if let Ok(mut current) = SIGNATURE_ENDPOINTS.write() {
+        *current = endpoints;
+    }
+    if let Ok(mut unhealthy) = UNHEALTHY_ENDPOINTS.write() {
+        unhealthy.clear();
+    }
+}
+
+/// The endpoints used to resolve selectors, in order of preference.
+pub fn signature_endpoints() -> Vec<SignatureEndpoint> {
+    SIGNATURE_ENDPOINTS.read().map(|endpoints| endpoints.clone()).unwrap_or_default()
+}
+
+/// The default rate limit, for configurations which don't set one.
+pub fn default_signature_rate_limit() -> u32 {
+    DEFAULT_SIGNATURE_RATE_LIMIT
+}
+
+/// Set the most requests made to signature databases per second. `0` disables the limit.
+pub fn set_signature_rate_limit(requests_per_second: u32) {
+    SIGNATURE_RATE_LIMIT.store(requests_per_second, Ordering::SeqCst);
+}
+
+/// Wait until another request to a signature database is allowed by the rate limit. Requests are
+/// spaced evenly, so concurrent lookups don't burst past what the databases accept.
+async fn throttle() {
+    let requests_per_second = SIGNATURE_RATE_LIMIT.load(Ordering::SeqCst);
+    if requests_per_second == 0 {
+        return
+    }
+
+    let wait = match NEXT_REQUEST.lock() {
+        Ok(mut next_request) => {
+            let now = Instant::now();
+            let slot = (*next_request).max(now);
+            *next_request = slot + Duration::from_secs(1) / requests_per_second;
+            slot - now
+        }
+        Err(_) => return,
+    };
+
+    if !wait.is_zero() {
+        async_sleep(wait).await;
+    }
+}
+
+#[async_trait]
+impl SignatureProvider for SignatureEndpoint {
+    async fn lookup(&self, kind: &str, selector: &str) -> Result<Option<Vec<String>>, Error> {
+        // a selector missing from a verified ABI may still be known to a signature database
+        let url = match self.lookup_url(kind, selector) {
+            Some(url) => url,
+            None => {
+                return Ok(self.verified_abi().await.and_then(|abi| abi.signatures(kind, selector)))
+            }
+        };
+
+        throttle().await;
+        let response = match get_json_from_url(&url, 10)
+            .await
+            .map_err(|e| Error::Generic(format!("error fetching signatures: {}", e)))?
+        {
+            Some(response) => response,
+            None => {
+                debug_max!("signature endpoint '{}' is unreachable, failing over", &self.url);
+                self.mark_unhealthy();
+                return Ok(None)
+            }
+        };
+
+        match self.parse_response(&response, kind, selector) {
+            Some(signatures) => Ok(Some(signatures)),
+            None => {
+                debug_max!("error parsing signatures from '{}', failing over", &self.url);
+                self.mark_unhealthy();
+                Ok(None)
+            }
+        }
+    }
+
+    async fn lookup_bulk(
+        &self,
+        kind: &str,
+        selectors: &[String],
+    ) -> Result<Option<HashMap<String, Vec<String>>>, Error> {
+        if !self.is_signature_database() {
+            let abi = self.verified_abi().await.unwrap_or_default();
+            return Ok(Some(
+                selectors
+                    .iter()
+                    .filter_map(|selector| {
+                        Some((selector.clone(), abi.signatures(kind, selector)?))
+                    })
+                    .collect(),
+            ))
+        }
+
+        let mut signatures = HashMap::new();
+        for chunk in selectors.chunks(MAX_BULK_LOOKUP) {
+            let url = match self.bulk_lookup_url(kind, chunk) {
+                Some(url) => url,
+                None => return Ok(None),
+            };
+
+            throttle().await;
+            let response = match get_json_from_url(&url, 10)
+                .await
+                .map_err(|e| Error::Generic(format!("error fetching signatures: {}", e)))?
+            {
+                Some(response) => response,
+                None => {
+                    debug_max!("signature endpoint '{}' is unreachable, failing over", &self.url);
+                    self.mark_unhealthy();
+                    break
+                }
+            };
+
+            match self.parse_bulk_response(&response, kind, chunk) {
+                Some(chunk_signatures) => signatures.extend(chunk_signatures),
+                None => {
+                    debug_max!("error parsing signatures from '{}', failing over", &self.url);
+                    self.mark_unhealthy();
+                    break
+                }
+            }
+        }
+
+        Ok((!signatures.is_empty()).then_some(signatures))
+    }
+}
+
+/// Set the contract being analyzed, whose verified ABI is used to resolve selectors by the
+/// `etherscan` and `sourcify` endpoints. `rpc_url` picks the chain it's verified on.
+pub fn set_signature_target(address: &str, rpc_url: &str) {
+    if let Ok(mut target) = SIGNATURE_TARGET.write() {
+        *target = Some((address.to_string(), rpc_url.to_string()));
+    }
+}
+
+/// The enabled endpoints which haven't been found to be unhealthy, in order of preference.
+fn healthy_signature_endpoints() -> Vec<SignatureEndpoint> {
+    let unhealthy = UNHEALTHY_ENDPOINTS.read().map(|set| set.clone()).unwrap_or_default();
+    signature_endpoints()
+        .into_iter()
+        .filter(|endpoint| endpoint.enabled && !unhealthy.contains(&endpoint.url))
+        .collect()
+}
+
+/// Look up the text signatures for the given selector, trying each enabled endpoint in order, and
+/// falling back to the next when one doesn't know the selector. Endpoints are health checked
+/// passively: one which can't be reached, or whose response can't be parsed, is marked unhealthy
+/// and skipped for the rest of the session. Returns an empty list if a signature database answered
+/// that it doesn't know the selector, and `None` if none could be reached, so an outage isn't
+/// mistaken for an unknown selector. `kind` is either `function` or `event`.
+pub async fn lookup_signatures(kind: &str, selector: &str) -> Result<Option<Vec<String>>, Error> {
+    // an unreachable endpoint isn't unhealthy when we're offline
+    if is_offline() {
+        return Ok(None)
+    }
+
+    let mut unknown = false;
+    for endpoint in healthy_signature_endpoints() {
+        match endpoint.lookup(kind, selector).await? {
+            Some(signatures) if !signatures.is_empty() => return Ok(Some(signatures)),
+            Some(_) => unknown = true,
+            None => continue,
+        }
+    }
+
+    Ok(unknown.then(Vec::new))
+}
+
+/// Look up the text signatures for many selectors at once, falling back through the enabled
+/// endpoints in order like [`lookup_signatures`]. A selector is only reported as unknown if every
+/// signature database was asked about it, so selectors which couldn't be looked up in bulk are
+/// missing from the result, and are left for [`lookup_signatures`] to resolve one at a time.
+/// `kind` is either `function` or `event`.
+pub async fn lookup_signatures_bulk(
+    kind: &str,
+    selectors: &[String],
+) -> Result<HashMap<String, Vec<String>>, Error> {
+    let mut signatures = HashMap::new();
+    if is_offline() {
+        return Ok(signatures)
+    }
+
+    let mut unknown: Option<HashSet<String>> = None;
+    for endpoint in healthy_signature_endpoints() {
+        let remaining = selectors
+            .iter()
+            .filter(|selector| !signatures.contains_key(*selector))
+            .cloned()
+            .collect::<Vec<String>>();
+        if remaining.is_empty() {
+            break
+        }
+
+        match endpoint.lookup_bulk(kind, &remaining).await? {
+            // a verified ABI omits the selectors it doesn't cover, rather than reporting them
+            // unknown
+            Some(found) if !endpoint.is_signature_database() => signatures.extend(found),
+            Some(found) => {
+                let found_unknown = found
+                    .iter()
+                    .filter(|(_, signatures)| signatures.is_empty())
+                    .map(|(selector, _)| selector.clone())
+                    .collect::<HashSet<String>>();
+                unknown = Some(match unknown {
+                    Some(unknown) => unknown.intersection(&found_unknown).cloned().collect(),
+                    None => found_unknown,
+                });
+                signatures
+                    .extend(found.into_iter().filter(|(_, signatures)| !signatures.is_empty()));
+            }
+            None => unknown = Some(HashSet::new()),
+        }
+    }
+
+    for selector in unknown.unwrap_or_default() {
+        signatures.insert(selector, Vec::new());
+    }
+
+    Ok(signatures)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::ether::endpoints::*;
+
+    #[test]
+    fn test_parse_endpoint_list() {
+        let endpoints = SignatureEndpoint::parse_list(
+            "http://localhost:3000/lookup/, 4byte=https://4byte.example",
+        )
+        .unwrap();
+
+        assert_eq!(endpoints.len(), 2);
+        assert_eq!(endpoints[0].flavor, SignatureFlavor::Openchain);
+        assert_eq!(endpoints[0].url, "http://localhost:3000/lookup");
+        assert_eq!(endpoints[1].flavor, SignatureFlavor::FourByte);
+        assert!(SignatureEndpoint::parse("sigdb=https://example.com").is_err());
+
+        // bare flavors use their default endpoints, so providers can be picked and ordered by name
+        let endpoints = SignatureEndpoint::parse_list("sourcify, !openchain, etherscan").unwrap();
+        assert_eq!(
+            endpoints.iter().map(|endpoint| endpoint.name()).collect::<Vec<_>>(),
+            vec!["sourcify", "openchain", "etherscan"]
+        );
+        assert_eq!(endpoints[1].url, "https://api.openchain.xyz/signature-database/v1/lookup");
+        assert!(!endpoints[1].enabled);
+    }
+
+    #[test]
+    fn test_lookup_urls() {
+        let endpoints = default_signature_endpoints();
+
+        // verified ABIs are fetched by address rather than looked up by selector
+        assert_eq!(endpoints[0].lookup_url("function", "ab"), None);
+        assert_eq!(endpoints[1].lookup_url("function", "ab"), None);
+        assert_eq!(
+            endpoints[2].lookup_url("event", "ab"),
+            Some(String::from(
+                "https://api.openchain.xyz/signature-database/v1/lookup?filter=true&event=0xab"
+            ))
+        );
+        assert_eq!(
+            endpoints[3].lookup_url("function", "ab"),
+            Some(String::from("https://www.4byte.directory/api/v1/signatures/?hex_signature=0xab"))
+        );
+    }
+
+    #[test]
+    fn test_parse_responses() {
+        let endpoints = default_signature_endpoints();
+
+        let openchain = json!({"result": {"function": {"0xab": [{"name": "a()"}]}}});
+        let fourbyte = json!({"results": [{"text_signature": "a()"}]});
+
+        assert_eq!(
+            endpoints[2].parse_response(&openchain, "function", "ab"),
+            Some(vec!["a()".to_string()])
+        );
+        assert_eq!(
+            endpoints[3].parse_response(&fourbyte, "function", "ab"),
+            Some(vec!["a()".to_string()])
+        );
+        assert_eq!(endpoints[2].parse_response(&fourbyte, "function", "ab"), None);
+    }
+
+    #[test]
+    fn test_bulk_lookups() {
+        let endpoints = default_signature_endpoints();
+        let selectors = vec![String::from("ab"), String::from("cd")];
+
+        assert_eq!(
+            endpoints[2].bulk_lookup_url("function", &selectors),
+            Some(String::from(
+                "https://api.openchain.xyz/signature-database/v1/lookup?filter=true&function=0xab,0xcd"
+            ))
+        );
+        assert_eq!(endpoints[3].bulk_lookup_url("function", &selectors), None);
+
+        // selectors missing from the response have no signatures, rather than failing the lookup
+        let response = json!({"result": {"function": {"0xab": [{"name": "a()"}], "0xcd": null}}});
+        assert_eq!(
+            endpoints[2].parse_bulk_response(&response, "function", &selectors),
+            Some(HashMap::from([
+                (String::from("ab"), vec![String::from("a()")]),
+                (String::from("cd"), vec![]),
+            ]))
+        );
+        assert_eq!(
+            endpoints[2].parse_bulk_response(&json!({"ok": false}), "function", &selectors),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_verified_abi_never_reports_unknown() {
+        let endpoint = default_signature_endpoints().remove(0);
+        let abi = VerifiedAbi::parse(
+            r#"[{"type":"function","name":"transfer","inputs":[{"name":"to","type":"address"},{"name":"amount","type":"uint256"}],"outputs":[],"stateMutability":"nonpayable"}]"#,
+        )
+        .unwrap();
+        set_signature_target("0x0000000000000000000000000000000000000001", "");
+        VERIFIED_ABIS.write().unwrap().insert(
+            format!("etherscan.{}.0x0000000000000000000000000000000000000001", endpoint.url),
+            Some(abi),
+        );
+
+        // a selector missing from the ABI is left to the signature databases
+        assert_eq!(
+            endpoint.lookup("function", "a9059cbb").await.unwrap(),
+            Some(vec![String::from("transfer(address,uint256)")])
+        );
+        assert_eq!(endpoint.lookup("function", "deadbeef").await.unwrap(), None);
+        assert_eq!(
+            endpoint
+                .lookup_bulk("function", &[String::from("a9059cbb"), String::from("deadbeef")])
+                .await
+                .unwrap()
+                .map(|found| found.len()),
+            Some(1)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_throttle_spaces_requests() {
+        set_signature_rate_limit(20);
+
+        let start = Instant::now();
+        for _ in 0..3 {
+            throttle().await;
+        }
+        set_signature_rate_limit(DEFAULT_SIGNATURE_RATE_LIMIT);
+
+        // the first request goes out immediately, and each after it waits 50ms
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+}
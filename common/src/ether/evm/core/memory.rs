@@ -1,6 +1,10 @@
+use std::ops::Range;
+
 use crate::utils::range_map::RangeMap;
 
-use super::opcodes::WrappedOpcode;
+use ethers::types::U256;
+
+use super::opcodes::{WrappedInput, WrappedOpcode};
 
 pub type ByteTracker = RangeMap;
 
@@ -109,6 +113,47 @@ impl Memory {
         self.bytes.write(offset, size, opcode);
     }
 
+    /// Copy `size` bytes within memory from `offset` to `dest_offset`, as `MCOPY` does. The
+    /// source and destination may overlap, in which case the result is as if the source was first
+    /// copied to an intermediate buffer. May extend the memory if necessary.
+    ///
+    /// Copied bytes keep the opcodes which last modified them at their source, so a value which is
+    /// copied can still be traced. Bytes which were never modified are attributed to `opcode`.
+    ///
+    /// ```
+    /// use heimdall_common::ether::evm::core::{memory::Memory, opcodes::WrappedOpcode};
+    ///
+    /// let mut memory = Memory::new();
+    /// memory.store(0, 4, &[0x01, 0x02, 0x03, 0x04]);
+    /// memory.copy_with_opcode(1, 0, 4, WrappedOpcode::new(0x5e, vec![]));
+    /// assert_eq!(memory.read(0, 5), vec![0x01, 0x01, 0x02, 0x03, 0x04]);
+    /// ```
+    pub fn copy_with_opcode(
+        &mut self,
+        dest_offset: usize,
+        offset: usize,
+        size: usize,
+        opcode: WrappedOpcode,
+    ) {
+        // Cap offsets and size to 2**16, as when storing
+        let dest_offset = dest_offset.min(65536);
+        let offset = offset.min(65536);
+        let size = size.min(65536);
+        if size == 0 {
+            return
+        }
+
+        // read the source in full before writing, so overlapping ranges are copied correctly
+        let value = self.read(offset, size);
+        let origins = self.origins(offset, size);
+
+        self.store(dest_offset, size, &value);
+        self.bytes.write(dest_offset, size, opcode);
+        for (range, origin) in origins {
+            self.bytes.write(range.start - offset + dest_offset, range.len(), origin);
+        }
+    }
+
     /// Read the given number of bytes from the memory at the given offset.
     /// If the offset + size is greater than the current size of the memory, null bytes will be
     /// appended to the value.
@@ -183,11 +228,93 @@ impl Memory {
     pub fn origin(&self, byte: usize) -> Option<WrappedOpcode> {
         self.bytes.get_by_offset(byte)
     }
+
+    /// Given a window of memory, returns each opcode which last modified bytes within it, along
+    /// with the bytes it modified as a half-open range clipped to the window. Adjacent bytes
+    /// written by the same opcode are merged into one range, and bytes which were never modified
+    /// are omitted.
+    ///
+    /// Unlike [`Memory::origin`], this recovers a value which was built from several writes, such
+    /// as a word packed with `MSTORE8`s.
+    ///
+    /// ```
+    /// use heimdall_common::ether::evm::core::{memory::Memory, opcodes::WrappedOpcode};
+    ///
+    /// let mut memory = Memory::new();
+    /// memory.store_with_opcode(0, 32, &[0xff], WrappedOpcode::new(0x52, vec![]));
+    /// memory.store_with_opcode(31, 1, &[0xee], WrappedOpcode::new(0x53, vec![]));
+    ///
+    /// let origins = memory.origins(16, 32);
+    /// assert_eq!(origins[0], (16..31, WrappedOpcode::new(0x52, vec![])));
+    /// assert_eq!(origins[1], (31..32, WrappedOpcode::new(0x53, vec![])));
+    /// ```
+    pub fn origins(&self, offset: usize, size: usize) -> Vec<(Range<usize>, WrappedOpcode)> {
+        let mut origins: Vec<(Range<usize>, WrappedOpcode)> = Vec::new();
+        for (range, opcode) in self.bytes.overlapping(offset, size) {
+            // byte tracker ranges are inclusive of their end
+            let range = range.start.max(offset)..(range.end + 1).min(offset + size);
+            match origins.last_mut() {
+                Some((last, last_opcode)) if last.end == range.start && last_opcode == opcode => {
+                    last.end = range.end
+                }
+                _ => origins.push((range, opcode.clone())),
+            }
+        }
+
+        origins
+    }
+
+    /// Given a window of memory which is a whole number of words, returns the expression each
+    /// word was stored with, if every word was either stored whole by an `MSTORE` or never
+    /// modified, in which case it's zero.
+    ///
+    /// ```
+    /// use heimdall_common::ether::evm::core::{
+    ///     memory::Memory,
+    ///     opcodes::{WrappedInput, WrappedOpcode},
+    /// };
+    ///
+    /// let caller = WrappedInput::Opcode(WrappedOpcode::new(0x33, vec![]));
+    /// let mut memory = Memory::new();
+    /// memory.store_with_opcode(
+    ///     0,
+    ///     32,
+    ///     &[0xff],
+    ///     WrappedOpcode::new(0x52, vec![WrappedInput::Raw(0.into()), caller.clone()]),
+    /// );
+    ///
+    /// assert_eq!(memory.words(0, 64), Some(vec![caller, WrappedInput::Raw(0.into())]));
+    /// assert_eq!(memory.words(16, 32), None);
+    /// ```
+    pub fn words(&self, offset: usize, size: usize) -> Option<Vec<WrappedInput>> {
+        if !size.is_multiple_of(32) {
+            return None
+        }
+
+        (offset..offset + size)
+            .step_by(32)
+            .map(|word| match self.origins(word, 32).as_slice() {
+                [] => Some(WrappedInput::Raw(U256::zero())),
+                [(range, origin)] if range.len() == 32 && origin.opcode.name == "MSTORE" => {
+                    origin.inputs.get(1).cloned()
+                }
+                _ => None,
+            })
+            .collect()
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{ether::evm::core::memory::Memory, utils::strings::decode_hex};
+    use ethers::types::U256;
+
+    use crate::{
+        ether::evm::core::{
+            memory::Memory,
+            opcodes::{WrappedInput, WrappedOpcode},
+        },
+        utils::strings::decode_hex,
+    };
 
     #[test]
     fn test_mstore_simple() {
@@ -334,4 +461,82 @@ mod tests {
         let memory = Memory::new();
         assert_eq!(memory.expansion_cost(32 * 32, 32), 101);
     }
+
+    #[test]
+    fn test_origins_of_packed_word() {
+        let mstore8 =
+            |offset: usize| WrappedOpcode::new(0x53, vec![WrappedInput::Raw(U256::from(offset))]);
+        let mut memory = Memory::new();
+        for offset in [28, 29, 31] {
+            memory.store_with_opcode(offset, 1, &[0xff], mstore8(offset));
+        }
+
+        // byte 30 was never written, so it has no origin
+        assert_eq!(
+            memory.origins(0, 32),
+            vec![(28..29, mstore8(28)), (29..30, mstore8(29)), (31..32, mstore8(31))]
+        );
+        assert_eq!(memory.origins(29, 1), vec![(29..30, mstore8(29))]);
+        assert!(memory.origins(31, 0).is_empty());
+    }
+
+    #[test]
+    fn test_origins_clipped_to_window() {
+        let mstore = WrappedOpcode::new(0x52, vec![WrappedInput::Raw(U256::zero())]);
+        let mstore8 = WrappedOpcode::new(0x53, vec![WrappedInput::Raw(U256::from(8))]);
+        let mut memory = Memory::new();
+        memory.store_with_opcode(0, 32, &[0xff], mstore.clone());
+        memory.store_with_opcode(8, 1, &[0xee], mstore8.clone());
+
+        // the split word's halves are reported separately, either side of the MSTORE8
+        assert_eq!(
+            memory.origins(4, 8),
+            vec![(4..8, mstore.clone()), (8..9, mstore8), (9..12, mstore)]
+        );
+    }
+
+    #[test]
+    fn test_copy_overlapping_forwards_and_backwards() {
+        let mcopy = WrappedOpcode::new(0x5e, vec![]);
+        let mut memory = Memory::new();
+        memory.store(0, 8, &[1, 2, 3, 4, 5, 6, 7, 8]);
+
+        // the destination overlaps the end of the source
+        memory.copy_with_opcode(2, 0, 4, mcopy.clone());
+        assert_eq!(memory.read(0, 8), vec![1, 2, 1, 2, 3, 4, 7, 8]);
+
+        // the destination overlaps the start of the source
+        memory.copy_with_opcode(0, 3, 4, mcopy.clone());
+        assert_eq!(memory.read(0, 8), vec![2, 3, 4, 7, 3, 4, 7, 8]);
+
+        // copying beyond the end of memory reads zeros, and extends it
+        memory.copy_with_opcode(40, 30, 4, mcopy.clone());
+        assert_eq!(memory.read(40, 4), vec![0, 0, 0, 0]);
+        assert_eq!(memory.size(), 64);
+
+        // an empty copy doesn't extend memory
+        memory.copy_with_opcode(1000, 0, 0, mcopy);
+        assert_eq!(memory.size(), 64);
+    }
+
+    #[test]
+    fn test_copy_propagates_origins() {
+        let mstore = WrappedOpcode::new(0x52, vec![WrappedInput::Raw(U256::zero())]);
+        let mstore8 = WrappedOpcode::new(0x53, vec![WrappedInput::Raw(U256::from(31))]);
+        let mcopy = WrappedOpcode::new(0x5e, vec![]);
+        let mut memory = Memory::new();
+        memory.store_with_opcode(0, 32, &[0xff], mstore.clone());
+        memory.store_with_opcode(31, 1, &[0xee], mstore8.clone());
+
+        // the last 8 bytes of the word, and 8 bytes which were never written
+        memory.copy_with_opcode(64, 24, 16, mcopy.clone());
+        assert_eq!(
+            memory.origins(64, 16),
+            vec![(64..71, mstore.clone()), (71..72, mstore8.clone()), (72..80, mcopy.clone())]
+        );
+
+        // a copy onto itself, shifted by a byte, moves each origin with its bytes
+        memory.copy_with_opcode(1, 0, 32, mcopy);
+        assert_eq!(memory.origins(0, 33), vec![(0..32, mstore), (32..33, mstore8)]);
+    }
 }
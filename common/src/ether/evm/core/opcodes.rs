@@ -87,7 +87,10 @@ impl Opcode {
             0x59 => Opcode { code, name: "MSIZE", mingas: 2, inputs: 0, outputs: 1 },
             0x5a => Opcode { code, name: "GAS", mingas: 2, inputs: 0, outputs: 1 },
             0x5b => Opcode { code, name: "JUMPDEST", mingas: 1, inputs: 0, outputs: 0 },
-            0x5f => Opcode { code, name: "PUSH0", mingas: 3, inputs: 0, outputs: 1 },
+            0x5c => Opcode { code, name: "TLOAD", mingas: 100, inputs: 1, outputs: 1 },
+            0x5d => Opcode { code, name: "TSTORE", mingas: 100, inputs: 2, outputs: 0 },
+            0x5e => Opcode { code, name: "MCOPY", mingas: 3, inputs: 3, outputs: 0 },
+            0x5f => Opcode { code, name: "PUSH0", mingas: 2, inputs: 0, outputs: 1 },
             0x60 => Opcode { code, name: "PUSH1", mingas: 3, inputs: 0, outputs: 1 },
             0x61 => Opcode { code, name: "PUSH2", mingas: 3, inputs: 0, outputs: 1 },
             0x62 => Opcode { code, name: "PUSH3", mingas: 3, inputs: 0, outputs: 1 },
@@ -172,11 +175,14 @@ impl Opcode {
     }
 }
 
-/// A WrappedInput can contain either a raw U256 value or a WrappedOpcode
+/// A WrappedInput can contain either a raw U256 value, a WrappedOpcode, or a named variable
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum WrappedInput {
     Raw(U256),
     Opcode(WrappedOpcode),
+    /// A value which no single path computes, such as a loop-carried variable, which takes a
+    /// new value each iteration
+    Variable(String),
 }
 
 /// A WrappedOpcode is an Opcode with its inputs wrapped in a WrappedInput
@@ -215,7 +221,7 @@ impl WrappedInput {
     /// ```
     pub fn depth(&self) -> u32 {
         match self {
-            WrappedInput::Raw(_) => 0,
+            WrappedInput::Raw(_) | WrappedInput::Variable(_) => 0,
             WrappedInput::Opcode(opcode) => opcode.depth(),
         }
     }
@@ -237,6 +243,7 @@ impl Display for WrappedInput {
         match self {
             WrappedInput::Raw(u256) => write!(f, "{u256}"),
             WrappedInput::Opcode(opcode) => write!(f, "{opcode}"),
+            WrappedInput::Variable(name) => write!(f, "{name}"),
         }
     }
 }
@@ -6,7 +6,10 @@ use std::{
 
 use ethers::prelude::U256;
 
-use super::opcodes::WrappedOpcode;
+use super::opcodes::{Opcode, WrappedOpcode};
+
+/// The most items the EVM allows on the stack.
+pub const MAX_STACK_SIZE: usize = 1024;
 
 /// The [`Stack`] struct represents the EVM stack.
 /// It is a LIFO data structure that holds a VecDeque of [`StackFrame`]s.
@@ -280,9 +283,92 @@ impl Display for Stack {
     }
 }
 
+/// A stack pattern which is invalid in the EVM, found at a single instruction. Corrupted or
+/// partial bytecode often contains these, i.e. a DUP16 with fewer than 16 items on the stack, or a
+/// block which leaves the stack unbalanced.
+///
+/// Execution isn't aborted when one is found. Missing items are read as unknown zero values, and
+/// the stack is allowed to grow past the limit, so the rest of the function is still analyzed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StackDiagnostic {
+    /// The pc of the instruction
+    pub pc: u128,
+    /// The name of the instruction
+    pub opcode: &'static str,
+    /// The stack depth the instruction needs, or the most the EVM allows
+    pub expected: usize,
+    /// The actual stack depth
+    pub actual: usize,
+    /// Whether the stack had too few items, rather than too many
+    pub underflow: bool,
+}
+
+impl StackDiagnostic {
+    /// Check an instruction's stack depths for patterns which exceed the EVM's limits. `before`
+    /// and `after` are the depths before and after the instruction was executed.
+    ///
+    /// ```
+    /// use heimdall_common::ether::evm::core::stack::StackDiagnostic;
+    ///
+    /// // DUP16 with only 3 items on the stack
+    /// let diagnostic = StackDiagnostic::check(0x1f, 0x8f, 3, 4).unwrap();
+    /// assert_eq!(diagnostic.expected, 16);
+    /// assert_eq!(
+    ///     diagnostic.to_string(),
+    ///     "stack underflow at pc 0x1f (DUP16): expected at least 16 items, found 3"
+    /// );
+    ///
+    /// assert_eq!(StackDiagnostic::check(0x1f, 0x8f, 16, 17), None);
+    /// ```
+    pub fn check(pc: u128, opcode: u8, before: usize, after: usize) -> Option<StackDiagnostic> {
+        let opcode = Opcode::new(opcode);
+        if before < opcode.inputs as usize {
+            return Some(StackDiagnostic {
+                pc,
+                opcode: opcode.name,
+                expected: opcode.inputs as usize,
+                actual: before,
+                underflow: true,
+            })
+        }
+
+        if after > MAX_STACK_SIZE {
+            return Some(StackDiagnostic {
+                pc,
+                opcode: opcode.name,
+                expected: MAX_STACK_SIZE,
+                actual: after,
+                underflow: false,
+            })
+        }
+
+        None
+    }
+}
+
+impl Display for StackDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.underflow {
+            true => write!(
+                f,
+                "stack underflow at pc {:#x} ({}): expected at least {} items, found {}",
+                self.pc, self.opcode, self.expected, self.actual
+            ),
+            false => write!(
+                f,
+                "stack overflow at pc {:#x} ({}): expected at most {} items, found {}",
+                self.pc, self.opcode, self.expected, self.actual
+            ),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::ether::evm::core::{opcodes::WrappedOpcode, stack::Stack};
+    use crate::ether::evm::core::{
+        opcodes::WrappedOpcode,
+        stack::{Stack, StackDiagnostic, MAX_STACK_SIZE},
+    };
     use ethers::types::U256;
 
     #[test]
@@ -348,4 +434,29 @@ mod tests {
         assert_eq!(stack.peek(2).value, U256::from(1));
         assert_eq!(stack.peek(3).value, U256::from(0));
     }
+
+    #[test]
+    fn test_stack_diagnostics() {
+        // SWAP16 reads 17 items
+        assert_eq!(
+            StackDiagnostic::check(0x10, 0x9f, 16, 16),
+            Some(StackDiagnostic {
+                pc: 0x10,
+                opcode: "SWAP16",
+                expected: 17,
+                actual: 16,
+                underflow: true
+            })
+        );
+        assert_eq!(StackDiagnostic::check(0x10, 0x9f, 17, 17), None);
+
+        // a PUSH onto a full stack
+        let diagnostic = StackDiagnostic::check(0x20, 0x60, MAX_STACK_SIZE, MAX_STACK_SIZE + 1);
+        assert_eq!(
+            diagnostic.map(|diagnostic| diagnostic.to_string()),
+            Some(String::from(
+                "stack overflow at pc 0x20 (PUSH1): expected at most 1024 items, found 1025"
+            ))
+        );
+    }
 }
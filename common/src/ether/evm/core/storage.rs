@@ -1,5 +1,16 @@
 use std::collections::{HashMap, HashSet};
 
+use super::opcodes::WrappedOpcode;
+
+pub type SlotTracker = HashMap<[u8; 32], StorageOrigin>;
+
+/// The expressions which computed the key and value of the last `SSTORE` to a slot.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StorageOrigin {
+    pub key: WrappedOpcode,
+    pub value: WrappedOpcode,
+}
+
 /// The [`Storage`] struct represents the storage of a contract. \
 /// \
 /// We keep track of the storage as a HashMap, as well as a HashSet of keys that have been accessed
@@ -8,6 +19,9 @@ use std::collections::{HashMap, HashSet};
 pub struct Storage {
     pub storage: HashMap<[u8; 32], [u8; 32]>,
     access_set: HashSet<[u8; 32]>,
+    /// Slot-tracking facility, allowing slots to be associated with the opcodes that last stored
+    /// to them
+    pub slots: SlotTracker,
 }
 
 impl Default for Storage {
@@ -25,7 +39,7 @@ impl Storage {
     /// let storage = Storage::new();
     /// ```
     pub fn new() -> Storage {
-        Storage { storage: HashMap::new(), access_set: HashSet::new() }
+        Storage { storage: HashMap::new(), access_set: HashSet::new(), slots: SlotTracker::new() }
     }
 
     /// Store a key-value pair in the storage map.
@@ -44,6 +58,42 @@ impl Storage {
         self.storage.insert(key, value);
     }
 
+    /// Store a key-value pair in the storage map, recording the opcodes which computed the key and
+    /// the value.
+    ///
+    /// ```
+    /// use heimdall_common::ether::evm::core::{opcodes::WrappedOpcode, storage::Storage};
+    ///
+    /// let mut storage = Storage::new();
+    /// storage.store_with_opcode(
+    ///     [1u8; 32],
+    ///     [2u8; 32],
+    ///     WrappedOpcode::new(0x20, vec![]),
+    ///     WrappedOpcode::new(0x33, vec![]),
+    /// );
+    ///
+    /// assert_eq!(storage.load([1u8; 32]), [2u8; 32]);
+    /// assert_eq!(storage.origin([1u8; 32]).unwrap().value, WrappedOpcode::new(0x33, vec![]));
+    /// ```
+    pub fn store_with_opcode(
+        &mut self,
+        key: [u8; 32],
+        value: [u8; 32],
+        key_opcode: WrappedOpcode,
+        value_opcode: WrappedOpcode,
+    ) {
+        self.store(key, value);
+        self.slots.insert(key, StorageOrigin { key: key_opcode, value: value_opcode });
+    }
+
+    /// Given a storage slot, returns the expressions which computed the key and value of the last
+    /// `SSTORE` to it (if it has been stored to at all)
+    ///
+    /// Due to the nature of `WrappedOpcode`, this allows the entire CFG branch to be traversed.
+    pub fn origin(&self, key: [u8; 32]) -> Option<&StorageOrigin> {
+        self.slots.get(&key)
+    }
+
     /// Load a value from the storage map.
     ///
     /// ```
@@ -64,6 +114,21 @@ impl Storage {
         }
     }
 
+    /// Get the keys which have been accessed, either by loading or storing.
+    ///
+    /// ```
+    /// use heimdall_common::ether::evm::core::storage::Storage;
+    ///
+    /// let mut storage = Storage::new();
+    /// storage.load([1u8; 32]);
+    /// storage.store([2u8; 32], [3u8; 32]);
+    ///
+    /// assert_eq!(storage.accessed_keys().len(), 2);
+    /// ```
+    pub fn accessed_keys(&self) -> &HashSet<[u8; 32]> {
+        &self.access_set
+    }
+
     /// calculate the cost of accessing a key in storage
     ///
     /// ```
@@ -110,6 +175,77 @@ impl Storage {
     }
 }
 
+/// The [`TransientStorage`] struct represents the transient storage of a contract, which is
+/// written with TSTORE and read with TLOAD, as specified by EIP-1153. \
+/// \
+/// Unlike [`Storage`], it's cleared at the end of each transaction, and every access costs the
+/// same amount of gas, so there's no need to track which keys are warm.
+#[derive(Clone, Debug, Default)]
+pub struct TransientStorage {
+    pub storage: HashMap<[u8; 32], [u8; 32]>,
+}
+
+impl TransientStorage {
+    /// Creates a new [`TransientStorage`] struct.
+    ///
+    /// ```
+    /// use heimdall_common::ether::evm::core::storage::TransientStorage;
+    ///
+    /// let transient_storage = TransientStorage::new();
+    /// ```
+    pub fn new() -> TransientStorage {
+        TransientStorage { storage: HashMap::new() }
+    }
+
+    /// Store a key-value pair in the transient storage map.
+    ///
+    /// ```
+    /// use heimdall_common::ether::evm::core::storage::TransientStorage;
+    ///
+    /// let mut transient_storage = TransientStorage::new();
+    /// transient_storage.store([1u8; 32], [2u8; 32]);
+    ///
+    /// assert_eq!(transient_storage.storage.get(&[1u8; 32]), Some(&[2u8; 32]));
+    /// ```
+    pub fn store(&mut self, key: [u8; 32], value: [u8; 32]) {
+        self.storage.insert(key, value);
+    }
+
+    /// Load a value from the transient storage map.
+    ///
+    /// ```
+    /// use heimdall_common::ether::evm::core::storage::TransientStorage;
+    ///
+    /// let mut transient_storage = TransientStorage::new();
+    /// transient_storage.store([1u8; 32], [2u8; 32]);
+    ///
+    /// assert_eq!(transient_storage.load([1u8; 32]), [2u8; 32]);
+    /// assert_eq!(transient_storage.load([3u8; 32]), [0u8; 32]);
+    /// ```
+    pub fn load(&self, key: [u8; 32]) -> [u8; 32] {
+        // return the value associated with the key, with a null word if it doesn't exist
+        match self.storage.get(&key) {
+            Some(value) => *value,
+            None => [0u8; 32],
+        }
+    }
+
+    /// Clear the transient storage, as happens at the end of a transaction.
+    ///
+    /// ```
+    /// use heimdall_common::ether::evm::core::storage::TransientStorage;
+    ///
+    /// let mut transient_storage = TransientStorage::new();
+    /// transient_storage.store([1u8; 32], [2u8; 32]);
+    /// transient_storage.clear();
+    ///
+    /// assert_eq!(transient_storage.load([1u8; 32]), [0u8; 32]);
+    /// ```
+    pub fn clear(&mut self) {
+        self.storage.clear();
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::ether::evm::core::storage::Storage;
@@ -1,5 +1,5 @@
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     ops::{Div, Rem, Shl, Shr},
     str::FromStr,
     time::{Instant, SystemTime, UNIX_EPOCH},
@@ -8,20 +8,29 @@ use std::{
 use ethers::{abi::AbiEncode, prelude::U256, types::I256, utils::keccak256};
 
 use crate::{
-    ether::evm::core::opcodes::{Opcode, WrappedInput, WrappedOpcode},
+    ether::evm::{
+        core::opcodes::{Opcode, WrappedInput, WrappedOpcode},
+        ext::exec::loops::NaturalLoop,
+    },
     utils::strings::{decode_hex, sign_uint},
 };
 
-use super::{log::Log, memory::Memory, stack::Stack, storage::Storage};
+use super::{
+    log::Log,
+    memory::Memory,
+    stack::{Stack, StackDiagnostic},
+    storage::{Storage, TransientStorage},
+};
 
 /// The [`VM`] struct represents an EVM instance. \
-/// It contains the EVM's [`Stack`], [`Memory`], [`Storage`], and other state variables needed to
-/// emulate EVM execution.
+/// It contains the EVM's [`Stack`], [`Memory`], [`Storage`], [`TransientStorage`], and other state
+/// variables needed to emulate EVM execution.
 #[derive(Clone, Debug)]
 pub struct VM {
     pub stack: Stack,
     pub memory: Memory,
     pub storage: Storage,
+    pub transient_storage: TransientStorage,
     pub instruction: u128,
     pub bytecode: Vec<u8>,
     pub calldata: Vec<u8>,
@@ -36,6 +45,39 @@ pub struct VM {
     pub exitcode: u128,
     pub timestamp: Instant,
     pub address_access_set: HashSet<U256>,
+    /// The block environment to execute in. When unset, placeholder values are used.
+    pub block: Option<BlockEnvironment>,
+    /// The contract's own balance, read by `SELFBALANCE` and by `BALANCE` of its address. When
+    /// unset, a placeholder value is used.
+    pub balance: Option<U256>,
+    /// What was hashed by each `SHA3`, keyed by the resulting hash
+    pub preimages: HashMap<U256, Preimage>,
+    /// The loops found by a previous symbolic execution. Each is traced in terms of its
+    /// loop-carried variables, and its paths end where they return to its header.
+    pub loops: Vec<NaturalLoop>,
+}
+
+/// [`Preimage`] is the input to a `SHA3`, which allows a hash, such as a mapping's or array's
+/// storage slot, to be traced back to what was hashed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Preimage {
+    /// The bytes which were hashed
+    pub data: Vec<u8>,
+    /// The expression each word of the hashed bytes was stored with, if they're known
+    pub words: Option<Vec<WrappedInput>>,
+}
+
+/// [`BlockEnvironment`] holds the values returned by block information opcodes, such as
+/// `TIMESTAMP` and `NUMBER`, which allows execution to be pinned to a historical block.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BlockEnvironment {
+    pub number: U256,
+    pub timestamp: U256,
+    pub coinbase: U256,
+    pub prevrandao: U256,
+    pub gas_limit: U256,
+    pub chain_id: U256,
+    pub base_fee: U256,
 }
 
 /// [`ExecutionResult`] is the result of a single contract execution.
@@ -60,7 +102,10 @@ pub struct State {
     pub stack: Stack,
     pub memory: Memory,
     pub storage: Storage,
+    pub transient_storage: TransientStorage,
     pub events: Vec<Log>,
+    /// The invalid stack pattern the instruction was executed with, if any
+    pub stack_diagnostic: Option<StackDiagnostic>,
 }
 
 /// [`Instruction`] is a single EVM instruction. It is returned by the [`VM::step`] function, and
@@ -108,6 +153,7 @@ impl VM {
             stack: Stack::new(),
             memory: Memory::new(),
             storage: Storage::new(),
+            transient_storage: TransientStorage::new(),
             instruction: 1,
             bytecode: decode_hex(&bytecode.replacen("0x", "", 1)).unwrap(),
             calldata: decode_hex(&calldata.replacen("0x", "", 1)).unwrap(),
@@ -122,6 +168,10 @@ impl VM {
             exitcode: 255,
             timestamp: Instant::now(),
             address_access_set: HashSet::new(),
+            block: None,
+            balance: None,
+            preimages: HashMap::new(),
+            loops: Vec::new(),
         }
     }
 
@@ -460,7 +510,7 @@ impl VM {
                 }
 
                 // consume dynamic gas
-                let exponent_byte_size = exponent.value.bits() / 8;
+                let exponent_byte_size = exponent.value.bits().div_ceil(8);
                 let gas_cost = 50 * exponent_byte_size;
                 self.consume_gas(gas_cost as u128);
 
@@ -472,15 +522,20 @@ impl VM {
                 let x = self.stack.pop().value;
                 let b = self.stack.pop().value;
 
-                let t = x * U256::from(8u32) + U256::from(7u32);
-                let sign_bit = U256::from(1u32) << t;
+                // a value which is already 32 bytes wide is left as it is
+                if x >= U256::from(31u32) {
+                    self.stack.push(b, operation);
+                } else {
+                    let t = x * U256::from(8u32) + U256::from(7u32);
+                    let sign_bit = U256::from(1u32) << t;
 
-                // (b & sign_bit - 1) - (b & sign_bit)
-                let result = (b & (sign_bit.overflowing_sub(U256::from(1u32)).0))
-                    .overflowing_sub(b & sign_bit)
-                    .0;
+                    // (b & sign_bit - 1) - (b & sign_bit)
+                    let result = (b & (sign_bit.overflowing_sub(U256::from(1u32)).0))
+                        .overflowing_sub(b & sign_bit)
+                        .0;
 
-                self.stack.push(result, operation)
+                    self.stack.push(result, operation)
+                }
             }
 
             // LT
@@ -677,26 +732,12 @@ impl VM {
                 let a = self.stack.pop();
                 let b = self.stack.pop();
 
-                // convert a to usize
-                let usize_a: usize = match a.value.try_into() {
-                    Ok(x) => x,
-                    Err(_) => {
-                        self.exit(2, Vec::new());
-                        return Instruction {
-                            instruction: last_instruction,
-                            opcode,
-                            opcode_details: Some(opcode_details),
-                            inputs,
-                            outputs: Vec::new(),
-                            input_operations,
-                            output_operations: Vec::new(),
-                        }
-                    }
-                };
+                // shifts of 256 or more fill every bit with the sign bit
+                let usize_a = a.value.min(U256::from(256u32)).as_usize();
 
                 let mut result = I256::zero();
                 if !b.value.is_zero() {
-                    result = sign_uint(b.value).shr(usize_a);
+                    result = sign_uint(b.value).asr(usize_a);
                 }
 
                 // if both inputs are PUSH instructions, simplify the operation
@@ -749,14 +790,26 @@ impl VM {
                 };
 
                 let data = self.memory.read(offset, size);
-                let result = keccak256(data);
+                let result = U256::from(keccak256(&data));
 
                 // consume dynamic gas
                 let minimum_word_size = ((size + 31) / 32) as u128;
                 let gas_cost = 6 * minimum_word_size + self.memory.expansion_cost(offset, size);
                 self.consume_gas(gas_cost);
 
-                self.stack.push(U256::from(result), operation);
+                // an array's slot is the hash of one word, and a mapping's the hash of two. the
+                // words they were stored with follow the offset and size in the operation's
+                // inputs, so the slot can be resolved to the key or array it belongs to
+                let words = match size {
+                    32 | 64 => self.memory.words(offset, size),
+                    _ => None,
+                };
+                if let Some(words) = &words {
+                    operation.inputs.extend(words.iter().cloned());
+                }
+                self.preimages.insert(result, Preimage { data, words });
+
+                self.stack.push(result, operation);
             }
 
             // ADDRESS
@@ -781,8 +834,12 @@ impl VM {
                     self.consume_gas(100);
                 }
 
-                // balance is set to 1 wei because we won't run into div by 0 errors
-                self.stack.push(U256::from(1), operation);
+                // other balances are set to 1 wei because we won't run into div by 0 errors
+                let balance = match self.balance {
+                    Some(balance) if address == U256::from_big_endian(&self.address) => balance,
+                    _ => U256::from(1),
+                };
+                self.stack.push(balance, operation);
             }
 
             // ORIGIN
@@ -814,24 +871,10 @@ impl VM {
             0x35 => {
                 let i = self.stack.pop().value;
 
-                // Safely convert U256 to usize
-                let i: usize = match i.try_into() {
-                    Ok(x) => x,
-                    Err(_) => {
-                        self.exit(2, Vec::new());
-                        return Instruction {
-                            instruction: last_instruction,
-                            opcode,
-                            opcode_details: Some(opcode_details),
-                            inputs,
-                            outputs: Vec::new(),
-                            input_operations,
-                            output_operations: Vec::new(),
-                        }
-                    }
-                };
+                // offsets past the end of calldata read zeros, however large they are
+                let i: usize = i.try_into().unwrap_or(usize::MAX);
 
-                let result = if i + 32 > self.calldata.len() {
+                let result = if i.saturating_add(32) > self.calldata.len() {
                     let mut value = [0u8; 32];
 
                     if i <= self.calldata.len() {
@@ -1149,17 +1192,43 @@ impl VM {
 
             // COINBASE
             0x41 => {
-                self.stack.push(
-                    U256::from_str("0x6865696d64616c6c00000000636f696e62617365").unwrap(),
-                    operation,
-                );
+                let coinbase = match &self.block {
+                    Some(block) => block.coinbase,
+                    None => U256::from_str("0x6865696d64616c6c00000000636f696e62617365").unwrap(),
+                };
+
+                self.stack.push(coinbase, operation);
             }
 
             // TIMESTAMP
             0x42 => {
-                let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+                let timestamp = match &self.block {
+                    Some(block) => block.timestamp,
+                    None => {
+                        U256::from(SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs())
+                    }
+                };
+
+                self.stack.push(timestamp, operation);
+            }
+
+            // NUMBER, PREVRANDAO, GASLIMIT, CHAINID, BASEFEE
+            0x43 | 0x44 | 0x45 | 0x46 | 0x48 if self.block.is_some() => {
+                let block = self.block.as_ref().expect("block environment is set");
+                let value = match opcode {
+                    0x43 => block.number,
+                    0x44 => block.prevrandao,
+                    0x45 => block.gas_limit,
+                    0x46 => block.chain_id,
+                    _ => block.base_fee,
+                };
 
-                self.stack.push(U256::from(timestamp), operation);
+                self.stack.push(value, operation);
+            }
+
+            // SELFBALANCE
+            0x47 if self.balance.is_some() => {
+                self.stack.push(self.balance.expect("balance is set"), operation);
             }
 
             // NUMBER -> BASEFEE
@@ -1273,14 +1342,19 @@ impl VM {
 
             // SSTORE
             0x55 => {
-                let key = self.stack.pop().value;
-                let value = self.stack.pop().value;
+                let key = self.stack.pop();
+                let value = self.stack.pop();
 
                 // consume dynamic gas
-                let gas_cost = self.storage.storage_cost(key.into(), value.into());
+                let gas_cost = self.storage.storage_cost(key.value.into(), value.value.into());
                 self.consume_gas(gas_cost);
 
-                self.storage.store(key.into(), value.into());
+                self.storage.store_with_opcode(
+                    key.value.into(),
+                    value.value.into(),
+                    key.operation,
+                    value.operation,
+                );
             }
 
             // JUMP
@@ -1305,7 +1379,7 @@ impl VM {
                 };
 
                 // Check if JUMPDEST is valid and throw with 790 if not (invalid jump destination)
-                if (pc <= self.bytecode.len().try_into().unwrap()) &&
+                if (pc < self.bytecode.len().try_into().unwrap()) &&
                     (self.bytecode[pc as usize] != 0x5b)
                 {
                     self.exit(790, Vec::new());
@@ -1348,7 +1422,7 @@ impl VM {
                 if !condition.eq(&U256::from(0u8)) {
                     // Check if JUMPDEST is valid and throw with 790 if not (invalid jump
                     // destination)
-                    if (pc <= self.bytecode.len().try_into().unwrap()) &&
+                    if (pc < self.bytecode.len().try_into().unwrap()) &&
                         (self.bytecode[pc as usize] != 0x5b)
                     {
                         self.exit(790, Vec::new());
@@ -1385,6 +1459,84 @@ impl VM {
                 self.stack.push(U256::from(self.gas_remaining), operation);
             }
 
+            // TLOAD
+            0x5c => {
+                let key = self.stack.pop().value;
+
+                self.stack.push(U256::from(self.transient_storage.load(key.into())), operation)
+            }
+
+            // TSTORE
+            0x5d => {
+                let key = self.stack.pop().value;
+                let value = self.stack.pop().value;
+
+                self.transient_storage.store(key.into(), value.into());
+            }
+
+            // MCOPY
+            0x5e => {
+                let dest_offset = self.stack.pop().value;
+                let offset = self.stack.pop().value;
+                let size = self.stack.pop().value;
+
+                // Safely convert U256 to usize
+                let dest_offset: usize = match dest_offset.try_into() {
+                    Ok(x) => x,
+                    Err(_) => {
+                        self.exit(2, Vec::new());
+                        return Instruction {
+                            instruction: last_instruction,
+                            opcode,
+                            opcode_details: Some(opcode_details),
+                            inputs,
+                            outputs: Vec::new(),
+                            input_operations,
+                            output_operations: Vec::new(),
+                        }
+                    }
+                };
+                let offset: usize = match offset.try_into() {
+                    Ok(x) => x,
+                    Err(_) => {
+                        self.exit(2, Vec::new());
+                        return Instruction {
+                            instruction: last_instruction,
+                            opcode,
+                            opcode_details: Some(opcode_details),
+                            inputs,
+                            outputs: Vec::new(),
+                            input_operations,
+                            output_operations: Vec::new(),
+                        }
+                    }
+                };
+                let size: usize = match size.try_into() {
+                    Ok(x) => x,
+                    Err(_) => {
+                        self.exit(2, Vec::new());
+                        return Instruction {
+                            instruction: last_instruction,
+                            opcode,
+                            opcode_details: Some(opcode_details),
+                            inputs,
+                            outputs: Vec::new(),
+                            input_operations,
+                            output_operations: Vec::new(),
+                        }
+                    }
+                };
+
+                // consume dynamic gas, where memory is extended to cover both the source and the
+                // destination
+                let minimum_word_size = size.div_ceil(32) as u128;
+                let gas_cost = 3 * minimum_word_size +
+                    self.memory.expansion_cost(dest_offset.max(offset), size);
+                self.consume_gas(gas_cost);
+
+                self.memory.copy_with_opcode(dest_offset, offset, size, operation);
+            }
+
             // PUSH0
             0x5f => {
                 self.stack.push(U256::zero(), operation);
@@ -1395,18 +1547,25 @@ impl VM {
                 // Get the number of bytes to push
                 let num_bytes = (opcode - 95) as u128;
 
-                // Get the bytes to push from bytecode
-                let bytes = &self.bytecode
-                    [(self.instruction - 1) as usize..(self.instruction - 1 + num_bytes) as usize];
+                // Get the bytes to push from bytecode. push data cut off by the end of the code
+                // is padded with zeros
+                let mut bytes = self
+                    .bytecode
+                    .iter()
+                    .skip((self.instruction - 1) as usize)
+                    .take(num_bytes as usize)
+                    .copied()
+                    .collect::<Vec<u8>>();
+                bytes.resize(num_bytes as usize, 0);
                 self.instruction += num_bytes;
 
                 // update the operation's inputs
-                let new_operation_inputs = vec![WrappedInput::Raw(U256::from(bytes))];
+                let new_operation_inputs = vec![WrappedInput::Raw(U256::from(bytes.as_slice()))];
 
                 operation.inputs = new_operation_inputs;
 
                 // Push the bytes to the stack
-                self.stack.push(U256::from(bytes), operation);
+                self.stack.push(U256::from(bytes.as_slice()), operation);
             }
 
             // DUP1 -> DUP16
@@ -1414,8 +1573,11 @@ impl VM {
                 // Get the number of items to swap
                 let index = opcode - 127;
 
-                // Perform the swap
-                self.stack.dup(index as usize);
+                // a DUP past the bottom of the stack duplicates an unknown value, so the stack
+                // stays balanced for the instructions after it
+                if !self.stack.dup(index as usize) {
+                    self.stack.push(U256::zero(), WrappedOpcode::default());
+                }
             }
 
             // SWAP1 -> SWAP16
@@ -1492,8 +1654,9 @@ impl VM {
 
             // CALL, CALLCODE
             0xF1 | 0xF2 => {
-                let address = self.stack.pop().value;
-                self.stack.pop_n(6);
+                // the gas limit is on top of the stack, followed by the address
+                let address = self.stack.pop_n(2)[1].value;
+                self.stack.pop_n(5);
 
                 // consume dynamic gas
                 if !self.address_access_set.contains(&address) {
@@ -1552,8 +1715,9 @@ impl VM {
 
             // DELEGATECALL, STATICCALL
             0xF4 | 0xFA => {
-                let address = self.stack.pop().value;
-                self.stack.pop_n(5);
+                // the gas limit is on top of the stack, followed by the address
+                let address = self.stack.pop_n(2)[1].value;
+                self.stack.pop_n(4);
 
                 // consume dynamic gas
                 if !self.address_access_set.contains(&address) {
@@ -1660,7 +1824,16 @@ impl VM {
     /// assert_eq!(vm.exitcode, 10);
     /// ```
     pub fn step(&mut self) -> State {
+        let stack_size = self.stack.size();
         let instruction = self._step();
+        let stack_diagnostic = instruction.opcode_details.as_ref().and_then(|_| {
+            StackDiagnostic::check(
+                instruction.instruction - 1,
+                instruction.opcode,
+                stack_size,
+                self.stack.size(),
+            )
+        });
 
         State {
             last_instruction: instruction,
@@ -1669,7 +1842,9 @@ impl VM {
             stack: self.stack.clone(),
             memory: self.memory.clone(),
             storage: self.storage.clone(),
+            transient_storage: self.transient_storage.clone(),
             events: self.events.clone(),
+            stack_diagnostic,
         }
     }
 
@@ -1734,6 +1909,7 @@ impl VM {
     pub fn reset(&mut self) {
         self.stack = Stack::new();
         self.memory = Memory::new();
+        self.transient_storage.clear();
         self.instruction = 1;
         self.gas_remaining = u128::max_value();
         self.gas_used = 21000;
@@ -1818,7 +1994,10 @@ mod tests {
 
     use ethers::prelude::U256;
 
-    use crate::{ether::evm::core::vm::VM, utils::strings::decode_hex};
+    use crate::{
+        ether::evm::core::vm::{BlockEnvironment, VM},
+        utils::strings::decode_hex,
+    };
 
     // creates a new test VM with calldata.
     fn new_test_vm(bytecode: &str) -> VM {
@@ -2018,6 +2197,15 @@ mod tests {
         assert_eq!(vm.stack.peek(0).value, U256::from_str("0x7f").unwrap());
     }
 
+    #[test]
+    fn test_signextend_wide() {
+        let mut vm = new_test_vm("0x60ff601f0b60ff680100000000000000000b");
+        vm.execute();
+
+        assert_eq!(vm.stack.peek(1).value, U256::from_str("0xff").unwrap());
+        assert_eq!(vm.stack.peek(0).value, U256::from_str("0xff").unwrap());
+    }
+
     #[test]
     fn test_lt() {
         let mut vm = new_test_vm("0x600a600910600a600a10");
@@ -2196,6 +2384,15 @@ mod tests {
         assert_eq!(vm.stack.peek(0).value, U256::from_str("0x00").unwrap());
     }
 
+    #[test]
+    fn test_sar_negative() {
+        let mut vm = new_test_vm("0x7ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff060041d7ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff06101001d");
+        vm.execute();
+
+        assert_eq!(vm.stack.peek(1).value, U256::MAX);
+        assert_eq!(vm.stack.peek(0).value, U256::MAX);
+    }
+
     #[test]
     fn test_sha3() {
         let mut vm = new_test_vm(
@@ -2238,6 +2435,15 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_calldataload_out_of_bounds() {
+        let mut vm = new_test_vm("0x6801000000000000000035");
+        vm.execute();
+
+        assert_eq!(vm.exitcode, 255);
+        assert_eq!(vm.stack.peek(0).value, U256::zero());
+    }
+
     #[test]
     fn test_calldatasize() {
         let mut vm = new_test_vm("0x36");
@@ -2293,6 +2499,20 @@ mod tests {
         assert_eq!(vm.stack.peek(0).value, U256::from_str("0x20").unwrap());
     }
 
+    #[test]
+    fn test_mcopy() {
+        // copies the word at 0x10, whose last byte is set by MSTORE8, to 0x20
+        let mut vm = new_test_vm("0x60ff601f536020601060205e59");
+        vm.execute();
+
+        assert_eq!(
+            vm.memory.read(32, 32),
+            decode_hex("000000000000000000000000000000ff00000000000000000000000000000000").unwrap()
+        );
+        assert_eq!(vm.memory.origin(47).map(|origin| origin.opcode.name), Some("MSTORE8"));
+        assert_eq!(vm.stack.peek(0).value, U256::from_str("0x40").unwrap());
+    }
+
     #[test]
     fn test_sload_sstore() {
         let mut vm = new_test_vm("0x602e600055600054600154");
@@ -2302,6 +2522,58 @@ mod tests {
         assert_eq!(vm.stack.peek(0).value, U256::from_str("0x00").unwrap());
     }
 
+    #[test]
+    fn test_tload_tstore() {
+        let mut vm = new_test_vm("0x602e60005d60005c60015c");
+        vm.execute();
+
+        assert_eq!(vm.stack.peek(1).value, U256::from_str("0x2e").unwrap());
+        assert_eq!(vm.stack.peek(0).value, U256::from_str("0x00").unwrap());
+
+        // transient writes don't touch storage, and are cleared with the transaction
+        assert!(vm.storage.storage.is_empty());
+        vm.reset();
+        assert_eq!(vm.transient_storage.load([0u8; 32]), [0u8; 32]);
+    }
+
+    #[test]
+    fn test_push0() {
+        let mut vm = new_test_vm("0x5f5f");
+        vm.execute();
+
+        assert_eq!(vm.stack.size(), 2);
+        assert_eq!(vm.stack.peek(0).value, U256::zero());
+
+        // PUSH0 costs 2 gas, one less than PUSH1
+        assert_eq!(vm.gas_used, 21004);
+    }
+
+    #[test]
+    fn test_truncated_push() {
+        // PUSH4 with only two bytes of push data left before the end of the code
+        let mut vm = new_test_vm("0x63aabb");
+        vm.execute();
+
+        assert_eq!(vm.stack.size(), 1);
+        assert_eq!(vm.stack.peek(0).value, U256::from(0xaabb0000u64));
+    }
+
+    #[test]
+    fn test_stack_underflow_diagnostic() {
+        // PUSH1 0x01, DUP3, ADD
+        let mut vm = new_test_vm("0x60018201");
+        vm.step();
+        let state = vm.step();
+
+        let diagnostic = state.stack_diagnostic.unwrap();
+        assert_eq!((diagnostic.pc, diagnostic.expected, diagnostic.actual), (2, 3, 1));
+
+        // the DUP pushed an unknown value, so the ADD after it is still balanced
+        assert_eq!(state.stack.size(), 2);
+        assert!(vm.step().stack_diagnostic.is_none());
+        assert_eq!(vm.stack.peek(0).value, U256::from(1));
+    }
+
     #[test]
     fn test_jump() {
         let mut vm = new_test_vm("0x60fe56");
@@ -2351,4 +2623,79 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn test_block_environment() {
+        // TIMESTAMP NUMBER CHAINID SELFBALANCE
+        let mut vm = new_test_vm("0x42434647");
+        vm.block = Some(BlockEnvironment {
+            number: U256::from(18_000_000),
+            timestamp: U256::from(1_693_066_895),
+            chain_id: U256::from(5),
+            ..Default::default()
+        });
+        vm.execute();
+
+        assert_eq!(vm.stack.peek(3).value, U256::from(1_693_066_895));
+        assert_eq!(vm.stack.peek(2).value, U256::from(18_000_000));
+        assert_eq!(vm.stack.peek(1).value, U256::from(5));
+        assert_eq!(vm.stack.peek(0).value, U256::from(1));
+    }
+
+    #[test]
+    fn test_balance() {
+        // SELFBALANCE ADDRESS BALANCE CALLER BALANCE
+        let mut vm = new_test_vm("0x473031333100");
+        vm.balance = Some(U256::from(16));
+        vm.execute();
+
+        assert_eq!(vm.stack.peek(2).value, U256::from(16));
+        assert_eq!(vm.stack.peek(1).value, U256::from(16));
+        assert_eq!(vm.stack.peek(0).value, U256::from(1));
+    }
+
+    #[test]
+    fn test_stack_expression_provenance() {
+        // arg1 * arg2, then arg0 through DUP1 POP SWAP1, then ADD
+        let mut vm = new_test_vm("0x604435602435026004358050900100");
+        vm.execute();
+        assert_eq!(vm.stack.peek(0).operation.solidify(), "(arg1 * arg2) + arg0");
+
+        // arg0 + arg1, then MUL by arg2
+        let mut vm = new_test_vm("0x604435602435600435010200");
+        vm.execute();
+        assert_eq!(vm.stack.peek(0).operation.solidify(), "(arg0 + arg1) * arg2");
+    }
+
+    #[test]
+    fn test_storage_expression_provenance() {
+        // storage[keccak256(msg.sender . 0x01)] = arg0
+        let mut vm = new_test_vm("0x33600052600160205260043560406000205500");
+        vm.execute();
+
+        let (_, origin) = vm.storage.slots.iter().next().unwrap();
+        assert_eq!(origin.key.opcode.name, "SHA3");
+        assert_eq!(origin.value.solidify(), "arg0");
+    }
+
+    #[test]
+    fn test_sha3_preimages() {
+        // storage[keccak256(msg.sender . 0x01)] = arg0
+        let mut vm = new_test_vm("0x33600052600160205260043560406000205500");
+        vm.execute();
+
+        let (hash, origin) = vm.storage.slots.iter().next().unwrap();
+        let preimage = vm.preimages.get(&U256::from(*hash)).unwrap();
+        assert_eq!(preimage.data.len(), 64);
+        assert_eq!(preimage.words.as_ref().map(|words| words.len()), Some(2));
+        assert_eq!(origin.key.solidify(), "keccak256(msg.sender, 0x01)");
+
+        // storage[keccak256(0x02) + arg0] = 0x01
+        let mut vm = new_test_vm("0x600260005260016004356020600020015500");
+        vm.execute();
+
+        let (_, origin) = vm.storage.slots.iter().next().unwrap();
+        assert_eq!(vm.preimages.len(), 1);
+        assert_eq!(origin.key.solidify(), "keccak256(0x02) + arg0");
+    }
 }
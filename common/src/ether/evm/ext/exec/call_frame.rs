@@ -0,0 +1,17 @@
+use ethers::types::U256;
+
+/// An internal function call on the current execution path. The caller pushes a return address
+/// before JUMPing to the function, and the function JUMPs back to it when it returns.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CallFrame {
+    pub entry_point: u128,
+    pub return_address: U256,
+    /// The position of the return address on the stack, counted from the bottom
+    pub return_position: usize,
+}
+
+impl CallFrame {
+    pub fn new(entry_point: u128, return_address: U256, return_position: usize) -> Self {
+        Self { entry_point, return_address, return_position }
+    }
+}
@@ -0,0 +1,295 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use petgraph::{algo::dominators::simple_fast, graphmap::DiGraphMap, Direction};
+
+use crate::ether::evm::core::{
+    opcodes::{WrappedInput, WrappedOpcode},
+    stack::{Stack, StackFrame},
+    vm::State,
+};
+
+use super::VMTrace;
+
+/// The names given to the first loop-carried variables, in the order their loops appear in the
+/// code. Later variables are named `i6`, `i7`, and so on.
+const LOOP_VARIABLE_NAMES: [&str; 5] = ["i", "j", "k", "m", "n"];
+
+/// The name of the loop-carried variable with the given index.
+fn loop_variable_name(index: usize) -> String {
+    match LOOP_VARIABLE_NAMES.get(index) {
+        Some(name) => name.to_string(),
+        None => format!("i{}", index + 1),
+    }
+}
+
+/// A natural loop in the control flow of a [`VMTrace`]: a set of blocks with a single entry, the
+/// header, which is jumped back to from within the loop.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NaturalLoop {
+    /// The pc of the JUMPDEST which every iteration starts at
+    pub header: u128,
+    /// The pc of the JUMPI which ends the header, and decides whether the loop continues
+    pub condition: u128,
+    /// The pcs of the blocks within the loop, including the header
+    pub body: HashSet<u128>,
+    /// The stack items which change between iterations
+    pub variables: Vec<LoopVariable>,
+}
+
+/// A stack item which changes between iterations of a loop.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LoopVariable {
+    /// The position of the item, counted from the bottom of the stack
+    pub position: usize,
+    /// The variable the item is traced as within the loop
+    pub variable: WrappedOpcode,
+}
+
+/// A loop entered by a path of a [`VMTrace`], which ends at the loop's condition. The child which
+/// stays in the loop is its body, and each of the body's paths ends where it returns to the
+/// header.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LoopEntry {
+    /// The pc of the loop's header
+    pub header: u128,
+    /// The loop's variables
+    pub variables: Vec<LoopVariable>,
+    /// The value of each variable when the loop was entered
+    pub initial_values: Vec<WrappedOpcode>,
+}
+
+impl LoopEntry {
+    /// The value of each variable when the first path of `body` which returns to the header does
+    /// so, i.e. how an iteration updates it. `None` if no path returns to the header.
+    pub fn updates(&self, body: &VMTrace) -> Option<Vec<WrappedOpcode>> {
+        match body.operations.last() {
+            Some(state) if body.children.is_empty() && pc(state) == self.header => self
+                .variables
+                .iter()
+                .map(|variable| {
+                    stack_item(&state.stack, variable.position).map(|frame| frame.operation.clone())
+                })
+                .collect(),
+            _ => body.children.iter().find_map(|child| self.updates(child)),
+        }
+    }
+}
+
+/// Whether the given operation depends on the given loop variable
+///
+/// ```
+/// use heimdall_common::ether::evm::{
+///     core::opcodes::{WrappedInput, WrappedOpcode},
+///     ext::exec::loops::uses_variable,
+/// };
+///
+/// let push = WrappedOpcode::new(0x60, vec![WrappedInput::Raw(1.into())]);
+/// assert!(uses_variable(&push, &push));
+/// assert!(!uses_variable(&WrappedOpcode::new(0x33, vec![]), &push));
+/// ```
+pub fn uses_variable(operation: &WrappedOpcode, variable: &WrappedOpcode) -> bool {
+    operation == variable ||
+        operation.inputs.iter().any(|input| match input {
+            WrappedInput::Opcode(input) => uses_variable(input, variable),
+            WrappedInput::Raw(_) | WrappedInput::Variable(_) => false,
+        })
+}
+
+/// The pc of the instruction a state was recorded after. The vm counts instructions from 1.
+fn pc(state: &State) -> u128 {
+    state.last_instruction.instruction - 1
+}
+
+/// The stack item at the given position, counted from the bottom of the stack
+fn stack_item(stack: &Stack, position: usize) -> Option<&StackFrame> {
+    stack.stack.len().checked_sub(position + 1).and_then(|index| stack.stack.get(index))
+}
+
+/// Find the natural loops in the control flow of the given trace. A back edge is a jump to a
+/// block which dominates it, i.e. which every path to it passes through, and its loop is every
+/// block which reaches the back edge without passing through that header. Only loops whose
+/// header decides whether to continue are returned, and the stack items which change between
+/// the first two times a path reaches the header are its variables.
+pub fn find_loops(trace: &VMTrace) -> Vec<NaturalLoop> {
+    let root = match trace.operations.first() {
+        Some(state) => pc(state),
+        None => return Vec::new(),
+    };
+
+    let mut cfg = DiGraphMap::new();
+    let mut terminators = HashMap::new();
+    cfg.add_node(root);
+    build_cfg(trace, None, &mut cfg, &mut terminators);
+    let dominators = simple_fast(&cfg, root);
+
+    let mut bodies: BTreeMap<u128, HashSet<u128>> = BTreeMap::new();
+    for (from, to, _) in cfg.all_edges() {
+        if !dominators.dominators(from).is_some_and(|mut blocks| blocks.any(|block| block == to)) {
+            continue
+        }
+
+        let body = bodies.entry(to).or_insert_with(|| HashSet::from([to]));
+        let mut stack = vec![from];
+        while let Some(block) = stack.pop() {
+            if body.insert(block) {
+                stack.extend(cfg.neighbors_directed(block, Direction::Incoming));
+            }
+        }
+    }
+
+    let mut names = (0..).map(loop_variable_name);
+    bodies
+        .into_iter()
+        .filter_map(|(header, body)| {
+            // the header must branch to both the rest of the loop and out of it
+            let (condition, opcode) = terminators.get(&header)?;
+            let successors = cfg.neighbors(header).collect::<Vec<_>>();
+            if *opcode != 0x57 ||
+                successors.len() != 2 ||
+                successors.iter().filter(|block| body.contains(block)).count() != 1
+            {
+                return None
+            }
+
+            let variables = match header_stacks(trace, header, None) {
+                Some((entry, reentry)) if entry.size() == reentry.size() => (0..entry.size())
+                    .filter(|position| {
+                        stack_item(entry, *position).map(|frame| &frame.operation) !=
+                            stack_item(reentry, *position).map(|frame| &frame.operation)
+                    })
+                    .zip(names.by_ref())
+                    .map(|(position, name)| LoopVariable {
+                        position,
+                        variable: WrappedOpcode::variable(&name),
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            };
+
+            Some(NaturalLoop { header, condition: *condition, body, variables })
+        })
+        .collect()
+}
+
+/// Add the blocks of a trace, and the jumps between them, to the control flow graph. A block
+/// starts at a JUMPDEST or after a jump, and is identified by the pc it starts at.
+fn build_cfg(
+    trace: &VMTrace,
+    previous: Option<(u128, u8)>,
+    cfg: &mut DiGraphMap<u128, ()>,
+    terminators: &mut HashMap<u128, (u128, u8)>,
+) {
+    let mut previous = previous;
+    for state in &trace.operations {
+        let pc = pc(state);
+        let opcode = state.last_instruction.opcode;
+        let block = match previous {
+            Some((block, last)) if opcode != 0x5b && last != 0x56 && last != 0x57 => block,
+            Some((block, _)) => {
+                cfg.add_edge(block, pc, ());
+                pc
+            }
+            None => pc,
+        };
+
+        // a path may end partway through a block, so its last instruction is the furthest seen
+        let terminator = terminators.entry(block).or_insert((pc, opcode));
+        if pc > terminator.0 {
+            *terminator = (pc, opcode);
+        }
+        previous = Some((block, opcode));
+    }
+
+    for child in &trace.children {
+        build_cfg(child, previous, cfg, terminators);
+    }
+}
+
+/// The stacks of the first path which reaches the given header twice, at each time it does
+fn header_stacks<'a>(
+    trace: &'a VMTrace,
+    header: u128,
+    entry: Option<&'a Stack>,
+) -> Option<(&'a Stack, &'a Stack)> {
+    let mut entry = entry;
+    for state in trace.operations.iter().filter(|state| pc(state) == header) {
+        match entry {
+            Some(entry) => return Some((entry, &state.stack)),
+            None => entry = Some(&state.stack),
+        }
+    }
+
+    trace.children.iter().find_map(|child| header_stacks(child, header, entry))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ether::evm::core::vm::VM;
+
+    use super::*;
+
+    fn new_vm(bytecode: &str) -> VM {
+        VM::new(
+            bytecode.to_string(),
+            String::from("0x12345678"),
+            String::from("0x6865696d64616c6c000000000061646472657373"),
+            String::from("0x6865696d64616c6c0000000000006f726967696e"),
+            String::from("0x6865696d64616c6c00000000000063616c6c6572"),
+            0,
+            u128::MAX,
+        )
+    }
+
+    #[test]
+    fn test_find_loops_counter() {
+        // for (uint256 i = 0; i < calldataload(4); i++) { sstore(i, i) }
+        let vm = new_vm("0x60005b8060043511156015578080556001016002565b00");
+        let (trace, _) = vm.symbolic_exec();
+        let loops = find_loops(&trace);
+
+        assert_eq!(loops.len(), 1);
+        assert_eq!(loops[0].header, 0x02);
+        assert_eq!(loops[0].condition, 0x0b);
+        assert_eq!(loops[0].body, HashSet::from([0x02, 0x0c]));
+        assert_eq!(
+            loops[0].variables,
+            vec![LoopVariable { position: 0, variable: WrappedOpcode::variable("i") }]
+        );
+    }
+
+    #[test]
+    fn test_loop_variable_names() {
+        let names = (0..7).map(loop_variable_name).collect::<Vec<_>>();
+        assert_eq!(names, vec!["i", "j", "k", "m", "n", "i6", "i7"]);
+    }
+
+    #[test]
+    fn test_find_loops_linear() {
+        let vm = new_vm("0x600160005500");
+        let (trace, _) = vm.symbolic_exec();
+
+        assert!(find_loops(&trace).is_empty());
+    }
+
+    #[test]
+    fn test_loop_entry_traced_with_variables() {
+        let mut vm = new_vm("0x60005b8060043511156015578080556001016002565b00");
+        let (trace, _) = vm.symbolic_exec();
+        vm.loops = find_loops(&trace);
+        let (trace, _) = vm.retrace_loops(trace, false);
+
+        // retracing the first trace finds the same loop as executing again would
+        assert_eq!(trace.loop_entry, vm.symbolic_exec().0.loop_entry);
+
+        // the path ends at the loop's condition, which is in terms of the variable
+        let entry = trace.loop_entry.clone().expect("loop wasn't entered");
+        let condition = &trace.operations.last().unwrap().last_instruction.input_operations[1];
+        assert_eq!(entry.header, 0x02);
+        assert_eq!(entry.initial_values[0].solidify(), "0");
+        assert!(uses_variable(condition, &WrappedOpcode::variable("i")));
+
+        // the body returns to the header having incremented the variable
+        let updates = trace.children.iter().find_map(|child| entry.updates(child)).unwrap();
+        assert_eq!(updates[0].solidify(), "0x01 + i");
+    }
+}
@@ -1,27 +1,33 @@
+mod call_frame;
 mod jump_frame;
+pub mod loops;
 mod util;
 
 use crate::{
     debug_max,
     ether::evm::{
         core::{
+            opcodes::{Opcode, WrappedOpcode},
             stack::Stack,
             vm::{State, VM},
         },
         ext::exec::{
+            call_frame::CallFrame,
             jump_frame::JumpFrame,
+            loops::LoopEntry,
             util::{
                 historical_diffs_approximately_equal, jump_condition_appears_recursive,
                 jump_condition_contains_mutated_memory_access,
                 jump_condition_contains_mutated_storage_access,
                 jump_stack_depth_less_than_max_stack_depth, stack_contains_too_many_items,
                 stack_contains_too_many_of_the_same_item, stack_diff,
-                stack_item_source_depth_too_deep,
+                stack_item_source_depth_too_deep, track_internal_call,
             },
         },
     },
     utils::strings::decode_hex,
 };
+use ethers::types::U256;
 use std::collections::HashMap;
 
 #[derive(Clone, Debug, Default)]
@@ -30,6 +36,22 @@ pub struct VMTrace {
     pub gas_used: u128,
     pub operations: Vec<State>,
     pub children: Vec<VMTrace>,
+    /// The recursive internal call this path ends at, if any
+    pub recursive_call: Option<RecursiveCall>,
+    /// The target of the jump this path ends at, when a code fragment jumps outside of itself
+    pub external_jump: Option<U256>,
+    /// The loop whose condition this path ends at, when the VM traces [`VM::loops`]
+    pub loop_entry: Option<LoopEntry>,
+}
+
+/// A call to an internal function from within itself. Symbolic execution doesn't follow these,
+/// since each call would be unrolled into another copy of the function.
+#[derive(Clone, Debug, Default)]
+pub struct RecursiveCall {
+    /// The entry point of the internal function
+    pub entry_point: u128,
+    /// The arguments passed to the call, in the order they were pushed
+    pub arguments: Vec<WrappedOpcode>,
 }
 
 impl VM {
@@ -37,8 +59,10 @@ impl VM {
     pub fn symbolic_exec_selector(&mut self, selector: &str, entry_point: u128) -> (VMTrace, u32) {
         self.calldata = decode_hex(selector).unwrap();
 
-        // step through the bytecode until we reach the entry point
-        while self.bytecode.len() >= self.instruction as usize && (self.instruction <= entry_point)
+        // step through the bytecode until we reach the entry point. the dispatcher may jump to a
+        // computed target, so the entry point isn't necessarily after the dispatcher's code
+        while self.bytecode.len() >= self.instruction as usize &&
+            self.instruction != entry_point + 1
         {
             self.step();
 
@@ -52,7 +76,7 @@ impl VM {
 
         // the VM is at the function entry point, begin tracing
         let mut branch_count = 0;
-        (self.recursive_map(&mut branch_count, &mut HashMap::new()), branch_count)
+        (self.recursive_map(&mut branch_count, &mut HashMap::new(), &[], &[], false), branch_count)
     }
 
     // build a map of function jump possibilities from the EVM bytecode
@@ -63,15 +87,103 @@ impl VM {
 
         // the VM is at the function entry point, begin tracing
         let mut branch_count = 0;
-        (vm.recursive_map(&mut branch_count, &mut HashMap::new()), branch_count)
+        (vm.recursive_map(&mut branch_count, &mut HashMap::new(), &[], &[], false), branch_count)
+    }
+
+    /// Run symbolic execution on a fragment of code which isn't a full contract, i.e. a snippet
+    /// from a trace, starting at `entry_pc`. There's no dispatcher to step through, and the
+    /// fragment's stack is whatever the code around it left, so missing items are read as unknown
+    /// values. A jump to a target which isn't a JUMPDEST in the fragment is treated as a jump to
+    /// code outside of it, which ends the path.
+    pub fn symbolic_exec_fragment(&self, entry_pc: u128) -> (VMTrace, u32) {
+        let mut vm = self.clone();
+        vm.instruction = entry_pc + 1;
+
+        debug_max!("beginning symbolic execution of fragment at pc {}", entry_pc);
+
+        let mut branch_count = 0;
+        (vm.recursive_map(&mut branch_count, &mut HashMap::new(), &[], &[], true), branch_count)
+    }
+
+    /// Trace [`VM::loops`] through a trace this VM produced before they were found, rather than
+    /// executing the whole function again. Each path is kept up to the first loop header it
+    /// reaches, and execution resumes at the header with the state the path reached it with.
+    /// `fragment` must match how the trace was produced.
+    pub fn retrace_loops(&self, trace: VMTrace, fragment: bool) -> (VMTrace, u32) {
+        debug_max!("retracing {} loops", self.loops.len());
+
+        let mut branch_count = 0;
+        (self.resume_at_loops(trace, &mut branch_count, fragment), branch_count)
+    }
+
+    fn resume_at_loops(
+        &self,
+        mut trace: VMTrace,
+        branch_count: &mut u32,
+        fragment: bool,
+    ) -> VMTrace {
+        let header = trace.operations.iter().position(|state| {
+            self.loops.iter().any(|l| l.header == state.last_instruction.instruction - 1)
+        });
+        let index = match header {
+            Some(index) => index,
+            None => {
+                if !trace.children.is_empty() {
+                    *branch_count += 1;
+                }
+                trace.children = trace
+                    .children
+                    .into_iter()
+                    .map(|child| self.resume_at_loops(child, branch_count, fragment))
+                    .collect();
+                return trace
+            }
+        };
+
+        // the state is recorded after the header, so the header's gas is refunded and it's
+        // stepped again, which enters the loop
+        let state = &trace.operations[index];
+        let gas = Opcode::new(0x5b).mingas as u128;
+        let mut vm = self.clone();
+        vm.instruction = state.last_instruction.instruction;
+        vm.gas_used = state.gas_used.saturating_sub(gas);
+        vm.gas_remaining = state.gas_remaining.saturating_add(gas);
+        vm.stack = state.stack.clone();
+        vm.memory = state.memory.clone();
+        vm.storage = state.storage.clone();
+        vm.transient_storage = state.transient_storage.clone();
+        vm.events = state.events.clone();
+        vm.returndata = Vec::new();
+        vm.exitcode = 255;
+
+        let resumed = vm.recursive_map(branch_count, &mut HashMap::new(), &[], &[], fragment);
+        trace.operations.truncate(index);
+        trace.operations.extend(resumed.operations);
+        trace.gas_used = resumed.gas_used;
+        trace.children = resumed.children;
+        trace.recursive_call = resumed.recursive_call;
+        trace.external_jump = resumed.external_jump;
+        trace.loop_entry = resumed.loop_entry;
+        trace
+    }
+
+    /// Whether the given jump target is a JUMPDEST in the code
+    fn is_jumpdest(&self, target: U256) -> bool {
+        target < U256::from(self.bytecode.len()) && self.bytecode[target.as_usize()] == 0x5b
     }
 
     fn recursive_map(
         &mut self,
         branch_count: &mut u32,
         handled_jumps: &mut HashMap<JumpFrame, Vec<Stack>>,
+        call_stack: &[CallFrame],
+        entered_loops: &[u128],
+        fragment: bool,
     ) -> VMTrace {
         let mut vm = self.clone();
+        let mut call_stack = call_stack.to_vec();
+        let mut entered_loops = entered_loops.to_vec();
+        let mut loop_entry: Option<(u128, LoopEntry)> = None;
 
         // create a new VMTrace object
         // this will essentially be a tree of executions, with each branch being a different path
@@ -81,6 +193,9 @@ impl VM {
             gas_used: 21000,
             operations: Vec::new(),
             children: Vec::new(),
+            recursive_call: None,
+            external_jump: None,
+            loop_entry: None,
         };
 
         // step through the bytecode until we find a JUMPI instruction
@@ -91,6 +206,29 @@ impl VM {
             vm_trace.operations.push(state.clone());
             vm_trace.gas_used = vm.gas_used;
 
+            // a loop's variables replace its loop-carried stack items when it's entered, and a
+            // path which returns to the header has completed an iteration
+            let pc = state.last_instruction.instruction - 1;
+            if let Some(natural_loop) = vm.loops.iter().find(|l| l.header == pc).cloned() {
+                if entered_loops.contains(&pc) {
+                    debug_max!("path returns to loop header at {}", pc);
+                    return vm_trace
+                }
+                entered_loops.push(pc);
+
+                let mut entry =
+                    LoopEntry { header: pc, variables: Vec::new(), initial_values: Vec::new() };
+                for variable in natural_loop.variables {
+                    if let Some(index) = vm.stack.size().checked_sub(variable.position + 1) {
+                        let frame = &mut vm.stack.stack[index];
+                        entry.initial_values.push(frame.operation.clone());
+                        frame.operation = variable.variable.clone();
+                        entry.variables.push(variable);
+                    }
+                }
+                loop_entry = Some((natural_loop.condition, entry));
+            }
+
             // if we encounter a JUMP(I), create children taking both paths and break
             if state.last_instruction.opcode == 0x57 || state.last_instruction.opcode == 0x56 {
                 debug_max!(
@@ -99,11 +237,48 @@ impl VM {
                     state.last_instruction.instruction
                 );
 
+                if loop_entry.as_ref().is_some_and(|(condition, _)| *condition == pc) {
+                    vm_trace.loop_entry = loop_entry.take().map(|(_, entry)| entry);
+                }
+
                 let jump_condition: Option<String> =
                     state.last_instruction.input_operations.get(1).map(|op| op.solidify());
                 let jump_taken =
                     state.last_instruction.inputs.get(1).map(|op| !op.is_zero()).unwrap_or(true);
 
+                // a fragment which jumps outside of itself leaves the code being analyzed. the
+                // JUMPI's other side still falls through
+                let jump_target = state.last_instruction.inputs[0];
+                if fragment && !vm.is_jumpdest(jump_target) {
+                    debug_max!("fragment jumps to external code at {}", jump_target);
+
+                    let external = VMTrace {
+                        instruction: vm.instruction,
+                        gas_used: vm.gas_used,
+                        external_jump: Some(jump_target),
+                        ..Default::default()
+                    };
+                    if state.last_instruction.opcode == 0x56 {
+                        vm_trace.external_jump = Some(jump_target);
+                        return vm_trace
+                    }
+
+                    // an external JUMPI which was taken exited the vm, so it's resumed after it
+                    let mut fallthrough = vm.clone();
+                    fallthrough.exitcode = 255;
+                    fallthrough.instruction = state.last_instruction.instruction + 1;
+                    *branch_count += 1;
+                    vm_trace.children.push(external);
+                    vm_trace.children.push(fallthrough.recursive_map(
+                        branch_count,
+                        handled_jumps,
+                        &call_stack,
+                        &entered_loops,
+                        fragment,
+                    ));
+                    break
+                }
+
                 // build hashable jump frame
                 let jump_frame = JumpFrame::new(
                     state.last_instruction.instruction,
@@ -112,6 +287,20 @@ impl VM {
                     jump_taken,
                 );
 
+                // if the JUMP re-enters an internal function which hasn't returned yet, stop here
+                // instead of unrolling the recursion
+                if state.last_instruction.opcode == 0x56 {
+                    vm_trace.recursive_call = track_internal_call(
+                        &mut call_stack,
+                        &vm.stack,
+                        &vm.bytecode,
+                        state.last_instruction.inputs[0],
+                    );
+                    if vm_trace.recursive_call.is_some() {
+                        return vm_trace
+                    }
+                }
+
                 // if the stack contains too many items, it's probably a loop
                 if stack_contains_too_many_items(&vm.stack) {
                     return vm_trace
@@ -251,19 +440,43 @@ impl VM {
                     // push a new vm trace to the children
                     let mut trace_vm = vm.clone();
                     trace_vm.instruction = state.last_instruction.inputs[0].as_u128() + 1;
-                    vm_trace.children.push(trace_vm.recursive_map(branch_count, handled_jumps));
+                    vm_trace.children.push(trace_vm.recursive_map(
+                        branch_count,
+                        handled_jumps,
+                        &call_stack,
+                        &entered_loops,
+                        fragment,
+                    ));
 
                     // push the current path onto the stack
-                    vm_trace.children.push(vm.recursive_map(branch_count, handled_jumps));
+                    vm_trace.children.push(vm.recursive_map(
+                        branch_count,
+                        handled_jumps,
+                        &call_stack,
+                        &entered_loops,
+                        fragment,
+                    ));
                     break
                 } else {
                     // push a new vm trace to the children
                     let mut trace_vm = vm.clone();
                     trace_vm.instruction = state.last_instruction.instruction + 1;
-                    vm_trace.children.push(trace_vm.recursive_map(branch_count, handled_jumps));
+                    vm_trace.children.push(trace_vm.recursive_map(
+                        branch_count,
+                        handled_jumps,
+                        &call_stack,
+                        &entered_loops,
+                        fragment,
+                    ));
 
                     // push the current path onto the stack
-                    vm_trace.children.push(vm.recursive_map(branch_count, handled_jumps));
+                    vm_trace.children.push(vm.recursive_map(
+                        branch_count,
+                        handled_jumps,
+                        &call_stack,
+                        &entered_loops,
+                        fragment,
+                    ));
                     break
                 }
             }
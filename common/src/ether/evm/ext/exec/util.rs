@@ -9,7 +9,7 @@ use crate::{
     utils::io::logging::Logger,
 };
 
-use super::jump_frame::JumpFrame;
+use super::{call_frame::CallFrame, jump_frame::JumpFrame, RecursiveCall};
 
 /// Given two stacks A and B, return A - B, i.e. the items in A that are not in B.
 /// This operation takes order into account, so if A = [1, 2, 3] and B = [1, 3, 2], then A - B =
@@ -207,3 +207,48 @@ pub fn historical_diffs_approximately_equal(stack: &Stack, historical_stacks: &[
 
     true
 }
+
+/// Update the internal call stack for a JUMP to `jumpdest`. A JUMP to the return address of an
+/// active call returns from it, and a JUMP with a new return address pushed above the current
+/// call's is a call. If the called function is already on the call stack, the call is recursive,
+/// and is returned rather than pushed, since following it would unroll the recursion.
+pub fn track_internal_call(
+    call_stack: &mut Vec<CallFrame>,
+    stack: &Stack,
+    bytecode: &[u8],
+    jumpdest: U256,
+) -> Option<RecursiveCall> {
+    if let Some(index) = call_stack.iter().rposition(|frame| frame.return_address == jumpdest) {
+        call_stack.truncate(index);
+        return None
+    }
+
+    let entry_point = u128::try_from(jumpdest).ok()?;
+    let is_jumpdest = |value: &U256| {
+        usize::try_from(*value).ok().and_then(|pc| bytecode.get(pc)).is_some_and(|op| *op == 0x5b)
+    };
+
+    // the return address is the topmost pushed JUMPDEST above the current call's return address
+    let minimum_depth = call_stack.last().map(|frame| frame.return_position + 1).unwrap_or(0);
+    let (depth, return_address) =
+        stack.stack.iter().enumerate().take(stack.size().saturating_sub(minimum_depth)).find(
+            |(_, frame)| {
+                (0x60..=0x7f).contains(&frame.operation.opcode.code) &&
+                    frame.value != jumpdest &&
+                    is_jumpdest(&frame.value)
+            },
+        )?;
+
+    if call_stack.iter().any(|frame| frame.entry_point == entry_point) {
+        debug_max!("jump to {} is a recursive internal call", entry_point);
+
+        // the arguments are pushed after the return address, so they're above it on the stack
+        return Some(RecursiveCall {
+            entry_point,
+            arguments: stack.stack.iter().take(depth).rev().map(|f| f.operation.clone()).collect(),
+        })
+    }
+
+    call_stack.push(CallFrame::new(entry_point, return_address.value, stack.size() - 1 - depth));
+    None
+}
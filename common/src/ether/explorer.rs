@@ -0,0 +1,541 @@
+use std::{
+    collections::HashMap,
+    sync::{Mutex, RwLock},
+    time::{Duration, Instant},
+};
+
+use heimdall_cache::{read_cache, store_cache};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    debug_max,
+    error::Error,
+    ether::rpc::{chain_id, get_transaction},
+    utils::{http::get_json_from_url, strings::encode_hex},
+};
+
+lazy_static! {
+    static ref EXPLORER_API_KEY: RwLock<String> = RwLock::new(String::new());
+    static ref NEXT_REQUEST: Mutex<HashMap<String, Instant>> = Mutex::new(HashMap::new());
+}
+
+/// The number of times a rate limited request is retried before giving up.
+const RATE_LIMIT_RETRIES: u8 = 3;
+
+/// The flavor of Etherscan-compatible API a block explorer speaks. They share the
+/// `?module=..&action=..` interface, but differ in hosting and rate limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExplorerFlavor {
+    Etherscan,
+    Blockscout,
+    Routescan,
+}
+
+/// A contract's verified source code, as reported by a block explorer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerifiedSource {
+    pub name: String,
+    pub compiler_version: String,
+    /// The contract's ABI, as JSON
+    pub abi: String,
+    /// Each source file, keyed by its path. Contracts verified as a single file use the
+    /// contract's name as the path.
+    pub sources: Vec<(String, String)>,
+}
+
+/// The account and transaction which deployed a contract.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContractCreation {
+    pub creator: String,
+    pub transaction_hash: String,
+    /// The creation code, when the explorer reports it
+    pub creation_code: Option<String>,
+}
+
+/// A contract deployed by an account, either directly or, for a factory, by one of its calls.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Deployment {
+    pub creator: String,
+    pub address: String,
+    pub transaction_hash: String,
+    pub block_number: Option<u64>,
+}
+
+/// A transaction sent to or from an account, as reported by a block explorer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExplorerTransaction {
+    pub hash: String,
+    pub from: String,
+    /// The recipient, which is empty for a deployment
+    pub to: String,
+    pub input: String,
+    /// The ether sent, in wei, as a decimal string
+    pub value: String,
+    /// The gas limit
+    pub gas: Option<u64>,
+    pub block_number: Option<u64>,
+    /// Whether the transaction reverted
+    pub failed: bool,
+}
+
+/// An event emitted by a contract, as reported by a block explorer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExplorerLog {
+    pub topics: Vec<String>,
+    pub data: String,
+    pub block_number: Option<u64>,
+    pub transaction_hash: String,
+}
+
+/// A client for an Etherscan-compatible block explorer API. Requests are throttled per API so
+/// keyless usage stays within the explorer's free rate limit, and responses are cached.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExplorerClient {
+    pub flavor: ExplorerFlavor,
+    pub chain_id: u64,
+    pub api_url: String,
+    pub api_key: String,
+}
+
+/// Set the API key used for explorer requests. An empty key makes keyless requests, which are
+/// throttled more heavily.
+pub fn set_explorer_api_key(api_key: &str) {
+    if let Ok(mut current) = EXPLORER_API_KEY.write() {
+        *current = api_key.to_string();
+    }
+}
+
+impl ExplorerClient {
+    /// The explorer for the given chain, if one is known.
+    ///
+    /// ```
+    /// use heimdall_common::ether::explorer::{ExplorerClient, ExplorerFlavor};
+    ///
+    /// let client = ExplorerClient::for_chain(100).unwrap();
+    /// assert_eq!(client.flavor, ExplorerFlavor::Blockscout);
+    /// assert!(ExplorerClient::for_chain(0).is_none());
+    /// ```
+    pub fn for_chain(chain_id: u64) -> Option<ExplorerClient> {
+        let (flavor, api_url) = match chain_id {
+            1 => (ExplorerFlavor::Etherscan, "https://api.etherscan.io/api".to_string()),
+            10 => {
+                (ExplorerFlavor::Etherscan, "https://api-optimistic.etherscan.io/api".to_string())
+            }
+            56 => (ExplorerFlavor::Etherscan, "https://api.bscscan.com/api".to_string()),
+            100 => (ExplorerFlavor::Blockscout, "https://gnosis.blockscout.com/api".to_string()),
+            137 => (ExplorerFlavor::Etherscan, "https://api.polygonscan.com/api".to_string()),
+            8453 => (ExplorerFlavor::Etherscan, "https://api.basescan.org/api".to_string()),
+            42161 => (ExplorerFlavor::Etherscan, "https://api.arbiscan.io/api".to_string()),
+            11155111 => {
+                (ExplorerFlavor::Etherscan, "https://api-sepolia.etherscan.io/api".to_string())
+            }
+            43114 | 43113 => (
+                ExplorerFlavor::Routescan,
+                format!(
+                    "https://api.routescan.io/v2/network/{}/evm/{chain_id}/etherscan/api",
+                    if chain_id == 43113 { "testnet" } else { "mainnet" }
+                ),
+            ),
+            _ => return None,
+        };
+
+        Some(ExplorerClient {
+            flavor,
+            chain_id,
+            api_url,
+            api_key: EXPLORER_API_KEY.read().map(|key| key.clone()).unwrap_or_default(),
+        })
+    }
+
+    /// The explorer for the chain the given RPC provider is connected to, if one is known.
+    pub async fn from_rpc_url(rpc_url: &str) -> Option<ExplorerClient> {
+        Self::for_chain(chain_id(rpc_url).await.ok()?)
+    }
+
+    /// The minimum time between two requests to this explorer.
+    pub fn request_interval(&self) -> Duration {
+        match (self.flavor, self.api_key.is_empty()) {
+            // etherscan allows one keyless request every five seconds
+            (ExplorerFlavor::Etherscan, true) => Duration::from_secs(5),
+            (ExplorerFlavor::Etherscan, false) => Duration::from_millis(200),
+            (_, true) => Duration::from_secs(1),
+            (_, false) => Duration::from_millis(100),
+        }
+    }
+
+    fn request_url(&self, module: &str, action: &str, parameters: &str) -> String {
+        let mut url = format!("{}?module={module}&action={action}&{parameters}", self.api_url);
+        if !self.api_key.is_empty() {
+            url.push_str(&format!("&apikey={}", self.api_key));
+        }
+        url
+    }
+
+    /// Wait until a request to this explorer is allowed, and reserve the next slot.
+    async fn throttle(&self) {
+        let wait = match NEXT_REQUEST.lock() {
+            Ok(mut next) => {
+                let now = Instant::now();
+                let slot =
+                    next.get(&self.api_url).copied().filter(|slot| *slot > now).unwrap_or(now);
+                next.insert(self.api_url.clone(), slot + self.request_interval());
+                slot - now
+            }
+            Err(_) => self.request_interval(),
+        };
+
+        if !wait.is_zero() {
+            debug_max!("waiting {:?} for explorer rate limit", wait);
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Make a request, returning the `result` field of the response. Responses are cached
+    /// under `cache_key`, if one is given. An account's history grows with every block, so it
+    /// isn't cached.
+    async fn request(
+        &self,
+        module: &str,
+        action: &str,
+        parameters: &str,
+        cache_key: Option<&str>,
+    ) -> Result<Option<Value>, Error> {
+        let cache_key = cache_key.map(|key| format!("explorer.{}.{key}", self.chain_id));
+        if let Some(cache_key) = &cache_key {
+            if let Some(result) = read_cache::<String>(cache_key)
+                .map_err(|e| Error::Generic(format!("failed to read explorer cache: {e}")))?
+            {
+                return Ok(serde_json::from_str(&result).ok())
+            }
+        }
+
+        for _ in 0..RATE_LIMIT_RETRIES {
+            self.throttle().await;
+            let response =
+                match get_json_from_url(&self.request_url(module, action, parameters), 10)
+                    .await
+                    .map_err(|e| Error::Generic(format!("error querying explorer: {e}")))?
+                {
+                    Some(response) => response,
+                    None => return Ok(None),
+                };
+
+            match (response["status"].as_str(), &response["result"]) {
+                (Some("1"), result) => {
+                    if let Some(cache_key) = &cache_key {
+                        store_cache(cache_key, result.to_string(), None).map_err(|e| {
+                            Error::Generic(format!("failed to cache explorer response: {e}"))
+                        })?;
+                    }
+                    return Ok(Some(result.clone()))
+                }
+                (_, Value::String(message)) if message.to_lowercase().contains("rate limit") => {
+                    debug_max!("explorer '{}' rate limited us, retrying", &self.api_url);
+                }
+                (_, result) => {
+                    debug_max!("explorer '{}' returned an error: {}", &self.api_url, result);
+                    return Ok(None)
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// The verified source code of the given contract, or `None` if it isn't verified.
+    pub async fn get_source_code(&self, address: &str) -> Result<Option<VerifiedSource>, Error> {
+        let result = self
+            .request(
+                "contract",
+                "getsourcecode",
+                &format!("address={address}"),
+                Some(&format!("source.{address}")),
+            )
+            .await?;
+
+        Ok(result.as_ref().and_then(|result| result.get(0)).and_then(parse_verified_source))
+    }
+
+    /// The creator and creation transaction of the given contract.
+    pub async fn get_contract_creation(
+        &self,
+        address: &str,
+    ) -> Result<Option<ContractCreation>, Error> {
+        let result = self
+            .request(
+                "contract",
+                "getcontractcreation",
+                &format!("contractaddresses={address}"),
+                Some(&format!("creation.{address}")),
+            )
+            .await?;
+
+        Ok(result.as_ref().and_then(|result| result.get(0)).and_then(|creation| {
+            Some(ContractCreation {
+                creator: creation["contractCreator"].as_str()?.to_string(),
+                transaction_hash: creation["txHash"].as_str()?.to_string(),
+                creation_code: creation["creationBytecode"]
+                    .as_str()
+                    .filter(|code| !code.is_empty())
+                    .map(|code| code.replacen("0x", "", 1)),
+            })
+        }))
+    }
+
+    /// The contracts the given account deployed, ordered by block. Contracts deployed by a
+    /// factory are found through its internal transactions.
+    pub async fn get_deployments(&self, creator: &str) -> Result<Vec<Deployment>, Error> {
+        let parameters = format!("address={creator}&startblock=0&sort=asc");
+        let transactions = self.request("account", "txlist", &parameters, None).await?;
+        let internal_transactions =
+            self.request("account", "txlistinternal", &parameters, None).await?;
+
+        let mut deployments = [transactions, internal_transactions]
+            .iter()
+            .flatten()
+            .filter_map(|result| result.as_array())
+            .flatten()
+            .filter_map(|transaction| parse_deployment(creator, transaction))
+            .collect::<Vec<Deployment>>();
+        deployments.sort_by_key(|deployment| deployment.block_number);
+        deployments.dedup_by(|a, b| a.address == b.address);
+
+        Ok(deployments)
+    }
+
+    /// The transactions sent to or from the given account, newest first. Explorers return at
+    /// most 10,000 transactions, so the most recent ones are kept.
+    pub async fn get_transactions(&self, address: &str) -> Result<Vec<ExplorerTransaction>, Error> {
+        let result = self
+            .request(
+                "account",
+                "txlist",
+                &format!("address={address}&startblock=0&sort=desc"),
+                None,
+            )
+            .await?;
+
+        Ok(result
+            .as_ref()
+            .and_then(|result| result.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|transaction| {
+                Some(ExplorerTransaction {
+                    hash: transaction["hash"].as_str()?.to_string(),
+                    from: transaction["from"].as_str()?.to_lowercase(),
+                    to: transaction["to"].as_str().unwrap_or_default().to_lowercase(),
+                    input: transaction["input"].as_str().unwrap_or_default().to_string(),
+                    value: transaction["value"].as_str().unwrap_or("0").to_string(),
+                    gas: transaction["gas"].as_str().and_then(parse_number),
+                    block_number: transaction["blockNumber"].as_str().and_then(parse_number),
+                    failed: transaction["isError"].as_str() == Some("1"),
+                })
+            })
+            .collect())
+    }
+
+    /// Every event with the given first topic which the given contract emitted, ordered by
+    /// block.
+    pub async fn get_logs(&self, address: &str, topic: &str) -> Result<Vec<ExplorerLog>, Error> {
+        let result = self
+            .request(
+                "logs",
+                "getLogs",
+                &format!("address={address}&fromBlock=0&toBlock=latest&topic0={topic}"),
+                None,
+            )
+            .await?;
+
+        Ok(result
+            .as_ref()
+            .and_then(|result| result.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|log| {
+                Some(ExplorerLog {
+                    topics: log["topics"]
+                        .as_array()?
+                        .iter()
+                        .filter_map(|topic| topic.as_str().map(|topic| topic.to_string()))
+                        .collect(),
+                    data: log["data"].as_str().unwrap_or_default().to_string(),
+                    block_number: log["blockNumber"].as_str().and_then(parse_number),
+                    transaction_hash: log["transactionHash"].as_str()?.to_string(),
+                })
+            })
+            .collect())
+    }
+}
+
+/// Parse a `txlist` or `txlistinternal` entry, which is a deployment if it created a contract.
+/// Direct deployments have no recipient, and factory deployments are `create` or `create2` calls.
+fn parse_deployment(creator: &str, transaction: &Value) -> Option<Deployment> {
+    let address = transaction["contractAddress"].as_str().filter(|address| !address.is_empty())?;
+    let is_creation = match transaction["type"].as_str() {
+        Some(kind) => kind.starts_with("create"),
+        None => transaction["to"].as_str().unwrap_or_default().is_empty(),
+    };
+    if !is_creation || !transaction["from"].as_str()?.eq_ignore_ascii_case(creator) {
+        return None
+    }
+
+    Some(Deployment {
+        creator: creator.to_lowercase(),
+        address: address.to_lowercase(),
+        transaction_hash: transaction["hash"].as_str()?.to_string(),
+        block_number: transaction["blockNumber"].as_str().and_then(parse_number),
+    })
+}
+
+/// Parse a number which explorers return as either a decimal or hex string.
+fn parse_number(number: &str) -> Option<u64> {
+    match number.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => number.parse().ok(),
+    }
+}
+
+/// Parse a `getsourcecode` result, which is `None` when the contract isn't verified.
+fn parse_verified_source(result: &Value) -> Option<VerifiedSource> {
+    let source_code = result["SourceCode"].as_str().filter(|source| !source.is_empty())?;
+    let name = result["ContractName"].as_str().unwrap_or_default().to_string();
+
+    // multi-file sources are standard JSON input, which etherscan wraps in an extra pair of braces
+    let standard_json = source_code
+        .strip_prefix("{{")
+        .and_then(|json| json.strip_suffix("}}"))
+        .map(|json| format!("{{{json}}}"))
+        .or_else(|| source_code.starts_with('{').then(|| source_code.to_string()))
+        .and_then(|json| serde_json::from_str::<Value>(&json).ok());
+    let sources = match standard_json.as_ref().and_then(|json| json["sources"].as_object()) {
+        Some(files) => files
+            .iter()
+            .filter_map(|(path, file)| Some((path.clone(), file["content"].as_str()?.to_string())))
+            .collect(),
+        None => vec![(name.clone(), source_code.to_string())],
+    };
+
+    Some(VerifiedSource {
+        name,
+        compiler_version: result["CompilerVersion"].as_str().unwrap_or_default().to_string(),
+        abi: result["ABI"].as_str().unwrap_or_default().to_string(),
+        sources,
+    })
+}
+
+/// The verified source of the given contract on the explorer for `rpc_url`'s chain, if any.
+/// Errors are logged and treated as the contract not being verified.
+pub async fn get_verified_source(address: &str, rpc_url: &str) -> Option<VerifiedSource> {
+    let client = ExplorerClient::from_rpc_url(rpc_url).await?;
+    client
+        .get_source_code(address)
+        .await
+        .map_err(|e| debug_max!("failed to fetch verified source for '{}': {}", address, e))
+        .ok()
+        .flatten()
+}
+
+/// The creation code of the given contract, from the explorer for `rpc_url`'s chain. When the
+/// explorer doesn't report it, the input of the creation transaction is used, which is only the
+/// creation code for contracts which weren't deployed by another contract.
+pub async fn get_creation_code(address: &str, rpc_url: &str) -> Result<Option<String>, Error> {
+    let client = match ExplorerClient::from_rpc_url(rpc_url).await {
+        Some(client) => client,
+        None => return Ok(None),
+    };
+    let creation = match client.get_contract_creation(address).await? {
+        Some(creation) => creation,
+        None => return Ok(None),
+    };
+    if creation.creation_code.is_some() {
+        return Ok(creation.creation_code)
+    }
+
+    let transaction = get_transaction(&creation.transaction_hash, rpc_url).await?;
+    Ok(transaction.to.is_none().then(|| encode_hex(transaction.input.to_vec())))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::ether::explorer::*;
+
+    #[test]
+    fn test_request_url() {
+        let mut client = ExplorerClient::for_chain(1).unwrap();
+        client.api_key = String::new();
+
+        assert_eq!(
+            client.request_url("contract", "getsourcecode", "address=0x01"),
+            "https://api.etherscan.io/api?module=contract&action=getsourcecode&address=0x01"
+        );
+        assert_eq!(client.request_interval(), Duration::from_secs(5));
+
+        client.api_key = String::from("key");
+        assert!(client.request_url("contract", "getsourcecode", "").ends_with("&apikey=key"));
+    }
+
+    #[test]
+    fn test_parse_deployments() {
+        let creator = "0x00000000000000000000000000000000000000aa";
+        let deployment = json!({
+            "from": "0x00000000000000000000000000000000000000AA",
+            "to": "",
+            "contractAddress": "0x00000000000000000000000000000000000000BB",
+            "hash": "0x01",
+            "blockNumber": "100"
+        });
+        assert_eq!(
+            parse_deployment(creator, &deployment),
+            Some(Deployment {
+                creator: creator.to_string(),
+                address: "0x00000000000000000000000000000000000000bb".to_string(),
+                transaction_hash: "0x01".to_string(),
+                block_number: Some(100),
+            })
+        );
+
+        // a factory's create call is a deployment, but any other call isn't
+        let mut internal = deployment.clone();
+        internal["type"] = json!("create2");
+        internal["to"] = json!("0x00000000000000000000000000000000000000bb");
+        assert!(parse_deployment(creator, &internal).is_some());
+        internal["type"] = json!("call");
+        assert!(parse_deployment(creator, &internal).is_none());
+
+        assert_eq!(parse_number("0x64"), Some(100));
+    }
+
+    #[test]
+    fn test_parse_single_file_source() {
+        let result = json!({
+            "SourceCode": "contract A {}",
+            "ContractName": "A",
+            "CompilerVersion": "v0.8.19+commit.7dd6d404",
+            "ABI": "[]"
+        });
+
+        let source = parse_verified_source(&result).unwrap();
+        assert_eq!(source.sources, vec![("A".to_string(), "contract A {}".to_string())]);
+        assert!(parse_verified_source(
+            &json!({"SourceCode": "", "ABI": "Contract source code not verified"})
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_parse_standard_json_source() {
+        let result = json!({
+            "SourceCode": "{{\"language\":\"Solidity\",\"sources\":{\"src/A.sol\":{\"content\":\"contract A {}\"}}}}",
+            "ContractName": "A",
+        });
+
+        let source = parse_verified_source(&result).unwrap();
+        assert_eq!(source.sources, vec![("src/A.sol".to_string(), "contract A {}".to_string())]);
+    }
+}
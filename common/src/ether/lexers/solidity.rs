@@ -180,10 +180,15 @@ impl WrappedOpcode {
             "BYTE" => {
                 solidified_wrapped_opcode.push_str(self.inputs[1]._solidify().as_str());
             }
-            "SHA3" => {
-                solidified_wrapped_opcode
-                    .push_str(&format!("keccak256(memory[{}])", self.inputs[0]._solidify()));
-            }
+            "SHA3" => match self.inputs.get(2..) {
+                // the words which were hashed, if the VM knows what they were stored with
+                Some(words) if !words.is_empty() => solidified_wrapped_opcode.push_str(&format!(
+                    "keccak256({})",
+                    words.iter().map(|word| word._solidify()).collect::<Vec<String>>().join(", ")
+                )),
+                _ => solidified_wrapped_opcode
+                    .push_str(&format!("keccak256(memory[{}])", self.inputs[0]._solidify())),
+            },
             "ADDRESS" => {
                 solidified_wrapped_opcode.push_str("address(this)");
             }
@@ -285,6 +290,10 @@ impl WrappedOpcode {
                 solidified_wrapped_opcode
                     .push_str(format!("storage[{}]", self.inputs[0]._solidify()).as_str());
             }
+            "TLOAD" => {
+                solidified_wrapped_opcode
+                    .push_str(format!("transient[{}]", self.inputs[0]._solidify()).as_str());
+            }
             "MLOAD" => {
                 let memloc = self.inputs[0]._solidify();
                 if memloc.contains("memory") {
@@ -370,6 +379,11 @@ impl WrappedOpcode {
             "PUSH0" => {
                 solidified_wrapped_opcode.push('0');
             }
+            "JUMPDEST" => {
+                if let Some(WrappedInput::Variable(name)) = self.inputs.first() {
+                    solidified_wrapped_opcode.push_str(name);
+                }
+            }
             opcode => {
                 if opcode.starts_with("PUSH") {
                     solidified_wrapped_opcode.push_str(self.inputs[0]._solidify().as_str());
@@ -386,6 +400,21 @@ impl WrappedOpcode {
     pub fn new(opcode_int: u8, inputs: Vec<WrappedInput>) -> WrappedOpcode {
         WrappedOpcode { opcode: Opcode::new(opcode_int), inputs }
     }
+
+    /// Wraps a named variable as the value of a stack item. No opcode computes a variable, so it's
+    /// wrapped by the JUMPDEST it's defined at, i.e. its loop's header, which never produces a
+    /// value of its own.
+    ///
+    /// ```
+    /// use heimdall_common::ether::evm::core::opcodes::{WrappedInput, WrappedOpcode};
+    ///
+    /// let i = WrappedOpcode::variable("i");
+    /// let increment = WrappedOpcode::new(0x01, vec![WrappedInput::Opcode(i), WrappedInput::Raw(1.into())]);
+    /// assert_eq!(increment.solidify(), "i + 0x01");
+    /// ```
+    pub fn variable(name: &str) -> WrappedOpcode {
+        WrappedOpcode::new(0x5b, vec![WrappedInput::Variable(name.to_string())])
+    }
 }
 
 impl Default for WrappedOpcode {
@@ -409,12 +438,18 @@ impl WrappedInput {
             WrappedInput::Opcode(opcode) => {
                 let solidified_opcode = opcode.solidify();
 
-                if solidified_opcode.contains(' ') {
+                // a resolved hash is a call, so it doesn't need parentheses
+                if solidified_opcode.contains(' ') &&
+                    !(opcode.opcode.name == "SHA3" && opcode.inputs.len() > 2)
+                {
                     solidified_wrapped_input.push_str(format!("({solidified_opcode})").as_str());
                 } else {
                     solidified_wrapped_input.push_str(solidified_opcode.as_str());
                 }
             }
+            WrappedInput::Variable(name) => {
+                solidified_wrapped_input.push_str(name);
+            }
         }
 
         solidified_wrapped_input
@@ -675,6 +710,23 @@ mod tests {
         assert_eq!(wrapped_opcode.solidify(), "keccak256(memory[0])");
     }
 
+    #[test]
+    fn test_wrapped_opcode_solidify_sha3_with_preimage() {
+        // keccak256(msg.sender . 0x01), as the VM records it
+        let caller = WrappedOpcode::new(0x33, vec![]);
+        let inputs = vec![
+            WrappedInput::Raw(U256::from(0u8)),
+            WrappedInput::Raw(U256::from(64u8)),
+            WrappedInput::Opcode(caller),
+            WrappedInput::Raw(U256::from(1u8)),
+        ];
+        let hash = WrappedOpcode::new(0x20, inputs);
+        assert_eq!(hash.solidify(), "keccak256(msg.sender, 0x01)");
+
+        let sload = WrappedOpcode::new(0x54, vec![WrappedInput::Opcode(hash)]);
+        assert_eq!(sload.solidify(), "storage[keccak256(msg.sender, 0x01)]");
+    }
+
     #[test]
     fn test_wrapped_opcode_solidify_address() {
         let opcode = Opcode { code: 0x30, name: "ADDRESS", mingas: 1, inputs: 0, outputs: 1 };
@@ -5,8 +5,20 @@ impl WrappedOpcode {
     pub fn yulify(&self) -> String {
         if self.opcode.name == "PUSH0" {
             "0".to_string()
-        } else if self.opcode.name.starts_with("PUSH") {
-            self.inputs[0]._yulify()
+        } else if self.opcode.name.starts_with("PUSH") || self.opcode.name == "JUMPDEST" {
+            self.inputs.first().map(|input| input._yulify()).unwrap_or_default()
+        } else if self.opcode.name == "SHA3" {
+            // the VM follows a hash's offset and size with the words it hashed, which yul can't
+            // express
+            format!(
+                "sha3({})",
+                self.inputs
+                    .iter()
+                    .take(2)
+                    .map(|input| input._yulify())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )
         } else {
             format!(
                 "{}({})",
@@ -29,6 +41,9 @@ impl WrappedInput {
             WrappedInput::Opcode(opcode) => {
                 solidified_wrapped_input.push_str(&opcode.yulify());
             }
+            WrappedInput::Variable(name) => {
+                solidified_wrapped_input.push_str(name);
+            }
         }
 
         solidified_wrapped_input
@@ -49,6 +64,21 @@ mod tests {
         assert_eq!(add_operation_wrapped.yulify(), "0");
     }
 
+    #[test]
+    fn test_yulify_sha3_with_preimage() {
+        // only the offset and size are hashed, not the words the VM resolved them to
+        let hash = WrappedOpcode::new(
+            0x20,
+            vec![
+                WrappedInput::Raw(U256::from(0u8)),
+                WrappedInput::Raw(U256::from(64u8)),
+                WrappedInput::Opcode(WrappedOpcode::new(0x33, vec![])),
+                WrappedInput::Raw(U256::from(1u8)),
+            ],
+        );
+        assert_eq!(hash.yulify(), "sha3(0, 0x40)");
+    }
+
     #[test]
     fn test_yulify_add() {
         // wraps an ADD operation with 2 raw inputs
@@ -1,7 +1,16 @@
+pub mod artifact;
+pub mod batch;
 pub mod bytecode;
 pub mod compiler;
+pub mod corpus;
+pub mod create;
+pub mod endpoints;
 pub mod evm;
+pub mod explorer;
 pub mod lexers;
+pub mod panics;
+pub mod providers;
+pub mod proxy;
 pub mod rpc;
 pub mod selectors;
 pub mod signatures;
@@ -0,0 +1,72 @@
+use ethers::types::U256;
+
+/// The selector of `Panic(uint256)`, which solc reverts with on failed assertions, checked
+/// arithmetic, and other compiler-inserted checks.
+pub const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// What a panic code means, from the Solidity documentation.
+///
+/// ```
+/// use ethers::types::U256;
+/// use heimdall_common::ether::panics::panic_description;
+///
+/// assert_eq!(panic_description(U256::from(0x11)), Some("arithmetic overflow or underflow"));
+/// assert_eq!(panic_description(U256::from(0x99)), None);
+/// ```
+pub fn panic_description(code: U256) -> Option<&'static str> {
+    if code.bits() > 8 {
+        return None
+    }
+
+    match code.low_u64() {
+        0x00 => Some("generic panic"),
+        0x01 => Some("assertion failed"),
+        0x11 => Some("arithmetic overflow or underflow"),
+        0x12 => Some("division or modulo by zero"),
+        0x21 => Some("invalid enum value"),
+        0x22 => Some("invalid storage byte array"),
+        0x31 => Some("pop on an empty array"),
+        0x32 => Some("array index out of bounds"),
+        0x41 => Some("out of memory"),
+        0x51 => Some("call to an uninitialized function"),
+        _ => None,
+    }
+}
+
+/// The panic code of revert data, if it's an encoded `Panic(uint256)`.
+///
+/// ```
+/// use ethers::types::U256;
+/// use heimdall_common::ether::panics::{decode_panic, PANIC_SELECTOR};
+///
+/// let mut data = PANIC_SELECTOR.to_vec();
+/// data.extend([0u8; 31]);
+/// data.push(0x12);
+///
+/// assert_eq!(decode_panic(&data), Some(U256::from(0x12)));
+/// assert_eq!(decode_panic(&data[..4]), None);
+/// ```
+pub fn decode_panic(data: &[u8]) -> Option<U256> {
+    if !data.starts_with(&PANIC_SELECTOR) {
+        return None
+    }
+
+    data.get(4..36).map(U256::from_big_endian)
+}
+
+/// A readable reason for a panic, i.e. `Panic(0x11): arithmetic overflow or underflow`. Unknown
+/// codes are only shown as `Panic(0x99)`.
+///
+/// ```
+/// use ethers::types::U256;
+/// use heimdall_common::ether::panics::describe_panic;
+///
+/// assert_eq!(describe_panic(U256::from(0x32)), "Panic(0x32): array index out of bounds");
+/// assert_eq!(describe_panic(U256::from(0x99)), "Panic(0x99)");
+/// ```
+pub fn describe_panic(code: U256) -> String {
+    match panic_description(code) {
+        Some(description) => format!("Panic({code:#04x}): {description}"),
+        None => format!("Panic({code:#04x})"),
+    }
+}
@@ -0,0 +1,219 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU32, AtomicUsize, Ordering},
+        RwLock,
+    },
+    time::{Duration, Instant},
+};
+
+use backoff::{backoff::Backoff, ExponentialBackoff};
+use lazy_static::lazy_static;
+
+use crate::debug_max;
+
+/// The default number of times a failed request to an RPC provider is retried.
+pub const DEFAULT_RPC_RETRIES: u32 = 5;
+
+/// How many failures within [`CIRCUIT_BREAKER_COOLDOWN`] take a provider out of rotation.
+const CIRCUIT_BREAKER_THRESHOLD: u32 = 3;
+
+/// How long a provider is out of rotation once its circuit opens.
+const CIRCUIT_BREAKER_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// How far each delay between retries is randomized, so concurrent requests which fail together
+/// don't retry together.
+const RETRY_JITTER: f64 = 0.5;
+
+static RPC_RETRIES: AtomicU32 = AtomicU32::new(DEFAULT_RPC_RETRIES);
+static NEXT_PROVIDER: AtomicUsize = AtomicUsize::new(0);
+
+lazy_static! {
+    static ref CIRCUITS: RwLock<HashMap<String, Circuit>> = RwLock::new(HashMap::new());
+}
+
+/// The recent failures of a single provider.
+#[derive(Debug, Clone, Copy)]
+struct Circuit {
+    failures: u32,
+    last_failure: Instant,
+    open_until: Option<Instant>,
+}
+
+impl Circuit {
+    fn is_open(&self, now: Instant) -> bool {
+        self.open_until.is_some_and(|open_until| now < open_until)
+    }
+}
+
+/// The default number of retries, for configurations which don't set one.
+pub fn default_rpc_retries() -> u32 {
+    DEFAULT_RPC_RETRIES
+}
+
+/// Set the number of times a failed request to an RPC provider is retried before giving up.
+pub fn set_rpc_retries(retries: u32) {
+    RPC_RETRIES.store(retries, Ordering::SeqCst);
+}
+
+/// Split an RPC URL into the providers it lists. Several providers may be given separated by
+/// commas, and requests rotate between them.
+///
+/// ```
+/// use heimdall_common::ether::providers::rpc_urls;
+///
+/// assert_eq!(rpc_urls("https://eth.llamarpc.com"), vec!["https://eth.llamarpc.com"]);
+/// assert_eq!(rpc_urls("http://localhost:8545, https://eth.llamarpc.com").len(), 2);
+/// assert!(rpc_urls("").is_empty());
+/// ```
+pub fn rpc_urls(rpc_url: &str) -> Vec<&str> {
+    rpc_url.split(',').map(str::trim).filter(|url| !url.is_empty()).collect()
+}
+
+/// Pick the provider to send the next request to. Providers are tried in the order they're
+/// listed, skipping those whose circuit is open. If every circuit is open, the one which closes
+/// soonest is tried anyway, rather than failing outright.
+pub fn select_provider(rpc_url: &str) -> String {
+    let urls = rpc_urls(rpc_url);
+    if urls.len() <= 1 {
+        return urls.first().unwrap_or(&rpc_url).to_string()
+    }
+
+    let now = Instant::now();
+    let circuits = CIRCUITS.read().expect("failed to lock provider circuits");
+    let start = NEXT_PROVIDER.load(Ordering::SeqCst);
+    let rotation = (0..urls.len()).map(|i| urls[(start + i) % urls.len()]).collect::<Vec<_>>();
+
+    rotation
+        .iter()
+        .find(|url| !circuits.get(**url).is_some_and(|circuit| circuit.is_open(now)))
+        .or_else(|| {
+            rotation
+                .iter()
+                .min_by_key(|url| circuits.get(**url).and_then(|circuit| circuit.open_until))
+        })
+        .unwrap_or(&urls[0])
+        .to_string()
+}
+
+/// Record a failed request to the given provider. Later requests rotate to the next provider,
+/// and a provider which keeps failing is taken out of rotation until its cooldown passes.
+pub fn record_provider_failure(provider_url: &str) {
+    let now = Instant::now();
+    let mut circuits = CIRCUITS.write().expect("failed to lock provider circuits");
+    let circuit = circuits.entry(provider_url.to_string()).or_insert(Circuit {
+        failures: 0,
+        last_failure: now,
+        open_until: None,
+    });
+
+    // failures are only counted against a provider while they're recent
+    if now.duration_since(circuit.last_failure) > CIRCUIT_BREAKER_COOLDOWN {
+        circuit.failures = 0;
+    }
+    circuit.failures += 1;
+    circuit.last_failure = now;
+
+    if circuit.failures >= CIRCUIT_BREAKER_THRESHOLD {
+        debug_max!(
+            "'{}' failed {} times, skipping it for {}s .",
+            provider_url,
+            circuit.failures,
+            CIRCUIT_BREAKER_COOLDOWN.as_secs()
+        );
+        circuit.open_until = Some(now + CIRCUIT_BREAKER_COOLDOWN);
+    }
+
+    NEXT_PROVIDER.fetch_add(1, Ordering::SeqCst);
+}
+
+/// The backoff between retries of a request to an RPC provider: exponential, with jitter, and
+/// giving up after the configured number of retries.
+pub fn rpc_backoff() -> RpcBackoff {
+    RpcBackoff {
+        inner: ExponentialBackoff {
+            randomization_factor: RETRY_JITTER,
+            max_elapsed_time: None,
+            ..ExponentialBackoff::default()
+        },
+        retries: 0,
+        max_retries: RPC_RETRIES.load(Ordering::SeqCst),
+    }
+}
+
+/// An [`ExponentialBackoff`] which stops after a number of retries, rather than after a length of
+/// time, so slow providers get as many attempts as fast ones.
+#[derive(Debug, Clone)]
+pub struct RpcBackoff {
+    inner: ExponentialBackoff,
+    retries: u32,
+    max_retries: u32,
+}
+
+impl Backoff for RpcBackoff {
+    fn reset(&mut self) {
+        self.inner.reset();
+        self.retries = 0;
+    }
+
+    fn next_backoff(&mut self) -> Option<Duration> {
+        if self.retries >= self.max_retries {
+            return None
+        }
+        self.retries += 1;
+        self.inner.next_backoff()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_provider_single() {
+        assert_eq!(select_provider("https://eth.llamarpc.com"), "https://eth.llamarpc.com");
+        assert_eq!(select_provider(""), "");
+    }
+
+    #[test]
+    fn test_select_provider_skips_open_circuits() {
+        let rpc_url = "http://unhealthy.invalid,http://healthy.invalid";
+        for _ in 0..CIRCUIT_BREAKER_THRESHOLD {
+            record_provider_failure("http://unhealthy.invalid");
+        }
+
+        // the healthy provider is picked wherever the rotation starts
+        for _ in 0..4 {
+            assert_eq!(select_provider(rpc_url), "http://healthy.invalid");
+            NEXT_PROVIDER.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_select_provider_all_open() {
+        let rpc_url = "http://down-a.invalid,http://down-b.invalid";
+        for _ in 0..CIRCUIT_BREAKER_THRESHOLD {
+            record_provider_failure("http://down-a.invalid");
+        }
+        std::thread::sleep(Duration::from_millis(1));
+        for _ in 0..CIRCUIT_BREAKER_THRESHOLD {
+            record_provider_failure("http://down-b.invalid");
+        }
+
+        // the circuit which opened first closes first
+        assert_eq!(select_provider(rpc_url), "http://down-a.invalid");
+    }
+
+    #[test]
+    fn test_rpc_backoff_gives_up() {
+        let mut backoff =
+            RpcBackoff { inner: ExponentialBackoff::default(), retries: 0, max_retries: 2 };
+
+        assert!(backoff.next_backoff().is_some());
+        assert!(backoff.next_backoff().is_some());
+        assert!(backoff.next_backoff().is_none());
+
+        backoff.reset();
+        assert!(backoff.next_backoff().is_some());
+    }
+}
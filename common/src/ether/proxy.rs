@@ -0,0 +1,239 @@
+use std::fmt::{Display, Formatter};
+
+use ethers::types::{Address, H256};
+
+use super::{
+    batch::get_storage_batch,
+    rpc::{call, get_block_number},
+};
+use crate::{constants::ADDRESS_REGEX, error::Error};
+
+/// The code a minimal proxy runs before pushing its implementation's address. The first is
+/// EIP-1167's, and the others are its `PUSH0` variants from ERC-7511 and solady.
+const MINIMAL_PROXY_PROLOGUES: [&str; 3] =
+    ["363d3d373d3d3d363d", "365f5f375f5f365f", "5f5f365f5f37365f"];
+
+/// `bytes32(uint256(keccak256("eip1967.proxy.implementation")) - 1)`
+pub const EIP1967_IMPLEMENTATION_SLOT: &str =
+    "0x360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc";
+
+/// `bytes32(uint256(keccak256("eip1967.proxy.beacon")) - 1)`
+pub const EIP1967_BEACON_SLOT: &str =
+    "0xa3f0ad74e5423aebfd80d3ef4346578335a9a72aeaee59ff6cb3582b35133d50";
+
+/// `keccak256("PROXIABLE")`
+pub const EIP1822_IMPLEMENTATION_SLOT: &str =
+    "0xc5f16f0fcc639fa48a6947836d9850f504798523bf8c9a3a87d5876cf622bcf7";
+
+/// The selector of a beacon's `implementation()` function.
+const BEACON_IMPLEMENTATION_SELECTOR: [u8; 4] = [0x5c, 0x60, 0xda, 0x1b];
+
+/// A pattern a proxy forwards its calls with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProxyKind {
+    /// An EIP-1167 minimal proxy, with its implementation's address in its code
+    Minimal,
+    /// An EIP-1967 proxy, with its implementation's address in the implementation slot
+    Eip1967,
+    /// An EIP-1967 beacon proxy, which asks the beacon in the beacon slot for its implementation
+    Beacon,
+    /// An EIP-1822 (UUPS) proxy, with its implementation's address in the `PROXIABLE` slot
+    Eip1822,
+}
+
+impl ProxyKind {
+    /// The slot the proxy keeps its implementation, or its beacon, in.
+    fn slot(&self) -> Option<&'static str> {
+        match self {
+            ProxyKind::Minimal => None,
+            ProxyKind::Eip1967 => Some(EIP1967_IMPLEMENTATION_SLOT),
+            ProxyKind::Beacon => Some(EIP1967_BEACON_SLOT),
+            ProxyKind::Eip1822 => Some(EIP1822_IMPLEMENTATION_SLOT),
+        }
+    }
+}
+
+impl Display for ProxyKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProxyKind::Minimal => write!(f, "EIP-1167 minimal proxy"),
+            ProxyKind::Eip1967 => write!(f, "EIP-1967 proxy"),
+            ProxyKind::Beacon => write!(f, "EIP-1967 beacon proxy"),
+            ProxyKind::Eip1822 => write!(f, "EIP-1822 proxy"),
+        }
+    }
+}
+
+/// A proxy, and the contract it forwards its calls to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Proxy {
+    pub kind: ProxyKind,
+    /// The address of the implementation
+    pub implementation: String,
+}
+
+/// The implementation's address if the bytecode is a minimal proxy, i.e. one of
+/// [`MINIMAL_PROXY_PROLOGUES`] followed by `PUSHn address GAS DELEGATECALL`. Vanity addresses
+/// with leading zero bytes are pushed with fewer than 20 bytes.
+///
+/// ```
+/// use heimdall_common::ether::proxy::get_minimal_proxy_implementation;
+///
+/// let bytecode = "363d3d373d3d3d363d73bebebebebebebebebebebebebebebebebebebebe5af43d82803e903d91602b57fd5bf3";
+/// assert_eq!(
+///     get_minimal_proxy_implementation(bytecode),
+///     Some(String::from("0xbebebebebebebebebebebebebebebebebebebebe"))
+/// );
+/// ```
+pub fn get_minimal_proxy_implementation(bytecode: &str) -> Option<String> {
+    let bytecode = bytecode.strip_prefix("0x").unwrap_or(bytecode).to_lowercase();
+    let rest =
+        MINIMAL_PROXY_PROLOGUES.iter().find_map(|prologue| bytecode.strip_prefix(prologue))?;
+
+    let push = u8::from_str_radix(rest.get(0..2)?, 16).ok()?;
+    if !(0x60..=0x73).contains(&push) {
+        return None
+    }
+    let size = (push - 0x5f) as usize * 2;
+    let address = rest.get(2..2 + size)?;
+    match rest.get(2 + size..6 + size) {
+        Some("5af4") => Some(format!("0x{address:0>40}")),
+        _ => None,
+    }
+}
+
+/// The storage-based proxy patterns whose slot the bytecode pushes, in the order they're
+/// checked. A UUPS implementation pushes the implementation slot too, to upgrade its proxy, so
+/// this only marks the bytecode as a possible proxy.
+pub fn find_proxy_slots(bytecode: &str) -> Vec<ProxyKind> {
+    let bytecode = bytecode.strip_prefix("0x").unwrap_or(bytecode).to_lowercase();
+    [ProxyKind::Eip1967, ProxyKind::Beacon, ProxyKind::Eip1822]
+        .into_iter()
+        .filter(|kind| {
+            kind.slot().is_some_and(|slot| bytecode.contains(&format!("7f{}", &slot[2..])))
+        })
+        .collect()
+}
+
+/// The proxy pattern the target uses, and its current implementation, if it's a proxy. A
+/// storage-based proxy's implementation is read from the target's storage, so it's only found
+/// for a deployed contract, and only when the slot is set.
+pub async fn get_proxy_from_target(
+    target: &str,
+    bytecode: &str,
+    rpc_url: &str,
+) -> Result<Option<Proxy>, Error> {
+    if let Some(implementation) = get_minimal_proxy_implementation(bytecode) {
+        return Ok(Some(Proxy { kind: ProxyKind::Minimal, implementation }))
+    }
+
+    let kinds = find_proxy_slots(bytecode);
+    if kinds.is_empty() || rpc_url.is_empty() || !ADDRESS_REGEX.is_match(target).unwrap_or(false) {
+        return Ok(None)
+    }
+
+    // every candidate slot is read in a single batch
+    let block = get_block_number(rpc_url).await?;
+    let slots = kinds
+        .iter()
+        .map(|kind| kind.slot().and_then(|slot| slot.parse::<H256>().ok()).unwrap_or_default())
+        .collect::<Vec<_>>();
+    let values = get_storage_batch(target, &slots, block, rpc_url).await?;
+    for (kind, value) in kinds.into_iter().zip(values) {
+        let address = Address::from(value);
+        if address.is_zero() {
+            continue
+        }
+
+        // a beacon proxy's implementation is whatever its beacon currently returns
+        let implementation = match kind {
+            ProxyKind::Beacon => {
+                let output = call(
+                    &format!("{address:#x}"),
+                    BEACON_IMPLEMENTATION_SELECTOR.to_vec(),
+                    block,
+                    rpc_url,
+                )
+                .await?;
+                match output.get(12..32) {
+                    Some(word) => Address::from_slice(word),
+                    None => continue,
+                }
+            }
+            _ => address,
+        };
+        if !implementation.is_zero() {
+            return Ok(Some(Proxy { kind, implementation: format!("{implementation:#x}") }))
+        }
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ether::proxy::*;
+
+    #[test]
+    fn test_minimal_proxy_implementation() {
+        let bytecode = "363d3d373d3d3d363d73bebebebebebebebebebebebebebebebebebebebe5af43d82803e903d91602b57fd5bf3";
+        assert_eq!(
+            get_minimal_proxy_implementation(bytecode),
+            Some(String::from("0xbebebebebebebebebebebebebebebebebebebebe"))
+        );
+    }
+
+    #[test]
+    fn test_minimal_proxy_implementation_with_vanity_address() {
+        let bytecode =
+            "0x363d3d373d3d3d363d6fbebebebebebebebebebebebebebebebe5af43d82803e903d91602757fd5bf3";
+        assert_eq!(
+            get_minimal_proxy_implementation(bytecode),
+            Some(String::from("0x00000000bebebebebebebebebebebebebebebebe"))
+        );
+    }
+
+    #[test]
+    fn test_minimal_proxy_implementation_with_push0() {
+        let bytecode = "365f5f375f5f365f73bebebebebebebebebebebebebebebebebebebebe5af43d5f5f3e5f3d91602a57fd5bf3";
+        assert_eq!(
+            get_minimal_proxy_implementation(bytecode),
+            Some(String::from("0xbebebebebebebebebebebebebebebebebebebebe"))
+        );
+    }
+
+    #[test]
+    fn test_minimal_proxy_implementation_without_delegatecall() {
+        let bytecode = "363d3d373d3d3d363d73bebebebebebebebebebebebebebebebebebebebe5af13d82803e903d91602b57fd5bf3";
+        assert_eq!(get_minimal_proxy_implementation(bytecode), None);
+        assert_eq!(get_minimal_proxy_implementation("6080604052"), None);
+    }
+
+    #[test]
+    fn test_find_proxy_slots() {
+        let bytecode = format!("60806040527f{}54", &EIP1967_IMPLEMENTATION_SLOT[2..]);
+        assert_eq!(find_proxy_slots(&bytecode), vec![ProxyKind::Eip1967]);
+
+        let bytecode =
+            format!("7f{}547f{}54", &EIP1822_IMPLEMENTATION_SLOT[2..], &EIP1967_BEACON_SLOT[2..]);
+        assert_eq!(find_proxy_slots(&bytecode), vec![ProxyKind::Beacon, ProxyKind::Eip1822]);
+
+        assert!(find_proxy_slots("6080604052").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_proxy_from_bytecode() {
+        let bytecode = "363d3d373d3d3d363d73bebebebebebebebebebebebebebebebebebebebe5af43d82803e903d91602b57fd5bf3";
+        assert_eq!(
+            get_proxy_from_target(bytecode, bytecode, "").await.unwrap(),
+            Some(Proxy {
+                kind: ProxyKind::Minimal,
+                implementation: String::from("0xbebebebebebebebebebebebebebebebebebebebe"),
+            })
+        );
+
+        // a storage-based proxy's implementation can't be read without a deployed contract
+        let bytecode = format!("7f{}54", &EIP1967_IMPLEMENTATION_SLOT[2..]);
+        assert_eq!(get_proxy_from_target(&bytecode, &bytecode, "").await.unwrap(), None);
+    }
+}
@@ -1,15 +1,39 @@
-use crate::{debug_max, error::Error, utils::io::logging::Logger};
-use backoff::ExponentialBackoff;
+use crate::{
+    debug_max,
+    error::Error,
+    ether::{
+        evm::core::vm::BlockEnvironment,
+        providers::{record_provider_failure, rpc_backoff, select_provider},
+    },
+    utils::{
+        io::{
+            exit::{record_failure, ExitStatus},
+            logging::Logger,
+        },
+        offline::is_offline,
+    },
+};
 use ethers::{
     core::types::Address,
-    providers::{Http, Middleware, Provider},
+    providers::{Http, Middleware, Provider, RpcError},
     types::{
+        BlockId,
         BlockNumber::{self},
-        BlockTrace, Filter, FilterBlockOption, StateDiff, TraceType, Transaction, H256,
+        BlockTrace, CallConfig, CallFrame, Filter, FilterBlockOption, GethDebugBuiltInTracerConfig,
+        GethDebugBuiltInTracerType, GethDebugTracerConfig, GethDebugTracerType,
+        GethDebugTracingOptions, GethTrace, GethTraceFrame, StateDiff, TraceType, Transaction,
+        TransactionRequest, H256, U256,
     },
 };
 use heimdall_cache::{read_cache, store_cache};
-use std::{str::FromStr, time::Duration};
+use std::str::FromStr;
+
+/// Build the error returned when a request to the RPC provider fails, recording it as a network
+/// failure.
+pub(crate) fn network_error(message: String) -> Error {
+    record_failure(ExitStatus::NetworkFailure);
+    Error::Generic(message)
+}
 
 /// Get the chainId of the provided RPC URL
 ///
@@ -20,12 +44,7 @@ use std::{str::FromStr, time::Duration};
 /// //assert_eq!(chain_id, 1);
 /// ```
 pub async fn chain_id(rpc_url: &str) -> Result<u64, Error> {
-    backoff::future::retry(
-        ExponentialBackoff {
-            max_elapsed_time: Some(Duration::from_secs(10)),
-            ..ExponentialBackoff::default()
-        },
-    || async {
+    backoff::future::retry(rpc_backoff(), || async {
         // get a new logger
         let logger = Logger::default();
 
@@ -46,11 +65,18 @@ pub async fn chain_id(rpc_url: &str) -> Result<u64, Error> {
             return Err(backoff::Error::Permanent(()))
         }
 
-        // create new provider
-        let provider = match Provider::<Http>::try_from(rpc_url) {
+        // offline mode never reaches the RPC provider
+        if is_offline() {
+            logger.debug(&format!("chain id for rpc url '{}' isn't cached, and `--offline` is set.", &rpc_url));
+            return Err(backoff::Error::Permanent(()))
+        }
+
+        // create new provider, rotating away from providers which keep failing
+        let provider_url = select_provider(rpc_url);
+        let provider = match Provider::<Http>::try_from(provider_url.as_str()) {
             Ok(provider) => provider,
             Err(_) => {
-                logger.error(&format!("failed to connect to RPC provider '{}' .", &rpc_url));
+                logger.error(&format!("failed to connect to RPC provider '{}' .", &provider_url));
                 return Err(backoff::Error::Permanent(()))
             }
         };
@@ -60,6 +86,7 @@ pub async fn chain_id(rpc_url: &str) -> Result<u64, Error> {
             Ok(chain_id) => chain_id,
             Err(_) => {
                 logger.error(&format!("failed to fetch chain id from '{}' .", &rpc_url));
+                record_provider_failure(&provider_url);
                 return Err(backoff::Error::Transient { err: (), retry_after: None })
             }
         };
@@ -73,7 +100,7 @@ pub async fn chain_id(rpc_url: &str) -> Result<u64, Error> {
         Ok(chain_id.as_u64())
     })
     .await
-    .map_err(|e| Error::Generic(format!("failed to get chain id: {:?}", e)))
+    .map_err(|e| network_error(format!("failed to get chain id: {:?}", e)))
 }
 
 /// Get the bytecode of the provided contract address
@@ -85,12 +112,27 @@ pub async fn chain_id(rpc_url: &str) -> Result<u64, Error> {
 /// // assert!(bytecode.is_ok());
 /// ```
 pub async fn get_code(contract_address: &str, rpc_url: &str) -> Result<String, Error> {
-    backoff::future::retry(
-        ExponentialBackoff {
-            max_elapsed_time: Some(Duration::from_secs(10)),
-            ..ExponentialBackoff::default()
-        },
-    || async {
+    get_code_at_block(contract_address, rpc_url, None).await
+}
+
+/// Get the bytecode of the provided contract address as of the given block, or the latest block
+/// if none is given
+///
+/// ```no_run
+/// use heimdall_common::ether::rpc::get_code_at_block;
+///
+/// // let bytecode = get_code_at_block("0x0", "https://eth.llamarpc.com", Some(18_000_000)).await;
+/// // assert!(bytecode.is_ok());
+/// ```
+pub async fn get_code_at_block(
+    contract_address: &str,
+    rpc_url: &str,
+    block_number: Option<u64>,
+) -> Result<String, Error> {
+    // historical bytecode is cached separately from the latest bytecode
+    let cache_suffix = block_number.map(|block| format!(".{block}")).unwrap_or_default();
+
+    backoff::future::retry(rpc_backoff(), || async {
         // get a new logger
         let logger = Logger::default();
 
@@ -98,7 +140,7 @@ pub async fn get_code(contract_address: &str, rpc_url: &str) -> Result<String, E
         let chain_id = chain_id(rpc_url).await.unwrap_or(1);
 
         // check the cache for a matching address
-        if let Some(bytecode) = read_cache(&format!("contract.{}.{}", &chain_id, &contract_address))
+        if let Some(bytecode) = read_cache(&format!("contract.{}.{}{}", &chain_id, &contract_address, &cache_suffix))
             .map_err(|_| logger.error(&format!("failed to read cache for contract: {:?}", &contract_address)))?
         {
             logger.debug(&format!("found cached bytecode for '{}' .", &contract_address));
@@ -113,11 +155,18 @@ pub async fn get_code(contract_address: &str, rpc_url: &str) -> Result<String, E
             return Err(backoff::Error::Permanent(()))
         }
 
-        // create new provider
-        let provider = match Provider::<Http>::try_from(rpc_url) {
+        // offline mode never reaches the RPC provider
+        if is_offline() {
+            logger.error("reading on-chain data requires network access, which is disabled by `--offline` .");
+            return Err(backoff::Error::Permanent(()))
+        }
+
+        // create new provider, rotating away from providers which keep failing
+        let provider_url = select_provider(rpc_url);
+        let provider = match Provider::<Http>::try_from(provider_url.as_str()) {
             Ok(provider) => provider,
             Err(_) => {
-                logger.error(&format!("failed to connect to RPC provider '{}' .", &rpc_url));
+                logger.error(&format!("failed to connect to RPC provider '{}' .", &provider_url));
                 return Err(backoff::Error::Permanent(()))
             }
         };
@@ -132,17 +181,18 @@ pub async fn get_code(contract_address: &str, rpc_url: &str) -> Result<String, E
         };
 
         // fetch the bytecode at the address
-        let bytecode_as_bytes = match provider.get_code(address, None).await {
+        let bytecode_as_bytes = match provider.get_code(address, block_number.map(BlockId::from)).await {
             Ok(bytecode) => bytecode,
             Err(_) => {
                 logger.error(&format!("failed to fetch bytecode from '{}' .", &contract_address));
+                record_provider_failure(&provider_url);
                 return Err(backoff::Error::Transient { err: (), retry_after: None })
             }
         };
 
         // cache the results
         store_cache(
-            &format!("contract.{}.{}", &chain_id, &contract_address),
+            &format!("contract.{}.{}{}", &chain_id, &contract_address, &cache_suffix),
             bytecode_as_bytes.to_string().replacen("0x", "", 1),
             None,
         )
@@ -151,7 +201,423 @@ pub async fn get_code(contract_address: &str, rpc_url: &str) -> Result<String, E
         Ok(bytecode_as_bytes.to_string().replacen("0x", "", 1))
     })
     .await
-    .map_err(|_| Error::Generic(format!("failed to get bytecode for contract: {:?}", &contract_address)))
+    .map_err(|_| network_error(format!("failed to get bytecode for contract: {:?}", &contract_address)))
+}
+
+/// Get the value of a storage slot of the provided contract address as of the given block
+///
+/// ```no_run
+/// use heimdall_common::ether::rpc::get_storage_at;
+///
+/// // let value = get_storage_at("0x0", H256::zero(), 18_000_000, "https://eth.llamarpc.com").await;
+/// // assert!(value.is_ok());
+/// ```
+pub async fn get_storage_at(
+    contract_address: &str,
+    slot: H256,
+    block_number: u64,
+    rpc_url: &str,
+) -> Result<H256, Error> {
+    backoff::future::retry(rpc_backoff(), || async {
+        // get a new logger
+        let logger = Logger::default();
+
+        // get chain_id
+        let chain_id = chain_id(rpc_url).await.unwrap_or(1);
+
+        // check the cache for a matching slot
+        let cache_key = format!("storage.{}.{}.{:?}.{}", &chain_id, &contract_address, &slot, &block_number);
+        if let Some(value) = read_cache(&cache_key)
+            .map_err(|_| logger.error(&format!("failed to read cache for slot: {:?}", &slot)))?
+        {
+            debug_max!("found cached value for slot '{:?}' .", &slot);
+            return Ok(value)
+        }
+
+        debug_max!("fetching slot '{:?}' of '{}' at block {} .", &slot, &contract_address, &block_number);
+
+        // make sure the RPC provider isn't empty
+        if rpc_url.is_empty() {
+            logger.error("reading on-chain data requires an RPC provider. Use `heimdall --help` for more information.");
+            return Err(backoff::Error::Permanent(()))
+        }
+
+        // offline mode never reaches the RPC provider
+        if is_offline() {
+            logger.error("reading on-chain data requires network access, which is disabled by `--offline` .");
+            return Err(backoff::Error::Permanent(()))
+        }
+
+        // create new provider, rotating away from providers which keep failing
+        let provider_url = select_provider(rpc_url);
+        let provider = match Provider::<Http>::try_from(provider_url.as_str()) {
+            Ok(provider) => provider,
+            Err(_) => {
+                logger.error(&format!("failed to connect to RPC provider '{}' .", &provider_url));
+                return Err(backoff::Error::Permanent(()))
+            }
+        };
+
+        // safely unwrap the address
+        let address = match contract_address.parse::<Address>() {
+            Ok(address) => address,
+            Err(_) => {
+                logger.error(&format!("failed to parse address '{}' .", &contract_address));
+                return Err(backoff::Error::Permanent(()))
+            }
+        };
+
+        // fetch the value of the slot
+        let value = match provider
+            .get_storage_at(address, slot, Some(BlockId::from(block_number)))
+            .await
+        {
+            Ok(value) => value,
+            Err(_) => {
+                logger.error(&format!(
+                    "failed to fetch storage at block {} . does your RPC provider support archive queries?",
+                    &block_number
+                ));
+                record_provider_failure(&provider_url);
+                return Err(backoff::Error::Transient { err: (), retry_after: None })
+            }
+        };
+
+        // cache the results
+        store_cache(&cache_key, value, None)
+            .map_err(|_| logger.error(&format!("failed to cache value for slot: {:?}", &slot)))?;
+
+        Ok(value)
+    })
+    .await
+    .map_err(|_| network_error(format!("failed to get storage slot {:?} for contract: {:?}", &slot, &contract_address)))
+}
+
+/// Get the ether balance of the given address at the given block
+///
+/// ```no_run
+/// use heimdall_common::ether::rpc::get_balance;
+///
+/// // let balance = get_balance("0x0", 18_000_000, "https://eth.llamarpc.com").await;
+/// // assert!(balance.is_ok());
+/// ```
+pub async fn get_balance(address: &str, block_number: u64, rpc_url: &str) -> Result<U256, Error> {
+    backoff::future::retry(rpc_backoff(), || async {
+        // get a new logger
+        let logger = Logger::default();
+
+        // get chain_id
+        let chain_id = chain_id(rpc_url).await.unwrap_or(1);
+
+        // check the cache for a matching balance
+        let cache_key = format!("balance.{}.{}.{}", &chain_id, &address, &block_number);
+        if let Some(balance) = read_cache(&cache_key)
+            .map_err(|_| logger.error(&format!("failed to read cache for balance: {:?}", &address)))?
+        {
+            debug_max!("found cached balance for '{}' .", &address);
+            return Ok(balance)
+        }
+
+        debug_max!("fetching balance of '{}' at block {} .", &address, &block_number);
+
+        // make sure the RPC provider isn't empty
+        if rpc_url.is_empty() {
+            logger.error("reading on-chain data requires an RPC provider. Use `heimdall --help` for more information.");
+            return Err(backoff::Error::Permanent(()))
+        }
+
+        // offline mode never reaches the RPC provider
+        if is_offline() {
+            logger.error("reading on-chain data requires network access, which is disabled by `--offline` .");
+            return Err(backoff::Error::Permanent(()))
+        }
+
+        // create new provider, rotating away from providers which keep failing
+        let provider_url = select_provider(rpc_url);
+        let provider = match Provider::<Http>::try_from(provider_url.as_str()) {
+            Ok(provider) => provider,
+            Err(_) => {
+                logger.error(&format!("failed to connect to RPC provider '{}' .", &provider_url));
+                return Err(backoff::Error::Permanent(()))
+            }
+        };
+
+        // safely unwrap the address
+        let parsed_address = match address.parse::<Address>() {
+            Ok(address) => address,
+            Err(_) => {
+                logger.error(&format!("failed to parse address '{}' .", &address));
+                return Err(backoff::Error::Permanent(()))
+            }
+        };
+
+        // fetch the balance
+        let balance = match provider
+            .get_balance(parsed_address, Some(BlockId::from(block_number)))
+            .await
+        {
+            Ok(balance) => balance,
+            Err(_) => {
+                logger.error(&format!(
+                    "failed to fetch balance at block {} . does your RPC provider support archive queries?",
+                    &block_number
+                ));
+                record_provider_failure(&provider_url);
+                return Err(backoff::Error::Transient { err: (), retry_after: None })
+            }
+        };
+
+        // cache the results
+        store_cache(&cache_key, balance, None)
+            .map_err(|_| logger.error(&format!("failed to cache balance for: {:?}", &address)))?;
+
+        Ok(balance)
+    })
+    .await
+    .map_err(|_| network_error(format!("failed to get balance for: {:?}", &address)))
+}
+
+/// Make a read-only call to the given contract at the given block, returning its output
+///
+/// ```no_run
+/// use heimdall_common::ether::rpc::call;
+///
+/// // let output = call("0x0", vec![0x06, 0xfd, 0xde, 0x03], 18_000_000, "https://eth.llamarpc.com").await;
+/// // assert!(output.is_ok());
+/// ```
+pub async fn call(
+    contract_address: &str,
+    calldata: Vec<u8>,
+    block_number: u64,
+    rpc_url: &str,
+) -> Result<Vec<u8>, Error> {
+    backoff::future::retry(rpc_backoff(), || async {
+        // get a new logger
+        let logger = Logger::default();
+
+        debug_max!("calling '{}' at block {} .", &contract_address, &block_number);
+
+        // make sure the RPC provider isn't empty
+        if rpc_url.is_empty() {
+            logger.error("reading on-chain data requires an RPC provider. Use `heimdall --help` for more information.");
+            return Err(backoff::Error::Permanent(()))
+        }
+
+        // offline mode never reaches the RPC provider
+        if is_offline() {
+            logger.error("reading on-chain data requires network access, which is disabled by `--offline` .");
+            return Err(backoff::Error::Permanent(()))
+        }
+
+        // create new provider, rotating away from providers which keep failing
+        let provider_url = select_provider(rpc_url);
+        let provider = match Provider::<Http>::try_from(provider_url.as_str()) {
+            Ok(provider) => provider,
+            Err(_) => {
+                logger.error(&format!("failed to connect to RPC provider '{}' .", &provider_url));
+                return Err(backoff::Error::Permanent(()))
+            }
+        };
+
+        // safely unwrap the address
+        let address = match contract_address.parse::<Address>() {
+            Ok(address) => address,
+            Err(_) => {
+                logger.error(&format!("failed to parse address '{}' .", &contract_address));
+                return Err(backoff::Error::Permanent(()))
+            }
+        };
+
+        // a reverted call is an error from the provider, and isn't worth retrying
+        let transaction = TransactionRequest::new().to(address).data(calldata.clone());
+        match provider.call(&transaction.into(), Some(BlockId::from(block_number))).await {
+            Ok(output) => Ok(output.to_vec()),
+            Err(_) => {
+                logger.error(&format!("call to '{}' failed at block {} .", &contract_address, &block_number));
+                Err(backoff::Error::Permanent(()))
+            }
+        }
+    })
+    .await
+    .map_err(|_| network_error(format!("failed to call contract: {:?}", &contract_address)))
+}
+
+/// Replay a transaction as a call at the given block, returning its revert data, or `None` if
+/// the call succeeds
+///
+/// ```no_run
+/// use ethers::types::TransactionRequest;
+/// use heimdall_common::ether::rpc::get_revert_data;
+///
+/// // let revert = get_revert_data(TransactionRequest::new(), 18_000_000, "https://eth.llamarpc.com").await;
+/// // assert!(revert.is_ok());
+/// ```
+pub async fn get_revert_data(
+    transaction: TransactionRequest,
+    block_number: u64,
+    rpc_url: &str,
+) -> Result<Option<Vec<u8>>, Error> {
+    backoff::future::retry(rpc_backoff(), || async {
+        // get a new logger
+        let logger = Logger::default();
+
+        debug_max!("replaying transaction at block {} .", &block_number);
+
+        // make sure the RPC provider isn't empty
+        if rpc_url.is_empty() {
+            logger.error("reading on-chain data requires an RPC provider. Use `heimdall --help` for more information.");
+            return Err(backoff::Error::Permanent(()))
+        }
+
+        // offline mode never reaches the RPC provider
+        if is_offline() {
+            logger.error("reading on-chain data requires network access, which is disabled by `--offline` .");
+            return Err(backoff::Error::Permanent(()))
+        }
+
+        // create new provider, rotating away from providers which keep failing
+        let provider_url = select_provider(rpc_url);
+        let provider = match Provider::<Http>::try_from(provider_url.as_str()) {
+            Ok(provider) => provider,
+            Err(_) => {
+                logger.error(&format!("failed to connect to RPC provider '{}' .", &provider_url));
+                return Err(backoff::Error::Permanent(()))
+            }
+        };
+
+        // a revert is reported as an error response carrying the revert data, while any other
+        // error is the provider's, and is worth retrying
+        match provider.call(&transaction.clone().into(), Some(BlockId::from(block_number))).await {
+            Ok(_) => Ok(None),
+            Err(e) => match e.as_error_response().and_then(|response| response.as_revert_data()) {
+                Some(data) => Ok(Some(data.to_vec())),
+                None => {
+                    logger.error(&format!("failed to replay transaction at block {} .", &block_number));
+                    record_provider_failure(&provider_url);
+                    Err(backoff::Error::Transient { err: (), retry_after: None })
+                }
+            },
+        }
+    })
+    .await
+    .map_err(|_| network_error(format!("failed to replay transaction at block {block_number}")))
+}
+
+/// Get the block environment (number, timestamp, coinbase, etc.) of the given block
+///
+/// ```no_run
+/// use heimdall_common::ether::rpc::get_block_environment;
+///
+/// // let environment = get_block_environment(18_000_000, "https://eth.llamarpc.com").await;
+/// // assert!(environment.is_ok());
+/// ```
+pub async fn get_block_environment(
+    block_number: u64,
+    rpc_url: &str,
+) -> Result<BlockEnvironment, Error> {
+    backoff::future::retry(rpc_backoff(), || async {
+        // get a new logger
+        let logger = Logger::default();
+
+        debug_max!("fetching block environment for block {} .", &block_number);
+
+        // make sure the RPC provider isn't empty
+        if rpc_url.is_empty() {
+            logger.error("reading on-chain data requires an RPC provider. Use `heimdall --help` for more information.");
+            return Err(backoff::Error::Permanent(()))
+        }
+
+        // offline mode never reaches the RPC provider
+        if is_offline() {
+            logger.error("reading on-chain data requires network access, which is disabled by `--offline` .");
+            return Err(backoff::Error::Permanent(()))
+        }
+
+        // create new provider, rotating away from providers which keep failing
+        let provider_url = select_provider(rpc_url);
+        let provider = match Provider::<Http>::try_from(provider_url.as_str()) {
+            Ok(provider) => provider,
+            Err(_) => {
+                logger.error(&format!("failed to connect to RPC provider '{}' .", &provider_url));
+                return Err(backoff::Error::Permanent(()))
+            }
+        };
+
+        // fetch the block header
+        let block = match provider.get_block(block_number).await {
+            Ok(Some(block)) => block,
+            Ok(None) => {
+                logger.error(&format!("block {} does not exist .", &block_number));
+                return Err(backoff::Error::Permanent(()))
+            }
+            Err(_) => {
+                logger.error(&format!("failed to fetch block {} .", &block_number));
+                record_provider_failure(&provider_url);
+                return Err(backoff::Error::Transient { err: (), retry_after: None })
+            }
+        };
+
+        Ok(BlockEnvironment {
+            number: U256::from(block_number),
+            timestamp: block.timestamp,
+            coinbase: block.author.map(|author| U256::from_big_endian(author.as_bytes())).unwrap_or_default(),
+            prevrandao: block.mix_hash.map(|hash| U256::from_big_endian(hash.as_bytes())).unwrap_or_default(),
+            gas_limit: block.gas_limit,
+            chain_id: U256::from(chain_id(rpc_url).await.unwrap_or(1)),
+            base_fee: block.base_fee_per_gas.unwrap_or_default(),
+        })
+    })
+    .await
+    .map_err(|_| network_error(format!("failed to get block environment for block: {:?}", &block_number)))
+}
+
+/// Get the number of the latest block
+///
+/// ```no_run
+/// use heimdall_common::ether::rpc::get_block_number;
+///
+/// // let block_number = get_block_number("https://eth.llamarpc.com").await;
+/// // assert!(block_number.is_ok());
+/// ```
+pub async fn get_block_number(rpc_url: &str) -> Result<u64, Error> {
+    backoff::future::retry(rpc_backoff(), || async {
+        // get a new logger
+        let logger = Logger::default();
+
+        // make sure the RPC provider isn't empty
+        if rpc_url.is_empty() {
+            logger.error("reading on-chain data requires an RPC provider. Use `heimdall --help` for more information.");
+            return Err(backoff::Error::Permanent(()))
+        }
+
+        // offline mode never reaches the RPC provider
+        if is_offline() {
+            logger.error("reading on-chain data requires network access, which is disabled by `--offline` .");
+            return Err(backoff::Error::Permanent(()))
+        }
+
+        // create new provider, rotating away from providers which keep failing
+        let provider_url = select_provider(rpc_url);
+        let provider = match Provider::<Http>::try_from(provider_url.as_str()) {
+            Ok(provider) => provider,
+            Err(_) => {
+                logger.error(&format!("failed to connect to RPC provider '{}' .", &provider_url));
+                return Err(backoff::Error::Permanent(()))
+            }
+        };
+
+        // the latest block changes, so it's never cached
+        match provider.get_block_number().await {
+            Ok(block_number) => Ok(block_number.as_u64()),
+            Err(_) => {
+                logger.error(&format!("failed to fetch the latest block from '{}' .", &rpc_url));
+                record_provider_failure(&provider_url);
+                Err(backoff::Error::Transient { err: (), retry_after: None })
+            }
+        }
+    })
+    .await
+    .map_err(|_| network_error(String::from("failed to get the latest block number")))
 }
 
 /// Get the raw transaction data of the provided transaction hash
@@ -164,12 +630,7 @@ pub async fn get_code(contract_address: &str, rpc_url: &str) -> Result<String, E
 /// ```
 /// TODO: check for caching
 pub async fn get_transaction(transaction_hash: &str, rpc_url: &str) -> Result<Transaction, Error> {
-    backoff::future::retry(
-        ExponentialBackoff {
-            max_elapsed_time: Some(Duration::from_secs(10)),
-            ..ExponentialBackoff::default()
-        },
-    || async {
+    backoff::future::retry(rpc_backoff(), || async {
         // get a new logger
         let logger = Logger::default();
 
@@ -184,11 +645,18 @@ pub async fn get_transaction(transaction_hash: &str, rpc_url: &str) -> Result<Tr
             return Err(backoff::Error::Permanent(()));
         }
 
-        // create new provider
-        let provider = match Provider::<Http>::try_from(rpc_url) {
+        // offline mode never reaches the RPC provider
+        if is_offline() {
+            logger.error("reading on-chain data requires network access, which is disabled by `--offline` .");
+            return Err(backoff::Error::Permanent(()))
+        }
+
+        // create new provider, rotating away from providers which keep failing
+        let provider_url = select_provider(rpc_url);
+        let provider = match Provider::<Http>::try_from(provider_url.as_str()) {
             Ok(provider) => provider,
             Err(_) => {
-                logger.error(&format!("failed to connect to RPC provider '{}' .", &rpc_url));
+                logger.error(&format!("failed to connect to RPC provider '{}' .", &provider_url));
                 return Err(backoff::Error::Permanent(()))
             }
         };
@@ -213,6 +681,7 @@ pub async fn get_transaction(transaction_hash: &str, rpc_url: &str) -> Result<Tr
             },
             Err(_) => {
                 logger.error(&format!("failed to fetch calldata from '{}' .", &transaction_hash));
+                record_provider_failure(&provider_url);
                 return Err(backoff::Error::Transient { err: (), retry_after: None })
             }
         };
@@ -220,7 +689,7 @@ pub async fn get_transaction(transaction_hash: &str, rpc_url: &str) -> Result<Tr
         Ok(tx)
     })
     .await
-    .map_err(|_| Error::Generic(format!("failed to get transaction: {:?}", &transaction_hash)))
+    .map_err(|_| network_error(format!("failed to get transaction: {:?}", &transaction_hash)))
 }
 
 /// Get the storage diff of the provided transaction hash
@@ -236,10 +705,7 @@ pub async fn get_storage_diff(
     rpc_url: &str,
 ) -> Result<Option<StateDiff>, Error> {
     backoff::future::retry(
-        ExponentialBackoff {
-            max_elapsed_time: Some(Duration::from_secs(10)),
-            ..ExponentialBackoff::default()
-        },
+        rpc_backoff(),
         || async {
             // create new logger
             let logger = Logger::default();
@@ -262,11 +728,18 @@ pub async fn get_storage_diff(
                 &transaction_hash
             ));
 
-            // create new provider
-            let provider = match Provider::<Http>::try_from(rpc_url) {
+            // offline mode never reaches the RPC provider
+            if is_offline() {
+                logger.error("reading on-chain data requires network access, which is disabled by `--offline` .");
+                return Err(backoff::Error::Permanent(()))
+            }
+
+            // create new provider, rotating away from providers which keep failing
+            let provider_url = select_provider(rpc_url);
+            let provider = match Provider::<Http>::try_from(provider_url.as_str()) {
                 Ok(provider) => provider,
                 Err(_) => {
-                    logger.error(&format!("failed to connect to RPC provider '{}' .", &rpc_url));
+                    logger.error(&format!("failed to connect to RPC provider '{}' .", &provider_url));
                     return Err(backoff::Error::Permanent(()))
                 }
             };
@@ -293,6 +766,7 @@ pub async fn get_storage_diff(
                         "failed to replay and trace transaction '{}' . does your RPC provider support it?",
                         &transaction_hash
                     ));
+                    record_provider_failure(&provider_url);
                     return Err(backoff::Error::Transient { err: (), retry_after: None })
                 }
             };
@@ -316,7 +790,7 @@ pub async fn get_storage_diff(
         },
     )
     .await
-    .map_err(|_| Error::Generic(format!("failed to get storage diff for transaction: {:?}", &transaction_hash)))
+    .map_err(|_| network_error(format!("failed to get storage diff for transaction: {:?}", &transaction_hash)))
 }
 
 /// Get the raw trace data of the provided transaction hash
@@ -330,10 +804,7 @@ pub async fn get_storage_diff(
 /// TODO: check for caching
 pub async fn get_trace(transaction_hash: &str, rpc_url: &str) -> Result<BlockTrace, Error> {
     backoff::future::retry(
-        ExponentialBackoff {
-            max_elapsed_time: Some(Duration::from_secs(10)),
-            ..ExponentialBackoff::default()
-        },
+        rpc_backoff(),
         || async {
             // create new logger
             let logger = Logger::default();
@@ -343,11 +814,18 @@ pub async fn get_trace(transaction_hash: &str, rpc_url: &str) -> Result<BlockTra
                 &transaction_hash
             ));
 
-            // create new provider
-            let provider = match Provider::<Http>::try_from(rpc_url) {
+            // offline mode never reaches the RPC provider
+            if is_offline() {
+                logger.error("reading on-chain data requires network access, which is disabled by `--offline` .");
+                return Err(backoff::Error::Permanent(()))
+            }
+
+            // create new provider, rotating away from providers which keep failing
+            let provider_url = select_provider(rpc_url);
+            let provider = match Provider::<Http>::try_from(provider_url.as_str()) {
                 Ok(provider) => provider,
                 Err(_) => {
-                    logger.error(&format!("failed to connect to RPC provider '{}' .", &rpc_url));
+                    logger.error(&format!("failed to connect to RPC provider '{}' .", &provider_url));
                     return Err(backoff::Error::Permanent(()))
                 }
             };
@@ -379,6 +857,7 @@ pub async fn get_trace(transaction_hash: &str, rpc_url: &str) -> Result<BlockTra
                         &transaction_hash
                     ));
                     logger.error(&format!("error: '{e}' ."));
+                    record_provider_failure(&provider_url);
                     return Err(backoff::Error::Transient { err: (), retry_after: None })
                 }
             };
@@ -389,7 +868,98 @@ pub async fn get_trace(transaction_hash: &str, rpc_url: &str) -> Result<BlockTra
         },
     )
     .await
-    .map_err(|_| Error::Generic(format!("failed to get trace for transaction: {:?}", &transaction_hash)))
+    .map_err(|_| network_error(format!("failed to get trace for transaction: {:?}", &transaction_hash)))
+}
+
+/// Get the call tree of the provided transaction hash with geth's `callTracer`, including the
+/// logs each call emits. Used when the RPC provider doesn't support `trace_replayTransaction`.
+///
+/// ```no_run
+/// use heimdall_common::ether::rpc::get_call_trace;
+///
+/// // let trace = get_call_trace("0x0", "https://eth.llamarpc.com").await;
+/// // assert!(trace.is_ok());
+/// ```
+pub async fn get_call_trace(transaction_hash: &str, rpc_url: &str) -> Result<CallFrame, Error> {
+    backoff::future::retry(rpc_backoff(), || async {
+        // create new logger
+        let logger = Logger::default();
+
+        debug_max!(&format!(
+            "fetching call trace from node for transaction: '{}' .",
+            &transaction_hash
+        ));
+
+        // offline mode never reaches the RPC provider
+        if is_offline() {
+            logger.error(
+                "reading on-chain data requires network access, which is disabled by `--offline` .",
+            );
+            return Err(backoff::Error::Permanent(()))
+        }
+
+        // create new provider, rotating away from providers which keep failing
+        let provider_url = select_provider(rpc_url);
+        let provider = match Provider::<Http>::try_from(provider_url.as_str()) {
+            Ok(provider) => provider,
+            Err(_) => {
+                logger.error(&format!("failed to connect to RPC provider '{}' .", &provider_url));
+                return Err(backoff::Error::Permanent(()))
+            }
+        };
+
+        // safely unwrap the transaction hash
+        let transaction_hash_hex = match H256::from_str(transaction_hash) {
+            Ok(transaction_hash) => transaction_hash,
+            Err(_) => {
+                logger
+                    .error(&format!("failed to parse transaction hash '{}' .", &transaction_hash));
+                return Err(backoff::Error::Permanent(()))
+            }
+        };
+
+        // fetch the call tree for the transaction
+        let options = GethDebugTracingOptions {
+            tracer: Some(GethDebugTracerType::BuiltInTracer(
+                GethDebugBuiltInTracerType::CallTracer,
+            )),
+            tracer_config: Some(GethDebugTracerConfig::BuiltInTracer(
+                GethDebugBuiltInTracerConfig::CallTracer(CallConfig {
+                    only_top_call: Some(false),
+                    with_log: Some(true),
+                }),
+            )),
+            ..Default::default()
+        };
+        let call_frame = match provider.debug_trace_transaction(transaction_hash_hex, options).await
+        {
+            Ok(GethTrace::Known(GethTraceFrame::CallTracer(call_frame))) => call_frame,
+            Ok(_) => {
+                logger.error(&format!(
+                    "unexpected call trace format for transaction '{}' .",
+                    &transaction_hash
+                ));
+                return Err(backoff::Error::Permanent(()))
+            }
+            Err(e) => {
+                logger.error(&format!(
+                    "failed to trace transaction '{}' . does your RPC provider support it?",
+                    &transaction_hash
+                ));
+                logger.error(&format!("error: '{e}' ."));
+                record_provider_failure(&provider_url);
+                return Err(backoff::Error::Transient { err: (), retry_after: None })
+            }
+        };
+
+        debug_max!("fetched call trace for transaction '{}' .", &transaction_hash);
+
+        Ok(call_frame)
+    })
+    .await
+    .map_err(|_| {
+        network_error(format!("failed to get call trace for transaction: {:?}", &transaction_hash))
+    })
 }
 
 /// Get all logs for the given block number
@@ -404,55 +974,59 @@ pub async fn get_block_logs(
     block_number: u64,
     rpc_url: &str,
 ) -> Result<Vec<ethers::core::types::Log>, Error> {
-    backoff::future::retry(
-        ExponentialBackoff {
-            max_elapsed_time: Some(Duration::from_secs(10)),
-            ..ExponentialBackoff::default()
-        },
-        || async {
-            // create new logger
-            let logger = Logger::default();
+    backoff::future::retry(rpc_backoff(), || async {
+        // create new logger
+        let logger = Logger::default();
 
-            debug_max!(&format!("fetching logs from node for block: '{}' .", &block_number));
+        debug_max!(&format!("fetching logs from node for block: '{}' .", &block_number));
 
-            // create new provider
-            let provider = match Provider::<Http>::try_from(rpc_url) {
-                Ok(provider) => provider,
-                Err(_) => {
-                    logger.error(&format!("failed to connect to RPC provider '{}' .", &rpc_url));
-                    return Err(backoff::Error::Permanent(()));
-                }
-            };
+        // offline mode never reaches the RPC provider
+        if is_offline() {
+            logger.error(
+                "reading on-chain data requires network access, which is disabled by `--offline` .",
+            );
+            return Err(backoff::Error::Permanent(()))
+        }
 
-            // fetch the logs for the block
-            let logs = match provider
-                .get_logs(&Filter {
-                    block_option: FilterBlockOption::Range {
-                        from_block: Some(BlockNumber::from(block_number)),
-                        to_block: Some(BlockNumber::from(block_number)),
-                    },
-                    address: None,
-                    topics: [None, None, None, None],
-                })
-                .await
-            {
-                Ok(logs) => logs,
-                Err(_) => {
-                    logger.error(&format!(
-                        "failed to fetch logs for block '{}' . does your RPC provider support it?",
-                        &block_number
-                    ));
-                    return Err(backoff::Error::Transient { err: (), retry_after: None });
-                }
-            };
+        // create new provider, rotating away from providers which keep failing
+        let provider_url = select_provider(rpc_url);
+        let provider = match Provider::<Http>::try_from(provider_url.as_str()) {
+            Ok(provider) => provider,
+            Err(_) => {
+                logger.error(&format!("failed to connect to RPC provider '{}' .", &provider_url));
+                return Err(backoff::Error::Permanent(()));
+            }
+        };
 
-            debug_max!("fetched logs for block '{}' .", &block_number);
+        // fetch the logs for the block
+        let logs = match provider
+            .get_logs(&Filter {
+                block_option: FilterBlockOption::Range {
+                    from_block: Some(BlockNumber::from(block_number)),
+                    to_block: Some(BlockNumber::from(block_number)),
+                },
+                address: None,
+                topics: [None, None, None, None],
+            })
+            .await
+        {
+            Ok(logs) => logs,
+            Err(_) => {
+                logger.error(&format!(
+                    "failed to fetch logs for block '{}' . does your RPC provider support it?",
+                    &block_number
+                ));
+                record_provider_failure(&provider_url);
+                return Err(backoff::Error::Transient { err: (), retry_after: None });
+            }
+        };
 
-            Ok(logs)
-        },
-    )
+        debug_max!("fetched logs for block '{}' .", &block_number);
+
+        Ok(logs)
+    })
     .await
-    .map_err(|_| Error::Generic(format!("failed to get logs for block: {:?}", &block_number)))
+    .map_err(|_| network_error(format!("failed to get logs for block: {:?}", &block_number)))
 }
 
 // TODO: add tests
@@ -1,17 +1,28 @@
 use std::{
     collections::{HashMap, HashSet},
+    ops::Range,
     sync::{Arc, Mutex},
     time::Duration,
 };
 
+use ethers::types::U256;
 use indicatif::ProgressBar;
-use tokio::task;
-
-use crate::utils::{io::logging::Logger, strings::decode_hex};
+use tokio::{sync::Semaphore, task, time::sleep as async_sleep};
+
+use crate::{
+    error::Error,
+    utils::{
+        io::{exit::record_partial_analysis, logging::Logger},
+        strings::decode_hex,
+    },
+};
 
 use super::{
-    evm::core::vm::VM,
-    signatures::{ResolveSelector, ResolvedFunction},
+    evm::core::{
+        opcodes::{WrappedInput, WrappedOpcode},
+        vm::VM,
+    },
+    signatures::{prefetch_signatures, ResolveSelector, ResolvedFunction},
 };
 use crate::debug_max;
 
@@ -44,89 +55,258 @@ pub async fn get_resolved_selectors(
     Ok((selectors, resolved_selectors))
 }
 
-/// find all function selectors in the given EVM assembly.
+/// the maximum number of instructions executed along a single path through the dispatcher
+const MAX_DISPATCHER_PATH_STEPS: usize = 10_000;
+
+/// find all function selectors in the given EVM bytecode, and their entry points.
+///
+/// the dispatcher is executed symbolically, following both sides of every JUMPI, and each JUMPI
+/// which compares the selector to a constant is recorded. only the side where the selector didn't
+/// match is followed from those, so the functions themselves aren't executed. this finds
+/// selectors in binary-search dispatchers and behind fallback logic, wherever they're compared.
+///
+/// a dispatcher which jumps to a target computed from the selector, i.e. a jump table, only
+/// reaches one of its buckets this way. if one is found, each PUSH4 in the assembly is also tried
+/// as a selector.
 pub fn find_function_selectors(evm: &VM, assembly: &str) -> HashMap<String, u128> {
-    let mut function_selectors = HashMap::new();
-    let mut handled_selectors = HashSet::new();
+    let (mut function_selectors, computed_jump) = walk_dispatcher(evm);
 
-    // search through assembly for PUSHN (where N <= 4) instructions, optimistically assuming that
-    // they are function selectors
-    let assembly: Vec<String> = assembly.split('\n').map(|line| line.trim().to_string()).collect();
-    for line in assembly.iter() {
-        let instruction_args: Vec<String> = line.split(' ').map(|arg| arg.to_string()).collect();
+    if computed_jump {
+        debug_max!("dispatcher jumps to a computed target, resolving PUSH4 instructions too");
+
+        for line in assembly.split('\n') {
+            let instruction_args: Vec<&str> = line.split_whitespace().collect();
+            if instruction_args.len() < 3 ||
+                instruction_args[1] != "PUSH4" ||
+                function_selectors.contains_key(instruction_args[2])
+            {
+                continue
+            }
 
-        if instruction_args.len() >= 2 {
-            let instruction = instruction_args[1].clone();
+            let function_selector = instruction_args[2].to_string();
+            match resolve_entry_point(evm, &function_selector) {
+                0 => continue,
+                function_entry_point => {
+                    debug_max!(
+                        "found function selector {} at entry point {}",
+                        function_selector,
+                        function_entry_point
+                    );
+                    function_selectors.insert(function_selector, function_entry_point);
+                }
+            }
+        }
+    }
 
-            if &instruction == "PUSH4" {
-                let function_selector = instruction_args[2].clone();
+    function_selectors
+}
 
-                // check if this function selector has already been handled
-                if handled_selectors.contains(&function_selector) {
-                    continue
+/// symbolically execute the dispatcher, returning every selector it compares calldata to and the
+/// entry point it dispatches that selector to, and whether it jumps to a computed target
+fn walk_dispatcher(evm: &VM) -> (HashMap<String, u128>, bool) {
+    let mut function_selectors = HashMap::new();
+    let mut computed_jump = false;
+    let mut handled_branches = HashSet::new();
+
+    let mut vm = evm.clone();
+    vm.calldata = Vec::new();
+    let mut paths = vec![(vm, Vec::<Range<usize>>::new())];
+
+    while let Some((mut vm, mut copied_from_calldata)) = paths.pop() {
+        let mut steps = 0;
+        while vm.bytecode.len() >= vm.instruction as usize && steps < MAX_DISPATCHER_PATH_STEPS {
+            steps += 1;
+            let call = vm.step();
+            let instruction = &call.last_instruction;
+
+            match instruction.opcode {
+                // CODECOPY at an offset derived from calldata, i.e. reading a jump table
+                0x39 if instruction
+                    .input_operations
+                    .get(1)
+                    .is_some_and(|offset| is_calldata_derived(offset, &copied_from_calldata)) =>
+                {
+                    let dest_offset = instruction.inputs[0].try_into().unwrap_or(usize::MAX);
+                    let size = instruction.inputs[2].try_into().unwrap_or(0);
+                    copied_from_calldata.push(dest_offset..dest_offset.saturating_add(size));
+                }
+
+                // JUMP to a computed target
+                0x56 => {
+                    computed_jump |= is_calldata_derived(
+                        &instruction.input_operations[0],
+                        &copied_from_calldata,
+                    );
                 }
 
-                debug_max!(
-                    "optimistically assuming instruction {} {} {} is a function selector",
-                    instruction_args[0],
-                    instruction_args[1],
-                    instruction_args[2]
-                );
-
-                // add the function selector to the handled selectors
-                handled_selectors.insert(function_selector.clone());
-
-                // get the function's entry point
-                let function_entry_point =
-                    match resolve_entry_point(&evm.clone(), &function_selector) {
-                        0 => continue,
-                        x => x,
+                // JUMPI, which is either a selector comparison or a branch to follow both sides of
+                0x57 => {
+                    computed_jump |= is_calldata_derived(
+                        &instruction.input_operations[0],
+                        &copied_from_calldata,
+                    );
+
+                    let target: u128 = instruction.inputs[0].try_into().unwrap_or(u128::MAX);
+                    let fallthrough = instruction.instruction;
+                    let successors = match selector_comparison(&instruction.input_operations[1]) {
+                        Some((selector, equal_when_taken)) => {
+                            let (entry_point, mismatch) = match equal_when_taken {
+                                true => (target, fallthrough),
+                                false => (fallthrough, target),
+                            };
+
+                            debug_max!(
+                                "found function selector {:08x} at entry point {}",
+                                selector,
+                                entry_point
+                            );
+                            function_selectors
+                                .entry(format!("{selector:08x}"))
+                                .or_insert(entry_point);
+                            vec![mismatch]
+                        }
+                        None => vec![target, fallthrough],
                     };
 
-                debug_max!(
-                    "found function selector {} at entry point {}",
-                    function_selector,
-                    function_entry_point
-                );
+                    for successor in successors {
+                        // jumps must land on a JUMPDEST, and each branch is only followed once
+                        if (successor != fallthrough &&
+                            vm.bytecode.get(successor as usize) != Some(&0x5b)) ||
+                            !handled_branches.insert((fallthrough, successor))
+                        {
+                            continue
+                        }
+
+                        let mut branch_vm = vm.clone();
+                        branch_vm.instruction = successor + 1;
+                        paths.push((branch_vm, copied_from_calldata.clone()));
+                    }
+                    break
+                }
+                _ => {}
+            }
 
-                function_selectors.insert(function_selector, function_entry_point);
+            if vm.exitcode != 255 || !vm.returndata.is_empty() {
+                break
             }
         }
     }
-    function_selectors
+
+    (function_selectors, computed_jump)
+}
+
+/// if the JUMPI condition compares the selector to a constant, i.e. `selector == 0xaaaaaaaa`,
+/// the constant and whether the jump is taken when they're equal
+fn selector_comparison(condition: &WrappedOpcode) -> Option<(u32, bool)> {
+    match condition.opcode.code {
+        // ISZERO, which flips the comparison
+        0x15 => match condition.inputs.first()? {
+            WrappedInput::Opcode(operation) => selector_comparison(operation)
+                .map(|(selector, equal_when_taken)| (selector, !equal_when_taken)),
+            WrappedInput::Raw(_) | WrappedInput::Variable(_) => None,
+        },
+
+        // EQ, or SUB and XOR, which are zero when they're equal
+        0x14 | 0x03 | 0x18 => {
+            let (selector, other) = match (
+                condition.inputs.first().and_then(constant),
+                condition.inputs.get(1).and_then(constant),
+            ) {
+                (Some(selector), None) => (selector, condition.inputs.get(1)?),
+                (None, Some(selector)) => (selector, condition.inputs.first()?),
+                _ => return None,
+            };
+
+            match selector.bits() <= 32 && reads_calldata_head(other) {
+                true => Some((selector.as_u32(), condition.opcode.code == 0x14)),
+                false => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// whether the input is derived from the first word of calldata, which holds the selector
+fn reads_calldata_head(input: &WrappedInput) -> bool {
+    match input {
+        WrappedInput::Raw(_) | WrappedInput::Variable(_) => false,
+        WrappedInput::Opcode(operation) => {
+            (operation.opcode.code == 0x35 &&
+                operation.inputs.first().and_then(constant) == Some(U256::zero())) ||
+                operation.inputs.iter().any(reads_calldata_head)
+        }
+    }
 }
 
 /// resolve a selector's function entry point from the EVM bytecode
+///
+/// the entry point is the target of the first JUMPI which compares the selector to calldata. if
+/// there isn't one, the dispatcher may compute the target from the selector instead, either
+/// arithmetically (e.g. `base + (selector % n) * k`) or by loading it from a jump table in code
+/// with CODECOPY, in which case the first jump whose target is derived from calldata is used.
 pub fn resolve_entry_point(evm: &VM, selector: &str) -> u128 {
     let mut vm = evm.clone();
     let mut handled_jumps = HashSet::new();
+    let mut computed_entry_point = None;
+    let mut copied_from_calldata = Vec::new();
 
     // execute the EVM call to find the entry point for the given selector
     vm.calldata = decode_hex(selector).expect("Failed to decode selector.");
     while vm.bytecode.len() >= vm.instruction as usize {
         let call = vm.step();
+        let instruction = &call.last_instruction;
+
+        match instruction.opcode {
+            // CODECOPY at an offset derived from calldata, i.e. reading a jump table
+            0x39 if instruction
+                .input_operations
+                .get(1)
+                .is_some_and(|offset| is_calldata_derived(offset, &copied_from_calldata)) =>
+            {
+                let dest_offset = instruction.inputs[0].try_into().unwrap_or(usize::MAX);
+                let size = instruction.inputs[2].try_into().unwrap_or(0);
+                copied_from_calldata.push(dest_offset..dest_offset.saturating_add(size));
+            }
 
-        // if the opcode is an JUMPI and it matched the selector, the next jumpi is the entry point
-        if call.last_instruction.opcode == 0x57 {
-            let jump_condition = call.last_instruction.input_operations[1].solidify();
-            let jump_taken = call.last_instruction.inputs[1].try_into().unwrap_or(1);
-
-            if jump_condition.contains(selector) &&
-                jump_condition.contains("msg.data[0]") &&
-                jump_condition.contains(" == ") &&
-                jump_taken == 1
+            // JUMP to a computed target
+            0x56 if computed_entry_point.is_none() &&
+                is_calldata_derived(&instruction.input_operations[0], &copied_from_calldata) =>
             {
-                return call.last_instruction.inputs[0].try_into().unwrap_or(0)
-            } else if jump_taken == 1 {
-                // if handled_jumps contains the jumpi, we have already handled this jump.
-                // loops aren't supported in the dispatcher, so we can just return 0
-                if handled_jumps.contains(&call.last_instruction.inputs[0].try_into().unwrap_or(0))
+                computed_entry_point = Some(instruction.inputs[0].try_into().unwrap_or(0));
+            }
+
+            // if the opcode is an JUMPI and it matched the selector, the next jumpi is the entry
+            // point
+            0x57 => {
+                let jump_condition = instruction.input_operations[1].solidify();
+                let jump_taken = instruction.inputs[1].try_into().unwrap_or(1);
+
+                if jump_condition.contains(selector) &&
+                    jump_condition.contains("msg.data[0]") &&
+                    jump_condition.contains(" == ") &&
+                    jump_taken == 1
                 {
-                    return 0
-                } else {
-                    handled_jumps.insert(call.last_instruction.inputs[0].try_into().unwrap_or(0));
+                    return instruction.inputs[0].try_into().unwrap_or(0)
+                } else if jump_taken == 1 {
+                    if computed_entry_point.is_none() &&
+                        is_calldata_derived(
+                            &instruction.input_operations[0],
+                            &copied_from_calldata,
+                        )
+                    {
+                        computed_entry_point = Some(instruction.inputs[0].try_into().unwrap_or(0));
+                    }
+
+                    // if handled_jumps contains the jumpi, we have already handled this jump.
+                    // loops aren't supported in the dispatcher, so we can stop here
+                    if handled_jumps.contains(&instruction.inputs[0].try_into().unwrap_or(0)) {
+                        return computed_entry_point.unwrap_or(0)
+                    } else {
+                        handled_jumps.insert(instruction.inputs[0].try_into().unwrap_or(0));
+                    }
                 }
             }
+            _ => {}
         }
 
         if vm.exitcode != 255 || !vm.returndata.is_empty() {
@@ -134,18 +314,76 @@ pub fn resolve_entry_point(evm: &VM, selector: &str) -> u128 {
         }
     }
 
-    0
+    computed_entry_point.unwrap_or(0)
 }
 
+/// whether the operation's value depends on calldata, either directly or by loading memory which
+/// was copied from code at a calldata-derived offset
+fn is_calldata_derived(operation: &WrappedOpcode, copied_from_calldata: &[Range<usize>]) -> bool {
+    match operation.opcode.code {
+        // CALLDATALOAD
+        0x35 => return true,
+        // MLOAD
+        0x51 if operation
+            .inputs
+            .first()
+            .and_then(constant)
+            .and_then(|offset| usize::try_from(offset).ok())
+            .is_some_and(|offset| {
+                copied_from_calldata.iter().any(|range| range.contains(&offset))
+            }) =>
+        {
+            return true
+        }
+        _ => {}
+    }
+
+    operation.inputs.iter().any(|input| match input {
+        WrappedInput::Raw(_) | WrappedInput::Variable(_) => false,
+        WrappedInput::Opcode(operation) => is_calldata_derived(operation, copied_from_calldata),
+    })
+}
+
+/// the value of an input, if it's a constant
+fn constant(input: &WrappedInput) -> Option<U256> {
+    match input {
+        WrappedInput::Raw(value) => Some(*value),
+        WrappedInput::Opcode(operation) => match operation.opcode.code {
+            // PUSH0
+            0x5f => Some(U256::zero()),
+            // PUSH1 -> PUSH32
+            0x60..=0x7f => operation.inputs.first().and_then(constant),
+            _ => None,
+        },
+        WrappedInput::Variable(_) => None,
+    }
+}
+
+/// the most selectors resolved at once
+const MAX_CONCURRENT_RESOLUTIONS: usize = 16;
+
+/// the most times resolving a single selector is attempted before it's given up on
+const MAX_RESOLVE_ATTEMPTS: u32 = 3;
+
 /// Resolve a list of selectors to their function signatures.
+///
+/// Selectors are resolved concurrently, at most [`MAX_CONCURRENT_RESOLUTIONS`] at a time, and
+/// requests to the signature databases are spaced by the configured rate limit. A selector which
+/// fails to resolve is retried with exponential backoff. Selectors which still fail are missing
+/// from the result, and are reported, rather than failing the whole resolution.
 pub async fn resolve_selectors<T>(selectors: Vec<String>) -> HashMap<String, Vec<T>>
 where
     T: ResolveSelector + Send + Clone + 'static, {
     // get a new logger
     let logger = Logger::default();
 
+    // look up uncached selectors in bulk first, so each task below reads them from the cache
+    prefetch_signatures(T::KIND, &selectors).await;
+
     let resolved_functions: Arc<Mutex<HashMap<String, Vec<T>>>> =
         Arc::new(Mutex::new(HashMap::new()));
+    let failed_selectors: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+    let permits = Arc::new(Semaphore::new(MAX_CONCURRENT_RESOLUTIONS));
 
     let resolve_progress: Arc<Mutex<ProgressBar>> =
         Arc::new(Mutex::new(ProgressBar::new_spinner()));
@@ -167,18 +405,33 @@ where
 
     for selector in selectors {
         let function_clone = resolved_functions.clone();
+        let failed_clone = failed_selectors.clone();
         let resolve_progress = resolve_progress.clone();
+        let permits = permits.clone();
 
-        // create a new thread for each selector
+        // create a new thread for each selector, which waits for a permit before resolving
         threads.push(task::spawn(async move {
-            if let Ok(Some(function)) = T::resolve(&selector).await {
-                let mut _resolved_functions =
-                    function_clone.lock().expect("Could not obtain lock on function_clone.");
-                let mut _resolve_progress =
-                    resolve_progress.lock().expect("Could not obtain lock on resolve_progress.");
-                _resolve_progress
-                    .set_message(format!("resolved {} selectors", _resolved_functions.len()));
-                _resolved_functions.insert(selector, function);
+            let _permit = permits.acquire_owned().await;
+
+            match resolve_with_retry::<T>(&selector).await {
+                Ok(Some(function)) => {
+                    let mut _resolved_functions =
+                        function_clone.lock().expect("Could not obtain lock on function_clone.");
+                    let mut _resolve_progress = resolve_progress
+                        .lock()
+                        .expect("Could not obtain lock on resolve_progress.");
+                    _resolve_progress
+                        .set_message(format!("resolved {} selectors", _resolved_functions.len()));
+                    _resolved_functions.insert(selector, function);
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    debug_max!("failed to resolve selector {}: {}", &selector, e);
+                    failed_clone
+                        .lock()
+                        .expect("Could not obtain lock on failed_clone.")
+                        .push(selector);
+                }
             }
         }));
     }
@@ -193,7 +446,164 @@ where
 
     resolve_progress.lock().unwrap().finish_and_clear();
 
+    // selectors which couldn't be resolved are left out, so the rest of the analysis goes ahead
+    let mut failed_selectors =
+        failed_selectors.lock().expect("Could not obtain lock on failed_selectors.").clone();
+    if !failed_selectors.is_empty() {
+        failed_selectors.sort();
+        logger.warn(&format!(
+            "failed to resolve {} selectors: {}",
+            failed_selectors.len(),
+            failed_selectors.join(", ")
+        ));
+        record_partial_analysis();
+    }
+
     let x =
         resolved_functions.lock().expect("Could not obtain lock on resolved_functions.").clone();
     x
 }
+
+/// Resolve a single selector, retrying with exponential backoff if resolving fails.
+async fn resolve_with_retry<T>(selector: &str) -> Result<Option<Vec<T>>, Error>
+where
+    T: ResolveSelector + Send, {
+    let mut attempt = 0;
+    loop {
+        match T::resolve(selector).await {
+            Ok(resolved) => return Ok(resolved),
+            Err(e) => {
+                attempt += 1;
+                if attempt >= MAX_RESOLVE_ATTEMPTS {
+                    return Err(e)
+                }
+
+                debug_max!("failed to resolve selector {}, retrying: {}", selector, e);
+                async_sleep(Duration::from_millis(2u64.pow(attempt) * 125)).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use async_trait::async_trait;
+
+    use crate::ether::selectors::*;
+
+    fn vm(bytecode: &str) -> VM {
+        VM::new(
+            bytecode.to_string(),
+            String::from("0x"),
+            String::from("0x6865696d64616c6c000000000061646472657373"),
+            String::from("0x6865696d64616c6c0000000000006f726967696e"),
+            String::from("0x6865696d64616c6c00000000000063616c6c6572"),
+            0,
+            u128::MAX,
+        )
+    }
+
+    #[test]
+    fn test_resolve_arithmetic_jump_target() {
+        // jumps to 0x20 + (selector % 2) * 0x20, where each function checks its selector
+        let evm = vm("60003560e01c8060029006602002602001565b60006000fd00000000000000005b8063aaaaaaaa141560125760016000550000000000000000000000000000005b8063bbbbbbbb1415601257600260005500");
+
+        assert_eq!(resolve_entry_point(&evm, "aaaaaaaa"), 0x20);
+        assert_eq!(resolve_entry_point(&evm, "bbbbbbbb"), 0x40);
+    }
+
+    #[test]
+    fn test_resolve_jump_table_target() {
+        // copies the target for selector % 2 from a jump table at the end of the code
+        let evm = vm("60003560e01c8060029006602002604501602090600039600051565b60006000fd5b8063aaaaaaaa1415601b576001600055005b8063bbbbbbbb1415601b5760026000550000000000000000000000000000000000000000000000000000000000000000210000000000000000000000000000000000000000000000000000000000000033");
+
+        assert_eq!(resolve_entry_point(&evm, "aaaaaaaa"), 0x21);
+        assert_eq!(resolve_entry_point(&evm, "bbbbbbbb"), 0x33);
+    }
+
+    #[test]
+    fn test_resolve_selector_comparison() {
+        // a solidity-style dispatcher, which compares the selector directly
+        let evm = vm("60003560e01c8063aaaaaaaa14601157005b00");
+
+        assert_eq!(resolve_entry_point(&evm, "aaaaaaaa"), 0x11);
+        assert_eq!(resolve_entry_point(&evm, "bbbbbbbb"), 0);
+    }
+
+    #[test]
+    fn test_find_binary_search_selectors() {
+        // splits on selector > 0x80000000 before comparing each half's selectors
+        let evm = vm("60003560e01c806380000000116028578063cccccccc146045578063dddddddd14604757600080fd5b8063111111111460415780632222222214604357600080fd5b005b005b005b00");
+
+        assert_eq!(
+            find_function_selectors(&evm, ""),
+            HashMap::from([
+                (String::from("11111111"), 0x41),
+                (String::from("22222222"), 0x43),
+                (String::from("cccccccc"), 0x45),
+                (String::from("dddddddd"), 0x47),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_find_selectors_behind_fallback_logic() {
+        // dispatches to 0xbbbbbbbb only if storage slot 0 is set, and to 0xaaaaaaaa otherwise
+        let evm = vm("60005460165760003560e01c63aaaaaaaa14602757005b60003560e01c63bbbbbbbb14602957005b005b00");
+
+        assert_eq!(
+            find_function_selectors(&evm, ""),
+            HashMap::from([(String::from("aaaaaaaa"), 0x27), (String::from("bbbbbbbb"), 0x29)])
+        );
+    }
+
+    #[test]
+    fn test_find_jump_table_selectors() {
+        // the bucket for selector % 2 == 0 is walked, and the other is resolved from its PUSH4
+        let evm = vm("60003560e01c8060029006602002602001565b60006000fd00000000000000005b8063aaaaaaaa141560125760016000550000000000000000000000000000005b8063bbbbbbbb1415601257600260005500");
+
+        assert_eq!(
+            find_function_selectors(&evm, "000022 PUSH4 aaaaaaaa\n000042 PUSH4 bbbbbbbb"),
+            HashMap::from([(String::from("aaaaaaaa"), 0x2c), (String::from("bbbbbbbb"), 0x40)])
+        );
+    }
+
+    static FLAKY_ATTEMPTS: AtomicU32 = AtomicU32::new(0);
+    static FAILING_ATTEMPTS: AtomicU32 = AtomicU32::new(0);
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct FlakyResolver;
+
+    #[async_trait]
+    impl ResolveSelector for FlakyResolver {
+        const KIND: &'static str = "function";
+
+        async fn resolve(selector: &str) -> Result<Option<Vec<Self>>, Error> {
+            match selector {
+                // fails once, then resolves
+                "aaaaaaaa" => match FLAKY_ATTEMPTS.fetch_add(1, Ordering::SeqCst) {
+                    0 => Err(Error::Generic(String::from("rate limited"))),
+                    _ => Ok(Some(vec![FlakyResolver])),
+                },
+                _ => {
+                    FAILING_ATTEMPTS.fetch_add(1, Ordering::SeqCst);
+                    Err(Error::Generic(String::from("unreachable")))
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resolve_with_retry() {
+        assert_eq!(
+            resolve_with_retry::<FlakyResolver>("aaaaaaaa").await.unwrap(),
+            Some(vec![FlakyResolver])
+        );
+        assert_eq!(FLAKY_ATTEMPTS.load(Ordering::SeqCst), 2);
+
+        assert!(resolve_with_retry::<FlakyResolver>("bbbbbbbb").await.is_err());
+        assert_eq!(FAILING_ATTEMPTS.load(Ordering::SeqCst), MAX_RESOLVE_ATTEMPTS);
+    }
+}
@@ -1,11 +1,28 @@
+use std::collections::HashMap;
+
 use async_trait::async_trait;
-use ethers::abi::Token;
-use heimdall_cache::{read_cache, store_cache};
+use ethers::{
+    abi::{Abi, Param, Token},
+    types::U256,
+    utils::keccak256,
+};
+use heimdall_cache::{exists, read_cache, store_cache};
 
 use crate::{
     debug_max,
     error::Error,
-    utils::{http::get_json_from_url, strings::replace_last},
+    ether::{
+        endpoints::{lookup_signatures, lookup_signatures_bulk},
+        explorer::get_verified_source,
+        rpc::chain_id,
+        selectors::resolve_selectors,
+    },
+    resources::labels::get_selector_label,
+    utils::{
+        http::get_json_from_url,
+        offline::is_offline,
+        strings::{encode_hex, replace_last},
+    },
 };
 use serde::{Deserialize, Serialize};
 
@@ -31,8 +48,214 @@ pub struct ResolvedLog {
     pub inputs: Vec<String>,
 }
 
+/// A source of text signatures for selectors, such as a signature database or a contract's
+/// verified ABI. Providers are chained in order of preference by
+/// [`crate::ether::endpoints::lookup_signatures`], which falls back to the next provider when one
+/// doesn't know a selector.
+#[async_trait]
+pub trait SignatureProvider {
+    /// Look up the text signatures for the given selector, without a `0x` prefix. `kind` is either
+    /// `function` or `event`. Returns `None` if the provider has no answer, i.e. it couldn't be
+    /// reached, so the next provider is tried.
+    async fn lookup(&self, kind: &str, selector: &str) -> Result<Option<Vec<String>>, Error>;
+
+    /// Look up the text signatures for many selectors at once. Returns `None` if the provider
+    /// doesn't support bulk lookups, or couldn't be reached.
+    async fn lookup_bulk(
+        &self,
+        _kind: &str,
+        _selectors: &[String],
+    ) -> Result<Option<HashMap<String, Vec<String>>>, Error> {
+        Ok(None)
+    }
+}
+
+/// The signatures in a contract's verified ABI, from a block explorer or Sourcify. These are
+/// ground truth for the contract they were verified for.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerifiedAbi {
+    /// The text signatures of the ABI's functions and errors by selector, and its events by
+    /// topic, keyed by `{kind}.{selector}` without a `0x` prefix
+    signatures: HashMap<String, Vec<String>>,
+}
+
+impl VerifiedAbi {
+    /// Parse an ABI, given as JSON.
+    ///
+    /// ```
+    /// use heimdall_common::ether::signatures::VerifiedAbi;
+    ///
+    /// let abi = VerifiedAbi::parse(
+    ///     r#"[{"type":"function","name":"transfer","inputs":[{"name":"to","type":"address"},{"name":"amount","type":"uint256"}],"outputs":[],"stateMutability":"nonpayable"}]"#,
+    /// )
+    /// .unwrap();
+    /// assert_eq!(abi.signatures("function", "a9059cbb"), Some(vec![String::from("transfer(address,uint256)")]));
+    /// ```
+    pub fn parse(abi: &str) -> Result<VerifiedAbi, Error> {
+        let abi: Abi = serde_json::from_str(abi)
+            .map_err(|e| Error::Generic(format!("failed to parse verified ABI: {e}")))?;
+        let text_signature = |name: &str, inputs: &[Param]| {
+            format!(
+                "{}({})",
+                name,
+                inputs.iter().map(|input| input.kind.to_string()).collect::<Vec<_>>().join(",")
+            )
+        };
+
+        let mut signatures: HashMap<String, Vec<String>> = HashMap::new();
+        for function in abi.functions() {
+            signatures
+                .entry(format!("function.{}", encode_hex(function.short_signature().to_vec())))
+                .or_default()
+                .push(text_signature(&function.name, &function.inputs));
+        }
+        for error in abi.errors() {
+            signatures
+                .entry(format!("function.{}", encode_hex(error.signature()[..4].to_vec())))
+                .or_default()
+                .push(text_signature(&error.name, &error.inputs));
+        }
+        for event in abi.events().filter(|event| !event.anonymous) {
+            let inputs =
+                event.inputs.iter().map(|input| input.kind.to_string()).collect::<Vec<_>>();
+            signatures
+                .entry(format!("event.{}", encode_hex(event.signature().as_bytes().to_vec())))
+                .or_default()
+                .push(format!("{}({})", event.name, inputs.join(",")));
+        }
+
+        Ok(VerifiedAbi { signatures })
+    }
+
+    /// The verified ABI of the given contract on the block explorer for `rpc_url`'s chain, if
+    /// it's verified there.
+    pub async fn from_explorer(address: &str, rpc_url: &str) -> Option<VerifiedAbi> {
+        let verified = get_verified_source(address, rpc_url).await?;
+        VerifiedAbi::parse(&verified.abi)
+            .map_err(|e| debug_max!("failed to parse verified ABI for '{}': {}", address, e))
+            .ok()
+    }
+
+    /// The verified ABI of the given contract on the Sourcify server at `url`, for `rpc_url`'s
+    /// chain, if it's verified there.
+    pub async fn from_sourcify(url: &str, address: &str, rpc_url: &str) -> Option<VerifiedAbi> {
+        let chain_id = chain_id(rpc_url).await.ok()?;
+        let response =
+            get_json_from_url(&format!("{url}/v2/contract/{chain_id}/{address}?fields=abi"), 10)
+                .await
+                .ok()??;
+
+        VerifiedAbi::parse(&response.get("abi")?.to_string())
+            .map_err(|e| debug_max!("failed to parse sourcify ABI for '{}': {}", address, e))
+            .ok()
+    }
+
+    /// The text signatures of the given selector in the ABI, if it has any. `kind` is either
+    /// `function` or `event`.
+    pub fn signatures(&self, kind: &str, selector: &str) -> Option<Vec<String>> {
+        self.signatures.get(&format!("{kind}.{}", selector.trim_start_matches("0x"))).cloned()
+    }
+}
+
+#[async_trait]
+impl SignatureProvider for VerifiedAbi {
+    async fn lookup(&self, kind: &str, selector: &str) -> Result<Option<Vec<String>>, Error> {
+        Ok(self.signatures(kind, selector))
+    }
+
+    async fn lookup_bulk(
+        &self,
+        kind: &str,
+        selectors: &[String],
+    ) -> Result<Option<HashMap<String, Vec<String>>>, Error> {
+        Ok(Some(
+            selectors
+                .iter()
+                .filter_map(|selector| Some((selector.clone(), self.signatures(kind, selector)?)))
+                .collect(),
+        ))
+    }
+}
+
+/// How long a selector which no signature database knows is cached for, so signatures added to
+/// the databases since are picked up.
+const UNKNOWN_SELECTOR_TTL: u64 = 60 * 60 * 24 * 7;
+
+/// When a cached lookup expires. Found signatures are kept for the cache's default lifetime, and
+/// unknown selectors for [`UNKNOWN_SELECTOR_TTL`].
+fn cache_expiry(found: bool) -> Option<u64> {
+    match found {
+        true => None,
+        false => std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .ok()
+            .map(|now| now.as_secs() + UNKNOWN_SELECTOR_TTL),
+    }
+}
+
+/// Look up the text signatures for the given selector. A signature in the local label store takes
+/// precedence over signatures fetched by [`prefetch_signatures`], which take precedence over the
+/// configured signature databases.
+async fn text_signatures(kind: &str, selector: &str) -> Result<Option<Vec<String>>, Error> {
+    if let Some(label) = get_selector_label(selector).filter(|label| label.contains('(')) {
+        debug_max!("using labeled signature '{}' for selector: {}", &label, &selector);
+        return Ok(Some(vec![label]))
+    }
+
+    if let Some(signatures) = read_cache::<Vec<String>>(&format!("signatures.{kind}.{selector}"))
+        .map_err(|e| Error::Generic(format!("error reading cache: {}", e)))?
+    {
+        debug_max!("found prefetched signatures for selector: {}", &selector);
+        return Ok(Some(signatures))
+    }
+
+    lookup_signatures(kind, selector).await
+}
+
+/// Look up the signatures of every selector which isn't cached yet in as few requests as the
+/// configured signature databases allow, and cache them, so resolving each selector afterwards
+/// doesn't make a request per selector. `kind` is either `function` or `event`.
+pub async fn prefetch_signatures(kind: &str, selectors: &[String]) {
+    if is_offline() {
+        return
+    }
+
+    let uncached = selectors
+        .iter()
+        .map(|selector| selector.trim_start_matches("0x").to_string())
+        .filter(|selector| {
+            get_selector_label(selector).is_none() &&
+                !exists(&format!("selector.{selector}")).unwrap_or(false) &&
+                !exists(&format!("signatures.{kind}.{selector}")).unwrap_or(false)
+        })
+        .collect::<Vec<String>>();
+    if uncached.is_empty() {
+        return
+    }
+
+    match lookup_signatures_bulk(kind, &uncached).await {
+        Ok(signatures) => {
+            debug_max!(
+                "prefetched signatures for {} of {} selectors",
+                signatures.len(),
+                uncached.len()
+            );
+            for (selector, signatures) in signatures {
+                let expiry = cache_expiry(!signatures.is_empty());
+                let _ = store_cache(&format!("signatures.{kind}.{selector}"), signatures, expiry)
+                    .map_err(|e| debug_max!("error storing signatures in cache: {}", e));
+            }
+        }
+        Err(e) => debug_max!("error prefetching signatures: {}", e),
+    }
+}
+
 #[async_trait]
 pub trait ResolveSelector {
+    /// The kind of signature database lookup the selector is resolved with, either `function` or
+    /// `event`
+    const KIND: &'static str;
+
     async fn resolve(selector: &str) -> Result<Option<Vec<Self>>, Error>
     where
         Self: Sized;
@@ -40,6 +263,8 @@ pub trait ResolveSelector {
 
 #[async_trait]
 impl ResolveSelector for ResolvedError {
+    const KIND: &'static str = "function";
+
     async fn resolve(selector: &str) -> Result<Option<Vec<Self>>, Error> {
         // normalize selector
         let selector = match selector.strip_prefix("0x") {
@@ -63,41 +288,17 @@ impl ResolveSelector for ResolvedError {
             }
         }
 
-        // get function possibilities from openchain
-        let signatures = match get_json_from_url(
-            &format!(
-                "https://api.openchain.xyz/signature-database/v1/lookup?filter=true&function=0x{}",
-                &selector
-            ),
-            10,
-        )
-        .await
-        .map_err(|e| Error::Generic(format!("error fetching signatures from openchain: {}", e)))?
-        {
-            Some(signatures) => signatures,
+        // get function possibilities from the label store or configured signature databases
+        let results = match text_signatures(Self::KIND, selector).await? {
+            Some(results) => results,
             None => return Ok(None),
         };
 
-        // convert the serde value into a vec of possible functions
-        let results = signatures
-            .get("result")
-            .and_then(|result| result.get("function"))
-            .and_then(|function| function.get(format!("0x{selector}")))
-            .and_then(|item| item.as_array())
-            .map(|array| array.to_vec())
-            .ok_or_else(|| Error::Generic("error parsing signatures from openchain".to_string()))?;
-
         debug_max!("found {} possible functions for selector: {}", &results.len(), &selector);
 
         let mut signature_list: Vec<ResolvedError> = Vec::new();
 
-        for signature in results {
-            // get the function text signature and unwrap it into a string
-            let text_signature = match signature.get("name") {
-                Some(text_signature) => text_signature.to_string().replace('"', ""),
-                None => continue,
-            };
-
+        for text_signature in results {
             // safely split the text signature into name and inputs
             let function_parts = match text_signature.split_once('(') {
                 Some(function_parts) => function_parts,
@@ -115,7 +316,8 @@ impl ResolveSelector for ResolvedError {
         }
 
         // cache the results
-        let _ = store_cache(&format!("selector.{selector}"), signature_list.clone(), None)
+        let expiry = cache_expiry(!signature_list.is_empty());
+        let _ = store_cache(&format!("selector.{selector}"), signature_list.clone(), expiry)
             .map_err(|e| debug_max!("error storing signatures in cache: {}", e));
 
         Ok(match signature_list.len() {
@@ -127,6 +329,8 @@ impl ResolveSelector for ResolvedError {
 
 #[async_trait]
 impl ResolveSelector for ResolvedLog {
+    const KIND: &'static str = "event";
+
     async fn resolve(selector: &str) -> Result<Option<Vec<Self>>, Error> {
         // normalize selector
         let selector = match selector.strip_prefix("0x") {
@@ -150,41 +354,17 @@ impl ResolveSelector for ResolvedLog {
             }
         }
 
-        // get function possibilities from openchain
-        let signatures = match get_json_from_url(
-            &format!(
-                "https://api.openchain.xyz/signature-database/v1/lookup?filter=true&event=0x{}",
-                &selector
-            ),
-            10,
-        )
-        .await
-        .map_err(|e| Error::Generic(format!("error fetching signatures from openchain: {}", e)))?
-        {
-            Some(signatures) => signatures,
+        // get function possibilities from the label store or configured signature databases
+        let results = match text_signatures(Self::KIND, selector).await? {
+            Some(results) => results,
             None => return Ok(None),
         };
 
-        // convert the serde value into a vec of possible functions
-        let results = signatures
-            .get("result")
-            .and_then(|result| result.get("event"))
-            .and_then(|function| function.get(format!("0x{selector}")))
-            .and_then(|item| item.as_array())
-            .map(|array| array.to_vec())
-            .ok_or_else(|| Error::Generic("error parsing signatures from openchain".to_string()))?;
-
         debug_max!("found {} possible functions for selector: {}", &results.len(), &selector);
 
         let mut signature_list: Vec<ResolvedLog> = Vec::new();
 
-        for signature in results {
-            // get the function text signature and unwrap it into a string
-            let text_signature = match signature.get("name") {
-                Some(text_signature) => text_signature.to_string().replace('"', ""),
-                None => continue,
-            };
-
+        for text_signature in results {
             // safely split the text signature into name and inputs
             let function_parts = match text_signature.split_once('(') {
                 Some(function_parts) => function_parts,
@@ -202,7 +382,8 @@ impl ResolveSelector for ResolvedLog {
         }
 
         // cache the results
-        let _ = store_cache(&format!("selector.{selector}"), signature_list.clone(), None)
+        let expiry = cache_expiry(!signature_list.is_empty());
+        let _ = store_cache(&format!("selector.{selector}"), signature_list.clone(), expiry)
             .map_err(|e| debug_max!("error storing signatures in cache: {}", e));
 
         Ok(match signature_list.len() {
@@ -214,6 +395,8 @@ impl ResolveSelector for ResolvedLog {
 
 #[async_trait]
 impl ResolveSelector for ResolvedFunction {
+    const KIND: &'static str = "function";
+
     async fn resolve(selector: &str) -> Result<Option<Vec<Self>>, Error> {
         // normalize selector
         let selector = match selector.strip_prefix("0x") {
@@ -237,41 +420,17 @@ impl ResolveSelector for ResolvedFunction {
             }
         }
 
-        // get function possibilities from openchain
-        let signatures = match get_json_from_url(
-            &format!(
-                "https://api.openchain.xyz/signature-database/v1/lookup?filter=true&function=0x{}",
-                &selector
-            ),
-            10,
-        )
-        .await
-        .map_err(|e| Error::Generic(format!("error fetching signatures from openchain: {}", e)))?
-        {
-            Some(signatures) => signatures,
+        // get function possibilities from the label store or configured signature databases
+        let results = match text_signatures(Self::KIND, selector).await? {
+            Some(results) => results,
             None => return Ok(None),
         };
 
-        // convert the serde value into a vec of possible functions
-        let results = signatures
-            .get("result")
-            .and_then(|result| result.get("function"))
-            .and_then(|function| function.get(format!("0x{selector}")))
-            .and_then(|item| item.as_array())
-            .map(|array| array.to_vec())
-            .ok_or_else(|| Error::Generic("error parsing signatures from openchain".to_string()))?;
-
         debug_max!("found {} possible functions for selector: {}", &results.len(), &selector);
 
         let mut signature_list: Vec<ResolvedFunction> = Vec::new();
 
-        for signature in results {
-            // get the function text signature and unwrap it into a string
-            let text_signature = match signature.get("name") {
-                Some(text_signature) => text_signature.to_string().replace('"', ""),
-                None => continue,
-            };
-
+        for text_signature in results {
             // safely split the text signature into name and inputs
             let function_parts = match text_signature.split_once('(') {
                 Some(function_parts) => function_parts,
@@ -290,7 +449,8 @@ impl ResolveSelector for ResolvedFunction {
         }
 
         // cache the results
-        let _ = store_cache(&format!("selector.{selector}"), signature_list.clone(), None)
+        let expiry = cache_expiry(!signature_list.is_empty());
+        let _ = store_cache(&format!("selector.{selector}"), signature_list.clone(), expiry)
             .map_err(|e| debug_max!("error storing signatures in cache: {}", e));
 
         Ok(match signature_list.len() {
@@ -300,6 +460,64 @@ impl ResolveSelector for ResolvedFunction {
     }
 }
 
+/// The selector an event is looked up by: its topic0 as a full 32-byte word, without a `0x`
+/// prefix. Unlike [`crate::utils::strings::encode_hex_reduced`], leading zeros are kept, since
+/// event hashes often begin with them.
+///
+/// ```
+/// use ethers::types::U256;
+/// use heimdall_common::ether::signatures::event_selector;
+///
+/// assert_eq!(event_selector(&U256::from(0xabcd)), format!("{}abcd", "0".repeat(60)));
+/// ```
+pub fn event_selector(topic: &U256) -> String {
+    format!("{topic:064x}")
+}
+
+/// The selector a custom error is looked up by: its 4-byte selector, without a `0x` prefix. Leading
+/// zeros are kept, as with [`event_selector`].
+///
+/// ```
+/// use ethers::types::U256;
+/// use heimdall_common::ether::signatures::error_selector;
+///
+/// assert_eq!(error_selector(&U256::from(0x00ab1234)), "00ab1234");
+/// ```
+pub fn error_selector(selector: &U256) -> String {
+    format!("{selector:08x}")
+}
+
+/// Resolve the event signatures for a 32-byte topic0 hash, i.e. the first topic of a LOG1-LOG4.
+/// Matches whose signature doesn't hash to the topic are discarded, and anonymous events, which
+/// have no topic0, never resolve.
+pub async fn resolve_event_signature(topic: &U256) -> Option<Vec<ResolvedLog>> {
+    resolve_event_signatures(&[*topic]).await.remove(topic)
+}
+
+/// Resolve the event signatures for many topic0 hashes at once. See [`resolve_event_signature`].
+pub async fn resolve_event_signatures(topics: &[U256]) -> HashMap<U256, Vec<ResolvedLog>> {
+    let topics: Vec<U256> = topics.iter().filter(|topic| !topic.is_zero()).copied().collect();
+    let resolved: HashMap<String, Vec<ResolvedLog>> =
+        resolve_selectors(topics.iter().map(event_selector).collect()).await;
+
+    topics
+        .into_iter()
+        .filter_map(|topic| {
+            let matches: Vec<ResolvedLog> = resolved
+                .get(&event_selector(&topic))?
+                .iter()
+                .filter(|event| U256::from(keccak256(&event.signature)) == topic)
+                .cloned()
+                .collect();
+
+            match matches.is_empty() {
+                true => None,
+                false => Some((topic, matches)),
+            }
+        })
+        .collect()
+}
+
 pub fn score_signature(signature: &str) -> u32 {
     // the score starts at 1000
     let mut score = 1000;
@@ -316,10 +534,13 @@ pub fn score_signature(signature: &str) -> u32 {
 
 #[cfg(test)]
 mod tests {
-    use heimdall_cache::delete_cache;
+    use ethers::{types::U256, utils::keccak256};
+    use heimdall_cache::{delete_cache, store_cache};
 
     use crate::ether::signatures::{
-        score_signature, ResolveSelector, ResolvedError, ResolvedFunction, ResolvedLog,
+        cache_expiry, event_selector, resolve_event_signature, score_signature, ResolveSelector,
+        ResolvedError, ResolvedFunction, ResolvedLog, SignatureProvider, VerifiedAbi,
+        UNKNOWN_SELECTOR_TTL,
     };
 
     #[tokio::test]
@@ -418,4 +639,87 @@ mod tests {
             (signature.matches(|c: char| c.is_numeric()).count() as u32) * 3;
         assert_eq!(score, expected_score);
     }
+
+    #[test]
+    fn cache_expiry_should_expire_unknown_selectors_sooner() {
+        let now =
+            std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+
+        assert_eq!(cache_expiry(true), None);
+        assert!(cache_expiry(false)
+            .is_some_and(|expiry| expiry >= now && expiry <= now + UNKNOWN_SELECTOR_TTL + 1));
+    }
+
+    #[tokio::test]
+    async fn resolve_function_signature_should_use_prefetched_signatures() {
+        let selector = "c0ffee00";
+        let _ = delete_cache(&format!("selector.{selector}"));
+        store_cache(&format!("signatures.function.{selector}"), vec!["brew(uint256)"], None)
+            .unwrap();
+
+        let result = ResolvedFunction::resolve(selector).await.unwrap().unwrap();
+        assert_eq!(result[0].signature, "brew(uint256)");
+        assert_eq!(result[0].inputs, vec!["uint256"]);
+
+        let _ = delete_cache(&format!("selector.{selector}"));
+        let _ = delete_cache(&format!("signatures.function.{selector}"));
+    }
+
+    #[tokio::test]
+    async fn resolve_event_signature_should_discard_mismatched_hashes() {
+        let topic = U256::from(keccak256("Brewed(address,uint256)"));
+        let selector = event_selector(&topic);
+        let _ = delete_cache(&format!("selector.{selector}"));
+        store_cache(
+            &format!("signatures.event.{selector}"),
+            vec!["Brewed(address,uint256)", "Spilled(uint256)"],
+            None,
+        )
+        .unwrap();
+
+        let result = resolve_event_signature(&topic).await.unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].name, "Brewed");
+        assert_eq!(result[0].inputs, vec!["address", "uint256"]);
+
+        // anonymous events have no topic0 to resolve
+        assert_eq!(resolve_event_signature(&U256::zero()).await, None);
+
+        let _ = delete_cache(&format!("selector.{selector}"));
+        let _ = delete_cache(&format!("signatures.event.{selector}"));
+    }
+
+    #[tokio::test]
+    async fn verified_abi_should_resolve_functions_errors_and_events() {
+        let abi = VerifiedAbi::parse(
+            r#"[
+                {"type":"function","name":"approve","inputs":[{"name":"spender","type":"address"},{"name":"amount","type":"uint256"}],"outputs":[{"name":"","type":"bool"}],"stateMutability":"nonpayable"},
+                {"type":"error","name":"Unauthorized","inputs":[]},
+                {"type":"event","name":"Transfer","anonymous":false,"inputs":[{"name":"from","type":"address","indexed":true},{"name":"to","type":"address","indexed":true},{"name":"value","type":"uint256","indexed":false}]}
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            abi.lookup("function", "095ea7b3").await.unwrap(),
+            Some(vec![String::from("approve(address,uint256)")])
+        );
+        assert_eq!(
+            abi.lookup("function", "0x82b42900").await.unwrap(),
+            Some(vec![String::from("Unauthorized()")])
+        );
+        assert_eq!(
+            abi.lookup("event", "ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef")
+                .await
+                .unwrap(),
+            Some(vec![String::from("Transfer(address,address,uint256)")])
+        );
+
+        // selectors which aren't in the ABI are left for the next provider
+        assert_eq!(abi.lookup("function", "a9059cbb").await.unwrap(), None);
+        assert_eq!(
+            abi.lookup_bulk("function", &[String::from("a9059cbb")]).await.unwrap(),
+            Some(Default::default())
+        );
+    }
 }
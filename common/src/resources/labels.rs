@@ -0,0 +1,327 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+#[allow(deprecated)]
+use std::env::home_dir;
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    error::Error,
+    utils::io::{
+        file::{read_file, write_file},
+        redaction::redaction,
+    },
+};
+
+lazy_static! {
+    static ref LABELS: LabelStore = LabelStore::load().unwrap_or_default();
+}
+
+/// Human readable labels for addresses, selectors, and storage slots, kept in
+/// `$HOME/.bifrost/labels.toml` and used to enrich the output of every subcommand. Labels are
+/// user annotations, so none are returned while annotations are redacted.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LabelStore {
+    #[serde(default)]
+    pub addresses: BTreeMap<String, String>,
+    #[serde(default)]
+    pub selectors: BTreeMap<String, String>,
+    #[serde(default)]
+    pub slots: BTreeMap<String, String>,
+}
+
+/// A file format labels can be imported from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LabelFormat {
+    /// An Etherscan label or address book CSV export
+    Etherscan,
+    /// A Dune spellbook labels CSV, with `blockchain`, `address`, and `name` columns
+    Dune,
+    /// A Tenderly project's contracts, exported as JSON
+    Tenderly,
+    /// A TOML file in the same layout as the label store
+    Toml,
+}
+
+impl LabelFormat {
+    /// Parse a format from its name.
+    pub fn from_name(name: &str) -> Result<LabelFormat, Error> {
+        match name.to_lowercase().as_str() {
+            "etherscan" => Ok(LabelFormat::Etherscan),
+            "dune" => Ok(LabelFormat::Dune),
+            "tenderly" => Ok(LabelFormat::Tenderly),
+            "toml" => Ok(LabelFormat::Toml),
+            _ => Err(Error::Generic(format!("unknown label format '{name}'"))),
+        }
+    }
+
+    /// Guess the format of a file from its extension and contents.
+    pub fn detect(path: &str, contents: &str) -> LabelFormat {
+        let header = contents.lines().next().unwrap_or_default().to_lowercase();
+        match path.rsplit('.').next().map(|extension| extension.to_lowercase()).as_deref() {
+            Some("toml") => LabelFormat::Toml,
+            Some("json") => LabelFormat::Tenderly,
+            _ if header.contains("blockchain") => LabelFormat::Dune,
+            _ => LabelFormat::Etherscan,
+        }
+    }
+}
+
+/// Normalize a hex key to lowercase with a `0x` prefix. Slots are also left-padded to 32 bytes,
+/// so `0x0` and the full-width slot 0 share a label.
+fn normalize(key: &str, width: Option<usize>) -> String {
+    let key = key.trim().trim_matches('"').to_lowercase();
+    let key = key.strip_prefix("0x").unwrap_or(&key);
+    match width {
+        Some(width) => format!("0x{key:0>width$}"),
+        None => format!("0x{key}"),
+    }
+}
+
+/// Split a CSV line into its fields, handling quoted fields and escaped quotes.
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+
+    fields.into_iter().map(|field| field.trim().to_string()).collect()
+}
+
+/// Read address labels from a CSV, using the first column whose header matches each of the
+/// candidate names.
+fn parse_csv(contents: &str, name_columns: &[&str]) -> Result<LabelStore, Error> {
+    let mut lines = contents.lines().filter(|line| !line.trim().is_empty());
+    let header = split_csv_line(lines.next().unwrap_or_default())
+        .into_iter()
+        .map(|column| column.to_lowercase())
+        .collect::<Vec<String>>();
+
+    let address_column = header
+        .iter()
+        .position(|column| column.contains("address"))
+        .ok_or_else(|| Error::Generic("CSV has no address column".to_string()))?;
+    let name_column = name_columns
+        .iter()
+        .find_map(|name| header.iter().position(|column| column == name))
+        .ok_or_else(|| Error::Generic("CSV has no name column".to_string()))?;
+
+    let mut labels = LabelStore::default();
+    for fields in lines.map(split_csv_line) {
+        match (fields.get(address_column), fields.get(name_column)) {
+            (Some(address), Some(name)) if !address.is_empty() && !name.is_empty() => {
+                labels.addresses.insert(normalize(address, None), name.to_string());
+            }
+            _ => continue,
+        }
+    }
+
+    Ok(labels)
+}
+
+/// Read contract labels from a Tenderly export, which is either a list of contracts or an
+/// object with a `contracts` list.
+fn parse_tenderly(contents: &str) -> Result<LabelStore, Error> {
+    let json = serde_json::from_str::<Value>(contents)
+        .map_err(|e| Error::Generic(format!("failed to parse Tenderly export: {e}")))?;
+    let contracts = json
+        .as_array()
+        .or_else(|| json["contracts"].as_array())
+        .ok_or_else(|| Error::Generic("Tenderly export has no contracts".to_string()))?;
+
+    let mut labels = LabelStore::default();
+    for contract in contracts {
+        let address =
+            contract["address"].as_str().or_else(|| contract["contract"]["address"].as_str());
+        let name = ["display_name", "contract_name", "name"].iter().find_map(|field| {
+            contract[field]
+                .as_str()
+                .or_else(|| contract["contract"][field].as_str())
+                .filter(|name| !name.is_empty())
+        });
+
+        if let (Some(address), Some(name)) = (address, name) {
+            labels.addresses.insert(normalize(address, None), name.to_string());
+        }
+    }
+
+    Ok(labels)
+}
+
+impl LabelStore {
+    #[allow(deprecated)]
+    fn path() -> Result<PathBuf, Error> {
+        let mut path = home_dir().ok_or_else(|| {
+            Error::Generic(
+                "couldn't resolve the bifrost directory. Is your $HOME variable set correctly?"
+                    .to_string(),
+            )
+        })?;
+        path.push(".bifrost");
+        path.push("labels.toml");
+        Ok(path)
+    }
+
+    /// Load the label store, which is empty if it hasn't been created yet.
+    pub fn load() -> Result<LabelStore, Error> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(LabelStore::default())
+        }
+
+        let contents = read_file(&path.to_string_lossy());
+        toml::from_str(&contents)
+            .map_err(|e| Error::Generic(format!("failed to parse label store: {e}")))
+    }
+
+    /// Write the label store to disk.
+    pub fn save(&self) -> Result<(), Error> {
+        let contents = toml::to_string(self)
+            .map_err(|e| Error::Generic(format!("failed to serialize label store: {e}")))?;
+        write_file(&Self::path()?.to_string_lossy(), &contents);
+        Ok(())
+    }
+
+    /// Parse labels from the contents of a file in the given format. Keys are normalized.
+    ///
+    /// ```
+    /// use heimdall_common::resources::labels::{LabelFormat, LabelStore};
+    ///
+    /// let labels = LabelStore::parse("[slots]\n\"0x0\" = \"owner\"", LabelFormat::Toml).unwrap();
+    /// assert_eq!(
+    ///     labels.slots.get("0x0000000000000000000000000000000000000000000000000000000000000000"),
+    ///     Some(&"owner".to_string())
+    /// );
+    /// ```
+    pub fn parse(contents: &str, format: LabelFormat) -> Result<LabelStore, Error> {
+        match format {
+            LabelFormat::Etherscan => {
+                parse_csv(contents, &["name tag", "private name tag", "nametag", "label", "name"])
+            }
+            LabelFormat::Dune => parse_csv(contents, &["name"]),
+            LabelFormat::Tenderly => parse_tenderly(contents),
+            LabelFormat::Toml => {
+                let labels = toml::from_str::<LabelStore>(contents)
+                    .map_err(|e| Error::Generic(format!("failed to parse labels: {e}")))?;
+                Ok(LabelStore {
+                    addresses: labels
+                        .addresses
+                        .into_iter()
+                        .map(|(key, label)| (normalize(&key, None), label))
+                        .collect(),
+                    selectors: labels
+                        .selectors
+                        .into_iter()
+                        .map(|(key, label)| (normalize(&key, None), label))
+                        .collect(),
+                    slots: labels
+                        .slots
+                        .into_iter()
+                        .map(|(key, label)| (normalize(&key, Some(64)), label))
+                        .collect(),
+                })
+            }
+        }
+    }
+
+    /// Add the given labels, replacing any existing labels for the same keys. Returns the number
+    /// of labels which were added or changed.
+    pub fn merge(&mut self, other: LabelStore) -> usize {
+        let mut changed = 0;
+        for (current, new) in [
+            (&mut self.addresses, other.addresses),
+            (&mut self.selectors, other.selectors),
+            (&mut self.slots, other.slots),
+        ] {
+            for (key, label) in new {
+                if current.get(&key) != Some(&label) {
+                    current.insert(key, label);
+                    changed += 1;
+                }
+            }
+        }
+        changed
+    }
+
+    pub fn len(&self) -> usize {
+        self.addresses.len() + self.selectors.len() + self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// The stored label for an address, if any.
+pub fn get_address_label(address: &str) -> Option<String> {
+    LABELS.addresses.get(&normalize(address, None)).cloned().filter(|_| !redaction().annotations)
+}
+
+/// The stored label for a function, error, or event selector, if any.
+pub fn get_selector_label(selector: &str) -> Option<String> {
+    LABELS.selectors.get(&normalize(selector, None)).cloned().filter(|_| !redaction().annotations)
+}
+
+/// The stored label for a storage slot, if any.
+pub fn get_slot_label(slot: &str) -> Option<String> {
+    LABELS.slots.get(&normalize(slot, Some(64))).cloned().filter(|_| !redaction().annotations)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::resources::labels::*;
+
+    #[test]
+    fn test_parse_etherscan_csv() {
+        let contents =
+            "\"Address\",\"Name Tag\",\"Labels\"\n\"0xABC\",\"Uniswap: Router, V2\",\"dex\"\n";
+
+        let labels = LabelStore::parse(contents, LabelFormat::Etherscan).unwrap();
+        assert_eq!(labels.addresses.get("0xabc"), Some(&"Uniswap: Router, V2".to_string()));
+    }
+
+    #[test]
+    fn test_parse_dune_csv() {
+        let contents = "blockchain,address,name,category\nethereum,0xabc,Binance 14,cex\n";
+
+        assert_eq!(LabelFormat::detect("labels.csv", contents), LabelFormat::Dune);
+        let labels = LabelStore::parse(contents, LabelFormat::Dune).unwrap();
+        assert_eq!(labels.addresses.get("0xabc"), Some(&"Binance 14".to_string()));
+    }
+
+    #[test]
+    fn test_parse_tenderly_export() {
+        let contents = r#"[{"contract": {"address": "0xabc", "contract_name": "Vault"}}, {"address": "0xdef", "display_name": "Treasury"}]"#;
+
+        let labels = LabelStore::parse(contents, LabelFormat::Tenderly).unwrap();
+        assert_eq!(labels.addresses.get("0xabc"), Some(&"Vault".to_string()));
+        assert_eq!(labels.addresses.get("0xdef"), Some(&"Treasury".to_string()));
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut labels = LabelStore::default();
+        labels.addresses.insert("0xabc".to_string(), "Vault".to_string());
+
+        let mut other = labels.clone();
+        other.selectors.insert("0xa9059cbb".to_string(), "transfer(address,uint256)".to_string());
+
+        assert_eq!(labels.merge(other), 1);
+        assert_eq!(labels.len(), 2);
+    }
+}
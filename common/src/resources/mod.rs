@@ -1,2 +1,3 @@
+pub mod labels;
 pub mod openai;
 pub mod transpose;
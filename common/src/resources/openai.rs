@@ -1,4 +1,4 @@
-use crate::utils::io::logging::Logger;
+use crate::utils::{io::logging::Logger, offline::is_offline};
 use async_openai::{types::CreateCompletionRequestArgs, Client};
 
 /// Complete the given prompt using the OpenAI API.
@@ -14,6 +14,13 @@ pub async fn complete(prompt: &str, api_key: &str) -> Option<String> {
 
     // get a new logger
     let logger = Logger::default();
+    if is_offline() {
+        logger.error(
+            "explaining with OpenAI requires network access, which is disabled by `--offline` .",
+        );
+        return None
+    }
+
     let request = match CreateCompletionRequestArgs::default()
         .model("text-davinci-003")
         .prompt(prompt)
@@ -4,7 +4,16 @@ use reqwest::header::HeaderMap;
 use serde_json::Value;
 use std::time::{Duration, Instant};
 
-use crate::{debug_max, utils::io::logging::Logger};
+use crate::{
+    debug_max,
+    utils::{
+        io::{
+            exit::{exit_with, ExitStatus},
+            logging::Logger,
+        },
+        offline::is_offline,
+    },
+};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,6 +32,13 @@ struct TransposeResponse {
 
 /// executes a transpose SQL query and returns the response
 async fn call_transpose(query: &str, api_key: &str) -> Option<TransposeResponse> {
+    if is_offline() {
+        Logger::default().error(
+            "querying Transpose requires network access, which is disabled by `--offline` .",
+        );
+        return None
+    }
+
     backoff::future::retry(
         ExponentialBackoff {
             max_elapsed_time: Some(Duration::from_secs(10)),
@@ -129,7 +145,7 @@ pub async fn get_transaction_list(
         Some(response) => response,
         None => {
             logger.error("failed to get transaction list from Transpose");
-            std::process::exit(1)
+            exit_with(ExitStatus::NetworkFailure, "failed to get transaction list from Transpose")
         }
     };
 
@@ -145,12 +161,18 @@ pub async fn get_transaction_list(
                 Some(block_number) => block_number as u128,
                 None => {
                     logger.error("failed to parse block_number from Transpose");
-                    std::process::exit(1)
+                    exit_with(
+                        ExitStatus::NetworkFailure,
+                        "failed to parse block_number from Transpose",
+                    )
                 }
             },
             None => {
                 logger.error("failed to fetch block_number from Transpose response");
-                std::process::exit(1)
+                exit_with(
+                    ExitStatus::NetworkFailure,
+                    "failed to fetch block_number from Transpose response",
+                )
             }
         };
         let transaction_hash: String = match result.get("transaction_hash") {
@@ -158,12 +180,18 @@ pub async fn get_transaction_list(
                 Some(transaction_hash) => transaction_hash.to_string(),
                 None => {
                     logger.error("failed to parse transaction_hash from Transpose");
-                    std::process::exit(1)
+                    exit_with(
+                        ExitStatus::NetworkFailure,
+                        "failed to parse transaction_hash from Transpose",
+                    )
                 }
             },
             None => {
                 logger.error("failed to fetch transaction_hash from Transpose response");
-                std::process::exit(1)
+                exit_with(
+                    ExitStatus::NetworkFailure,
+                    "failed to fetch transaction_hash from Transpose response",
+                )
             }
         };
 
@@ -211,7 +239,7 @@ pub async fn get_contract_creation(
         Some(response) => response,
         None => {
             logger.error("failed to get creation tx from Transpose");
-            std::process::exit(1)
+            exit_with(ExitStatus::NetworkFailure, "failed to get creation tx from Transpose")
         }
     };
 
@@ -225,12 +253,18 @@ pub async fn get_contract_creation(
                 Some(block_number) => block_number as u128,
                 None => {
                     logger.error("failed to parse block_number from Transpose");
-                    std::process::exit(1)
+                    exit_with(
+                        ExitStatus::NetworkFailure,
+                        "failed to parse block_number from Transpose",
+                    )
                 }
             },
             None => {
                 logger.error("failed to fetch block_number from Transpose response");
-                std::process::exit(1)
+                exit_with(
+                    ExitStatus::NetworkFailure,
+                    "failed to fetch block_number from Transpose response",
+                )
             }
         };
         let transaction_hash: String = match result.get("transaction_hash") {
@@ -238,12 +272,18 @@ pub async fn get_contract_creation(
                 Some(transaction_hash) => transaction_hash.to_string(),
                 None => {
                     logger.error("failed to parse transaction_hash from Transpose");
-                    std::process::exit(1)
+                    exit_with(
+                        ExitStatus::NetworkFailure,
+                        "failed to parse transaction_hash from Transpose",
+                    )
                 }
             },
             None => {
                 logger.error("failed to fetch transaction_hash from Transpose response");
-                std::process::exit(1)
+                exit_with(
+                    ExitStatus::NetworkFailure,
+                    "failed to fetch transaction_hash from Transpose response",
+                )
             }
         };
 
@@ -1,4 +1,4 @@
-use crate::debug_max;
+use crate::{debug_max, utils::offline::is_offline};
 use async_recursion::async_recursion;
 use reqwest::Client;
 use serde_json::Value;
@@ -7,7 +7,8 @@ use tokio::time::sleep as async_sleep;
 
 static APP_USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"),);
 
-/// Make a GET request to the target URL and return the response body as JSON
+/// Make a GET request to the target URL and return the response body as JSON. Returns `None`
+/// without making a request in offline mode.
 ///
 /// ```no_run
 /// use heimdall_common::utils::http::get_json_from_url;
@@ -31,6 +32,12 @@ async fn _get_json_from_url(
 ) -> Result<Option<Value>, reqwest::Error> {
     debug_max!("GET {}", &url);
 
+    // offline mode never makes requests, so callers fall back to their caches
+    if is_offline() {
+        debug_max!("GET {}: skipped, `--offline` is set", &url);
+        return Ok(None)
+    }
+
     let client = Client::builder()
         .danger_accept_invalid_certs(true)
         .user_agent(APP_USER_AGENT)
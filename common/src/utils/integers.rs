@@ -1,3 +1,5 @@
+use ethers::types::U256;
+
 pub trait ToLocaleString {
     fn to_locale_string(&self) -> String;
 }
@@ -25,9 +27,95 @@ impl ToLocaleString for usize {
     }
 }
 
+/// The longest duration, in seconds, which is rendered as one. Longer multiples of an hour are
+/// more likely to be amounts than durations.
+const MAX_DURATION: u64 = 10 * 365 * 86400;
+
+/// A readable form of a constant, if it has one. Checked in order:
+/// - `2^n - 1`, for widths of at least 64 bits, as `type(uintN).max`
+/// - multiples of an hour up to 10 years as durations, e.g. `30 days` or `12 hours`
+/// - numbers of at least a million with at most three significant digits in scientific notation,
+///   e.g. `1e18` or `2.5e6`
+///
+/// ```
+/// use ethers::types::U256;
+/// use heimdall_common::utils::integers::format_literal;
+///
+/// assert_eq!(format_literal(U256::exp10(18)), Some(String::from("1e18")));
+/// assert_eq!(format_literal(U256::MAX), Some(String::from("type(uint256).max")));
+/// assert_eq!(format_literal(U256::from(2592000)), Some(String::from("30 days")));
+/// assert_eq!(format_literal(U256::from(1234)), None);
+/// ```
+pub fn format_literal(value: U256) -> Option<String> {
+    if let Some(bits) = (64..=256usize).step_by(8).find(|bits| value == U256::MAX >> (256 - bits)) {
+        return Some(format!("type(uint{bits}).max"))
+    }
+
+    if value >= U256::from(3600) && value <= U256::from(MAX_DURATION) {
+        let seconds = value.as_u64();
+        if seconds.is_multiple_of(86400) {
+            return Some(format!("{} days", seconds / 86400))
+        }
+        if seconds.is_multiple_of(3600) {
+            return Some(format!("{} hours", seconds / 3600))
+        }
+    }
+
+    if value >= U256::exp10(6) {
+        let digits = value.to_string();
+        let significant = digits.trim_end_matches('0');
+        if significant.len() <= 3 {
+            let exponent = digits.len() - 1;
+            return Some(match significant.split_at(1) {
+                (whole, "") => format!("{whole}e{exponent}"),
+                (whole, fraction) => format!("{whole}.{fraction}e{exponent}"),
+            })
+        }
+    }
+
+    None
+}
+
+/// The percentage a number of basis points is, e.g. `0.3` for 30.
+pub fn basis_points_to_percent(bps: U256) -> String {
+    let whole = bps / 100;
+    let fraction = (bps % 100).as_u64();
+    match fraction {
+        0 => whole.to_string(),
+        _ => format!("{whole}.{}", format!("{fraction:02}").trim_end_matches('0')),
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::utils::integers::ToLocaleString;
+    use ethers::types::U256;
+
+    use crate::utils::integers::{basis_points_to_percent, format_literal, ToLocaleString};
+
+    #[test]
+    fn test_format_literal() {
+        assert_eq!(format_literal(U256::from(1_000_000)), Some(String::from("1e6")));
+        assert_eq!(format_literal(U256::exp10(17) * 25), Some(String::from("2.5e18")));
+        assert_eq!(format_literal(U256::exp10(16) * 125), Some(String::from("1.25e18")));
+        assert_eq!(format_literal(U256::exp10(16) * 1234), None);
+        assert_eq!(format_literal(U256::from(999_999)), None);
+        assert_eq!(format_literal(U256::from(u128::MAX)), Some(String::from("type(uint128).max")));
+        assert_eq!(format_literal(U256::MAX >> 96), Some(String::from("type(uint160).max")));
+        assert_eq!(format_literal(U256::from(0xff)), None);
+        assert_eq!(format_literal(U256::from(86400)), Some(String::from("1 days")));
+        assert_eq!(format_literal(U256::from(604800)), Some(String::from("7 days")));
+        assert_eq!(format_literal(U256::from(7200)), Some(String::from("2 hours")));
+        assert_eq!(format_literal(U256::from(60)), None);
+        assert_eq!(format_literal(U256::zero()), None);
+    }
+
+    #[test]
+    fn test_basis_points_to_percent() {
+        assert_eq!(basis_points_to_percent(U256::from(30)), "0.3");
+        assert_eq!(basis_points_to_percent(U256::from(250)), "2.5");
+        assert_eq!(basis_points_to_percent(U256::from(5)), "0.05");
+        assert_eq!(basis_points_to_percent(U256::from(10000)), "100");
+    }
 
     #[test]
     fn test_to_locale_string() {
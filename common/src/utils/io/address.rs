@@ -0,0 +1,120 @@
+use std::sync::RwLock;
+
+use ethers::{types::H160, utils::to_checksum};
+use lazy_static::lazy_static;
+
+use crate::{constants::EMBEDDED_ADDRESS_REGEX, error::Error};
+
+lazy_static! {
+    static ref ADDRESS_FORMAT: RwLock<AddressFormat> = RwLock::new(AddressFormat::default());
+}
+
+/// How addresses are written in output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AddressFormat {
+    /// EIP-55 mixed-case checksummed, e.g. `0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2`
+    #[default]
+    Checksum,
+    /// All lowercase, e.g. `0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2`
+    Lowercase,
+    /// Checksummed, keeping only the first and last four digits, e.g. `0xC02a...6Cc2`
+    Short,
+}
+
+impl AddressFormat {
+    /// Parse `checksum`, `lowercase`, or `short`.
+    ///
+    /// ```
+    /// use heimdall_common::utils::io::address::AddressFormat;
+    ///
+    /// assert_eq!(AddressFormat::parse("short").unwrap(), AddressFormat::Short);
+    /// assert!(AddressFormat::parse("uppercase").is_err());
+    /// ```
+    pub fn parse(value: &str) -> Result<AddressFormat, Error> {
+        match value.trim() {
+            "checksum" => Ok(AddressFormat::Checksum),
+            "lowercase" => Ok(AddressFormat::Lowercase),
+            "short" => Ok(AddressFormat::Short),
+            _ => Err(Error::Generic(format!(
+                "unknown address format '{value}' . expected one of: checksum, lowercase, short."
+            ))),
+        }
+    }
+}
+
+/// Set how addresses are written in output.
+pub fn set_address_format(format: AddressFormat) {
+    if let Ok(mut current) = ADDRESS_FORMAT.write() {
+        *current = format;
+    }
+}
+
+/// How addresses are currently written in output.
+pub fn address_format() -> AddressFormat {
+    ADDRESS_FORMAT.read().map(|format| *format).unwrap_or_default()
+}
+
+/// Write an address in the current [`AddressFormat`].
+///
+/// ```
+/// use ethers::types::H160;
+/// use heimdall_common::utils::io::address::format_address;
+///
+/// let address = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2".parse::<H160>().unwrap();
+/// assert_eq!(format_address(&address), "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2");
+/// ```
+pub fn format_address(address: &H160) -> String {
+    let checksummed = to_checksum(address, None);
+    match address_format() {
+        AddressFormat::Checksum => checksummed,
+        AddressFormat::Lowercase => format!("{address:#x}"),
+        AddressFormat::Short => format!("{}...{}", &checksummed[..6], &checksummed[38..]),
+    }
+}
+
+/// Rewrite every address in a report in the current [`AddressFormat`], so that every output
+/// writes addresses the same way. Hex strings longer than an address are left alone, as are
+/// 160-bit masks, which aren't addresses.
+pub fn format_addresses(report: &str) -> String {
+    EMBEDDED_ADDRESS_REGEX
+        .replace_all(report, |captures: &fancy_regex::Captures| {
+            let address = &captures[0];
+            match address[2..].eq_ignore_ascii_case(&"f".repeat(40)) {
+                true => address.to_string(),
+                false => address
+                    .parse::<H160>()
+                    .map(|address| format_address(&address))
+                    .unwrap_or_else(|_| address.to_string()),
+            }
+        })
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::io::address::*;
+
+    #[test]
+    fn test_format_addresses() {
+        let report = "{\"to\": \"0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2\", \"mask\": \
+                      \"0xffffffffffffffffffffffffffffffffffffffff\", \"slot\": \
+                      \"0x000000000000000000000000c02aaa39b223fe8d0a0e5c4f27ead9083c756cc2\"}";
+        let formatted = format_addresses(report);
+
+        // the format is global, so each is checked in turn
+        set_address_format(AddressFormat::Lowercase);
+        let lowercase = format_addresses(&formatted);
+        set_address_format(AddressFormat::Short);
+        let short = format_addresses(&formatted);
+        set_address_format(AddressFormat::Checksum);
+
+        assert_eq!(
+            formatted,
+            "{\"to\": \"0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2\", \"mask\": \
+             \"0xffffffffffffffffffffffffffffffffffffffff\", \"slot\": \
+             \"0x000000000000000000000000c02aaa39b223fe8d0a0e5c4f27ead9083c756cc2\"}"
+        );
+        assert_eq!(lowercase, report);
+        assert!(short.starts_with("{\"to\": \"0xC02a...6Cc2\""));
+    }
+}
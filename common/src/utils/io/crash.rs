@@ -0,0 +1,186 @@
+use std::{
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use ethers::utils::keccak256;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    constants::URL_REGEX,
+    error::Error,
+    utils::{
+        io::file::write_file,
+        strings::{decode_hex, encode_hex},
+        version::current_version,
+    },
+};
+
+/// The maximum number of passes kept in the pass history of a [`CrashContext`].
+const MAX_PASS_HISTORY: usize = 32;
+
+lazy_static! {
+    static ref CRASH_CONTEXT: Mutex<CrashContext> = Mutex::new(CrashContext::default());
+}
+
+/// The [`CrashContext`] is recorded while heimdall runs, so that a [`CrashReport`] can be built
+/// if a pass panics or fails.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CrashContext {
+    /// The subcommand being executed, i.e. `decompile`
+    pub command: String,
+    /// The keccak256 hash of the bytecode being analyzed, if any
+    pub bytecode_hash: Option<String>,
+    /// The passes entered so far, oldest first
+    pub passes: Vec<String>,
+}
+
+/// A redacted report describing a failure, which can be attached to an issue. \
+/// \
+/// Reports never contain the analyzed bytecode, RPC URLs, or API keys. The bytecode is identified
+/// only by its hash.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrashReport {
+    pub version: String,
+    pub timestamp: u64,
+    pub command: String,
+    pub bytecode_hash: Option<String>,
+    pub failing_pass: Option<String>,
+    pub pass_history: Vec<String>,
+    pub message: String,
+    pub trace: Vec<String>,
+}
+
+/// Set the subcommand recorded in the crash context.
+pub fn set_crash_command(command: &str) {
+    if let Ok(mut context) = CRASH_CONTEXT.lock() {
+        context.command = command.to_string();
+    }
+}
+
+/// Record the bytecode under analysis in the crash context. Only its hash is kept.
+///
+/// ```
+/// use heimdall_common::utils::io::crash::{crash_context, set_crash_bytecode};
+///
+/// set_crash_bytecode("0x00");
+/// assert_eq!(
+///     crash_context().bytecode_hash,
+///     Some("0xbc36789e7a1e281436464229828f817d6612f7b477d66591ff96a9e064bcc98a".to_string())
+/// );
+/// ```
+pub fn set_crash_bytecode(bytecode: &str) {
+    let bytes = decode_hex(&bytecode.replacen("0x", "", 1)).unwrap_or_default();
+    if let Ok(mut context) = CRASH_CONTEXT.lock() {
+        context.bytecode_hash = Some(format!("0x{}", encode_hex(keccak256(bytes).to_vec())));
+    }
+}
+
+/// Mark the start of a new analysis pass, i.e. `symbolic execution (0x06fdde03)`.
+pub fn enter_pass(pass: &str) {
+    if let Ok(mut context) = CRASH_CONTEXT.lock() {
+        if context.passes.len() >= MAX_PASS_HISTORY {
+            context.passes.remove(0);
+        }
+        context.passes.push(pass.to_string());
+    }
+}
+
+/// Get a copy of the current crash context.
+pub fn crash_context() -> CrashContext {
+    match CRASH_CONTEXT.lock() {
+        Ok(context) => context.clone(),
+        Err(poisoned) => poisoned.into_inner().clone(),
+    }
+}
+
+/// Redact URLs and the user's home directory from the given string.
+///
+/// ```
+/// use heimdall_common::utils::io::crash::redact;
+///
+/// assert_eq!(
+///     redact("failed to connect to RPC provider 'https://eth.llamarpc.com/key' ."),
+///     "failed to connect to RPC provider '<redacted>' ."
+/// );
+/// ```
+pub fn redact(message: &str) -> String {
+    let mut redacted = URL_REGEX.replace_all(message, "<redacted>").to_string();
+
+    if let Some(home) = std::env::var_os("HOME").and_then(|home| home.into_string().ok()) {
+        if home.len() > 1 {
+            redacted = redacted.replace(&home, "~");
+        }
+    }
+
+    redacted
+}
+
+impl CrashReport {
+    /// Build a new [`CrashReport`] from the current crash context, the failure message, and the
+    /// frames of the reproducing trace.
+    pub fn new(message: &str, trace: Vec<String>) -> CrashReport {
+        let context = crash_context();
+
+        CrashReport {
+            version: current_version().to_string(),
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or(0),
+            command: context.command,
+            bytecode_hash: context.bytecode_hash,
+            failing_pass: context.passes.last().cloned(),
+            pass_history: context.passes,
+            message: redact(message),
+            trace: trace.iter().map(|frame| redact(frame)).collect(),
+        }
+    }
+
+    /// Write the report as JSON to `{directory}/heimdall-crash-{timestamp}.json`, returning the
+    /// path of the written file.
+    pub fn write(&self, directory: &str) -> Result<String, Error> {
+        let contents = serde_json::to_string_pretty(self)
+            .map_err(|e| Error::Generic(format!("failed to serialize crash report: {}", e)))?;
+
+        Ok(write_file(
+            &format!("{}/heimdall-crash-{}.json", directory.trim_end_matches('/'), self.timestamp),
+            &contents,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::io::crash::*;
+
+    #[test]
+    fn test_redact_removes_urls() {
+        let message = "failed to fetch bytecode from 'http://localhost:8545' .";
+        assert_eq!(redact(message), "failed to fetch bytecode from '<redacted>' .");
+    }
+
+    #[test]
+    fn test_crash_report_uses_context() {
+        set_crash_command("decompile");
+        enter_pass("disassemble");
+        enter_pass("symbolic execution");
+
+        let report = CrashReport::new("index out of bounds", vec!["frame".to_string()]);
+
+        assert_eq!(report.command, "decompile");
+        assert_eq!(report.failing_pass, Some("symbolic execution".to_string()));
+        assert_eq!(report.message, "index out of bounds");
+        assert_eq!(report.version, current_version().to_string());
+    }
+
+    #[test]
+    fn test_pass_history_is_bounded() {
+        for i in 0..(MAX_PASS_HISTORY + 8) {
+            enter_pass(&format!("pass {i}"));
+        }
+
+        assert!(crash_context().passes.len() <= MAX_PASS_HISTORY);
+    }
+}
@@ -0,0 +1,168 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Mutex,
+};
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::io::crash::{crash_context, redact};
+
+lazy_static! {
+    static ref FAILURE: Mutex<Option<ExitStatus>> = Mutex::new(None);
+}
+
+static PARTIAL_ANALYSIS: AtomicBool = AtomicBool::new(false);
+static JSON_ERRORS: AtomicBool = AtomicBool::new(false);
+
+/// The status heimdall exits with. Each status has a distinct, stable exit code, so scripts can
+/// tell why a run failed without parsing its output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExitStatus {
+    /// The run completed
+    Success,
+    /// An unexpected error, which may be worth reporting
+    InternalError,
+    /// The arguments were invalid. clap also uses this code for usage errors
+    InvalidArguments,
+    /// An RPC provider or remote API couldn't be reached, or returned an error
+    NetworkFailure,
+    /// The target isn't bytecode heimdall can analyze
+    UnsupportedBytecode,
+    /// The run completed, but part of the analysis was skipped, i.e. a function timed out
+    PartialAnalysis,
+    /// The run completed, but a post-condition given with `--assert` didn't hold
+    AssertionFailure,
+}
+
+impl ExitStatus {
+    /// The process exit code for this status.
+    ///
+    /// ```
+    /// use heimdall_common::utils::io::exit::ExitStatus;
+    ///
+    /// assert_eq!(ExitStatus::Success.code(), 0);
+    /// assert_eq!(ExitStatus::NetworkFailure.code(), 3);
+    /// ```
+    pub fn code(&self) -> i32 {
+        match self {
+            ExitStatus::Success => 0,
+            ExitStatus::InternalError => 1,
+            ExitStatus::InvalidArguments => 2,
+            ExitStatus::NetworkFailure => 3,
+            ExitStatus::UnsupportedBytecode => 4,
+            ExitStatus::PartialAnalysis => 5,
+            ExitStatus::AssertionFailure => 6,
+        }
+    }
+}
+
+/// Record why the current run is failing. The first failure recorded is kept, since later
+/// failures are usually a consequence of it.
+pub fn record_failure(status: ExitStatus) {
+    if let Ok(mut failure) = FAILURE.lock() {
+        failure.get_or_insert(status);
+    }
+}
+
+/// Record that part of the analysis was skipped.
+pub fn record_partial_analysis() {
+    PARTIAL_ANALYSIS.store(true, Ordering::SeqCst);
+}
+
+/// Forget the failures and skipped analysis recorded so far, so a new run in the same process is
+/// reported on its own.
+pub fn reset_status() {
+    if let Ok(mut failure) = FAILURE.lock() {
+        *failure = None;
+    }
+    PARTIAL_ANALYSIS.store(false, Ordering::SeqCst);
+}
+
+/// The status to exit with after a run fails. Failures which weren't recorded are internal
+/// errors.
+pub fn failure_status() -> ExitStatus {
+    FAILURE.lock().ok().and_then(|failure| *failure).unwrap_or(ExitStatus::InternalError)
+}
+
+/// The status to exit with after a run completes.
+pub fn success_status() -> ExitStatus {
+    match PARTIAL_ANALYSIS.load(Ordering::SeqCst) {
+        true => ExitStatus::PartialAnalysis,
+        false => ExitStatus::Success,
+    }
+}
+
+/// A machine-readable description of why a run failed. Its fields are stable, so it can be
+/// consumed by other tools.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorReport {
+    pub status: ExitStatus,
+    pub code: i32,
+    pub command: String,
+    pub message: String,
+}
+
+impl ErrorReport {
+    /// Build a report for the current subcommand. URLs are removed from the message.
+    pub fn new(status: ExitStatus, message: &str) -> ErrorReport {
+        ErrorReport {
+            status,
+            code: status.code(),
+            command: crash_context().command,
+            message: redact(message),
+        }
+    }
+}
+
+/// Enable or disable writing an [`ErrorReport`] to stderr when a run fails.
+pub fn set_json_errors(enabled: bool) {
+    JSON_ERRORS.store(enabled, Ordering::SeqCst);
+}
+
+/// Write an [`ErrorReport`] to stderr if JSON errors are enabled.
+pub fn report_error(status: ExitStatus, message: &str) {
+    if !JSON_ERRORS.load(Ordering::SeqCst) {
+        return
+    }
+    if let Ok(report) = serde_json::to_string(&ErrorReport::new(status, message)) {
+        eprintln!("{report}");
+    }
+}
+
+/// Exit immediately with the given status, for failures which can't be returned to the caller.
+pub fn exit_with(status: ExitStatus, message: &str) -> ! {
+    record_failure(status);
+    report_error(status, message);
+    std::process::exit(status.code())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::io::exit::*;
+
+    #[test]
+    fn test_first_failure_is_kept() {
+        record_failure(ExitStatus::NetworkFailure);
+        record_failure(ExitStatus::UnsupportedBytecode);
+
+        assert_eq!(failure_status(), ExitStatus::NetworkFailure);
+    }
+
+    #[test]
+    fn test_error_report_json() {
+        let report = ErrorReport::new(
+            ExitStatus::UnsupportedBytecode,
+            "file 'a.bin' doesn't contain valid bytecode.",
+        );
+
+        assert_eq!(
+            serde_json::to_string(&report).unwrap(),
+            format!(
+                "{{\"status\":\"unsupported_bytecode\",\"code\":4,\"command\":\"{}\",\"message\":\"file 'a.bin' doesn't contain valid bytecode.\"}}",
+                report.command
+            )
+        );
+    }
+}
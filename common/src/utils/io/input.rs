@@ -0,0 +1,121 @@
+use std::{
+    fs,
+    io::{stdin, Read},
+    path::Path,
+};
+
+use serde_json::Value;
+
+use crate::{constants::BYTECODE_REGEX, error::Error, ether::artifact::artifact_bytecode};
+
+/// Read a target, which may be given inline, as the path to a file, or as `-` to read it from
+/// stdin. Whichever way it's given, the target is normalized with [`normalize_input`].
+///
+/// ```no_run
+/// use heimdall_common::utils::io::input::read_input;
+///
+/// let target = read_input("-").unwrap();
+/// ```
+pub fn read_input(target: &str) -> Result<String, Error> {
+    let contents = if target == "-" {
+        let mut contents = String::new();
+        stdin()
+            .read_to_string(&mut contents)
+            .map_err(|e| Error::IOError(format!("failed to read stdin: {e}")))?;
+        contents
+    } else if Path::new(target).is_file() {
+        fs::read_to_string(target)
+            .map_err(|e| Error::IOError(format!("failed to read file '{target}': {e}")))?
+    } else {
+        return Ok(normalize_input(target))
+    };
+
+    // a target read from a file or stdin should be a single value
+    let normalized = normalize_input(&contents);
+    if normalized.is_empty() || normalized.contains(char::is_whitespace) {
+        return Err(Error::Generic(format!(
+            "'{target}' doesn't contain bytecode, calldata, or an address."
+        )))
+    }
+
+    Ok(normalized)
+}
+
+/// Normalize a target, so it's recognized however it was copied or piped:
+///
+///  - surrounding whitespace and quotes are removed,
+///  - whitespace within hex, such as line breaks, is removed,
+///  - a compiler artifact is replaced by its deployed bytecode.
+///
+/// ```
+/// use heimdall_common::utils::io::input::normalize_input;
+///
+/// assert_eq!(normalize_input(" 0x6080\n6040\n"), "0x60806040");
+/// assert_eq!(normalize_input(r#"{"deployedBytecode": {"object": "0x6080"}}"#), "0x6080");
+/// ```
+pub fn normalize_input(input: &str) -> String {
+    let input = input.trim();
+
+    // hardhat, foundry, and solc artifacts all include the contract's bytecode
+    if input.starts_with('{') {
+        if let Some(bytecode) =
+            serde_json::from_str::<Value>(input).ok().as_ref().and_then(artifact_bytecode)
+        {
+            return bytecode.trim().to_string()
+        }
+    }
+
+    let input = input.strip_prefix('"').and_then(|input| input.strip_suffix('"')).unwrap_or(input);
+    let compact = input.chars().filter(|c| !c.is_whitespace()).collect::<String>();
+    match BYTECODE_REGEX.is_match(&compact).unwrap_or(false) {
+        true => compact,
+        false => input.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::io::input::*;
+
+    #[test]
+    fn test_normalize_hex() {
+        assert_eq!(normalize_input("0x6080"), "0x6080");
+        assert_eq!(normalize_input("  6080 6040\r\n60 00\n"), "608060406000");
+        assert_eq!(normalize_input("\"0x6080\"\n"), "0x6080");
+        assert_eq!(normalize_input("vitalik.eth\n"), "vitalik.eth");
+    }
+
+    #[test]
+    fn test_normalize_artifacts() {
+        // foundry
+        assert_eq!(
+            normalize_input(
+                r#"{"bytecode": {"object": "0x6001"}, "deployedBytecode": {"object": "0x6002"}}"#
+            ),
+            "0x6002"
+        );
+
+        // hardhat
+        assert_eq!(
+            normalize_input(r#"{"bytecode": "0x6001", "deployedBytecode": "0x"}"#),
+            "0x6001"
+        );
+
+        // solc
+        assert_eq!(normalize_input(r#"{"evm": {"deployedBytecode": {"object": "6002"}}}"#), "6002");
+    }
+
+    #[test]
+    fn test_read_input_from_file() {
+        let path = std::env::temp_dir().join("heimdall-test-read-input.hex");
+        fs::write(&path, "0x6080\n6040\n").unwrap();
+        assert_eq!(read_input(&path.to_string_lossy()).unwrap(), "0x60806040");
+
+        fs::write(&path, "not bytecode\n").unwrap();
+        assert!(read_input(&path.to_string_lossy()).is_err());
+        fs::remove_file(&path).unwrap();
+
+        // anything which isn't a file is taken as given
+        assert_eq!(read_input("0x6080").unwrap(), "0x6080");
+    }
+}
@@ -0,0 +1,190 @@
+use std::{collections::HashMap, sync::RwLock};
+
+use lazy_static::lazy_static;
+
+use crate::error::Error;
+
+lazy_static! {
+    static ref MESSAGES: RwLock<HashMap<String, String>> = RwLock::new(HashMap::new());
+}
+
+/// The English text of every message reports are written with, by key. Values may contain
+/// `{name}` placeholders, which are filled in by [`format_message`].
+pub const DEFAULT_MESSAGES: &[(&str, &str)] = &[
+    // snapshot governance report
+    ("governance.title", "Governance"),
+    ("governance.timelock.title", "Timelock"),
+    ("governance.timelock.queued_by", "Queued by"),
+    ("governance.timelock.executed_by", "Executed by"),
+    ("governance.timelock.delays", "Delay checks"),
+    ("governance.timelock.none", "No timelock was found."),
+    ("governance.risks.title", "Centralization risks"),
+    ("governance.risks.none", "No centralization risks were found."),
+    ("governance.risks.controlled_by", "Controlled by {controllers}."),
+    ("governance.functions.title", "Admin-controlled functions"),
+    ("governance.functions.none", "No admin-only state-changing functions were found."),
+    ("governance.functions.function", "Function"),
+    ("governance.functions.callers", "Callers"),
+    ("governance.functions.writes", "Writes"),
+    ("governance.functions.delay", "Delay"),
+    ("governance.functions.external_calls_only", "external calls only"),
+    ("governance.delay.timelock", "timelock"),
+    ("governance.delay.self", "only the contract itself"),
+    ("governance.delay.none", "none"),
+    // centralization risk labels and descriptions
+    ("risk.pause.label", "pause"),
+    ("risk.pause.description", "can block {functions}"),
+    ("risk.withdrawal.label", "withdrawal"),
+    ("risk.withdrawal.description", "can move ether or tokens out of the contract"),
+    ("risk.self_destruct.label", "self_destruct"),
+    ("risk.self_destruct.description", "can destroy the contract"),
+    ("risk.unbounded_fee.label", "unbounded_fee"),
+    (
+        "risk.unbounded_fee.description",
+        "sets a fee without checking it against an upper bound, so it can be set to 100%",
+    ),
+    // snapshot funds report
+    ("funds.title", "Funds at risk"),
+    (
+        "funds.summary",
+        "Balances at block {block}. Ether and {tokens} tokens were checked, and only tokens with a balance are listed.",
+    ),
+    ("funds.asset", "Asset"),
+    ("funds.address", "Address"),
+    ("funds.balance", "Balance"),
+    // snapshot revert reasons report
+    ("reverts.title", "Revert reasons"),
+    ("reverts.none", "No revert messages or custom errors were found."),
+    ("reverts.reason", "Reason"),
+    ("reverts.functions", "Functions"),
+];
+
+/// Replace the messages reports are written with. Keys which aren't given keep their English
+/// text from [`DEFAULT_MESSAGES`].
+pub fn set_messages(messages: HashMap<String, String>) -> Result<(), Error> {
+    if let Some(key) =
+        messages.keys().find(|key| !DEFAULT_MESSAGES.iter().any(|(default, _)| default == key))
+    {
+        return Err(Error::Generic(format!("unknown message '{key}'")))
+    }
+
+    if let Ok(mut current) = MESSAGES.write() {
+        *current = messages;
+    }
+    Ok(())
+}
+
+/// Load a message catalog from a TOML file, i.e. `governance.title = "Gouvernance"`, and use
+/// it for every report.
+pub fn load_messages(path: &str) -> Result<(), Error> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| Error::IOError(format!("failed to read message catalog '{path}': {e}")))?;
+    let table = contents
+        .parse::<toml::Table>()
+        .map_err(|e| Error::Generic(format!("failed to parse message catalog '{path}': {e}")))?;
+
+    let mut messages = HashMap::new();
+    flatten_messages("", &toml::Value::Table(table), &mut messages)?;
+    set_messages(messages)
+}
+
+/// Flatten a table of messages into dotted keys, so a catalog can use either tables or dotted
+/// keys.
+fn flatten_messages(
+    prefix: &str,
+    value: &toml::Value,
+    messages: &mut HashMap<String, String>,
+) -> Result<(), Error> {
+    match value {
+        toml::Value::Table(table) => {
+            for (key, value) in table {
+                let key = match prefix.is_empty() {
+                    true => key.to_string(),
+                    false => format!("{prefix}.{key}"),
+                };
+                flatten_messages(&key, value, messages)?;
+            }
+        }
+        toml::Value::String(message) => {
+            messages.insert(prefix.to_string(), message.to_string());
+        }
+        _ => return Err(Error::Generic(format!("message '{prefix}' must be a string"))),
+    }
+
+    Ok(())
+}
+
+/// The text of a message, from the loaded catalog if it has one, otherwise in English.
+///
+/// ```
+/// use heimdall_common::utils::io::messages::message;
+///
+/// assert_eq!(message("funds.title"), "Funds at risk");
+/// ```
+pub fn message(key: &str) -> String {
+    if let Some(message) = MESSAGES.read().ok().and_then(|messages| messages.get(key).cloned()) {
+        return message
+    }
+
+    DEFAULT_MESSAGES
+        .iter()
+        .find(|(default, _)| *default == key)
+        .map(|(_, message)| message.to_string())
+        .unwrap_or_else(|| key.to_string())
+}
+
+/// The text of a message, with each `{name}` placeholder replaced by its value.
+///
+/// ```
+/// use heimdall_common::utils::io::messages::format_message;
+///
+/// assert_eq!(
+///     format_message("governance.risks.controlled_by", &[("controllers", "`storage[0]`")]),
+///     "Controlled by `storage[0]`."
+/// );
+/// ```
+pub fn format_message(key: &str, values: &[(&str, &str)]) -> String {
+    values
+        .iter()
+        .fold(message(key), |message, (name, value)| message.replace(&format!("{{{name}}}"), value))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::utils::io::messages::*;
+
+    #[test]
+    fn test_default_messages_are_unique() {
+        let mut keys = DEFAULT_MESSAGES.iter().map(|(key, _)| *key).collect::<Vec<_>>();
+        keys.sort();
+        keys.dedup();
+        assert_eq!(keys.len(), DEFAULT_MESSAGES.len());
+    }
+
+    #[test]
+    fn test_messages() {
+        let mut values = HashMap::new();
+        flatten_messages(
+            "",
+            &toml::Value::Table(
+                "reverts.title = \"Motifs de revert\"\n[funds]\ntitle = \"Fonds exposés\""
+                    .parse::<toml::Table>()
+                    .unwrap(),
+            ),
+            &mut values,
+        )
+        .unwrap();
+        assert_eq!(values.get("funds.title").map(String::as_str), Some("Fonds exposés"));
+
+        assert!(set_messages(HashMap::from([("unknown".to_string(), String::new())])).is_err());
+        set_messages(values).unwrap();
+        assert_eq!(message("reverts.title"), "Motifs de revert");
+        assert_eq!(message("reverts.none"), "No revert messages or custom errors were found.");
+        assert_eq!(message("missing.key"), "missing.key");
+
+        set_messages(HashMap::new()).unwrap();
+        assert_eq!(message("reverts.title"), "Revert reasons");
+    }
+}
@@ -1,4 +1,11 @@
+pub mod address;
+pub mod crash;
+pub mod exit;
 pub mod file;
+pub mod input;
 pub mod logging;
 pub mod macros;
+pub mod messages;
+pub mod redaction;
 pub mod types;
+pub mod watch;
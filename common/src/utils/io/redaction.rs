@@ -0,0 +1,175 @@
+use std::sync::RwLock;
+
+use ethers::utils::keccak256;
+use lazy_static::lazy_static;
+
+use crate::{
+    constants::{LONG_HEX_REGEX, URL_REGEX},
+    error::Error,
+    utils::strings::{decode_hex, encode_hex},
+};
+
+lazy_static! {
+    static ref REDACTION: RwLock<Redaction> = RwLock::new(Redaction::default());
+    static ref SECRETS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+}
+
+/// What to redact from reports before they're written or printed, so they can be shared without
+/// leaking details of the environment they were generated in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Redaction {
+    /// URLs, such as RPC providers
+    pub urls: bool,
+    /// API keys and RPC URLs registered with [`register_secret`]
+    pub secrets: bool,
+    /// The home and working directories
+    pub paths: bool,
+    /// Labels from the local label store
+    pub annotations: bool,
+    /// Bytecode, which is replaced by its keccak256 hash
+    pub bytecode: bool,
+}
+
+impl Redaction {
+    /// Parse a comma separated list of `urls`, `keys`, `paths`, `annotations`, and `bytecode`,
+    /// or `all`.
+    ///
+    /// ```
+    /// use heimdall_common::utils::io::redaction::Redaction;
+    ///
+    /// let redaction = Redaction::parse("urls,bytecode").unwrap();
+    /// assert!(redaction.urls && redaction.bytecode && !redaction.paths);
+    /// ```
+    pub fn parse(value: &str) -> Result<Redaction, Error> {
+        let mut redaction = Redaction::default();
+        for item in value.split(',').map(|item| item.trim()).filter(|item| !item.is_empty()) {
+            match item {
+                "urls" => redaction.urls = true,
+                "keys" => redaction.secrets = true,
+                "paths" => redaction.paths = true,
+                "annotations" => redaction.annotations = true,
+                "bytecode" => redaction.bytecode = true,
+                "all" => {
+                    redaction = Redaction {
+                        urls: true,
+                        secrets: true,
+                        paths: true,
+                        annotations: true,
+                        bytecode: true,
+                    }
+                }
+                _ => return Err(Error::Generic(format!("unknown redaction '{item}'"))),
+            }
+        }
+
+        Ok(redaction)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Redaction::default()
+    }
+}
+
+/// Set what is redacted from reports.
+pub fn set_redaction(redaction: Redaction) {
+    if let Ok(mut current) = REDACTION.write() {
+        *current = redaction;
+    }
+}
+
+/// What is currently redacted from reports.
+pub fn redaction() -> Redaction {
+    REDACTION.read().map(|redaction| *redaction).unwrap_or_default()
+}
+
+/// Register a value, such as an API key, which is removed from reports when secrets are
+/// redacted.
+pub fn register_secret(secret: &str) {
+    // very short values would redact unrelated output
+    if secret.len() < 8 {
+        return
+    }
+    if let Ok(mut secrets) = SECRETS.write() {
+        secrets.push(secret.to_string());
+    }
+}
+
+/// Apply the current [`Redaction`] to a report.
+pub fn redact_report(report: &str) -> String {
+    let redaction = redaction();
+    if redaction.is_empty() {
+        return report.to_string()
+    }
+
+    let mut redacted = report.to_string();
+    if redaction.secrets {
+        for secret in SECRETS.read().map(|secrets| secrets.clone()).unwrap_or_default() {
+            redacted = redacted.replace(&secret, "<redacted>");
+        }
+    }
+    if redaction.urls {
+        redacted = URL_REGEX.replace_all(&redacted, "<redacted>").to_string();
+    }
+    if redaction.paths {
+        if let Some(cwd) =
+            std::env::current_dir().ok().and_then(|cwd| cwd.into_os_string().into_string().ok())
+        {
+            if cwd.len() > 1 {
+                redacted = redacted.replace(&cwd, ".");
+            }
+        }
+        if let Some(home) = std::env::var_os("HOME").and_then(|home| home.into_string().ok()) {
+            if home.len() > 1 {
+                redacted = redacted.replace(&home, "~");
+            }
+        }
+    }
+    if redaction.bytecode {
+        redacted = LONG_HEX_REGEX
+            .replace_all(&redacted, |captures: &fancy_regex::Captures| {
+                // an odd number of digits isn't bytecode, so the digits themselves are hashed
+                let hex = captures[0].replacen("0x", "", 1);
+                let bytes = match hex.len() % 2 {
+                    0 => decode_hex(&hex).unwrap_or_default(),
+                    _ => hex.into_bytes(),
+                };
+                format!("<bytecode keccak256 0x{}>", encode_hex(keccak256(bytes).to_vec()))
+            })
+            .to_string();
+    }
+
+    redacted
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::io::redaction::*;
+
+    #[test]
+    fn test_redact_report() {
+        set_redaction(Redaction::parse("keys,urls,bytecode").unwrap());
+        register_secret("my-secret-api-key");
+
+        let report = format!(
+            "{{\"rpc\": \"https://rpc.example/my-secret-api-key\", \"key\": \"my-secret-api-key\", \"code\": \"0x{}\"}}",
+            "00".repeat(65)
+        );
+        let redacted = redact_report(&report);
+
+        // an odd number of digits is hashed as is, rather than as no bytes at all
+        let odd = redact_report(&format!("0x{}", "0".repeat(131)));
+        set_redaction(Redaction::default());
+
+        assert!(!redacted.contains("my-secret-api-key"));
+        assert!(!redacted.contains("https://"));
+        assert!(redacted.contains("\"code\": \"<bytecode keccak256 0x"));
+        assert!(odd.starts_with("<bytecode keccak256 0x"));
+        assert!(!odd.contains(&encode_hex(keccak256([]).to_vec())));
+    }
+
+    #[test]
+    fn test_parse_unknown_redaction() {
+        assert!(Redaction::parse("urls,passwords").is_err());
+        assert!(Redaction::parse("").unwrap().is_empty());
+    }
+}
@@ -1,7 +1,9 @@
 use colored::Colorize;
 use ethers::abi::Token;
 
-use crate::utils::hex::ToLowerHex;
+use crate::utils::{
+    hex::ToLowerHex, integers::format_literal, io::address::format_address, time::format_timestamp,
+};
 
 /// A helper function used by the decode module to pretty format decoded tokens.
 pub fn display(inputs: Vec<Token>, prefix: &str) -> Vec<String> {
@@ -10,9 +12,16 @@ pub fn display(inputs: Vec<Token>, prefix: &str) -> Vec<String> {
 
     for input in inputs {
         match input {
-            Token::Address(_) => output.push(format!("{prefix}{} 0x{input}", "address".blue())),
+            Token::Address(val) => {
+                output.push(format!("{prefix}{} {}", "address".blue(), format_address(&val)))
+            }
             Token::Int(val) => output.push(format!("{prefix}{} {}", "int    ".blue(), val)),
-            Token::Uint(val) => output.push(format!("{prefix}{} {}", "uint   ".blue(), val)),
+            Token::Uint(val) => match format_literal(val).or_else(|| format_timestamp(val)) {
+                Some(literal) => {
+                    output.push(format!("{prefix}{} {val} ({literal})", "uint   ".blue()))
+                }
+                None => output.push(format!("{prefix}{} {}", "uint   ".blue(), val)),
+            },
             Token::String(val) => output.push(format!("{prefix}{} {val}", "string ".blue())),
             Token::Bool(val) => {
                 if val {
@@ -80,9 +89,12 @@ pub trait Parameterize {
 impl Parameterize for Token {
     fn parameterize(&self) -> String {
         match self {
-            Token::Address(val) => format!("address: {}", val.to_lower_hex()),
+            Token::Address(val) => format!("address: {}", format_address(val)),
             Token::Int(val) => format!("int: {}", val),
-            Token::Uint(val) => format!("uint: {}", val),
+            Token::Uint(val) => match format_timestamp(*val) {
+                Some(date) => format!("uint: {} ({})", val, date),
+                None => format!("uint: {}", val),
+            },
             Token::String(val) => format!("string: {}", val),
             Token::Bool(val) => format!("bool: {}", val),
             Token::Bytes(val) => format!("bytes: 0x{}", val.to_lower_hex()),
@@ -173,7 +185,7 @@ impl Parameterize for Token {
 
 #[cfg(test)]
 mod tests {
-    use ethers::types::Address;
+    use ethers::types::{Address, U256};
 
     use super::*;
 
@@ -195,6 +207,25 @@ mod tests {
         assert_eq!(output, "uint: 1".to_string());
     }
 
+    #[test]
+    fn test_display_uint_literal() {
+        let output = display(vec![Token::Uint(U256::exp10(18)), Token::Uint(1.into())], "");
+        assert!(output[0].ends_with(" 1000000000000000000 (1e18)"));
+        assert!(output[1].ends_with(" 1"));
+    }
+
+    #[test]
+    fn test_display_uint_timestamp() {
+        let output = display(vec![Token::Uint(1_700_000_123.into())], "");
+        assert!(output[0].ends_with(" 1700000123 (2023-11-14 22:15:23 UTC)"));
+    }
+
+    #[test]
+    fn test_parameterize_uint_timestamp() {
+        let output = Token::Uint(1_700_000_000.into()).parameterize();
+        assert_eq!(output, "uint: 1700000000 (2023-11-14 22:13:20 UTC)".to_string());
+    }
+
     #[test]
     fn test_parameterize_string() {
         let output = Token::String("test".to_string()).parameterize();
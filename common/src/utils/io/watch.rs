@@ -0,0 +1,81 @@
+use std::{fs, time::SystemTime};
+
+use crate::error::Error;
+
+use super::input::normalize_input;
+
+/// Polls a target file for changes to the value it holds, so an analysis of it can be re-run
+/// while it's being worked on.
+pub struct TargetWatcher {
+    path: String,
+    /// The file's modification time and length when it was last read
+    modified: Option<(SystemTime, u64)>,
+    /// The normalized target the file held when it was last read
+    input: Option<String>,
+}
+
+impl TargetWatcher {
+    pub fn new(path: &str) -> TargetWatcher {
+        TargetWatcher { path: path.to_string(), modified: None, input: None }
+    }
+
+    /// Whether the target changed since the last poll. The first poll is always a change, so the
+    /// analysis runs once on start.
+    ///
+    /// The file is only read when its modification time or length changes, and a rewrite which
+    /// leaves the target unchanged, such as recompiling an artifact whose bytecode didn't change,
+    /// isn't a change. A file which is missing or empty, as it may be while it's rewritten, is
+    /// read again on the next poll.
+    pub fn poll(&mut self) -> Result<bool, Error> {
+        let modified = match fs::metadata(&self.path) {
+            Ok(metadata) => (metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH), metadata.len()),
+            Err(_) => return Ok(false),
+        };
+        if self.modified == Some(modified) {
+            return Ok(false)
+        }
+
+        let contents = fs::read_to_string(&self.path)
+            .map_err(|e| Error::IOError(format!("failed to read file '{}': {e}", self.path)))?;
+        let input = normalize_input(&contents);
+        if input.is_empty() {
+            return Ok(false)
+        }
+
+        self.modified = Some(modified);
+        if self.input.as_ref() == Some(&input) {
+            return Ok(false)
+        }
+        self.input = Some(input);
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::io::watch::*;
+
+    #[test]
+    fn test_poll() {
+        let path = std::env::temp_dir().join("heimdall-test-watch.json");
+        let path_str = path.to_str().unwrap();
+        fs::write(&path, r#"{"deployedBytecode": {"object": "0x6001"}, "ast": 1}"#).unwrap();
+
+        let mut watcher = TargetWatcher::new(path_str);
+        assert!(watcher.poll().unwrap());
+        assert!(!watcher.poll().unwrap());
+
+        // recompiling without changing the bytecode isn't a change
+        fs::write(&path, r#"{"deployedBytecode": {"object": "0x6001"}, "ast": 22}"#).unwrap();
+        assert!(!watcher.poll().unwrap());
+
+        fs::write(&path, r#"{"deployedBytecode": {"object": "0x600201"}, "ast": 22}"#).unwrap();
+        assert!(watcher.poll().unwrap());
+
+        // a file which is being rewritten is waited on
+        fs::write(&path, "").unwrap();
+        assert!(!watcher.poll().unwrap());
+        fs::remove_file(&path).unwrap();
+        assert!(!watcher.poll().unwrap());
+    }
+}
@@ -4,6 +4,7 @@ pub mod http;
 pub mod integers;
 pub mod io;
 pub mod iter;
+pub mod offline;
 pub mod range_map;
 pub mod strings;
 pub mod sync;
@@ -0,0 +1,22 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// Enable or disable offline mode. While offline, no network requests are made: lookups which
+/// miss the cache resolve to nothing, and operations which need an RPC provider fail.
+pub fn set_offline(offline: bool) {
+    OFFLINE.store(offline, Ordering::SeqCst);
+}
+
+/// Whether offline mode is enabled.
+///
+/// ```
+/// use heimdall_common::utils::offline::{is_offline, set_offline};
+///
+/// set_offline(true);
+/// assert!(is_offline());
+/// set_offline(false);
+/// ```
+pub fn is_offline() -> bool {
+    OFFLINE.load(Ordering::SeqCst)
+}
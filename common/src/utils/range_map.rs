@@ -1,4 +1,4 @@
-use std::{collections::HashMap, ops::Range};
+use std::{collections::BTreeMap, ops::Range};
 
 use crate::ether::evm::core::opcodes::WrappedOpcode;
 
@@ -9,17 +9,50 @@ enum CollisionKind {
     Shortening,
 }
 
+/// Inclusive ranges of memory, each associated with the opcode which last wrote it.
+///
+/// Ranges never overlap, so keying them by their start is enough to find the range containing an
+/// offset, or the ranges colliding with a write, in logarithmic time.
 #[derive(Clone, Debug, Eq, PartialEq)]
-pub struct RangeMap(pub HashMap<Range<usize>, WrappedOpcode>);
+pub struct RangeMap(BTreeMap<usize, (usize, WrappedOpcode)>);
 
 impl RangeMap {
     pub fn new() -> Self {
-        Self(HashMap::new())
+        Self(BTreeMap::new())
     }
 
     /// Given an offset into memory, returns the associated opcode if it exists
     pub fn get_by_offset(&self, offset: usize) -> Option<WrappedOpcode> {
-        self.0.get(self.find_range(offset).expect("RangeMap::have_range is broken")).cloned()
+        self.find_range(offset).and_then(|range| self.get(&range)).cloned()
+    }
+
+    /// Iterates over each range and its associated opcode, in order of offset
+    pub fn iter(&self) -> impl Iterator<Item = (Range<usize>, &WrappedOpcode)> {
+        self.0.iter().map(|(start, (end, opcode))| (Range { start: *start, end: *end }, opcode))
+    }
+
+    /// Iterates over each range which overlaps `[offset, offset + size - 1]` and its associated
+    /// opcode, in order of offset. Ranges are returned whole, so the first and last may extend
+    /// beyond the window.
+    pub fn overlapping(
+        &self,
+        offset: usize,
+        size: usize,
+    ) -> impl Iterator<Item = (Range<usize>, &WrappedOpcode)> {
+        let window = match size {
+            0 => None,
+            size => Some(Range { start: offset, end: offset + size - 1 }),
+        };
+        window.into_iter().flat_map(move |window| {
+            // the ranges starting within the window, and the one before it, which may extend
+            // into it
+            let preceding = self.0.range(..window.start).next_back();
+            preceding
+                .into_iter()
+                .chain(self.0.range(window.start..=window.end))
+                .map(|(start, (end, opcode))| (Range { start: *start, end: *end }, opcode))
+                .filter(move |(incumbent, _)| Self::range_collides(&window, incumbent))
+        })
     }
 
     /// Associates the provided opcode with the range of memory modified by writing a `size`-byte
@@ -32,53 +65,68 @@ impl RangeMap {
     ///  - split, if our range overwrites a subset that partitions it,
     ///  - shortened, if our range overwrites such that only one "end" of it is overwritten
     pub fn write(&mut self, offset: usize, size: usize, opcode: WrappedOpcode) {
+        // an empty write doesn't touch memory, and has no inclusive range
+        if size == 0 {
+            return
+        }
+
         let range: Range<usize> = Range { start: offset, end: offset + size - 1 };
         let incumbents: Vec<Range<usize>> = self.affected_ranges(range.clone());
 
-        if incumbents.is_empty() {
-            self.0.insert(range, opcode);
-        } else {
-            incumbents.iter().for_each(|incumbent| {
-                match Self::classify_collision(&range, incumbent) {
-                    CollisionKind::Deletion => {
-                        self.0.remove(incumbent);
-                    }
-                    CollisionKind::Splitting => {
-                        let left: Range<usize> =
+        incumbents.iter().for_each(|incumbent| {
+            match Self::classify_collision(&range, incumbent) {
+                CollisionKind::Deletion => {
+                    self.remove(incumbent);
+                }
+                CollisionKind::Splitting => {
+                    let left: Range<usize> = Range { start: incumbent.start, end: range.start - 1 };
+                    let right: Range<usize> = Range { start: range.end + 1, end: incumbent.end };
+                    let old_opcode: WrappedOpcode = self.get(incumbent).expect("").clone();
+
+                    self.remove(incumbent);
+                    self.insert(left, old_opcode.clone());
+                    self.insert(right, old_opcode.clone());
+                }
+                CollisionKind::Shortening => {
+                    // an incoming range which starts with the incumbent, but ends before
+                    // it, overwrites its left end
+                    let needs_right_shortening =
+                        |incoming: &Range<usize>, incumbent: &Range<usize>| {
+                            incoming.start > incumbent.start
+                        };
+
+                    if needs_right_shortening(&range, incumbent) {
+                        let remainder: Range<usize> =
                             Range { start: incumbent.start, end: range.start - 1 };
-                        let right: Range<usize> =
+                        let old_opcode: WrappedOpcode = self.get(incumbent).cloned().unwrap();
+                        self.remove(incumbent);
+                        self.insert(remainder, old_opcode);
+                    } else {
+                        let remainder: Range<usize> =
                             Range { start: range.end + 1, end: incumbent.end };
-                        let old_opcode: WrappedOpcode = self.0.get(incumbent).expect("").clone();
-
-                        self.0.remove(incumbent);
-                        self.0.insert(left, old_opcode.clone());
-                        self.0.insert(right, old_opcode.clone());
-                    }
-                    CollisionKind::Shortening => {
-                        let needs_right_shortening =
-                            |incoming: &Range<usize>, incumbent: &Range<usize>| {
-                                incoming.start >= incumbent.start
-                            };
-
-                        if needs_right_shortening(&range, incumbent) {
-                            let remainder: Range<usize> =
-                                Range { start: incumbent.start, end: range.start - 1 };
-                            let old_opcode: WrappedOpcode = self.0.get(incumbent).cloned().unwrap();
-                            self.0.remove(incumbent);
-                            self.0.insert(remainder, old_opcode);
-                        } else {
-                            let remainder: Range<usize> =
-                                Range { start: range.end + 1, end: incumbent.end };
-                            let old_opcode: WrappedOpcode = self.0.get(incumbent).cloned().unwrap();
-                            self.0.remove(incumbent);
-                            self.0.insert(remainder, old_opcode);
-                        }
+                        let old_opcode: WrappedOpcode = self.get(incumbent).cloned().unwrap();
+                        self.remove(incumbent);
+                        self.insert(remainder, old_opcode);
                     }
                 }
+            }
+        });
 
-                self.0.insert(range.clone(), opcode.clone());
-            });
-        }
+        // ranges are keyed by their start, so the incoming range is only inserted once every
+        // incumbent, which may share its start, is gone
+        self.insert(range, opcode);
+    }
+
+    fn get(&self, range: &Range<usize>) -> Option<&WrappedOpcode> {
+        self.0.get(&range.start).map(|(_, opcode)| opcode)
+    }
+
+    fn insert(&mut self, range: Range<usize>, opcode: WrappedOpcode) {
+        self.0.insert(range.start, (range.end, opcode));
+    }
+
+    fn remove(&mut self, range: &Range<usize>) {
+        self.0.remove(&range.start);
     }
 
     fn classify_collision(incoming: &Range<usize>, incumbent: &Range<usize>) -> CollisionKind {
@@ -98,12 +146,20 @@ impl RangeMap {
         }
     }
 
-    fn find_range(&self, offset: usize) -> Option<&Range<usize>> {
-        self.0.keys().find(|range| range.contains(&offset))
+    fn find_range(&self, offset: usize) -> Option<Range<usize>> {
+        // only the last range starting at or before the offset can contain it, since ranges are
+        // inclusive of their end and never overlap
+        self.0
+            .range(..=offset)
+            .next_back()
+            .filter(|(_, (end, _))| offset <= *end)
+            .map(|(start, (end, _))| Range { start: *start, end: *end })
     }
 
     fn affected_ranges(&self, range: Range<usize>) -> Vec<Range<usize>> {
-        self.0.keys().filter(|incumbent| Self::range_collides(&range, incumbent)).cloned().collect()
+        self.overlapping(range.start, range.end - range.start + 1)
+            .map(|(incumbent, _)| incumbent)
+            .collect()
     }
 
     fn range_collides(incoming: &Range<usize>, incumbent: &Range<usize>) -> bool {
@@ -116,11 +172,26 @@ impl RangeMap {
     }
 }
 
+impl FromIterator<(Range<usize>, WrappedOpcode)> for RangeMap {
+    /// Collects ranges which are already known not to overlap, such as those of another map.
+    fn from_iter<I: IntoIterator<Item = (Range<usize>, WrappedOpcode)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        iter.into_iter().for_each(|(range, opcode)| map.insert(range, opcode));
+        map
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use std::{collections::HashMap, ops::Range};
+    use std::ops::Range;
 
-    use crate::{ether::evm::core::opcodes::WrappedOpcode, utils::range_map::RangeMap};
+    use ethers::types::U256;
+    use proptest::prelude::*;
+
+    use crate::{
+        ether::evm::core::opcodes::{WrappedInput, WrappedOpcode},
+        utils::range_map::RangeMap,
+    };
 
     #[test]
     fn test_one_incumbent_and_needs_deletion() {
@@ -130,9 +201,9 @@ mod tests {
         let initial_pairs: Vec<((usize, usize), WrappedOpcode)> =
             vec![((8, 16), some_op.clone()), ((32, 64), some_op.clone())];
 
-        let mut actual_byte_tracker: RangeMap = RangeMap(HashMap::from_iter(
+        let mut actual_byte_tracker: RangeMap = RangeMap::from_iter(
             initial_pairs.iter().cloned().map(|((a, b), v)| (Range { start: a, end: b }, v)),
-        ));
+        );
 
         let offset: usize = 7;
         let size: usize = 11;
@@ -140,9 +211,9 @@ mod tests {
 
         let expected_pairs: Vec<((usize, usize), WrappedOpcode)> =
             vec![((7, 17), some_op.clone()), ((32, 64), some_op.clone())];
-        let expected_byte_tracker: RangeMap = RangeMap(HashMap::from_iter(
+        let expected_byte_tracker: RangeMap = RangeMap::from_iter(
             expected_pairs.iter().cloned().map(|((a, b), v)| (Range { start: a, end: b }, v)),
-        ));
+        );
 
         assert_eq!(actual_byte_tracker, expected_byte_tracker);
     }
@@ -155,9 +226,9 @@ mod tests {
         let initial_pairs: Vec<((usize, usize), WrappedOpcode)> =
             vec![((7, 18), some_op.clone()), ((32, 64), some_op.clone())];
 
-        let mut actual_byte_tracker: RangeMap = RangeMap(HashMap::from_iter(
+        let mut actual_byte_tracker: RangeMap = RangeMap::from_iter(
             initial_pairs.iter().cloned().map(|((a, b), v)| (Range { start: a, end: b }, v)),
-        ));
+        );
 
         let offset: usize = 8;
         let size: usize = 8;
@@ -169,9 +240,9 @@ mod tests {
             ((16, 18), some_op.clone()),
             ((32, 64), some_op.clone()),
         ];
-        let expected_byte_tracker: RangeMap = RangeMap(HashMap::from_iter(
+        let expected_byte_tracker: RangeMap = RangeMap::from_iter(
             expected_pairs.iter().cloned().map(|((a, b), v)| (Range { start: a, end: b }, v)),
-        ));
+        );
 
         assert_eq!(actual_byte_tracker, expected_byte_tracker);
     }
@@ -184,9 +255,9 @@ mod tests {
         let initial_pairs: Vec<((usize, usize), WrappedOpcode)> =
             vec![((7, 18), some_op.clone()), ((32, 64), some_op.clone())];
 
-        let mut actual_byte_tracker: RangeMap = RangeMap(HashMap::from_iter(
+        let mut actual_byte_tracker: RangeMap = RangeMap::from_iter(
             initial_pairs.iter().cloned().map(|((a, b), v)| (Range { start: a, end: b }, v)),
-        ));
+        );
 
         let offset: usize = 10;
         let size: usize = 14;
@@ -197,9 +268,9 @@ mod tests {
             ((10, 23), some_op.clone()),
             ((32, 64), some_op.clone()),
         ];
-        let expected_byte_tracker: RangeMap = RangeMap(HashMap::from_iter(
+        let expected_byte_tracker: RangeMap = RangeMap::from_iter(
             expected_pairs.iter().cloned().map(|((a, b), v)| (Range { start: a, end: b }, v)),
-        ));
+        );
 
         assert_eq!(actual_byte_tracker, expected_byte_tracker);
     }
@@ -212,9 +283,9 @@ mod tests {
         let initial_pairs: Vec<((usize, usize), WrappedOpcode)> =
             vec![((7, 18), some_op.clone()), ((32, 64), some_op.clone())];
 
-        let mut actual_byte_tracker: RangeMap = RangeMap(HashMap::from_iter(
+        let mut actual_byte_tracker: RangeMap = RangeMap::from_iter(
             initial_pairs.iter().cloned().map(|((a, b), v)| (Range { start: a, end: b }, v)),
-        ));
+        );
 
         let offset: usize = 2;
         let size: usize = 8;
@@ -225,9 +296,9 @@ mod tests {
             ((10, 18), some_op.clone()),
             ((32, 64), some_op.clone()),
         ];
-        let expected_byte_tracker: RangeMap = RangeMap(HashMap::from_iter(
+        let expected_byte_tracker: RangeMap = RangeMap::from_iter(
             expected_pairs.iter().cloned().map(|((a, b), v)| (Range { start: a, end: b }, v)),
-        ));
+        );
 
         assert_eq!(actual_byte_tracker, expected_byte_tracker);
     }
@@ -247,4 +318,69 @@ mod tests {
 
         assert!(!RangeMap::range_collides(&range, &incumbent));
     }
+
+    #[test]
+    fn test_get_by_offset_includes_range_end() {
+        let some_op: WrappedOpcode = WrappedOpcode::default();
+        let mut byte_tracker = RangeMap::new();
+        byte_tracker.write(0, 32, some_op.clone());
+
+        assert_eq!(byte_tracker.get_by_offset(31), Some(some_op));
+        assert_eq!(byte_tracker.get_by_offset(32), None);
+    }
+
+    #[test]
+    fn test_shortening_from_shared_start() {
+        let old_op = WrappedOpcode::new(0x60, vec![WrappedInput::Raw(U256::from(1))]);
+        let new_op = WrappedOpcode::new(0x60, vec![WrappedInput::Raw(U256::from(2))]);
+        let mut byte_tracker = RangeMap::new();
+        byte_tracker.write(0, 32, old_op.clone());
+
+        // starts with the incumbent, so its left end is overwritten
+        byte_tracker.write(0, 8, new_op.clone());
+
+        let expected_pairs = vec![((0, 7), new_op), ((8, 31), old_op)];
+        assert_eq!(
+            byte_tracker,
+            RangeMap::from_iter(
+                expected_pairs.into_iter().map(|((a, b), v)| (Range { start: a, end: b }, v))
+            )
+        );
+    }
+
+    #[test]
+    fn test_empty_write_is_ignored() {
+        let some_op: WrappedOpcode = WrappedOpcode::default();
+        let mut byte_tracker = RangeMap::new();
+        byte_tracker.write(0, 0, some_op.clone());
+        byte_tracker.write(8, 0, some_op);
+
+        assert_eq!(byte_tracker, RangeMap::new());
+    }
+
+    proptest! {
+        /// Every byte is attributed to the last write which covered it, whatever the overlap
+        /// between writes, and ranges never overlap or go empty.
+        #[test]
+        fn test_writes_match_byte_model(
+            writes in prop::collection::vec((0usize..64, 0usize..48), 1..24)
+        ) {
+            let mut byte_tracker = RangeMap::new();
+            let mut model: Vec<Option<WrappedOpcode>> = vec![None; 128];
+
+            for (i, (offset, size)) in writes.into_iter().enumerate() {
+                let opcode = WrappedOpcode::new(0x60, vec![WrappedInput::Raw(U256::from(i))]);
+                byte_tracker.write(offset, size, opcode.clone());
+                model[offset..offset + size].iter_mut().for_each(|byte| *byte = Some(opcode.clone()));
+            }
+
+            for (offset, expected) in model.iter().enumerate() {
+                prop_assert_eq!(&byte_tracker.get_by_offset(offset), expected);
+            }
+
+            let ranges = byte_tracker.iter().map(|(range, _)| range).collect::<Vec<Range<usize>>>();
+            prop_assert!(ranges.iter().all(|range| range.start <= range.end));
+            prop_assert!(ranges.windows(2).all(|pair| pair[0].end < pair[1].start));
+        }
+    }
 }
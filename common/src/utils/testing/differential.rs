@@ -0,0 +1,256 @@
+use std::{collections::BTreeMap, process::Command};
+
+use ethers::types::U256;
+use serde_json::Value;
+
+use crate::{error::Error, ether::evm::core::vm::VM, utils::strings::encode_hex};
+
+/// The address programs run at, in both VMs. It's the address geth's `evm` tool uses by default.
+pub const RECEIVER: &str = "0x0000000000000000000000007265636569766572";
+
+/// The opcodes programs are built from, with the number of stack items each takes. They're all
+/// deterministic, and only use the stack, calldata, and a bounded amount of memory and storage,
+/// so any difference between the VMs is a difference in the interpreter.
+const OPCODES: &[(u8, usize)] = &[
+    (0x01, 2), // ADD
+    (0x02, 2), // MUL
+    (0x03, 2), // SUB
+    (0x04, 2), // DIV
+    (0x05, 2), // SDIV
+    (0x06, 2), // MOD
+    (0x07, 2), // SMOD
+    (0x08, 3), // ADDMOD
+    (0x09, 3), // MULMOD
+    (0x0a, 2), // EXP
+    (0x0b, 2), // SIGNEXTEND
+    (0x10, 2), // LT
+    (0x11, 2), // GT
+    (0x12, 2), // SLT
+    (0x13, 2), // SGT
+    (0x14, 2), // EQ
+    (0x15, 1), // ISZERO
+    (0x16, 2), // AND
+    (0x17, 2), // OR
+    (0x18, 2), // XOR
+    (0x19, 1), // NOT
+    (0x1a, 2), // BYTE
+    (0x1b, 2), // SHL
+    (0x1c, 2), // SHR
+    (0x1d, 2), // SAR
+    (0x35, 1), // CALLDATALOAD
+    (0x36, 0), // CALLDATASIZE
+    (0x50, 1), // POP
+];
+
+/// The final state of a program's execution.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Outcome {
+    /// Whether the program ran to its final `STOP`
+    pub success: bool,
+    /// The stack, from its bottom
+    pub stack: Vec<U256>,
+    /// Every non-zero storage slot
+    pub storage: BTreeMap<U256, U256>,
+    /// The gas used, excluding the transaction's intrinsic gas
+    pub gas_used: u128,
+}
+
+/// Build a program from a sequence of random choices. Each choice either pushes its word, runs
+/// an opcode from [`OPCODES`], duplicates or swaps stack items, or reads or writes memory or
+/// storage at a small offset. Choices which would underflow the stack push instead, so every
+/// program runs to the `STOP` it ends with, unless it runs out of gas.
+pub fn build_program(choices: &[(u8, U256)]) -> Vec<u8> {
+    let mut program = Vec::new();
+    let mut depth = 0usize;
+
+    let push = |program: &mut Vec<u8>, word: U256, size: usize| {
+        let mut bytes = [0u8; 32];
+        word.to_big_endian(&mut bytes);
+        program.push(0x5f + size as u8);
+        program.extend_from_slice(&bytes[32 - size..]);
+    };
+
+    for (choice, word) in choices {
+        // memory and storage are kept small, so neither VM spends long expanding them
+        let offset = U256::from(word.byte(0) & 0x7f);
+        let n = (word.byte(1) % 16) as usize + 1;
+        let kind = *choice as usize % (OPCODES.len() + 6);
+
+        match kind {
+            kind if kind < OPCODES.len() && depth >= OPCODES[kind].1 && depth < 1024 => {
+                let (opcode, inputs) = OPCODES[kind];
+                program.push(opcode);
+                depth = depth - inputs + 1;
+                if opcode == 0x50 {
+                    depth -= 1;
+                }
+            }
+            // DUPn
+            kind if kind == OPCODES.len() && depth >= n && depth < 1024 => {
+                program.push(0x7f + n as u8);
+                depth += 1;
+            }
+            // SWAPn
+            kind if kind == OPCODES.len() + 1 && depth > n => program.push(0x8f + n as u8),
+            // MSTORE
+            kind if kind == OPCODES.len() + 2 && depth >= 1 => {
+                push(&mut program, offset, 1);
+                program.push(0x52);
+                depth -= 1;
+            }
+            // MLOAD
+            kind if kind == OPCODES.len() + 3 && depth < 1024 => {
+                push(&mut program, offset, 1);
+                program.push(0x51);
+                depth += 1;
+            }
+            // SSTORE
+            kind if kind == OPCODES.len() + 4 && depth >= 1 => {
+                push(&mut program, offset, 1);
+                program.push(0x55);
+                depth -= 1;
+            }
+            // SLOAD
+            kind if kind == OPCODES.len() + 5 && depth < 1024 => {
+                push(&mut program, offset, 1);
+                program.push(0x54);
+                depth += 1;
+            }
+            _ if depth < 1024 => {
+                push(&mut program, *word, (word.byte(2) % 32) as usize + 1);
+                depth += 1;
+            }
+            _ => {}
+        }
+    }
+
+    program.push(0x00);
+    program
+}
+
+/// Run a program with heimdall's VM.
+pub fn run_heimdall(program: &[u8], calldata: &[u8], gas: u128) -> Outcome {
+    let mut vm = VM::new(
+        encode_hex(program.to_vec()),
+        encode_hex(calldata.to_vec()),
+        RECEIVER.to_string(),
+        String::from("0x0000000000000000000000000000000000000001"),
+        String::from("0x0000000000000000000000000000000000000001"),
+        0,
+        gas + 21000,
+    );
+    let result = vm.execute();
+
+    Outcome {
+        success: result.exitcode == 10 || result.exitcode == 0,
+        stack: vm.stack.stack.iter().rev().map(|frame| frame.value).collect(),
+        storage: vm
+            .storage
+            .storage
+            .iter()
+            .map(|(slot, value)| (U256::from_big_endian(slot), U256::from_big_endian(value)))
+            .filter(|(_, value)| !value.is_zero())
+            .collect(),
+        gas_used: result.gas_used.saturating_sub(21000),
+    }
+}
+
+/// Run a program with geth's `evm` tool at `evm`, reading the final stack from its JSON trace
+/// and the final storage from its state dump.
+pub fn run_reference(
+    evm: &str,
+    program: &[u8],
+    calldata: &[u8],
+    gas: u128,
+) -> Result<Outcome, Error> {
+    let output = Command::new(evm)
+        .args([
+            "--code",
+            &encode_hex(program.to_vec()),
+            "--input",
+            &encode_hex(calldata.to_vec()),
+            "--gas",
+            &gas.to_string(),
+            "--receiver",
+            RECEIVER,
+            "--json",
+            "--nomemory",
+            "--dump",
+            "run",
+        ])
+        .output()
+        .map_err(|e| Error::IOError(format!("failed to run '{evm}': {e}")))?;
+
+    // the trace is written to stderr, one JSON object per line, ending with a summary
+    let mut outcome = Outcome::default();
+    for line in String::from_utf8_lossy(&output.stderr).lines() {
+        let Ok(step) = serde_json::from_str::<Value>(line) else { continue };
+        if let Some(stack) = step.get("stack").and_then(|stack| stack.as_array()) {
+            outcome.stack =
+                stack.iter().filter_map(|item| item.as_str().and_then(parse_word)).collect();
+        }
+        if let Some(gas_used) = step.get("gasUsed").and_then(|gas| gas.as_str()) {
+            outcome.gas_used = parse_word(gas_used).unwrap_or_default().as_u128();
+            outcome.success = step.get("error").is_none_or(|error| error.is_null());
+        }
+    }
+
+    // the state dump is written to stdout
+    let dump = serde_json::from_slice::<Value>(&output.stdout)
+        .map_err(|e| Error::Generic(format!("failed to parse state dump: {e}")))?;
+    let account =
+        dump.get("accounts").and_then(|accounts| accounts.as_object()).and_then(|accounts| {
+            accounts.iter().find(|(address, _)| address.eq_ignore_ascii_case(RECEIVER)).map(|a| a.1)
+        });
+    if let Some(storage) = account.and_then(|account| account.get("storage")?.as_object()) {
+        outcome.storage = storage
+            .iter()
+            .filter_map(|(slot, value)| Some((parse_word(slot)?, parse_word(value.as_str()?)?)))
+            .filter(|(_, value)| !value.is_zero())
+            .collect();
+    }
+
+    Ok(outcome)
+}
+
+/// Each way heimdall's outcome differs from the reference's. Only whether the program succeeded
+/// is compared when either VM failed, since a failed program's state is discarded.
+pub fn compare(heimdall: &Outcome, reference: &Outcome) -> Vec<String> {
+    if heimdall.success != reference.success {
+        return vec![format!(
+            "success: heimdall {}, reference {}",
+            heimdall.success, reference.success
+        )]
+    }
+    if !heimdall.success {
+        return Vec::new()
+    }
+
+    let mut differences = Vec::new();
+    if heimdall.stack != reference.stack {
+        differences
+            .push(format!("stack: heimdall {:?}, reference {:?}", heimdall.stack, reference.stack));
+    }
+    if heimdall.storage != reference.storage {
+        differences.push(format!(
+            "storage: heimdall {:?}, reference {:?}",
+            heimdall.storage, reference.storage
+        ));
+    }
+    if heimdall.gas_used != reference.gas_used {
+        differences.push(format!(
+            "gas used: heimdall {}, reference {}",
+            heimdall.gas_used, reference.gas_used
+        ));
+    }
+    differences
+}
+
+/// A word written in hex, i.e. `0x2a`, as geth writes them.
+fn parse_word(value: &str) -> Option<U256> {
+    let value = value.trim_start_matches("0x");
+    match value.is_empty() {
+        true => Some(U256::zero()),
+        false => U256::from_str_radix(value, 16).ok(),
+    }
+}
@@ -52,9 +52,10 @@ pub fn task_pool<
         handles.push(handle);
     }
 
-    // Wait for all threads to finish and collect the results
+    // Wait for all threads to finish and collect the results. There may be fewer chunks than
+    // threads, so only wait for as many results as there are chunks
     let mut results = Vec::new();
-    for _ in 0..num_threads {
+    for _ in 0..handles.len() {
         let chunk_results = rx.recv().unwrap();
         results.extend(chunk_results);
     }
@@ -1,4 +1,8 @@
-use chrono::Local;
+use chrono::{DateTime, Local};
+use ethers::types::U256;
+
+/// The range of values read as timestamps: from Ethereum's genesis block to the start of 2100.
+const TIMESTAMP_RANGE: (u64, u64) = (1_438_269_973, 4_102_444_800);
 
 /// Calculate the ETA for a process based on the number of items processed per second
 ///
@@ -47,6 +51,25 @@ pub fn pretty_timestamp() -> String {
     now.format("%d-%m-%Y %H:%M:%S.%f").to_string()
 }
 
+/// Format a value as a UTC date, if it's in the range of timestamps a contract could plausibly
+/// be given, i.e. between Ethereum's genesis and 2100.
+///
+/// ```
+/// use ethers::types::U256;
+/// use heimdall_common::utils::time::format_timestamp;
+///
+/// assert_eq!(format_timestamp(U256::from(1_700_000_000)), Some(String::from("2023-11-14 22:13:20 UTC")));
+/// assert_eq!(format_timestamp(U256::from(86400)), None);
+/// ```
+pub fn format_timestamp(value: U256) -> Option<String> {
+    if value < U256::from(TIMESTAMP_RANGE.0) || value > U256::from(TIMESTAMP_RANGE.1) {
+        return None
+    }
+
+    DateTime::from_timestamp(value.as_u64() as i64, 0)
+        .map(|date| date.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use crate::utils::time::*;
@@ -71,4 +94,18 @@ mod tests {
         assert_eq!(format_eta(172800), "2d 0s");
         assert_eq!(format_eta(180065), "2d 2h 1m 5s ");
     }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(
+            format_timestamp(U256::from(1_438_269_973)),
+            Some(String::from("2015-07-30 15:26:13 UTC"))
+        );
+        assert_eq!(
+            format_timestamp(U256::from(4_102_444_800u64)),
+            Some(String::from("2100-01-01 00:00:00 UTC"))
+        );
+        assert_eq!(format_timestamp(U256::from(1_438_269_972)), None);
+        assert_eq!(format_timestamp(U256::MAX), None);
+    }
 }
@@ -0,0 +1,128 @@
+#[cfg(test)]
+mod benchmark {
+    use std::ops::Range;
+
+    use heimdall_common::{
+        ether::evm::core::{memory::Memory, opcodes::WrappedOpcode},
+        utils::testing::benchmarks::benchmark,
+    };
+
+    /// The byte tracker as it was before ranges were indexed by start: every write and lookup
+    /// scans every range. Only kept as a baseline for the benchmarks below.
+    #[derive(Default)]
+    struct LinearRangeMap(Vec<(Range<usize>, WrappedOpcode)>);
+
+    impl LinearRangeMap {
+        fn write(&mut self, offset: usize, size: usize, opcode: WrappedOpcode) {
+            let range = Range { start: offset, end: offset + size - 1 };
+            let mut ranges = Vec::with_capacity(self.0.len() + 2);
+            for (incumbent, old_opcode) in self.0.drain(..) {
+                if incumbent.end < range.start || incumbent.start > range.end {
+                    ranges.push((incumbent, old_opcode));
+                    continue
+                }
+
+                // keep whatever sticks out of either end of the incoming range
+                if incumbent.start < range.start {
+                    ranges.push((
+                        Range { start: incumbent.start, end: range.start - 1 },
+                        old_opcode.clone(),
+                    ));
+                }
+                if incumbent.end > range.end {
+                    ranges.push((Range { start: range.end + 1, end: incumbent.end }, old_opcode));
+                }
+            }
+            ranges.push((range, opcode));
+            self.0 = ranges;
+        }
+
+        fn get_by_offset(&self, offset: usize) -> Option<WrappedOpcode> {
+            self.0
+                .iter()
+                .find(|(range, _)| range.start <= offset && offset <= range.end)
+                .map(|(_, opcode)| opcode.clone())
+        }
+    }
+
+    /// The operations the benchmarks make on a byte tracker.
+    trait ByteTracker {
+        fn write(&mut self, offset: usize, size: usize);
+        fn origin(&self, offset: usize) -> Option<WrappedOpcode>;
+    }
+
+    impl ByteTracker for Memory {
+        fn write(&mut self, offset: usize, size: usize) {
+            self.store_with_opcode(offset, size, &[0xff], WrappedOpcode::default());
+        }
+
+        fn origin(&self, offset: usize) -> Option<WrappedOpcode> {
+            Memory::origin(self, offset)
+        }
+    }
+
+    impl ByteTracker for LinearRangeMap {
+        fn write(&mut self, offset: usize, size: usize) {
+            LinearRangeMap::write(self, offset, size, WrappedOpcode::default());
+        }
+
+        fn origin(&self, offset: usize) -> Option<WrappedOpcode> {
+            self.get_by_offset(offset)
+        }
+    }
+
+    fn sequential_writes(tracker: &mut impl ByteTracker) {
+        for i in 0..2048 {
+            tracker.write(i * 32, 32);
+        }
+        for i in 0..2048 {
+            let _ = tracker.origin(i * 32 + 16);
+        }
+    }
+
+    fn overlapping_writes(tracker: &mut impl ByteTracker) {
+        for i in 0..2048 {
+            // unaligned writes which split or shorten the ranges before them
+            tracker.write((i * 37) % 16384, 32 + i % 7);
+        }
+        for i in 0..16384 {
+            let _ = tracker.origin(i);
+        }
+    }
+
+    #[test]
+    fn benchmark_byte_tracker_sequential_writes() {
+        fn bench() {
+            sequential_writes(&mut Memory::new());
+        }
+
+        benchmark("benchmark_byte_tracker_sequential_writes", 100, bench);
+    }
+
+    #[test]
+    fn benchmark_byte_tracker_sequential_writes_linear_baseline() {
+        fn bench() {
+            sequential_writes(&mut LinearRangeMap::default());
+        }
+
+        benchmark("benchmark_byte_tracker_sequential_writes_linear_baseline", 100, bench);
+    }
+
+    #[test]
+    fn benchmark_byte_tracker_overlapping_writes() {
+        fn bench() {
+            overlapping_writes(&mut Memory::new());
+        }
+
+        benchmark("benchmark_byte_tracker_overlapping_writes", 100, bench);
+    }
+
+    #[test]
+    fn benchmark_byte_tracker_overlapping_writes_linear_baseline() {
+        fn bench() {
+            overlapping_writes(&mut LinearRangeMap::default());
+        }
+
+        benchmark("benchmark_byte_tracker_overlapping_writes_linear_baseline", 100, bench);
+    }
+}
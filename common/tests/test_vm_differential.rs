@@ -0,0 +1,69 @@
+#[cfg(test)]
+mod differential {
+    use ethers::types::U256;
+    use heimdall_common::utils::{strings::encode_hex, testing::differential::*};
+    use proptest::{
+        prelude::*,
+        test_runner::{Config, TestRunner},
+    };
+
+    fn choices() -> impl Strategy<Value = Vec<(u8, U256)>> {
+        prop::collection::vec(
+            (any::<u8>(), any::<[u8; 32]>().prop_map(|bytes| U256::from_big_endian(&bytes))),
+            1..64,
+        )
+    }
+
+    fn calldata() -> impl Strategy<Value = Vec<u8>> {
+        prop::collection::vec(any::<u8>(), 0..96)
+    }
+
+    proptest! {
+        /// Generated programs never underflow the stack, so heimdall's VM runs them to their
+        /// final `STOP`.
+        #[test]
+        fn test_generated_programs_halt(choices in choices(), calldata in calldata()) {
+            let outcome = run_heimdall(&build_program(&choices), &calldata, 10_000_000);
+            prop_assert!(outcome.success);
+        }
+    }
+
+    /// Run random programs through heimdall's VM and geth's `evm` tool, and compare their final
+    /// stack, storage, and gas used. Set `HEIMDALL_REFERENCE_EVM` to the path of the `evm` tool,
+    /// and optionally `HEIMDALL_DIFFERENTIAL_CASES` to the number of programs to run:
+    ///
+    /// `HEIMDALL_REFERENCE_EVM=$(which evm) cargo test -p heimdall-common --test
+    /// test_vm_differential -- --ignored`
+    #[test]
+    #[ignore]
+    fn test_differential_against_reference_evm() {
+        let Ok(evm) = std::env::var("HEIMDALL_REFERENCE_EVM") else {
+            eprintln!("HEIMDALL_REFERENCE_EVM is not set, skipping differential test");
+            return
+        };
+        let cases = std::env::var("HEIMDALL_DIFFERENTIAL_CASES")
+            .ok()
+            .and_then(|cases| cases.parse().ok())
+            .unwrap_or(1000);
+
+        let mut runner = TestRunner::new(Config { cases, ..Config::default() });
+        runner
+            .run(&(choices(), calldata()), |(choices, calldata)| {
+                let program = build_program(&choices);
+                let heimdall = run_heimdall(&program, &calldata, 10_000_000);
+                let reference = run_reference(&evm, &program, &calldata, 10_000_000)
+                    .map_err(|e| TestCaseError::fail(e.to_string()))?;
+
+                let differences = compare(&heimdall, &reference);
+                prop_assert!(
+                    differences.is_empty(),
+                    "program 0x{} with calldata 0x{}:\n{}",
+                    encode_hex(program),
+                    encode_hex(calldata),
+                    differences.join("\n")
+                );
+                Ok(())
+            })
+            .unwrap();
+    }
+}
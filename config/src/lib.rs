@@ -1,7 +1,14 @@
 use clap::{AppSettings, Parser};
-use heimdall_common::utils::io::{
-    file::{delete_path, read_file, write_file},
-    logging::*,
+use heimdall_common::{
+    ether::{
+        batch::{default_rpc_batch_size, default_rpc_concurrency},
+        endpoints::{default_signature_endpoints, default_signature_rate_limit, SignatureEndpoint},
+        providers::default_rpc_retries,
+    },
+    utils::io::{
+        file::{delete_path, read_file, write_file},
+        logging::*,
+    },
 };
 use serde::{Deserialize, Serialize};
 #[allow(deprecated)]
@@ -40,6 +47,21 @@ pub struct Configuration {
     pub etherscan_api_key: String,
     pub transpose_api_key: String,
     pub openai_api_key: String,
+    /// The signature databases used to resolve selectors, in order of preference
+    #[serde(default = "default_signature_endpoints")]
+    pub signature_endpoints: Vec<SignatureEndpoint>,
+    /// The most requests made to the signature databases per second, or `0` for no limit
+    #[serde(default = "default_signature_rate_limit")]
+    pub signature_rate_limit: u32,
+    /// The most queries sent to the RPC provider in a single JSON-RPC batch
+    #[serde(default = "default_rpc_batch_size")]
+    pub rpc_batch_size: usize,
+    /// The most JSON-RPC batches sent to the RPC provider at once
+    #[serde(default = "default_rpc_concurrency")]
+    pub rpc_concurrency: usize,
+    /// The number of times a failed request to the RPC provider is retried
+    #[serde(default = "default_rpc_retries")]
+    pub rpc_retries: u32,
 }
 
 #[allow(deprecated)]
@@ -149,6 +171,46 @@ pub fn update_config(key: &str, value: &str) {
         "openai_api_key" => {
             contents.openai_api_key = value.to_string();
         }
+        "signature_endpoints" => match SignatureEndpoint::parse_list(value) {
+            Ok(endpoints) => contents.signature_endpoints = endpoints,
+            Err(e) => {
+                let (logger, _) = Logger::new("");
+                logger.error(&format!("invalid signature endpoints: {e}"));
+                std::process::exit(1)
+            }
+        },
+        "signature_rate_limit" => match value.parse::<u32>() {
+            Ok(rate_limit) => contents.signature_rate_limit = rate_limit,
+            Err(_) => {
+                let (logger, _) = Logger::new("");
+                logger.error(&format!("invalid signature rate limit: {value}"));
+                std::process::exit(1)
+            }
+        },
+        "rpc_batch_size" => match value.parse::<usize>() {
+            Ok(batch_size) if batch_size > 0 => contents.rpc_batch_size = batch_size,
+            _ => {
+                let (logger, _) = Logger::new("");
+                logger.error(&format!("invalid rpc batch size: {value}"));
+                std::process::exit(1)
+            }
+        },
+        "rpc_concurrency" => match value.parse::<usize>() {
+            Ok(concurrency) if concurrency > 0 => contents.rpc_concurrency = concurrency,
+            _ => {
+                let (logger, _) = Logger::new("");
+                logger.error(&format!("invalid rpc concurrency: {value}"));
+                std::process::exit(1)
+            }
+        },
+        "rpc_retries" => match value.parse::<u32>() {
+            Ok(retries) => contents.rpc_retries = retries,
+            Err(_) => {
+                let (logger, _) = Logger::new("");
+                logger.error(&format!("invalid rpc retries: {value}"));
+                std::process::exit(1)
+            }
+        },
         _ => {
             let (logger, _) = Logger::new("");
             logger.error(&format!("unknown configuration key \'{key}\' ."));
@@ -0,0 +1,171 @@
+use clap::{AppSettings, Parser};
+use derive_builder::Builder;
+use ethers::types::H160;
+use heimdall_common::{
+    ether::{
+        bytecode::get_bytecode_from_target,
+        create::{create2_address, create_address, init_code_hash},
+    },
+    utils::{
+        io::logging::{set_logger_env, Logger},
+        strings::{decode_hex, encode_hex},
+    },
+};
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+
+#[derive(Debug, Clone, Parser, Builder)]
+#[clap(
+    about = "Compute the address a contract is deployed to with CREATE or CREATE2",
+    after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    global_setting = AppSettings::DeriveDisplayOrder,
+    override_usage = "heimdall address <DEPLOYER> [OPTIONS]"
+)]
+pub struct AddressArgs {
+    /// The address of the account or factory deploying the contract.
+    #[clap(required = true)]
+    pub deployer: String,
+
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// The deployer's nonce, for a CREATE deployment.
+    #[clap(long)]
+    pub nonce: Option<u64>,
+
+    /// The 32-byte salt, for a CREATE2 deployment.
+    #[clap(long, default_value = "", hide_default_value = true)]
+    pub salt: String,
+
+    /// The init code of a CREATE2 deployment, either a file or hex, including any constructor
+    /// arguments.
+    #[clap(long, default_value = "", hide_default_value = true)]
+    pub init_code: String,
+
+    /// The keccak256 hash of the init code of a CREATE2 deployment, instead of --init-code.
+    #[clap(long, default_value = "", hide_default_value = true)]
+    pub init_code_hash: String,
+
+    /// The output directory to write the output to or 'print' to print to the console
+    #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
+    pub output: String,
+
+    /// The name for the output file
+    #[clap(long, short, default_value = "", hide_default_value = true)]
+    pub name: String,
+}
+
+impl AddressArgsBuilder {
+    pub fn new() -> Self {
+        Self {
+            deployer: Some(String::new()),
+            verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
+            nonce: Some(None),
+            salt: Some(String::new()),
+            init_code: Some(String::new()),
+            init_code_hash: Some(String::new()),
+            output: Some(String::new()),
+            name: Some(String::new()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AddressResult {
+    /// Either `CREATE` or `CREATE2`
+    pub scheme: String,
+    pub deployer: String,
+    pub address: String,
+    pub nonce: Option<u64>,
+    pub salt: Option<String>,
+    pub init_code_hash: Option<String>,
+}
+
+/// Compute the address a contract is deployed to, from the deployer's nonce for `CREATE`, or from
+/// a salt and the init code or its hash for `CREATE2`.
+pub async fn address(args: AddressArgs) -> Result<AddressResult, Error> {
+    set_logger_env(&args.verbose);
+
+    // get a new logger
+    let (logger, _) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    let deployer = H160::from_slice(&parse_bytes(&args.deployer, 20, "deployer")?);
+    let deployer_hex = format!("0x{}", encode_hex(deployer.as_bytes().to_vec()));
+
+    let result = match (args.nonce, args.salt.is_empty()) {
+        (Some(nonce), true) => AddressResult {
+            scheme: String::from("CREATE"),
+            deployer: deployer_hex,
+            address: format!(
+                "0x{}",
+                encode_hex(create_address(deployer, nonce).as_bytes().to_vec())
+            ),
+            nonce: Some(nonce),
+            salt: None,
+            init_code_hash: None,
+        },
+        (None, false) => {
+            let salt: [u8; 32] =
+                parse_bytes(&args.salt, 32, "salt")?.try_into().expect("salt is 32 bytes");
+            let hash: [u8; 32] = match (args.init_code.is_empty(), args.init_code_hash.is_empty()) {
+                (false, true) => {
+                    let init_code = get_bytecode_from_target(&args.init_code, "")
+                        .await
+                        .map_err(|e| Error::GenericError(e.to_string()))?;
+                    init_code_hash(&decode_hex(&init_code).map_err(|e| {
+                        Error::GenericError(format!("failed to decode init code: {e}"))
+                    })?)
+                }
+                (true, false) => parse_bytes(&args.init_code_hash, 32, "init code hash")?
+                    .try_into()
+                    .expect("init code hash is 32 bytes"),
+                _ => {
+                    return Err(Error::GenericError(
+                        "CREATE2 requires exactly one of --init-code or --init-code-hash."
+                            .to_string(),
+                    ))
+                }
+            };
+
+            AddressResult {
+                scheme: String::from("CREATE2"),
+                deployer: deployer_hex,
+                address: format!(
+                    "0x{}",
+                    encode_hex(create2_address(deployer, salt, hash).as_bytes().to_vec())
+                ),
+                nonce: None,
+                salt: Some(format!("0x{}", encode_hex(salt.to_vec()))),
+                init_code_hash: Some(format!("0x{}", encode_hex(hash.to_vec()))),
+            }
+        }
+        _ => {
+            return Err(Error::GenericError(
+                "exactly one of --nonce, for CREATE, or --salt, for CREATE2, is required."
+                    .to_string(),
+            ))
+        }
+    };
+
+    logger.info(&format!("{} deploys to {} .", result.scheme, result.address));
+
+    Ok(result)
+}
+
+/// Parse `len` bytes of hex, with or without a `0x` prefix. Shorter values, such as a salt of
+/// `0x01`, are left-padded with zeros.
+fn parse_bytes(value: &str, len: usize, name: &str) -> Result<Vec<u8>, Error> {
+    let hex = value.replacen("0x", "", 1);
+    let bytes = decode_hex(&format!("{}{hex}", "0".repeat((len * 2).saturating_sub(hex.len()))))
+        .map_err(|_| Error::GenericError(format!("invalid {name} '{value}'")))?;
+
+    match bytes.len() == len {
+        true => Ok(bytes),
+        false => Err(Error::GenericError(format!("{name} '{value}' isn't {len} bytes"))),
+    }
+}
@@ -0,0 +1,162 @@
+pub mod util;
+
+use std::{
+    io::{stdin, Read},
+    ops::Range,
+    path::Path,
+};
+
+use clap::{AppSettings, Parser};
+use derive_builder::Builder;
+use heimdall_common::{
+    ether::bytecode::{get_bytecode_from_target, split_sections},
+    utils::{
+        io::logging::{set_logger_env, Logger},
+        strings::{decode_hex, encode_hex},
+    },
+};
+
+use crate::{error::Error, minimize::util::basic_blocks};
+
+use self::util::{assemble, replace_block};
+
+#[derive(Debug, Clone, Parser, Builder)]
+#[clap(
+    about = "Assemble mnemonics into bytecode, or patch them into a basic block of a bytecode",
+    after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    global_setting = AppSettings::DeriveDisplayOrder,
+    override_usage = "heimdall asm <TARGET> [--patch <BYTECODE> --block <OFFSET>] [OPTIONS]"
+)]
+pub struct AsmArgs {
+    /// The assembly to assemble, either a file, inline, or `-` to read it from stdin. Any format
+    /// `heimdall disassemble` writes other than JSON can be assembled again.
+    #[clap(required = true)]
+    pub target: String,
+
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// The bytecode to patch, either a file, bytecode, contract address, or ENS name. The basic
+    /// block at --block is replaced with the assembled code, and jumps past it are shifted.
+    #[clap(long, default_value = "", hide_default_value = true)]
+    pub patch: String,
+
+    /// The offset of the basic block to replace, in decimal or `0x` prefixed hex. Any offset
+    /// within the block may be given.
+    #[clap(long, default_value = "0")]
+    pub block: String,
+
+    /// The RPC provider to use for fetching target bytecode.
+    #[clap(long = "rpc-url", short, default_value = "", hide_default_value = true)]
+    pub rpc_url: String,
+
+    /// The output directory to write the output to or 'print' to print to the console
+    #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
+    pub output: String,
+
+    /// The name for the output file
+    #[clap(long, short, default_value = "", hide_default_value = true)]
+    pub name: String,
+}
+
+impl AsmArgsBuilder {
+    pub fn new() -> Self {
+        Self {
+            target: Some(String::new()),
+            verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
+            patch: Some(String::new()),
+            block: Some(String::from("0")),
+            rpc_url: Some(String::new()),
+            output: Some(String::new()),
+            name: Some(String::new()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AsmResult {
+    /// The assembled or patched bytecode, hex encoded
+    pub bytecode: String,
+    /// The bytes the replaced block spanned, when patching
+    pub block: Option<Range<usize>>,
+    /// The number of jump targets which were shifted, when patching
+    pub fixed_jumps: usize,
+}
+
+/// Read the assembly, which may be given inline, as the path to a file, or as `-` to read it
+/// from stdin.
+fn read_source(target: &str) -> Result<String, Error> {
+    if target == "-" {
+        let mut source = String::new();
+        stdin()
+            .read_to_string(&mut source)
+            .map_err(|e| Error::GenericError(format!("failed to read stdin: {e}")))?;
+        return Ok(source)
+    }
+    match Path::new(target).is_file() {
+        true => std::fs::read_to_string(target)
+            .map_err(|e| Error::GenericError(format!("failed to read file '{target}': {e}"))),
+        false => Ok(target.to_string()),
+    }
+}
+
+/// Assemble the given mnemonics into bytecode, so proofs of concept can be crafted without a
+/// compiler. With `--patch`, the assembled code replaces a basic block of an existing bytecode
+/// instead. Only the code is patched, so data and metadata are kept as they are, although the
+/// offsets of any `CODECOPY`s of them aren't shifted.
+pub async fn asm(args: AsmArgs) -> Result<AsmResult, Error> {
+    set_logger_env(&args.verbose);
+
+    // get a new logger
+    let (logger, _) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    let source = read_source(&args.target)?;
+    if args.patch.is_empty() {
+        let bytecode = assemble(&source, 0)?;
+        logger.info(&format!("assembled {} bytes successfully.", bytecode.len()));
+
+        return Ok(AsmResult { bytecode: encode_hex(bytecode), block: None, fixed_jumps: 0 })
+    }
+
+    let offset = match args.block.strip_prefix("0x") {
+        Some(hex) => usize::from_str_radix(hex, 16).ok(),
+        None => args.block.parse::<usize>().ok(),
+    }
+    .ok_or_else(|| Error::GenericError(format!("invalid block offset '{}'", args.block)))?;
+
+    let patched_bytecode = get_bytecode_from_target(&args.patch, &args.rpc_url)
+        .await
+        .map_err(|e| Error::GenericError(e.to_string()))?;
+    let bytecode = decode_hex(&patched_bytecode.replacen("0x", "", 1))
+        .map_err(|e| Error::GenericError(format!("failed to decode bytecode: {e}")))?;
+    let sections = split_sections(&bytecode);
+
+    // the replacement's labels are offsets in the patched code, so it's assembled where the
+    // block begins
+    let block_start = basic_blocks(&bytecode[..sections.code])
+        .into_iter()
+        .find(|block| block.contains(&offset))
+        .map(|block| block.start)
+        .unwrap_or(offset);
+    let replacement = assemble(&source, block_start)?;
+    let mut patch = replace_block(&bytecode[..sections.code], offset, &replacement)?;
+    patch.bytecode.extend_from_slice(&bytecode[sections.code..]);
+
+    logger.info(&format!(
+        "replaced {} bytes at 0x{:x} with {} bytes, shifting {} jump targets.",
+        patch.block.len(),
+        patch.block.start,
+        replacement.len(),
+        patch.fixed_jumps
+    ));
+
+    Ok(AsmResult {
+        bytecode: encode_hex(patch.bytecode),
+        block: Some(patch.block),
+        fixed_jumps: patch.fixed_jumps,
+    })
+}
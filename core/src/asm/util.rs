@@ -0,0 +1,352 @@
+use std::collections::{HashMap, HashSet};
+
+use heimdall_common::{ether::evm::core::opcodes::Opcode, utils::strings::decode_hex};
+
+use crate::{error::Error, minimize::util::basic_blocks};
+
+/// The opcode with the given mnemonic, e.g. `PUSH1` or `JUMPDEST`. `KECCAK256` is accepted for
+/// `SHA3`, and undefined opcodes are named by their byte, i.e. `UNKNOWN_0c`.
+pub fn opcode_by_name(name: &str) -> Option<Opcode> {
+    let name = match name.to_uppercase().as_str() {
+        "KECCAK256" => String::from("SHA3"),
+        name => name.to_string(),
+    };
+    if let Some(code) = name.strip_prefix("UNKNOWN_") {
+        return u8::from_str_radix(code, 16)
+            .ok()
+            .map(Opcode::new)
+            .filter(|opcode| opcode.name == "unknown")
+    }
+    (0..=255u8).map(Opcode::new).find(|opcode| opcode.name != "unknown" && opcode.name == name)
+}
+
+/// The number of bytes a PUSH pushes, or `None` for any other opcode.
+fn push_size(opcode: &Opcode) -> Option<usize> {
+    opcode.name.strip_prefix("PUSH").and_then(|size| size.parse().ok())
+}
+
+/// A line of assembly, before labels are resolved.
+enum Item {
+    /// An opcode, and what it pushes
+    Instruction(Opcode, Option<Operand>),
+    /// Raw bytes, such as a `DATA` or `METADATA` region
+    Bytes(Vec<u8>),
+}
+
+enum Operand {
+    Value(Vec<u8>),
+    Label(String),
+}
+
+impl Item {
+    fn size(&self) -> usize {
+        match self {
+            Item::Instruction(opcode, _) => 1 + push_size(opcode).unwrap_or(0),
+            Item::Bytes(bytes) => bytes.len(),
+        }
+    }
+}
+
+/// Decode a hex operand, with or without its `0x` prefix, dropping leading zero bytes.
+fn parse_value(operand: &str) -> Option<Vec<u8>> {
+    let operand = operand.strip_prefix("0x").unwrap_or(operand);
+    let padded = match operand.len() % 2 {
+        0 => operand.to_string(),
+        _ => format!("0{operand}"),
+    };
+    let bytes = decode_hex(&padded).ok()?;
+    Some(bytes.into_iter().skip_while(|byte| *byte == 0).collect())
+}
+
+/// Assemble the given mnemonics into bytecode, as though it begins at `offset`. This is the
+/// inverse of `heimdall disassemble`, and accepts any of its formats other than JSON:
+///
+///  - one instruction per line, optionally preceded by its program counter, which is ignored,
+///  - PUSH operands in hex, with or without `0x`. A bare `PUSH` is sized to fit its operand,
+///  - `name:` labels an offset, which a PUSH of `name` pushes. A bare `PUSH` of a label is a
+///    `PUSH2`,
+///  - `DATA`, `STRING`, `JUMPTABLE`, and `METADATA` followed by raw bytes in hex,
+///  - anything after a `;` is a comment.
+///
+/// ```
+/// use heimdall_core::asm::util::assemble;
+///
+/// let bytecode = assemble("PUSH end\nJUMP\nend:\nJUMPDEST", 0).unwrap();
+/// assert_eq!(bytecode, vec![0x61, 0x00, 0x04, 0x56, 0x5b]);
+/// ```
+pub fn assemble(source: &str, offset: usize) -> Result<Vec<u8>, Error> {
+    let lines = source
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.split(';').next().unwrap_or_default().trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .collect::<Vec<_>>();
+    let label_names = lines
+        .iter()
+        .filter_map(|(_, line)| line.strip_suffix(':'))
+        .map(|label| label.trim().to_string())
+        .collect::<HashSet<String>>();
+
+    // lay out each instruction, so labels can be resolved once every offset is known
+    let mut items = Vec::new();
+    let mut labels = HashMap::new();
+    let mut program_counter = offset;
+    for (line_number, line) in lines {
+        let error =
+            |message: &str| Error::GenericError(format!("line {line_number}: {message}: '{line}'"));
+
+        if let Some(label) = line.strip_suffix(':') {
+            if labels.insert(label.trim().to_string(), program_counter).is_some() {
+                return Err(error("label is defined more than once"))
+            }
+            continue
+        }
+
+        let mut tokens = line.split_whitespace().collect::<Vec<_>>();
+
+        // skip the program counter of disassembled instructions, i.e. `000004 JUMPDEST`
+        if tokens.len() > 1 &&
+            opcode_by_name(tokens[0]).is_none() &&
+            tokens[0].chars().all(|c| c.is_ascii_hexdigit())
+        {
+            tokens.remove(0);
+        }
+
+        let item = match (tokens[0].to_uppercase().as_str(), &tokens[1..]) {
+            ("DATA" | "STRING" | "JUMPTABLE" | "METADATA", [bytes]) => Item::Bytes(
+                decode_hex(bytes.strip_prefix("0x").unwrap_or(bytes))
+                    .map_err(|_| error("invalid bytes"))?,
+            ),
+            ("PUSH", [operand]) => match label_names.contains(*operand) {
+                true => {
+                    Item::Instruction(Opcode::new(0x61), Some(Operand::Label(operand.to_string())))
+                }
+                false => {
+                    let value = parse_value(operand).ok_or_else(|| error("invalid operand"))?;
+                    if value.len() > 32 {
+                        return Err(error("operand is larger than 32 bytes"))
+                    }
+                    Item::Instruction(
+                        Opcode::new(0x5f + value.len().max(1) as u8),
+                        Some(Operand::Value(value)),
+                    )
+                }
+            },
+            (name, operands) => {
+                let opcode = opcode_by_name(name).ok_or_else(|| error("unknown opcode"))?;
+                match (push_size(&opcode), operands) {
+                    (Some(0) | None, []) => Item::Instruction(opcode, None),
+                    (Some(size), [operand]) if size > 0 => {
+                        let operand = match label_names.contains(*operand) {
+                            true => Operand::Label(operand.to_string()),
+                            false => {
+                                let value =
+                                    parse_value(operand).ok_or_else(|| error("invalid operand"))?;
+                                if value.len() > size {
+                                    return Err(error("operand doesn't fit"))
+                                }
+                                Operand::Value(value)
+                            }
+                        };
+                        Item::Instruction(opcode, Some(operand))
+                    }
+                    (Some(size), []) if size > 0 => return Err(error("missing operand")),
+                    _ => return Err(error("unexpected operand")),
+                }
+            }
+        };
+
+        program_counter += item.size();
+        items.push((line_number, item));
+    }
+
+    let mut bytecode = Vec::new();
+    for (line_number, item) in items {
+        match item {
+            Item::Bytes(bytes) => bytecode.extend(bytes),
+            Item::Instruction(opcode, operand) => {
+                bytecode.push(opcode.code);
+                let size = push_size(&opcode).unwrap_or(0);
+                let value = match operand {
+                    Some(Operand::Value(value)) => value,
+                    Some(Operand::Label(label)) => labels[&label]
+                        .to_be_bytes()
+                        .into_iter()
+                        .skip_while(|byte| *byte == 0)
+                        .collect(),
+                    None => Vec::new(),
+                };
+                if value.len() > size {
+                    return Err(Error::GenericError(format!(
+                        "line {line_number}: label's offset doesn't fit in {}",
+                        opcode.name
+                    )))
+                }
+                bytecode.extend(vec![0; size - value.len()]);
+                bytecode.extend(value);
+            }
+        }
+    }
+
+    Ok(bytecode)
+}
+
+/// Shift the targets of jumps in `code` after its size changed by `delta` at `from`, returning
+/// how many were shifted. A PUSH of a JUMPDEST which moved is assumed to push a jump target,
+/// whether the jump follows it or, like a return address, comes later. Pushes in `skip` aren't
+/// changed.
+pub fn fix_jump_targets(
+    code: &mut [u8],
+    from: usize,
+    delta: isize,
+    skip: std::ops::Range<usize>,
+) -> Result<usize, Error> {
+    let mut instructions = Vec::new();
+    let mut program_counter = 0;
+    while program_counter < code.len() {
+        let opcode = Opcode::new(code[program_counter]);
+        let size = push_size(&opcode).unwrap_or(0);
+        instructions.push((program_counter, opcode, size));
+        program_counter += 1 + size;
+    }
+
+    // the targets which moved, as they were before the change
+    let jumpdests = instructions
+        .iter()
+        .filter(|(pc, opcode, _)| *pc >= from && opcode.name == "JUMPDEST")
+        .map(|(pc, ..)| (*pc as isize - delta) as usize)
+        .collect::<HashSet<usize>>();
+
+    let mut fixed = 0;
+    for (pc, _, size) in instructions {
+        if size == 0 || skip.contains(&pc) || pc + 1 + size > code.len() {
+            continue
+        }
+        let pushed = &code[pc + 1..pc + 1 + size];
+        if pushed.iter().take(size.saturating_sub(8)).any(|byte| *byte != 0) {
+            continue
+        }
+        let target = pushed.iter().fold(0usize, |acc, byte| acc << 8 | *byte as usize);
+        if !jumpdests.contains(&target) {
+            continue
+        }
+
+        let mut shifted = (target as isize + delta) as usize;
+        if (usize::BITS - shifted.leading_zeros()).div_ceil(8) as usize > size {
+            return Err(Error::GenericError(format!(
+                "jump target 0x{shifted:x} at 0x{pc:x} no longer fits in PUSH{size}"
+            )))
+        }
+        for byte in code[pc + 1..pc + 1 + size].iter_mut().rev() {
+            *byte = shifted as u8;
+            shifted >>= 8;
+        }
+        fixed += 1;
+    }
+
+    Ok(fixed)
+}
+
+/// The result of replacing a basic block.
+#[derive(Debug, Clone)]
+pub struct Patch {
+    pub bytecode: Vec<u8>,
+    /// The bytes the replaced block spanned
+    pub block: std::ops::Range<usize>,
+    /// The number of jump targets which were shifted
+    pub fixed_jumps: usize,
+}
+
+/// Replace the basic block of `code` containing `offset` with `replacement`, shifting the
+/// targets of jumps to the code after it. Jumps in the replacement are left as they are, since
+/// they're assembled for where the block begins.
+pub fn replace_block(code: &[u8], offset: usize, replacement: &[u8]) -> Result<Patch, Error> {
+    let block =
+        basic_blocks(code).into_iter().find(|block| block.contains(&offset)).ok_or_else(|| {
+            Error::GenericError(format!("offset 0x{offset:x} is outside of the code"))
+        })?;
+
+    // the block may be jumped to, so it must still begin with a JUMPDEST
+    if code[block.start] == 0x5b && replacement.first() != Some(&0x5b) {
+        return Err(Error::GenericError(format!(
+            "the block at 0x{:x} is a jump target, so its replacement must begin with JUMPDEST",
+            block.start
+        )))
+    }
+
+    let mut bytecode = code[..block.start].to_vec();
+    bytecode.extend_from_slice(replacement);
+    bytecode.extend_from_slice(&code[block.end..]);
+
+    let delta = replacement.len() as isize - block.len() as isize;
+    let new_end = block.start + replacement.len();
+    let fixed_jumps = match delta {
+        0 => 0,
+        _ => fix_jump_targets(&mut bytecode, new_end, delta, block.start..new_end)?,
+    };
+
+    Ok(Patch { bytecode, block, fixed_jumps })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::asm::util::*;
+
+    #[test]
+    fn test_assemble() {
+        let bytecode = assemble(
+            "; a comment\nPUSH1 0x80\nPUSH 40\nMSTORE ; trailing\nPUSH2 dest\nJUMP\ndest:\nJUMPDEST\nSTOP",
+            0,
+        )
+        .unwrap();
+
+        assert_eq!(bytecode, decode_hex("6080604052610009565b00").unwrap());
+    }
+
+    #[test]
+    fn test_assemble_disassembly() {
+        // the flat format counts instructions by their last byte, and ends with the data
+        let bytecode =
+            assemble("0 JUMPDEST \n2 PUSH1 00\n3 JUMP \n4 DATA 7f0102a26469706673ffff\n", 0)
+                .unwrap();
+
+        assert_eq!(bytecode, decode_hex("5b6000567f0102a26469706673ffff").unwrap());
+    }
+
+    #[test]
+    fn test_assemble_at_offset() {
+        let bytecode = assemble("here:\nJUMPDEST\nPUSH here\nJUMP", 0x10).unwrap();
+
+        assert_eq!(bytecode, vec![0x5b, 0x61, 0x00, 0x10, 0x56]);
+    }
+
+    #[test]
+    fn test_assemble_errors() {
+        for source in ["PUSH1 0x0102", "ADD 0x01", "PUSH2", "FOO", "a:\na:", "PUSH1 zz"] {
+            assert!(assemble(source, 0).is_err(), "{source}");
+        }
+    }
+
+    #[test]
+    fn test_replace_block() {
+        // PUSH1 0x04 JUMP | STOP | JUMPDEST STOP, where the STOP is replaced with PUSH1 0x00 POP
+        // STOP, moving the JUMPDEST from 0x04 to 0x07
+        let code = decode_hex("600456005b00").unwrap();
+        let patch = replace_block(&code, 3, &decode_hex("60005000").unwrap()).unwrap();
+
+        assert_eq!(patch.block, 3..4);
+        assert_eq!(patch.fixed_jumps, 1);
+        assert_eq!(patch.bytecode, decode_hex("600756600050005b00").unwrap());
+    }
+
+    #[test]
+    fn test_replace_jump_target() {
+        let code = decode_hex("600456005b00").unwrap();
+
+        // the JUMPDEST's block can't lose its JUMPDEST
+        assert!(replace_block(&code, 4, &[0x00]).is_err());
+        let patch = replace_block(&code, 4, &[0x5b, 0x60, 0x01, 0x00]).unwrap();
+        assert_eq!(patch.bytecode, decode_hex("600456005b600100").unwrap());
+        assert_eq!(patch.fixed_jumps, 0);
+    }
+}
@@ -0,0 +1,182 @@
+pub mod output;
+pub mod util;
+
+use std::{collections::BTreeMap, time::Duration};
+
+use clap::{AppSettings, Parser};
+use derive_builder::Builder;
+use heimdall_common::{
+    ether::{
+        bytecode::get_bytecode_from_target, evm::core::vm::VM, selectors::find_function_selectors,
+    },
+    utils::{
+        io::{
+            crash::enter_pass,
+            exit::record_partial_analysis,
+            logging::{set_logger_env, Logger},
+        },
+        threading::run_with_timeout,
+    },
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    disassemble::{disassemble, DisassemblerArgs},
+    error::Error,
+};
+
+use self::util::{CallSite, CallTargetBuilder};
+
+#[derive(Debug, Clone, Parser, Builder)]
+#[clap(
+    about = "List the external calls each function makes, and who controls where they go",
+    after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    global_setting = AppSettings::DeriveDisplayOrder,
+    override_usage = "heimdall calls <TARGET> [OPTIONS]"
+)]
+pub struct CallsArgs {
+    /// The target to list the calls of, either a file, bytecode, contract address, or ENS name.
+    #[clap(required = true)]
+    pub target: String,
+
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// The RPC provider to use for fetching target bytecode.
+    #[clap(long = "rpc-url", short, default_value = "", hide_default_value = true)]
+    pub rpc_url: String,
+
+    /// The format to generate, one of 'json' or 'markdown'.
+    #[clap(long, short, default_value = "json")]
+    pub format: String,
+
+    /// The output directory to write the output to or 'print' to print to the console
+    #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
+    pub output: String,
+
+    /// The name for the output file
+    #[clap(long, short, default_value = "", hide_default_value = true)]
+    pub name: String,
+
+    /// The timeout for each function's symbolic execution in milliseconds.
+    #[clap(long, short, default_value = "10000", hide_default_value = true)]
+    pub timeout: u64,
+}
+
+impl CallsArgsBuilder {
+    pub fn new() -> Self {
+        Self {
+            target: Some(String::new()),
+            verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
+            rpc_url: Some(String::new()),
+            format: Some(String::from("json")),
+            output: Some(String::new()),
+            name: Some(String::new()),
+            timeout: Some(10000),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallsResult {
+    /// The external calls each function makes, by function
+    pub functions: BTreeMap<String, Vec<CallSite>>,
+}
+
+/// List the external calls each function of the target makes. Each call is classified by its
+/// kind, where its target address comes from, and whether it forwards value, so calls whose
+/// target the caller chooses stand out.
+pub async fn calls(args: CallsArgs) -> Result<CallsResult, Error> {
+    set_logger_env(&args.verbose);
+
+    // get a new logger
+    let (logger, _) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    // check the format before analyzing, so an invalid one fails fast
+    if !["json", "markdown"].contains(&args.format.as_str()) {
+        return Err(Error::GenericError(format!(
+            "invalid format '{}' . expected one of: json, markdown.",
+            args.format
+        )))
+    }
+
+    let contract_bytecode = get_bytecode_from_target(&args.target, &args.rpc_url)
+        .await
+        .map_err(|e| Error::GenericError(e.to_string()))?;
+
+    enter_pass("disassemble");
+    let disassembled_bytecode = disassemble(DisassemblerArgs {
+        target: contract_bytecode.clone(),
+        verbose: args.verbose.clone(),
+        rpc_url: args.rpc_url.clone(),
+        decimal_counter: false,
+        format: String::from("asm"),
+        verify: false,
+        name: String::new(),
+        output: String::new(),
+    })
+    .await
+    .map_err(|e| Error::GenericError(format!("failed to disassemble bytecode: {e}")))?;
+
+    let evm = VM::new(
+        contract_bytecode,
+        String::from("0x"),
+        String::from("0x6865696d64616c6c000000000061646472657373"),
+        String::from("0x6865696d64616c6c0000000000006f726967696e"),
+        String::from("0x6865696d64616c6c00000000000063616c6c6572"),
+        0,
+        u128::MAX,
+    );
+
+    let mut selectors = find_function_selectors(&evm, &disassembled_bytecode)
+        .into_iter()
+        .collect::<Vec<(String, u128)>>();
+    selectors.sort();
+
+    let mut builder = CallTargetBuilder::new();
+    for (selector, entry_point) in selectors {
+        enter_pass(&format!("symbolic execution (0x{selector})"));
+        let mut evm_clone = evm.clone();
+        let selector_clone = selector.clone();
+        match run_with_timeout(
+            move || evm_clone.symbolic_exec_selector(&selector_clone, entry_point),
+            Duration::from_millis(args.timeout),
+        ) {
+            Some((trace, _)) => builder.add_trace(&trace, &format!("0x{selector}")),
+            None => record_partial_analysis(),
+        }
+    }
+
+    enter_pass("symbolic execution (fallback)");
+    let evm_clone = evm.clone();
+    match run_with_timeout(move || evm_clone.symbolic_exec(), Duration::from_millis(args.timeout)) {
+        Some((trace, _)) => {
+            let mut contract_wide = CallTargetBuilder::new();
+            contract_wide.add_trace(&trace, "fallback");
+            builder.add_unattributed(contract_wide, "fallback");
+        }
+        None => record_partial_analysis(),
+    }
+
+    let functions = builder.build();
+    logger.info(&format!(
+        "found {} external calls across {} functions.",
+        functions.values().map(|calls| calls.len()).sum::<usize>(),
+        functions.len()
+    ));
+    for (function, calls) in &functions {
+        for call in calls.iter().filter(|call| call.is_dangerous()) {
+            logger.warn(&format!(
+                "{function} makes a {} at pc {:#x} to an address its caller chooses.",
+                call.kind.name(),
+                call.pc
+            ));
+        }
+    }
+
+    Ok(CallsResult { functions })
+}
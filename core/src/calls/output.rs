@@ -0,0 +1,121 @@
+use heimdall_common::{
+    resources::labels::{get_address_label, get_selector_label},
+    utils::strings::encode_hex,
+};
+
+use super::{
+    util::{CallSite, CallTarget, CallValue},
+    CallsResult,
+};
+
+/// Write the calls as markdown, with a table of each function's calls. Calls which let the
+/// caller choose the code run in the contract's context are highlighted in red.
+pub fn build_markdown(result: &CallsResult) -> String {
+    let mut output = vec![String::from("# External calls"), String::new()];
+
+    let dangerous = result.functions.values().flatten().filter(|call| call.is_dangerous()).count();
+    if dangerous > 0 {
+        output.push(red(&format!(
+            "{dangerous} delegatecalls or callcodes go to an address the caller chooses."
+        )));
+        output.push(String::new());
+    }
+
+    output.push(String::from("| function | pc | kind | target | value |"));
+    output.push(String::from("| --- | --- | --- | --- | --- |"));
+    for (function, calls) in &result.functions {
+        let name = match get_selector_label(function) {
+            Some(label) => format!("`{label}` ({function})"),
+            None => format!("`{function}`"),
+        };
+        if calls.is_empty() {
+            output.push(format!("| {name} | - | - | - | - |"));
+        }
+        for call in calls {
+            output.push(call_row(&name, call));
+        }
+    }
+
+    output.join("\n")
+}
+
+/// A row of the calls table.
+fn call_row(function: &str, call: &CallSite) -> String {
+    let (kind, target) = match call.is_dangerous() {
+        true => (red(call.kind.name()), red(&describe_target(&call.target))),
+        false => (call.kind.name().to_string(), describe_target(&call.target)),
+    };
+    let value = match call.value {
+        CallValue::None => "none",
+        CallValue::Constant => "constant",
+        CallValue::CallValue => "msg.value",
+        CallValue::Computed => "computed",
+    };
+
+    format!("| {function} | {:#x} | {kind} | {target} | {value} |", call.pc)
+}
+
+/// Where a call's target comes from, e.g. `storage slot 0x1` or `calldata argument 0`.
+fn describe_target(target: &CallTarget) -> String {
+    match target {
+        CallTarget::Hardcoded { address } => {
+            let address = format!("0x{}", encode_hex(address.as_bytes().to_vec()));
+            match get_address_label(&address) {
+                Some(label) => format!("`{address}` ({label})"),
+                None => format!("`{address}`"),
+            }
+        }
+        CallTarget::Storage { slot } => format!("storage slot {slot:#x}"),
+        CallTarget::Calldata { argument: Some(argument) } => {
+            format!("calldata argument {argument}")
+        }
+        CallTarget::Calldata { argument: None } => String::from("calldata"),
+        CallTarget::Caller => String::from("msg.sender"),
+        CallTarget::Computed => String::from("computed"),
+    }
+}
+
+/// Markdown renderers which allow inline HTML show this in red.
+fn red(text: &str) -> String {
+    format!("<span style=\"color:red\">**{text}**</span>")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::{calls::output::*, map::util::ReferenceKind};
+
+    #[test]
+    fn test_build_markdown() {
+        let result = CallsResult {
+            functions: BTreeMap::from([
+                (
+                    String::from("0x00000001"),
+                    vec![CallSite {
+                        pc: 0x2a,
+                        kind: ReferenceKind::DelegateCall,
+                        target: CallTarget::Calldata { argument: Some(0) },
+                        value: CallValue::None,
+                    }],
+                ),
+                (String::from("0x00000002"), Vec::new()),
+            ]),
+        };
+
+        assert_eq!(
+            build_markdown(&result),
+            [
+                "# External calls",
+                "",
+                "<span style=\"color:red\">**1 delegatecalls or callcodes go to an address the caller chooses.**</span>",
+                "",
+                "| function | pc | kind | target | value |",
+                "| --- | --- | --- | --- | --- |",
+                "| `0x00000001` | 0x2a | <span style=\"color:red\">**delegatecall**</span> | <span style=\"color:red\">**calldata argument 0**</span> | none |",
+                "| `0x00000002` | - | - | - | - |",
+            ]
+            .join("\n")
+        );
+    }
+}
@@ -0,0 +1,327 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use ethers::types::{Address, U256};
+use heimdall_common::ether::evm::{
+    core::opcodes::{WrappedInput, WrappedOpcode},
+    ext::exec::VMTrace,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::map::util::ReferenceKind;
+
+/// Where the address a call is made to comes from.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(tag = "source", rename_all = "snake_case")]
+pub enum CallTarget {
+    /// The address is a constant in the bytecode
+    Hardcoded { address: Address },
+    /// The address is read from a storage slot
+    Storage { slot: U256 },
+    /// The address is passed in calldata, as the argument at `argument` if its offset is constant
+    Calldata { argument: Option<usize> },
+    /// The address is `msg.sender`
+    Caller,
+    /// The address is computed some other way, e.g. returned by an earlier call
+    Computed,
+}
+
+impl CallTarget {
+    /// Whether whoever calls the function chooses the address.
+    pub fn is_caller_controlled(&self) -> bool {
+        matches!(self, CallTarget::Calldata { .. } | CallTarget::Caller)
+    }
+}
+
+/// How much value a call forwards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CallValue {
+    /// No value is sent, either because the call kind can't send any or because it's zero
+    None,
+    /// A constant amount of wei
+    Constant,
+    /// The value the function was called with, i.e. `msg.value`
+    CallValue,
+    /// An amount computed at runtime, e.g. a balance or an argument
+    Computed,
+}
+
+/// An external call made by a function.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct CallSite {
+    pub pc: u128,
+    pub kind: ReferenceKind,
+    pub target: CallTarget,
+    pub value: CallValue,
+}
+
+impl CallSite {
+    /// Whether the caller chooses the code which runs in the contract's own context.
+    pub fn is_dangerous(&self) -> bool {
+        matches!(self.kind, ReferenceKind::DelegateCall | ReferenceKind::CallCode) &&
+            self.target.is_caller_controlled()
+    }
+}
+
+/// Accumulates the external calls each function makes from its traces.
+#[derive(Debug, Clone, Default)]
+pub struct CallTargetBuilder {
+    /// The key read by each SLOAD, by the operation it pushed
+    loads: HashMap<WrappedOpcode, U256>,
+    calls: BTreeMap<String, BTreeSet<CallSite>>,
+}
+
+impl CallTargetBuilder {
+    pub fn new() -> CallTargetBuilder {
+        CallTargetBuilder::default()
+    }
+
+    /// Record every call in the trace as being made by `function`.
+    pub fn add_trace(&mut self, trace: &VMTrace, function: &str) {
+        let calls = self.calls.entry(function.to_string()).or_default();
+        for operation in &trace.operations {
+            let instruction = &operation.last_instruction;
+
+            // SLOAD
+            if instruction.opcode == 0x54 {
+                if let (Some(key), Some(key_operation)) =
+                    (instruction.inputs.first(), instruction.input_operations.first())
+                {
+                    self.loads.insert(
+                        WrappedOpcode::new(0x54, vec![WrappedInput::Opcode(key_operation.clone())]),
+                        *key,
+                    );
+                }
+                continue
+            }
+
+            let Some(kind) = ReferenceKind::from_opcode(instruction.opcode) else { continue };
+            let (Some(address), Some(address_operation)) =
+                (instruction.inputs.get(1), instruction.input_operations.get(1))
+            else {
+                continue
+            };
+
+            // only CALL and CALLCODE take a value
+            let value = match kind {
+                ReferenceKind::Call | ReferenceKind::CallCode => {
+                    match (instruction.inputs.get(2), instruction.input_operations.get(2)) {
+                        (Some(value), Some(operation)) => call_value(*value, operation),
+                        _ => CallValue::Computed,
+                    }
+                }
+                _ => CallValue::None,
+            };
+
+            calls.insert(CallSite {
+                pc: instruction.instruction - 1,
+                kind,
+                target: find_target(&self.loads, *address, address_operation),
+                value,
+            });
+        }
+
+        for child in &trace.children {
+            self.add_trace(child, function);
+        }
+    }
+
+    /// Add the calls recorded by `other` which this builder doesn't already attribute to some
+    /// function, as made by `function`. A contract-wide trace also passes through every
+    /// function, so this attributes only the calls no function made, e.g. a proxy's fallback.
+    pub fn add_unattributed(&mut self, other: CallTargetBuilder, function: &str) {
+        let attributed = self
+            .calls
+            .values()
+            .flat_map(|calls| calls.iter().map(|call| call.pc))
+            .collect::<BTreeSet<u128>>();
+        let unattributed = other
+            .calls
+            .into_values()
+            .flatten()
+            .filter(|call| !attributed.contains(&call.pc))
+            .collect::<BTreeSet<CallSite>>();
+
+        if !unattributed.is_empty() {
+            self.calls.entry(function.to_string()).or_default().extend(unattributed);
+        }
+    }
+
+    /// The calls each function makes, ordered by pc. Every function added is listed, including
+    /// those which make no calls.
+    pub fn build(self) -> BTreeMap<String, Vec<CallSite>> {
+        self.calls
+            .into_iter()
+            .map(|(function, calls)| {
+                let mut calls = calls.into_iter().collect::<Vec<CallSite>>();
+                calls.sort_by_key(|call| call.pc);
+                (function, calls)
+            })
+            .collect()
+    }
+}
+
+/// Where the address of a call comes from. Calldata and the caller take precedence over
+/// storage, since a caller-chosen key into a mapping of addresses is still caller-controlled.
+fn find_target(
+    loads: &HashMap<WrappedOpcode, U256>,
+    address: U256,
+    operation: &WrappedOpcode,
+) -> CallTarget {
+    if let Some(offset) = find_opcode(operation, 0x35) {
+        let argument = constant(offset)
+            .and_then(|offset| usize::try_from(offset).ok())
+            .filter(|offset| *offset >= 4)
+            .map(|offset| (offset - 4) / 32);
+        return CallTarget::Calldata { argument }
+    }
+    if find_opcode(operation, 0x33).is_some() {
+        return CallTarget::Caller
+    }
+    if let Some(slot) = find_load(loads, operation) {
+        return CallTarget::Storage { slot }
+    }
+    match is_constant(operation) {
+        true => {
+            let mut bytes = [0u8; 32];
+            address.to_big_endian(&mut bytes);
+            CallTarget::Hardcoded { address: Address::from_slice(&bytes[12..]) }
+        }
+        false => CallTarget::Computed,
+    }
+}
+
+/// How much value a call with the given value operation forwards.
+fn call_value(value: U256, operation: &WrappedOpcode) -> CallValue {
+    if find_opcode(operation, 0x34).is_some() {
+        return CallValue::CallValue
+    }
+    match (is_constant(operation), value.is_zero()) {
+        (true, true) => CallValue::None,
+        (true, false) => CallValue::Constant,
+        (false, _) => CallValue::Computed,
+    }
+}
+
+/// The first operation with the given opcode in the operation's tree, returning the operation
+/// which is its first input.
+fn find_opcode(operation: &WrappedOpcode, opcode: u8) -> Option<&WrappedOpcode> {
+    if operation.opcode.code == opcode {
+        return match operation.inputs.first() {
+            Some(WrappedInput::Opcode(input)) => Some(input),
+            _ => Some(operation),
+        }
+    }
+
+    operation.inputs.iter().find_map(|input| match input {
+        WrappedInput::Opcode(input) => find_opcode(input, opcode),
+        WrappedInput::Raw(_) | WrappedInput::Variable(_) => None,
+    })
+}
+
+/// The slot of the SLOAD the operation's value was derived from, if any.
+fn find_load(loads: &HashMap<WrappedOpcode, U256>, operation: &WrappedOpcode) -> Option<U256> {
+    if let Some(slot) = loads.get(operation) {
+        return Some(*slot)
+    }
+
+    operation.inputs.iter().find_map(|input| match input {
+        WrappedInput::Opcode(operation) => find_load(loads, operation),
+        WrappedInput::Raw(_) | WrappedInput::Variable(_) => None,
+    })
+}
+
+/// The value of a pushed constant.
+fn constant(operation: &WrappedOpcode) -> Option<U256> {
+    match (operation.opcode.code, operation.inputs.first()) {
+        (0x5f..=0x7f, Some(WrappedInput::Raw(value))) => Some(*value),
+        (0x5f, None) => Some(U256::zero()),
+        _ => None,
+    }
+}
+
+/// Whether the operation only combines constants, and so doesn't depend on calldata, storage, or
+/// the environment.
+fn is_constant(operation: &WrappedOpcode) -> bool {
+    matches!(operation.opcode.code, 0x01..=0x1d | 0x5f..=0x7f) &&
+        operation.inputs.iter().all(|input| match input {
+            WrappedInput::Opcode(input) => is_constant(input),
+            WrappedInput::Raw(_) => true,
+            WrappedInput::Variable(_) => false,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use heimdall_common::ether::evm::core::vm::VM;
+
+    use crate::{calls::util::*, map::util::VM_ADDRESSES};
+
+    fn build(bytecode: &str) -> Vec<CallSite> {
+        let vm = VM::new(
+            bytecode.to_string(),
+            String::from("0x"),
+            String::from(VM_ADDRESSES[0]),
+            String::from(VM_ADDRESSES[1]),
+            String::from(VM_ADDRESSES[2]),
+            0,
+            u128::MAX,
+        );
+
+        let mut builder = CallTargetBuilder::new();
+        builder.add_trace(&vm.symbolic_exec().0, "fallback");
+        builder.build().remove("fallback").unwrap_or_default()
+    }
+
+    #[test]
+    fn test_call_targets() {
+        // STATICCALL(gas, sload(1), 0, 0, 0, 0), CALL(gas, 0x1111..11, callvalue, 0, 0, 0, 0), then
+        // DELEGATECALL(gas, calldataload(4), 0, 0, 0, 0)
+        let calls = build(
+            &[
+                "600060006000600060015461fffffa50",
+                "60006000600060003473111111111111111111111111111111111111111161fffff150",
+                "600060006000600060043561fffff450",
+                "00",
+            ]
+            .concat(),
+        );
+
+        assert_eq!(calls.len(), 3);
+        assert_eq!(calls[0].kind, ReferenceKind::StaticCall);
+        assert_eq!(calls[0].target, CallTarget::Storage { slot: U256::one() });
+        assert_eq!(calls[0].value, CallValue::None);
+        assert_eq!(calls[1].target, CallTarget::Hardcoded { address: Address::repeat_byte(0x11) });
+        assert_eq!(calls[1].value, CallValue::CallValue);
+        assert_eq!(calls[2].target, CallTarget::Calldata { argument: Some(0) });
+        assert!(calls[2].is_dangerous());
+        assert!(!calls[0].is_dangerous());
+    }
+
+    #[test]
+    fn test_contract_wide_calls_only_fill_gaps() {
+        let mut builder = CallTargetBuilder::new();
+        builder.calls.entry(String::from("0xaaaaaaaa")).or_default().insert(CallSite {
+            pc: 4,
+            kind: ReferenceKind::Call,
+            target: CallTarget::Caller,
+            value: CallValue::None,
+        });
+
+        let mut contract_wide = CallTargetBuilder::new();
+        for pc in [4, 8] {
+            contract_wide.calls.entry(String::from("fallback")).or_default().insert(CallSite {
+                pc,
+                kind: ReferenceKind::Call,
+                target: CallTarget::Caller,
+                value: CallValue::None,
+            });
+        }
+        builder.add_unattributed(contract_wide, "fallback");
+
+        let calls = builder.build();
+        assert_eq!(calls["0xaaaaaaaa"].len(), 1);
+        assert_eq!(calls["fallback"].len(), 1);
+        assert_eq!(calls["fallback"][0].pc, 8);
+    }
+}
@@ -1,26 +1,100 @@
-use std::{collections::HashMap, sync::Mutex};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{Display, Formatter},
+};
 
 use ethers::prelude::U256;
 use heimdall_common::{ether::evm::ext::exec::VMTrace, utils::strings::encode_hex_reduced};
 use petgraph::{matrix_graph::NodeIndex, Graph};
+use serde::{Deserialize, Serialize};
 
-use lazy_static::lazy_static;
-lazy_static! {
-    static ref INSTRUCTION_NODE_MAP: Mutex<HashMap<u128, NodeIndex<u32>>> =
-        Mutex::new(HashMap::new());
-    static ref CONNECTING_EDGES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+/// An instruction in a [`CfgBlock`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CfgInstruction {
+    pub pc: u128,
+    pub opcode: String,
+    /// The value pushed, if the instruction is a PUSH
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operand: Option<String>,
+}
+
+/// A block of the control flow graph, which runs from a jump's destination up to and including
+/// the next jump, or until execution halts.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CfgBlock {
+    /// The pc of the block's first instruction
+    pub start: u128,
+    /// The pc of the block's last instruction
+    pub end: u128,
+    pub instructions: Vec<CfgInstruction>,
+}
+
+impl Display for CfgBlock {
+    /// The block's assembly, one instruction per line. Instructions are numbered from 1, as the
+    /// graph's labels always have been.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for instruction in &self.instructions {
+            writeln!(
+                f,
+                "{} {} {}",
+                encode_hex_reduced(U256::from(instruction.pc + 1)),
+                instruction.opcode,
+                instruction.operand.as_deref().unwrap_or("")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// An edge of the control flow graph, from a block to a block it can jump or fall through to.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CfgEdge {
+    /// Whether the edge is a jump, rather than falling through a JUMPI which isn't taken
+    pub jump_taken: bool,
+    /// The condition of the JUMPI the edge leaves from, which holds when the jump is taken
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
+}
+
+impl Display for CfgEdge {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.jump_taken)
+    }
+}
+
+/// The blocks and edges already added to a control flow graph. Kept per graph, so graphs can be
+/// built concurrently.
+#[derive(Debug, Default)]
+struct CfgState {
+    instruction_node_map: HashMap<u128, NodeIndex<u32>>,
+    connecting_edges: HashSet<String>,
 }
 
 /// convert a symbolic execution [`VMTrace`] into a [`Graph`] of blocks, illustrating the
 /// control-flow graph found by the symbolic execution engine.
 pub fn build_cfg(
     vm_trace: &VMTrace,
-    contract_cfg: &mut Graph<String, String>,
+    contract_cfg: &mut Graph<CfgBlock, CfgEdge>,
     parent_node: Option<NodeIndex<u32>>,
     jump_taken: bool,
 ) {
-    let mut cfg_node: String = String::new();
-    let mut parent_node = parent_node;
+    add_trace_to_cfg(
+        vm_trace,
+        contract_cfg,
+        parent_node,
+        CfgEdge { jump_taken, condition: None },
+        &mut CfgState::default(),
+    )
+}
+
+fn add_trace_to_cfg(
+    vm_trace: &VMTrace,
+    contract_cfg: &mut Graph<CfgBlock, CfgEdge>,
+    parent_node: Option<NodeIndex<u32>>,
+    edge: CfgEdge,
+    state: &mut CfgState,
+) {
+    let mut cfg_node = CfgBlock::default();
 
     // add the current operations to the cfg
     for operation in &vm_trace.operations {
@@ -28,71 +102,70 @@ pub fn build_cfg(
 
         let opcode_name = instruction.opcode_details.clone().unwrap().name;
 
-        let assembly = format!(
-            "{} {} {}",
-            encode_hex_reduced(U256::from(instruction.instruction)),
-            opcode_name,
-            if opcode_name.contains("PUSH") {
-                encode_hex_reduced(*instruction.outputs.clone().first().unwrap())
-            } else {
-                String::from("")
-            }
-        );
-
-        cfg_node.push_str(&format!("{}\n", &assembly));
+        // the vm records the pc after the instruction's opcode
+        cfg_node.instructions.push(CfgInstruction {
+            pc: instruction.instruction - 1,
+            opcode: opcode_name.to_string(),
+            operand: match opcode_name.contains("PUSH") {
+                true => Some(encode_hex_reduced(*instruction.outputs.clone().first().unwrap())),
+                false => None,
+            },
+        });
     }
+    cfg_node.start = cfg_node.instructions.first().map(|i| i.pc).unwrap_or_default();
+    cfg_node.end = cfg_node.instructions.last().map(|i| i.pc).unwrap_or_default();
 
     // check if the map already contains the current node
-    let mut instruction_node_map = INSTRUCTION_NODE_MAP.lock().unwrap();
     let chunk_index = match vm_trace.operations.first() {
         Some(operation) => operation.last_instruction.instruction,
         None => 0,
     };
 
-    match instruction_node_map.get(&chunk_index) {
-        Some(node_index) => {
-            // this node already exists, so we need to add an edge to it.
-            if let Some(parent_node) = parent_node {
-                // check if the edge already exists
-                let mut connecting_edges = CONNECTING_EDGES.lock().unwrap();
-                let edge = format!("{} -> {}", parent_node.index(), node_index.index());
-                if !connecting_edges.contains(&edge) {
-                    contract_cfg.add_edge(parent_node, *node_index, jump_taken.to_string());
-                    connecting_edges.push(edge);
-                }
-                drop(connecting_edges)
-            }
-        }
+    let node_index = match state.instruction_node_map.get(&chunk_index).copied() {
+        // this node already exists, so only the edge to it is added
+        Some(node_index) => node_index,
         None => {
-            // this node does not exist, so we need to add it to the map and the graph
             let node_index = contract_cfg.add_node(cfg_node);
-
-            if let Some(parent_node) = parent_node {
-                // check if the edge already exists
-                let mut connecting_edges = CONNECTING_EDGES.lock().unwrap();
-                let edge = format!("{} -> {}", parent_node.index(), node_index.index());
-                if !connecting_edges.contains(&edge) {
-                    contract_cfg.add_edge(parent_node, node_index, jump_taken.to_string());
-                    connecting_edges.push(edge);
-                }
-                drop(connecting_edges)
-            }
-
-            instruction_node_map.insert(chunk_index, node_index);
-            parent_node = Some(node_index);
+            state.instruction_node_map.insert(chunk_index, node_index);
+            node_index
         }
     };
+    if let Some(parent_node) = parent_node {
+        // check if the edge already exists
+        let edge_key = format!("{} -> {}", parent_node.index(), node_index.index());
+        if state.connecting_edges.insert(edge_key) {
+            contract_cfg.add_edge(parent_node, node_index, edge);
+        }
+    }
 
-    drop(instruction_node_map);
+    // both of a JUMPI's edges share its condition
+    let condition = vm_trace
+        .operations
+        .last()
+        .filter(|operation| operation.last_instruction.opcode == 0x57)
+        .and_then(|operation| operation.last_instruction.input_operations.get(1))
+        .map(|condition| condition.solidify());
 
     // recurse into the children of the VMTrace map
     for child in vm_trace.children.iter() {
-        build_cfg(
+        add_trace_to_cfg(
             child,
             contract_cfg,
-            parent_node,
-            child.operations.first().unwrap().last_instruction.opcode_details.clone().unwrap().name ==
-                "JUMPDEST",
+            Some(node_index),
+            CfgEdge {
+                jump_taken: child
+                    .operations
+                    .first()
+                    .unwrap()
+                    .last_instruction
+                    .opcode_details
+                    .clone()
+                    .unwrap()
+                    .name ==
+                    "JUMPDEST",
+                condition: condition.clone(),
+            },
+            state,
         );
     }
 }
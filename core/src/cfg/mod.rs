@@ -7,7 +7,13 @@ use heimdall_common::{
         bytecode::get_bytecode_from_target, compiler::detect_compiler,
         selectors::find_function_selectors,
     },
-    utils::threading::run_with_timeout,
+    utils::{
+        io::{
+            crash::enter_pass,
+            exit::{record_failure, ExitStatus},
+        },
+        threading::run_with_timeout,
+    },
 };
 use indicatif::ProgressBar;
 use std::time::Duration;
@@ -17,7 +23,7 @@ use heimdall_common::{ether::evm::core::vm::VM, utils::io::logging::*};
 use petgraph::Graph;
 
 use crate::{
-    cfg::graph::build_cfg,
+    cfg::graph::{build_cfg, CfgBlock, CfgEdge},
     disassemble::{disassemble, DisassemblerArgs},
 };
 
@@ -61,6 +67,11 @@ pub struct CFGArgs {
     /// Timeout for symbolic execution
     #[clap(long, short, default_value = "10000", hide_default_value = true)]
     pub timeout: u64,
+
+    /// The format to generate, one of 'dot' or 'json'. JSON lists each block's instructions, and
+    /// each edge's jump condition.
+    #[clap(long, short, default_value = "dot")]
+    pub format: String,
 }
 
 impl CFGArgsBuilder {
@@ -74,16 +85,21 @@ impl CFGArgsBuilder {
             output: Some(String::new()),
             name: Some(String::new()),
             timeout: Some(10000),
+            format: Some(String::from("dot")),
         }
     }
 }
 
 /// The main entry point for the CFG module. Will generate a control flow graph of the target
 /// bytecode, after performing symbolic execution and discovering all possible execution paths.
-pub async fn cfg(args: CFGArgs) -> Result<Graph<String, String>, Box<dyn std::error::Error>> {
+pub async fn cfg(args: CFGArgs) -> Result<Graph<CfgBlock, CfgEdge>, Box<dyn std::error::Error>> {
     use std::time::Instant;
     let now = Instant::now();
 
+    if !["dot", "json"].contains(&args.format.as_str()) {
+        return Err(format!("invalid format '{}' . expected one of: dot, json.", args.format).into())
+    }
+
     set_logger_env(&args.verbose);
 
     let (logger, mut trace) = Logger::new(match args.verbose.log_level() {
@@ -112,11 +128,14 @@ pub async fn cfg(args: CFGArgs) -> Result<Graph<String, String>, Box<dyn std::er
     let contract_bytecode = get_bytecode_from_target(&args.target, &args.rpc_url).await?;
 
     // disassemble the bytecode
+    enter_pass("disassemble");
     let disassembled_bytecode = disassemble(DisassemblerArgs {
         target: contract_bytecode.clone(),
         verbose: args.verbose.clone(),
         rpc_url: args.rpc_url.clone(),
         decimal_counter: false,
+        format: String::from("asm"),
+        verify: false,
         name: String::from(""),
         output: String::from(""),
     })
@@ -187,7 +206,7 @@ pub async fn cfg(args: CFGArgs) -> Result<Graph<String, String>, Box<dyn std::er
     progress.set_style(logger.info_spinner());
 
     // create a new petgraph StableGraph
-    let mut contract_cfg = Graph::<String, String>::new();
+    let mut contract_cfg = Graph::<CfgBlock, CfgEdge>::new();
 
     // add the call to the trace
     let map_trace = trace.add_call(
@@ -200,11 +219,13 @@ pub async fn cfg(args: CFGArgs) -> Result<Graph<String, String>, Box<dyn std::er
     );
 
     // get a map of possible jump destinations
+    enter_pass("symbolic execution");
     let (map, jumpdest_count) =
         match run_with_timeout(move || evm.symbolic_exec(), Duration::from_millis(args.timeout)) {
             Some(map) => map,
             None => {
                 logger.error("symbolic execution timed out.");
+                record_failure(ExitStatus::PartialAnalysis);
                 return Err("symbolic execution timed out.".into())
             }
         };
@@ -217,6 +238,7 @@ pub async fn cfg(args: CFGArgs) -> Result<Graph<String, String>, Box<dyn std::er
     );
 
     debug_max!("building control flow graph from symbolic execution trace");
+    enter_pass("build cfg");
     build_cfg(&map, &mut contract_cfg, None, false);
 
     progress.finish_and_clear();
@@ -1,9 +1,29 @@
-use petgraph::{dot::Dot, graph::Graph};
+use petgraph::{dot::Dot, graph::Graph, visit::EdgeRef};
+use serde::{Deserialize, Serialize};
 
-use super::CFGArgs;
+use super::{
+    graph::{CfgBlock, CfgEdge},
+    CFGArgs,
+};
+
+/// The control flow graph in a machine-readable form, with blocks in pc order.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CfgJson {
+    pub blocks: Vec<CfgBlock>,
+    pub edges: Vec<CfgJsonEdge>,
+}
+
+/// An edge between the blocks starting at `from` and `to`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CfgJsonEdge {
+    pub from: u128,
+    pub to: u128,
+    #[serde(flatten)]
+    pub edge: CfgEdge,
+}
 
 /// Write the generated CFG to a file in the `dot` graphviz format.
-pub fn build_cfg(contract_cfg: &Graph<String, String>, args: &CFGArgs) -> String {
+pub fn build_cfg(contract_cfg: &Graph<CfgBlock, CfgEdge>, args: &CFGArgs) -> String {
     let output = format!("{}", Dot::with_config(&contract_cfg, &[]));
 
     // find regex matches and replace
@@ -26,3 +46,22 @@ pub fn build_cfg(contract_cfg: &Graph<String, String>, args: &CFGArgs) -> String
 
     output
 }
+
+/// Convert the generated CFG into its JSON form, with each edge referring to blocks by their
+/// first pc.
+pub fn build_cfg_json(contract_cfg: &Graph<CfgBlock, CfgEdge>) -> CfgJson {
+    let mut blocks = contract_cfg.node_weights().cloned().collect::<Vec<_>>();
+    blocks.sort_by_key(|block| block.start);
+
+    let mut edges = contract_cfg
+        .edge_references()
+        .map(|edge| CfgJsonEdge {
+            from: contract_cfg[edge.source()].start,
+            to: contract_cfg[edge.target()].start,
+            edge: edge.weight().clone(),
+        })
+        .collect::<Vec<_>>();
+    edges.sort_by_key(|edge| (edge.from, edge.to));
+
+    CfgJson { blocks, edges }
+}
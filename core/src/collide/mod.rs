@@ -0,0 +1,174 @@
+pub mod search;
+
+use std::time::Instant;
+
+use clap::{AppSettings, Parser};
+use derive_builder::Builder;
+use heimdall_common::utils::{
+    io::{
+        file::read_file,
+        logging::{set_logger_env, Logger},
+    },
+    strings::decode_hex,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+
+use self::search::{
+    dictionary_candidate, dictionary_candidate_count, expand_pattern, search, COMMON_PARAMETERS,
+    COMMON_WORDS,
+};
+
+#[derive(Debug, Clone, Parser, Builder)]
+#[clap(
+    about = "Search for function signatures which hash to a given selector",
+    after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    global_setting = AppSettings::DeriveDisplayOrder,
+    override_usage = "heimdall collide <SELECTOR> [OPTIONS]"
+)]
+pub struct CollideArgs {
+    /// The 4-byte selector to find signatures for.
+    #[clap(required = true)]
+    pub selector: String,
+
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// Path to a newline-separated list of words to build function names from. Defaults to a
+    /// built-in list of common words.
+    #[clap(long, short, default_value = "", hide_default_value = true)]
+    pub dictionary: String,
+
+    /// A signature pattern to brute-force instead of searching the dictionary, where `*` is
+    /// replaced by every string of identifier characters, e.g. `clash_*(uint256)`.
+    #[clap(long, short, default_value = "", hide_default_value = true)]
+    pub pattern: String,
+
+    /// The maximum number of pattern expansions to try.
+    #[clap(long, default_value = "100000000", hide_default_value = true)]
+    pub max_attempts: u64,
+
+    /// The number of matching signatures to stop after.
+    #[clap(long, short, default_value = "10", hide_default_value = true)]
+    pub limit: usize,
+
+    /// The number of threads to search with.
+    #[clap(long, short, default_value = "8", hide_default_value = true)]
+    pub threads: usize,
+
+    /// The output directory to write the output to or 'print' to print to the console
+    #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
+    pub output: String,
+
+    /// The name for the output file
+    #[clap(long, short, default_value = "", hide_default_value = true)]
+    pub name: String,
+}
+
+impl CollideArgsBuilder {
+    pub fn new() -> Self {
+        Self {
+            selector: Some(String::new()),
+            verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
+            dictionary: Some(String::new()),
+            pattern: Some(String::new()),
+            max_attempts: Some(100_000_000),
+            limit: Some(10),
+            threads: Some(8),
+            output: Some(String::new()),
+            name: Some(String::new()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollideResult {
+    pub selector: String,
+    /// Every signature found which hashes to the selector
+    pub signatures: Vec<String>,
+    /// The number of candidate signatures which were hashed
+    pub attempts: u64,
+}
+
+/// Search for human-readable signatures hashing to the given selector, either by combining
+/// dictionary words with common parameter lists, or by brute-forcing a `--pattern`.
+pub async fn collide(args: CollideArgs) -> Result<CollideResult, Error> {
+    set_logger_env(&args.verbose);
+
+    // get a new logger
+    let (logger, _) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    let selector: [u8; 4] = decode_hex(args.selector.trim_start_matches("0x"))
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(Error::GenericError(format!("invalid selector '{}' .", args.selector)))?;
+
+    let start_time = Instant::now();
+    let (signatures, attempts) = if args.pattern.is_empty() {
+        let words = if args.dictionary.is_empty() {
+            COMMON_WORDS.iter().map(|word| word.to_string()).collect()
+        } else {
+            read_file(&args.dictionary)
+                .lines()
+                .map(|line| line.trim().to_string())
+                .filter(|line| !line.is_empty())
+                .collect::<Vec<String>>()
+        };
+
+        let count = dictionary_candidate_count(&words, COMMON_PARAMETERS);
+        logger.debug(&format!("searching {} dictionary candidates.", count));
+
+        search(
+            move |index| dictionary_candidate(&words, COMMON_PARAMETERS, index),
+            count,
+            selector,
+            args.threads,
+            args.limit,
+        )
+    } else {
+        if !args.pattern.contains('*') {
+            return Err(Error::GenericError(format!(
+                "pattern '{}' must contain a '*' to expand.",
+                args.pattern
+            )))
+        }
+
+        logger.debug(&format!(
+            "searching up to {} expansions of '{}'.",
+            args.max_attempts, args.pattern
+        ));
+
+        let pattern = args.pattern.clone();
+        search(
+            move |index| expand_pattern(&pattern, index),
+            args.max_attempts,
+            selector,
+            args.threads,
+            args.limit,
+        )
+    };
+
+    logger.debug(&format!(
+        "hashed {} candidates in {} ms.",
+        attempts,
+        start_time.elapsed().as_millis()
+    ));
+
+    if signatures.is_empty() {
+        logger.warn(&format!("no signatures found for selector '{}' .", args.selector));
+    }
+    for signature in &signatures {
+        logger.success(&format!("found signature: {signature}"));
+    }
+
+    Ok(CollideResult {
+        selector: format!("0x{}", args.selector.trim_start_matches("0x")),
+        signatures,
+        attempts,
+    })
+}
@@ -0,0 +1,248 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use ethers::utils::keccak256;
+
+/// Parameter lists tried against every dictionary name.
+pub const COMMON_PARAMETERS: &[&str] = &[
+    "",
+    "address",
+    "uint256",
+    "bool",
+    "bytes",
+    "bytes32",
+    "string",
+    "address,uint256",
+    "address,address",
+    "address,bool",
+    "uint256,uint256",
+    "address,address,uint256",
+    "address,uint256,bytes",
+];
+
+/// Words which commonly appear in function names, used when no dictionary is given.
+pub const COMMON_WORDS: &[&str] = &[
+    "transfer",
+    "approve",
+    "allowance",
+    "balance",
+    "mint",
+    "burn",
+    "deposit",
+    "withdraw",
+    "claim",
+    "stake",
+    "unstake",
+    "swap",
+    "execute",
+    "call",
+    "multicall",
+    "permit",
+    "initialize",
+    "upgrade",
+    "implementation",
+    "admin",
+    "owner",
+    "pause",
+    "unpause",
+    "set",
+    "get",
+    "update",
+    "total",
+    "supply",
+    "token",
+    "reward",
+    "fee",
+    "price",
+    "rate",
+    "limit",
+    "nonce",
+    "all",
+    "to",
+    "from",
+    "for",
+    "with",
+];
+
+/// The characters a `*` in a pattern expands to.
+const PATTERN_ALPHABET: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ_";
+
+/// The 4-byte selector of the given signature.
+///
+/// ```
+/// use heimdall_core::collide::search::selector_of;
+///
+/// assert_eq!(selector_of("transfer(address,uint256)"), [0xa9, 0x05, 0x9c, 0xbb]);
+/// ```
+pub fn selector_of(signature: &str) -> [u8; 4] {
+    let hash = keccak256(signature.as_bytes());
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+/// Build the `index`th expansion of the given pattern, where the first `*` is replaced by the
+/// bijective base-63 encoding of `index` over identifier characters. Index 0 expands to the
+/// empty string, so every string of identifier characters is reached exactly once.
+pub fn expand_pattern(pattern: &str, index: u64) -> String {
+    let mut suffix = Vec::new();
+    let mut remaining = index;
+    while remaining > 0 {
+        remaining -= 1;
+        suffix.push(PATTERN_ALPHABET[(remaining % PATTERN_ALPHABET.len() as u64) as usize]);
+        remaining /= PATTERN_ALPHABET.len() as u64;
+    }
+    suffix.reverse();
+
+    pattern.replacen('*', &String::from_utf8_lossy(&suffix), 1)
+}
+
+/// The number of candidate signatures built from the given words by [`dictionary_candidate`].
+pub fn dictionary_candidate_count(words: &[String], parameters: &[&str]) -> u64 {
+    let words = words.len() as u64;
+    words.saturating_mul(words).saturating_mul(parameters.len() as u64)
+}
+
+/// Build the `index`th candidate signature from the given words, with each of the given parameter
+/// lists. Candidates are each word on its own, followed by each pair of distinct words in
+/// camelCase, so they're built as they're searched rather than held in memory.
+pub fn dictionary_candidate(words: &[String], parameters: &[&str], index: u64) -> String {
+    let params = parameters[(index % parameters.len() as u64) as usize];
+    let name = (index / parameters.len() as u64) as usize;
+    if name < words.len() {
+        return format!("{}({params})", words[name])
+    }
+
+    // the pairs skip each word followed by itself
+    let pair = name - words.len();
+    let first = pair / (words.len() - 1);
+    let second = match pair % (words.len() - 1) {
+        second if second < first => second,
+        second => second + 1,
+    };
+
+    let mut chars = words[second].chars();
+    let head = chars.next().map(|head| head.to_uppercase().to_string()).unwrap_or_default();
+    format!("{}{head}{}({params})", words[first], chars.as_str())
+}
+
+/// Search the candidates `0..count`, built with `candidate`, for signatures whose selector is
+/// `selector`. The search is split across `threads` threads and stops once `limit` matches are
+/// found. Returns the matching signatures, sorted, and the number of candidates hashed.
+pub fn search<F>(
+    candidate: F,
+    count: u64,
+    selector: [u8; 4],
+    threads: usize,
+    limit: usize,
+) -> (Vec<String>, u64)
+where
+    F: Fn(u64) -> String + Send + Sync + 'static, {
+    if limit == 0 {
+        return (Vec::new(), 0)
+    }
+
+    let candidate = Arc::new(candidate);
+    let matches = Arc::new(Mutex::new(Vec::new()));
+    let attempts = Arc::new(AtomicU64::new(0));
+    let done = Arc::new(AtomicBool::new(false));
+    let threads = threads.max(1) as u64;
+
+    let handles = (0..threads)
+        .map(|offset| {
+            let candidate = Arc::clone(&candidate);
+            let matches = Arc::clone(&matches);
+            let attempts = Arc::clone(&attempts);
+            let done = Arc::clone(&done);
+
+            thread::spawn(move || {
+                // each thread hashes every `threads`th candidate
+                let mut index = offset;
+                let mut hashed = 0;
+                while index < count && !done.load(Ordering::Relaxed) {
+                    let signature = candidate(index);
+                    if selector_of(&signature) == selector {
+                        let mut matches = matches.lock().expect("failed to lock matches");
+                        matches.push(signature);
+                        if matches.len() >= limit {
+                            done.store(true, Ordering::Relaxed);
+                        }
+                    }
+
+                    hashed += 1;
+                    index = match index.checked_add(threads) {
+                        Some(index) => index,
+                        None => break,
+                    };
+                }
+                attempts.fetch_add(hashed, Ordering::Relaxed);
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for handle in handles {
+        let _ = handle.join();
+    }
+
+    let mut matches = matches.lock().expect("failed to lock matches").clone();
+    matches.sort();
+    matches.truncate(limit);
+
+    (matches, attempts.load(Ordering::Relaxed))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::collide::search::*;
+
+    #[test]
+    fn test_expand_pattern() {
+        assert_eq!(expand_pattern("clash_*()", 0), "clash_()");
+        assert_eq!(expand_pattern("clash_*()", 1), "clash_0()");
+        assert_eq!(expand_pattern("clash_*()", 63), "clash__()");
+        assert_eq!(expand_pattern("clash_*()", 64), "clash_00()");
+    }
+
+    #[test]
+    fn test_dictionary_candidates() {
+        let words = vec!["transfer".to_string(), "from".to_string()];
+        let count = dictionary_candidate_count(&words, &["", "address"]);
+        let candidates = (0..count)
+            .map(|index| dictionary_candidate(&words, &["", "address"], index))
+            .collect::<Vec<String>>();
+
+        assert_eq!(count, 8);
+        assert!(candidates.contains(&"transfer()".to_string()));
+        assert!(candidates.contains(&"transferFrom(address)".to_string()));
+        assert!(candidates.contains(&"fromTransfer()".to_string()));
+    }
+
+    #[test]
+    fn test_search_finds_match() {
+        let words = COMMON_WORDS.iter().map(|word| word.to_string()).collect::<Vec<String>>();
+        let count = dictionary_candidate_count(&words, COMMON_PARAMETERS);
+
+        let (matches, attempts) = search(
+            move |index| dictionary_candidate(&words, COMMON_PARAMETERS, index),
+            count,
+            [0xa9, 0x05, 0x9c, 0xbb],
+            4,
+            1,
+        );
+
+        assert_eq!(matches, vec!["transfer(address,uint256)".to_string()]);
+        assert!(attempts <= count);
+    }
+
+    #[test]
+    fn test_search_zero_limit() {
+        let (matches, attempts) =
+            search(|index| expand_pattern("clash_*()", index), u64::MAX, [0; 4], 4, 0);
+
+        assert!(matches.is_empty());
+        assert_eq!(attempts, 0);
+    }
+}
@@ -0,0 +1,493 @@
+use ethers::types::U256;
+use heimdall_common::{
+    ether::evm::core::{opcodes::Opcode, vm::VM},
+    utils::strings::encode_hex,
+};
+
+use super::watchpoint::{WatchTarget, Watchpoint};
+
+/// The number of jumps shown when a watchpoint is hit.
+const MAX_PATH_DISPLAY: usize = 16;
+
+/// A [`Breakpoint`] pauses execution before the matching instruction is executed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Breakpoint {
+    /// Break at the given program counter
+    ProgramCounter(u128),
+    /// Break at every occurrence of the given opcode, i.e. `SSTORE`
+    Opcode(String),
+}
+
+impl Breakpoint {
+    /// Parse a breakpoint from either a program counter (decimal or `0x` prefixed hex) or an
+    /// opcode name.
+    pub fn parse(value: &str) -> Option<Breakpoint> {
+        let value = value.trim();
+
+        if let Some(hex) = value.strip_prefix("0x") {
+            return u128::from_str_radix(hex, 16).ok().map(Breakpoint::ProgramCounter)
+        }
+        if let Ok(pc) = value.parse::<u128>() {
+            return Some(Breakpoint::ProgramCounter(pc))
+        }
+
+        // only accept opcodes which actually exist
+        let name = value.to_uppercase();
+        (0..=255u8).any(|code| Opcode::new(code).name == name).then_some(Breakpoint::Opcode(name))
+    }
+
+    fn matches(&self, pc: u128, opcode: &Opcode) -> bool {
+        match self {
+            Breakpoint::ProgramCounter(target) => *target == pc,
+            Breakpoint::Opcode(name) => opcode.name == name,
+        }
+    }
+}
+
+/// A single command entered at the debugger prompt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Step(usize),
+    Next,
+    Continue,
+    Break(Breakpoint),
+    Delete(usize),
+    Breakpoints,
+    Watch(Watchpoint),
+    Unwatch(usize),
+    Watchpoints,
+    Stack,
+    Memory,
+    Storage,
+    Help,
+    Quit,
+}
+
+impl Command {
+    /// Parse a command from a line of user input. An empty line is treated as `step`.
+    pub fn parse(line: &str) -> Result<Command, String> {
+        let mut parts = line.split_whitespace();
+        let command = parts.next().unwrap_or("step");
+        let arguments = parts.collect::<Vec<&str>>();
+        let argument = arguments.first().copied();
+
+        match command {
+            "s" | "step" => match argument {
+                Some(count) => count
+                    .parse::<usize>()
+                    .map(Command::Step)
+                    .map_err(|_| format!("invalid step count '{count}' .")),
+                None => Ok(Command::Step(1)),
+            },
+            "n" | "next" => Ok(Command::Next),
+            "c" | "continue" => Ok(Command::Continue),
+            "b" | "break" => {
+                let target = argument.ok_or("usage: break <PC|OPCODE>")?;
+                Breakpoint::parse(target)
+                    .map(Command::Break)
+                    .ok_or(format!("invalid breakpoint '{target}' ."))
+            }
+            "d" | "delete" => {
+                let index = argument.ok_or("usage: delete <INDEX>")?;
+                index
+                    .parse::<usize>()
+                    .map(Command::Delete)
+                    .map_err(|_| format!("invalid breakpoint index '{index}' ."))
+            }
+            "bl" | "breakpoints" => Ok(Command::Breakpoints),
+            "w" | "watch" => Watchpoint::parse(&arguments.join(" ")).map(Command::Watch),
+            "uw" | "unwatch" => {
+                let index = argument.ok_or("usage: unwatch <INDEX>")?;
+                index
+                    .parse::<usize>()
+                    .map(Command::Unwatch)
+                    .map_err(|_| format!("invalid watchpoint index '{index}' ."))
+            }
+            "wl" | "watchpoints" => Ok(Command::Watchpoints),
+            "stack" => Ok(Command::Stack),
+            "mem" | "memory" => Ok(Command::Memory),
+            "storage" => Ok(Command::Storage),
+            "h" | "help" => Ok(Command::Help),
+            "q" | "quit" | "exit" => Ok(Command::Quit),
+            _ => Err(format!("unknown command '{command}' . Use 'help' to list commands.")),
+        }
+    }
+}
+
+/// The [`Debugger`] wraps a [`VM`], stepping through it one instruction at a time and pausing at
+/// breakpoints and watchpoints.
+#[derive(Debug, Clone)]
+pub struct Debugger {
+    pub vm: VM,
+    pub breakpoints: Vec<Breakpoint>,
+    pub watchpoints: Vec<Watchpoint>,
+    /// The destinations of every jump taken so far
+    pub path: Vec<u128>,
+}
+
+impl Debugger {
+    pub fn new(vm: VM, breakpoints: Vec<Breakpoint>, watchpoints: Vec<Watchpoint>) -> Debugger {
+        Debugger { vm, breakpoints, watchpoints, path: Vec::new() }
+    }
+
+    /// The program counter of the next instruction to be executed.
+    pub fn pc(&self) -> u128 {
+        self.vm.instruction - 1
+    }
+
+    /// Whether the VM has stopped executing.
+    pub fn finished(&self) -> bool {
+        self.vm.bytecode.len() < self.vm.instruction as usize ||
+            self.vm.exitcode != 255 ||
+            !self.vm.returndata.is_empty()
+    }
+
+    /// The opcode of the next instruction to be executed.
+    pub fn current_opcode(&self) -> Opcode {
+        Opcode::new(self.vm.bytecode.get(self.pc() as usize).copied().unwrap_or(0))
+    }
+
+    /// Execute a single instruction, returning lines describing it and any watchpoints it hit,
+    /// and whether a hit watchpoint asked to pause.
+    pub fn step(&mut self) -> (Vec<String>, bool) {
+        let pc = self.pc();
+        let watched_memory = self
+            .watchpoints
+            .iter()
+            .map(|watchpoint| match &watchpoint.target {
+                WatchTarget::Memory(range) => self.vm.memory.read(range.start, range.len()),
+                WatchTarget::Storage(_) => Vec::new(),
+            })
+            .collect::<Vec<Vec<u8>>>();
+
+        let state = self.vm.step();
+        let instruction = &state.last_instruction;
+        let opcode = instruction.opcode_details.clone().unwrap_or(Opcode::new(0));
+        let format_values = |values: &Vec<U256>| {
+            values.iter().map(|value| format!("{value:#x}")).collect::<Vec<String>>().join(" ")
+        };
+
+        let mut line = format!("{:06x} {}", pc, opcode.name);
+        if !instruction.inputs.is_empty() {
+            line.push_str(&format!(" {}", format_values(&instruction.inputs)));
+        }
+        if !instruction.outputs.is_empty() {
+            line.push_str(&format!(" -> {}", format_values(&instruction.outputs)));
+        }
+        let mut lines = vec![line];
+
+        // record jumps which were taken
+        if matches!(opcode.name, "JUMP" | "JUMPI") && self.pc() != pc + 1 {
+            self.path.push(self.pc());
+        }
+
+        // check each watchpoint for writes made by this instruction
+        let mut pause = false;
+        for (index, watchpoint) in self.watchpoints.iter().enumerate() {
+            let provenance = match &watchpoint.target {
+                WatchTarget::Storage(slot) => {
+                    if opcode.name != "SSTORE" || instruction.inputs.first() != Some(slot) {
+                        continue
+                    }
+                    format!(
+                        "slot {slot:#x} = {:#x} ({})",
+                        instruction.inputs[1], instruction.input_operations[1]
+                    )
+                }
+                WatchTarget::Memory(range) => {
+                    let current = self.vm.memory.read(range.start, range.len());
+                    let Some(changed) =
+                        current.iter().zip(&watched_memory[index]).position(|(a, b)| a != b)
+                    else {
+                        continue
+                    };
+                    format!(
+                        "memory {:#x}..{:#x} = 0x{} ({})",
+                        range.start,
+                        range.end,
+                        encode_hex(current),
+                        self.vm
+                            .memory
+                            .origin(range.start + changed)
+                            .map(|origin| origin.to_string())
+                            .unwrap_or("unknown origin".to_string())
+                    )
+                }
+            };
+
+            lines.push(format!(
+                "hit watchpoint {index} at {pc:06x} ({}): {provenance}",
+                opcode.name
+            ));
+            lines.push(format!("  via jumps: {}", self.format_path()));
+            pause |= watchpoint.pause;
+        }
+
+        (lines, pause)
+    }
+
+    /// Format the most recent jumps taken, oldest first.
+    fn format_path(&self) -> String {
+        if self.path.is_empty() {
+            return "none".to_string()
+        }
+
+        let start = self.path.len().saturating_sub(MAX_PATH_DISPLAY);
+        let jumps =
+            self.path[start..].iter().map(|pc| format!("{pc:06x}")).collect::<Vec<String>>();
+        format!("{}{}", if start > 0 { "... -> " } else { "" }, jumps.join(" -> "))
+    }
+
+    /// Execute instructions until a breakpoint is hit or execution finishes. The current
+    /// instruction is always executed, so continuing from a breakpoint doesn't stop immediately.
+    pub fn resume(&mut self) -> Vec<String> {
+        let mut lines = Vec::new();
+
+        while !self.finished() {
+            let (step_lines, pause) = self.step();
+            lines.extend(step_lines);
+            if pause {
+                break
+            }
+
+            let (pc, opcode) = (self.pc(), self.current_opcode());
+            if let Some(index) = self.breakpoints.iter().position(|b| b.matches(pc, &opcode)) {
+                lines.push(format!("hit breakpoint {index} at {pc:06x} ({}).", opcode.name));
+                break
+            }
+        }
+
+        lines
+    }
+
+    /// Execute instructions until control flow leaves the current basic block, either through
+    /// a jump or by falling into a `JUMPDEST`.
+    pub fn next_block(&mut self) -> Vec<String> {
+        let mut lines = Vec::new();
+
+        while !self.finished() {
+            let opcode = self.current_opcode();
+            let (step_lines, pause) = self.step();
+            lines.extend(step_lines);
+
+            if pause ||
+                matches!(opcode.name, "JUMP" | "JUMPI") ||
+                self.current_opcode().name == "JUMPDEST"
+            {
+                break
+            }
+        }
+
+        lines
+    }
+
+    /// Execute the given command, returning the lines to display.
+    pub fn execute(&mut self, command: &Command) -> Vec<String> {
+        match command {
+            Command::Step(count) => {
+                let mut lines = Vec::new();
+                for _ in 0..*count {
+                    if self.finished() {
+                        break
+                    }
+                    let (step_lines, pause) = self.step();
+                    lines.extend(step_lines);
+                    if pause {
+                        break
+                    }
+                }
+                lines
+            }
+            Command::Next => self.next_block(),
+            Command::Continue => self.resume(),
+            Command::Break(breakpoint) => {
+                self.breakpoints.push(breakpoint.clone());
+                vec![format!("added breakpoint {}.", self.breakpoints.len() - 1)]
+            }
+            Command::Delete(index) => {
+                if *index < self.breakpoints.len() {
+                    self.breakpoints.remove(*index);
+                    vec![format!("deleted breakpoint {index}.")]
+                } else {
+                    vec![format!("no breakpoint with index {index}.")]
+                }
+            }
+            Command::Breakpoints => self
+                .breakpoints
+                .iter()
+                .enumerate()
+                .map(|(i, breakpoint)| match breakpoint {
+                    Breakpoint::ProgramCounter(pc) => format!("{i}: pc {pc:06x}"),
+                    Breakpoint::Opcode(name) => format!("{i}: opcode {name}"),
+                })
+                .collect(),
+            Command::Watch(watchpoint) => {
+                self.watchpoints.push(watchpoint.clone());
+                vec![format!("added watchpoint {}.", self.watchpoints.len() - 1)]
+            }
+            Command::Unwatch(index) => {
+                if *index < self.watchpoints.len() {
+                    self.watchpoints.remove(*index);
+                    vec![format!("deleted watchpoint {index}.")]
+                } else {
+                    vec![format!("no watchpoint with index {index}.")]
+                }
+            }
+            Command::Watchpoints => self
+                .watchpoints
+                .iter()
+                .enumerate()
+                .map(|(i, watchpoint)| format!("{i}: {watchpoint}"))
+                .collect(),
+            Command::Stack => self
+                .vm
+                .stack
+                .stack
+                .iter()
+                .enumerate()
+                .map(|(i, frame)| format!("{i:>4}: {:#066x} ({})", frame.value, frame.operation))
+                .collect(),
+            Command::Memory => self
+                .vm
+                .memory
+                .memory
+                .chunks(32)
+                .enumerate()
+                .map(|(i, word)| format!("{:06x}: {}", i * 32, encode_hex(word.to_vec())))
+                .collect(),
+            Command::Storage => {
+                let mut slots = self.vm.storage.storage.iter().collect::<Vec<_>>();
+                slots.sort();
+                slots
+                    .into_iter()
+                    .map(|(key, value)| {
+                        format!("{:#066x} => {:#066x}", U256::from(key), U256::from(value))
+                    })
+                    .collect()
+            }
+            Command::Help => vec![
+                "step [N]        (s)  execute the next N instructions".to_string(),
+                "next            (n)  execute until the end of the current basic block".to_string(),
+                "continue        (c)  execute until a breakpoint is hit".to_string(),
+                "break <PC|OP>   (b)  add a breakpoint at a pc or opcode".to_string(),
+                "delete <INDEX>  (d)  delete a breakpoint".to_string(),
+                "breakpoints     (bl) list breakpoints".to_string(),
+                "watch <TARGET>  (w)  add a watchpoint, i.e. 'storage 0x0' or 'memory 0x40 32'"
+                    .to_string(),
+                "                     append 'log' to log hits without pausing".to_string(),
+                "unwatch <INDEX> (uw) delete a watchpoint".to_string(),
+                "watchpoints     (wl) list watchpoints".to_string(),
+                "stack | memory | storage  inspect the VM state".to_string(),
+                "quit            (q)  stop debugging".to_string(),
+            ],
+            Command::Quit => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use heimdall_common::ether::evm::core::vm::VM;
+
+    use crate::debug::debugger::*;
+
+    fn new_test_debugger(bytecode: &str) -> Debugger {
+        Debugger::new(
+            VM::new(
+                String::from(bytecode),
+                String::from("0x"),
+                String::from("0x6865696d64616c6c000000000061646472657373"),
+                String::from("0x6865696d64616c6c0000000000006f726967696e"),
+                String::from("0x6865696d64616c6c00000000000063616c6c6572"),
+                0,
+                u128::MAX,
+            ),
+            Vec::new(),
+            Vec::new(),
+        )
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(Command::parse(""), Ok(Command::Step(1)));
+        assert_eq!(Command::parse("s 4"), Ok(Command::Step(4)));
+        assert_eq!(
+            Command::parse("break 0x0a"),
+            Ok(Command::Break(Breakpoint::ProgramCounter(10)))
+        );
+        assert_eq!(
+            Command::parse("b sstore"),
+            Ok(Command::Break(Breakpoint::Opcode("SSTORE".to_string())))
+        );
+        assert!(Command::parse("break NOTANOPCODE").is_err());
+        assert!(Command::parse("frobnicate").is_err());
+    }
+
+    #[test]
+    fn test_step_and_inspect_stack() {
+        // PUSH1 0x01 PUSH1 0x02 ADD STOP
+        let mut debugger = new_test_debugger("600160020100");
+
+        let lines = debugger.execute(&Command::Step(3));
+
+        assert_eq!(
+            lines,
+            vec!["000000 PUSH1 -> 0x1", "000002 PUSH1 -> 0x2", "000004 ADD 0x2 0x1 -> 0x3"]
+        );
+        assert_eq!(debugger.vm.stack.peek(0).value, U256::from(3));
+        assert_eq!(debugger.execute(&Command::Stack).len(), 1);
+    }
+
+    #[test]
+    fn test_continue_stops_at_breakpoints() {
+        // PUSH1 0x01 PUSH1 0x00 SSTORE STOP
+        let mut debugger = new_test_debugger("600160005500");
+        debugger.execute(&Command::Break(Breakpoint::Opcode("SSTORE".to_string())));
+
+        debugger.execute(&Command::Continue);
+        assert_eq!(debugger.pc(), 4);
+        assert!(debugger.vm.storage.storage.is_empty());
+
+        debugger.execute(&Command::Continue);
+        assert!(debugger.finished());
+        assert_eq!(debugger.execute(&Command::Storage).len(), 1);
+    }
+
+    #[test]
+    fn test_storage_watchpoint_reports_provenance() {
+        // PUSH1 0x06 JUMP INVALID INVALID INVALID JUMPDEST CALLER PUSH1 0x00 SSTORE STOP
+        let mut debugger = new_test_debugger("600656fefefe5b3360005500");
+        debugger.execute(&Command::Watch(Watchpoint::parse("storage 0x0").unwrap()));
+
+        let lines = debugger.execute(&Command::Continue);
+
+        assert!(lines
+            .iter()
+            .any(|line| line.contains("hit watchpoint 0") && line.contains("CALLER")));
+        assert!(lines.iter().any(|line| line.contains("via jumps: 000006")));
+        assert_eq!(debugger.pc(), 11);
+    }
+
+    #[test]
+    fn test_memory_watchpoint_logs_without_pausing() {
+        // PUSH1 0x01 PUSH1 0x00 MSTORE PUSH1 0x02 PUSH1 0x20 MSTORE STOP
+        let mut debugger = new_test_debugger("6001600052600260205200");
+        debugger.execute(&Command::Watch(Watchpoint::parse("memory 0 32 log").unwrap()));
+
+        let lines = debugger.execute(&Command::Continue);
+
+        assert_eq!(lines.iter().filter(|line| line.contains("hit watchpoint 0")).count(), 1);
+        assert!(debugger.finished());
+    }
+
+    #[test]
+    fn test_next_stops_at_end_of_block() {
+        // PUSH1 0x04 JUMP INVALID JUMPDEST STOP
+        let mut debugger = new_test_debugger("600456fe5b00");
+
+        let lines = debugger.execute(&Command::Next);
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(debugger.pc(), 4);
+    }
+}
@@ -0,0 +1,142 @@
+pub mod debugger;
+pub mod watchpoint;
+
+use std::io::{stdin, stdout, Write};
+
+use clap::{AppSettings, Parser};
+use derive_builder::Builder;
+use heimdall_common::{
+    ether::{bytecode::get_bytecode_from_target, evm::core::vm::VM},
+    utils::io::logging::{set_logger_env, Logger},
+};
+
+use crate::error::Error;
+
+use self::{
+    debugger::{Breakpoint, Command, Debugger},
+    watchpoint::Watchpoint,
+};
+
+#[derive(Debug, Clone, Parser, Builder)]
+#[clap(
+    about = "Step through EVM bytecode one instruction at a time",
+    after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    global_setting = AppSettings::DeriveDisplayOrder,
+    override_usage = "heimdall debug <TARGET> [OPTIONS]"
+)]
+pub struct DebugArgs {
+    /// The target to debug, either a file, bytecode, contract address, or ENS name.
+    #[clap(required = true)]
+    pub target: String,
+
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// The RPC provider to use for fetching target bytecode.
+    #[clap(long = "rpc-url", short, default_value = "", hide_default_value = true)]
+    pub rpc_url: String,
+
+    /// The calldata to execute the bytecode with.
+    #[clap(long, short, default_value = "", hide_default_value = true)]
+    pub calldata: String,
+
+    /// The value, in wei, to execute the bytecode with.
+    #[clap(long, default_value = "0", hide_default_value = true)]
+    pub value: u128,
+
+    /// Breakpoints to set before execution begins, either a pc or an opcode name.
+    #[clap(long = "break", short = 'b', multiple_occurrences = true)]
+    pub breakpoints: Vec<String>,
+
+    /// Watchpoints to set before execution begins, i.e. 'storage 0x0' or 'memory 0x40 32 log'.
+    #[clap(long = "watch", short = 'w', multiple_occurrences = true)]
+    pub watchpoints: Vec<String>,
+}
+
+impl DebugArgsBuilder {
+    pub fn new() -> Self {
+        Self {
+            target: Some(String::new()),
+            verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
+            rpc_url: Some(String::new()),
+            calldata: Some(String::new()),
+            value: Some(0),
+            breakpoints: Some(Vec::new()),
+            watchpoints: Some(Vec::new()),
+        }
+    }
+}
+
+/// Start an interactive debugging session for the given target's bytecode.
+pub async fn debug(args: DebugArgs) -> Result<(), Error> {
+    set_logger_env(&args.verbose);
+
+    // get a new logger
+    let (logger, _) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    let breakpoints = args
+        .breakpoints
+        .iter()
+        .map(|breakpoint| {
+            Breakpoint::parse(breakpoint)
+                .ok_or(Error::GenericError(format!("invalid breakpoint '{breakpoint}' .")))
+        })
+        .collect::<Result<Vec<Breakpoint>, Error>>()?;
+    let watchpoints = args
+        .watchpoints
+        .iter()
+        .map(|watchpoint| Watchpoint::parse(watchpoint).map_err(Error::GenericError))
+        .collect::<Result<Vec<Watchpoint>, Error>>()?;
+
+    let contract_bytecode = get_bytecode_from_target(&args.target, &args.rpc_url)
+        .await
+        .map_err(|e| Error::GenericError(e.to_string()))?;
+
+    let vm = VM::new(
+        contract_bytecode,
+        format!("0x{}", args.calldata.replacen("0x", "", 1)),
+        String::from("0x6865696d64616c6c000000000061646472657373"),
+        String::from("0x6865696d64616c6c0000000000006f726967696e"),
+        String::from("0x6865696d64616c6c00000000000063616c6c6572"),
+        args.value,
+        u128::MAX,
+    );
+    let mut debugger = Debugger::new(vm, breakpoints, watchpoints);
+
+    logger.info("starting debugger. Use 'help' to list commands.");
+
+    let mut reported_exit = false;
+    loop {
+        if debugger.finished() && !reported_exit {
+            reported_exit = true;
+            logger.info(&format!(
+                "execution finished with exit code {} after using {} gas.",
+                debugger.vm.exitcode, debugger.vm.gas_used
+            ));
+        }
+
+        print!("({:06x} {}) > ", debugger.pc(), debugger.current_opcode().name);
+        let _ = stdout().flush();
+
+        let mut line = String::new();
+        if stdin().read_line(&mut line).map_err(|e| Error::GenericError(e.to_string()))? == 0 {
+            break
+        }
+
+        match Command::parse(&line) {
+            Ok(Command::Quit) => break,
+            Ok(command) => {
+                for output in debugger.execute(&command) {
+                    println!("{output}");
+                }
+            }
+            Err(e) => logger.error(&e),
+        }
+    }
+
+    Ok(())
+}
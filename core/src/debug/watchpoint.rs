@@ -0,0 +1,107 @@
+use std::{fmt::Display, ops::Range};
+
+use ethers::types::U256;
+
+/// The location a [`Watchpoint`] observes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WatchTarget {
+    /// Writes to the given storage slot
+    Storage(U256),
+    /// Changes to any byte within the given memory range
+    Memory(Range<usize>),
+}
+
+/// A [`Watchpoint`] reports every write to a storage slot or memory range, along with the
+/// provenance of the written value and the jumps taken to reach it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Watchpoint {
+    pub target: WatchTarget,
+    /// Whether execution pauses when the watchpoint is hit, rather than only logging it
+    pub pause: bool,
+}
+
+impl Watchpoint {
+    /// Parse a watchpoint from `storage <SLOT> [log]` or `memory <OFFSET> <SIZE> [log]`, where
+    /// numbers are either decimal or `0x` prefixed hex.
+    pub fn parse(value: &str) -> Result<Watchpoint, String> {
+        let parts = value.split_whitespace().collect::<Vec<&str>>();
+        let pause = parts.last() != Some(&"log");
+
+        let target = match parts.as_slice() {
+            ["storage", slot, ..] => WatchTarget::Storage(parse_number(slot)?),
+            ["memory", offset, size, ..] => {
+                let offset = parse_usize(offset)?;
+                let size = parse_usize(size)?;
+                if size == 0 {
+                    return Err("memory watchpoints must have a non-zero size.".to_string())
+                }
+                let end = offset
+                    .checked_add(size)
+                    .ok_or("memory watchpoint range exceeds the addressable memory.".to_string())?;
+                WatchTarget::Memory(offset..end)
+            }
+            _ => {
+                return Err(
+                    "usage: watch storage <SLOT> [log] | memory <OFFSET> <SIZE> [log]".to_string()
+                )
+            }
+        };
+
+        Ok(Watchpoint { target, pause })
+    }
+}
+
+impl Display for Watchpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.target {
+            WatchTarget::Storage(slot) => write!(f, "storage {slot:#x}")?,
+            WatchTarget::Memory(range) => write!(f, "memory {:#x}..{:#x}", range.start, range.end)?,
+        }
+        if !self.pause {
+            write!(f, " (log)")?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_number(value: &str) -> Result<U256, String> {
+    match value.strip_prefix("0x") {
+        Some(hex) => U256::from_str_radix(hex, 16).ok(),
+        None => U256::from_dec_str(value).ok(),
+    }
+    .ok_or(format!("invalid number '{value}' ."))
+}
+
+fn parse_usize(value: &str) -> Result<usize, String> {
+    parse_number(value)?.try_into().map_err(|_| format!("number '{value}' is too large."))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::debug::watchpoint::*;
+
+    #[test]
+    fn test_parse_storage_watchpoint() {
+        assert_eq!(
+            Watchpoint::parse("storage 0x0a"),
+            Ok(Watchpoint { target: WatchTarget::Storage(U256::from(10)), pause: true })
+        );
+    }
+
+    #[test]
+    fn test_parse_memory_watchpoint_log() {
+        assert_eq!(
+            Watchpoint::parse("memory 64 32 log"),
+            Ok(Watchpoint { target: WatchTarget::Memory(64..96), pause: false })
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_watchpoint() {
+        assert!(Watchpoint::parse("storage").is_err());
+        assert!(Watchpoint::parse("memory 0x40 0").is_err());
+        assert!(Watchpoint::parse("calldata 0x0").is_err());
+        assert!(Watchpoint::parse("memory 0x10000000000000000000000000000000000 32").is_err());
+        assert!(Watchpoint::parse("memory 0xffffffffffffffff 32").is_err());
+    }
+}
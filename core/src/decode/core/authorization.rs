@@ -0,0 +1,135 @@
+use ethers::{
+    types::{Address, Signature, Transaction, H256, U256, U64},
+    utils::{keccak256, rlp::RlpStream},
+};
+use serde::Deserialize;
+
+/// The magic byte prefixed to an authorization's RLP encoding before it's signed, as defined by
+/// EIP-7702.
+const AUTHORIZATION_MAGIC: u8 = 0x05;
+
+/// An entry in a set code (type 4) transaction's authorization list, which delegates the signing
+/// EOA's code to `address`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Authorization {
+    pub chain_id: U256,
+    pub address: Address,
+    pub nonce: U64,
+    pub y_parity: U64,
+    pub r: U256,
+    pub s: U256,
+}
+
+impl Authorization {
+    /// Read the authorization list of the transaction, returning `None` if it isn't a type 4
+    /// transaction. The list isn't part of [`Transaction`], so it's read from the extra fields the
+    /// RPC provider returned.
+    pub fn from_transaction(transaction: &Transaction) -> Option<Vec<Authorization>> {
+        if transaction.transaction_type != Some(U64::from(4)) {
+            return None
+        }
+
+        Some(
+            transaction
+                .other
+                .get_deserialized("authorizationList")
+                .and_then(|list| list.ok())
+                .unwrap_or_default(),
+        )
+    }
+
+    /// The EOA which signed the authorization, if the signature is valid.
+    pub fn authority(&self) -> Option<Address> {
+        let mut stream = RlpStream::new_list(3);
+        stream.append(&self.chain_id);
+        stream.append(&self.address);
+        stream.append(&self.nonce);
+
+        let mut preimage = vec![AUTHORIZATION_MAGIC];
+        preimage.extend(stream.out());
+
+        Signature { r: self.r, s: self.s, v: self.y_parity.as_u64() }
+            .recover(H256::from(keccak256(preimage)))
+            .ok()
+    }
+
+    /// The authorization as a line of output, aligned with the rest of the decoded transaction.
+    pub fn display(&self, index: usize) -> String {
+        format!(
+            "auth {}:{}{} delegates to {:#x} (chain {}, nonce {})",
+            index,
+            " ".repeat(5 - index.to_string().len()),
+            match self.authority() {
+                Some(authority) => format!("{authority:#x}"),
+                None => String::from("(invalid signature)"),
+            },
+            self.address,
+            self.chain_id,
+            self.nonce
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::signers::{LocalWallet, Signer};
+    use serde_json::json;
+
+    use crate::decode::core::authorization::*;
+
+    #[test]
+    fn test_non_authorization_transaction() {
+        let transaction =
+            Transaction { transaction_type: Some(U64::from(3)), ..Default::default() };
+        assert!(Authorization::from_transaction(&transaction).is_none());
+    }
+
+    #[test]
+    fn test_authorization_list_recovers_authority() {
+        let wallet = "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318"
+            .parse::<LocalWallet>()
+            .unwrap();
+        let mut authorization = Authorization {
+            chain_id: U256::one(),
+            address: "0x63c0c19a282a1b52b07dd5a65b58948a07dae32b".parse().unwrap(),
+            nonce: U64::from(7),
+            y_parity: U64::zero(),
+            r: U256::zero(),
+            s: U256::zero(),
+        };
+
+        // sign the authorization as the EOA would
+        let mut stream = RlpStream::new_list(3);
+        stream.append(&authorization.chain_id);
+        stream.append(&authorization.address);
+        stream.append(&authorization.nonce);
+        let mut preimage = vec![AUTHORIZATION_MAGIC];
+        preimage.extend(stream.out());
+        let signature = wallet.sign_hash(H256::from(keccak256(preimage))).unwrap();
+        authorization.r = signature.r;
+        authorization.s = signature.s;
+        authorization.y_parity = U64::from(signature.v - 27);
+
+        let mut transaction =
+            Transaction { transaction_type: Some(U64::from(4)), ..Default::default() };
+        transaction.other = serde_json::from_value(json!({
+            "authorizationList": [{
+                "chainId": "0x1",
+                "address": "0x63c0c19a282a1b52b07dd5a65b58948a07dae32b",
+                "nonce": "0x7",
+                "yParity": format!("{:#x}", authorization.y_parity),
+                "r": format!("{:#x}", authorization.r),
+                "s": format!("{:#x}", authorization.s),
+            }],
+        }))
+        .unwrap();
+
+        let authorizations = Authorization::from_transaction(&transaction).unwrap();
+        assert_eq!(authorizations, vec![authorization]);
+        assert_eq!(authorizations[0].authority(), Some(wallet.address()));
+        assert!(authorizations[0]
+            .display(0)
+            .starts_with(&format!("auth 0:    {:#x}", wallet.address())));
+    }
+}
@@ -0,0 +1,144 @@
+use ethers::{
+    core::k256::sha2::{Digest, Sha256},
+    types::{Bytes, Transaction, H256, U256, U64},
+};
+
+/// The version byte of a KZG commitment's versioned hash, as defined by EIP-4844.
+const VERSIONED_HASH_VERSION_KZG: u8 = 0x01;
+
+/// The EIP-4844 fields of a blob-carrying (type 3) transaction.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlobTransaction {
+    pub max_fee_per_blob_gas: Option<U256>,
+    pub blobs: Vec<Blob>,
+}
+
+/// A blob referenced by a transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Blob {
+    pub versioned_hash: H256,
+    /// The blob's KZG commitment, if the RPC provider included the transaction's sidecar
+    pub commitment: Option<Bytes>,
+}
+
+impl BlobTransaction {
+    /// Read the blob fields of the transaction, returning `None` if it isn't a type 3 transaction.
+    /// These fields aren't part of [`Transaction`], so they're read from the extra fields the RPC
+    /// provider returned.
+    pub fn from_transaction(transaction: &Transaction) -> Option<BlobTransaction> {
+        if transaction.transaction_type != Some(U64::from(3)) {
+            return None
+        }
+
+        let versioned_hashes: Vec<H256> = transaction
+            .other
+            .get_deserialized("blobVersionedHashes")
+            .and_then(|hashes| hashes.ok())
+            .unwrap_or_default();
+        let commitments: Vec<Bytes> = transaction
+            .other
+            .get("sidecar")
+            .and_then(|sidecar| sidecar.get("commitments"))
+            .and_then(|commitments| serde_json::from_value(commitments.clone()).ok())
+            .unwrap_or_default();
+
+        Some(BlobTransaction {
+            max_fee_per_blob_gas: transaction
+                .other
+                .get_deserialized("maxFeePerBlobGas")
+                .and_then(|fee| fee.ok()),
+            blobs: versioned_hashes
+                .into_iter()
+                .map(|versioned_hash| Blob {
+                    versioned_hash,
+                    commitment: commitments
+                        .iter()
+                        .find(|commitment| kzg_to_versioned_hash(commitment) == versioned_hash)
+                        .cloned(),
+                })
+                .collect(),
+        })
+    }
+
+    /// The blob fields as lines of output, aligned with the rest of the decoded transaction.
+    pub fn display(&self) -> Vec<String> {
+        let mut lines = vec![format!(
+            "blob fee:  {}",
+            match self.max_fee_per_blob_gas {
+                Some(fee) => format!("{fee} wei max per blob gas"),
+                None => String::from("unknown"),
+            }
+        )];
+        for (i, blob) in self.blobs.iter().enumerate() {
+            lines.push(format!(
+                "blob {}:{}{:#x}",
+                i,
+                " ".repeat(5 - i.to_string().len()),
+                blob.versioned_hash
+            ));
+            if let Some(commitment) = &blob.commitment {
+                lines.push(format!("           commitment: {commitment}"));
+            }
+        }
+        lines
+    }
+}
+
+/// The versioned hash of a KZG commitment, i.e. `0x01 . sha256(commitment)[1:]`.
+pub fn kzg_to_versioned_hash(commitment: &[u8]) -> H256 {
+    let mut hash: [u8; 32] = Sha256::digest(commitment).into();
+    hash[0] = VERSIONED_HASH_VERSION_KZG;
+    H256::from(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::decode::core::blob::*;
+
+    fn blob_transaction(other: serde_json::Value) -> Transaction {
+        let mut transaction =
+            Transaction { transaction_type: Some(U64::from(3)), ..Default::default() };
+        transaction.other = serde_json::from_value(other).unwrap();
+        transaction
+    }
+
+    #[test]
+    fn test_non_blob_transaction() {
+        let transaction =
+            Transaction { transaction_type: Some(U64::from(2)), ..Default::default() };
+        assert!(BlobTransaction::from_transaction(&transaction).is_none());
+    }
+
+    #[test]
+    fn test_blob_transaction_without_sidecar() {
+        let transaction = blob_transaction(json!({
+            "maxFeePerBlobGas": "0x3b9aca00",
+            "blobVersionedHashes": [
+                "0x01b0a4cdd5f55589f5c5b4d46c76704bb6ce95c0a8c09f77f197a57808dded28",
+                "0x01c8a4cdd5f55589f5c5b4d46c76704bb6ce95c0a8c09f77f197a57808dded28",
+            ],
+        }));
+
+        let blob_transaction = BlobTransaction::from_transaction(&transaction).unwrap();
+        assert_eq!(blob_transaction.max_fee_per_blob_gas, Some(U256::from(1_000_000_000u64)));
+        assert_eq!(blob_transaction.blobs.len(), 2);
+        assert!(blob_transaction.blobs.iter().all(|blob| blob.commitment.is_none()));
+    }
+
+    #[test]
+    fn test_blob_transaction_with_sidecar() {
+        let commitment = Bytes::from(vec![0xc0; 48]);
+        let versioned_hash = kzg_to_versioned_hash(&commitment);
+        let transaction = blob_transaction(json!({
+            "blobVersionedHashes": [versioned_hash],
+            "sidecar": { "commitments": [commitment.clone()] },
+        }));
+
+        let blob_transaction = BlobTransaction::from_transaction(&transaction).unwrap();
+        assert_eq!(blob_transaction.max_fee_per_blob_gas, None);
+        assert_eq!(versioned_hash.as_bytes()[0], VERSIONED_HASH_VERSION_KZG);
+        assert_eq!(blob_transaction.blobs[0].commitment, Some(commitment));
+    }
+}
@@ -1 +1,3 @@
 pub mod abi;
+pub mod authorization;
+pub mod blob;
@@ -1,7 +1,10 @@
 mod core;
 mod util;
 
-use std::{collections::HashSet, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
 
 use clap::{AppSettings, Parser};
 use derive_builder::Builder;
@@ -22,6 +25,7 @@ use heimdall_common::{
     },
     utils::{
         io::{
+            exit::{record_failure, ExitStatus},
             logging::{set_logger_env, Logger},
             types::display,
         },
@@ -33,7 +37,12 @@ use indicatif::ProgressBar;
 use strsim::normalized_damerau_levenshtein as similarity;
 
 use crate::{
-    decode::{core::abi::try_decode_dynamic_parameter, util::get_explanation},
+    decode::{
+        core::{
+            abi::try_decode_dynamic_parameter, authorization::Authorization, blob::BlobTransaction,
+        },
+        util::{get_explanation, score_layout},
+    },
     error::Error,
 };
 
@@ -137,11 +146,41 @@ pub async fn decode(args: DecodeArgs) -> Result<Vec<ResolvedFunction>, Error> {
         calldata = args.target.to_string().replacen("0x", "", 1);
     } else {
         logger.error("invalid target. must be a transaction hash or calldata (bytes).");
+        record_failure(ExitStatus::InvalidArguments);
         return Err(Error::GenericError(
             "invalid target. must be a transaction hash or calldata (bytes).".to_string(),
         ));
     }
 
+    // type 3 transactions also carry blobs, and type 4 transactions an authorization list, which
+    // are decoded alongside the calldata
+    let mut transaction_details = Vec::new();
+    if let Some(blob_transaction) = BlobTransaction::from_transaction(&raw_transaction) {
+        transaction_details.extend(blob_transaction.display());
+    }
+    if let Some(authorizations) = Authorization::from_transaction(&raw_transaction) {
+        transaction_details.extend(
+            authorizations.iter().enumerate().map(|(i, authorization)| authorization.display(i)),
+        );
+    }
+
+    // these transactions don't need calldata, in which case only their details are decoded
+    if calldata.is_empty() && !transaction_details.is_empty() {
+        let decode_call = trace.add_call(
+            0,
+            line!(),
+            "heimdall".to_string(),
+            "decode".to_string(),
+            vec![args.target],
+            "()".to_string(),
+        );
+        trace.br(decode_call);
+        trace.add_message(decode_call, line!(), vec!["calldata:  0 bytes".to_string()]);
+        trace.add_message(decode_call, line!(), transaction_details);
+        trace.display();
+        return Ok(Vec::new())
+    }
+
     // check if the calldata length is a standard length
     if calldata.len() % 2 != 0 || calldata.len() < 8 {
         logger.error("calldata is not a valid hex string.");
@@ -185,6 +224,7 @@ pub async fn decode(args: DecodeArgs) -> Result<Vec<ResolvedFunction>, Error> {
     }
 
     let mut matches: Vec<ResolvedFunction> = Vec::new();
+    let mut layout_scores: HashMap<String, u32> = HashMap::new();
     for potential_match in &potential_matches {
         // convert the string inputs into a vector of decoded types
         let mut inputs: Vec<ParamType> = Vec::new();
@@ -215,6 +255,11 @@ pub async fn decode(args: DecodeArgs) -> Result<Vec<ResolvedFunction>, Error> {
             .encode_input(&result);
             match decoded_function_call {
                 Ok(decoded_function_call) => {
+                    let layout_score = score_layout(
+                        decoded_function_call.get(4..).unwrap_or_default(),
+                        &byte_args,
+                    );
+
                     // decode the function call in trimmed bytes, removing 0s, because contracts
                     // can use nonstandard sized words and padding is
                     // hard
@@ -234,8 +279,13 @@ pub async fn decode(args: DecodeArgs) -> Result<Vec<ResolvedFunction>, Error> {
                     if similarity(decoded_function_call, &calldata[8..].replace('0', "")).abs() >=
                         0.90
                     {
+                        logger.debug(&format!(
+                            "potential match '{}' lays out the calldata with score {}.",
+                            &potential_match.signature, layout_score
+                        ));
                         let mut found_match = potential_match.clone();
                         found_match.decoded_inputs = Some(result);
+                        layout_scores.insert(found_match.signature.clone(), layout_score);
                         matches.push(found_match);
                     } else {
                         logger.debug(&format!("potential match '{}' ignored. decoded inputs differed from provided calldata.", &potential_match.signature).to_string());
@@ -349,10 +399,13 @@ pub async fn decode(args: DecodeArgs) -> Result<Vec<ResolvedFunction>, Error> {
 
     let mut selection: u8 = 0;
 
-    // sort matches by signature using score heuristic from `score_signature`
+    // sort matches by how closely they lay out the calldata, using `score_layout`, and then by
+    // signature using score heuristic from `score_signature`
     matches.sort_by(|a, b| {
-        let a_score = score_signature(&a.signature);
-        let b_score = score_signature(&b.signature);
+        let a_score =
+            (layout_scores.get(&a.signature).copied().unwrap_or(0), score_signature(&a.signature));
+        let b_score =
+            (layout_scores.get(&b.signature).copied().unwrap_or(0), score_signature(&b.signature));
         b_score.cmp(&a_score)
     });
 
@@ -395,6 +448,9 @@ pub async fn decode(args: DecodeArgs) -> Result<Vec<ResolvedFunction>, Error> {
         line!(),
         vec![format!("calldata:  {} bytes", calldata.len() / 2usize)],
     );
+    if !transaction_details.is_empty() {
+        trace.add_message(decode_call, line!(), transaction_details.clone());
+    }
     trace.br(decode_call);
 
     // build decoded string for --explain
@@ -405,6 +461,9 @@ pub async fn decode(args: DecodeArgs) -> Result<Vec<ResolvedFunction>, Error> {
         format!("selector: 0x{function_selector}"),
         format!("calldata: {} bytes", calldata.len() / 2usize)
     );
+    if !transaction_details.is_empty() {
+        decoded_string.push_str(&format!("\n{}", transaction_details.join("\n")));
+    }
 
     // build inputs
     for (i, input) in
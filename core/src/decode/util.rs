@@ -26,3 +26,43 @@ pub async fn get_explanation(
     );
     heimdall_common::resources::openai::complete(&prompt, openai_api_key).await
 }
+
+/// Score how closely a candidate signature's re-encoding of the decoded arguments lays out the
+/// same words as the calldata, from 0 to 1000. Both exclude the selector. Signatures which only
+/// decode part of the calldata, or read words as the wrong type, score lower than one which
+/// reproduces it exactly.
+pub fn score_layout(encoded: &[u8], calldata: &[u8]) -> u32 {
+    let encoded_words = encoded.chunks(32).collect::<Vec<_>>();
+    let calldata_words = calldata.chunks(32).collect::<Vec<_>>();
+    let total = encoded_words.len().max(calldata_words.len());
+    if total == 0 {
+        return 1000
+    }
+
+    let matching = encoded_words.iter().zip(calldata_words.iter()).filter(|(a, b)| a == b).count();
+    (matching * 1000 / total) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::decode::util::score_layout;
+
+    #[test]
+    fn test_score_layout_exact() {
+        let calldata = [[0u8; 32], [1u8; 32]].concat();
+        assert_eq!(score_layout(&calldata, &calldata), 1000);
+        assert_eq!(score_layout(&[], &[]), 1000);
+    }
+
+    #[test]
+    fn test_score_layout_partial() {
+        let calldata = [[0u8; 32], [1u8; 32], [2u8; 32], [3u8; 32]].concat();
+
+        // only the first two words are decoded
+        assert_eq!(score_layout(&calldata[..64], &calldata), 500);
+
+        // the last word is read differently
+        let encoded = [[0u8; 32], [1u8; 32], [2u8; 32], [4u8; 32]].concat();
+        assert_eq!(score_layout(&encoded, &calldata), 750);
+    }
+}
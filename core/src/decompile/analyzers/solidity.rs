@@ -3,12 +3,20 @@ use ethers::{
     prelude::U256,
 };
 use heimdall_common::{
-    ether::evm::{
-        core::{
-            opcodes::WrappedOpcode,
-            types::{byte_size_to_type, convert_bitmask},
+    ether::{
+        evm::{
+            core::{
+                opcodes::{WrappedInput, WrappedOpcode},
+                types::{byte_size_to_type, convert_bitmask},
+                vm::Instruction,
+            },
+            ext::exec::{
+                loops::{uses_variable, LoopEntry},
+                VMTrace,
+            },
         },
-        ext::exec::VMTrace,
+        panics::{decode_panic, describe_panic, PANIC_SELECTOR},
+        signatures::error_selector,
     },
     utils::{
         io::logging::TraceFactory,
@@ -16,10 +24,13 @@ use heimdall_common::{
     },
 };
 
-use super::super::{constants::AND_BITMASK_REGEX, precompile::decode_precompile};
+use super::super::{
+    constants::AND_BITMASK_REGEX,
+    precompile::{decode_precompile, decode_predeploy},
+};
 use crate::decompile::{
     constants::VARIABLE_SIZE_CHECK_REGEX,
-    util::{CalldataFrame, Function, StorageFrame},
+    util::{internal_function_name, is_memory_safe, CalldataFrame, Function, StorageFrame},
 };
 
 /// Converts a VMTrace to a Function through lexical and syntactic analysis
@@ -33,6 +44,7 @@ use crate::decompile::{
 /// - `branch` - Branch metadata for the current trace. In the format of (branch_depth,
 ///   branch_index)
 ///     - @jon-becker: This will be used later to determin if a condition is a require
+/// - `chain` - The chain the contract is deployed on, used to recognize its precompiles
 ///
 ///
 /// ## Returns
@@ -44,15 +56,44 @@ pub fn analyze_sol(
     trace_parent: u32,
     conditional_map: &mut Vec<String>,
     branch: (u32, u8),
+    chain: &str,
 ) -> Function {
     // make a clone of the recursed analysis function
     let mut function = function;
     let mut jumped_conditional: Option<String> = None;
 
+    // the body and exit of the loop whose condition ends this path, and the updates which end
+    // each iteration
+    let mut natural_loop: Option<(usize, usize, Vec<String>)> = None;
+
+    // the lowest and highest pc of the instructions since a line of logic was last emitted. jumps
+    // within a branch mean these aren't necessarily the first and last instructions
+    let mut pcs: Option<(u128, u128)> = None;
+
     // perform analysis on the operations of the current VMTrace branch
     for operation in &vm_trace.operations {
+        // attribute any lines the previous instruction emitted
+        if function.map_logic_pcs(pcs) {
+            pcs = None;
+        }
+
         let instruction = operation.last_instruction.clone();
-        let _storage = operation.storage.clone();
+
+        // the vm counts instructions from 1, rather than from pc 0
+        let pc = instruction.instruction - 1;
+        pcs = Some(pcs.map_or((pc, pc), |(low, high)| (low.min(pc), high.max(pc))));
+
+        // invalid stack patterns are recovered from rather than ending the path, so they're only
+        // reported
+        if let Some(diagnostic) = &operation.stack_diagnostic {
+            let notice = diagnostic.to_string();
+            if !function.notices.contains(&notice) {
+                trace.add_warn(trace_parent, instruction.instruction.try_into().unwrap(), &notice);
+                function.notices.push(notice);
+            }
+        }
+
+        let storage = operation.storage.clone();
         let memory = operation.memory.clone();
 
         let opcode_name = instruction.opcode_details.clone().unwrap().name;
@@ -78,6 +119,8 @@ pub fn analyze_sol(
                 "BASEFEE",
                 "SLOAD",
                 "SSTORE",
+                "TLOAD",
+                "TSTORE",
                 "CREATE",
                 "SELFDESTRUCT",
                 "CALL",
@@ -103,6 +146,7 @@ pub fn analyze_sol(
         if function.view &&
             [
                 "SSTORE",
+                "TSTORE",
                 "CREATE",
                 "SELFDESTRUCT",
                 "CALL",
@@ -190,6 +234,16 @@ pub fn analyze_sol(
                 ));
             }
         } else if opcode_name == "JUMPI" {
+            function.record_time_bound(&instruction, &vm_trace.children);
+
+            // the condition of a loop opens the loop, rather than a branch
+            if let Some(entry) = &vm_trace.loop_entry {
+                natural_loop = open_loop(&instruction, entry, &vm_trace.children, &mut function);
+                if natural_loop.is_some() {
+                    continue
+                }
+            }
+
             // this is an if conditional for the children branches
             let conditional = instruction.input_operations[1].solidify();
 
@@ -268,18 +322,48 @@ pub fn analyze_sol(
                     }
                 }
             }
-            // handle case with panics
-            else if revert_data.starts_with(&decode_hex("4e487b71").unwrap()) {
-                continue
+            // handle case with panics, which are decoded into what the compiler was checking
+            else if revert_data.starts_with(&PANIC_SELECTOR) {
+                let code = match decode_panic(&revert_data) {
+                    Some(code) => code,
+                    None => continue,
+                };
+                let panic_logic = |condition: &str| match code == U256::one() {
+                    true => format!("assert({condition});"),
+                    false => format!("require({condition}, \"{}\");", describe_panic(code)),
+                };
+
+                revert_logic = match jumped_conditional.clone() {
+                    Some(condition) => panic_logic(&condition),
+                    None => {
+                        // loop backwards through logic to find the last IF statement
+                        for i in (0..function.logic.len()).rev() {
+                            if function.logic[i].starts_with("if") {
+                                let conditional = match conditional_map.pop() {
+                                    Some(condition) => condition,
+                                    None => break,
+                                };
+
+                                function.logic[i] = panic_logic(&conditional);
+                            }
+                        }
+                        continue
+                    }
+                }
             }
             // handle case with custom error OR empty revert
             else {
                 let custom_error_placeholder = match revert_data.get(0..4) {
                     Some(selector) => {
-                        function.errors.insert(U256::from(selector), None);
+                        let selector = U256::from(selector);
+                        let arguments =
+                            function.error_arguments(instruction.inputs[0], &revert_data);
+                        function.errors.insert(selector, None);
+                        function.error_arguments.insert(selector, arguments.len());
                         format!(
-                            "CustomError_{}()",
-                            encode_hex_reduced(U256::from(selector)).replacen("0x", "", 1)
+                            "CustomError_{}({})",
+                            error_selector(&selector),
+                            arguments.join(", ")
                         )
                     }
                     None => "()".to_string(),
@@ -293,6 +377,12 @@ pub fn analyze_sol(
                             format!("require({condition}, {custom_error_placeholder});")
                         }
                     }
+                    // a custom error which isn't guarded by a condition is reverted with outright
+                    None if custom_error_placeholder != *"()" &&
+                        !function.logic.iter().any(|line| line.starts_with("if")) =>
+                    {
+                        format!("revert {custom_error_placeholder};")
+                    }
                     None => {
                         // loop backwards through logic to find the last IF statement
                         for i in (0..function.logic.len()).rev() {
@@ -386,8 +476,28 @@ pub fn analyze_sol(
 
             // add the sstore to the function's storage map
             function.storage.insert(key, StorageFrame { value, operations });
+
+            // prefer the expressions the VM recorded for the slot, whose hashes are resolved to
+            // the keys they were computed from
+            let mut slot = [0u8; 32];
+            key.to_big_endian(&mut slot);
+            let (key_operation, value_operation) = match storage.origin(slot) {
+                Some(origin) => (origin.key.clone(), origin.value.clone()),
+                None => (
+                    instruction.input_operations[0].clone(),
+                    instruction.input_operations[1].clone(),
+                ),
+            };
             function.logic.push(format!(
                 "storage[{}] = {};",
+                key_operation.solidify(),
+                value_operation.solidify(),
+            ));
+        } else if opcode_name == "TSTORE" {
+            // transient storage isn't part of the function's storage map, since it doesn't
+            // outlive the transaction
+            function.logic.push(format!(
+                "transient[{}] = {};",
                 instruction.input_operations[0].solidify(),
                 instruction.input_operations[1].solidify(),
             ));
@@ -415,6 +525,18 @@ pub fn analyze_sol(
                 source_offset,
                 source_offset.saturating_add(size_bytes)
             ));
+        } else if opcode_name == "MCOPY" {
+            let memory_offset = &instruction.input_operations[0];
+            let source_offset = &instruction.input_operations[1];
+            let size_bytes = &instruction.input_operations[2];
+
+            function.logic.push(format!(
+                "memory[{}] = memory[{}:{} + {}];",
+                memory_offset.solidify(),
+                source_offset.solidify(),
+                source_offset.solidify(),
+                size_bytes.solidify()
+            ));
         } else if opcode_name == "CODECOPY" {
             let memory_offset = &instruction.input_operations[0];
             let source_offset = instruction.inputs[1];
@@ -464,7 +586,18 @@ pub fn analyze_sol(
                     function.logic.push(precompile_logic);
                 }
                 _ => {
-                    function.logic.push(format!(
+                    if let Some(predeploy_logic) = decode_predeploy(
+                        chain,
+                        &function,
+                        &memory,
+                        instruction.inputs[1],
+                        instruction.inputs[2],
+                        instruction.inputs[3],
+                        &instruction.input_operations[4],
+                    ) {
+                        function.logic.push(predeploy_logic);
+                    } else {
+                        function.logic.push(format!(
                         "(bool success, bytes memory ret0) = address({}).staticcall{}(abi.encode({}));",
                         address.solidify(),
                         modifier,
@@ -474,6 +607,7 @@ pub fn analyze_sol(
                             .collect::<Vec<String>>()
                             .join(", "),
                     ));
+                    }
                 }
             }
         } else if opcode_name == "DELEGATECALL" {
@@ -499,7 +633,18 @@ pub fn analyze_sol(
                     function.logic.push(precompile_logic);
                 }
                 _ => {
-                    function.logic.push(format!(
+                    if let Some(predeploy_logic) = decode_predeploy(
+                        chain,
+                        &function,
+                        &memory,
+                        instruction.inputs[1],
+                        instruction.inputs[2],
+                        instruction.inputs[3],
+                        &instruction.input_operations[4],
+                    ) {
+                        function.logic.push(predeploy_logic);
+                    } else {
+                        function.logic.push(format!(
                         "(bool success, bytes memory ret0) = address({}).delegatecall{}(abi.encode({}));",
                         address.solidify(),
                         modifier,
@@ -509,6 +654,7 @@ pub fn analyze_sol(
                             .collect::<Vec<String>>()
                             .join(", "),
                     ));
+                    }
                 }
             }
         } else if opcode_name == "CALL" || opcode_name == "CALLCODE" {
@@ -541,7 +687,18 @@ pub fn analyze_sol(
                     function.logic.push(precompile_logic);
                 }
                 _ => {
-                    function.logic.push(format!(
+                    if let Some(predeploy_logic) = decode_predeploy(
+                        chain,
+                        &function,
+                        &memory,
+                        instruction.inputs[1],
+                        instruction.inputs[3],
+                        instruction.inputs[4],
+                        &instruction.input_operations[5],
+                    ) {
+                        function.logic.push(predeploy_logic);
+                    } else {
+                        function.logic.push(format!(
                         "(bool success, bytes memory ret0) = address({}).call{}(abi.encode({}));",
                         address.solidify(),
                         modifier,
@@ -551,22 +708,29 @@ pub fn analyze_sol(
                             .collect::<Vec<String>>()
                             .join(", ")
                     ));
+                    }
                 }
             }
-        } else if opcode_name == "CREATE" {
-            function.logic.push(format!(
-                "assembly {{ addr := create({}, {}, {}) }}",
-                instruction.input_operations[0].solidify(),
-                instruction.input_operations[1].solidify(),
-                instruction.input_operations[2].solidify(),
-            ));
-        } else if opcode_name == "CREATE2" {
+        } else if opcode_name == "CREATE" || opcode_name == "CREATE2" {
+            if opcode_name == "CREATE2" {
+                function.record_create2(&instruction, &memory);
+            }
+
+            // the initcode is read from memory, so the block is memory-safe if that read is
             function.logic.push(format!(
-                "assembly {{ addr := create({}, {}, {}, {}) }}",
-                instruction.input_operations[0].solidify(),
-                instruction.input_operations[1].solidify(),
-                instruction.input_operations[2].solidify(),
-                instruction.input_operations[3].solidify(),
+                "assembly {}{{ addr := {}({}) }}",
+                if is_memory_safe(&memory, instruction.inputs[1], instruction.inputs[2]) {
+                    "(\"memory-safe\") "
+                } else {
+                    ""
+                },
+                opcode_name.to_lowercase(),
+                instruction
+                    .input_operations
+                    .iter()
+                    .map(|operation| operation.solidify())
+                    .collect::<Vec<String>>()
+                    .join(", ")
             ));
         } else if opcode_name == "CALLDATALOAD" {
             let slot_as_usize: usize = instruction.inputs[0].try_into().unwrap_or(usize::MAX);
@@ -726,6 +890,56 @@ pub fn analyze_sol(
         }
     }
 
+    // the path ends at a recursive internal call, which symbolic execution doesn't follow
+    if let Some(recursive_call) = &vm_trace.recursive_call {
+        function.logic.push(format!(
+            "{}({});",
+            internal_function_name(recursive_call.entry_point),
+            recursive_call
+                .arguments
+                .iter()
+                .map(|argument| argument.solidify())
+                .collect::<Vec<String>>()
+                .join(", ")
+        ));
+        if !function.recursive_entry_points.contains(&recursive_call.entry_point) {
+            function.recursive_entry_points.push(recursive_call.entry_point);
+        }
+    }
+
+    // the path ends at a jump out of the code fragment being analyzed
+    if let Some(target) = &vm_trace.external_jump {
+        function.logic.push(format!("// jumps to {target:#x}, outside the fragment"));
+    }
+
+    function.map_logic_pcs(pcs);
+
+    // the loop's body is closed before the code after the loop
+    if let Some((body, exit, updates)) = natural_loop {
+        function = analyze_sol(
+            &vm_trace.children[body],
+            function,
+            trace,
+            trace_parent,
+            conditional_map,
+            (branch.0 + 1, body as u8),
+            chain,
+        );
+        function.logic.extend(updates);
+        function.logic.push("}".to_string());
+        function.map_logic_pcs(None);
+
+        return analyze_sol(
+            &vm_trace.children[exit],
+            function,
+            trace,
+            trace_parent,
+            conditional_map,
+            (branch.0 + 1, exit as u8),
+            chain,
+        )
+    }
+
     // recurse into the children of the VMTrace map
     for (i, child) in vm_trace.children.iter().enumerate() {
         function = analyze_sol(
@@ -735,6 +949,7 @@ pub fn analyze_sol(
             trace_parent,
             conditional_map,
             (branch.0 + 1, i as u8),
+            chain,
         );
     }
 
@@ -753,5 +968,88 @@ pub fn analyze_sol(
         function.logic.push("}".to_string());
     }
 
+    function.map_logic_pcs(None);
+
     function
 }
+
+/// Open the loop whose condition is the given JUMPI, as a `for` loop over the variable its
+/// condition depends on, or a `while` loop if there isn't one. Other loop-carried variables are
+/// declared before the loop, and updated at the end of each iteration.
+///
+/// Returns the index of the child which is the loop's body, the index of the child which exits
+/// the loop, and the updates which end each iteration, or `None` if the children aren't a loop.
+fn open_loop(
+    instruction: &Instruction,
+    entry: &LoopEntry,
+    children: &[VMTrace],
+    function: &mut Function,
+) -> Option<(usize, usize, Vec<String>)> {
+    // the body is the child which returns to the loop's header
+    let bodies = children
+        .iter()
+        .enumerate()
+        .filter_map(|(i, child)| entry.updates(child).map(|updates| (i, updates)))
+        .collect::<Vec<_>>();
+    let [(body, updates)] = bodies.as_slice() else { return None };
+    let exit = (0..children.len()).find(|i| i != body)?;
+
+    // the JUMPI jumps to the body when its condition holds, and falls through to it otherwise
+    let condition = &instruction.input_operations[1];
+    let destination = u128::try_from(instruction.inputs[0]).ok().and_then(|pc| pc.checked_add(1));
+    let condition = match Some(children[*body].instruction) == destination {
+        true => condition.clone(),
+        false => match condition.opcode.name {
+            "ISZERO" => match condition.inputs.first() {
+                Some(WrappedInput::Opcode(operation)) => operation.clone(),
+                _ => WrappedOpcode::new(0x15, vec![WrappedInput::Opcode(condition.clone())]),
+            },
+            _ => WrappedOpcode::new(0x15, vec![WrappedInput::Opcode(condition.clone())]),
+        },
+    };
+
+    let variables = entry
+        .variables
+        .iter()
+        .zip(entry.initial_values.iter())
+        .zip(updates.iter())
+        .map(|((variable, initial_value), update)| {
+            let name = variable.variable.solidify();
+            let update = (update != &variable.variable).then(|| match update.solidify() {
+                update
+                    if [format!("{name} + 0x01"), format!("0x01 + {name}")].contains(&update) =>
+                {
+                    format!("{name}++")
+                }
+                update => format!("{name} = {update}"),
+            });
+            (name, initial_value.solidify(), update, uses_variable(&condition, &variable.variable))
+        })
+        .collect::<Vec<_>>();
+
+    let counter = variables.iter().position(|(_, _, _, in_condition)| *in_condition);
+    for (i, (name, initial_value, ..)) in variables.iter().enumerate() {
+        if Some(i) != counter {
+            function.logic.push(format!("uint256 {name} = {initial_value};"));
+        }
+    }
+    function.logic.push(match counter {
+        Some(i) => {
+            let (name, initial_value, update, _) = &variables[i];
+            format!(
+                "for (uint256 {name} = {initial_value}; {}; {}) {{",
+                condition.solidify(),
+                update.clone().unwrap_or_default()
+            )
+        }
+        None => format!("while ({}) {{", condition.solidify()),
+    });
+
+    let updates = variables
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| Some(*i) != counter)
+        .filter_map(|(_, (_, _, update, _))| update.map(|update| format!("{update};")))
+        .collect();
+    Some((*body, exit, updates))
+}
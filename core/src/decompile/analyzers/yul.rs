@@ -1,6 +1,12 @@
-use ethers::abi::{decode, AbiEncode, ParamType};
+use ethers::{
+    abi::{decode, AbiEncode, ParamType},
+    types::U256,
+};
 use heimdall_common::{
-    ether::evm::{core::types::convert_bitmask, ext::exec::VMTrace},
+    ether::{
+        evm::{core::types::convert_bitmask, ext::exec::VMTrace},
+        panics::{decode_panic, describe_panic, PANIC_SELECTOR},
+    },
     utils::{
         io::logging::TraceFactory,
         strings::{decode_hex, encode_hex_reduced},
@@ -31,9 +37,39 @@ pub fn analyze_yul(
     let mut function = function;
     let mut jumped_conditional: Option<String> = None;
 
+    // the index of the conditional in the logic, and the pc it jumps to
+    let mut conditional_jump: Option<(usize, U256)> = None;
+
+    // the logic emitted by this branch, rather than by its parents, starts here
+    let branch_start = function.logic.len();
+
+    // the lowest and highest pc of the instructions since a line of logic was last emitted. jumps
+    // within a branch mean these aren't necessarily the first and last instructions
+    let mut pcs: Option<(u128, u128)> = None;
+
     // perform analysis on the operations of the current VMTrace branch
     for operation in &vm_trace.operations {
+        // attribute any lines the previous instruction emitted
+        if function.map_logic_pcs(pcs) {
+            pcs = None;
+        }
+
         let instruction = operation.last_instruction.clone();
+
+        // the vm counts instructions from 1, rather than from pc 0
+        let pc = instruction.instruction - 1;
+        pcs = Some(pcs.map_or((pc, pc), |(low, high)| (low.min(pc), high.max(pc))));
+
+        // invalid stack patterns are recovered from rather than ending the path, so they're only
+        // reported
+        if let Some(diagnostic) = &operation.stack_diagnostic {
+            let notice = diagnostic.to_string();
+            if !function.notices.contains(&notice) {
+                trace.add_warn(trace_parent, instruction.instruction.try_into().unwrap(), &notice);
+                function.notices.push(notice);
+            }
+        }
+
         let _storage = operation.storage.clone();
         let memory = operation.memory.clone();
 
@@ -60,6 +96,8 @@ pub fn analyze_yul(
                 "BASEFEE",
                 "SLOAD",
                 "SSTORE",
+                "TLOAD",
+                "TSTORE",
                 "CREATE",
                 "SELFDESTRUCT",
                 "CALL",
@@ -85,6 +123,7 @@ pub fn analyze_yul(
         if function.view &&
             [
                 "SSTORE",
+                "TSTORE",
                 "CREATE",
                 "SELFDESTRUCT",
                 "CALL",
@@ -106,6 +145,10 @@ pub fn analyze_yul(
             );
         }
 
+        if opcode_name == "CREATE2" {
+            function.record_create2(&instruction, &memory);
+        }
+
         if (0xA0..=0xA4).contains(&opcode_number) {
             // LOG0, LOG1, LOG2, LOG3, LOG4
             let logged_event = match operation.events.last() {
@@ -143,9 +186,12 @@ pub fn analyze_yul(
                 ));
             }
         } else if opcode_name == "JUMPI" {
+            function.record_time_bound(&instruction, &vm_trace.children);
+
             // this is an if conditional for the children branches
             let conditional = instruction.input_operations[1].yulify();
 
+            conditional_jump = Some((function.logic.len(), instruction.inputs[0]));
             function.logic.push(format!("if {conditional} {{").to_string());
             jumped_conditional = Some(conditional.clone());
             conditional_map.push(conditional);
@@ -165,33 +211,49 @@ pub fn analyze_yul(
             //       - if (true != false) { revert() };
             //       - require(true != false)
 
-            // handle case with error string abiencoded
-            if revert_data.starts_with(&decode_hex("4e487b71").unwrap()) {
-                continue
-            }
-            // handle case with custom error OR empty revert
-            else {
-                for i in (0..function.logic.len()).rev() {
-                    if function.logic[i].starts_with("if") {
-                        // get matching conditional
-                        let conditional = function.logic[i].split("if ").collect::<Vec<&str>>()[1]
-                            .split(" {")
-                            .collect::<Vec<&str>>()[0]
-                            .to_string();
-
-                        // we can negate the conditional to get the revert logic
-                        // TODO: make this a require statement, if revert is rlly gross but its
-                        // technically correct       I just ran into
-                        // issues with ending bracket matching
-                        function.logic[i] = format!(
-                            "if {conditional} {{ revert({}, {}); }} else {{",
-                            instruction.input_operations[0].yulify(),
-                            instruction.input_operations[1].yulify()
-                        );
-
-                        break
-                    }
+            // handle case with panics, which are annotated with what the compiler was checking
+            let panic_comment = match revert_data.starts_with(&PANIC_SELECTOR) {
+                true => match decode_panic(&revert_data) {
+                    Some(code) => format!("/* {} */ ", describe_panic(code)),
+                    None => continue,
+                },
+                false => String::new(),
+            };
+
+            let revert = format!(
+                "{panic_comment}revert({}, {})",
+                instruction.input_operations[0].yulify(),
+                instruction.input_operations[1].yulify()
+            );
+
+            // the revert happens when the last conditional jump isn't taken, so it's guarded by
+            // the negated conditional. the rest of the conditional's logic follows in a block of
+            // its own, which is closed once its children are analyzed
+            match (0..function.logic.len()).rev().find(|i| {
+                function.logic[*i].starts_with("if") && !function.logic[*i].contains("revert(")
+            }) {
+                Some(i) => {
+                    let conditional = function.logic[i].split("if ").collect::<Vec<&str>>()[1]
+                        .split(" {")
+                        .collect::<Vec<&str>>()[0]
+                        .to_string();
+
+                    // this branch's writes, such as the revert data, must happen before it reverts
+                    let mut body = match branch_start > i {
+                        true => function.logic.drain(branch_start..).collect::<Vec<String>>(),
+                        false => Vec::new(),
+                    };
+                    body.push(revert);
+
+                    function.logic[i] = format!(
+                        "if iszero({conditional}) {{\n{}\n}} {{",
+                        body.iter()
+                            .map(|line| format!("    {line}"))
+                            .collect::<Vec<String>>()
+                            .join("\n")
+                    );
                 }
+                None => function.logic.push(revert),
             }
         } else if opcode_name == "RETURN" {
             function.logic.push(format!(
@@ -221,6 +283,12 @@ pub fn analyze_yul(
                 instruction.input_operations[0].yulify(),
                 instruction.input_operations[1].yulify(),
             ));
+        } else if opcode_name == "TSTORE" {
+            function.logic.push(format!(
+                "tstore({}, {})",
+                instruction.input_operations[0].yulify(),
+                instruction.input_operations[1].yulify(),
+            ));
         } else if opcode_name.contains("MSTORE") || opcode_name.contains("MSTORE8") {
             let key = instruction.inputs[0];
             let value = instruction.inputs[1];
@@ -245,6 +313,7 @@ pub fn analyze_yul(
             "CODECOPY",
             "EXTCODECOPY",
             "RETURNDATACOPY",
+            "MCOPY",
         ]
         .contains(&opcode_name)
         {
@@ -416,11 +485,57 @@ pub fn analyze_yul(
         }
     }
 
+    // the path ends at a recursive internal call, which symbolic execution doesn't follow
+    if let Some(recursive_call) = &vm_trace.recursive_call {
+        function.logic.push(format!(
+            "{}({})",
+            internal_function_name(recursive_call.entry_point),
+            recursive_call
+                .arguments
+                .iter()
+                .map(|argument| argument.yulify())
+                .collect::<Vec<String>>()
+                .join(", ")
+        ));
+        if !function.recursive_entry_points.contains(&recursive_call.entry_point) {
+            function.recursive_entry_points.push(recursive_call.entry_point);
+        }
+    }
+
+    // the path ends at a jump out of the code fragment being analyzed
+    if let Some(target) = &vm_trace.external_jump {
+        function.logic.push(format!("// jumps to {target:#x}, outside the fragment"));
+    }
+
+    function.map_logic_pcs(pcs);
+
     // recurse into the children of the VMTrace map
     for (_, child) in vm_trace.children.iter().enumerate() {
         function = analyze_yul(child, function, trace, trace_parent, conditional_map);
     }
 
+    // the revert's guard assumes the jump is taken unless it reverts, but it may be the branch
+    // jumped to which reverts
+    if let Some((index, target)) = conditional_jump {
+        let reverts_on_jump = vm_trace.children.iter().any(|child| {
+            child.children.is_empty() &&
+                child.operations.first().is_some_and(|operation| {
+                    U256::from(operation.last_instruction.instruction - 1) == target
+                }) &&
+                child
+                    .operations
+                    .last()
+                    .is_some_and(|operation| operation.last_instruction.opcode == 0xfd)
+        });
+        if let (true, Some(conditional)) = (reverts_on_jump, &jumped_conditional) {
+            if let Some(line) = function.logic.get_mut(index) {
+                if let Some(guard) = line.strip_prefix(&format!("if iszero({conditional}) {{")) {
+                    *line = format!("if {conditional} {{{guard}");
+                }
+            }
+        }
+    }
+
     // check if the ending brackets are needed
     if jumped_conditional.is_some() &&
         conditional_map.contains(&jumped_conditional.clone().unwrap())
@@ -433,13 +548,23 @@ pub fn analyze_yul(
             }
         }
 
-        // if the last logic is an if statement, remove it because it's empty
-        if function.logic.last().unwrap().contains("if") {
+        // if the last logic is the conditional, remove it because it's empty
+        if function.logic.last() == jumped_conditional.map(|c| format!("if {c} {{")).as_ref() {
             function.logic.pop();
+        } else if let Some(guard) = function.logic.last().and_then(|line| line.strip_suffix(" {")) {
+            // nothing follows the revert's guard, so its block is empty
+            if guard.ends_with('}') && guard.contains("revert(") {
+                let guard = guard.to_string();
+                *function.logic.last_mut().unwrap() = guard;
+            } else {
+                function.logic.push("}".to_string());
+            }
         } else {
             function.logic.push("}".to_string());
         }
     }
 
+    function.map_logic_pcs(None);
+
     function
 }
@@ -24,12 +24,24 @@ lazy_static! {
     /// detects a storage access
     pub static ref STORAGE_ACCESS_REGEX: Regex = Regex::new(r"storage\[.*\]").unwrap();
 
+    /// detects a transient storage access
+    pub static ref TRANSIENT_ACCESS_REGEX: Regex = Regex::new(r"transient\[.*\]").unwrap();
+
     /// detects division by 1
     pub static ref DIV_BY_ONE_REGEX: Regex = Regex::new(r" \/ 0x01(?!\d)").unwrap();
 
     /// detects multiplication by 1
     pub static ref MUL_BY_ONE_REGEX: Regex = Regex::new(r"\b0x01\b\s*\*\s*| \*\s*\b0x01\b").unwrap();
 
+    /// detects a hex literal
+    pub static ref HEX_LITERAL_REGEX: Regex = Regex::new(r"\b0x[a-fA-F0-9]{1,64}\b").unwrap();
+
+    /// detects division by 10000, i.e. by 100% in basis points
+    pub static ref BASIS_POINTS_REGEX: Regex = Regex::new(r" \/ 0x2710\b").unwrap();
+
+    /// detects a constant multiplied into a division by 10000, capturing the constant
+    pub static ref BASIS_POINTS_FACTOR_REGEX: Regex = Regex::new(r"\* (0x[a-fA-F0-9]+)(\)*) \/ 10000\b").unwrap();
+
     /// memory variable regex
     pub static ref MEM_VAR_REGEX: Regex = Regex::new(r"^var_[a-zA-Z]{1,2}$").unwrap();
 
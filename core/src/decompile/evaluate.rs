@@ -0,0 +1,264 @@
+use heimdall_common::ether::artifact::Artifact;
+use serde::{Deserialize, Serialize};
+
+use super::util::Function;
+
+/// How well a single function in the artifact's ABI was recovered.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FunctionEvaluation {
+    pub selector: String,
+    pub signature: String,
+    /// Whether the selector was found in the bytecode
+    pub found: bool,
+    /// The type of each argument
+    pub expected_arguments: Vec<String>,
+    /// The type the decompiler inferred for each argument, if the selector was found
+    pub inferred_arguments: Option<Vec<String>>,
+    pub expected_mutability: String,
+    /// The state mutability the decompiler inferred, if the selector was found
+    pub inferred_mutability: Option<String>,
+}
+
+impl FunctionEvaluation {
+    pub fn arguments_match(&self) -> bool {
+        self.inferred_arguments.as_ref().map(|inferred| inferred.len()) ==
+            Some(self.expected_arguments.len())
+    }
+
+    /// The number of arguments whose type was inferred correctly.
+    pub fn types_correct(&self) -> usize {
+        self.inferred_arguments.as_ref().map_or(0, |inferred| {
+            inferred.iter().zip(&self.expected_arguments).filter(|(a, b)| a == b).count()
+        })
+    }
+
+    pub fn mutability_match(&self) -> bool {
+        self.inferred_mutability.as_ref() == Some(&self.expected_mutability)
+    }
+}
+
+/// What the decompiler recovered from a contract, compared with its compiler artifact.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Evaluation {
+    /// Each function in the artifact's ABI, ordered by selector
+    pub functions: Vec<FunctionEvaluation>,
+    /// Selectors found in the bytecode which aren't in the ABI
+    pub unexpected: Vec<String>,
+}
+
+impl Evaluation {
+    /// Compare the analyzed functions with the artifact's ABI. What the decompiler inferred is
+    /// compared, rather than the ABI's signatures which were applied to the output.
+    pub fn new(artifact: &Artifact, functions: &[Function]) -> Evaluation {
+        let mut evaluation = Evaluation::default();
+        for (selector, expected) in &artifact.functions {
+            let analyzed = functions.iter().find(|function| &function.selector == selector);
+            evaluation.functions.push(FunctionEvaluation {
+                selector: format!("0x{selector}"),
+                signature: expected.resolved.signature.clone(),
+                found: analyzed.is_some(),
+                expected_arguments: expected.resolved.inputs.clone(),
+                inferred_arguments: analyzed.map(inferred_arguments),
+                expected_mutability: expected.state_mutability.clone(),
+                inferred_mutability: analyzed
+                    .map(|function| function.state_mutability().to_string()),
+            });
+        }
+        evaluation.functions.sort_by(|a, b| a.selector.cmp(&b.selector));
+
+        evaluation.unexpected = functions
+            .iter()
+            .filter(|function| !artifact.functions.contains_key(&function.selector))
+            .map(|function| format!("0x{}", function.selector))
+            .collect();
+        evaluation.unexpected.sort();
+        evaluation
+    }
+
+    /// The fraction of the ABI's selectors found in the bytecode.
+    pub fn selector_recall(&self) -> f64 {
+        ratio(self.functions.iter().filter(|function| function.found).count(), self.functions.len())
+    }
+
+    /// The fraction of found functions whose argument count was inferred correctly.
+    pub fn argument_accuracy(&self) -> f64 {
+        let found = self.functions.iter().filter(|function| function.found);
+        ratio(found.clone().filter(|function| function.arguments_match()).count(), found.count())
+    }
+
+    /// The fraction of found functions' arguments whose type was inferred correctly.
+    pub fn type_accuracy(&self) -> f64 {
+        let found = self.functions.iter().filter(|function| function.found);
+        ratio(
+            found.clone().map(|function| function.types_correct()).sum(),
+            found.map(|function| function.expected_arguments.len()).sum(),
+        )
+    }
+
+    /// The fraction of found functions whose state mutability was inferred correctly.
+    pub fn mutability_accuracy(&self) -> f64 {
+        let found = self.functions.iter().filter(|function| function.found);
+        ratio(found.clone().filter(|function| function.mutability_match()).count(), found.count())
+    }
+
+    /// Write the evaluation as markdown, with a row for each mismatched function.
+    pub fn to_markdown(&self) -> String {
+        let mut output = vec![
+            format!(
+                "- selectors: {:.1}% of {} found, {} unexpected",
+                self.selector_recall() * 100.0,
+                self.functions.len(),
+                self.unexpected.len()
+            ),
+            format!("- arguments: {:.1}% correct", self.argument_accuracy() * 100.0),
+            format!("- types: {:.1}% correct", self.type_accuracy() * 100.0),
+            format!("- mutability: {:.1}% correct", self.mutability_accuracy() * 100.0),
+        ];
+
+        let mismatches = self
+            .functions
+            .iter()
+            .filter(|function| {
+                function.types_correct() < function.expected_arguments.len() ||
+                    !function.arguments_match() ||
+                    !function.mutability_match()
+            })
+            .collect::<Vec<&FunctionEvaluation>>();
+        if !mismatches.is_empty() {
+            output.push(String::new());
+            output.push(String::from("| function | arguments | mutability |"));
+            output.push(String::from("| --- | --- | --- |"));
+            for function in mismatches {
+                output.push(match function.found {
+                    true => format!(
+                        "| `{}` | ({}) (expected ({})) | {} (expected {}) |",
+                        function.signature,
+                        function.inferred_arguments.clone().unwrap_or_default().join(","),
+                        function.expected_arguments.join(","),
+                        function.inferred_mutability.as_deref().unwrap_or_default(),
+                        function.expected_mutability
+                    ),
+                    false => format!("| `{}` | not found | not found |", function.signature),
+                });
+            }
+        }
+
+        output.join("\n")
+    }
+}
+
+/// The type the decompiler gives each argument in its ABI, in order.
+fn inferred_arguments(function: &Function) -> Vec<String> {
+    let mut arguments = function.arguments.iter().collect::<Vec<_>>();
+    arguments.sort_by_key(|(slot, _)| **slot);
+    arguments
+        .into_iter()
+        .map(|(_, (_, potential_types))| potential_types.first().cloned().unwrap_or_default())
+        .collect()
+}
+
+/// The ratio of `count` to `total`, where nothing out of nothing is perfect.
+pub(crate) fn ratio(count: usize, total: usize) -> f64 {
+    match total {
+        0 => 1.0,
+        total => count as f64 / total as f64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use heimdall_common::ether::artifact::Artifact;
+
+    use crate::decompile::{
+        evaluate::*,
+        util::{CalldataFrame, Function},
+    };
+
+    fn function(selector: &str, view: bool) -> Function {
+        Function {
+            selector: selector.to_string(),
+            entry_point: 0,
+            arguments: HashMap::new(),
+            storage: HashMap::new(),
+            memory: HashMap::new(),
+            returns: None,
+            logic: Vec::new(),
+            logic_pcs: Vec::new(),
+            events: HashMap::new(),
+            errors: HashMap::new(),
+            error_arguments: HashMap::new(),
+            resolved_function: None,
+            indent_depth: 0,
+            notices: Vec::new(),
+            pure: false,
+            view,
+            payable: false,
+            alias_of: None,
+            recursive_entry_points: Vec::new(),
+            gas_estimate: None,
+            suggestion: None,
+            usage: None,
+            create2_sites: Vec::new(),
+            time_bounds: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_evaluation() {
+        let artifact = Artifact::parse(
+            r#"{"abi": [
+                {"type": "function", "name": "totalSupply", "stateMutability": "view",
+                 "inputs": [], "outputs": [{"name": "", "type": "uint256"}]},
+                {"type": "function", "name": "pause", "stateMutability": "nonpayable",
+                 "inputs": [], "outputs": []},
+                {"type": "function", "name": "transfer", "stateMutability": "nonpayable",
+                 "inputs": [{"name": "to", "type": "address"}, {"name": "amount", "type": "uint256"}],
+                 "outputs": [{"name": "", "type": "bool"}]}
+            ], "deployedBytecode": "0x6001"}"#,
+        )
+        .unwrap();
+
+        let mut transfer = function("a9059cbb", false);
+        for (slot, potential_types) in [(0, vec!["address"]), (1, vec!["bytes32", "uint256"])] {
+            transfer.arguments.insert(
+                slot,
+                (
+                    CalldataFrame {
+                        slot,
+                        operation: String::new(),
+                        mask_size: 32,
+                        heuristics: Vec::new(),
+                    },
+                    potential_types.into_iter().map(String::from).collect(),
+                ),
+            );
+        }
+        let evaluation = Evaluation::new(
+            &artifact,
+            &[function("18160ddd", false), transfer, function("deadbeef", true)],
+        );
+
+        assert_eq!(evaluation.unexpected, vec![String::from("0xdeadbeef")]);
+        assert_eq!(evaluation.argument_accuracy(), 1.0);
+        assert_eq!(evaluation.type_accuracy(), 0.5);
+        assert_eq!(evaluation.mutability_accuracy(), 0.5);
+        assert_eq!(
+            evaluation.to_markdown(),
+            [
+                "- selectors: 66.7% of 3 found, 1 unexpected",
+                "- arguments: 100.0% correct",
+                "- types: 50.0% correct",
+                "- mutability: 50.0% correct",
+                "",
+                "| function | arguments | mutability |",
+                "| --- | --- | --- |",
+                "| `totalSupply()` | () (expected ()) | nonpayable (expected view) |",
+                "| `pause()` | not found | not found |",
+                "| `transfer(address,uint256)` | (address,bytes32) (expected (address,uint256)) | nonpayable (expected nonpayable) |",
+            ]
+            .join("\n")
+        );
+    }
+}
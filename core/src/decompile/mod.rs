@@ -1,39 +1,62 @@
 pub mod analyzers;
 pub mod constants;
+pub mod evaluate;
 pub mod out;
 pub mod precompile;
 pub mod resolve;
+pub mod stream;
+pub mod summarize;
 pub mod util;
 use heimdall_common::{
+    constants::ADDRESS_REGEX,
     debug_max,
-    ether::{bytecode::get_bytecode_from_target, evm::ext::exec::VMTrace},
-    utils::{strings::get_shortned_target, threading::run_with_timeout},
+    ether::{
+        artifact::Artifact,
+        bytecode::{get_bytecode_from_target, get_delegation_from_target},
+        endpoints::set_signature_target,
+        evm::ext::exec::{loops::find_loops, VMTrace},
+        explorer::{get_verified_source, VerifiedSource},
+        proxy::{get_proxy_from_target, Proxy},
+    },
+    utils::{
+        io::{
+            crash::enter_pass,
+            exit::{exit_with, record_partial_analysis, ExitStatus},
+        },
+        strings::get_shortned_target,
+        threading::run_with_timeout,
+    },
 };
 
 use crate::{
     decompile::{
         analyzers::{solidity::analyze_sol, yul::analyze_yul},
+        evaluate::Evaluation,
         out::{abi::build_abi, solidity::build_solidity_output, yul::build_yul_output},
         resolve::*,
+        summarize::summarize,
         util::*,
     },
     disassemble::{disassemble, DisassemblerArgs},
+    interface::output::build_solidity_interface,
+    usage::{get_observed_calls, util::mine_usage},
 };
 
 use derive_builder::Builder;
-use heimdall_common::{
-    ether::{
-        compiler::detect_compiler,
-        selectors::{find_function_selectors, resolve_selectors},
-    },
-    utils::strings::encode_hex_reduced,
+use ethers::types::{H160, U256};
+use heimdall_common::ether::{
+    compiler::detect_compiler,
+    selectors::{find_function_selectors, resolve_selectors},
 };
 use indicatif::ProgressBar;
 use std::{collections::HashMap, time::Duration};
 
 use clap::{AppSettings, Parser};
 use heimdall_common::{
-    ether::{evm::core::vm::VM, signatures::*},
+    ether::{
+        evm::core::vm::VM,
+        signatures::{self, *},
+    },
     utils::io::logging::*,
 };
 
@@ -71,10 +94,16 @@ pub struct DecompilerArgs {
     #[clap(long = "include-sol")]
     pub include_solidity: bool,
 
-    /// Whether to include yul source code in the output (in beta).
+    /// Whether to include yul source code in the output (in beta). Loops are unrolled rather
+    /// than reconstructed, since the yul analyzer doesn't emit `for` loops.
     #[clap(long = "include-yul")]
     pub include_yul: bool,
 
+    /// Whether to include a Solidity interface in the output, with the resolved functions,
+    /// events, and errors.
+    #[clap(long = "include-interface")]
+    pub include_interface: bool,
+
     /// The output directory to write the output to or 'print' to print to the console
     #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
     pub output: String,
@@ -86,6 +115,53 @@ pub struct DecompilerArgs {
     /// The timeout for each function's symbolic execution in milliseconds.
     #[clap(long, short, default_value = "10000", hide_default_value = true)]
     pub timeout: u64,
+
+    /// Print each function as a line of JSON as soon as it's analyzed, instead of writing the
+    /// output once decompilation completes.
+    #[clap(long)]
+    pub stream: bool,
+
+    /// The chain of the target. On arbitrum, optimism, base, and zksync, calls to the chain's
+    /// precompiles and predeploys are decompiled as calls to the named contract.
+    #[clap(long, default_value = "ethereum", hide_default_value = true)]
+    pub chain: String,
+
+    /// A command which proposes a name and summary for each function. It's given the function as
+    /// JSON on stdin, and should print JSON with optional `name` and `summary` fields, which are
+    /// added to the output as unverified suggestions.
+    #[clap(long, default_value = "", hide_default_value = true)]
+    pub summarizer: String,
+
+    /// Annotate each function with how it's called in practice, from the target's transaction
+    /// history. Requires a contract address on a chain with a known block explorer.
+    #[clap(long)]
+    pub usage: bool,
+
+    /// A foundry, hardhat, or solc artifact for the target. Its ABI is used in place of resolved
+    /// signatures, and the output is evaluated against it.
+    #[clap(long, default_value = "", hide_default_value = true)]
+    pub artifact: String,
+
+    /// Whether to end each line of the decompiled source with a comment naming the range of pcs
+    /// it was decompiled from.
+    #[clap(long = "include-pcs")]
+    pub include_pcs: bool,
+
+    /// Whether the target is a fragment of code rather than a full contract, i.e. a snippet from
+    /// a trace or extracted init code. It's analyzed as a single function starting at
+    /// `--entry-pc`, without looking for a dispatcher, and jumps outside of it are treated as
+    /// jumps to external code.
+    #[clap(long)]
+    pub fragment: bool,
+
+    /// The pc a fragment is analyzed from, either in hex with a leading 0x, or in decimal.
+    #[clap(long = "entry-pc", default_value = "0", hide_default_value = true)]
+    pub entry_pc: String,
+
+    /// Whether to decompile a proxy's own code rather than its implementation's. The proxy's
+    /// pattern is noted in the output either way.
+    #[clap(long = "no-follow-proxy")]
+    pub no_follow_proxy: bool,
 }
 
 impl DecompilerArgsBuilder {
@@ -98,9 +174,19 @@ impl DecompilerArgsBuilder {
             skip_resolving: Some(false),
             include_solidity: Some(false),
             include_yul: Some(false),
+            include_interface: Some(false),
             output: Some(String::new()),
             name: Some(String::new()),
             timeout: Some(10000),
+            stream: Some(false),
+            chain: Some(String::from("ethereum")),
+            summarizer: Some(String::new()),
+            usage: Some(false),
+            artifact: Some(String::new()),
+            include_pcs: Some(false),
+            fragment: Some(false),
+            entry_pc: Some(String::from("0")),
+            no_follow_proxy: Some(false),
         }
     }
 }
@@ -109,10 +195,31 @@ impl DecompilerArgsBuilder {
 pub struct DecompileResult {
     pub source: Option<String>,
     pub abi: Option<Vec<ABIStructure>>,
+    /// The target's Solidity interface, if one was requested
+    pub interface: Option<String>,
+    /// The target's verified source code, when it's a verified contract
+    pub verified: Option<VerifiedSource>,
+    /// The contract the target delegates to, when it's an EOA with an EIP-7702 delegation
+    pub delegation: Option<String>,
+    /// The target's proxy pattern and implementation, when it's a proxy
+    pub proxy: Option<Proxy>,
+    /// How the output compares with the target's artifact, if one was given
+    pub evaluation: Option<Evaluation>,
 }
 
 pub async fn decompile(
     args: DecompilerArgs,
+) -> Result<DecompileResult, Box<dyn std::error::Error>> {
+    decompile_with(args, None, |_| {}).await
+}
+
+/// Decompile the target, calling `on_function` with each function as soon as it's analyzed. The
+/// given artifact takes the place of the one at `args.artifact`, for compiler outputs with several
+/// contracts.
+pub(crate) async fn decompile_with<F: FnMut(&Function)>(
+    args: DecompilerArgs,
+    artifact: Option<Artifact>,
+    mut on_function: F,
 ) -> Result<DecompileResult, Box<dyn std::error::Error>> {
     use std::time::Instant;
     let now = Instant::now();
@@ -131,7 +238,10 @@ pub async fn decompile(
     // ensure both --include-sol and --include-yul aren't set
     if args.include_solidity && args.include_yul {
         logger.error("arguments '--include-sol' and '--include-yul' are mutually exclusive.");
-        std::process::exit(1);
+        exit_with(
+            ExitStatus::InvalidArguments,
+            "arguments '--include-sol' and '--include-yul' are mutually exclusive.",
+        );
     }
 
     let shortened_target = get_shortned_target(&args.target);
@@ -144,14 +254,91 @@ pub async fn decompile(
         "()".to_string(),
     );
 
-    let contract_bytecode = get_bytecode_from_target(&args.target, &args.rpc_url).await?;
+    let artifact = match artifact {
+        Some(artifact) => Some(artifact),
+        None if !args.artifact.is_empty() => Some(Artifact::read(&args.artifact)?),
+        None => None,
+    };
+
+    let mut contract_bytecode = get_bytecode_from_target(&args.target, &args.rpc_url).await?;
+
+    // a proxy's own code only forwards calls, so its implementation is decompiled in its place
+    let proxy = match get_proxy_from_target(&args.target, &contract_bytecode, &args.rpc_url).await {
+        Ok(proxy) => proxy,
+        Err(e) => {
+            logger.warn(&format!("failed to resolve the target's proxy implementation: {e}"));
+            None
+        }
+    };
+    let mut analyzed_target = args.target.clone();
+    if let Some(proxy) = &proxy {
+        logger.info(&format!(
+            "'{}' is an {} for {} .",
+            &args.target, proxy.kind, &proxy.implementation
+        ));
+        if !args.no_follow_proxy {
+            match get_bytecode_from_target(&proxy.implementation, &args.rpc_url).await {
+                Ok(bytecode) => {
+                    contract_bytecode = bytecode;
+                    analyzed_target = proxy.implementation.clone();
+                }
+                Err(e) => logger.warn(&format!(
+                    "failed to fetch the implementation's bytecode, decompiling the proxy instead: {e}"
+                )),
+            }
+        }
+    }
+    let followed_proxy = analyzed_target != args.target;
+
+    // a deployed contract's selectors are resolved from its verified ABI first, when it has one
+    if ADDRESS_REGEX.is_match(&analyzed_target).unwrap_or(false) {
+        set_signature_target(&analyzed_target, &args.rpc_url);
+    }
+    let delegation = get_delegation_from_target(&args.target, &args.rpc_url).await?;
+
+    // a deployed contract is the deployer of the contracts it creates, so their addresses can be
+    // predicted
+    let deployer = match ADDRESS_REGEX.is_match(&args.target).unwrap_or(false) {
+        true => args.target.parse::<H160>().ok(),
+        false => None,
+    };
+
+    // a verified contract's source is a shortcut around reading the decompiled output
+    let verified = match ADDRESS_REGEX.is_match(&analyzed_target).unwrap_or(false) {
+        true => get_verified_source(&analyzed_target, &args.rpc_url).await,
+        false => None,
+    };
+    if let Some(verified) = &verified {
+        logger.info(&format!(
+            "'{}' is verified as '{}' ({}), its source is included in the output.",
+            &analyzed_target, &verified.name, &verified.compiler_version
+        ));
+    }
+
+    // how each function is called in practice, which is only known for a deployed contract
+    let usage = match args.usage {
+        true => {
+            enter_pass("transaction history");
+            match get_observed_calls(&args.target, &args.rpc_url, false).await {
+                Ok(calls) => mine_usage(&calls),
+                Err(e) => {
+                    logger.warn(&format!("failed to read the target's transaction history: {e}"));
+                    Vec::new()
+                }
+            }
+        }
+        false => Vec::new(),
+    };
 
     // disassemble the bytecode
+    enter_pass("disassemble");
     let disassembled_bytecode = disassemble(DisassemblerArgs {
         target: contract_bytecode.clone(),
         verbose: args.verbose.clone(),
         rpc_url: args.rpc_url.clone(),
         decimal_counter: false,
+        format: String::from("asm"),
+        verify: false,
         name: String::from(""),
         output: String::from(""),
     })
@@ -207,11 +394,24 @@ pub async fn decompile(
         (contract_bytecode.len() / 2usize).try_into()?,
     );
 
-    // find and resolve all selectors in the bytecode
-    let selectors = find_function_selectors(&evm, &disassembled_bytecode);
+    // find and resolve all selectors in the bytecode. a fragment has no dispatcher, so it's
+    // analyzed as a single function, named after its entry pc
+    let entry_pc = parse_pc(&args.entry_pc)?;
+    let selectors = match args.fragment {
+        true => HashMap::from([(format!("{entry_pc:08x}"), entry_pc)]),
+        false => find_function_selectors(&evm, &disassembled_bytecode),
+    };
 
     let mut resolved_selectors = HashMap::new();
-    if !args.skip_resolving {
+    if args.fragment {
+        logger.info(&format!("analyzing fragment from pc {entry_pc:#x}."));
+    } else if let Some(artifact) = &artifact {
+        logger.info(&format!(
+            "found {} possible function selectors, {} of which are in the artifact's ABI.",
+            selectors.len(),
+            selectors.keys().filter(|selector| artifact.functions.contains_key(*selector)).count()
+        ));
+    } else if !args.skip_resolving {
         resolved_selectors = resolve_selectors(selectors.keys().cloned().collect()).await;
 
         // if resolved selectors are empty, we can't perform symbolic execution
@@ -232,6 +432,9 @@ pub async fn decompile(
     }
 
     logger.info(&format!("performing symbolic execution on '{shortened_target}' ."));
+    if args.include_yul {
+        logger.debug("loops will be unrolled, since the yul analyzer doesn't emit `for` loops.");
+    }
 
     // get a new progress bar
     let mut decompilation_progress = ProgressBar::new_spinner();
@@ -240,6 +443,9 @@ pub async fn decompile(
 
     // perform EVM analysis
     let mut analyzed_functions = Vec::new();
+    let mut analyzed_entry_points: HashMap<u128, Function> = HashMap::new();
+    let mut selectors = selectors.into_iter().collect::<Vec<(String, u128)>>();
+    selectors.sort();
     for (selector, function_entry_point) in selectors {
         decompilation_progress.set_message(format!("executing '0x{selector}'"));
 
@@ -258,98 +464,179 @@ pub async fn decompile(
             &format!("discovered entry point: {function_entry_point}"),
         );
 
-        // get a map of possible jump destinations
-        let mut evm_clone = evm.clone();
-        let selector_clone = selector.clone();
-        let (map, jumpdest_count) = match run_with_timeout(
-            move || evm_clone.symbolic_exec_selector(&selector_clone, function_entry_point),
-            Duration::from_millis(args.timeout),
-        ) {
-            Some(map) => map,
-            None => {
-                trace.add_error(func_analysis_trace, line!(), "symbolic execution timed out!");
-                (VMTrace::default(), 0)
-            }
-        };
+        // selectors which jump to the same entry point share a body, so it's only analyzed once
+        let mut analyzed_function = match analyzed_entry_points.get(&function_entry_point) {
+            Some(shared) => {
+                trace.add_info(
+                    func_analysis_trace,
+                    line!(),
+                    &format!("shares its body with '0x{}'", shared.selector),
+                );
 
-        trace.add_debug(
-            func_analysis_trace,
-            function_entry_point.try_into()?,
-            &format!(
-                "execution tree {}",
-                match jumpdest_count {
-                    0 => {
-                        "appears to be linear".to_string()
-                    }
-                    _ => format!("has {jumpdest_count} unique branches"),
+                Function {
+                    selector: selector.clone(),
+                    alias_of: Some(shared.selector.clone()),
+                    ..shared.clone()
                 }
-            ),
-        );
+            }
+            None => {
+                // get a map of possible jump destinations
+                enter_pass(&format!("symbolic execution (0x{selector})"));
+                let mut evm_clone = evm.clone();
+                let selector_clone = selector.clone();
+                let fragment = args.fragment;
+                let reconstruct_loops = !args.include_yul;
+                let (map, jumpdest_count) = match run_with_timeout(
+                    move || {
+                        let (map, jumpdest_count) = match fragment {
+                            true => evm_clone.symbolic_exec_fragment(function_entry_point),
+                            false => evm_clone
+                                .symbolic_exec_selector(&selector_clone, function_entry_point),
+                        };
+
+                        // loops are traced again from their headers in terms of their
+                        // loop-carried variables, so they're reconstructed rather than unrolled
+                        let loops = if reconstruct_loops { find_loops(&map) } else { Vec::new() };
+                        if loops.is_empty() {
+                            return (map, jumpdest_count)
+                        }
+                        evm_clone.loops = loops;
+                        evm_clone.retrace_loops(map, fragment)
+                    },
+                    Duration::from_millis(args.timeout),
+                ) {
+                    Some(map) => map,
+                    None => {
+                        trace.add_error(
+                            func_analysis_trace,
+                            line!(),
+                            "symbolic execution timed out!",
+                        );
+                        record_partial_analysis();
+                        (VMTrace::default(), 0)
+                    }
+                };
 
-        decompilation_progress.set_message(format!("analyzing '0x{selector}'"));
+                trace.add_debug(
+                    func_analysis_trace,
+                    function_entry_point.try_into()?,
+                    &format!(
+                        "execution tree {}",
+                        match jumpdest_count {
+                            0 => {
+                                "appears to be linear".to_string()
+                            }
+                            _ => format!("has {jumpdest_count} unique branches"),
+                        }
+                    ),
+                );
 
-        // analyze execution tree
-        let mut analyzed_function;
-        if args.include_yul {
-            debug_max!("analyzing symbolic execution trace '0x{}' with yul analyzer", selector);
-            analyzed_function = analyze_yul(
-                &map,
-                Function {
-                    selector: selector.clone(),
-                    entry_point: function_entry_point,
-                    arguments: HashMap::new(),
-                    storage: HashMap::new(),
-                    memory: HashMap::new(),
-                    returns: None,
-                    logic: Vec::new(),
-                    events: HashMap::new(),
-                    errors: HashMap::new(),
-                    resolved_function: None,
-                    indent_depth: 0,
-                    notices: Vec::new(),
-                    pure: true,
-                    view: true,
-                    payable: true,
-                },
-                &mut trace,
-                func_analysis_trace,
-                &mut Vec::new(),
-            );
-        } else {
-            debug_max!("analyzing symbolic execution trace '0x{}' with sol analyzer", selector);
-            analyzed_function = analyze_sol(
-                &map,
-                Function {
-                    selector: selector.clone(),
-                    entry_point: function_entry_point,
-                    arguments: HashMap::new(),
-                    storage: HashMap::new(),
-                    memory: HashMap::new(),
-                    returns: None,
-                    logic: Vec::new(),
-                    events: HashMap::new(),
-                    errors: HashMap::new(),
-                    resolved_function: None,
-                    indent_depth: 0,
-                    notices: Vec::new(),
-                    pure: true,
-                    view: true,
-                    payable: true,
-                },
-                &mut trace,
-                func_analysis_trace,
-                &mut Vec::new(),
-                (0, 0),
-            );
-        }
+                decompilation_progress.set_message(format!("analyzing '0x{selector}'"));
 
-        // add notice to analyzed_function if jumpdest_count == 0, indicating that
-        // symbolic execution timed out
-        if jumpdest_count == 0 {
-            analyzed_function
-                .notices
-                .push("symbolic execution timed out. please report this!".to_string());
-        }
+                // analyze execution tree
+                enter_pass(&format!("analysis (0x{selector})"));
+                let mut analyzed_function;
+                if args.include_yul {
+                    debug_max!(
+                        "analyzing symbolic execution trace '0x{}' with yul analyzer",
+                        selector
+                    );
+                    analyzed_function = analyze_yul(
+                        &map,
+                        Function {
+                            selector: selector.clone(),
+                            entry_point: function_entry_point,
+                            arguments: HashMap::new(),
+                            storage: HashMap::new(),
+                            memory: HashMap::new(),
+                            returns: None,
+                            logic: Vec::new(),
+                            logic_pcs: Vec::new(),
+                            events: HashMap::new(),
+                            errors: HashMap::new(),
+                            error_arguments: HashMap::new(),
+                            resolved_function: None,
+                            indent_depth: 0,
+                            notices: Vec::new(),
+                            pure: true,
+                            view: true,
+                            payable: true,
+                            alias_of: None,
+                            recursive_entry_points: Vec::new(),
+                            gas_estimate: estimate_gas(&map),
+                            suggestion: None,
+                            usage: None,
+                            create2_sites: Vec::new(),
+                            time_bounds: Vec::new(),
+                        },
+                        &mut trace,
+                        func_analysis_trace,
+                        &mut Vec::new(),
+                    );
+                } else {
+                    debug_max!(
+                        "analyzing symbolic execution trace '0x{}' with sol analyzer",
+                        selector
+                    );
+                    analyzed_function = analyze_sol(
+                        &map,
+                        Function {
+                            selector: selector.clone(),
+                            entry_point: function_entry_point,
+                            arguments: HashMap::new(),
+                            storage: HashMap::new(),
+                            memory: HashMap::new(),
+                            returns: None,
+                            logic: Vec::new(),
+                            logic_pcs: Vec::new(),
+                            events: HashMap::new(),
+                            errors: HashMap::new(),
+                            error_arguments: HashMap::new(),
+                            resolved_function: None,
+                            indent_depth: 0,
+                            notices: Vec::new(),
+                            pure: true,
+                            view: true,
+                            payable: true,
+                            alias_of: None,
+                            recursive_entry_points: Vec::new(),
+                            gas_estimate: estimate_gas(&map),
+                            suggestion: None,
+                            usage: None,
+                            create2_sites: Vec::new(),
+                            time_bounds: Vec::new(),
+                        },
+                        &mut trace,
+                        func_analysis_trace,
+                        &mut Vec::new(),
+                        (0, 0),
+                        &args.chain,
+                    );
+                }
+
+                // add notice to analyzed_function if jumpdest_count == 0, indicating that
+                // symbolic execution timed out
+                if jumpdest_count == 0 {
+                    analyzed_function
+                        .notices
+                        .push("symbolic execution timed out. please report this!".to_string());
+                }
+                if args.fragment {
+                    analyzed_function.notices.push(format!(
+                        "decompiled from a code fragment, starting at pc {function_entry_point:#x}"
+                    ));
+                }
+                for site in analyzed_function.create2_sites.clone() {
+                    analyzed_function.notices.push(site.describe(deployer));
+                }
+                for bound in analyzed_function.time_bounds.clone() {
+                    analyzed_function.notices.push(bound.describe());
+                }
+
+                analyzed_entry_points.insert(function_entry_point, analyzed_function.clone());
+                analyzed_function
+            }
+        };
 
         let argument_count = analyzed_function.arguments.len();
 
@@ -387,7 +674,28 @@ pub async fn decompile(
         }
 
         // resolve signatures
-        if !args.skip_resolving {
+        if let Some(artifact) = &artifact {
+            // the artifact's ABI is ground truth, so it's used as is
+            if let Some(function) = artifact.functions.get(&selector) {
+                analyzed_function.resolved_function = Some(function.resolved.clone());
+            }
+            for error_selector in analyzed_function.errors.clone().into_keys() {
+                if let Some(error) = artifact.errors.get(&error_selector) {
+                    analyzed_function.errors.insert(error_selector, Some(error.clone()));
+                    all_resolved_errors
+                        .insert(signatures::error_selector(&error_selector), error.clone());
+                }
+            }
+            for (event_selector, (_, raw_event)) in analyzed_function.events.clone() {
+                if let Some(event) = artifact.events.get(&event_selector) {
+                    analyzed_function
+                        .events
+                        .insert(event_selector, (Some(event.clone()), raw_event));
+                    all_resolved_events
+                        .insert(signatures::event_selector(&event_selector), event.clone());
+                }
+            }
+        } else if !args.skip_resolving && !args.fragment {
             let resolved_functions = match resolved_selectors.get(&selector) {
                 Some(func) => func.clone(),
                 None => {
@@ -464,15 +772,11 @@ pub async fn decompile(
             // resolve custom error signatures
             let mut resolved_counter = 0;
             let resolved_errors: HashMap<String, Vec<ResolvedError>> = resolve_selectors(
-                analyzed_function
-                    .errors
-                    .keys()
-                    .map(|error_selector| encode_hex_reduced(*error_selector).replacen("0x", "", 1))
-                    .collect(),
+                analyzed_function.errors.keys().map(signatures::error_selector).collect(),
             )
             .await;
             for (error_selector, _) in analyzed_function.errors.clone() {
-                let error_selector_str = encode_hex_reduced(error_selector).replacen("0x", "", 1);
+                let error_selector_str = signatures::error_selector(&error_selector);
                 let mut selected_error_index: u8 = 0;
                 let mut resolved_error_selectors = match resolved_errors.get(&error_selector_str) {
                     Some(func) => func.clone(),
@@ -529,18 +833,14 @@ pub async fn decompile(
 
             // resolve custom event signatures
             resolved_counter = 0;
-            let resolved_events: HashMap<String, Vec<ResolvedLog>> = resolve_selectors(
-                analyzed_function
-                    .events
-                    .keys()
-                    .map(|event_selector| encode_hex_reduced(*event_selector).replacen("0x", "", 1))
-                    .collect(),
+            let resolved_events: HashMap<U256, Vec<ResolvedLog>> = resolve_event_signatures(
+                &analyzed_function.events.keys().copied().collect::<Vec<U256>>(),
             )
             .await;
             for (event_selector, (_, raw_event)) in analyzed_function.events.clone() {
                 let mut selected_event_index: u8 = 0;
-                let event_selector_str = encode_hex_reduced(event_selector).replacen("0x", "", 1);
-                let mut resolved_event_selectors = match resolved_events.get(&event_selector_str) {
+                let event_selector_str = signatures::event_selector(&event_selector);
+                let mut resolved_event_selectors = match resolved_events.get(&event_selector) {
                     Some(func) => func.clone(),
                     None => Vec::new(),
                 };
@@ -599,38 +899,91 @@ pub async fn decompile(
         decompilation_progress.enable_steady_tick(Duration::from_millis(100));
         decompilation_progress.set_style(logger.info_spinner());
 
+        // propose a name and summary with the user's summarizer, if there is one
+        if !args.summarizer.is_empty() && analyzed_function.alias_of.is_none() {
+            enter_pass(&format!("summarization (0x{selector})"));
+            match summarize(
+                &args.summarizer,
+                &analyzed_function,
+                Duration::from_millis(args.timeout),
+            ) {
+                Ok(suggestion) => analyzed_function.suggestion = suggestion,
+                Err(e) => {
+                    trace.add_warn(
+                        func_analysis_trace,
+                        line!(),
+                        &format!("failed to summarize: {e}"),
+                    );
+                }
+            }
+        }
+
+        analyzed_function.usage =
+            usage.iter().find(|usage| usage.selector == format!("0x{selector}")).cloned();
+
+        on_function(&analyzed_function);
         analyzed_functions.push(analyzed_function.clone());
     }
     decompilation_progress.finish_and_clear();
     logger.info("symbolic execution completed.");
     logger.info("building decompilation output.");
+    enter_pass("output");
+
+    let evaluation =
+        artifact.as_ref().map(|artifact| Evaluation::new(artifact, &analyzed_functions));
+    if let Some(evaluation) = &evaluation {
+        logger.info(&format!(
+            "found {:.1}% of the artifact's selectors, with {:.1}% of arguments and {:.1}% of mutabilities correct.",
+            evaluation.selector_recall() * 100.0,
+            evaluation.argument_accuracy() * 100.0,
+            evaluation.mutability_accuracy() * 100.0
+        ));
+    }
 
     let abi = build_abi(&args, analyzed_functions.clone(), &mut trace, decompile_call)?;
     trace.display();
     logger.debug(&format!("decompilation completed in {:?}.", now.elapsed()));
 
+    // named as `heimdall interface` names it by default
+    let interface = args.include_interface.then(|| build_solidity_interface("IContract", &abi));
+
+    let source = if args.include_solidity {
+        Some(build_solidity_output(
+            &args,
+            &abi,
+            analyzed_functions,
+            all_resolved_errors,
+            all_resolved_events,
+            &mut trace,
+            decompile_call,
+        )?)
+    } else if args.include_yul {
+        Some(build_yul_output(
+            &args,
+            analyzed_functions,
+            all_resolved_events,
+            &mut trace,
+            decompile_call,
+        )?)
+    } else {
+        None
+    };
+
     Ok(DecompileResult {
-        source: if args.include_solidity {
-            Some(build_solidity_output(
-                &args,
-                &abi,
-                analyzed_functions,
-                all_resolved_errors,
-                all_resolved_events,
-                &mut trace,
-                decompile_call,
-            )?)
-        } else if args.include_yul {
-            Some(build_yul_output(
-                &args,
-                analyzed_functions,
-                all_resolved_events,
-                &mut trace,
-                decompile_call,
-            )?)
-        } else {
-            None
-        },
+        // a delegating EOA runs its delegate's code, so the output notes whose code it is
+        source: match &delegation {
+            Some(delegate) => source.map(|source| add_delegation_banner(&source, delegate)),
+            None => source,
+        }
+        .map(|source| match &proxy {
+            Some(proxy) => add_proxy_banner(&source, proxy, followed_proxy),
+            None => source,
+        }),
         abi: Some(abi),
+        interface,
+        verified,
+        delegation,
+        proxy,
+        evaluation,
     })
 }
@@ -1,9 +1,12 @@
 use std::time::Duration;
 
 use ethers::{abi::AbiEncode, types::U256};
-use heimdall_common::utils::io::{
-    file::short_path,
-    logging::{Logger, TraceFactory},
+use heimdall_common::{
+    ether::signatures,
+    utils::io::{
+        file::short_path,
+        logging::{Logger, TraceFactory},
+    },
 };
 use indicatif::ProgressBar;
 use serde::{Deserialize, Serialize};
@@ -168,16 +171,7 @@ pub fn build_abi(
         };
 
         // determine the state mutability of the function
-        let state_mutability = match function.payable {
-            true => "payable",
-            false => match function.pure {
-                true => "pure",
-                false => match function.view {
-                    true => "view",
-                    false => "nonpayable",
-                },
-            },
-        };
+        let state_mutability = function.state_mutability();
 
         let constant = state_mutability == "pure" && function_inputs.is_empty();
 
@@ -224,27 +218,31 @@ pub fn build_abi(
                     }));
                 }
                 None => {
+                    let name =
+                        format!("CustomError_{}", signatures::error_selector(error_selector));
+
                     // check if the error is already in the ABI
                     if abi.iter().any(|x| match x {
-                        ABIStructure::Error(x) => {
-                            x.name ==
-                                format!(
-                                    "CustomError_{}",
-                                    &error_selector.encode_hex().replacen("0x", "", 1)
-                                )
-                        }
+                        ABIStructure::Error(x) => x.name == name,
                         _ => false,
                     }) {
                         continue
                     }
 
+                    // without a signature, each word the error is reverted with is an argument
+                    let inputs =
+                        (0..function.error_arguments.get(error_selector).copied().unwrap_or(0))
+                            .map(|index| ABIToken {
+                                name: format!("arg{index}"),
+                                internal_type: String::from("bytes32"),
+                                type_: String::from("bytes32"),
+                            })
+                            .collect();
+
                     abi.push(ABIStructure::Error(ErrorABI {
                         type_: "error".to_string(),
-                        name: format!(
-                            "CustomError_{}",
-                            &error_selector.encode_hex().replacen("0x", "", 1)
-                        ),
-                        inputs: Vec::new(),
+                        name,
+                        inputs,
                     }));
                 }
             }
@@ -1,17 +1,30 @@
 use super::super::super::constants::{
-    AND_BITMASK_REGEX, AND_BITMASK_REGEX_2, DIV_BY_ONE_REGEX, MEM_ACCESS_REGEX, MUL_BY_ONE_REGEX,
-    NON_ZERO_BYTE_REGEX,
+    AND_BITMASK_REGEX, AND_BITMASK_REGEX_2, BASIS_POINTS_FACTOR_REGEX, BASIS_POINTS_REGEX,
+    DIV_BY_ONE_REGEX, HEX_LITERAL_REGEX, MEM_ACCESS_REGEX, MUL_BY_ONE_REGEX, NON_ZERO_BYTE_REGEX,
+};
+use crate::decompile::{
+    constants::{
+        ENCLOSED_EXPRESSION_REGEX, MEM_VAR_REGEX, STORAGE_ACCESS_REGEX, TRANSIENT_ACCESS_REGEX,
+    },
+    precompile::calls_predeploy,
+    util::split_pc_comment,
+};
+use ethers::{
+    abi::{decode, Token},
+    types::U256,
 };
-use crate::decompile::constants::{ENCLOSED_EXPRESSION_REGEX, MEM_VAR_REGEX, STORAGE_ACCESS_REGEX};
 use heimdall_common::{
     constants::TYPE_CAST_REGEX,
     ether::{
-        evm::core::types::{byte_size_to_type, find_cast},
+        evm::core::types::{byte_size_to_type, find_cast, parse_function_parameters},
         signatures::{ResolvedError, ResolvedLog},
     },
-    utils::strings::{
-        base26_encode, classify_token, find_balanced_encapsulator,
-        find_balanced_encapsulator_backwards, tokenize, TokenType,
+    utils::{
+        integers::{basis_points_to_percent, format_literal},
+        strings::{
+            base26_encode, classify_token, decode_hex, encode_hex, find_balanced_encapsulator,
+            find_balanced_encapsulator_backwards, tokenize, TokenType,
+        },
     },
 };
 use indicatif::ProgressBar;
@@ -27,7 +40,12 @@ lazy_static! {
     static ref VARIABLE_MAP: Mutex<HashMap<String, String>> = Mutex::new(HashMap::new());
     static ref MEMORY_TYPE_MAP: Mutex<HashMap<String, String>> = Mutex::new(HashMap::new());
     static ref STORAGE_TYPE_MAP: Mutex<HashMap<String, String>> = Mutex::new(HashMap::new());
+    static ref TRANSIENT_LOOKUP_MAP: Mutex<HashMap<String, String>> = Mutex::new(HashMap::new());
+    static ref TRANSIENT_TYPE_MAP: Mutex<HashMap<String, String>> = Mutex::new(HashMap::new());
     static ref MEMORY_TYPE_DECLARATION_SET: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+
+    /// Held while postprocessing, since the maps above are shared by every decompilation
+    static ref POSTPROCESS_LOCK: Mutex<()> = Mutex::new(());
 }
 
 /// Convert bitwise operations to a variable type cast
@@ -277,6 +295,99 @@ fn simplify_parentheses(line: &str, paren_index: usize) -> String {
     cleaned
 }
 
+/// Split off the last of a list of comma-separated arguments, ignoring commas within nested
+/// parentheses or brackets.
+fn split_last_argument(arguments: &str) -> Option<(&str, &str)> {
+    let mut depth = 0;
+    let mut split = None;
+    for (i, c) in arguments.char_indices() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            ',' if depth == 0 => split = Some(i),
+            _ => {}
+        }
+    }
+
+    split.map(|i| (arguments[..i].trim(), arguments[i + 1..].trim()))
+}
+
+/// The arguments of a `keccak256(...)` call, if the call is the whole expression.
+fn hashed_arguments(expression: &str) -> Option<&str> {
+    let arguments = expression.strip_prefix("keccak256(")?.strip_suffix(')')?;
+    match find_balanced_encapsulator(expression, ('(', ')')) {
+        (_, end, true) if end == expression.len() => Some(arguments),
+        _ => None,
+    }
+}
+
+/// Whether an expression is a constant storage slot, e.g. `0x01`.
+fn is_constant_slot(expression: &str) -> bool {
+    expression
+        .strip_prefix("0x")
+        .is_some_and(|digits| !digits.is_empty() && digits.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// The slot and keys of a resolved mapping access, `keccak256(key, slot)`, where a nested
+/// mapping's slot is itself a resolved mapping access. Keys are in the order they're indexed by.
+fn mapping_access(slot: &str) -> Option<(String, Vec<String>)> {
+    let (key, slot) = split_last_argument(hashed_arguments(slot)?)?;
+    let (slot, mut keys) = match is_constant_slot(slot) {
+        true => (slot.to_string(), Vec::new()),
+        false => mapping_access(slot)?,
+    };
+    keys.push(key.to_string());
+    Some((slot, keys))
+}
+
+/// The slot and index of a resolved dynamic array access, `keccak256(slot) + index`.
+fn array_access(slot: &str) -> Option<(String, String)> {
+    let (base, index) = match (slot.split_once(" + "), slot.rsplit_once(" + ")) {
+        (Some((base, index)), _) if hashed_arguments(base).is_some() => (base, index),
+        (_, Some((index, base))) if hashed_arguments(base).is_some() => (base, index),
+        (None, _) => (slot, "0"),
+        _ => return None,
+    };
+    let array_slot = hashed_arguments(base)?;
+    is_constant_slot(array_slot).then(|| (array_slot.to_string(), index.to_string()))
+}
+
+/// The name of the mapping or array which was already named for `slot`, if any. Every access to
+/// the same slot shares a variable, however it's indexed.
+fn resolved_variable(
+    stor_map: &HashMap<String, String>,
+    slot: &str,
+    array: bool,
+) -> Option<String> {
+    stor_map
+        .iter()
+        .find_map(|(access, name)| {
+            let access = access.strip_prefix("storage[")?.strip_suffix(']')?;
+            let access_slot = match array {
+                true => array_access(access).map(|(slot, _)| slot),
+                false => mapping_access(access).map(|(slot, _)| slot),
+            };
+            (access_slot.as_deref() == Some(slot))
+                .then(|| name.split('[').next().unwrap_or_default())
+        })
+        .map(|name| name.to_string())
+}
+
+/// The type of a mapping or array variable, given an access to it and the types of its keys and
+/// values. Nested mappings are typed with the same key type at each level.
+fn collection_type(access: &str, variable: &str, key_type: &str, value_type: &str) -> String {
+    if variable.starts_with("stor_array_") {
+        return format!("{value_type}[]")
+    }
+
+    let depth = access
+        .strip_prefix("storage[")
+        .and_then(|access| access.strip_suffix(']'))
+        .and_then(mapping_access)
+        .map_or(1, |(_, keys)| keys.len());
+    (0..depth).fold(value_type.to_string(), |value, _| format!("mapping({key_type} => {value})"))
+}
+
 /// Converts memory and storage accesses to variables
 fn convert_access_to_variable(line: &str) -> String {
     let mut cleaned = line.to_owned();
@@ -330,6 +441,61 @@ fn convert_access_to_variable(line: &str) -> String {
         cleaned = convert_access_to_variable(&cleaned);
     }
 
+    // find a transient storage access, whose variables are named apart from storage's
+    let transient_access = match TRANSIENT_ACCESS_REGEX.find(&cleaned).unwrap() {
+        Some(x) => x.as_str(),
+        None => "",
+    };
+
+    // since the regex is greedy, match the transient storage brackets
+    let matched_loc = find_balanced_encapsulator(transient_access, ('[', ']'));
+    if matched_loc.2 {
+        let mut transient_map = TRANSIENT_LOOKUP_MAP.lock().unwrap();
+
+        // safe to unwrap since we know these indices exist
+        let memloc =
+            format!("transient{}", transient_access.get(matched_loc.0..matched_loc.1).unwrap());
+
+        let variable_name = match transient_map.get(&memloc) {
+            Some(loc) => loc.to_owned(),
+            None => {
+                let idex = transient_map.len() + 1;
+
+                // get the variable name and its type, which defaults to bytes32
+                let (variable_name, variable_type) = if memloc.contains("keccak256") {
+                    let keccak_key = find_balanced_encapsulator(&memloc, ('(', ')'));
+                    (
+                        format!(
+                            "tstor_map_{}[{}]",
+                            base26_encode(idex),
+                            memloc.get(keccak_key.0 + 1..keccak_key.1 - 1).unwrap_or("?")
+                        ),
+                        String::from("mapping(bytes32 => bytes32)"),
+                    )
+                } else {
+                    (format!("tstor_{}", base26_encode(idex)), String::from("bytes32"))
+                };
+
+                // add the variable to the maps
+                transient_map.insert(memloc.clone(), variable_name.clone());
+                TRANSIENT_TYPE_MAP.lock().unwrap().insert(
+                    variable_name.split('[').next().unwrap_or_default().to_string(),
+                    variable_type,
+                );
+                variable_name
+            }
+        };
+
+        // unlock the map
+        drop(transient_map);
+
+        // update the transient storage name
+        cleaned = cleaned.replace(memloc.as_str(), &variable_name);
+
+        // recurse to replace any other transient storage accesses
+        cleaned = convert_access_to_variable(&cleaned);
+    }
+
     // find a storage access
     let storage_access = match STORAGE_ACCESS_REGEX.find(&cleaned).unwrap() {
         Some(x) => x.as_str(),
@@ -351,26 +517,33 @@ fn convert_access_to_variable(line: &str) -> String {
                 // add the memory location to the map
                 let idex = stor_map.len() + 1;
 
-                // get the variable name
-                if memloc.contains("keccak256") {
+                // get the variable name. resolved mappings and arrays are indexed by their keys
+                let slot = &memloc["storage[".len()..memloc.len() - 1];
+                let variable_name = if let Some((slot, keys)) = mapping_access(slot) {
+                    let name = resolved_variable(&stor_map, &slot, false)
+                        .unwrap_or_else(|| format!("stor_map_{}", base26_encode(idex)));
+                    format!(
+                        "{name}{}",
+                        keys.iter().map(|key| format!("[{key}]")).collect::<String>()
+                    )
+                } else if let Some((slot, index)) = array_access(slot) {
+                    let name = resolved_variable(&stor_map, &slot, true)
+                        .unwrap_or_else(|| format!("stor_array_{}", base26_encode(idex)));
+                    format!("{name}[{index}]")
+                } else if memloc.contains("keccak256") {
                     let keccak_key = find_balanced_encapsulator(&memloc, ('(', ')'));
-
-                    let variable_name = format!(
+                    format!(
                         "stor_map_{}[{}]",
                         base26_encode(idex),
                         memloc.get(keccak_key.0 + 1..keccak_key.1 - 1).unwrap_or("?")
-                    );
-
-                    // add the variable to the map
-                    stor_map.insert(memloc.clone(), variable_name.clone());
-                    variable_name
+                    )
                 } else {
-                    let variable_name = format!("stor_{}", base26_encode(idex));
+                    format!("stor_{}", base26_encode(idex))
+                };
 
-                    // add the variable to the map
-                    stor_map.insert(memloc.clone(), variable_name.clone());
-                    variable_name
-                }
+                // add the variable to the map
+                stor_map.insert(memloc.clone(), variable_name.clone());
+                variable_name
             }
         };
 
@@ -413,7 +586,12 @@ fn convert_access_to_variable(line: &str) -> String {
 /// Checks if the current line contains an unnecessary assignment
 fn contains_unnecessary_assignment(line: &str, lines: &Vec<&str>) -> bool {
     // skip lines that don't contain an assignment, or contain a return or external calls
-    if !line.contains(" = ") || line.contains("bool success") || line.contains("return") {
+    if !line.contains(" = ") ||
+        line.starts_with("for (") ||
+        line.contains("bool success") ||
+        line.contains("return") ||
+        calls_predeploy(line)
+    {
         return false
     }
 
@@ -574,6 +752,17 @@ fn inherit_infer_mem_type(line: &str) -> String {
         }
     }
 
+    // a for loop declares its variable in its first clause
+    if let Some(header) = line.trim().strip_prefix("for (") {
+        let declaration = header.split(';').next().unwrap_or_default();
+        if let Some((variable, _)) = declaration.split_once(" = ") {
+            if let Some((var_type, var_name)) = variable.rsplit_once(' ') {
+                type_map.insert(var_name.to_string(), var_type.to_string());
+            }
+        }
+        return cleaned
+    }
+
     // if the line does not contains an instantiation, return
     if !line.contains(" = ") || line.trim().starts_with("stor") {
         return cleaned
@@ -691,7 +880,7 @@ fn inherit_infer_storage_type(line: &str) {
             }
 
             // add to type map
-            let mapping_type = format!("mapping({lhs_type} => {rhs_type})");
+            let mapping_type = collection_type(&storage_slot, &var_name, &lhs_type, &rhs_type);
             storage_map.insert(var_name, mapping_type);
         } else {
             // get the type of the rhs
@@ -733,7 +922,7 @@ fn inherit_infer_storage_type(line: &str) {
                     }
 
                     // add to type map
-                    let mapping_type = format!("mapping({lhs_type} => {rhs_type})");
+                    let mapping_type = collection_type(access, &var_name, &lhs_type, &rhs_type);
                     storage_map.insert(var_name.to_string(), mapping_type);
                 } else {
                     let mut handled = false;
@@ -772,6 +961,7 @@ fn replace_resolved(
     for (selector, error) in all_resolved_errors.iter() {
         let selector = selector.get(0..8).unwrap_or("00000000");
         if cleaned.contains(selector) {
+            cleaned = decode_error_arguments(&cleaned, &format!("CustomError_{selector}"), error);
             cleaned = cleaned.replace(&format!("CustomError_{selector}"), &error.name);
         }
     }
@@ -786,6 +976,69 @@ fn replace_resolved(
     cleaned
 }
 
+/// Decodes the arguments of a resolved custom error, if every word it is reverted with is a
+/// literal. Otherwise, the arguments are left as the expressions they were stored from.
+fn decode_error_arguments(line: &str, placeholder: &str, error: &ResolvedError) -> String {
+    let start = match line.find(&format!("{placeholder}(")) {
+        Some(start) => start + placeholder.len(),
+        None => return line.to_owned(),
+    };
+    let (arg_start, arg_end, valid) = find_balanced_encapsulator(&line[start..], ('(', ')'));
+    if !valid {
+        return line.to_owned()
+    }
+
+    let arguments = &line[start + arg_start + 1..start + arg_end - 1];
+    let mut data = Vec::new();
+    for word in arguments.split(", ").filter(|word| !word.is_empty()) {
+        let word = match word.strip_prefix("0x") {
+            Some(word) if word.len() <= 64 => format!("{word:0>64}"),
+            _ => return line.to_owned(),
+        };
+        match decode_hex(&word) {
+            Ok(word) => data.extend(word),
+            Err(_) => return line.to_owned(),
+        }
+    }
+
+    let tokens = match parse_function_parameters(&error.signature)
+        .and_then(|types| decode(&types, &data).ok())
+    {
+        Some(tokens) => tokens,
+        None => return line.to_owned(),
+    };
+
+    format!(
+        "{}{}{}",
+        &line[..start + arg_start + 1],
+        tokens.iter().map(render_token).collect::<Vec<String>>().join(", "),
+        &line[start + arg_end - 1..]
+    )
+}
+
+/// Renders a decoded ABI token as a Solidity literal
+fn render_token(token: &Token) -> String {
+    match token {
+        Token::Address(address) => format!("0x{}", encode_hex(address.as_bytes().to_vec())),
+        Token::FixedBytes(bytes) | Token::Bytes(bytes) => {
+            format!("hex\"{}\"", encode_hex(bytes.clone()))
+        }
+        Token::Int(value) => match value.bit(255) {
+            true => format!("-{}", (!*value).overflowing_add(1.into()).0),
+            false => value.to_string(),
+        },
+        Token::Uint(value) => value.to_string(),
+        Token::Bool(value) => value.to_string(),
+        Token::String(value) => format!("{value:?}"),
+        Token::FixedArray(tokens) | Token::Array(tokens) => {
+            format!("[{}]", tokens.iter().map(render_token).collect::<Vec<String>>().join(", "))
+        }
+        Token::Tuple(tokens) => {
+            format!("({})", tokens.iter().map(render_token).collect::<Vec<String>>().join(", "))
+        }
+    }
+}
+
 /// Simplifies arithmatic by removing unnecessary operations
 fn simplify_arithmatic(line: &str) -> String {
     let cleaned = DIV_BY_ONE_REGEX.replace_all(line, "");
@@ -795,17 +1048,52 @@ fn simplify_arithmatic(line: &str) -> String {
     cleaned.replace("!!", "")
 }
 
+/// Renders constants which have a readable form, e.g. `1e18`, `type(uint256).max`, or `30 days`,
+/// and notes divisions by 10000 as basis points.
+fn format_literals(line: &str) -> String {
+    let mut cleaned = HEX_LITERAL_REGEX
+        .replace_all(line, |captures: &fancy_regex::Captures| {
+            let literal = &captures[0];
+            U256::from_str_radix(&literal[2..], 16)
+                .ok()
+                .and_then(format_literal)
+                .unwrap_or_else(|| literal.to_string())
+        })
+        .to_string();
+
+    if !BASIS_POINTS_REGEX.is_match(&cleaned).unwrap_or(false) {
+        return cleaned
+    }
+    cleaned = BASIS_POINTS_REGEX.replace_all(&cleaned, " / 10000").to_string();
+
+    // the constant multiplied in is the rate, e.g. `amount * 30 / 10000` takes 0.3%
+    let factor = BASIS_POINTS_FACTOR_REGEX.captures(&cleaned).ok().flatten().and_then(|captures| {
+        let factor = U256::from_str_radix(&captures[1][2..], 16).ok()?;
+        Some((captures[0].to_string(), captures[2].to_string(), factor))
+    });
+    match factor {
+        Some((expression, parentheses, factor)) => format!(
+            "{} // {factor} bps = {}%",
+            cleaned.replace(&expression, &format!("* {factor}{parentheses} / 10000")),
+            basis_points_to_percent(factor)
+        ),
+        None => format!("{cleaned} // basis points"),
+    }
+}
+
 /// Cleans up a line using postprocessing techniques
 fn cleanup(
     line: &str,
     all_resolved_errors: HashMap<String, ResolvedError>,
     all_resolved_events: HashMap<String, ResolvedLog>,
 ) -> String {
-    let mut cleaned = line.to_owned();
+    // only the code is cleaned up, and its pc comment is kept if the code is
+    let (code, pc_comment) = split_pc_comment(line);
+    let mut cleaned = code.to_owned();
 
     // skip comments
     if cleaned.starts_with('/') {
-        return cleaned
+        return line.to_owned()
     }
 
     // Find and convert all castings
@@ -835,13 +1123,20 @@ fn cleanup(
     // Simplify arithmatic
     cleaned = simplify_arithmatic(&cleaned);
 
-    cleaned
+    // Render constants readably
+    cleaned = format_literals(&cleaned);
+
+    match cleaned.is_empty() {
+        true => cleaned,
+        false => format!("{cleaned}{pc_comment}"),
+    }
 }
 
 /// Finalizes postprocessing by removing unnecessary assignments
 fn finalize(lines: Vec<String>, bar: &ProgressBar) -> Vec<String> {
     let mut cleaned_lines: Vec<String> = Vec::new();
     let mut function_count = 0;
+    let code = lines.iter().map(|line| split_pc_comment(line).0).collect::<Vec<&str>>();
 
     // remove unused assignments
     for (i, line) in lines.iter().enumerate() {
@@ -858,6 +1153,11 @@ fn finalize(lines: Vec<String>, bar: &ProgressBar) -> Vec<String> {
                 ));
             }
 
+            // transient storage vars are declared with them, so they're told apart from storage
+            for (var_name, var_type) in TRANSIENT_TYPE_MAP.lock().unwrap().iter() {
+                storage_var_lines.push(format!("{var_type} transient {var_name};"));
+            }
+
             // sort storage vars by length, shortest first, then alphabetically
             storage_var_lines.sort_by(|a, b| a.len().cmp(&b.len()).then(a.cmp(b)));
 
@@ -874,10 +1174,7 @@ fn finalize(lines: Vec<String>, bar: &ProgressBar) -> Vec<String> {
         }
 
         // cleaned_lines.push(line.to_string());
-        if !contains_unnecessary_assignment(
-            line.trim(),
-            &lines[i + 1..].iter().map(|x| x.as_str()).collect(),
-        ) {
+        if !contains_unnecessary_assignment(code[i].trim(), &code[i + 1..].to_vec()) {
             cleaned_lines.push(line.to_string());
         } else {
             continue
@@ -917,6 +1214,18 @@ pub fn postprocess(
     all_resolved_events: HashMap<String, ResolvedLog>,
     bar: &ProgressBar,
 ) -> Vec<String> {
+    let _guard = POSTPROCESS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+    // start from a clean state, rather than the previous decompilation's
+    MEM_LOOKUP_MAP.lock().unwrap().clear();
+    STORAGE_LOOKUP_MAP.lock().unwrap().clear();
+    VARIABLE_MAP.lock().unwrap().clear();
+    MEMORY_TYPE_MAP.lock().unwrap().clear();
+    STORAGE_TYPE_MAP.lock().unwrap().clear();
+    TRANSIENT_LOOKUP_MAP.lock().unwrap().clear();
+    TRANSIENT_TYPE_MAP.lock().unwrap().clear();
+    MEMORY_TYPE_DECLARATION_SET.lock().unwrap().clear();
+
     let mut function_count = 0;
     let mut cleaned_lines: Vec<String> = Vec::new();
 
@@ -944,6 +1253,7 @@ mod tests {
 
     use std::collections::HashMap;
 
+    use heimdall_common::ether::signatures::{ResolvedError, ResolvedLog};
     use indicatif::ProgressBar;
 
     use crate::decompile::out::postprocessers::solidity::postprocess;
@@ -960,6 +1270,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_bitmask_conversion_keeps_pc_comment() {
+        let lines = vec![String::from(
+            "(0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff) & (arg0); // pc 0x1f-0x26",
+        )];
+
+        assert_eq!(
+            postprocess(lines, HashMap::new(), HashMap::new(), &ProgressBar::new(128)),
+            vec![String::from("uint256(arg0); // pc 0x1f-0x26")]
+        );
+    }
+
     #[test]
     fn test_bitmask_conversion_mask_after() {
         let lines = vec![String::from(
@@ -1067,4 +1389,122 @@ mod tests {
             vec![String::from("if (cast(((arg0 * (arg1)) + 1) / 10)) {")]
         );
     }
+
+    #[test]
+    fn test_format_literals() {
+        let lines = vec![
+            String::from("require(msg.value > 0x0de0b6b3a7640000);"),
+            String::from("require(block.timestamp > (arg0 + 0x278d00));"),
+            format!("return 0x{};", "f".repeat(64)),
+            String::from("return 0x0f4240; // pc 0x1f-0x26"),
+            String::from("return 0x2a;"),
+        ];
+
+        assert_eq!(
+            postprocess(lines, HashMap::new(), HashMap::new(), &ProgressBar::new(128)),
+            vec![
+                String::from("require(msg.value > 1e18);"),
+                String::from("require(block.timestamp > (arg0 + 30 days));"),
+                String::from("return type(uint256).max;"),
+                String::from("return 1e6; // pc 0x1f-0x26"),
+                String::from("return 0x2a;"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_format_basis_points() {
+        let lines = vec![
+            String::from("return (arg0 * 0x1e) / 0x2710;"),
+            String::from("return (arg0 * arg1) / 0x2710;"),
+        ];
+
+        assert_eq!(
+            postprocess(lines, HashMap::new(), HashMap::new(), &ProgressBar::new(128)),
+            vec![
+                String::from("return (arg0 * 30) / 10000; // 30 bps = 0.3%"),
+                String::from("return (arg0 * arg1) / 10000; // basis points"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resolved_error_decodes_literal_arguments() {
+        let lines = vec![
+            format!("revert CustomError_0000abcd(0x01, 0x{});", "f".repeat(64)),
+            String::from("require(arg0, CustomError_0000abcd(msg.sender, 0x01));"),
+        ];
+        let all_resolved_errors = HashMap::from([(
+            String::from("0000abcd"),
+            ResolvedError {
+                name: String::from("Overdrawn"),
+                signature: String::from("Overdrawn(address,int256)"),
+                inputs: vec![String::from("address"), String::from("int256")],
+            },
+        )]);
+
+        assert_eq!(
+            postprocess(lines, all_resolved_errors, HashMap::new(), &ProgressBar::new(128)),
+            vec![
+                String::from("revert Overdrawn(0x0000000000000000000000000000000000000001, -1);"),
+                String::from("require(arg0, Overdrawn(msg.sender, 0x01));"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resolved_event_with_leading_zeros() {
+        let lines = vec![String::from("emit Event_0000abcd(arg0);")];
+        let all_resolved_events = HashMap::from([(
+            format!("0000abcd{}", "0".repeat(56)),
+            ResolvedLog {
+                name: String::from("Brewed"),
+                signature: String::from("Brewed(uint256)"),
+                inputs: vec![String::from("uint256")],
+            },
+        )]);
+
+        assert_eq!(
+            postprocess(lines, HashMap::new(), all_resolved_events, &ProgressBar::new(128)),
+            vec![String::from("emit Brewed(arg0);")]
+        );
+    }
+
+    #[test]
+    fn test_resolved_mapping_keys_share_a_variable() {
+        let lines = vec![
+            String::from("storage[keccak256(msg.sender, 0x01)] = arg0;"),
+            String::from("storage[keccak256(arg1, 0x01)] = arg2;"),
+            String::from("storage[keccak256(arg1, 0x02)] = arg2;"),
+        ];
+
+        assert_eq!(
+            postprocess(lines, HashMap::new(), HashMap::new(), &ProgressBar::new(128)),
+            vec![
+                String::from("stor_map_a[msg.sender] = arg0;"),
+                String::from("stor_map_a[arg1] = arg2;"),
+                String::from("stor_map_c[arg1] = arg2;"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resolved_nested_mappings_and_arrays() {
+        let lines = vec![
+            String::from("storage[keccak256(arg0, keccak256(msg.sender, 0x01))] = arg1;"),
+            String::from("storage[keccak256(0x02) + arg0] = arg1;"),
+            String::from("storage[arg2 + keccak256(0x02)] = arg1;"),
+            String::from("storage[keccak256(0x02)] = arg1;"),
+        ];
+
+        assert_eq!(
+            postprocess(lines, HashMap::new(), HashMap::new(), &ProgressBar::new(128)),
+            vec![
+                String::from("stor_map_a[msg.sender][arg0] = arg1;"),
+                String::from("stor_array_b[arg0] = arg1;"),
+                String::from("stor_array_b[arg2] = arg1;"),
+                String::from("stor_array_b[0] = arg1;"),
+            ]
+        );
+    }
 }
@@ -6,12 +6,18 @@ use indicatif::ProgressBar;
 use lazy_static::lazy_static;
 use std::{collections::HashMap, sync::Mutex};
 
-use crate::decompile::constants::{ARGS_SPLIT_REGEX, ENCLOSED_EXPRESSION_REGEX};
+use crate::decompile::{
+    constants::{ARGS_SPLIT_REGEX, ENCLOSED_EXPRESSION_REGEX},
+    util::split_pc_comment,
+};
 
 lazy_static! {
     static ref MEM_LOOKUP_MAP: Mutex<HashMap<String, String>> = Mutex::new(HashMap::new());
     static ref VARIABLE_MAP: Mutex<HashMap<String, String>> = Mutex::new(HashMap::new());
     static ref TYPE_MAP: Mutex<HashMap<String, String>> = Mutex::new(HashMap::new());
+
+    /// Held while postprocessing, since the maps above are shared by every decompilation
+    static ref POSTPROCESS_LOCK: Mutex<()> = Mutex::new(());
 }
 
 /// Remove double negations from a line
@@ -279,8 +285,9 @@ fn add_resolved_events(line: &str, all_resolved_events: HashMap<String, Resolved
     );
 
     // get the event matching the log's selector
+    // topics are keyed by their full 32-byte word, but logged without leading zeros
     for (selector, resolved_event) in all_resolved_events.iter() {
-        if log_args.contains(&format!("0x{selector}")) {
+        if log_args.contains(&format!("0x{}", selector.trim_start_matches('0'))) {
             cleaned = format!(
                 "\n/* \"{}({})\" */\n{}",
                 resolved_event.name,
@@ -295,11 +302,13 @@ fn add_resolved_events(line: &str, all_resolved_events: HashMap<String, Resolved
 
 /// Cleans up a line using postprocessing techniques
 fn cleanup(line: &str, all_resolved_events: HashMap<String, ResolvedLog>) -> String {
-    let mut cleaned = line.to_owned();
+    // only the code is cleaned up, not its pc comment
+    let (code, pc_comment) = split_pc_comment(line);
+    let mut cleaned = code.to_owned();
 
     // skip comments
     if cleaned.starts_with('/') {
-        return cleaned
+        return line.to_owned()
     }
 
     // remove double negations
@@ -320,7 +329,7 @@ fn cleanup(line: &str, all_resolved_events: HashMap<String, ResolvedLog>) -> Str
     // add resolved events as comments
     cleaned = add_resolved_events(&cleaned, all_resolved_events);
 
-    cleaned
+    format!("{cleaned}{pc_comment}")
 }
 
 /// Postprocesses the cleaned lines
@@ -329,8 +338,19 @@ pub fn postprocess(
     all_resolved_events: HashMap<String, ResolvedLog>,
     bar: &ProgressBar,
 ) -> Vec<String> {
+    let _guard = POSTPROCESS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+    // start from a clean state, rather than the previous decompilation's
+    MEM_LOOKUP_MAP.lock().unwrap().clear();
+    VARIABLE_MAP.lock().unwrap().clear();
+    TYPE_MAP.lock().unwrap().clear();
+
     let mut indentation: usize = 0;
     let mut function_count = 0;
+
+    // the indentation of the helper function being defined, if any. helpers are written exactly
+    // as they're defined, since cleaning up e.g. `castToAddress` would make it call itself
+    let mut helper_indentation: Option<usize> = None;
     let mut cleaned_lines: Vec<String> = lines;
 
     // clean up each line using postprocessing techniques
@@ -346,8 +366,17 @@ pub fn postprocess(
             indentation = indentation.saturating_sub(1);
         }
 
-        // cleanup the line
-        let cleaned = cleanup(line, all_resolved_events.clone());
+        // cleanup the line, unless it's part of a helper function
+        if line.starts_with("function ") {
+            helper_indentation = Some(indentation);
+        }
+        let cleaned = match helper_indentation {
+            Some(_) => line.to_owned(),
+            None => cleanup(line, all_resolved_events.clone()),
+        };
+        if line.starts_with('}') && helper_indentation == Some(indentation) {
+            helper_indentation = None;
+        }
 
         // apply postprocessing and indentation
         *line = format!(
@@ -15,7 +15,7 @@ use indicatif::ProgressBar;
 use super::{
     super::{
         constants::{DECOMPILED_SOURCE_HEADER_SOL, STORAGE_ACCESS_REGEX},
-        util::Function,
+        util::{internal_function_name, Function},
         DecompilerArgs,
     },
     abi::ABIStructure,
@@ -135,10 +135,21 @@ pub fn build_solidity_output(
             function.logic = vec![format!(
                 "return string(rlp.encodePacked(storage[{}]));",
                 storage_access[storage_access_loc.0 + 1..storage_access_loc.1 - 1].to_string()
-            )]
+            )];
+            function.logic_pcs = vec![function
+                .logic_pcs
+                .iter()
+                .flatten()
+                .copied()
+                .reduce(|a, b| (a.0.min(b.0), a.1.max(b.1)))];
         }
     }
 
+    // selectors which share another function's body are listed in that function's header,
+    // rather than repeating the body
+    let (aliases, functions): (Vec<Function>, Vec<Function>) =
+        functions.into_iter().partition(|function| function.alias_of.is_some());
+
     for function in functions {
         progress_bar.set_message(format!("writing logic for '0x{}'", function.selector));
 
@@ -163,7 +174,7 @@ pub fn build_solidity_output(
             }
         );
 
-        let function_header = match function.resolved_function {
+        let function_header = match &function.resolved_function {
             Some(resolved_function) => {
                 format!(
                     "function {}({}) {}{}",
@@ -236,13 +247,90 @@ pub fn build_solidity_output(
             ),
         ]);
 
-        for notice in function.notices {
+        let function_aliases = aliases
+            .iter()
+            .filter(|alias| alias.alias_of.as_ref() == Some(&function.selector))
+            .collect::<Vec<&Function>>();
+        for alias in &function_aliases {
+            decompiled_output.push(format!(
+                "/// @custom:alias       0x{} {}",
+                alias.selector,
+                match &alias.resolved_function {
+                    Some(resolved_function) => resolved_function.signature.clone(),
+                    None => format!("Unresolved_{}", alias.selector),
+                }
+            ));
+        }
+
+        for entry_point in &function.recursive_entry_points {
+            decompiled_output
+                .push(format!("/// @custom:recursive   {}", internal_function_name(*entry_point)));
+        }
+
+        // suggestions from the user's summarizer are marked as such, since heimdall didn't
+        // recover them
+        if let Some(suggestion) = &function.suggestion {
+            if let Some(name) = &suggestion.name {
+                decompiled_output
+                    .push(format!("/// @custom:suggested   {name} {}", suggestion.provenance()));
+            }
+            if let Some(summary) = &suggestion.summary {
+                decompiled_output
+                    .push(format!("/// @custom:summary     {summary} {}", suggestion.provenance()));
+            }
+        }
+
+        // empirical usage, from the target's transaction history
+        if let Some(usage) = &function.usage {
+            for note in usage.notes() {
+                decompiled_output.push(format!("/// @custom:usage       {note}"));
+            }
+        }
+
+        decompiled_output.push(format!("/// @notice             {}", function.summary()));
+        for notice in &function.notices {
             decompiled_output.push(format!("/// @notice             {notice}"));
         }
+        for entry_point in &function.recursive_entry_points {
+            decompiled_output.push(format!(
+                "/// @notice             {} calls itself, so its recursive calls aren't inlined",
+                internal_function_name(*entry_point)
+            ));
+        }
+        if !function_aliases.is_empty() {
+            decompiled_output.push(format!(
+                "/// @notice             {} {} dispatched to the same entry point, so {} this body",
+                function_aliases
+                    .iter()
+                    .map(|alias| format!("0x{}", alias.selector))
+                    .collect::<Vec<String>>()
+                    .join(", "),
+                if function_aliases.len() > 1 { "are" } else { "is" },
+                if function_aliases.len() > 1 { "they share" } else { "it shares" }
+            ));
+        }
+
+        // evidence for the decompiled body, for readers who didn't run the decompiler
+        decompiled_output.extend(vec![
+            format!(
+                "/// @dev                selector 0x{}, confidence {}",
+                function.selector,
+                function.confidence()
+            ),
+            format!("/// @dev                guards: {}", function.guards()),
+            match function.gas_estimate {
+                Some(gas) => {
+                    format!(
+                        "/// @dev                gas: ~{gas} on the most expensive successful path"
+                    )
+                }
+                None => String::from("/// @dev                gas: unknown"),
+            },
+        ]);
 
         // sort arguments by their calldata index
-        let mut sorted_arguments: Vec<_> = function.arguments.into_iter().collect();
-        sorted_arguments.sort_by(|x, y| x.0.cmp(&y.0));
+        let mut sorted_arguments: Vec<_> = function.arguments.iter().collect();
+        sorted_arguments.sort_by(|x, y| x.0.cmp(y.0));
 
         for (index, (_, solidity_type)) in sorted_arguments {
             decompiled_output.push(format!("/// @param              arg{index} {solidity_type:?}"));
@@ -251,7 +339,10 @@ pub fn build_solidity_output(
         decompiled_output.push(function_header);
 
         // build the function's body
-        decompiled_output.extend(function.logic);
+        decompiled_output.extend(match args.include_pcs {
+            true => function.annotated_logic(),
+            false => function.logic,
+        });
 
         decompiled_output.push(String::from("}"));
     }
@@ -1,6 +1,10 @@
 use std::{collections::HashMap, time::Duration};
 
-use crate::decompile::{constants::DECOMPILED_SOURCE_HEADER_YUL, util::Function, DecompilerArgs};
+use crate::decompile::{
+    constants::DECOMPILED_SOURCE_HEADER_YUL,
+    util::{internal_function_name, Function},
+    DecompilerArgs,
+};
 use heimdall_common::{
     ether::signatures::ResolvedLog,
     utils::io::{
@@ -63,7 +67,7 @@ pub fn build_yul_output(
         progress_bar.set_message(format!("building logic for '0x{}'", function.selector));
 
         // build the function's header and parameters
-        let function_header = match function.resolved_function {
+        let function_header = match &function.resolved_function {
             Some(resolved_function) => {
                 format!(
                     "{}({})",
@@ -119,13 +123,46 @@ pub fn build_yul_output(
             }
         };
 
+        // selectors which share another function's body don't repeat it
+        if let Some(alias_of) = &function.alias_of {
+            decompiled_output
+                .push(format!("case 0x{} /* \"{}\" */ {{", function.selector, function_header));
+            decompiled_output.push(format!("// shares its body with 0x{alias_of}"));
+            decompiled_output.push(String::from("}"));
+            continue
+        }
+
         // sort arguments by their calldata index
-        let mut sorted_arguments: Vec<_> = function.arguments.into_iter().collect();
-        sorted_arguments.sort_by(|x, y| x.0.cmp(&y.0));
+        let mut sorted_arguments: Vec<_> = function.arguments.iter().collect();
+        sorted_arguments.sort_by(|x, y| x.0.cmp(y.0));
 
         decompiled_output
             .push(format!("case 0x{} /* \"{}\" */ {{", function.selector, function_header));
-        decompiled_output.extend(function.logic);
+        if let Some(suggestion) = &function.suggestion {
+            if let Some(name) = &suggestion.name {
+                decompiled_output
+                    .push(format!("// suggested name: {name} {}", suggestion.provenance()));
+            }
+            if let Some(summary) = &suggestion.summary {
+                decompiled_output
+                    .push(format!("// summary: {summary} {}", suggestion.provenance()));
+            }
+        }
+        if let Some(usage) = &function.usage {
+            for note in usage.notes() {
+                decompiled_output.push(format!("// usage: {note}"));
+            }
+        }
+        for entry_point in &function.recursive_entry_points {
+            decompiled_output.push(format!(
+                "// {} calls itself, so its recursive calls aren't inlined",
+                internal_function_name(*entry_point)
+            ));
+        }
+        decompiled_output.extend(match args.include_pcs {
+            true => function.annotated_logic(),
+            false => function.logic,
+        });
         decompiled_output.push(String::from("}"));
     }
 
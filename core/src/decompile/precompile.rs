@@ -1,7 +1,7 @@
-use ethers::types::U256;
-use heimdall_common::ether::evm::core::opcodes::WrappedOpcode;
+use ethers::{types::U256, utils::keccak256};
+use heimdall_common::ether::evm::core::{memory::Memory, opcodes::WrappedOpcode};
 
-use super::util::StorageFrame;
+use super::util::{Function, StorageFrame};
 
 /// Detects the usage of precompiled contracts within the EVM. Whenever an internal call is found
 /// within symbolic execution traces, this function will attempt to detect if the call is to a
@@ -64,3 +64,230 @@ pub fn decode_precompile(
 
     (is_ext_call_precompile, ext_call_logic)
 }
+
+/// A chain-specific precompile or predeploy, and the functions it exposes, given as their
+/// signature and return type.
+struct Predeploy {
+    name: &'static str,
+    address: &'static str,
+    functions: &'static [(&'static str, Option<&'static str>)],
+}
+
+const ARBITRUM_PREDEPLOYS: &[Predeploy] = &[
+    Predeploy {
+        name: "ArbSys",
+        address: "0x64",
+        functions: &[
+            ("arbBlockNumber()", Some("uint256")),
+            ("arbBlockHash(uint256)", Some("bytes32")),
+            ("arbChainID()", Some("uint256")),
+            ("arbOSVersion()", Some("uint256")),
+            ("getStorageGasAvailable()", Some("uint256")),
+            ("isTopLevelCall()", Some("bool")),
+            ("wasMyCallersAddressAliased()", Some("bool")),
+            ("myCallersAddressWithoutAliasing()", Some("address")),
+            ("mapL1SenderContractAddressToL2Alias(address,address)", Some("address")),
+            ("sendTxToL1(address,bytes)", Some("uint256")),
+            ("withdrawEth(address)", Some("uint256")),
+        ],
+    },
+    Predeploy {
+        name: "ArbInfo",
+        address: "0x65",
+        functions: &[("getBalance(address)", Some("uint256")), ("getCode(address)", Some("bytes"))],
+    },
+    Predeploy {
+        name: "ArbGasInfo",
+        address: "0x6c",
+        functions: &[
+            ("getPricesInWei()", Some("bytes")),
+            ("getL1BaseFeeEstimate()", Some("uint256")),
+            ("getL1GasPriceEstimate()", Some("uint256")),
+            ("getCurrentTxL1GasFees()", Some("uint256")),
+            ("getMinimumGasPrice()", Some("uint256")),
+        ],
+    },
+    Predeploy {
+        name: "ArbRetryableTx",
+        address: "0x6e",
+        functions: &[
+            ("redeem(bytes32)", Some("bytes32")),
+            ("getLifetime()", Some("uint256")),
+            ("getTimeout(bytes32)", Some("uint256")),
+            ("keepalive(bytes32)", Some("uint256")),
+            ("getBeneficiary(bytes32)", Some("address")),
+            ("cancel(bytes32)", None),
+            ("getCurrentRedeemer()", Some("address")),
+        ],
+    },
+];
+
+const OPTIMISM_PREDEPLOYS: &[Predeploy] = &[
+    Predeploy {
+        name: "L2CrossDomainMessenger",
+        address: "0x4200000000000000000000000000000000000007",
+        functions: &[
+            ("sendMessage(address,bytes,uint32)", None),
+            ("xDomainMessageSender()", Some("address")),
+        ],
+    },
+    Predeploy {
+        name: "GasPriceOracle",
+        address: "0x420000000000000000000000000000000000000f",
+        functions: &[
+            ("getL1Fee(bytes)", Some("uint256")),
+            ("getL1GasUsed(bytes)", Some("uint256")),
+            ("l1BaseFee()", Some("uint256")),
+            ("blobBaseFee()", Some("uint256")),
+            ("baseFee()", Some("uint256")),
+            ("gasPrice()", Some("uint256")),
+            ("decimals()", Some("uint256")),
+        ],
+    },
+    Predeploy {
+        name: "L2StandardBridge",
+        address: "0x4200000000000000000000000000000000000010",
+        functions: &[
+            ("withdraw(address,uint256,uint32,bytes)", None),
+            ("withdrawTo(address,address,uint256,uint32,bytes)", None),
+        ],
+    },
+    Predeploy {
+        name: "L1Block",
+        address: "0x4200000000000000000000000000000000000015",
+        functions: &[
+            ("number()", Some("uint64")),
+            ("timestamp()", Some("uint64")),
+            ("basefee()", Some("uint256")),
+            ("hash()", Some("bytes32")),
+            ("sequenceNumber()", Some("uint64")),
+            ("batcherHash()", Some("bytes32")),
+            ("l1FeeOverhead()", Some("uint256")),
+            ("l1FeeScalar()", Some("uint256")),
+            ("blobBaseFee()", Some("uint256")),
+            ("baseFeeScalar()", Some("uint32")),
+            ("blobBaseFeeScalar()", Some("uint32")),
+        ],
+    },
+    Predeploy {
+        name: "L2ToL1MessagePasser",
+        address: "0x4200000000000000000000000000000000000016",
+        functions: &[
+            ("initiateWithdrawal(address,uint256,bytes)", None),
+            ("messageNonce()", Some("uint256")),
+        ],
+    },
+];
+
+const ZKSYNC_PREDEPLOYS: &[Predeploy] = &[
+    Predeploy {
+        name: "NonceHolder",
+        address: "0x8003",
+        functions: &[
+            ("getMinNonce(address)", Some("uint256")),
+            ("getRawNonce(address)", Some("uint256")),
+            ("incrementMinNonceIfEquals(uint256)", None),
+        ],
+    },
+    Predeploy {
+        name: "ContractDeployer",
+        address: "0x8006",
+        functions: &[
+            ("create(bytes32,bytes32,bytes)", Some("address")),
+            ("create2(bytes32,bytes32,bytes)", Some("address")),
+            ("getNewAddressCreate(address,uint256)", Some("address")),
+            ("getNewAddressCreate2(address,bytes32,bytes32,bytes)", Some("address")),
+        ],
+    },
+    Predeploy {
+        name: "L1Messenger",
+        address: "0x8008",
+        functions: &[("sendToL1(bytes)", Some("bytes32"))],
+    },
+    Predeploy {
+        name: "L2BaseToken",
+        address: "0x800a",
+        functions: &[
+            ("balanceOf(uint256)", Some("uint256")),
+            ("totalSupply()", Some("uint256")),
+            ("withdraw(address)", None),
+        ],
+    },
+    Predeploy {
+        name: "SystemContext",
+        address: "0x800b",
+        functions: &[
+            ("chainId()", Some("uint256")),
+            ("origin()", Some("address")),
+            ("gasPrice()", Some("uint256")),
+            ("baseFee()", Some("uint256")),
+            ("getBlockNumber()", Some("uint128")),
+            ("getBlockTimestamp()", Some("uint128")),
+            ("getBlockHashEVM(uint256)", Some("bytes32")),
+            ("txNumberInBlock()", Some("uint16")),
+        ],
+    },
+];
+
+/// The precompiles and predeploys specific to the given chain. Chains which share a stack, such as
+/// Optimism and Base, share their predeploys.
+fn chain_predeploys(chain: &str) -> &'static [Predeploy] {
+    match chain.to_lowercase().as_str() {
+        "arbitrum" | "arbitrum-nova" => ARBITRUM_PREDEPLOYS,
+        "optimism" | "base" => OPTIMISM_PREDEPLOYS,
+        "zksync" => ZKSYNC_PREDEPLOYS,
+        _ => &[],
+    }
+}
+
+/// Whether the line calls a chain's precompile or predeploy. These may have side effects, so
+/// the call is kept even if its result is unused.
+pub fn calls_predeploy(line: &str) -> bool {
+    [ARBITRUM_PREDEPLOYS, OPTIMISM_PREDEPLOYS, ZKSYNC_PREDEPLOYS]
+        .iter()
+        .flat_map(|predeploys| predeploys.iter())
+        .any(|predeploy| line.contains(&format!("= {}.", predeploy.name)))
+}
+
+/// Detects calls to the precompiles and predeploys of the given chain, such as Arbitrum's `ArbSys`
+/// or Optimism's `L1Block`, and formats them as calls to the named contract, e.g.
+/// `ArbSys.arbBlockNumber()`. Returns `None` if the call isn't to a known function of one.
+pub fn decode_predeploy(
+    chain: &str,
+    function: &Function,
+    memory: &Memory,
+    address: U256,
+    calldata_offset: U256,
+    calldata_size: U256,
+    return_data_offset: &WrappedOpcode,
+) -> Option<String> {
+    let predeploy = chain_predeploys(chain).iter().find(|predeploy| {
+        U256::from_str_radix(predeploy.address.trim_start_matches("0x"), 16).ok() == Some(address)
+    })?;
+
+    // the selector is read from memory directly, since it's rarely stored at a word boundary
+    let selector = memory.read(calldata_offset.try_into().unwrap_or(usize::MAX), 4);
+    let (signature, returns) = predeploy
+        .functions
+        .iter()
+        .find(|(signature, _)| keccak256(signature).get(..4) == Some(selector.as_slice()))?;
+
+    let call = format!(
+        "{}.{}({})",
+        predeploy.name,
+        signature.split('(').next().unwrap_or(signature),
+        function
+            .get_memory_range(
+                calldata_offset.saturating_add(U256::from(4)),
+                calldata_size.saturating_sub(U256::from(4))
+            )
+            .iter()
+            .map(|x| x.operations.solidify())
+            .collect::<Vec<String>>()
+            .join(", ")
+    );
+    Some(match returns {
+        Some(returns) => format!("{returns} memory[{}] = {call};", return_data_offset.solidify()),
+        None => format!("{call};"),
+    })
+}
@@ -0,0 +1,91 @@
+use futures::{stream, Stream};
+use heimdall_common::utils::io::logging::TraceFactory;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+
+use crate::{
+    decompile::{
+        decompile_with,
+        out::abi::{build_abi, ABIStructure},
+        summarize::Suggestion,
+        util::Function,
+        DecompilerArgs,
+    },
+    error::Error,
+    usage::util::FunctionUsage,
+};
+
+/// A single function's decompilation result, available as soon as the function is analyzed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DecompiledFunction {
+    pub selector: String,
+    pub entry_point: u128,
+    /// The resolved signature, if the selector was resolved
+    pub signature: Option<String>,
+    /// The ABI entries for the function, and the custom errors and events it uses
+    pub abi: Vec<ABIStructure>,
+    pub notices: Vec<String>,
+    /// The selector of the function whose body this one shares, if any
+    #[serde(default)]
+    pub alias_of: Option<String>,
+    /// The name and summary proposed by the summarizer, if any
+    #[serde(default)]
+    pub suggestion: Option<Suggestion>,
+    /// How the function is called in practice, if the transaction history was read
+    #[serde(default)]
+    pub usage: Option<FunctionUsage>,
+}
+
+impl DecompiledFunction {
+    fn from_function(args: &DecompilerArgs, function: &Function) -> Result<Self, Error> {
+        // the function's ABI is built outside of the decompilation's trace
+        let abi = build_abi(args, vec![function.clone()], &mut TraceFactory::default(), 0)
+            .map_err(|e| Error::GenericError(format!("failed to build ABI: {e}")))?;
+
+        Ok(DecompiledFunction {
+            selector: format!("0x{}", function.selector),
+            entry_point: function.entry_point,
+            signature: function.resolved_function.as_ref().map(|f| f.signature.clone()),
+            abi,
+            notices: function.notices.clone(),
+            alias_of: function.alias_of.as_ref().map(|selector| format!("0x{selector}")),
+            suggestion: function.suggestion.clone(),
+            usage: function.usage.clone(),
+        })
+    }
+}
+
+/// Decompile the target, yielding each function as soon as it's analyzed rather than once the
+/// whole contract is done. If decompilation fails, the error is the last item of the stream.
+///
+/// Decompilation runs on its own thread, and never prompts: where there are multiple possible
+/// signatures for a selector, the best match is used.
+pub fn decompile_stream(
+    mut args: DecompilerArgs,
+) -> impl Stream<Item = Result<DecompiledFunction, Error>> {
+    args.default = true;
+
+    let (tx, rx) = unbounded_channel();
+    std::thread::spawn(move || run(args, tx));
+
+    stream::unfold(rx, |mut rx| async move { rx.recv().await.map(|item| (item, rx)) })
+}
+
+fn run(args: DecompilerArgs, tx: UnboundedSender<Result<DecompiledFunction, Error>>) {
+    let runtime = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            let _ = tx.send(Err(Error::GenericError(format!("failed to start runtime: {e}"))));
+            return
+        }
+    };
+
+    let function_args = args.clone();
+    let result = runtime.block_on(decompile_with(args, None, |function| {
+        let _ = tx.send(DecompiledFunction::from_function(&function_args, function));
+    }));
+
+    if let Err(e) = result {
+        let _ = tx.send(Err(Error::GenericError(e.to_string())));
+    }
+}
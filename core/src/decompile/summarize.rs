@@ -0,0 +1,181 @@
+use std::{
+    io::{Read, Write},
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{decompile::util::Function, error::Error};
+
+/// A name and summary for a function, proposed by the user's summarizer rather than recovered by
+/// heimdall, so it's always shown alongside the command which proposed it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Suggestion {
+    pub name: Option<String>,
+    pub summary: Option<String>,
+    /// The summarizer command which proposed the suggestion
+    #[serde(default)]
+    pub source: String,
+}
+
+impl Suggestion {
+    /// Where the suggestion came from, e.g. ``(proposed by `./label.sh`, unverified)``.
+    pub fn provenance(&self) -> String {
+        format!("(proposed by `{}`, unverified)", self.source)
+    }
+}
+
+/// What the summarizer is given for each function, as JSON on its stdin.
+#[derive(Debug, Serialize)]
+struct SummaryRequest<'a> {
+    selector: String,
+    signature: Option<&'a str>,
+    /// The function's body, before postprocessing
+    pseudocode: &'a [String],
+    notices: &'a [String],
+}
+
+/// Run the summarizer `command` for the function, which proposes a name and summary for it. The
+/// command is run through the shell with the function as JSON on its stdin, and should print a
+/// JSON object with optional `name` and `summary` fields. heimdall never reaches the network
+/// itself here, so the summarizer may be a local model, a script, or a client for a hosted one.
+///
+/// Returns `None` if the summarizer proposed nothing.
+pub fn summarize(
+    command: &str,
+    function: &Function,
+    timeout: Duration,
+) -> Result<Option<Suggestion>, Error> {
+    let request = serde_json::to_vec(&SummaryRequest {
+        selector: format!("0x{}", function.selector),
+        signature: function.resolved_function.as_ref().map(|f| f.signature.as_str()),
+        pseudocode: &function.logic,
+        notices: &function.notices,
+    })?;
+
+    let (shell, flag) = if cfg!(windows) { ("cmd", "/C") } else { ("sh", "-c") };
+    let mut child = Command::new(shell)
+        .args([flag, command])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| Error::GenericError(format!("failed to run summarizer: {e}")))?;
+
+    // stdin and stdout are handled on their own threads, so a summarizer which doesn't read its
+    // input, or writes a large output, can't block past the timeout. the summarizer may stop
+    // reading early, so a failed write is only reported through its output
+    let mut stdin = child.stdin.take();
+    thread::spawn(move || {
+        if let Some(stdin) = stdin.as_mut() {
+            let _ = stdin.write_all(&request);
+        }
+    });
+    let mut stdout = child.stdout.take();
+    let reader = thread::spawn(move || {
+        let mut output = Vec::new();
+        if let Some(stdout) = stdout.as_mut() {
+            let _ = stdout.read_to_end(&mut output);
+        }
+        output
+    });
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() < deadline => thread::sleep(Duration::from_millis(10)),
+            Ok(None) => {
+                let _ = child.kill();
+                return Err(Error::GenericError(format!(
+                    "summarizer timed out after {}ms",
+                    timeout.as_millis()
+                )))
+            }
+            Err(e) => return Err(Error::GenericError(format!("failed to run summarizer: {e}"))),
+        }
+    };
+    if !status.success() {
+        return Err(Error::GenericError(format!("summarizer exited with {status}")))
+    }
+
+    let output = reader.join().unwrap_or_default();
+    let suggestion: Suggestion = serde_json::from_slice(&output)?;
+
+    // suggestions are written into comments, so they're kept to a single line
+    let single_line = |text: Option<String>| {
+        text.map(|text| text.split_whitespace().collect::<Vec<&str>>().join(" "))
+            .filter(|text| !text.is_empty())
+    };
+    let suggestion = Suggestion {
+        name: single_line(suggestion.name),
+        summary: single_line(suggestion.summary),
+        source: command.to_string(),
+    };
+
+    Ok(match suggestion.name.is_some() || suggestion.summary.is_some() {
+        true => Some(suggestion),
+        false => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::decompile::summarize::*;
+
+    fn function() -> Function {
+        Function {
+            selector: String::from("aaaaaaaa"),
+            entry_point: 0,
+            arguments: HashMap::new(),
+            storage: HashMap::new(),
+            memory: HashMap::new(),
+            returns: None,
+            logic: vec![String::from("stor_a = 0x01;")],
+            logic_pcs: Vec::new(),
+            events: HashMap::new(),
+            errors: HashMap::new(),
+            error_arguments: HashMap::new(),
+            resolved_function: None,
+            indent_depth: 0,
+            notices: Vec::new(),
+            pure: false,
+            view: false,
+            payable: false,
+            alias_of: None,
+            recursive_entry_points: Vec::new(),
+            gas_estimate: None,
+            suggestion: None,
+            usage: None,
+            create2_sites: Vec::new(),
+            time_bounds: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_summarizer_receives_function() {
+        let suggestion = summarize(
+            "grep -q aaaaaaaa && echo '{\"name\": \"setFlag\"}'",
+            &function(),
+            Duration::from_secs(10),
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(suggestion.name.as_deref(), Some("setFlag"));
+        assert_eq!(suggestion.summary, None);
+    }
+
+    #[test]
+    fn test_summarizer_failures() {
+        let timeout = Duration::from_secs(10);
+        assert!(summarize("echo 'not json'", &function(), timeout).is_err());
+        assert!(summarize("exit 1", &function(), timeout).is_err());
+        assert!(summarize("sleep 5", &function(), Duration::from_millis(100)).is_err());
+        assert_eq!(summarize("echo '{}'", &function(), timeout).unwrap(), None);
+    }
+}
@@ -1,11 +1,28 @@
 use std::collections::HashMap;
 
-use ethers::prelude::U256;
-use heimdall_common::ether::{
-    evm::core::{log::Log, opcodes::WrappedOpcode},
-    signatures::{ResolvedError, ResolvedFunction, ResolvedLog},
+use ethers::prelude::{H160, U256};
+use heimdall_common::{
+    ether::{
+        create::{create2_address, init_code_hash},
+        evm::{
+            core::{
+                log::Log,
+                memory::Memory,
+                opcodes::{WrappedInput, WrappedOpcode},
+                vm::Instruction,
+            },
+            ext::exec::VMTrace,
+        },
+        proxy::Proxy,
+        signatures::{ResolvedError, ResolvedFunction, ResolvedLog},
+    },
+    utils::strings::{encode_hex, encode_hex_reduced},
 };
 
+use crate::{error::Error, storage_layout::util::constant, usage::util::FunctionUsage};
+
+use super::summarize::Suggestion;
+
 /// The [`Function`] struct represents a decompiled function found in the contract's bytecode.
 /// Throughout the decompilation process, we will build up this function's structure, and eventually
 /// write it to a file.
@@ -39,6 +56,10 @@ pub struct Function {
     // holds function logic to be written to the output solidity file.
     pub logic: Vec<String>,
 
+    // the inclusive pc range each line of logic was emitted from, or None for lines which don't
+    // correspond to an instruction, such as closing brackets.
+    pub logic_pcs: Vec<Option<(u128, u128)>>,
+
     // holds all found events used to generate solidity error definitions
     // as well as ABI specifications.
     pub events: HashMap<U256, (Option<ResolvedLog>, Log)>,
@@ -47,6 +68,10 @@ pub struct Function {
     // as well as ABI specifications.
     pub errors: HashMap<U256, Option<ResolvedError>>,
 
+    // the number of words each custom error is reverted with after its selector, which are its
+    // arguments until its signature is resolved.
+    pub error_arguments: HashMap<U256, usize>,
+
     // stores the matched resolved function for this Functon
     pub resolved_function: Option<ResolvedFunction>,
 
@@ -61,6 +86,110 @@ pub struct Function {
     pub pure: bool,
     pub view: bool,
     pub payable: bool,
+
+    // the selector of the function whose body this function shares, if the dispatcher jumps to
+    // the same entry point for both.
+    pub alias_of: Option<String>,
+
+    // the entry points of internal functions which call themselves. their recursive calls are
+    // emitted as calls rather than unrolled.
+    pub recursive_entry_points: Vec<u128>,
+
+    // the gas used by the most expensive path through the function which doesn't revert, if
+    // symbolic execution found one.
+    pub gas_estimate: Option<u128>,
+
+    // the name and summary proposed by the user's summarizer, if any.
+    pub suggestion: Option<Suggestion>,
+
+    // how the function is called in practice, if the target's transaction history was read.
+    pub usage: Option<FunctionUsage>,
+
+    // the CREATE2s whose salt and init code don't depend on the call, so the addresses they
+    // deploy to can be predicted.
+    pub create2_sites: Vec<Create2Site>,
+
+    // the parameters and storage slots which guards compare against block.timestamp.
+    pub time_bounds: Vec<TimeBound>,
+}
+
+/// Whether a value compared against `block.timestamp` is a deadline, which the call must be made
+/// by, or an unlock time, which the call must wait for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeBoundKind {
+    Deadline,
+    UnlockTime,
+}
+
+/// A parameter or storage slot which a guard compares against `block.timestamp`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimeBound {
+    pub kind: TimeBoundKind,
+    /// The parameter or slot, e.g. `arg1` or `storage slot 0x02`
+    pub subject: String,
+}
+
+impl TimeBound {
+    /// What the bound means for the caller, e.g. `arg1 is a deadline, the call reverts once
+    /// block.timestamp passes it`.
+    pub fn describe(&self) -> String {
+        match self.kind {
+            TimeBoundKind::Deadline => format!(
+                "{} is a deadline, the call reverts once block.timestamp passes it",
+                self.subject
+            ),
+            TimeBoundKind::UnlockTime => format!(
+                "{} is an unlock time, the call reverts until block.timestamp reaches it",
+                self.subject
+            ),
+        }
+    }
+}
+
+/// The parameter or constant storage slot an expression reads, if any. Masks and arithmetic
+/// around it are looked through, so `storage[0x02] + 0x015180` is storage slot 0x02.
+fn time_bound_subject(input: &WrappedInput) -> Option<String> {
+    let WrappedInput::Opcode(operation) = input else { return None };
+    match (operation.opcode.code, operation.inputs.first().and_then(constant)) {
+        // CALLDATALOAD
+        (0x35, Some(offset)) if offset >= U256::from(4) && (offset - 4) % 32 == U256::zero() => {
+            Some(format!("arg{}", (offset - 4) / 32))
+        }
+        // SLOAD
+        (0x54, Some(slot)) => Some(format!("storage slot {}", encode_hex_reduced(slot))),
+        _ => operation.inputs.iter().find_map(time_bound_subject),
+    }
+}
+
+/// A `CREATE2` with a constant salt and init code.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Create2Site {
+    pub pc: u128,
+    pub salt: [u8; 32],
+    pub init_code_hash: [u8; 32],
+}
+
+impl Create2Site {
+    /// Where the site deploys to, e.g. `CREATE2 at pc 0x1f deploys to 0x... (salt 0x..., init code
+    /// hash 0x...)`. The address is only known if the deployer is.
+    pub fn describe(&self, deployer: Option<H160>) -> String {
+        let parameters = format!(
+            "salt 0x{}, init code hash 0x{}",
+            encode_hex(self.salt.to_vec()),
+            encode_hex(self.init_code_hash.to_vec())
+        );
+
+        match deployer {
+            Some(deployer) => format!(
+                "CREATE2 at pc {:#x} deploys to 0x{} ({parameters})",
+                self.pc,
+                encode_hex(
+                    create2_address(deployer, self.salt, self.init_code_hash).as_bytes().to_vec()
+                )
+            ),
+            None => format!("CREATE2 at pc {:#x} deploys with {parameters}", self.pc),
+        }
+    }
 }
 
 ///
@@ -98,4 +227,408 @@ impl Function {
 
         memory_slice
     }
+
+    /// Attribute lines added to the logic since this was last called to `pcs`, and forget lines
+    /// which were removed. Returns whether any lines were attributed.
+    pub fn map_logic_pcs(&mut self, pcs: Option<(u128, u128)>) -> bool {
+        let mapped = self.logic_pcs.len() < self.logic.len();
+        self.logic_pcs.resize(self.logic.len(), pcs);
+        mapped
+    }
+
+    /// The function's logic, with each line followed by a comment naming the pc range it was
+    /// emitted from, e.g. `return arg0; // pc 0x2a-0x31`.
+    pub fn annotated_logic(&self) -> Vec<String> {
+        self.logic
+            .iter()
+            .enumerate()
+            .map(|(i, line)| match self.logic_pcs.get(i).copied().flatten() {
+                Some(pcs) => format!("{line}{}", pc_comment(pcs)),
+                None => line.clone(),
+            })
+            .collect()
+    }
+
+    /// The arguments of a custom error reverted with `revert_data`, read from memory at `offset`.
+    /// Each word after the selector is an argument, given as the expression which was stored to
+    /// it, or as its value if it wasn't stored by this function.
+    pub fn error_arguments(&self, offset: U256, revert_data: &[u8]) -> Vec<String> {
+        revert_data
+            .get(4..)
+            .unwrap_or_default()
+            .chunks(32)
+            .enumerate()
+            .map(|(index, word)| {
+                match self.memory.get(&offset.saturating_add(U256::from(4 + 32 * index))) {
+                    Some(frame) => frame.operations.solidify(),
+                    None => encode_hex_reduced(U256::from_big_endian(word)),
+                }
+            })
+            .collect()
+    }
+
+    /// Record the `CREATE2` executed by `instruction` as a [`Create2Site`], if its salt and init
+    /// code are constant. `memory` is the memory the init code is read from.
+    pub fn record_create2(&mut self, instruction: &Instruction, memory: &Memory) {
+        // CREATE2 takes value, offset, size, and salt
+        let (Some(salt), [_, offset, size, _]) =
+            (instruction.input_operations.get(3), instruction.inputs.as_slice())
+        else {
+            return
+        };
+        let (Ok(offset), Ok(size)) = (usize::try_from(*offset), usize::try_from(*size)) else {
+            return
+        };
+
+        // bytes which were never written are zero, so only written bytes need to be checked
+        if !is_constant(salt) ||
+            !memory.origins(offset, size).iter().all(|(_, origin)| is_constant(origin))
+        {
+            return
+        }
+
+        let mut salt = [0u8; 32];
+        instruction.inputs[3].to_big_endian(&mut salt);
+        let site = Create2Site {
+            pc: instruction.instruction - 1,
+            salt,
+            init_code_hash: init_code_hash(&memory.read(offset, size)),
+        };
+        if !self.create2_sites.contains(&site) {
+            self.create2_sites.push(site);
+        }
+    }
+
+    /// Record the parameter or storage slot a JUMPI compares against `block.timestamp`, if the
+    /// JUMPI is a guard, i.e. every path down one of its branches reverts. Which side of the
+    /// comparison the timestamp must stay on for the call to succeed tells deadlines and unlock
+    /// times apart.
+    pub fn record_time_bound(&mut self, instruction: &Instruction, children: &[VMTrace]) {
+        let (Some(target), Some(condition)) =
+            (instruction.inputs.first(), instruction.input_operations.get(1))
+        else {
+            return
+        };
+        if children.len() != 2 {
+            return
+        }
+
+        // whether the guard passes when the jump is taken
+        let mut passing =
+            children.iter().filter(|child| estimate_gas(child).is_some()).map(|child| {
+                child.operations.first().is_some_and(|operation| {
+                    U256::from(operation.last_instruction.instruction - 1) == *target
+                })
+            });
+        let (Some(mut holds), None) = (passing.next(), passing.next()) else { return };
+
+        // ISZERO
+        let mut condition = condition;
+        while condition.opcode.code == 0x15 {
+            let Some(WrappedInput::Opcode(inner)) = condition.inputs.first() else { return };
+            condition = inner;
+            holds = !holds;
+        }
+
+        // LT, SLT, GT, SGT
+        let less_than = match condition.opcode.code {
+            0x10 | 0x12 => true,
+            0x11 | 0x13 => false,
+            _ => return,
+        };
+        let is_timestamp = |input: &WrappedInput| matches!(input, WrappedInput::Opcode(operation) if operation.opcode.code == 0x42);
+        let (subject, timestamp_first) = match condition.inputs.as_slice() {
+            [lhs, rhs] if is_timestamp(lhs) => (rhs, true),
+            [lhs, rhs] if is_timestamp(rhs) => (lhs, false),
+            _ => return,
+        };
+        let Some(subject) = time_bound_subject(subject) else { return };
+
+        // the timestamp must stay below a deadline, and rise above an unlock time
+        let bound = TimeBound {
+            kind: match (less_than == timestamp_first) == holds {
+                true => TimeBoundKind::Deadline,
+                false => TimeBoundKind::UnlockTime,
+            },
+            subject,
+        };
+        if !self.time_bounds.contains(&bound) {
+            self.time_bounds.push(bound);
+        }
+    }
+
+    /// The function's state mutability, as it's written in an ABI.
+    pub fn state_mutability(&self) -> &'static str {
+        match (self.payable, self.pure, self.view) {
+            (true, ..) => "payable",
+            (false, true, _) => "pure",
+            (false, false, true) => "view",
+            (false, false, false) => "nonpayable",
+        }
+    }
+
+    /// A one-line summary of what the function does, e.g. `Writes storage and emits 1 event.`
+    pub fn summary(&self) -> String {
+        let mut effects = Vec::new();
+        if self.payable {
+            effects.push(String::from("accepts ether"));
+        }
+        if !self.storage.is_empty() {
+            effects.push(String::from("writes storage"));
+        }
+        if self.logic.iter().any(|line| {
+            [").call{", ").delegatecall{", ").staticcall{"].iter().any(|call| line.contains(call))
+        }) {
+            effects.push(String::from("calls other contracts"));
+        }
+        if self.logic.iter().any(|line| line.contains("addr := create")) {
+            effects.push(String::from("deploys contracts"));
+        }
+        if self.logic.iter().any(|line| line.starts_with("selfdestruct(")) {
+            effects.push(String::from("self-destructs"));
+        }
+        if !self.events.is_empty() {
+            effects.push(format!(
+                "emits {} event{}",
+                self.events.len(),
+                if self.events.len() > 1 { "s" } else { "" }
+            ));
+        }
+        if effects.is_empty() && self.view && !self.pure {
+            effects.push(String::from("reads state"));
+        }
+        if let Some(returns) = &self.returns {
+            effects.push(format!("returns {returns}"));
+        }
+
+        if effects.is_empty() {
+            return String::from("Has no observable effects.")
+        }
+        let summary = join_list(&effects);
+        let mut chars = summary.chars();
+        match chars.next() {
+            Some(first) => format!("{}{}.", first.to_uppercase(), chars.as_str()),
+            None => summary,
+        }
+    }
+
+    /// The function's `require` statements, and what they check, e.g. `2 require checks, on the
+    /// caller and arguments`.
+    pub fn guards(&self) -> String {
+        let conditions =
+            self.logic.iter().filter(|line| line.starts_with("require(")).collect::<Vec<_>>();
+        if conditions.is_empty() {
+            return String::from("none")
+        }
+
+        let kinds = [
+            ("the caller", vec!["msg.sender", "tx.origin"]),
+            ("the value", vec!["msg.value"]),
+            ("arguments", vec!["arg", "msg.data"]),
+            ("state", vec!["storage[", "balance"]),
+            ("time", vec!["block.timestamp", "block.number"]),
+        ]
+        .into_iter()
+        .filter(|(_, patterns)| {
+            conditions
+                .iter()
+                .any(|condition| patterns.iter().any(|pattern| condition.contains(pattern)))
+        })
+        .map(|(kind, _)| kind.to_string())
+        .collect::<Vec<String>>();
+
+        format!(
+            "{} require check{}{}",
+            conditions.len(),
+            if conditions.len() > 1 { "s" } else { "" },
+            match kinds.is_empty() {
+                true => String::new(),
+                false => format!(", on {}", join_list(&kinds)),
+            }
+        )
+    }
+
+    /// How much of the function was recovered, and why.
+    pub fn confidence(&self) -> String {
+        let resolved = self.resolved_function.is_some();
+        let executed = self.gas_estimate.is_some();
+        format!(
+            "{} ({}, {})",
+            match (resolved, executed) {
+                (true, true) => "high",
+                (false, false) => "low",
+                _ => "medium",
+            },
+            match resolved {
+                true => "signature resolved",
+                false => "signature unresolved",
+            },
+            match executed {
+                true => "a successful path was executed",
+                false => "no successful path was executed",
+            }
+        )
+    }
+}
+
+/// Whether an operation's value is the same on every call, i.e. it's built only from constants,
+/// arithmetic, and the contract's own code.
+fn is_constant(operation: &WrappedOpcode) -> bool {
+    let name = operation.opcode.name;
+    (name.starts_with("PUSH") ||
+        [
+            "ADD",
+            "MUL",
+            "SUB",
+            "DIV",
+            "SDIV",
+            "MOD",
+            "SMOD",
+            "ADDMOD",
+            "MULMOD",
+            "EXP",
+            "SIGNEXTEND",
+            "LT",
+            "GT",
+            "SLT",
+            "SGT",
+            "EQ",
+            "ISZERO",
+            "AND",
+            "OR",
+            "XOR",
+            "NOT",
+            "BYTE",
+            "SHL",
+            "SHR",
+            "SAR",
+            "CODESIZE",
+            "CODECOPY",
+            "MSTORE",
+            "MSTORE8",
+            "MCOPY",
+        ]
+        .contains(&name)) &&
+        operation.inputs.iter().all(|input| match input {
+            WrappedInput::Raw(_) => true,
+            WrappedInput::Opcode(operation) => is_constant(operation),
+            WrappedInput::Variable(_) => false,
+        })
+}
+
+/// Join items into an English list, e.g. `a, b, and c`.
+fn join_list(items: &[String]) -> String {
+    match items.len() {
+        0 | 1 => items.join(""),
+        2 => items.join(" and "),
+        n => format!("{}, and {}", items[..n - 1].join(", "), items[n - 1]),
+    }
+}
+
+/// The end-of-line comment naming the inclusive pc range a line was emitted from.
+pub fn pc_comment(pcs: (u128, u128)) -> String {
+    match pcs {
+        (start, end) if start == end => format!(" // pc {start:#x}"),
+        (start, end) => format!(" // pc {start:#x}-{end:#x}"),
+    }
+}
+
+/// Split a line into its code and its pc comment, if it has one, so that postprocessing only
+/// rewrites the code.
+pub fn split_pc_comment(line: &str) -> (&str, &str) {
+    match line.find(" // pc 0x") {
+        Some(index) => line.split_at(index),
+        None => (line, ""),
+    }
+}
+
+/// Parse a program counter, either in hex with a leading 0x, or in decimal.
+pub fn parse_pc(pc: &str) -> Result<u128, Error> {
+    match pc.strip_prefix("0x") {
+        Some(hex) => u128::from_str_radix(hex, 16),
+        None => pc.parse(),
+    }
+    .map_err(|_| Error::GenericError(format!("invalid program counter '{pc}'")))
+}
+
+/// The gas used by the most expensive path through the trace which doesn't revert, including the
+/// intrinsic cost of the call. Returns `None` if every path reverts, or the trace is empty.
+pub fn estimate_gas(trace: &VMTrace) -> Option<u128> {
+    if !trace.children.is_empty() {
+        return trace.children.iter().filter_map(estimate_gas).max()
+    }
+
+    // REVERT, INVALID
+    match trace.operations.last() {
+        Some(operation) if ![0xfd, 0xfe].contains(&operation.last_instruction.opcode) => {
+            Some(trace.gas_used)
+        }
+        _ => None,
+    }
+}
+
+/// The name given to the internal function at `entry_point`, e.g. `internal_0x0046`.
+pub fn internal_function_name(entry_point: u128) -> String {
+    format!("internal_{entry_point:#06x}")
+}
+
+/// Whether an assembly block which accesses `size` bytes of memory at `offset` follows Solidity's
+/// memory model, and can be annotated as `memory-safe`. The block may use the scratch space at
+/// 0x00-0x3f, or memory from 0x80 onwards if the contract keeps a free memory pointer, but not the
+/// free memory pointer or the zero slot themselves.
+pub fn is_memory_safe(memory: &Memory, offset: U256, size: U256) -> bool {
+    if size.is_zero() {
+        return true
+    }
+    let Some(end) = offset.checked_add(size) else { return false };
+    if end <= U256::from(0x40) {
+        return true
+    }
+
+    let free_memory_pointer = U256::from_big_endian(&memory.read(0x40, 32));
+    free_memory_pointer >= U256::from(0x80) && offset >= U256::from(0x80)
+}
+
+/// Add a banner above the decompiled contract, noting that the target is an EOA which delegates
+/// to `delegate` under EIP-7702, and that the decompiled code is the delegate's.
+pub fn add_delegation_banner(source: &str, delegate: &str) -> String {
+    add_banner(
+        source,
+        &format!(
+            "/// @custom:delegation EOA delegates to {delegate} (EIP-7702), and runs the code below"
+        ),
+    )
+}
+
+/// Add a banner above the decompiled contract, noting the target's proxy pattern and
+/// implementation, and whether the decompiled code is the implementation's or the proxy's own.
+pub fn add_proxy_banner(source: &str, proxy: &Proxy, followed: bool) -> String {
+    add_banner(
+        source,
+        &format!(
+            "/// @custom:proxy {} for {}, the code below is the {}",
+            proxy.kind,
+            proxy.implementation,
+            match followed {
+                true => "implementation's",
+                false => "proxy's own",
+            }
+        ),
+    )
+}
+
+/// Insert `banner` on the line above the decompiled contract's declaration.
+fn add_banner(source: &str, banner: &str) -> String {
+    source
+        .split('\n')
+        .flat_map(|line| {
+            match line.starts_with("contract DecompiledContract") ||
+                line.starts_with("object \"DecompiledContract\"")
+            {
+                true => vec![banner.to_string(), line.to_string()],
+                false => vec![line.to_string()],
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
 }
@@ -0,0 +1,203 @@
+pub mod util;
+
+use std::collections::BTreeMap;
+
+use clap::{AppSettings, Parser};
+use derive_builder::Builder;
+use heimdall_common::{
+    ether::bytecode::{get_bytecode_from_target, split_sections},
+    utils::{
+        io::logging::{set_logger_env, Logger},
+        strings::decode_hex,
+    },
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{error::Error, fingerprint::util::fingerprint_functions};
+
+use self::util::{
+    align_blocks, block_changes, constant_changes, BlockChange, BlockChangeKind, ConstantChange,
+};
+
+#[derive(Debug, Clone, Parser, Builder)]
+#[clap(
+    about = "Compare two bytecodes' functions, basic blocks, and constants",
+    after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    global_setting = AppSettings::DeriveDisplayOrder,
+    override_usage = "heimdall diff <OLD> <NEW> [OPTIONS]"
+)]
+pub struct DiffArgs {
+    /// The bytecode to compare from, either a file, bytecode, contract address, or ENS name.
+    #[clap(required = true, value_name = "OLD")]
+    pub old_target: String,
+
+    /// The bytecode to compare to, either a file, bytecode, contract address, or ENS name.
+    #[clap(required = true, value_name = "NEW")]
+    pub new_target: String,
+
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// The RPC provider to use for fetching target bytecode.
+    #[clap(long = "rpc-url", short, default_value = "", hide_default_value = true)]
+    pub rpc_url: String,
+
+    /// The output directory to write the output to or 'print' to print to the console
+    #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
+    pub output: String,
+
+    /// The name for the output file
+    #[clap(long, short, default_value = "", hide_default_value = true)]
+    pub name: String,
+
+    /// The timeout for each function's symbolic execution in milliseconds.
+    #[clap(long, short, default_value = "10000", hide_default_value = true)]
+    pub timeout: u64,
+}
+
+impl DiffArgsBuilder {
+    pub fn new() -> Self {
+        Self {
+            old_target: Some(String::new()),
+            new_target: Some(String::new()),
+            verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
+            rpc_url: Some(String::new()),
+            output: Some(String::new()),
+            name: Some(String::new()),
+            timeout: Some(10000),
+        }
+    }
+}
+
+/// A function both bytecodes dispatch, whose code differs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FunctionChange {
+    pub selector: String,
+    /// How similar the function's opcodes are in each bytecode, from 0 to 1
+    pub similarity: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiffResult {
+    /// Selectors only the new bytecode dispatches
+    pub added_functions: Vec<String>,
+    /// Selectors only the old bytecode dispatches
+    pub removed_functions: Vec<String>,
+    /// Functions whose code changed, least similar first
+    pub changed_functions: Vec<FunctionChange>,
+    /// The number of basic blocks matched between the bytecodes
+    pub matched_blocks: usize,
+    /// Each run of basic blocks which was added, removed, or replaced
+    pub blocks: Vec<BlockChange>,
+    /// PUSH values which changed in matched blocks, other than jump targets
+    pub constants: Vec<ConstantChange>,
+    /// Whether the data following the code, such as immutables or a Vyper data section, changed
+    pub data_changed: bool,
+    /// Whether the compiler metadata changed
+    pub metadata_changed: bool,
+}
+
+impl DiffResult {
+    pub fn is_empty(&self) -> bool {
+        self.added_functions.is_empty() &&
+            self.removed_functions.is_empty() &&
+            self.changed_functions.is_empty() &&
+            self.blocks.is_empty() &&
+            self.constants.is_empty() &&
+            !self.data_changed
+    }
+}
+
+/// Compare two bytecodes, i.e. a proxy's implementation before and after an upgrade. Basic
+/// blocks are aligned by their opcodes, reporting runs of blocks which were added, removed, or
+/// replaced, and constants which changed in the blocks which match. Functions are compared by
+/// their fingerprints.
+pub async fn diff(args: DiffArgs) -> Result<DiffResult, Error> {
+    set_logger_env(&args.verbose);
+
+    // get a new logger
+    let (logger, _) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    let old_bytecode = get_bytecode_from_target(&args.old_target, &args.rpc_url)
+        .await
+        .map_err(|e| Error::GenericError(e.to_string()))?;
+    let new_bytecode = get_bytecode_from_target(&args.new_target, &args.rpc_url)
+        .await
+        .map_err(|e| Error::GenericError(e.to_string()))?;
+    let old = decode_hex(&old_bytecode.replacen("0x", "", 1))
+        .map_err(|e| Error::GenericError(format!("failed to decode bytecode: {e}")))?;
+    let new = decode_hex(&new_bytecode.replacen("0x", "", 1))
+        .map_err(|e| Error::GenericError(format!("failed to decode bytecode: {e}")))?;
+
+    // compare functions by their fingerprints, which ignore where their code is
+    let old_functions = fingerprint_functions(&old_bytecode, args.timeout)?
+        .into_iter()
+        .map(|function| (function.selector.clone(), function))
+        .collect::<BTreeMap<_, _>>();
+    let new_functions = fingerprint_functions(&new_bytecode, args.timeout)?
+        .into_iter()
+        .map(|function| (function.selector.clone(), function))
+        .collect::<BTreeMap<_, _>>();
+    let mut changed_functions = old_functions
+        .iter()
+        .filter_map(|(selector, old_function)| {
+            let new_function = new_functions.get(selector)?;
+            (old_function.opcodes != new_function.opcodes).then(|| FunctionChange {
+                selector: selector.clone(),
+                similarity: old_function.similarity(new_function),
+            })
+        })
+        .collect::<Vec<FunctionChange>>();
+    changed_functions.sort_by(|a, b| a.similarity.total_cmp(&b.similarity));
+
+    // only the code sections are aligned, since data and metadata aren't made of blocks
+    let old_sections = split_sections(&old);
+    let new_sections = split_sections(&new);
+    let (old_code, new_code) = (&old[..old_sections.code], &new[..new_sections.code]);
+    let alignment = align_blocks(old_code, new_code);
+    let blocks = block_changes(&alignment);
+    let constants = constant_changes(old_code, new_code, &alignment);
+
+    let result = DiffResult {
+        added_functions: new_functions
+            .keys()
+            .filter(|selector| !old_functions.contains_key(*selector))
+            .cloned()
+            .collect(),
+        removed_functions: old_functions
+            .keys()
+            .filter(|selector| !new_functions.contains_key(*selector))
+            .cloned()
+            .collect(),
+        changed_functions,
+        matched_blocks: alignment.pairs.len(),
+        blocks,
+        constants,
+        data_changed: old[old_sections.data_start()..old_sections.metadata_start()] !=
+            new[new_sections.data_start()..new_sections.metadata_start()],
+        metadata_changed: old[old_sections.metadata_start()..] !=
+            new[new_sections.metadata_start()..],
+    };
+
+    logger.info(&format!(
+        "matched {} of {} blocks. {} added, {} removed, and {} changed functions.",
+        result.matched_blocks,
+        alignment.old_blocks.len().max(alignment.new_blocks.len()),
+        result.added_functions.len(),
+        result.removed_functions.len(),
+        result.changed_functions.len()
+    ));
+    logger.debug(&format!(
+        "{} block runs changed, {} added, {} removed, and {} constants changed.",
+        result.blocks.iter().filter(|block| block.kind == BlockChangeKind::Changed).count(),
+        result.blocks.iter().filter(|block| block.kind == BlockChangeKind::Added).count(),
+        result.blocks.iter().filter(|block| block.kind == BlockChangeKind::Removed).count(),
+        result.constants.len()
+    ));
+
+    Ok(result)
+}
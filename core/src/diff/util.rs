@@ -0,0 +1,269 @@
+use std::{collections::HashSet, ops::Range};
+
+use heimdall_common::{ether::evm::core::opcodes::Opcode, utils::strings::encode_hex};
+use serde::{Deserialize, Serialize};
+
+use crate::{metrics::clones::normalize, minimize::util::basic_blocks};
+
+/// How a run of basic blocks differs between the two bytecodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BlockChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// A run of basic blocks which only one bytecode has, or which were replaced.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockChange {
+    pub kind: BlockChangeKind,
+    /// The bytes the blocks span in the old bytecode, if it has them
+    pub old: Option<Range<usize>>,
+    /// The bytes the blocks span in the new bytecode, if it has them
+    pub new: Option<Range<usize>>,
+}
+
+/// A PUSH whose value changed within a basic block which is otherwise the same.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConstantChange {
+    /// The offset of the PUSH in the old bytecode
+    pub old_offset: usize,
+    /// The offset of the PUSH in the new bytecode
+    pub new_offset: usize,
+    pub old: String,
+    pub new: String,
+}
+
+/// The longest common subsequence of `old` and `new`, as the indices of each matched pair.
+/// Computed with Hirschberg's algorithm, so memory is linear in the length of `new`.
+pub fn longest_common_subsequence<T: PartialEq>(old: &[T], new: &[T]) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+    hirschberg(old, new, 0, 0, &mut pairs);
+    pairs
+}
+
+/// The length of the longest common subsequence of `old` and each prefix of `new`.
+fn lcs_lengths<T: PartialEq>(old: &[T], new: &[T]) -> Vec<usize> {
+    let mut previous = vec![0; new.len() + 1];
+    let mut current = vec![0; new.len() + 1];
+    for item in old {
+        for j in 1..=new.len() {
+            current[j] = match *item == new[j - 1] {
+                true => previous[j - 1] + 1,
+                false => previous[j].max(current[j - 1]),
+            };
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous
+}
+
+fn hirschberg<T: PartialEq>(
+    old: &[T],
+    new: &[T],
+    old_offset: usize,
+    new_offset: usize,
+    pairs: &mut Vec<(usize, usize)>,
+) {
+    if old.is_empty() || new.is_empty() {
+        return
+    }
+    if old.len() == 1 {
+        if let Some(j) = new.iter().position(|item| *item == old[0]) {
+            pairs.push((old_offset, new_offset + j));
+        }
+        return
+    }
+
+    // split `new` where the halves of `old` share the most with it
+    let middle = old.len() / 2;
+    let forward = lcs_lengths(&old[..middle], new);
+    let backward = lcs_lengths(
+        &old[middle..].iter().rev().collect::<Vec<&T>>(),
+        &new.iter().rev().collect::<Vec<&T>>(),
+    );
+    let split = (0..=new.len())
+        .max_by_key(|j| (forward[*j] + backward[new.len() - j], std::cmp::Reverse(*j)))
+        .unwrap_or(0);
+
+    hirschberg(&old[..middle], &new[..split], old_offset, new_offset, pairs);
+    hirschberg(&old[middle..], &new[split..], old_offset + middle, new_offset + split, pairs);
+}
+
+/// The basic blocks of two code sections, and which of them were matched.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockAlignment {
+    pub old_blocks: Vec<Range<usize>>,
+    pub new_blocks: Vec<Range<usize>>,
+    /// The indices of each pair of matched blocks, in order
+    pub pairs: Vec<(usize, usize)>,
+}
+
+/// Align the basic blocks of two code sections by their opcodes, ignoring PUSH data, so blocks
+/// which only moved or had their constants changed are still matched.
+pub fn align_blocks(old: &[u8], new: &[u8]) -> BlockAlignment {
+    let old_blocks = basic_blocks(old);
+    let new_blocks = basic_blocks(new);
+    let old_keys = old_blocks.iter().map(|block| normalize(old, block)).collect::<Vec<Vec<u8>>>();
+    let new_keys = new_blocks.iter().map(|block| normalize(new, block)).collect::<Vec<Vec<u8>>>();
+
+    // most upgrades only touch a few blocks, so the common prefix and suffix are matched first
+    let prefix = old_keys.iter().zip(&new_keys).take_while(|(a, b)| a == b).count();
+    let suffix = old_keys[prefix..]
+        .iter()
+        .rev()
+        .zip(new_keys[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut pairs = (0..prefix).map(|i| (i, i)).collect::<Vec<(usize, usize)>>();
+    pairs.extend(
+        longest_common_subsequence(
+            &old_keys[prefix..old_keys.len() - suffix],
+            &new_keys[prefix..new_keys.len() - suffix],
+        )
+        .into_iter()
+        .map(|(i, j)| (prefix + i, prefix + j)),
+    );
+    pairs.extend((0..suffix).rev().map(|i| (old_keys.len() - 1 - i, new_keys.len() - 1 - i)));
+
+    BlockAlignment { old_blocks, new_blocks, pairs }
+}
+
+/// Each run of blocks between matched blocks. A run which both bytecodes have blocks in was
+/// changed, otherwise it was added or removed.
+pub fn block_changes(alignment: &BlockAlignment) -> Vec<BlockChange> {
+    let BlockAlignment { old_blocks, new_blocks, pairs } = alignment;
+    let span = |blocks: &[Range<usize>], run: Range<usize>| {
+        (!run.is_empty()).then(|| blocks[run.start].start..blocks[run.end - 1].end)
+    };
+
+    let mut changes = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    for (next_i, next_j) in pairs.iter().copied().chain([(old_blocks.len(), new_blocks.len())]) {
+        let old = span(old_blocks, i..next_i);
+        let new = span(new_blocks, j..next_j);
+        let kind = match (&old, &new) {
+            (Some(_), Some(_)) => BlockChangeKind::Changed,
+            (Some(_), None) => BlockChangeKind::Removed,
+            (None, Some(_)) => BlockChangeKind::Added,
+            (None, None) => {
+                (i, j) = (next_i + 1, next_j + 1);
+                continue
+            }
+        };
+        changes.push(BlockChange { kind, old, new });
+        (i, j) = (next_i + 1, next_j + 1);
+    }
+
+    changes
+}
+
+/// The offset of each `JUMPDEST` in the code.
+fn jump_destinations(code: &[u8]) -> HashSet<usize> {
+    instructions(code, &(0..code.len()))
+        .filter(|(_, opcode, _)| *opcode == 0x5b)
+        .map(|(offset, ..)| offset)
+        .collect()
+}
+
+/// Each instruction in a block, as its offset, opcode, and PUSH data.
+fn instructions<'a>(
+    code: &'a [u8],
+    block: &Range<usize>,
+) -> impl Iterator<Item = (usize, u8, &'a [u8])> + 'a {
+    let end = block.end.min(code.len());
+    let mut program_counter = block.start;
+    std::iter::from_fn(move || {
+        if program_counter >= end {
+            return None
+        }
+
+        let offset = program_counter;
+        let operation = Opcode::new(code[offset]);
+        let byte_count = operation
+            .name
+            .strip_prefix("PUSH")
+            .and_then(|count| count.parse::<usize>().ok())
+            .unwrap_or(0);
+        program_counter = (offset + 1 + byte_count).min(end);
+        Some((offset, code[offset], &code[offset + 1..program_counter]))
+    })
+}
+
+/// The PUSH values which changed between each pair of matched blocks. Jump targets are
+/// skipped, since they change whenever code before them grows or shrinks.
+pub fn constant_changes(old: &[u8], new: &[u8], alignment: &BlockAlignment) -> Vec<ConstantChange> {
+    let BlockAlignment { old_blocks, new_blocks, pairs } = alignment;
+    let old_destinations = jump_destinations(old);
+    let new_destinations = jump_destinations(new);
+    let is_destination = |destinations: &HashSet<usize>, data: &[u8]| {
+        data.len() <= 8 &&
+            destinations.contains(
+                &(data.iter().fold(0u64, |value, byte| value << 8 | *byte as u64) as usize),
+            )
+    };
+
+    pairs
+        .iter()
+        .flat_map(|(i, j)| {
+            instructions(old, &old_blocks[*i]).zip(instructions(new, &new_blocks[*j]))
+        })
+        .filter(|((_, _, old_data), (_, _, new_data))| {
+            old_data != new_data &&
+                !(is_destination(&old_destinations, old_data) &&
+                    is_destination(&new_destinations, new_data))
+        })
+        .map(|((old_offset, _, old_data), (new_offset, _, new_data))| ConstantChange {
+            old_offset,
+            new_offset,
+            old: format!("0x{}", encode_hex(old_data.to_vec())),
+            new: format!("0x{}", encode_hex(new_data.to_vec())),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diff::util::*;
+
+    #[test]
+    fn test_longest_common_subsequence() {
+        let old = "ABCBDAB".chars().collect::<Vec<char>>();
+        let new = "BDCABA".chars().collect::<Vec<char>>();
+
+        let pairs = longest_common_subsequence(&old, &new);
+        assert_eq!(pairs.len(), 4);
+        assert!(pairs.windows(2).all(|pair| pair[0].0 < pair[1].0 && pair[0].1 < pair[1].1));
+        assert!(pairs.iter().all(|(i, j)| old[*i] == new[*j]));
+    }
+
+    #[test]
+    fn test_diff_blocks_and_constants() {
+        // PUSH1 0x2a, PUSH1 0x05, JUMP | JUMPDEST STOP
+        let old = vec![0x60, 0x2a, 0x60, 0x05, 0x56, 0x5b, 0x00];
+        // PUSH1 0x2b, PUSH1 0x09, JUMP | JUMPDEST CALLER POP STOP | JUMPDEST STOP
+        let new = vec![0x60, 0x2b, 0x60, 0x09, 0x56, 0x5b, 0x33, 0x50, 0x00, 0x5b, 0x00];
+
+        let alignment = align_blocks(&old, &new);
+        assert_eq!(alignment.pairs, vec![(0, 0), (1, 2)]);
+
+        let changes = block_changes(&alignment);
+        assert_eq!(
+            changes,
+            vec![BlockChange { kind: BlockChangeKind::Added, old: None, new: Some(5..9) }]
+        );
+
+        // the jump target moved with the code, so only the first constant changed
+        assert_eq!(
+            constant_changes(&old, &new, &alignment),
+            vec![ConstantChange {
+                old_offset: 0,
+                new_offset: 0,
+                old: "0x2a".to_string(),
+                new: "0x2b".to_string(),
+            }]
+        );
+    }
+}
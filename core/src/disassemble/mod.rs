@@ -1,12 +1,24 @@
+pub mod output;
+
+use std::ops::Range;
+
 use clap::{AppSettings, Parser};
 use derive_builder::Builder;
 use heimdall_common::{
-    ether::{bytecode::get_bytecode_from_target, evm::core::opcodes::Opcode},
+    ether::{
+        bytecode::{classify_regions, get_bytecode_from_target, split_sections, RegionKind},
+        evm::core::opcodes::Opcode,
+    },
     utils::{
         io::logging::{set_logger_env, Logger},
         strings::{decode_hex, encode_hex},
     },
 };
+use serde::{Deserialize, Serialize};
+
+use crate::{asm::util::assemble, error::Error};
+
+use self::output::{build_annotated, build_asm};
 
 #[derive(Debug, Clone, Parser, Builder)]
 #[clap(about = "Disassemble EVM bytecode to Assembly",
@@ -30,6 +42,17 @@ pub struct DisassemblerArgs {
     #[clap(long = "decimal-counter", short = 'd')]
     pub decimal_counter: bool,
 
+    /// The format to generate, one of 'asm', 'json', or 'annotated'. JSON lists each
+    /// instruction's offsets and pushed bytes, and annotated labels each JUMPDEST and separates
+    /// basic blocks.
+    #[clap(long, short, default_value = "asm")]
+    pub format: String,
+
+    /// Check that assembling the disassembly reproduces the bytecode exactly, other than its
+    /// metadata, and fail if it doesn't.
+    #[clap(long)]
+    pub verify: bool,
+
     /// Name of the output file.
     #[clap(long, short, default_value = "", hide_default_value = true)]
     pub name: String,
@@ -46,17 +69,123 @@ impl DisassemblerArgsBuilder {
             verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
             rpc_url: Some(String::new()),
             decimal_counter: Some(false),
+            format: Some(String::from("asm")),
+            verify: Some(false),
             name: Some(String::new()),
             output: Some(String::new()),
         }
     }
 }
 
+/// A single disassembled instruction, or a region of bytes which can't be executed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Instruction {
+    /// The offset of the instruction's opcode
+    pub pc: usize,
+    /// The opcode's name, or the region's kind, i.e. `DATA`, `STRING`, `JUMPTABLE`, or `METADATA`
+    pub op: String,
+    /// The bytes a PUSH pushes, or a region's contents
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pushed: Option<String>,
+    /// The bytes the instruction spans, including any it pushes
+    pub offsets: Range<usize>,
+}
+
+impl Instruction {
+    /// Whether this is a region of bytes which can't be executed, rather than an instruction.
+    pub fn is_data(&self) -> bool {
+        matches!(self.op.as_str(), "DATA" | "STRING" | "JUMPTABLE" | "METADATA")
+    }
+}
+
+/// Split the given bytecode into instructions. Bytes which can't be executed, such as unreachable
+/// code, trailing data, and metadata, are returned as a single instruction per region, named by
+/// what the region was classified as. Undefined opcodes are named by their byte, i.e.
+/// `UNKNOWN_0c`.
+pub fn disassemble_instructions(bytecode: &[u8]) -> Vec<Instruction> {
+    let mut instructions = Vec::new();
+
+    for region in classify_regions(bytecode) {
+        match region.kind {
+            RegionKind::Code => {
+                let mut program_counter = region.offsets.start;
+                while program_counter < region.offsets.end {
+                    let operation = Opcode::new(bytecode[program_counter]);
+                    let byte_count = operation
+                        .name
+                        .strip_prefix("PUSH")
+                        .and_then(|byte_count| byte_count.parse::<usize>().ok())
+                        .unwrap_or(0);
+
+                    // pushed bytes are in the region which follows, and are never cut off
+                    let end = program_counter + 1 + byte_count;
+                    instructions.push(Instruction {
+                        pc: program_counter,
+                        op: match operation.name {
+                            "unknown" => format!("UNKNOWN_{:02x}", operation.code),
+                            name => name.to_string(),
+                        },
+                        pushed: operation
+                            .name
+                            .starts_with("PUSH")
+                            .then(|| encode_hex(bytecode[program_counter + 1..end].to_vec())),
+                        offsets: program_counter..end,
+                    });
+                    program_counter = end;
+                }
+            }
+            RegionKind::PushData => {}
+            kind => instructions.push(Instruction {
+                pc: region.offsets.start,
+                op: kind.name().to_string(),
+                pushed: Some(encode_hex(bytecode[region.offsets.clone()].to_vec())),
+                offsets: region.offsets,
+            }),
+        }
+    }
+
+    instructions
+}
+
+/// Check that assembling `assembly` reproduces `bytecode` exactly, other than its metadata, which
+/// only needs to be as long as it was.
+pub fn verify_disassembly(bytecode: &[u8], assembly: &str) -> Result<(), Error> {
+    let assembled = assemble(assembly, 0)?;
+
+    let metadata_start = split_sections(bytecode).metadata_start();
+    if let Some(offset) =
+        (0..metadata_start).find(|offset| assembled.get(*offset) != Some(&bytecode[*offset]))
+    {
+        return Err(Error::GenericError(format!(
+            "the disassembly doesn't reassemble to the original bytecode. they first differ at \
+             0x{offset:x}."
+        )))
+    }
+    if assembled.len() != bytecode.len() {
+        return Err(Error::GenericError(format!(
+            "the disassembly reassembles to {} bytes, rather than {}.",
+            assembled.len(),
+            bytecode.len()
+        )))
+    }
+
+    Ok(())
+}
+
 /// Disassemble the given target's bytecode to assembly.
 pub async fn disassemble(args: DisassemblerArgs) -> Result<String, Box<dyn std::error::Error>> {
     use std::time::Instant;
     let now = Instant::now();
 
+    // check the format before disassembling, so an invalid one fails fast
+    if !["asm", "json", "annotated"].contains(&args.format.as_str()) {
+        return Err(format!(
+            "invalid format '{}' . expected one of: asm, json, annotated.",
+            args.format
+        )
+        .into())
+    }
+
     set_logger_env(&args.verbose);
 
     // get a new logger
@@ -66,46 +195,98 @@ pub async fn disassemble(args: DisassemblerArgs) -> Result<String, Box<dyn std::
     });
 
     let contract_bytecode = get_bytecode_from_target(&args.target, &args.rpc_url).await?;
-
-    let mut program_counter = 0;
-    let mut output: String = String::new();
-
-    // Iterate over the bytecode, disassembling each instruction.
     let byte_array = decode_hex(&contract_bytecode.replacen("0x", "", 1))?;
-    while program_counter < byte_array.len() {
-        let operation = Opcode::new(byte_array[program_counter]);
-        let mut pushed_bytes: String = String::new();
-
-        if operation.name.contains("PUSH") {
-            let byte_count_to_push: u8 = operation.name.strip_prefix("PUSH").unwrap().parse()?;
-
-            pushed_bytes = match byte_array
-                .get(program_counter + 1..program_counter + 1 + byte_count_to_push as usize)
-            {
-                Some(bytes) => encode_hex(bytes.to_vec()),
-                None => break,
-            };
-            program_counter += byte_count_to_push as usize;
+    let sections = split_sections(&byte_array);
+    let instructions = disassemble_instructions(&byte_array);
+
+    let format_counter = |program_counter: usize| match args.decimal_counter {
+        true => program_counter.to_string(),
+        false => format!("{:06x}", program_counter),
+    };
+    let output = match args.format.as_str() {
+        "json" => serde_json::to_string_pretty(&instructions)?,
+        "annotated" => {
+            build_annotated(&byte_array[..sections.code], &instructions, &format_counter)
         }
+        _ => build_asm(&instructions, &format_counter),
+    };
 
-        output.push_str(
-            format!(
-                "{} {} {}\n",
-                if args.decimal_counter {
-                    program_counter.to_string()
-                } else {
-                    format!("{:06x}", program_counter)
-                },
-                operation.name,
-                pushed_bytes
-            )
-            .as_str(),
-        );
-        program_counter += 1;
+    // JSON can't be assembled, so the flat format of the same instructions is checked instead
+    if args.verify {
+        let assembly = match args.format.as_str() {
+            "json" => build_asm(&instructions, &format_counter),
+            _ => output.clone(),
+        };
+        verify_disassembly(&byte_array, &assembly)?;
+        logger.debug("verified that the disassembly reassembles to the original bytecode.");
     }
 
-    logger.info(&format!("disassembled {program_counter} bytes successfully."));
+    logger.debug(&format!(
+        "found {} bytes of code, {} bytes of data, and {} bytes of metadata.",
+        sections.code, sections.data, sections.metadata
+    ));
+    logger.debug(&format!(
+        "found {} embedded strings and {} jump tables.",
+        instructions.iter().filter(|instruction| instruction.op == "STRING").count(),
+        instructions.iter().filter(|instruction| instruction.op == "JUMPTABLE").count()
+    ));
+
+    logger.info(&format!(
+        "disassembled {} bytes successfully.",
+        instructions
+            .iter()
+            .take_while(|instruction| instruction.pc < sections.code)
+            .last()
+            .map_or(0, |instruction| instruction.offsets.end)
+    ));
     logger.debug(&format!("disassembly completed in {} ms.", now.elapsed().as_millis()));
 
     Ok(output)
 }
+#[cfg(test)]
+mod tests {
+    use crate::disassemble::{output::*, *};
+
+    #[test]
+    fn test_verify_unusual_layouts() {
+        // a PUSH32 which runs past the end of the code, an undefined opcode, and a lone PUSH
+        for bytecode in ["60016002017f0102", "600c0c00", "7f", ""] {
+            let bytecode = decode_hex(bytecode).unwrap();
+            let instructions = disassemble_instructions(&bytecode);
+
+            verify_disassembly(&bytecode, &build_asm(&instructions, &|pc| pc.to_string())).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_verify_random_bytecode() {
+        let mut seed = 0x5eed_u64;
+        let mut next = || {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 33) as u8
+        };
+
+        for _ in 0..1000 {
+            let length = next() as usize % 96;
+            let bytecode = (0..length).map(|_| next()).collect::<Vec<u8>>();
+            let instructions = disassemble_instructions(&bytecode);
+            let code = &bytecode[..split_sections(&bytecode).code];
+
+            for assembly in [
+                build_asm(&instructions, &|pc| format!("{pc:06x}")),
+                build_annotated(code, &instructions, &|pc| format!("{pc:06x}")),
+            ] {
+                verify_disassembly(&bytecode, &assembly).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_verify_mismatch() {
+        let bytecode = decode_hex("6001600201").unwrap();
+
+        assert!(verify_disassembly(&bytecode, "PUSH1 01\nPUSH1 03\nADD").is_err());
+        assert!(verify_disassembly(&bytecode, "PUSH1 01\nPUSH1 02").is_err());
+        assert!(verify_disassembly(&bytecode, "PUSH1 01\nPUSH1 02\nADD").is_ok());
+    }
+}
@@ -0,0 +1,159 @@
+use std::collections::HashSet;
+
+use heimdall_common::utils::strings::decode_hex;
+
+use crate::minimize::util::basic_blocks;
+
+use super::Instruction;
+
+/// Build flat assembly, with one instruction per line. Instructions are counted by their last
+/// byte, and regions which can't be executed by their first.
+pub fn build_asm(instructions: &[Instruction], format_counter: &dyn Fn(usize) -> String) -> String {
+    instructions
+        .iter()
+        .map(|instruction| {
+            let counter = match instruction.is_data() {
+                true => instruction.pc,
+                false => instruction.offsets.end - 1,
+            };
+            format!(
+                "{} {} {}\n",
+                format_counter(counter),
+                instruction.op,
+                instruction.pushed.as_deref().unwrap_or_default()
+            )
+        })
+        .collect()
+}
+
+/// Build annotated assembly, where each basic block is preceded by its bounds, each JUMPDEST by
+/// a label, and each PUSH of a jump target by the label it jumps to. Regions which can't be
+/// executed are preceded by their kind, with strings decoded and jump tables' entries labeled.
+pub fn build_annotated(
+    code: &[u8],
+    instructions: &[Instruction],
+    format_counter: &dyn Fn(usize) -> String,
+) -> String {
+    let label = |program_counter: usize| format!("label_{}", format_counter(program_counter));
+    let jumpdests = instructions
+        .iter()
+        .filter(|instruction| instruction.op == "JUMPDEST")
+        .map(|instruction| instruction.pc)
+        .collect::<HashSet<usize>>();
+    let starts = instructions
+        .iter()
+        .filter(|instruction| !instruction.is_data())
+        .map(|instruction| instruction.pc)
+        .collect::<HashSet<usize>>();
+    let mut blocks = basic_blocks(code)
+        .into_iter()
+        .filter(|block| starts.contains(&block.start))
+        .enumerate()
+        .peekable();
+
+    let mut lines = Vec::new();
+    for (i, instruction) in instructions.iter().enumerate() {
+        if let Some((index, block)) = blocks.next_if(|(_, block)| block.start == instruction.pc) {
+            if !lines.is_empty() {
+                lines.push(String::new());
+            }
+            lines.push(format!(
+                "; block {index}, {} - {}",
+                format_counter(block.start),
+                format_counter(block.end - 1)
+            ));
+        }
+        if instruction.is_data() {
+            lines.push(String::new());
+            lines.push(format!("; {}", instruction.op.to_lowercase()));
+        }
+        if jumpdests.contains(&instruction.pc) {
+            lines.push(format!("{}:", label(instruction.pc)));
+        }
+
+        let mut line = format!("{} {}", format_counter(instruction.pc), instruction.op);
+        if let Some(pushed) = &instruction.pushed {
+            line.push_str(&format!(" {pushed}"));
+        }
+
+        // a pushed jump target is only known to be one if the next instruction jumps
+        let target = instruction
+            .pushed
+            .as_ref()
+            .filter(|_| instruction.op.starts_with("PUSH"))
+            .and_then(|pushed| usize::from_str_radix(pushed, 16).ok())
+            .filter(|target| jumpdests.contains(target));
+        let jumps = instructions
+            .get(i + 1)
+            .is_some_and(|next| matches!(next.op.as_str(), "JUMP" | "JUMPI"));
+        if let (Some(target), true) = (target, jumps) {
+            line.push_str(&format!(" ; -> {}", label(target)));
+        }
+
+        let contents = instruction.pushed.as_deref().and_then(|pushed| decode_hex(pushed).ok());
+        match (instruction.op.as_str(), contents) {
+            ("STRING", Some(contents)) => {
+                line.push_str(&format!(" ; {:?}", String::from_utf8_lossy(&contents)))
+            }
+            ("JUMPTABLE", Some(contents)) => line.push_str(&format!(
+                " ; -> {}",
+                contents
+                    .chunks(2)
+                    .map(|entry| label(u16::from_be_bytes([entry[0], entry[1]]) as usize))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )),
+            _ => {}
+        }
+        lines.push(line);
+    }
+
+    lines.iter().map(|line| format!("{line}\n")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::disassemble::{output::*, *};
+
+    #[test]
+    fn test_build_annotated() {
+        // PUSH1 0x04 JUMP, an unreachable STOP, JUMPDEST PUSH1 0x00 STOP
+        let code = decode_hex("600456005b600000").unwrap();
+        let annotated =
+            build_annotated(&code, &disassemble_instructions(&code), &|pc| pc.to_string());
+
+        assert_eq!(
+            annotated,
+            "; block 0, 0 - 2\n0 PUSH1 04 ; -> label_4\n2 JUMP\n\n; data\n3 DATA 00\n\n\
+             ; block 1, 4 - 7\nlabel_4:\n4 JUMPDEST\n5 PUSH1 00\n7 STOP\n"
+        );
+    }
+
+    #[test]
+    fn test_build_annotated_ignores_non_jumps() {
+        // PUSH1 0x03 POP JUMPDEST
+        let code = decode_hex("6003505b").unwrap();
+        let annotated =
+            build_annotated(&code, &disassemble_instructions(&code), &|pc| pc.to_string());
+
+        assert!(!annotated.contains("->"));
+        assert!(annotated.contains("label_3:\n3 JUMPDEST\n"));
+    }
+
+    #[test]
+    fn test_build_annotated_regions() {
+        // JUMPDEST STOP, then a jump table of 0x0000 twice, then "too large" which is never
+        // executed
+        let mut code = decode_hex("5b0000000000").unwrap();
+        code.extend(b"too large");
+        let annotated =
+            build_annotated(&code, &disassemble_instructions(&code), &|pc| pc.to_string());
+
+        assert_eq!(
+            annotated,
+            "; block 0, 0 - 1\nlabel_0:\n0 JUMPDEST\n1 STOP\n\n; jumptable\n\
+             2 JUMPTABLE 00000000 ; -> label_0, label_0\n\n; string\n\
+             6 STRING 746f6f206c61726765 ; \"too large\"\n"
+        );
+    }
+}
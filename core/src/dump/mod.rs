@@ -8,10 +8,15 @@ use derive_builder::Builder;
 use ethers::types::H160;
 use heimdall_common::{
     resources::transpose::{get_contract_creation, get_transaction_list},
-    utils::io::logging::*,
+    utils::io::{
+        exit::{exit_with, ExitStatus},
+        logging::*,
+    },
 };
 use std::{collections::HashMap, env, str::FromStr, time::Instant};
 
+use crate::storage_layout::{storage_layout, util::StorageLayout, StorageLayoutArgsBuilder};
+
 use self::{
     constants::DUMP_STATE,
     menus::TUIView,
@@ -56,8 +61,8 @@ pub struct DumpArgs {
     #[clap(long, default_value = "0", hide_default_value = true)]
     pub from_block: u128,
 
-    /// The block number to stop dumping at.
-    #[clap(long, default_value = "9999999999", hide_default_value = true)]
+    /// The block number to stop dumping at. Storage is dumped as of this block.
+    #[clap(long, alias = "block", default_value = "9999999999", hide_default_value = true)]
     pub to_block: u128,
 
     /// Whether to skip opening the TUI.
@@ -108,7 +113,7 @@ pub async fn dump(args: DumpArgs) -> Result<Vec<DumpRow>, Box<dyn std::error::Er
             Ok(dir) => dir.into_os_string().into_string().unwrap(),
             Err(_) => {
                 logger.error("failed to get current directory.");
-                std::process::exit(1);
+                exit_with(ExitStatus::InternalError, "failed to get current directory.");
             }
         };
         output_dir.push_str("/output");
@@ -118,7 +123,7 @@ pub async fn dump(args: DumpArgs) -> Result<Vec<DumpRow>, Box<dyn std::error::Er
     if args.transpose_api_key.is_empty() {
         logger.error("you must provide a Transpose API key, which is used to fetch all normal and internal transactions for your target.");
         logger.info("you can get a free API key at https://app.transpose.io/?utm_medium=organic&utm_source=heimdall-rs");
-        std::process::exit(1);
+        exit_with(ExitStatus::InvalidArguments, "you must provide a Transpose API key, which is used to fetch all normal and internal transactions for your target.");
     }
 
     // get the contract creation tx
@@ -129,7 +134,10 @@ pub async fn dump(args: DumpArgs) -> Result<Vec<DumpRow>, Box<dyn std::error::Er
                 logger.error(
                 "failed to get contract creation transaction. Is the target a contract address?",
             );
-                std::process::exit(1);
+                exit_with(
+                ExitStatus::NetworkFailure,
+                "failed to get contract creation transaction. Is the target a contract address?",
+            );
             }
         };
 
@@ -146,7 +154,10 @@ pub async fn dump(args: DumpArgs) -> Result<Vec<DumpRow>, Box<dyn std::error::Er
         Ok(addr) => addr,
         Err(_) => {
             logger.error(&format!("failed to parse target '{}' .", &args.target));
-            std::process::exit(1);
+            exit_with(
+                ExitStatus::InvalidArguments,
+                &format!("failed to parse target '{}' .", &args.target),
+            );
         }
     };
 
@@ -173,6 +184,23 @@ pub async fn dump(args: DumpArgs) -> Result<Vec<DumpRow>, Box<dyn std::error::Er
         });
     }
 
+    // infer the storage layout, so the slots the contract declares can be labeled
+    let layout = match storage_layout(
+        StorageLayoutArgsBuilder::new()
+            .target(args.target.clone())
+            .verbose(args.verbose.clone())
+            .rpc_url(args.rpc_url.clone())
+            .build()?,
+    )
+    .await
+    {
+        Ok(layout) => layout,
+        Err(e) => {
+            logger.warn(&format!("failed to infer storage layout, slots won't be labeled: {e}"));
+            StorageLayout::default()
+        }
+    };
+
     // update state
     let mut state = DUMP_STATE.lock().unwrap();
     *state = DumpState {
@@ -181,6 +209,7 @@ pub async fn dump(args: DumpArgs) -> Result<Vec<DumpRow>, Box<dyn std::error::Er
         scroll_index: 0,
         selection_size: 1,
         storage: HashMap::new(),
+        layout,
         view: TUIView::Main,
         start_time: Instant::now(),
         input_buffer: String::new(),
@@ -209,7 +238,7 @@ pub async fn dump(args: DumpArgs) -> Result<Vec<DumpRow>, Box<dyn std::error::Er
             Err(e) => {
                 logger.error("failed to join indexer thread.");
                 logger.error(&format!("{e:?}"));
-                std::process::exit(1);
+                exit_with(ExitStatus::InternalError, "failed to join indexer thread.");
             }
         }
     } else {
@@ -219,7 +248,7 @@ pub async fn dump(args: DumpArgs) -> Result<Vec<DumpRow>, Box<dyn std::error::Er
             Err(e) => {
                 logger.error("failed to join TUI thread.");
                 logger.error(&format!("{e:?}"));
-                std::process::exit(1);
+                exit_with(ExitStatus::InternalError, "failed to join TUI thread.");
             }
         }
     }
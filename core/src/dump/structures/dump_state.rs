@@ -2,7 +2,10 @@ use std::{collections::HashMap, time::Instant};
 
 use ethers::types::H256;
 
-use crate::dump::{menus::TUIView, DumpArgs};
+use crate::{
+    dump::{menus::TUIView, DumpArgs},
+    storage_layout::util::StorageLayout,
+};
 
 use super::{storage_slot::StorageSlot, transaction::Transaction};
 
@@ -16,6 +19,8 @@ pub struct DumpState {
     pub selection_size: usize,
     pub transactions: Vec<Transaction>,
     pub storage: HashMap<H256, StorageSlot>,
+    /// The target's inferred storage layout, which labels the slots it declares
+    pub layout: StorageLayout,
     pub view: TUIView,
     pub start_time: Instant,
     pub input_buffer: String,
@@ -42,6 +47,7 @@ impl DumpState {
             selection_size: 1,
             transactions: Vec::new(),
             storage: HashMap::new(),
+            layout: StorageLayout::default(),
             view: TUIView::Main,
             start_time: Instant::now(),
             input_buffer: String::new(),
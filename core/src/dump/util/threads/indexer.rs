@@ -3,7 +3,8 @@ use std::time::Duration;
 use ethers::types::{Diff, H160};
 use heimdall_common::{
     ether::rpc::get_storage_diff,
-    utils::{io::logging::Logger, threading::task_pool},
+    resources::labels::get_slot_label,
+    utils::{io::logging::Logger, strings::encode_hex, threading::task_pool},
 };
 use indicatif::ProgressBar;
 
@@ -96,13 +97,20 @@ pub async fn handle(addr_hash: H160) {
                             slot.modifiers.push((block_number, tx.hash.clone().to_owned()));
                         }
                         None => {
+                            // label the slot from the label store, or the inferred layout
+                            let alias = get_slot_label(&format!(
+                                "0x{}",
+                                encode_hex(slot.to_fixed_bytes().into())
+                            ))
+                            .or_else(|| state.layout.slot_label(slot));
+
                             // insert into state
                             state.storage.insert(
                                 *slot,
                                 StorageSlot {
                                     value: *value,
                                     modifiers: vec![(block_number, tx.hash.clone().to_owned())],
-                                    alias: None,
+                                    alias,
                                     decode_as_type_index: 0,
                                 },
                             );
@@ -0,0 +1,373 @@
+pub mod output;
+
+use std::{collections::BTreeMap, path::Path};
+
+use clap::{AppSettings, Parser};
+use derive_builder::Builder;
+use ethers::types::U256;
+use heimdall_common::{
+    ether::artifact::{find_artifacts, Artifact, StorageVariable},
+    utils::io::{
+        crash::enter_pass,
+        logging::{set_logger_env, Logger},
+    },
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    decompile::{
+        decompile_with,
+        evaluate::{ratio, Evaluation},
+        DecompilerArgsBuilder,
+    },
+    error::Error,
+    layout::{
+        layout,
+        util::{SlotKind, SlotUsage},
+        LayoutArgsBuilder,
+    },
+};
+
+#[derive(Debug, Clone, Parser, Builder)]
+#[clap(
+    about = "Evaluate the decompiler against compiled contracts, using their artifacts as ground truth",
+    after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    global_setting = AppSettings::DeriveDisplayOrder,
+    override_usage = "heimdall evaluate <TARGET> [OPTIONS]"
+)]
+pub struct EvaluateArgs {
+    /// A foundry, hardhat, or solc artifact, or a directory of them such as foundry's `out` or
+    /// hardhat's `artifacts`. solc's standard JSON and `--combined-json` outputs are also
+    /// accepted.
+    #[clap(required = true)]
+    pub target: String,
+
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// The timeout for each function's symbolic execution in milliseconds.
+    #[clap(long, short, default_value = "10000", hide_default_value = true)]
+    pub timeout: u64,
+
+    /// The format to generate, one of 'json' or 'markdown'.
+    #[clap(long, short, default_value = "markdown")]
+    pub format: String,
+
+    /// The output directory to write the output to or 'print' to print to the console
+    #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
+    pub output: String,
+
+    /// The name for the output file
+    #[clap(long, short, default_value = "", hide_default_value = true)]
+    pub name: String,
+}
+
+impl EvaluateArgsBuilder {
+    pub fn new() -> Self {
+        Self {
+            target: Some(String::new()),
+            verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
+            timeout: Some(10000),
+            format: Some(String::from("markdown")),
+            output: Some(String::new()),
+            name: Some(String::new()),
+        }
+    }
+}
+
+/// The recovered storage layout, compared with the artifact's.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct StorageEvaluation {
+    /// The number of slots in the artifact's layout
+    pub expected: usize,
+    /// The number of those slots which were recovered
+    pub found: usize,
+    /// The number of recovered slots whose kind, i.e. value, mapping, or array, is correct
+    pub kinds_correct: usize,
+    /// The variables in slots which weren't recovered, which may simply be unused
+    pub missing: Vec<String>,
+    /// Recovered slots outside of the artifact's layout, e.g. EIP-1967 slots
+    pub unexpected: Vec<U256>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContractEvaluation {
+    /// The contract's name, or its artifact's path if the artifact doesn't name it
+    pub name: String,
+    pub path: String,
+    pub functions: Evaluation,
+    /// The storage evaluation, if the artifact includes a storage layout
+    pub storage: Option<StorageEvaluation>,
+}
+
+/// The decompiler's accuracy across every contract, for judging whether a change improves it.
+/// Each metric is pooled over every function or slot, rather than averaged over contracts.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Metrics {
+    pub contracts: usize,
+    /// The number of functions in the artifacts' ABIs
+    pub functions: usize,
+    pub selector_recall: f64,
+    pub argument_accuracy: f64,
+    pub type_accuracy: f64,
+    pub mutability_accuracy: f64,
+    /// The fraction of slots recovered, if any artifact includes a storage layout
+    pub storage_recall: Option<f64>,
+    /// The fraction of recovered slots with the correct kind, if any artifact includes a storage
+    /// layout
+    pub storage_kind_accuracy: Option<f64>,
+}
+
+impl Metrics {
+    pub fn new(contracts: &[ContractEvaluation]) -> Metrics {
+        let functions = Evaluation {
+            functions: contracts
+                .iter()
+                .flat_map(|contract| contract.functions.functions.clone())
+                .collect(),
+            unexpected: Vec::new(),
+        };
+        let storage = contracts.iter().filter_map(|contract| contract.storage.as_ref()).fold(
+            None,
+            |total: Option<(usize, usize, usize)>, storage| {
+                let (expected, found, kinds_correct) = total.unwrap_or_default();
+                Some((
+                    expected + storage.expected,
+                    found + storage.found,
+                    kinds_correct + storage.kinds_correct,
+                ))
+            },
+        );
+
+        Metrics {
+            contracts: contracts.len(),
+            functions: functions.functions.len(),
+            selector_recall: functions.selector_recall(),
+            argument_accuracy: functions.argument_accuracy(),
+            type_accuracy: functions.type_accuracy(),
+            mutability_accuracy: functions.mutability_accuracy(),
+            storage_recall: storage.map(|(expected, found, _)| ratio(found, expected)),
+            storage_kind_accuracy: storage
+                .map(|(_, found, kinds_correct)| ratio(kinds_correct, found)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvaluateResult {
+    pub metrics: Metrics,
+    /// Each contract with deployed bytecode, ordered by artifact path
+    pub contracts: Vec<ContractEvaluation>,
+}
+
+/// Decompile each compiled contract, and compare what's recovered with its artifact: whether
+/// each selector is found, and each function's arguments, their types, and its state mutability
+/// are inferred correctly, and, when the artifact includes a storage layout, whether each slot is
+/// recovered with the correct kind.
+pub async fn evaluate(args: EvaluateArgs) -> Result<EvaluateResult, Error> {
+    set_logger_env(&args.verbose);
+
+    // get a new logger
+    let (logger, _) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    // check the format before decompiling, so an invalid one fails fast
+    if !["json", "markdown"].contains(&args.format.as_str()) {
+        return Err(Error::GenericError(format!(
+            "invalid format '{}' . expected one of: json, markdown.",
+            args.format
+        )))
+    }
+
+    let target = Path::new(&args.target);
+    let paths = match target.is_dir() {
+        true => find_artifacts(target),
+        false => vec![target.to_path_buf()],
+    };
+
+    let mut contracts = Vec::new();
+    for path in paths {
+        let path = path.to_string_lossy().to_string();
+
+        // interfaces, abstract contracts, and other JSON files aren't evaluated
+        let artifacts = match Artifact::read_all(&path) {
+            Ok(artifacts) => artifacts,
+            Err(e) => {
+                logger.debug(&format!("skipping '{path}' : {e}"));
+                continue
+            }
+        };
+
+        for artifact in artifacts {
+            let contract = evaluate_artifact(&args, &path, artifact).await?;
+            logger.info(&format!("evaluated '{}' .", contract.name));
+            contracts.push(contract);
+        }
+    }
+
+    if contracts.is_empty() {
+        return Err(Error::GenericError(format!(
+            "'{}' doesn't contain any artifacts with deployed bytecode.",
+            args.target
+        )))
+    }
+
+    Ok(EvaluateResult { metrics: Metrics::new(&contracts), contracts })
+}
+
+/// Decompile a single contract, and recover its storage layout if the artifact includes one to
+/// compare it with.
+async fn evaluate_artifact(
+    args: &EvaluateArgs,
+    path: &str,
+    artifact: Artifact,
+) -> Result<ContractEvaluation, Error> {
+    let name = artifact.name.clone().unwrap_or_else(|| path.to_string());
+    enter_pass(&format!("evaluation ({name})"));
+
+    let result = decompile_with(
+        DecompilerArgsBuilder::new()
+            .target(artifact.bytecode.clone())
+            .verbose(args.verbose.clone())
+            .timeout(args.timeout)
+            .build()
+            .map_err(|e| Error::GenericError(e.to_string()))?,
+        Some(artifact.clone()),
+        |_| {},
+    )
+    .await
+    .map_err(|e| Error::GenericError(format!("failed to decompile '{name}' : {e}")))?;
+
+    let storage = match &artifact.storage {
+        Some(expected) => {
+            let recovered = layout(
+                LayoutArgsBuilder::new()
+                    .target(artifact.bytecode.clone())
+                    .verbose(args.verbose.clone())
+                    .timeout(args.timeout)
+                    .build()
+                    .map_err(|e| Error::GenericError(e.to_string()))?,
+            )
+            .await?
+            .layout;
+            Some(evaluate_storage(expected, &recovered))
+        }
+        None => None,
+    };
+
+    Ok(ContractEvaluation {
+        name,
+        path: path.to_string(),
+        functions: result.evaluation.unwrap_or_default(),
+        storage,
+    })
+}
+
+/// Compare the recovered storage layout with the artifact's. Packed variables share a slot, so
+/// slots are compared rather than variables.
+pub fn evaluate_storage(
+    expected: &[StorageVariable],
+    recovered: &[SlotUsage],
+) -> StorageEvaluation {
+    // the variables in each slot of the artifact's layout, by slot
+    let mut slots: BTreeMap<U256, Vec<&StorageVariable>> = BTreeMap::new();
+    for variable in expected {
+        slots.entry(variable.slot).or_default().push(variable);
+    }
+
+    let mut evaluation = StorageEvaluation { expected: slots.len(), ..Default::default() };
+    for (slot, variables) in &slots {
+        match recovered.iter().find(|usage| &usage.slot == slot) {
+            Some(usage) => {
+                evaluation.found += 1;
+                if variables.iter().any(|variable| expected_kinds(variable).contains(&usage.kind)) {
+                    evaluation.kinds_correct += 1;
+                }
+            }
+            None => {
+                evaluation.missing.extend(variables.iter().map(|variable| variable.label.clone()))
+            }
+        }
+    }
+
+    // a struct or a fixed-size array spans the slots after its own, which aren't listed
+    evaluation.unexpected = recovered
+        .iter()
+        .map(|usage| usage.slot)
+        .filter(|slot| {
+            !expected.iter().any(|variable| {
+                let slots = (variable.offset + variable.size).div_ceil(32).max(1);
+                *slot >= variable.slot && *slot < variable.slot.saturating_add(U256::from(slots))
+            })
+        })
+        .collect();
+    evaluation
+}
+
+/// The slot kinds a variable may be recovered as. A `bytes` or `string` is stored in its slot when
+/// it's short, or at its slot's hash, like an array, when it isn't.
+fn expected_kinds(variable: &StorageVariable) -> &'static [SlotKind] {
+    match variable.encoding.as_str() {
+        "mapping" => &[SlotKind::Mapping],
+        "dynamic_array" => &[SlotKind::Array],
+        "bytes" => &[SlotKind::Value, SlotKind::Array],
+        _ => &[SlotKind::Value],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::evaluate::*;
+
+    fn variable(label: &str, slot: u64, encoding: &str) -> StorageVariable {
+        StorageVariable {
+            label: label.to_string(),
+            slot: U256::from(slot),
+            offset: 0,
+            size: 32,
+            type_name: String::new(),
+            encoding: encoding.to_string(),
+        }
+    }
+
+    fn usage(slot: u64, kind: SlotKind) -> SlotUsage {
+        SlotUsage {
+            slot: U256::from(slot),
+            kind,
+            label: None,
+            reads: BTreeSet::new(),
+            writes: BTreeSet::new(),
+        }
+    }
+
+    #[test]
+    fn test_evaluate_storage() {
+        let expected = vec![
+            variable("owner", 0, "inplace"),
+            variable("paused", 0, "inplace"),
+            StorageVariable { size: 64, ..variable("config", 1, "inplace") },
+            variable("balances", 3, "mapping"),
+            variable("holders", 4, "dynamic_array"),
+        ];
+        let recovered = vec![
+            usage(0, SlotKind::Value),
+            usage(2, SlotKind::Value),
+            usage(3, SlotKind::Value),
+            usage(7, SlotKind::Value),
+        ];
+
+        let evaluation = evaluate_storage(&expected, &recovered);
+        assert_eq!(evaluation.expected, 4);
+        assert_eq!(evaluation.found, 2);
+        assert_eq!(evaluation.kinds_correct, 1);
+        assert_eq!(evaluation.missing, vec![String::from("config"), String::from("holders")]);
+
+        // slot 2 is the second half of `config`
+        assert_eq!(evaluation.unexpected, vec![U256::from(7)]);
+    }
+}
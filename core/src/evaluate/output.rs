@@ -0,0 +1,152 @@
+use super::{EvaluateResult, Metrics, StorageEvaluation};
+
+/// Write the evaluation as markdown, with a summary table and a section for each contract.
+pub fn build_markdown(result: &EvaluateResult) -> String {
+    let mut output = vec![String::from("# Decompiler evaluation"), String::new()];
+    output.extend(metrics_notes(&result.metrics).into_iter().map(|note| format!("- {note}")));
+    output.push(String::new());
+    output
+        .push(String::from("| contract | selectors | arguments | types | mutability | storage |"));
+    output.push(String::from("| --- | --- | --- | --- | --- | --- |"));
+    for contract in &result.contracts {
+        output.push(format!(
+            "| `{}` | {:.1}% | {:.1}% | {:.1}% | {:.1}% | {} |",
+            contract.name,
+            contract.functions.selector_recall() * 100.0,
+            contract.functions.argument_accuracy() * 100.0,
+            contract.functions.type_accuracy() * 100.0,
+            contract.functions.mutability_accuracy() * 100.0,
+            match &contract.storage {
+                Some(storage) => format!("{}/{}", storage.found, storage.expected),
+                None => String::from("-"),
+            }
+        ));
+    }
+
+    for contract in &result.contracts {
+        output.push(String::new());
+        output.push(format!("## `{}`", contract.name));
+        output.push(String::new());
+        output.push(contract.functions.to_markdown());
+        if let Some(storage) = &contract.storage {
+            output.push(String::new());
+            output.extend(storage_notes(storage).into_iter().map(|note| format!("- {note}")));
+        }
+    }
+
+    output.join("\n")
+}
+
+/// Notes on the decompiler's accuracy across every contract, e.g. `selectors: 95.0% of 40
+/// found`.
+fn metrics_notes(metrics: &Metrics) -> Vec<String> {
+    let mut notes = vec![
+        format!(
+            "selectors: {:.1}% of {} found, across {} contracts",
+            metrics.selector_recall * 100.0,
+            metrics.functions,
+            metrics.contracts
+        ),
+        format!("arguments: {:.1}% correct", metrics.argument_accuracy * 100.0),
+        format!("types: {:.1}% correct", metrics.type_accuracy * 100.0),
+        format!("mutability: {:.1}% correct", metrics.mutability_accuracy * 100.0),
+    ];
+    if let (Some(recall), Some(kind_accuracy)) =
+        (metrics.storage_recall, metrics.storage_kind_accuracy)
+    {
+        notes.push(format!(
+            "storage: {:.1}% of slots found, {:.1}% with the correct kind",
+            recall * 100.0,
+            kind_accuracy * 100.0
+        ));
+    }
+    notes
+}
+
+/// Notes on the recovered storage layout, e.g. `storage: 2 of 3 slots found, 2 with the correct
+/// kind`.
+fn storage_notes(storage: &StorageEvaluation) -> Vec<String> {
+    let mut notes = vec![format!(
+        "storage: {} of {} slots found, {} with the correct kind",
+        storage.found, storage.expected, storage.kinds_correct
+    )];
+    if !storage.missing.is_empty() {
+        notes.push(format!(
+            "missing: {}",
+            storage
+                .missing
+                .iter()
+                .map(|label| format!("`{label}`"))
+                .collect::<Vec<String>>()
+                .join(", ")
+        ));
+    }
+    if !storage.unexpected.is_empty() {
+        notes.push(format!(
+            "unexpected slots: {}",
+            storage
+                .unexpected
+                .iter()
+                .map(|slot| format!("`{slot:#x}`"))
+                .collect::<Vec<String>>()
+                .join(", ")
+        ));
+    }
+    notes
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::types::U256;
+
+    use crate::{
+        decompile::evaluate::Evaluation,
+        evaluate::{output::*, ContractEvaluation, Metrics},
+    };
+
+    #[test]
+    fn test_build_markdown() {
+        let contracts = vec![ContractEvaluation {
+            name: String::from("Token"),
+            path: String::from("out/Token.sol/Token.json"),
+            functions: Evaluation::default(),
+            storage: Some(StorageEvaluation {
+                expected: 2,
+                found: 1,
+                kinds_correct: 1,
+                missing: vec![String::from("balances")],
+                unexpected: vec![U256::from(7)],
+            }),
+        }];
+        let result = EvaluateResult { metrics: Metrics::new(&contracts), contracts };
+
+        assert_eq!(
+            build_markdown(&result),
+            [
+                "# Decompiler evaluation",
+                "",
+                "- selectors: 100.0% of 0 found, across 1 contracts",
+                "- arguments: 100.0% correct",
+                "- types: 100.0% correct",
+                "- mutability: 100.0% correct",
+                "- storage: 50.0% of slots found, 100.0% with the correct kind",
+                "",
+                "| contract | selectors | arguments | types | mutability | storage |",
+                "| --- | --- | --- | --- | --- | --- |",
+                "| `Token` | 100.0% | 100.0% | 100.0% | 100.0% | 1/2 |",
+                "",
+                "## `Token`",
+                "",
+                "- selectors: 100.0% of 0 found, 0 unexpected",
+                "- arguments: 100.0% correct",
+                "- types: 100.0% correct",
+                "- mutability: 100.0% correct",
+                "",
+                "- storage: 1 of 2 slots found, 1 with the correct kind",
+                "- missing: `balances`",
+                "- unexpected slots: `0x7`",
+            ]
+            .join("\n")
+        );
+    }
+}
@@ -0,0 +1,142 @@
+pub mod util;
+
+use std::sync::Arc;
+
+use clap::{AppSettings, Parser};
+use derive_builder::Builder;
+use heimdall_common::{
+    ether::{bytecode::get_bytecode_from_target, corpus::Corpus},
+    utils::io::{
+        crash::enter_pass,
+        logging::{set_logger_env, Logger},
+    },
+};
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+
+use self::util::{fingerprint_functions, match_function, FunctionFingerprint, FunctionMatch};
+
+#[derive(Debug, Clone, Parser, Builder)]
+#[clap(
+    about = "Fingerprint functions and match them against a corpus of contracts",
+    after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    global_setting = AppSettings::DeriveDisplayOrder,
+    override_usage = "heimdall fingerprint <TARGET> [OPTIONS]"
+)]
+pub struct FingerprintArgs {
+    /// The target to fingerprint, either a file, bytecode, contract address, or ENS name.
+    #[clap(required = true)]
+    pub target: String,
+
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// The RPC provider to use for fetching target bytecode.
+    #[clap(long = "rpc-url", short, default_value = "", hide_default_value = true)]
+    pub rpc_url: String,
+
+    /// The selector of the function to match against the corpus.
+    #[clap(long, short, default_value = "", hide_default_value = true)]
+    pub selector: String,
+
+    /// A directory with one contract per file, or a file with one contract per line, to search
+    /// for functions similar to the selected function.
+    #[clap(long, short, default_value = "", hide_default_value = true)]
+    pub corpus: String,
+
+    /// The minimum similarity, from 0 to 1, for a corpus function to be reported as a match.
+    #[clap(long, default_value = "0.9")]
+    pub threshold: f64,
+
+    /// The maximum number of matches to report.
+    #[clap(long, short, default_value = "10")]
+    pub limit: usize,
+
+    /// The number of threads to fingerprint corpus contracts with.
+    #[clap(long, default_value = "8")]
+    pub threads: usize,
+
+    /// The output directory to write the output to or 'print' to print to the console
+    #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
+    pub output: String,
+
+    /// The name for the output file
+    #[clap(long, short, default_value = "", hide_default_value = true)]
+    pub name: String,
+
+    /// The timeout for each function's symbolic execution in milliseconds.
+    #[clap(long, short, default_value = "10000", hide_default_value = true)]
+    pub timeout: u64,
+}
+
+impl FingerprintArgsBuilder {
+    pub fn new() -> Self {
+        Self {
+            target: Some(String::new()),
+            verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
+            rpc_url: Some(String::new()),
+            selector: Some(String::new()),
+            corpus: Some(String::new()),
+            threshold: Some(0.9),
+            limit: Some(10),
+            threads: Some(8),
+            output: Some(String::new()),
+            name: Some(String::new()),
+            timeout: Some(10000),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FingerprintResult {
+    /// A fingerprint for each function in the target
+    pub functions: Vec<FunctionFingerprint>,
+    /// Corpus functions similar to the selected function, most similar first
+    pub matches: Vec<FunctionMatch>,
+}
+
+/// Fingerprint each function in the target. If a corpus is given, the function with the given
+/// selector is matched against every function in the corpus.
+pub async fn fingerprint(args: FingerprintArgs) -> Result<FingerprintResult, Error> {
+    set_logger_env(&args.verbose);
+
+    // get a new logger
+    let (logger, _) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    let contract_bytecode = get_bytecode_from_target(&args.target, &args.rpc_url)
+        .await
+        .map_err(|e| Error::GenericError(e.to_string()))?;
+
+    enter_pass("fingerprint");
+    let functions = fingerprint_functions(&contract_bytecode, args.timeout)?;
+    logger.info(&format!("fingerprinted {} functions.", functions.len()));
+
+    if args.corpus.is_empty() {
+        return Ok(FingerprintResult { functions, matches: Vec::new() })
+    }
+
+    if args.selector.is_empty() {
+        return Err(Error::GenericError(
+            "a selector is required to match against a corpus.".to_string(),
+        ))
+    }
+    let selector = format!("0x{}", args.selector.trim_start_matches("0x").to_lowercase());
+    let function = functions.iter().find(|f| f.selector == selector).ok_or_else(|| {
+        Error::GenericError(format!("function '{selector}' was not found in the target."))
+    })?;
+
+    enter_pass("match");
+    let corpus =
+        Arc::new(Corpus::open(&args.corpus).map_err(|e| Error::GenericError(e.to_string()))?);
+    logger.info(&format!("matching '{selector}' against {} contracts.", corpus.len()));
+    let matches =
+        match_function(function, corpus, args.threshold, args.limit, args.threads, args.timeout);
+    logger.info(&format!("found {} matching functions.", matches.len()));
+
+    Ok(FingerprintResult { functions, matches })
+}
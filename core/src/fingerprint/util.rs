@@ -0,0 +1,257 @@
+use std::{collections::HashSet, sync::Arc, time::Duration};
+
+use ethers::utils::keccak256;
+use heimdall_common::{
+    ether::{corpus::Corpus, evm::core::vm::VM, selectors::find_function_selectors},
+    utils::{
+        strings::decode_hex,
+        threading::{run_with_timeout, task_pool},
+    },
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    disassemble::{disassemble, DisassemblerArgs},
+    error::Error,
+    metrics::codesize::{dispatcher_pcs, executed_pcs},
+};
+
+/// The number of consecutive opcodes hashed together into each feature of a simhash.
+const SHINGLE_SIZE: usize = 4;
+
+/// A normalized fingerprint of a single function: the opcodes it executes, in code order and
+/// without PUSH data, so copies of a function compare equal wherever they're deployed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FunctionFingerprint {
+    pub selector: String,
+    pub entry_point: u128,
+    pub opcodes: Vec<u8>,
+    /// A 64 bit simhash of the function's opcode shingles. Similar functions have simhashes
+    /// which differ in few bits.
+    pub simhash: u64,
+}
+
+impl FunctionFingerprint {
+    /// Build a fingerprint from the opcodes a function executes.
+    pub fn new(selector: &str, entry_point: u128, opcodes: Vec<u8>) -> FunctionFingerprint {
+        FunctionFingerprint {
+            selector: selector.to_string(),
+            entry_point,
+            simhash: simhash(&opcodes),
+            opcodes,
+        }
+    }
+
+    /// The similarity of two fingerprints from 0 to 1, where 1 is identical: one minus the
+    /// Levenshtein distance between their opcodes, relative to the longer of the two.
+    pub fn similarity(&self, other: &FunctionFingerprint) -> f64 {
+        let longest = self.opcodes.len().max(other.opcodes.len());
+        if longest == 0 {
+            return 1.0
+        }
+
+        let distance = strsim::generic_levenshtein(&self.opcodes, &other.opcodes);
+        (longest - distance) as f64 / longest as f64
+    }
+
+    /// The highest similarity this fingerprint could have with another, based on their lengths
+    /// alone. Much cheaper than [`FunctionFingerprint::similarity`].
+    pub fn max_similarity(&self, other: &FunctionFingerprint) -> f64 {
+        let (shorter, longer) = match self.opcodes.len() <= other.opcodes.len() {
+            true => (self.opcodes.len(), other.opcodes.len()),
+            false => (other.opcodes.len(), self.opcodes.len()),
+        };
+        match longer {
+            0 => 1.0,
+            _ => shorter as f64 / longer as f64,
+        }
+    }
+
+    /// The number of bits in which the two fingerprints' simhashes differ.
+    pub fn simhash_distance(&self, other: &FunctionFingerprint) -> u32 {
+        (self.simhash ^ other.simhash).count_ones()
+    }
+}
+
+/// Compute the simhash of an opcode sequence, hashing each run of [`SHINGLE_SIZE`] opcodes.
+pub fn simhash(opcodes: &[u8]) -> u64 {
+    let mut weights = [0i64; 64];
+    for shingle in opcodes.windows(SHINGLE_SIZE.min(opcodes.len()).max(1)) {
+        let hash = keccak256(shingle);
+        let hash = u64::from_be_bytes(hash[..8].try_into().expect("hash is 32 bytes"));
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            match hash >> bit & 1 {
+                1 => *weight += 1,
+                _ => *weight -= 1,
+            }
+        }
+    }
+
+    weights
+        .iter()
+        .enumerate()
+        .filter(|(_, weight)| **weight > 0)
+        .fold(0, |simhash, (bit, _)| simhash | 1 << bit)
+}
+
+/// The opcodes at the given program counters, in code order.
+fn opcodes_at(bytecode: &[u8], pcs: &HashSet<usize>) -> Vec<u8> {
+    let mut pcs = pcs.iter().copied().collect::<Vec<usize>>();
+    pcs.sort_unstable();
+    pcs.into_iter().filter_map(|pc| bytecode.get(pc).copied()).collect()
+}
+
+/// Fingerprint every function in the given bytecode. Each function is symbolically executed,
+/// and the instructions it shares with the dispatcher are left out. Functions whose symbolic
+/// execution times out are skipped.
+pub fn fingerprint_functions(
+    contract_bytecode: &str,
+    timeout: u64,
+) -> Result<Vec<FunctionFingerprint>, Error> {
+    let contract_bytecode = contract_bytecode.replacen("0x", "", 1);
+    let bytecode = decode_hex(&contract_bytecode)
+        .map_err(|e| Error::GenericError(format!("failed to decode bytecode: {e}")))?;
+
+    // disassembling bytecode never waits on the network, so it can be run to completion here
+    let disassembled_bytecode = futures::executor::block_on(disassemble(DisassemblerArgs {
+        target: contract_bytecode.clone(),
+        verbose: clap_verbosity_flag::Verbosity::new(0, 1),
+        rpc_url: String::new(),
+        decimal_counter: false,
+        format: String::from("asm"),
+        verify: false,
+        name: String::new(),
+        output: String::new(),
+    }))
+    .map_err(|e| Error::GenericError(format!("failed to disassemble bytecode: {e}")))?;
+
+    let evm = VM::new(
+        contract_bytecode,
+        String::from("0x"),
+        String::from("0x6865696d64616c6c000000000061646472657373"),
+        String::from("0x6865696d64616c6c0000000000006f726967696e"),
+        String::from("0x6865696d64616c6c00000000000063616c6c6572"),
+        0,
+        u128::MAX,
+    );
+
+    let mut selectors = find_function_selectors(&evm, &disassembled_bytecode)
+        .into_iter()
+        .collect::<Vec<(String, u128)>>();
+    selectors.sort();
+
+    let mut fingerprints = Vec::new();
+    for (selector, entry_point) in selectors {
+        let mut evm_clone = evm.clone();
+        let selector_clone = selector.clone();
+        let trace = match run_with_timeout(
+            move || evm_clone.symbolic_exec_selector(&selector_clone, entry_point),
+            Duration::from_millis(timeout),
+        ) {
+            Some((trace, _)) => trace,
+            None => continue,
+        };
+
+        let mut pcs = HashSet::new();
+        executed_pcs(&trace, &mut pcs);
+        let dispatcher = dispatcher_pcs(&evm, &selector, entry_point);
+        pcs.retain(|pc| !dispatcher.contains(pc));
+
+        fingerprints.push(FunctionFingerprint::new(
+            &format!("0x{selector}"),
+            entry_point,
+            opcodes_at(&bytecode, &pcs),
+        ));
+    }
+
+    Ok(fingerprints)
+}
+
+/// A function in a corpus contract which is similar to the function being matched.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FunctionMatch {
+    /// The name of the corpus contract
+    pub contract: String,
+    pub selector: String,
+    pub similarity: f64,
+    pub simhash_distance: u32,
+}
+
+/// Search a corpus for functions at least `threshold` similar to the given function, returning
+/// the `limit` most similar, best first. Corpus contracts are fingerprinted across `threads`
+/// threads; contracts which can't be read or decoded are skipped.
+pub fn match_function(
+    function: &FunctionFingerprint,
+    corpus: Arc<Corpus>,
+    threshold: f64,
+    limit: usize,
+    threads: usize,
+    timeout: u64,
+) -> Vec<FunctionMatch> {
+    let function = function.clone();
+    let mut matches = task_pool((0..corpus.len()).collect(), threads.max(1), move |index| {
+        let entry = match corpus.get(index) {
+            Ok(entry) => entry,
+            Err(_) => return Vec::new(),
+        };
+
+        fingerprint_functions(&entry.bytecode(), timeout)
+            .unwrap_or_default()
+            .into_iter()
+            // the length bound rules out most functions without computing their distance
+            .filter(|candidate| function.max_similarity(candidate) >= threshold)
+            .filter_map(|candidate| {
+                let similarity = function.similarity(&candidate);
+                (similarity >= threshold).then(|| FunctionMatch {
+                    contract: entry.name.clone(),
+                    selector: candidate.selector.clone(),
+                    similarity,
+                    simhash_distance: function.simhash_distance(&candidate),
+                })
+            })
+            .collect::<Vec<FunctionMatch>>()
+    })
+    .into_iter()
+    .flatten()
+    .collect::<Vec<FunctionMatch>>();
+
+    matches.sort_by(|a, b| {
+        b.similarity
+            .total_cmp(&a.similarity)
+            .then(a.simhash_distance.cmp(&b.simhash_distance))
+            .then(a.contract.cmp(&b.contract))
+            .then(a.selector.cmp(&b.selector))
+    });
+    matches.truncate(limit);
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fingerprint::util::*;
+
+    #[test]
+    fn test_similarity() {
+        let function = FunctionFingerprint::new("0x01", 0, vec![0x5b, 0x60, 0x35, 0x14, 0x57]);
+        let changed = FunctionFingerprint::new("0x02", 0, vec![0x5b, 0x60, 0x35, 0x15, 0x57]);
+
+        assert_eq!(function.similarity(&function.clone()), 1.0);
+        assert_eq!(function.similarity(&changed), 0.8);
+        assert_eq!(function.simhash_distance(&function.clone()), 0);
+    }
+
+    #[test]
+    fn test_max_similarity_bounds_similarity() {
+        let function = FunctionFingerprint::new("0x01", 0, vec![0x5b, 0x60, 0x35, 0x14]);
+        let longer = FunctionFingerprint::new("0x02", 0, vec![0x5b, 0x60, 0x35, 0x14, 0x57, 0x00]);
+
+        assert!(function.similarity(&longer) <= function.max_similarity(&longer));
+        assert_eq!(function.similarity(&longer), 4.0 / 6.0);
+    }
+
+    #[test]
+    fn test_simhash_of_short_sequences() {
+        assert_eq!(simhash(&[]), 0);
+        assert_eq!(simhash(&[0x00]), simhash(&[0x00]));
+    }
+}
@@ -4,7 +4,10 @@ use ethers::types::Address;
 use futures::future::try_join_all;
 
 use crate::{error::Error, inspect::InspectArgs};
-use heimdall_common::{resources::transpose::get_label, utils::hex::ToLowerHex};
+use heimdall_common::{
+    resources::{labels::get_address_label, transpose::get_label},
+    utils::{hex::ToLowerHex, io::address::format_address},
+};
 
 #[derive(Debug, Clone)]
 pub struct Contracts {
@@ -26,7 +29,7 @@ impl Contracts {
     pub async fn add(&mut self, address: Address) -> Result<(), Error> {
         // if skip resolving, just add the address
         if self.skip_resolving {
-            self.contracts.insert(address, address.to_lower_hex());
+            self.contracts.insert(address, format_address(&address));
             return Ok(())
         }
 
@@ -35,15 +38,21 @@ impl Contracts {
             return Ok(())
         }
 
+        // prefer the local label store over remote labels
+        if let Some(label) = get_address_label(&address.to_lower_hex()) {
+            self.contracts.insert(address, label);
+            return Ok(())
+        }
+
         if !self.transpose_api_key.is_empty() {
             self.contracts.insert(
                 address,
                 get_label(&address.to_lower_hex(), &self.transpose_api_key)
                     .await
-                    .unwrap_or(address.to_lower_hex()),
+                    .unwrap_or(format_address(&address)),
             );
         } else {
-            self.contracts.insert(address, address.to_lower_hex());
+            self.contracts.insert(address, format_address(&address));
         }
 
         Ok(())
@@ -53,10 +62,18 @@ impl Contracts {
         // if skip resolving, just add the address
         if self.skip_resolving {
             self.contracts
-                .extend(addresses.into_iter().map(|address| (address, address.to_lower_hex())));
+                .extend(addresses.into_iter().map(|address| (address, format_address(&address))));
             return Ok(())
         }
 
+        // prefer the local label store over remote labels
+        let (labeled, addresses): (HashSet<Address>, HashSet<Address>) = addresses
+            .into_iter()
+            .partition(|address| get_address_label(&address.to_lower_hex()).is_some());
+        self.contracts.extend(labeled.into_iter().filter_map(|address| {
+            get_address_label(&address.to_lower_hex()).map(|label| (address, label))
+        }));
+
         // for each address, get the label
         if !self.transpose_api_key.is_empty() {
             let transpose_api_key_clone = self.transpose_api_key.clone();
@@ -78,12 +95,12 @@ impl Contracts {
                 addresses
                     .into_iter()
                     .zip(labels.into_iter())
-                    .map(|(address, label)| (address, label.unwrap_or(address.to_lower_hex()))),
+                    .map(|(address, label)| (address, label.unwrap_or(format_address(&address)))),
             );
             // replace None
         } else {
             self.contracts
-                .extend(addresses.into_iter().map(|address| (address, address.to_lower_hex())));
+                .extend(addresses.into_iter().map(|address| (address, format_address(&address))));
         }
 
         Ok(())
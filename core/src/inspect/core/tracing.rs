@@ -7,17 +7,21 @@ use async_recursion::async_recursion;
 use ethers::{
     abi::Token,
     types::{
-        ActionType, Address, Bytes, Call, CallResult, CallType, Create, CreateResult,
-        ExecutedInstruction, Reward, StorageDiff, Suicide, TransactionTrace, VMTrace, U256,
+        Action, ActionType, Address, Bytes, Call, CallFrame, CallResult, CallType, Create,
+        CreateResult, ExecutedInstruction, NameOrAddress, Res, Reward, StorageDiff, Suicide,
+        TransactionTrace, VMTrace, U256,
     },
 };
 use heimdall_common::{
     debug_max,
-    ether::signatures::ResolvedFunction,
+    ether::{
+        panics::{decode_panic, describe_panic},
+        signatures::ResolvedFunction,
+    },
     utils::{
         env::get_env,
         hex::ToLowerHex,
-        io::{logging::TraceFactory, types::Parameterize},
+        io::{address::format_address, logging::TraceFactory, types::Parameterize},
     },
 };
 use serde::{Deserialize, Serialize};
@@ -362,6 +366,34 @@ impl DecodedTransactionTrace {
         Ok(())
     }
 
+    /// Join logs to the calls which emitted them, using a `callTracer` call tree instead of a
+    /// VM trace. Each of the call tree's logs takes the matching decoded log, so logs keep their
+    /// resolved events and indices.
+    pub fn join_call_logs(
+        &mut self,
+        decoded_logs: &mut VecDeque<DecodedLog>,
+        call_frame: &CallFrame,
+    ) -> Result<(), Error> {
+        for log in call_frame.logs.iter().flatten() {
+            let position = decoded_logs
+                .iter()
+                .position(|decoded_log| {
+                    Some(decoded_log.address) == log.address &&
+                        Some(&decoded_log.topics) == log.topics.as_ref() &&
+                        Some(&decoded_log.data) == log.data.as_ref()
+                })
+                .ok_or(Error::DecodeError)?;
+            self.logs.extend(decoded_logs.remove(position));
+        }
+
+        // the call tree's calls are in the same order as the subtraces built from it
+        for (subtrace, call) in self.subtraces.iter_mut().zip(call_frame.calls.iter().flatten()) {
+            subtrace.join_call_logs(decoded_logs, call)?;
+        }
+
+        Ok(())
+    }
+
     #[async_recursion]
     pub async fn build_state_diffs(
         &mut self,
@@ -400,17 +432,25 @@ impl DecodedTransactionTrace {
         Ok(())
     }
 
+    /// The number of calls made within this one, at any depth.
+    pub fn nested_calls(&self) -> usize {
+        self.subtraces.iter().map(|subtrace| 1 + subtrace.nested_calls()).sum()
+    }
+
+    /// Add the call and the calls made within it to the trace. Calls nested more than
+    /// `max_depth` calls below the transaction's are collapsed into a count.
     pub fn add_to_trace(
         &self,
         contracts: &Contracts,
         trace: &mut TraceFactory,
         parent_trace_index: u32,
+        max_depth: Option<usize>,
     ) {
         let parent_trace_index = match &self.action {
             DecodedAction::Call(call) => trace.add_call_with_extra(
                 parent_trace_index,
                 call.gas.as_u32(),
-                contracts.get(call.to).unwrap_or(&call.to.to_lower_hex()).clone(),
+                contracts.get(call.to).unwrap_or(&format_address(&call.to)).clone(),
                 match call.resolved_function.as_ref() {
                     Some(f) => f.name.clone(),
                     None => "fallback".to_string(),
@@ -433,7 +473,10 @@ impl DecodedTransactionTrace {
                             .map(|token| token.parameterize())
                             .collect::<Vec<String>>();
 
-                        if outputs.is_empty() {
+                        // compiler panics are shown as what the compiler was checking
+                        if let Some(code) = decode_panic(&call_result.output) {
+                            describe_panic(code)
+                        } else if outputs.is_empty() {
                             [call_result.output.to_lower_hex()].join(", ")
                         } else {
                             outputs.join(", ")
@@ -453,7 +496,7 @@ impl DecodedTransactionTrace {
                 match &self.result.as_ref() {
                     Some(DecodedRes::Create(create_result)) => contracts
                         .get(create_result.address)
-                        .unwrap_or(&create_result.address.to_lower_hex())
+                        .unwrap_or(&format_address(&create_result.address))
                         .clone(),
                     _ => "".to_string(),
                 },
@@ -462,17 +505,17 @@ impl DecodedTransactionTrace {
             DecodedAction::Suicide(suicide) => trace.add_suicide(
                 parent_trace_index,
                 0,
-                suicide.address.to_lower_hex(),
-                suicide.refund_address.to_lower_hex(),
+                format_address(&suicide.address),
+                format_address(&suicide.refund_address),
                 wei_to_ether(suicide.balance),
             ),
             DecodedAction::Reward(reward) => trace.add_call_with_extra(
                 parent_trace_index,
                 0,
-                Address::zero().to_lower_hex(),
+                format_address(&Address::zero()),
                 "reward".to_string(),
                 vec![
-                    reward.author.to_lower_hex(),
+                    format_address(&reward.author),
                     format!("{:?}", reward.reward_type).to_lowercase(),
                 ],
                 "()".to_string(),
@@ -519,13 +562,110 @@ impl DecodedTransactionTrace {
             );
         }
 
+        // collapse the calls below the maximum depth
+        if max_depth.is_some_and(|max_depth| self.trace_address.len() >= max_depth) {
+            let nested_calls = self.nested_calls();
+            if nested_calls > 0 {
+                trace.add_message(
+                    parent_trace_index,
+                    line!(),
+                    vec![format!(
+                        "{} nested call{} collapsed",
+                        nested_calls,
+                        if nested_calls == 1 { "" } else { "s" }
+                    )],
+                );
+            }
+            return
+        }
+
         // iterate over traces
         for decoded_trace in self.subtraces.iter() {
-            decoded_trace.add_to_trace(contracts, trace, parent_trace_index)
+            decoded_trace.add_to_trace(contracts, trace, parent_trace_index, max_depth)
         }
     }
 }
 
+/// Flatten a `callTracer` call tree into the traces `trace_replayTransaction` returns, each
+/// with its position in the tree as its trace address.
+pub fn traces_from_call_frame(call_frame: &CallFrame) -> Vec<TransactionTrace> {
+    let mut traces = Vec::new();
+    flatten_call_frame(call_frame, Vec::new(), &mut traces);
+    traces
+}
+
+fn flatten_call_frame(
+    call_frame: &CallFrame,
+    trace_address: Vec<usize>,
+    traces: &mut Vec<TransactionTrace>,
+) {
+    let to = match &call_frame.to {
+        Some(NameOrAddress::Address(address)) => *address,
+        _ => Address::zero(),
+    };
+    let value = call_frame.value.unwrap_or_default();
+    let output = call_frame.output.clone().unwrap_or_default();
+
+    let (action, action_type, result) = match call_frame.typ.to_uppercase().as_str() {
+        "CREATE" | "CREATE2" => (
+            Action::Create(Create {
+                from: call_frame.from,
+                value,
+                gas: call_frame.gas,
+                init: call_frame.input.clone(),
+            }),
+            ActionType::Create,
+            Res::Create(CreateResult { gas_used: call_frame.gas_used, code: output, address: to }),
+        ),
+        "SELFDESTRUCT" => (
+            Action::Suicide(Suicide {
+                address: call_frame.from,
+                refund_address: to,
+                balance: value,
+            }),
+            ActionType::Suicide,
+            Res::None,
+        ),
+        call_type => (
+            Action::Call(Call {
+                from: call_frame.from,
+                to,
+                value,
+                gas: call_frame.gas,
+                input: call_frame.input.clone(),
+                call_type: match call_type {
+                    "DELEGATECALL" => CallType::DelegateCall,
+                    "STATICCALL" => CallType::StaticCall,
+                    "CALLCODE" => CallType::CallCode,
+                    _ => CallType::Call,
+                },
+            }),
+            ActionType::Call,
+            Res::Call(CallResult { gas_used: call_frame.gas_used, output }),
+        ),
+    };
+
+    let calls = call_frame.calls.clone().unwrap_or_default();
+    traces.push(TransactionTrace {
+        trace_address: trace_address.clone(),
+        subtraces: calls.len(),
+        action,
+        action_type,
+        // failed calls have no result, as with `trace_replayTransaction`
+        result: match call_frame.error {
+            Some(_) => None,
+            None => Some(result),
+        },
+        error: call_frame.error.clone(),
+    });
+
+    for (index, call) in calls.iter().enumerate() {
+        let mut call_address = trace_address.clone();
+        call_address.push(index);
+        flatten_call_frame(call, call_address, traces);
+    }
+}
+
 fn wei_to_ether(wei: U256) -> f64 {
     // convert U256 to u64 safely
     let wei_f64 = wei.min(U256::from(u64::MAX)).as_u64() as f64;
@@ -537,3 +677,70 @@ fn wei_to_ether(wei: U256) -> f64 {
 
     wei_f64 / 10f64.powf(18.0)
 }
+
+#[cfg(test)]
+mod tests {
+    use ethers::types::{CallFrame, NameOrAddress};
+
+    use crate::inspect::core::tracing::*;
+
+    fn call_frame(typ: &str, calls: Vec<CallFrame>) -> CallFrame {
+        CallFrame {
+            typ: typ.to_string(),
+            to: Some(NameOrAddress::Address(Address::repeat_byte(0x22))),
+            calls: Some(calls),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_traces_from_call_frame() {
+        let mut reverted = call_frame("STATICCALL", Vec::new());
+        reverted.error = Some("execution reverted".to_string());
+        let frame = call_frame(
+            "CALL",
+            vec![call_frame("DELEGATECALL", vec![reverted]), call_frame("CREATE2", Vec::new())],
+        );
+
+        let traces = traces_from_call_frame(&frame);
+        assert_eq!(
+            traces.iter().map(|trace| trace.trace_address.clone()).collect::<Vec<_>>(),
+            vec![vec![], vec![0], vec![0, 0], vec![1]]
+        );
+        assert_eq!(traces[0].subtraces, 2);
+        assert!(matches!(
+            &traces[1].action,
+            Action::Call(call) if call.call_type == CallType::DelegateCall
+        ));
+        assert!(traces[2].result.is_none());
+        assert_eq!(traces[2].error, Some("execution reverted".to_string()));
+        assert_eq!(traces[3].action_type, ActionType::Create);
+        assert!(matches!(
+            &traces[3].result,
+            Some(Res::Create(create)) if create.address == Address::repeat_byte(0x22)
+        ));
+    }
+
+    #[test]
+    fn test_nested_calls() {
+        let trace = |trace_address: Vec<usize>, subtraces: Vec<DecodedTransactionTrace>| {
+            DecodedTransactionTrace {
+                trace_address,
+                action: DecodedAction::Suicide(Suicide::default()),
+                action_type: ActionType::Suicide,
+                result: None,
+                error: None,
+                subtraces,
+                logs: Vec::new(),
+                diff: Vec::new(),
+            }
+        };
+        let decoded_trace = trace(
+            vec![],
+            vec![trace(vec![0], vec![trace(vec![0, 0], Vec::new())]), trace(vec![1], Vec::new())],
+        );
+
+        assert_eq!(decoded_trace.nested_calls(), 3);
+        assert_eq!(decoded_trace.subtraces[0].nested_calls(), 1);
+    }
+}
@@ -6,11 +6,11 @@ use clap::{AppSettings, Parser};
 
 use derive_builder::Builder;
 
-use ethers::types::{Log, TransactionTrace, U256, U64};
+use ethers::types::{BlockTrace, Log, TransactionTrace, U256, U64};
 use futures::future::try_join_all;
 use heimdall_common::{
     debug_max,
-    ether::rpc::{get_block_logs, get_trace, get_transaction},
+    ether::rpc::{get_block_logs, get_call_trace, get_trace, get_transaction},
     utils::{
         env::set_env,
         hex::ToLowerHex,
@@ -20,7 +20,11 @@ use heimdall_common::{
 
 use crate::error::Error;
 
-use self::core::{contracts::Contracts, logs::DecodedLog, tracing::DecodedTransactionTrace};
+use self::core::{
+    contracts::Contracts,
+    logs::DecodedLog,
+    tracing::{traces_from_call_frame, DecodedTransactionTrace},
+};
 
 #[derive(Debug, Clone, Parser, Builder)]
 #[clap(
@@ -61,6 +65,11 @@ pub struct InspectArgs {
     /// Whether to skip resolving function selectors and contract labels.
     #[clap(long = "skip-resolving")]
     pub skip_resolving: bool,
+
+    /// Collapse calls nested more than this many calls below the transaction's, showing how many
+    /// were collapsed instead.
+    #[clap(long = "max-depth")]
+    pub max_depth: Option<usize>,
 }
 
 impl InspectArgsBuilder {
@@ -74,6 +83,7 @@ impl InspectArgsBuilder {
             name: Some(String::new()),
             output: Some(String::from("output")),
             skip_resolving: Some(false),
+            max_depth: Some(None),
         }
     }
 }
@@ -105,9 +115,24 @@ pub async fn inspect(args: InspectArgs) -> Result<InspectResult, Error> {
         .map_err(|e| Error::RpcError(e.to_string()))?;
     let block_number = transaction.block_number.unwrap_or(U64::zero()).as_u64();
 
-    // get trace
-    let block_trace =
-        get_trace(&args.target, &args.rpc_url).await.map_err(|e| Error::RpcError(e.to_string()))?;
+    // get trace, falling back to geth's call tracer when the provider can't replay transactions
+    let (block_trace, call_frame) = match get_trace(&args.target, &args.rpc_url).await {
+        Ok(block_trace) => (block_trace, None),
+        Err(_) => {
+            logger.warn("failed to replay transaction. falling back to debug_traceTransaction");
+            let call_frame = get_call_trace(&args.target, &args.rpc_url)
+                .await
+                .map_err(|e| Error::RpcError(e.to_string()))?;
+            let block_trace = BlockTrace {
+                output: call_frame.output.clone().unwrap_or_default(),
+                trace: Some(traces_from_call_frame(&call_frame)),
+                vm_trace: None,
+                state_diff: None,
+                transaction_hash: Some(transaction.hash),
+            };
+            (block_trace, Some(call_frame))
+        }
+    };
 
     // get logs for this transaction
     let transaction_logs = get_block_logs(block_number, &args.rpc_url)
@@ -167,6 +192,9 @@ pub async fn inspect(args: InspectArgs) -> Result<InspectResult, Error> {
             let _ = decoded_trace.join_logs(&mut decoded_logs, vm_trace.clone(), Vec::new()).await;
             // build state diffs within trace
             let _ = decoded_trace.build_state_diffs(vm_trace, Vec::new()).await;
+        } else if let Some(call_frame) = &call_frame {
+            // the call tracer knows which call emitted each log, but not the storage written
+            let _ = decoded_trace.join_call_logs(&mut decoded_logs, call_frame);
         } else {
             logger.warn("no vm trace found for transaction. skipping joining logs");
         }
@@ -181,7 +209,7 @@ pub async fn inspect(args: InspectArgs) -> Result<InspectResult, Error> {
             "()".to_string(),
         );
 
-        decoded_trace.add_to_trace(&contracts, &mut trace, inspect_call);
+        decoded_trace.add_to_trace(&contracts, &mut trace, inspect_call, args.max_depth);
 
         trace.display();
     } else {
@@ -0,0 +1,151 @@
+pub mod output;
+
+use clap::{AppSettings, Parser};
+use derive_builder::Builder;
+use heimdall_common::utils::io::logging::{set_logger_env, Logger};
+
+use crate::{
+    decompile::{decompile, out::abi::ABIStructure, DecompilerArgs},
+    error::Error,
+};
+
+use self::output::{
+    build_abigen_json, build_abigen_module, build_alloy_module, build_solidity_interface,
+    build_typescript_abi,
+};
+
+#[derive(Debug, Clone, Parser, Builder)]
+#[clap(
+    about = "Generate a Solidity interface or typed bindings for EVM bytecode",
+    after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    global_setting = AppSettings::DeriveDisplayOrder,
+    override_usage = "heimdall interface <TARGET> [OPTIONS]"
+)]
+pub struct InterfaceArgs {
+    /// The target to generate an interface for, either a file, bytecode, contract address, or
+    /// ENS name.
+    #[clap(required = true)]
+    pub target: String,
+
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// The RPC provider to use for fetching target bytecode.
+    #[clap(long = "rpc-url", short, default_value = "", hide_default_value = true)]
+    pub rpc_url: String,
+
+    /// The format to generate, one of 'solidity', 'abigen', 'abigen-json', 'alloy', or
+    /// 'typescript'.
+    #[clap(long, short, default_value = "solidity")]
+    pub format: String,
+
+    /// The name of the generated interface.
+    #[clap(long = "interface-name", default_value = "IContract")]
+    pub interface_name: String,
+
+    /// Whether to skip resolving function selectors.
+    #[clap(long = "skip-resolving")]
+    pub skip_resolving: bool,
+
+    /// The output directory to write the output to or 'print' to print to the console
+    #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
+    pub output: String,
+
+    /// The name for the output file
+    #[clap(long, short, default_value = "", hide_default_value = true)]
+    pub name: String,
+
+    /// The timeout for each function's symbolic execution in milliseconds.
+    #[clap(long, short, default_value = "10000", hide_default_value = true)]
+    pub timeout: u64,
+}
+
+impl InterfaceArgsBuilder {
+    pub fn new() -> Self {
+        Self {
+            target: Some(String::new()),
+            verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
+            rpc_url: Some(String::new()),
+            format: Some(String::from("solidity")),
+            interface_name: Some(String::from("IContract")),
+            skip_resolving: Some(false),
+            output: Some(String::new()),
+            name: Some(String::new()),
+            timeout: Some(10000),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InterfaceResult {
+    /// The generated interface or bindings
+    pub source: String,
+    /// The file extension for the generated source
+    pub extension: String,
+    pub abi: Vec<ABIStructure>,
+}
+
+/// Generate an interface for the given target from its decompiled ABI, leaving out the
+/// decompiled function bodies.
+pub async fn interface(args: InterfaceArgs) -> Result<InterfaceResult, Error> {
+    set_logger_env(&args.verbose);
+
+    // get a new logger
+    let (logger, _) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    // check the format before decompiling, so an invalid one fails fast
+    let extension = match args.format.as_str() {
+        "solidity" => "sol",
+        "abigen" | "alloy" => "rs",
+        "abigen-json" => "json",
+        "typescript" => "ts",
+        format => {
+            return Err(Error::GenericError(format!(
+                "invalid format '{format}' . expected one of: solidity, abigen, abigen-json, alloy, typescript."
+            )))
+        }
+    };
+
+    let abi = decompile(DecompilerArgs {
+        target: args.target.clone(),
+        verbose: args.verbose.clone(),
+        rpc_url: args.rpc_url.clone(),
+        default: true,
+        skip_resolving: args.skip_resolving,
+        include_solidity: false,
+        include_yul: false,
+        include_interface: false,
+        output: String::new(),
+        name: String::new(),
+        timeout: args.timeout,
+        stream: false,
+        chain: String::from("ethereum"),
+        summarizer: String::new(),
+        usage: false,
+        artifact: String::new(),
+        include_pcs: false,
+        fragment: false,
+        entry_pc: String::from("0"),
+        no_follow_proxy: false,
+    })
+    .await
+    .map_err(|e| Error::GenericError(format!("failed to decompile target: {e}")))?
+    .abi
+    .unwrap_or_default();
+
+    logger.debug(&format!("generating {} interface from {} ABI entries.", args.format, abi.len()));
+
+    let source = match args.format.as_str() {
+        "solidity" => build_solidity_interface(&args.interface_name, &abi),
+        "abigen" => build_abigen_module(&args.interface_name, &abi),
+        "abigen-json" => build_abigen_json(&abi),
+        "alloy" => build_alloy_module(&args.interface_name, &abi),
+        _ => build_typescript_abi(&args.interface_name, &abi),
+    };
+
+    Ok(InterfaceResult { source, extension: extension.to_string(), abi })
+}
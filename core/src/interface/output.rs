@@ -0,0 +1,375 @@
+use ethers::utils::keccak256;
+use heimdall_common::utils::strings::encode_hex;
+use serde_json::{json, Value};
+
+use crate::decompile::out::abi::{ABIStructure, ABIToken, FunctionABI};
+
+/// Whether a structure's name was recovered, rather than being a placeholder containing its
+/// selector. Placeholder names hash to a different selector, so they can't be declared.
+fn is_resolved(name: &str) -> bool {
+    !["Unresolved_", "CustomError_", "Event_"].iter().any(|prefix| name.starts_with(prefix))
+}
+
+/// The comma separated canonical types of the given tokens.
+fn types(tokens: &[ABIToken]) -> String {
+    tokens.iter().map(|token| token.type_.clone()).collect::<Vec<String>>().join(",")
+}
+
+/// The 4-byte selector of a function with the given name and inputs.
+fn selector(function: &FunctionABI) -> String {
+    let signature = format!("{}({})", function.name, types(&function.inputs));
+    encode_hex(keccak256(signature.as_bytes())[0..4].to_vec())
+}
+
+/// Render parameters as Solidity, adding `location` to dynamically sized types.
+fn solidity_parameters(tokens: &[ABIToken], location: &str) -> String {
+    tokens
+        .iter()
+        .map(|token| {
+            let dynamic = token.type_ == "bytes" ||
+                token.type_ == "string" ||
+                token.type_.ends_with(']') ||
+                token.type_.starts_with('(');
+            match (dynamic, location.is_empty()) {
+                (true, false) => format!("{} {} {}", token.type_, location, token.name),
+                _ => format!("{} {}", token.type_, token.name),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// A comment describing a structure whose name couldn't be resolved.
+fn unresolved_comment(kind: &str, name: &str) -> String {
+    let selector = name.rsplit('_').next().unwrap_or_default();
+    format!("// {kind} 0x{selector} could not be resolved, and must be called by selector")
+}
+
+/// Notes on how much of a structure heimdall recovered, and which parts are inferred.
+fn confidence_notes(structure: &ABIStructure) -> Vec<String> {
+    match structure {
+        ABIStructure::Function(function) => {
+            let mut notes = vec![
+                format!(
+                    "Name and parameter types were resolved from selector 0x{} using a signature database.",
+                    selector(function)
+                ),
+                format!(
+                    "State mutability ({}) was inferred from symbolic execution.",
+                    function.state_mutability
+                ),
+            ];
+            if !function.outputs.is_empty() {
+                notes.push("Return types were inferred from the returned data.".to_string());
+            }
+            notes
+        }
+        ABIStructure::Error(_) => {
+            vec!["Name and parameter types were resolved using a signature database.".to_string()]
+        }
+        ABIStructure::Event(_) => vec![
+            "Name and parameter types were resolved using a signature database.".to_string(),
+            "Which parameters are indexed could not be recovered.".to_string(),
+        ],
+    }
+}
+
+/// The selectors of every structure whose name couldn't be resolved.
+fn unresolved_selectors(abi: &[ABIStructure]) -> Vec<String> {
+    abi.iter()
+        .map(|structure| match structure {
+            ABIStructure::Function(function) => &function.name,
+            ABIStructure::Error(error) => &error.name,
+            ABIStructure::Event(event) => &event.name,
+        })
+        .filter(|name| !is_resolved(name))
+        .map(|name| format!("0x{}", name.rsplit('_').next().unwrap_or_default()))
+        .collect()
+}
+
+/// Render the ABI as a Solidity interface. Structures whose names couldn't be resolved are
+/// left as comments.
+pub fn build_solidity_interface(name: &str, abi: &[ABIStructure]) -> String {
+    let mut lines = vec![
+        "// SPDX-License-Identifier: MIT".to_string(),
+        "pragma solidity >=0.8.0;".to_string(),
+        String::new(),
+        format!("interface {name} {{"),
+    ];
+
+    for structure in abi {
+        let line = match structure {
+            ABIStructure::Function(function) if !is_resolved(&function.name) => {
+                unresolved_comment("function", &function.name)
+            }
+            ABIStructure::Function(function) => {
+                let mutability = match function.state_mutability.as_str() {
+                    "nonpayable" => String::new(),
+                    mutability => format!(" {mutability}"),
+                };
+                let returns = match function.outputs.is_empty() {
+                    true => String::new(),
+                    false => {
+                        format!(" returns ({})", solidity_parameters(&function.outputs, "memory"))
+                    }
+                };
+
+                format!(
+                    "function {}({}) external{}{}; // 0x{}",
+                    function.name,
+                    solidity_parameters(&function.inputs, "calldata"),
+                    mutability,
+                    returns,
+                    selector(function)
+                )
+            }
+            ABIStructure::Error(error) if !is_resolved(&error.name) => {
+                unresolved_comment("error", &error.name)
+            }
+            ABIStructure::Error(error) => {
+                format!("error {}({});", error.name, solidity_parameters(&error.inputs, ""))
+            }
+            ABIStructure::Event(event) if !is_resolved(&event.name) => {
+                unresolved_comment("event", &event.name)
+            }
+            ABIStructure::Event(event) => {
+                format!("event {}({});", event.name, solidity_parameters(&event.inputs, ""))
+            }
+        };
+
+        lines.push(format!("    {line}"));
+    }
+
+    lines.push("}".to_string());
+    lines.join("\n") + "\n"
+}
+
+/// Render the ABI as an ethers-rs `abigen!` invocation using human-readable signatures.
+/// Structures whose names couldn't be resolved are omitted.
+pub fn build_abigen_module(name: &str, abi: &[ABIStructure]) -> String {
+    let signatures = abi
+        .iter()
+        .filter_map(|structure| match structure {
+            ABIStructure::Function(function) if is_resolved(&function.name) => {
+                let mutability = match function.state_mutability.as_str() {
+                    "nonpayable" => String::new(),
+                    mutability => format!(" {mutability}"),
+                };
+                let returns = match function.outputs.is_empty() {
+                    true => String::new(),
+                    false => format!(" returns ({})", types(&function.outputs)),
+                };
+
+                Some(format!(
+                    "function {}({}) external{}{}",
+                    function.name,
+                    types(&function.inputs),
+                    mutability,
+                    returns
+                ))
+            }
+            ABIStructure::Error(error) if is_resolved(&error.name) => {
+                Some(format!("error {}({})", error.name, types(&error.inputs)))
+            }
+            ABIStructure::Event(event) if is_resolved(&event.name) => {
+                Some(format!("event {}({})", event.name, types(&event.inputs)))
+            }
+            _ => None,
+        })
+        .map(|signature| format!("        {signature}"))
+        .collect::<Vec<String>>();
+
+    format!(
+        "use ethers::contract::abigen;\n\nabigen!(\n    {name},\n    r#\"[\n{}\n    ]\"#\n);\n",
+        signatures.join("\n")
+    )
+}
+
+/// Render the ABI as a TypeScript constant, typed `as const` so viem can infer argument and
+/// return types from it. Structures whose names couldn't be resolved are omitted.
+pub fn build_typescript_abi(name: &str, abi: &[ABIStructure]) -> String {
+    let items = abi
+        .iter()
+        .filter_map(|structure| match structure {
+            ABIStructure::Function(function) if is_resolved(&function.name) => {
+                Some(json!(function))
+            }
+            ABIStructure::Error(error) if is_resolved(&error.name) => Some(json!(error)),
+            ABIStructure::Event(event) if is_resolved(&event.name) => Some(json!(event)),
+            _ => None,
+        })
+        .collect::<Vec<Value>>();
+
+    format!(
+        "export const {}Abi = {} as const;\n",
+        name,
+        serde_json::to_string_pretty(&items).unwrap_or("[]".to_string())
+    )
+}
+
+/// Render the ABI as an alloy `sol!` invocation, with doc comments describing which parts of
+/// each structure were inferred. Structures whose names couldn't be resolved are left as
+/// comments.
+pub fn build_alloy_module(name: &str, abi: &[ABIStructure]) -> String {
+    let mut lines = vec![
+        "use alloy::sol;".to_string(),
+        String::new(),
+        "sol! {".to_string(),
+        "    #[sol(rpc)]".to_string(),
+        format!("    interface {name} {{"),
+    ];
+
+    for (line, structure) in build_solidity_interface(name, abi).lines().skip(4).zip(abi.iter()) {
+        let line = line.trim();
+        if !line.starts_with("//") {
+            lines.extend(
+                confidence_notes(structure).into_iter().map(|note| format!("        /// {note}")),
+            );
+        }
+        lines.push(format!("        {line}"));
+    }
+
+    lines.push("    }".to_string());
+    lines.push("}".to_string());
+    lines.join("\n") + "\n"
+}
+
+/// Render the ABI as a JSON artifact which ethers-rs `abigen!` accepts, with the notes on which
+/// parts of each structure were inferred in its `devdoc`. Structures whose names couldn't be
+/// resolved are omitted from the ABI and listed in the `devdoc` instead.
+pub fn build_abigen_json(abi: &[ABIStructure]) -> String {
+    let mut items = Vec::new();
+    let mut methods = serde_json::Map::new();
+    let mut errors = serde_json::Map::new();
+    let mut events = serde_json::Map::new();
+
+    for structure in abi {
+        let details = json!({ "details": confidence_notes(structure).join(" ") });
+        match structure {
+            ABIStructure::Function(function) if is_resolved(&function.name) => {
+                items.push(json!(function));
+                methods.insert(format!("{}({})", function.name, types(&function.inputs)), details);
+            }
+            ABIStructure::Error(error) if is_resolved(&error.name) => {
+                items.push(json!(error));
+                errors
+                    .insert(format!("{}({})", error.name, types(&error.inputs)), json!([details]));
+            }
+            ABIStructure::Event(event) if is_resolved(&event.name) => {
+                items.push(json!(event));
+                events.insert(format!("{}({})", event.name, types(&event.inputs)), details);
+            }
+            _ => {}
+        }
+    }
+
+    let unresolved = unresolved_selectors(abi);
+    let mut devdoc = json!({ "methods": methods, "errors": errors, "events": events });
+    if !unresolved.is_empty() {
+        devdoc["details"] = json!(format!(
+            "The following selectors could not be resolved, and must be called by selector: {}.",
+            unresolved.join(", ")
+        ));
+    }
+
+    serde_json::to_string_pretty(&json!({ "abi": items, "devdoc": devdoc }))
+        .unwrap_or("{}".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        decompile::out::abi::{ABIStructure, ABIToken, FunctionABI},
+        interface::output::*,
+    };
+
+    fn token(name: &str, type_: &str) -> ABIToken {
+        ABIToken {
+            name: name.to_string(),
+            internal_type: type_.to_string(),
+            type_: type_.to_string(),
+        }
+    }
+
+    fn abi() -> Vec<ABIStructure> {
+        vec![
+            ABIStructure::Function(FunctionABI {
+                type_: "function".to_string(),
+                name: "transfer".to_string(),
+                inputs: vec![token("arg0", "address"), token("arg1", "uint256")],
+                outputs: vec![token("ret0", "bool")],
+                state_mutability: "nonpayable".to_string(),
+                constant: false,
+            }),
+            ABIStructure::Function(FunctionABI {
+                type_: "function".to_string(),
+                name: "name".to_string(),
+                inputs: Vec::new(),
+                outputs: vec![token("ret0", "string")],
+                state_mutability: "view".to_string(),
+                constant: false,
+            }),
+            ABIStructure::Function(FunctionABI {
+                type_: "function".to_string(),
+                name: "Unresolved_deadbeef".to_string(),
+                inputs: vec![token("arg0", "bytes")],
+                outputs: Vec::new(),
+                state_mutability: "payable".to_string(),
+                constant: false,
+            }),
+        ]
+    }
+
+    #[test]
+    fn test_solidity_interface() {
+        let interface = build_solidity_interface("IToken", &abi());
+
+        assert!(interface.contains("interface IToken {"));
+        assert!(interface.contains(
+            "function transfer(address arg0, uint256 arg1) external returns (bool ret0); // 0xa9059cbb"
+        ));
+        assert!(interface.contains("function name() external view returns (string memory ret0);"));
+        assert!(interface.contains("// function 0xdeadbeef could not be resolved"));
+    }
+
+    #[test]
+    fn test_abigen_module() {
+        let module = build_abigen_module("IToken", &abi());
+
+        assert!(module.contains("abigen!(\n    IToken,"));
+        assert!(module.contains("function transfer(address,uint256) external returns (bool)"));
+        assert!(!module.contains("Unresolved_deadbeef"));
+    }
+
+    #[test]
+    fn test_alloy_module() {
+        let module = build_alloy_module("IToken", &abi());
+
+        assert!(module.contains("sol! {\n    #[sol(rpc)]\n    interface IToken {"));
+        assert!(module.contains(
+            "        /// Return types were inferred from the returned data.\n        function name()"
+        ));
+        assert!(module.contains("        // function 0xdeadbeef could not be resolved"));
+    }
+
+    #[test]
+    fn test_abigen_json() {
+        let artifact: serde_json::Value = serde_json::from_str(&build_abigen_json(&abi())).unwrap();
+
+        assert_eq!(artifact["abi"].as_array().unwrap().len(), 2);
+        assert!(artifact["devdoc"]["methods"]["transfer(address,uint256)"]["details"]
+            .as_str()
+            .unwrap()
+            .contains("0xa9059cbb"));
+        assert!(artifact["devdoc"]["details"].as_str().unwrap().contains("0xdeadbeef"));
+    }
+
+    #[test]
+    fn test_typescript_abi() {
+        let abi = build_typescript_abi("IToken", &abi());
+
+        assert!(abi.starts_with("export const ITokenAbi = ["));
+        assert!(abi.contains("\"stateMutability\": \"view\""));
+        assert!(!abi.contains("Unresolved_deadbeef"));
+    }
+}
@@ -0,0 +1,105 @@
+use clap::{AppSettings, Parser};
+use heimdall_cache::delete_cache;
+use heimdall_common::{
+    resources::labels::{LabelFormat, LabelStore},
+    utils::io::{
+        file::read_file,
+        logging::{set_logger_env, Logger},
+    },
+};
+
+use crate::error::Error;
+
+#[derive(Debug, Clone, Parser)]
+#[clap(
+    about = "Manage the labels used to enrich addresses, selectors, and storage slots in output",
+    after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    global_setting = AppSettings::DeriveDisplayOrder,
+    override_usage = "heimdall labels <SUBCOMMAND>"
+)]
+pub struct LabelsArgs {
+    #[clap(subcommand)]
+    pub sub: LabelsSubcommands,
+}
+
+#[derive(Debug, Clone, Parser)]
+#[allow(clippy::large_enum_variant)]
+pub enum LabelsSubcommands {
+    #[clap(name = "import", about = "Import labels into ~/.bifrost/labels.toml")]
+    Import(ImportArgs),
+
+    #[clap(name = "ls", about = "Lists all labels in ~/.bifrost/labels.toml")]
+    Ls(ListArgs),
+}
+
+#[derive(Debug, Clone, Parser)]
+pub struct ImportArgs {
+    /// The file to import labels from.
+    #[clap(required = true)]
+    pub file: String,
+
+    /// The format of the file, one of 'etherscan', 'dune', 'tenderly', or 'toml'. Detected from
+    /// the file if not given.
+    #[clap(long, short, default_value = "", hide_default_value = true)]
+    pub format: String,
+
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+}
+
+#[derive(Debug, Clone, Parser)]
+pub struct ListArgs {}
+
+/// Import labels into, or list the contents of, the local label store.
+pub fn labels(args: LabelsArgs) -> Result<(), Error> {
+    match args.sub {
+        LabelsSubcommands::Import(args) => {
+            set_logger_env(&args.verbose);
+
+            // get a new logger
+            let (logger, _) = Logger::new(match args.verbose.log_level() {
+                Some(level) => level.as_str(),
+                None => "SILENT",
+            });
+
+            let contents = read_file(&args.file);
+            let format = match args.format.is_empty() {
+                true => LabelFormat::detect(&args.file, &contents),
+                false => LabelFormat::from_name(&args.format)
+                    .map_err(|e| Error::GenericError(e.to_string()))?,
+            };
+            logger.debug(&format!("importing '{}' as {:?} labels.", &args.file, format));
+
+            let imported = LabelStore::parse(&contents, format)
+                .map_err(|e| Error::GenericError(format!("failed to import labels: {e}")))?;
+
+            // cached signature lookups would otherwise shadow newly labeled selectors
+            for selector in imported.selectors.keys() {
+                let _ = delete_cache(&format!("selector.{}", selector.trim_start_matches("0x")));
+            }
+
+            let mut store = LabelStore::load().map_err(|e| Error::GenericError(e.to_string()))?;
+            let changed = store.merge(imported);
+            store.save().map_err(|e| Error::GenericError(e.to_string()))?;
+
+            println!("Imported {} labels, {} labels stored.", changed, store.len());
+        }
+        LabelsSubcommands::Ls(_) => {
+            let store = LabelStore::load().map_err(|e| Error::GenericError(e.to_string()))?;
+            println!("Displaying {} labels:", store.len());
+
+            for (kind, labels) in [
+                ("address", &store.addresses),
+                ("selector", &store.selectors),
+                ("slot", &store.slots),
+            ] {
+                for (key, label) in labels {
+                    println!("{kind:>8} : {key} : {label}");
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,240 @@
+pub mod output;
+pub mod util;
+
+use std::{collections::BTreeMap, time::Duration};
+
+use clap::{AppSettings, Parser};
+use derive_builder::Builder;
+use heimdall_common::{
+    ether::{
+        bytecode::get_bytecode_from_target, evm::core::vm::VM, selectors::find_function_selectors,
+    },
+    utils::{
+        io::{
+            crash::enter_pass,
+            exit::record_partial_analysis,
+            logging::{set_logger_env, Logger},
+        },
+        threading::run_with_timeout,
+    },
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    disassemble::{disassemble, DisassemblerArgs},
+    error::Error,
+};
+
+use self::util::{
+    build_write_set, find_collisions, LayoutBuilder, SlotCollision, SlotUsage, SlotWrite,
+};
+
+#[derive(Debug, Clone, Parser, Builder)]
+#[clap(
+    about = "Recover a contract's storage layout, and check a proxy's layout against its implementation",
+    after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    global_setting = AppSettings::DeriveDisplayOrder,
+    override_usage = "heimdall layout <TARGET> [OPTIONS]"
+)]
+pub struct LayoutArgs {
+    /// The target to recover the layout of, either a file, bytecode, contract address, or ENS
+    /// name. With --implementation, the proxy.
+    #[clap(required = true)]
+    pub target: String,
+
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// The RPC provider to use for fetching target bytecode.
+    #[clap(long = "rpc-url", short, default_value = "", hide_default_value = true)]
+    pub rpc_url: String,
+
+    /// The proxy's implementation, either a file, bytecode, contract address, or ENS name. Slots
+    /// used by both, other than the EIP-1967 and EIP-1822 slots, are reported as collisions.
+    #[clap(long, short, default_value = "", hide_default_value = true)]
+    pub implementation: String,
+
+    /// The output directory to write the output to or 'print' to print to the console
+    #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
+    pub output: String,
+
+    /// The name for the output file
+    #[clap(long, short, default_value = "", hide_default_value = true)]
+    pub name: String,
+
+    /// The format to generate, one of 'json' or 'markdown'. Markdown gives the write-set matrix
+    /// of each contract, i.e. which slots each function can write.
+    #[clap(long, short, default_value = "json")]
+    pub format: String,
+
+    /// The timeout for each function's symbolic execution in milliseconds.
+    #[clap(long, short, default_value = "10000", hide_default_value = true)]
+    pub timeout: u64,
+}
+
+impl LayoutArgsBuilder {
+    pub fn new() -> Self {
+        Self {
+            target: Some(String::new()),
+            verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
+            rpc_url: Some(String::new()),
+            implementation: Some(String::new()),
+            output: Some(String::new()),
+            name: Some(String::new()),
+            format: Some(String::from("json")),
+            timeout: Some(10000),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LayoutResult {
+    /// The target's base storage slots
+    pub layout: Vec<SlotUsage>,
+    /// The base slots each of the target's functions can write, by function
+    pub write_set: BTreeMap<String, Vec<SlotWrite>>,
+    /// The implementation's base storage slots, if one was given
+    pub implementation: Option<Vec<SlotUsage>>,
+    /// The base slots each of the implementation's functions can write, if one was given
+    pub implementation_write_set: Option<BTreeMap<String, Vec<SlotWrite>>>,
+    /// Slots used by both the target and its implementation
+    pub collisions: Vec<SlotCollision>,
+}
+
+/// Recover the storage layout of the target, and of its implementation if one is given. When
+/// both are given, the target is treated as a proxy, and any slot which both contracts use is a
+/// collision: the implementation's state would overwrite the proxy's, or vice versa.
+pub async fn layout(args: LayoutArgs) -> Result<LayoutResult, Error> {
+    set_logger_env(&args.verbose);
+
+    // get a new logger
+    let (logger, _) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    if !["json", "markdown"].contains(&args.format.as_str()) {
+        return Err(Error::GenericError(format!(
+            "invalid format '{}' . expected one of: json, markdown.",
+            args.format
+        )))
+    }
+
+    let (layout, functions) = recover_layout(&args.target, &args).await?;
+    let write_set = build_write_set(&layout, &functions);
+    logger.info(&format!("recovered {} storage slots.", layout.len()));
+
+    if args.implementation.is_empty() {
+        return Ok(LayoutResult {
+            layout,
+            write_set,
+            implementation: None,
+            implementation_write_set: None,
+            collisions: Vec::new(),
+        })
+    }
+
+    let (implementation, implementation_functions) =
+        recover_layout(&args.implementation, &args).await?;
+    let implementation_write_set = build_write_set(&implementation, &implementation_functions);
+    logger.info(&format!("recovered {} implementation storage slots.", implementation.len()));
+
+    let collisions = find_collisions(&layout, &implementation);
+    for collision in &collisions {
+        logger.warn(&format!(
+            "storage slot {:#x} is used by both the proxy and its implementation.",
+            collision.slot
+        ));
+    }
+
+    Ok(LayoutResult {
+        layout,
+        write_set,
+        implementation: Some(implementation),
+        implementation_write_set: Some(implementation_write_set),
+        collisions,
+    })
+}
+
+/// Recover the base storage slots of a single contract, along with its functions. Each function
+/// is symbolically executed, along with the whole contract, which also covers code outside of any
+/// function such as a proxy's fallback.
+async fn recover_layout(
+    target: &str,
+    args: &LayoutArgs,
+) -> Result<(Vec<SlotUsage>, Vec<String>), Error> {
+    let contract_bytecode = get_bytecode_from_target(target, &args.rpc_url)
+        .await
+        .map_err(|e| Error::GenericError(e.to_string()))?;
+
+    enter_pass("disassemble");
+    let disassembled_bytecode = disassemble(DisassemblerArgs {
+        target: contract_bytecode.clone(),
+        verbose: args.verbose.clone(),
+        rpc_url: args.rpc_url.clone(),
+        decimal_counter: false,
+        format: String::from("asm"),
+        verify: false,
+        name: String::new(),
+        output: String::new(),
+    })
+    .await
+    .map_err(|e| Error::GenericError(format!("failed to disassemble bytecode: {e}")))?;
+
+    let evm = VM::new(
+        contract_bytecode,
+        String::from("0x"),
+        String::from("0x6865696d64616c6c000000000061646472657373"),
+        String::from("0x6865696d64616c6c0000000000006f726967696e"),
+        String::from("0x6865696d64616c6c00000000000063616c6c6572"),
+        0,
+        u128::MAX,
+    );
+
+    let mut selectors = find_function_selectors(&evm, &disassembled_bytecode)
+        .into_iter()
+        .collect::<Vec<(String, u128)>>();
+    selectors.sort();
+
+    let mut functions = selectors
+        .iter()
+        .map(|(selector, _)| format!("0x{selector}"))
+        .chain([String::from("fallback")])
+        .collect::<Vec<String>>();
+    let mut builder = LayoutBuilder::new();
+    for (selector, entry_point) in selectors {
+        enter_pass(&format!("symbolic execution (0x{selector})"));
+        let mut evm_clone = evm.clone();
+        let selector_clone = selector.clone();
+        match run_with_timeout(
+            move || evm_clone.symbolic_exec_selector(&selector_clone, entry_point),
+            Duration::from_millis(args.timeout),
+        ) {
+            Some((trace, _)) => builder.add_trace(&trace, &format!("0x{selector}")),
+            None => record_partial_analysis(),
+        }
+    }
+
+    enter_pass("symbolic execution (fallback)");
+    let evm_clone = evm.clone();
+    match run_with_timeout(move || evm_clone.symbolic_exec(), Duration::from_millis(args.timeout)) {
+        Some((trace, _)) => {
+            let mut contract_wide = LayoutBuilder::new();
+            contract_wide.add_trace(&trace, "fallback");
+            builder.add_unattributed(contract_wide, "fallback");
+        }
+        None => record_partial_analysis(),
+    }
+
+    // the fallback is only listed if it accesses storage, or if there are no other functions
+    let layout = builder.build();
+    let fallback_accesses = layout
+        .iter()
+        .any(|usage| usage.reads.contains("fallback") || usage.writes.contains("fallback"));
+    if !fallback_accesses && functions.len() > 1 {
+        functions.pop();
+    }
+
+    Ok((layout, functions))
+}
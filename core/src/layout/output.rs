@@ -0,0 +1,138 @@
+use std::collections::BTreeMap;
+
+use heimdall_common::resources::labels::get_selector_label;
+
+use super::{
+    util::{SlotKind, SlotUsage, SlotWrite},
+    LayoutResult,
+};
+
+/// Write the layout as markdown, with the write-set matrix of the target and of its
+/// implementation, if one was given.
+pub fn build_markdown(result: &LayoutResult) -> String {
+    let mut output = vec![String::from("# Storage write set")];
+
+    match (&result.implementation, &result.implementation_write_set) {
+        (Some(implementation), Some(implementation_write_set)) => {
+            output.push(String::new());
+            output.push(String::from("## Proxy"));
+            output.extend(write_set_matrix(&result.layout, &result.write_set));
+            output.push(String::new());
+            output.push(String::from("## Implementation"));
+            output.extend(write_set_matrix(implementation, implementation_write_set));
+        }
+        _ => output.extend(write_set_matrix(&result.layout, &result.write_set)),
+    }
+
+    output.join("\n")
+}
+
+/// A table with a row for each function and a column for each slot any function writes, marking
+/// the slots each function can write.
+fn write_set_matrix(
+    layout: &[SlotUsage],
+    write_set: &BTreeMap<String, Vec<SlotWrite>>,
+) -> Vec<String> {
+    let written =
+        layout.iter().filter(|usage| !usage.writes.is_empty()).collect::<Vec<&SlotUsage>>();
+    if written.is_empty() {
+        return vec![String::new(), String::from("No function writes to storage.")]
+    }
+
+    let mut output = vec![
+        String::new(),
+        format!(
+            "| function | {} |",
+            written.iter().map(|usage| column_name(usage)).collect::<Vec<String>>().join(" | ")
+        ),
+        format!("| --- |{}", " :-: |".repeat(written.len())),
+    ];
+    for (function, writes) in write_set {
+        let name = match get_selector_label(function) {
+            Some(label) => format!("`{label}` ({function})"),
+            None => format!("`{function}`"),
+        };
+        let cells = written
+            .iter()
+            .map(|usage| match writes.iter().any(|write| write.slot == usage.slot) {
+                true => "W",
+                false => "",
+            })
+            .collect::<Vec<&str>>();
+        output.push(format!("| {name} | {} |", cells.join(" | ")));
+    }
+
+    output
+}
+
+/// The column heading for a slot, e.g. `mapping 0x1` or `slot 0x0 (owner)`
+fn column_name(usage: &SlotUsage) -> String {
+    let kind = match usage.kind {
+        SlotKind::Value => "slot",
+        SlotKind::Mapping => "mapping",
+        SlotKind::Array => "array",
+    };
+    match &usage.label {
+        Some(label) => format!("{kind} {:#x} ({label})", usage.slot),
+        None => format!("{kind} {:#x}", usage.slot),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use ethers::types::U256;
+
+    use crate::layout::{output::*, util::build_write_set};
+
+    #[test]
+    fn test_build_markdown() {
+        let layout = vec![
+            SlotUsage {
+                slot: U256::zero(),
+                kind: SlotKind::Value,
+                label: None,
+                reads: BTreeSet::from([String::from("0x00000001")]),
+                writes: BTreeSet::new(),
+            },
+            SlotUsage {
+                slot: U256::from(1),
+                kind: SlotKind::Mapping,
+                label: None,
+                reads: BTreeSet::new(),
+                writes: BTreeSet::from([String::from("0x00000002")]),
+            },
+        ];
+        let write_set =
+            build_write_set(&layout, &[String::from("0x00000001"), String::from("0x00000002")]);
+        let result = LayoutResult {
+            layout,
+            write_set,
+            implementation: None,
+            implementation_write_set: None,
+            collisions: Vec::new(),
+        };
+
+        assert_eq!(
+            build_markdown(&result),
+            "# Storage write set\n\n| function | mapping 0x1 |\n| --- | :-: |\n| `0x00000001` |  |\n| `0x00000002` | W |"
+        );
+    }
+
+    #[test]
+    fn test_build_markdown_without_writes() {
+        let result = LayoutResult {
+            layout: Vec::new(),
+            write_set: BTreeMap::new(),
+            implementation: None,
+            implementation_write_set: None,
+            collisions: Vec::new(),
+        };
+
+        assert_eq!(
+            build_markdown(&result),
+            "# Storage write set\n\nNo function writes to storage."
+        );
+    }
+}
@@ -0,0 +1,310 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use ethers::types::U256;
+use heimdall_common::{
+    ether::evm::ext::exec::VMTrace, resources::labels::get_slot_label, utils::strings::encode_hex,
+};
+use serde::{Deserialize, Serialize};
+
+/// Slots reserved by proxy standards, which are expected to be used by both a proxy and its
+/// implementation:
+/// - EIP-1967 `implementation`, `admin`, and `beacon`, i.e. `keccak256("eip1967.proxy.*") - 1`
+/// - EIP-1822 `PROXIABLE`, i.e. `keccak256("PROXIABLE")`
+const STANDARD_SLOTS: [&str; 4] = [
+    "360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc",
+    "b53127684a568b3173ae13b9f8a6016e243e63b6e8ee1178d6a717850b5d6103",
+    "a3f0ad74e5423aebfd80d3ef4346578335a9a72aeaee59ff6cb3582b35133d50",
+    "c5f16f0fcc639fa48a6947836d9850f504798523bf8c9a3a87d5876cf622bcf7",
+];
+
+/// The largest offset from a hashed slot which is still considered part of it, e.g. a struct
+/// member within a mapping value.
+const MAX_SLOT_OFFSET: u64 = 256;
+
+/// How a base slot is used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SlotKind {
+    /// The slot holds a value directly
+    Value,
+    /// The slot is the base of a mapping, i.e. accessed slots are `keccak256(key . slot)`
+    Mapping,
+    /// The slot is the base of a dynamic array, i.e. accessed slots are `keccak256(slot) + i`
+    Array,
+}
+
+/// A base storage slot, and the functions which read and write it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlotUsage {
+    pub slot: U256,
+    pub kind: SlotKind,
+    /// The stored label for the slot, if any
+    pub label: Option<String>,
+    pub reads: BTreeSet<String>,
+    pub writes: BTreeSet<String>,
+}
+
+/// A slot which both the proxy and its implementation use.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlotCollision {
+    pub slot: U256,
+    pub proxy: SlotUsage,
+    pub implementation: SlotUsage,
+}
+
+/// A base slot which a function can write. For mappings and arrays, this covers every slot in
+/// the family, i.e. every key or index.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlotWrite {
+    pub slot: U256,
+    pub kind: SlotKind,
+    /// The stored label for the slot, if any
+    pub label: Option<String>,
+}
+
+/// Accumulates the storage layout of a contract from its traces.
+#[derive(Debug, Clone, Default)]
+pub struct LayoutBuilder {
+    /// The preimage of every hash computed by SHA3
+    preimages: HashMap<U256, Vec<u8>>,
+    slots: BTreeMap<U256, SlotUsage>,
+}
+
+impl LayoutBuilder {
+    pub fn new() -> LayoutBuilder {
+        LayoutBuilder::default()
+    }
+
+    /// Record every storage access in the trace as being made by `function`.
+    pub fn add_trace(&mut self, trace: &VMTrace, function: &str) {
+        for operation in &trace.operations {
+            let instruction = &operation.last_instruction;
+            match instruction.opcode {
+                // SHA3
+                0x20 => {
+                    let (Some(offset), Some(size), Some(hash)) = (
+                        instruction.inputs.first(),
+                        instruction.inputs.get(1),
+                        instruction.outputs.first(),
+                    ) else {
+                        continue
+                    };
+                    if let (Ok(offset), Ok(size)) =
+                        (usize::try_from(*offset), usize::try_from(*size))
+                    {
+                        self.preimages.insert(*hash, operation.memory.read(offset, size));
+                    }
+                }
+                // SLOAD, SSTORE
+                0x54 | 0x55 => {
+                    if let Some(key) = instruction.inputs.first() {
+                        self.add_access(*key, function, instruction.opcode == 0x55);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for child in &trace.children {
+            self.add_trace(child, function);
+        }
+    }
+
+    /// Add the accesses recorded by `other` which this layout doesn't already attribute to some
+    /// function, as made by `function`. A contract-wide trace also passes through every function,
+    /// so this attributes only the accesses no function made, e.g. a proxy's fallback.
+    pub fn add_unattributed(&mut self, other: LayoutBuilder, function: &str) {
+        for (slot, usage) in other.slots {
+            let existing = self.slots.get(&slot);
+            let read = !usage.reads.is_empty() && existing.is_none_or(|e| e.reads.is_empty());
+            let write = !usage.writes.is_empty() && existing.is_none_or(|e| e.writes.is_empty());
+            if read {
+                self.add_base_access(slot, usage.kind, function, false);
+            }
+            if write {
+                self.add_base_access(slot, usage.kind, function, true);
+            }
+        }
+    }
+
+    fn add_access(&mut self, key: U256, function: &str, write: bool) {
+        let (slot, kind) = self.base_slot(key).unwrap_or((key, SlotKind::Value));
+        self.add_base_access(slot, kind, function, write);
+    }
+
+    fn add_base_access(&mut self, slot: U256, kind: SlotKind, function: &str, write: bool) {
+        let usage = self.slots.entry(slot).or_insert_with(|| SlotUsage {
+            slot,
+            kind,
+            label: get_slot_label(&format!("{slot:#x}")),
+            reads: BTreeSet::new(),
+            writes: BTreeSet::new(),
+        });
+
+        // the length of a dynamic array is stored at its base slot, so a hashed access takes
+        // precedence over a direct one
+        if usage.kind == SlotKind::Value {
+            usage.kind = kind;
+        }
+        match write {
+            true => usage.writes.insert(function.to_string()),
+            false => usage.reads.insert(function.to_string()),
+        };
+    }
+
+    /// The base slot and kind of a hashed key, following nested mappings and arrays back to the
+    /// slot they're declared at. Returns `None` if the key isn't derived from a hash.
+    fn base_slot(&self, key: U256) -> Option<(U256, SlotKind)> {
+        let preimage = (0..MAX_SLOT_OFFSET).find_map(|offset| {
+            key.checked_sub(U256::from(offset)).and_then(|hash| self.preimages.get(&hash))
+        })?;
+
+        let (base, kind) = match preimage.len() {
+            64 => (U256::from_big_endian(&preimage[32..]), SlotKind::Mapping),
+            32 => (U256::from_big_endian(preimage), SlotKind::Array),
+            _ => return None,
+        };
+        Some(self.base_slot(base).unwrap_or((base, kind)))
+    }
+
+    /// The recovered layout, ordered by slot.
+    pub fn build(self) -> Vec<SlotUsage> {
+        self.slots.into_values().collect()
+    }
+}
+
+/// The base slots each function can write, i.e. the layout inverted. Every function is listed,
+/// including those which write nothing.
+pub fn build_write_set(
+    layout: &[SlotUsage],
+    functions: &[String],
+) -> BTreeMap<String, Vec<SlotWrite>> {
+    functions
+        .iter()
+        .map(|function| {
+            let writes = layout
+                .iter()
+                .filter(|usage| usage.writes.contains(function))
+                .map(|usage| SlotWrite {
+                    slot: usage.slot,
+                    kind: usage.kind,
+                    label: usage.label.clone(),
+                })
+                .collect();
+            (function.clone(), writes)
+        })
+        .collect()
+}
+
+/// Whether a slot is reserved by a proxy standard.
+pub fn is_standard_slot(slot: &U256) -> bool {
+    let mut bytes = [0u8; 32];
+    slot.to_big_endian(&mut bytes);
+    STANDARD_SLOTS.contains(&encode_hex(bytes.to_vec()).as_str())
+}
+
+/// The base slots which both the proxy and implementation use, other than the standard proxy
+/// slots.
+pub fn find_collisions(proxy: &[SlotUsage], implementation: &[SlotUsage]) -> Vec<SlotCollision> {
+    proxy
+        .iter()
+        .filter(|usage| !is_standard_slot(&usage.slot))
+        .filter_map(|proxy_usage| {
+            implementation.iter().find(|usage| usage.slot == proxy_usage.slot).map(
+                |implementation_usage| SlotCollision {
+                    slot: proxy_usage.slot,
+                    proxy: proxy_usage.clone(),
+                    implementation: implementation_usage.clone(),
+                },
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::layout::util::*;
+
+    fn usage(slot: U256, function: &str) -> SlotUsage {
+        SlotUsage {
+            slot,
+            kind: SlotKind::Value,
+            label: None,
+            reads: BTreeSet::new(),
+            writes: BTreeSet::from([function.to_string()]),
+        }
+    }
+
+    #[test]
+    fn test_standard_slots_do_not_collide() {
+        let implementation_slot = U256::from_str_radix(STANDARD_SLOTS[0], 16).unwrap();
+        let proxy = vec![usage(U256::zero(), "fallback"), usage(implementation_slot, "fallback")];
+        let implementation =
+            vec![usage(U256::zero(), "0x8129fc1c"), usage(implementation_slot, "0x3659cfe6")];
+
+        let collisions = find_collisions(&proxy, &implementation);
+
+        assert_eq!(collisions.len(), 1);
+        assert_eq!(collisions[0].slot, U256::zero());
+        assert!(is_standard_slot(&implementation_slot));
+    }
+
+    #[test]
+    fn test_nested_mapping_resolves_to_declared_slot() {
+        let mut builder = LayoutBuilder::new();
+
+        // keccak256(key . 3), then keccak256(key . keccak256(key . 3))
+        let outer = U256::from(0x1111);
+        let mut preimage = vec![0u8; 64];
+        U256::from(3).to_big_endian(&mut preimage[32..]);
+        builder.preimages.insert(outer, preimage);
+        let inner = U256::from(0x2222);
+        let mut preimage = vec![0u8; 64];
+        outer.to_big_endian(&mut preimage[32..]);
+        builder.preimages.insert(inner, preimage);
+
+        // a struct member of the inner mapping's value
+        builder.add_access(inner + 1, "0xa9059cbb", true);
+        builder.add_access(U256::from(4), "0xa9059cbb", false);
+
+        let layout = builder.build();
+        assert_eq!(layout.len(), 2);
+        assert_eq!(layout[0].slot, U256::from(3));
+        assert_eq!(layout[0].kind, SlotKind::Mapping);
+        assert_eq!(layout[1].kind, SlotKind::Value);
+    }
+
+    #[test]
+    fn test_write_set_lists_every_function() {
+        let layout = vec![
+            usage(U256::zero(), "0x8129fc1c"),
+            SlotUsage { kind: SlotKind::Mapping, ..usage(U256::from(1), "0xa9059cbb") },
+        ];
+        let functions = [String::from("0x70a08231"), String::from("0xa9059cbb")];
+
+        let write_set = build_write_set(&layout, &functions);
+
+        // functions which weren't found, such as 0x8129fc1c, aren't listed
+        assert_eq!(write_set.len(), 2);
+        assert!(write_set["0x70a08231"].is_empty());
+        assert_eq!(
+            write_set["0xa9059cbb"],
+            vec![SlotWrite { slot: U256::from(1), kind: SlotKind::Mapping, label: None }]
+        );
+    }
+
+    #[test]
+    fn test_contract_wide_accesses_only_fill_gaps() {
+        let mut builder = LayoutBuilder::new();
+        builder.add_access(U256::zero(), "0x8da5cb5b", false);
+
+        let mut contract_wide = LayoutBuilder::new();
+        contract_wide.add_access(U256::zero(), "fallback", false);
+        contract_wide.add_access(U256::from(1), "fallback", true);
+        builder.add_unattributed(contract_wide, "fallback");
+
+        let layout = builder.build();
+        assert_eq!(layout[0].reads, BTreeSet::from([String::from("0x8da5cb5b")]));
+        assert_eq!(layout[1].writes, BTreeSet::from([String::from("fallback")]));
+    }
+}
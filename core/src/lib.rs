@@ -1,8 +1,32 @@
+pub mod address;
+pub mod asm;
+pub mod calls;
 pub mod cfg;
+pub mod collide;
+pub mod debug;
 pub mod decode;
 pub mod decompile;
+pub mod diff;
 pub mod disassemble;
 pub mod dump;
 pub mod error;
+pub mod evaluate;
+pub mod fingerprint;
 pub mod inspect;
+pub mod interface;
+pub mod labels;
+pub mod layout;
+pub mod lineage;
+pub mod locate;
+pub mod map;
+pub mod metrics;
+pub mod minimize;
+pub mod predeploy;
+pub mod query;
+pub mod roles;
+pub mod scan;
+pub mod simulate;
 pub mod snapshot;
+pub mod stats;
+pub mod storage_layout;
+pub mod usage;
@@ -0,0 +1,293 @@
+pub mod output;
+pub mod util;
+
+use clap::{AppSettings, Parser};
+use derive_builder::Builder;
+use ethers::types::{Address, H256};
+use heimdall_common::{
+    constants::ADDRESS_REGEX,
+    ether::{
+        explorer::{Deployment, ExplorerClient},
+        rpc::{get_block_number, get_code, get_storage_at, get_transaction},
+    },
+    resources::labels::get_address_label,
+    utils::io::{
+        crash::enter_pass,
+        exit::{record_failure, ExitStatus},
+        file::read_file,
+        logging::{set_logger_env, Logger},
+    },
+};
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+
+use self::util::{
+    parse_index, parse_upgrades, LineageContract, IMPLEMENTATION_SLOT, UPGRADED_TOPIC,
+};
+
+#[derive(Debug, Clone, Parser, Builder)]
+#[clap(
+    about = "Trace who deployed a contract, what else they deployed, and a proxy's past implementations",
+    after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    global_setting = AppSettings::DeriveDisplayOrder,
+    override_usage = "heimdall lineage <TARGET> [OPTIONS]"
+)]
+pub struct LineageArgs {
+    /// The address of the contract to trace the lineage of.
+    #[clap(required = true)]
+    pub target: String,
+
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// The RPC provider to use. The block explorer for its chain is used to find deployments.
+    #[clap(long = "rpc-url", short, default_value = "", hide_default_value = true)]
+    pub rpc_url: String,
+
+    /// A local index of deployments to search before the block explorer, as a JSON array of
+    /// `{creator, address, transaction_hash, block_number}` objects.
+    #[clap(long, short, default_value = "", hide_default_value = true)]
+    pub index: String,
+
+    /// The format to generate, one of 'json' or 'markdown'.
+    #[clap(long, short, default_value = "markdown")]
+    pub format: String,
+
+    /// The output directory to write the output to or 'print' to print to the console
+    #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
+    pub output: String,
+
+    /// The name for the output file
+    #[clap(long, short, default_value = "", hide_default_value = true)]
+    pub name: String,
+}
+
+impl LineageArgsBuilder {
+    pub fn new() -> Self {
+        Self {
+            target: Some(String::new()),
+            verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
+            rpc_url: Some(String::new()),
+            index: Some(String::new()),
+            format: Some(String::from("markdown")),
+            output: Some(String::new()),
+            name: Some(String::new()),
+        }
+    }
+}
+
+/// The account which deployed the target.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Deployer {
+    pub address: String,
+    pub label: Option<String>,
+    /// Whether the deployer is a contract, i.e. the target was deployed by a factory
+    pub is_factory: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LineageResult {
+    /// The target, with the transaction which deployed it
+    pub target: LineageContract,
+    pub deployer: Option<Deployer>,
+    /// The deployer's other deployments, oldest first
+    pub deployments: Vec<LineageContract>,
+    /// The implementations the target has been upgraded to, if it's a proxy, oldest first
+    pub implementations: Vec<LineageContract>,
+}
+
+impl LineageResult {
+    /// Every labeled address in the lineage, which may attribute the target to a known team or
+    /// attacker.
+    pub fn associates(&self) -> Vec<(String, String)> {
+        let deployer = self
+            .deployer
+            .iter()
+            .filter_map(|deployer| Some((deployer.address.clone(), deployer.label.clone()?)));
+        let contracts = self
+            .deployments
+            .iter()
+            .chain(&self.implementations)
+            .filter_map(|contract| Some((contract.address.clone(), contract.label.clone()?)));
+
+        let mut associates = Vec::new();
+        for associate in deployer.chain(contracts) {
+            if !associates.contains(&associate) {
+                associates.push(associate);
+            }
+        }
+        associates
+    }
+}
+
+/// Trace the lineage of the target: who deployed it, what else they deployed, and, for an
+/// EIP-1967 proxy, the implementations it has been upgraded to. Deployments are looked up in
+/// the local `--index` first, then the block explorer for the RPC provider's chain.
+pub async fn lineage(args: LineageArgs) -> Result<LineageResult, Error> {
+    set_logger_env(&args.verbose);
+
+    // get a new logger
+    let (logger, _) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    // check the format before tracing, so an invalid one fails fast
+    if !["json", "markdown"].contains(&args.format.as_str()) {
+        return Err(Error::GenericError(format!(
+            "invalid format '{}' . expected one of: json, markdown.",
+            args.format
+        )))
+    }
+
+    // deployments are indexed by address, so only an address can be traced
+    let target = match ADDRESS_REGEX.is_match(&args.target).unwrap_or(false) {
+        true => format!(
+            "{:#x}",
+            args.target.parse::<Address>().map_err(|e| {
+                record_failure(ExitStatus::InvalidArguments);
+                Error::GenericError(format!("invalid target address: {e}"))
+            })?
+        ),
+        false => {
+            record_failure(ExitStatus::InvalidArguments);
+            return Err(Error::GenericError(format!(
+                "invalid target '{}' . lineage requires a contract address.",
+                args.target
+            )))
+        }
+    };
+
+    let index = match args.index.is_empty() {
+        true => Vec::new(),
+        false => parse_index(&read_file(&args.index))
+            .map_err(|e| Error::GenericError(format!("failed to parse index: {e}")))?,
+    };
+    let explorer = match args.rpc_url.is_empty() {
+        true => None,
+        false => ExplorerClient::from_rpc_url(&args.rpc_url).await,
+    };
+    if explorer.is_none() {
+        if index.is_empty() {
+            return Err(Error::GenericError(String::from(
+                "tracing lineage requires either a block explorer for the RPC provider's chain, or a local --index .",
+            )))
+        }
+        logger.warn("no block explorer is known for this chain, so only the local index is used.");
+    }
+
+    // find the deployer
+    enter_pass("deployer");
+    let mut creation = index.iter().find(|deployment| deployment.address == target).cloned();
+    if let (None, Some(explorer)) = (&creation, &explorer) {
+        creation = match explorer
+            .get_contract_creation(&target)
+            .await
+            .map_err(|e| Error::GenericError(e.to_string()))?
+        {
+            Some(explorer_creation) => {
+                // the explorer doesn't report the block, so it's read from the transaction
+                let block_number =
+                    get_transaction(&explorer_creation.transaction_hash, &args.rpc_url)
+                        .await
+                        .ok()
+                        .and_then(|transaction| transaction.block_number)
+                        .map(|block_number| block_number.as_u64());
+                Some(Deployment {
+                    creator: explorer_creation.creator.to_lowercase(),
+                    address: target.clone(),
+                    transaction_hash: explorer_creation.transaction_hash,
+                    block_number,
+                })
+            }
+            None => None,
+        };
+    }
+
+    let target_contract = match &creation {
+        Some(creation) => LineageContract::from(creation),
+        None => {
+            logger.warn(&format!("couldn't find the deployer of '{target}' ."));
+            LineageContract::new(&target, None, None)
+        }
+    };
+
+    let deployer = match &creation {
+        Some(creation) => Some(Deployer {
+            address: creation.creator.clone(),
+            label: get_address_label(&creation.creator),
+            is_factory: !args.rpc_url.is_empty() &&
+                !get_code(&creation.creator, &args.rpc_url)
+                    .await
+                    .map_err(|e| Error::GenericError(e.to_string()))?
+                    .is_empty(),
+        }),
+        None => None,
+    };
+
+    // find the deployer's other deployments
+    enter_pass("deployments");
+    let mut deployments = Vec::new();
+    if let Some(deployer) = &deployer {
+        let mut found = index
+            .iter()
+            .filter(|deployment| deployment.creator == deployer.address)
+            .cloned()
+            .collect::<Vec<Deployment>>();
+        if let Some(explorer) = &explorer {
+            found.extend(
+                explorer
+                    .get_deployments(&deployer.address)
+                    .await
+                    .map_err(|e| Error::GenericError(e.to_string()))?,
+            );
+        }
+
+        for deployment in &found {
+            if deployment.address != target &&
+                !deployments
+                    .iter()
+                    .any(|contract: &LineageContract| contract.address == deployment.address)
+            {
+                deployments.push(LineageContract::from(deployment));
+            }
+        }
+        deployments.sort_by_key(|contract| contract.block_number);
+        logger.info(&format!("found {} other deployments by the deployer.", deployments.len()));
+    }
+
+    // find the implementations behind the target, if it's a proxy
+    enter_pass("implementations");
+    let mut implementations = match &explorer {
+        Some(explorer) => parse_upgrades(
+            &explorer
+                .get_logs(&target, UPGRADED_TOPIC)
+                .await
+                .map_err(|e| Error::GenericError(e.to_string()))?,
+        ),
+        None => Vec::new(),
+    };
+
+    // a proxy which was initialized in its constructor may never have emitted `Upgraded`, so the
+    // current implementation is also read from its slot
+    if !args.rpc_url.is_empty() {
+        let block = get_block_number(&args.rpc_url)
+            .await
+            .map_err(|e| Error::GenericError(e.to_string()))?;
+        let slot =
+            IMPLEMENTATION_SLOT.parse::<H256>().expect("IMPLEMENTATION_SLOT is a valid slot");
+        let implementation = get_storage_at(&target, slot, block, &args.rpc_url)
+            .await
+            .map_err(|e| Error::GenericError(e.to_string()))?;
+        let implementation = format!("{:#x}", Address::from(implementation));
+        if implementation != format!("{:#x}", Address::zero()) &&
+            implementations.last().map(|contract| &contract.address) != Some(&implementation)
+        {
+            implementations.push(LineageContract::new(&implementation, None, None));
+        }
+    }
+
+    Ok(LineageResult { target: target_contract, deployer, deployments, implementations })
+}
@@ -0,0 +1,112 @@
+use super::{util::LineageContract, LineageResult};
+
+/// A contract's address, with its label if it has one.
+fn name(address: &str, label: &Option<String>) -> String {
+    match label {
+        Some(label) => format!("`{address}` ({label})"),
+        None => format!("`{address}`"),
+    }
+}
+
+/// Where and when a contract was deployed or upgraded to, e.g. `block 100, 0x01`.
+fn provenance(contract: &LineageContract) -> String {
+    let mut provenance = Vec::new();
+    if let Some(block_number) = contract.block_number {
+        provenance.push(format!("block {block_number}"));
+    }
+    if let Some(transaction_hash) = &contract.transaction_hash {
+        provenance.push(format!("`{transaction_hash}`"));
+    }
+    match provenance.is_empty() {
+        true => String::from("unknown"),
+        false => provenance.join(", "),
+    }
+}
+
+/// Write the lineage as a markdown report.
+pub fn build_markdown(result: &LineageResult) -> String {
+    let mut output = vec![
+        format!("# Lineage of {}", name(&result.target.address, &result.target.label)),
+        String::new(),
+    ];
+
+    match &result.deployer {
+        Some(deployer) => {
+            output.push(format!(
+                "Deployed by {}{} at {}.",
+                if deployer.is_factory { "the factory " } else { "" },
+                name(&deployer.address, &deployer.label),
+                provenance(&result.target)
+            ));
+        }
+        None => output.push(String::from("The deployer couldn't be found.")),
+    }
+
+    let associates = result.associates();
+    if !associates.is_empty() {
+        output.push(String::new());
+        output.push(String::from("## Known associates"));
+        output.push(String::new());
+        output.extend(associates.iter().map(|(address, label)| format!("- `{address}`: {label}")));
+    }
+
+    for (title, contracts) in [
+        ("Other deployments by the deployer", &result.deployments),
+        ("Implementations", &result.implementations),
+    ] {
+        if contracts.is_empty() {
+            continue
+        }
+        output.push(String::new());
+        output.push(format!("## {title}"));
+        output.push(String::new());
+        output.extend(contracts.iter().map(|contract| {
+            format!("- {} at {}", name(&contract.address, &contract.label), provenance(contract))
+        }));
+    }
+
+    output.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lineage::{output::*, Deployer};
+
+    #[test]
+    fn test_build_markdown() {
+        let contract = |address: &str, label: Option<&str>| LineageContract {
+            address: address.to_string(),
+            label: label.map(|label| label.to_string()),
+            transaction_hash: Some(String::from("0x01")),
+            block_number: Some(100),
+        };
+        let result = LineageResult {
+            target: contract("0xaa", None),
+            deployer: Some(Deployer {
+                address: String::from("0xbb"),
+                label: Some(String::from("Exploiter 1")),
+                is_factory: false,
+            }),
+            deployments: vec![contract("0xcc", None)],
+            implementations: Vec::new(),
+        };
+
+        assert_eq!(
+            build_markdown(&result),
+            [
+                "# Lineage of `0xaa`",
+                "",
+                "Deployed by `0xbb` (Exploiter 1) at block 100, `0x01`.",
+                "",
+                "## Known associates",
+                "",
+                "- `0xbb`: Exploiter 1",
+                "",
+                "## Other deployments by the deployer",
+                "",
+                "- `0xcc` at block 100, `0x01`",
+            ]
+            .join("\n")
+        );
+    }
+}
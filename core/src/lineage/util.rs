@@ -0,0 +1,113 @@
+use ethers::types::Address;
+use heimdall_common::{
+    ether::explorer::{Deployment, ExplorerLog},
+    resources::labels::get_address_label,
+};
+use serde::{Deserialize, Serialize};
+
+/// `Upgraded(address indexed implementation)`, emitted by EIP-1967 proxies on every upgrade.
+pub const UPGRADED_TOPIC: &str =
+    "0xbc7cd75a20ee27fd9adebab32041f755214dbc6bffa90cc0225b39da2e5c2d3b";
+
+/// The EIP-1967 implementation slot, i.e. `keccak256("eip1967.proxy.implementation") - 1`.
+pub const IMPLEMENTATION_SLOT: &str =
+    "0x360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc";
+
+/// A contract in the target's lineage.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LineageContract {
+    pub address: String,
+    /// The address's label, if it's known
+    pub label: Option<String>,
+    /// The transaction which deployed the contract, or which upgraded a proxy to it
+    pub transaction_hash: Option<String>,
+    pub block_number: Option<u64>,
+}
+
+impl LineageContract {
+    pub fn new(
+        address: &str,
+        transaction_hash: Option<String>,
+        block_number: Option<u64>,
+    ) -> LineageContract {
+        LineageContract {
+            address: address.to_lowercase(),
+            label: get_address_label(address),
+            transaction_hash,
+            block_number,
+        }
+    }
+}
+
+impl From<&Deployment> for LineageContract {
+    fn from(deployment: &Deployment) -> LineageContract {
+        LineageContract::new(
+            &deployment.address,
+            Some(deployment.transaction_hash.clone()),
+            deployment.block_number,
+        )
+    }
+}
+
+/// Parse a local index of deployments, which is a JSON array of `{creator, address,
+/// transaction_hash, block_number}` objects, e.g. exported from a self-hosted indexer.
+pub fn parse_index(contents: &str) -> Result<Vec<Deployment>, serde_json::Error> {
+    let mut deployments = serde_json::from_str::<Vec<Deployment>>(contents)?;
+    for deployment in deployments.iter_mut() {
+        deployment.creator = deployment.creator.to_lowercase();
+        deployment.address = deployment.address.to_lowercase();
+    }
+    Ok(deployments)
+}
+
+/// The implementations a proxy was upgraded to, oldest first, from its `Upgraded` events.
+pub fn parse_upgrades(logs: &[ExplorerLog]) -> Vec<LineageContract> {
+    logs.iter()
+        .filter_map(|log| {
+            let topic = log.topics.get(1)?;
+            let implementation = topic.strip_prefix("0x").unwrap_or(topic).get(24..)?;
+            let implementation = format!("0x{implementation}").parse::<Address>().ok()?;
+            Some(LineageContract::new(
+                &format!("{implementation:#x}"),
+                Some(log.transaction_hash.clone()),
+                log.block_number,
+            ))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lineage::util::*;
+
+    #[test]
+    fn test_parse_index() {
+        let deployments = parse_index(
+            r#"[{"creator": "0x00000000000000000000000000000000000000AA", "address": "0x00000000000000000000000000000000000000BB", "transaction_hash": "0x01", "block_number": 1}]"#,
+        )
+        .unwrap();
+
+        assert_eq!(deployments.len(), 1);
+        assert_eq!(deployments[0].creator, "0x00000000000000000000000000000000000000aa");
+        assert_eq!(deployments[0].address, "0x00000000000000000000000000000000000000bb");
+        assert!(parse_index("{}").is_err());
+    }
+
+    #[test]
+    fn test_parse_upgrades() {
+        let log = ExplorerLog {
+            topics: vec![
+                UPGRADED_TOPIC.to_string(),
+                "0x000000000000000000000000000000000000000000000000000000000000abcd".to_string(),
+            ],
+            data: String::from("0x"),
+            block_number: Some(10),
+            transaction_hash: String::from("0x01"),
+        };
+
+        let upgrades = parse_upgrades(&[log]);
+        assert_eq!(upgrades.len(), 1);
+        assert_eq!(upgrades[0].address, "0x000000000000000000000000000000000000abcd");
+        assert_eq!(upgrades[0].block_number, Some(10));
+    }
+}
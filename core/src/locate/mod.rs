@@ -0,0 +1,407 @@
+use std::{collections::HashMap, time::Duration};
+
+use clap::{AppSettings, Parser};
+use derive_builder::Builder;
+use heimdall_common::{
+    ether::{
+        bytecode::get_bytecode_from_target,
+        evm::{
+            core::{opcodes::Opcode, vm::VM},
+            ext::exec::VMTrace,
+        },
+        selectors::find_function_selectors,
+    },
+    utils::{
+        io::{
+            crash::enter_pass,
+            exit::record_partial_analysis,
+            logging::{set_logger_env, Logger},
+        },
+        strings::decode_hex,
+        threading::run_with_timeout,
+    },
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    decompile::{
+        decompile,
+        util::{parse_pc, split_pc_comment},
+        DecompilerArgsBuilder,
+    },
+    disassemble::{disassemble, DisassemblerArgs},
+    error::Error,
+};
+
+#[derive(Debug, Clone, Parser, Builder)]
+#[clap(
+    about = "Find the decompiled function, statement, and expression at a program counter",
+    after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    global_setting = AppSettings::DeriveDisplayOrder,
+    override_usage = "heimdall locate <TARGET> <PC> [OPTIONS]"
+)]
+pub struct LocateArgs {
+    /// The target to locate the program counter in, either a file, bytecode, contract address,
+    /// or ENS name.
+    #[clap(required = true)]
+    pub target: String,
+
+    /// The program counter to locate, either in hex with a leading 0x, or in decimal.
+    #[clap(required = true)]
+    pub pc: String,
+
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// The RPC provider to use for fetching target bytecode.
+    #[clap(long = "rpc-url", short, default_value = "", hide_default_value = true)]
+    pub rpc_url: String,
+
+    /// Whether to skip resolving function selectors.
+    #[clap(long = "skip-resolving")]
+    pub skip_resolving: bool,
+
+    /// The output directory to write the output to or 'print' to print to the console
+    #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
+    pub output: String,
+
+    /// The name for the output file
+    #[clap(long, short, default_value = "", hide_default_value = true)]
+    pub name: String,
+
+    /// The timeout for each function's symbolic execution in milliseconds.
+    #[clap(long, short, default_value = "10000", hide_default_value = true)]
+    pub timeout: u64,
+}
+
+impl LocateArgsBuilder {
+    pub fn new() -> Self {
+        Self {
+            target: Some(String::new()),
+            pc: Some(String::from("0")),
+            verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
+            rpc_url: Some(String::new()),
+            skip_resolving: Some(false),
+            output: Some(String::new()),
+            name: Some(String::new()),
+            timeout: Some(10000),
+        }
+    }
+}
+
+/// Where a function executes the program counter, in its decompiled source.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Location {
+    /// The selector of the function, e.g. `0xa9059cbb`
+    pub selector: String,
+    /// The function's name and arguments in the decompiled source, e.g. `transfer(address arg0,
+    /// uint256 arg1)`
+    pub function: Option<String>,
+    /// The line of the decompiled source emitted from the narrowest pc range containing the
+    /// program counter, counting from 1. `None` if the instruction doesn't emit or feed a
+    /// statement, e.g. it's part of a compiler-inserted check.
+    pub line: Option<usize>,
+    /// The statement on that line, without its pc comment
+    pub statement: Option<String>,
+    /// The inclusive pc range the statement was emitted from
+    pub statement_pcs: Option<(u128, u128)>,
+    /// The value the instruction computes, or the operation it performs if it doesn't push one,
+    /// e.g. `storage[0]` or `SSTORE(0x01, msg.sender)`
+    pub expression: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocateResult {
+    pub pc: u128,
+    /// The instruction at the program counter, e.g. `SLOAD`
+    pub opcode: String,
+    /// Each function which executes the program counter. Code shared between functions, such as
+    /// internal functions, is located in each of them.
+    pub locations: Vec<Location>,
+}
+
+/// Locate a program counter in the target's decompiled source. The target is decompiled with pc
+/// comments to find the statement at the program counter, and each function is symbolically
+/// executed to find which of them execute it, and what the instruction computes there.
+pub async fn locate(args: LocateArgs) -> Result<LocateResult, Error> {
+    set_logger_env(&args.verbose);
+
+    // get a new logger
+    let (logger, _) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    let pc = parse_pc(&args.pc)?;
+    let contract_bytecode = get_bytecode_from_target(&args.target, &args.rpc_url)
+        .await
+        .map_err(|e| Error::GenericError(e.to_string()))?;
+    let opcode = opcode_at(
+        &decode_hex(&contract_bytecode.replacen("0x", "", 1))
+            .map_err(|e| Error::GenericError(format!("invalid bytecode: {e}")))?,
+        pc,
+    )?;
+
+    // the statements are found in the decompiled source, since its lines are what users read
+    let source = decompile(
+        DecompilerArgsBuilder::new()
+            .target(contract_bytecode.clone())
+            .verbose(args.verbose.clone())
+            .rpc_url(args.rpc_url.clone())
+            .skip_resolving(args.skip_resolving)
+            .include_solidity(true)
+            .include_pcs(true)
+            .timeout(args.timeout)
+            .build()
+            .map_err(|e| Error::GenericError(e.to_string()))?,
+    )
+    .await
+    .map_err(|e| Error::GenericError(format!("failed to decompile target: {e}")))?
+    .source
+    .unwrap_or_default();
+    let statements = find_statements(&source, pc);
+
+    enter_pass("disassemble");
+    let disassembled_bytecode = disassemble(DisassemblerArgs {
+        target: contract_bytecode.clone(),
+        verbose: args.verbose.clone(),
+        rpc_url: args.rpc_url.clone(),
+        decimal_counter: false,
+        format: String::from("asm"),
+        verify: false,
+        name: String::new(),
+        output: String::new(),
+    })
+    .await
+    .map_err(|e| Error::GenericError(format!("failed to disassemble bytecode: {e}")))?;
+
+    let evm = VM::new(
+        contract_bytecode,
+        String::from("0x"),
+        String::from("0x6865696d64616c6c000000000061646472657373"),
+        String::from("0x6865696d64616c6c0000000000006f726967696e"),
+        String::from("0x6865696d64616c6c00000000000063616c6c6572"),
+        0,
+        u128::MAX,
+    );
+
+    let mut selectors = find_function_selectors(&evm, &disassembled_bytecode)
+        .into_iter()
+        .collect::<Vec<(String, u128)>>();
+    selectors.sort();
+
+    let mut locations = Vec::new();
+    for (selector, entry_point) in selectors {
+        enter_pass(&format!("symbolic execution (0x{selector})"));
+        let mut evm_clone = evm.clone();
+        let selector_clone = selector.clone();
+        let trace = match run_with_timeout(
+            move || evm_clone.symbolic_exec_selector(&selector_clone, entry_point),
+            Duration::from_millis(args.timeout),
+        ) {
+            Some((trace, _)) => trace,
+            None => {
+                record_partial_analysis();
+                continue
+            }
+        };
+
+        let expression = match find_expression(&trace, pc) {
+            Some(expression) => expression,
+            None => continue,
+        };
+        let selector = format!("0x{selector}");
+        let statement = statements.get(&selector);
+        locations.push(Location {
+            function: statement.and_then(|statement| statement.function.clone()),
+            line: statement.and_then(|statement| statement.line),
+            statement: statement.and_then(|statement| statement.code.clone()),
+            statement_pcs: statement.and_then(|statement| statement.pcs),
+            selector,
+            expression,
+        });
+    }
+
+    if locations.is_empty() {
+        logger.warn(&format!("no function executes pc {pc:#x}."));
+    }
+    for location in &locations {
+        logger.info(&format!(
+            "pc {pc:#x} ({opcode}) is `{}` in {}{}.",
+            location.expression,
+            location.function.as_deref().unwrap_or(&location.selector),
+            match (&location.line, &location.statement) {
+                (Some(line), Some(statement)) => format!(", at line {line}: `{statement}`"),
+                _ => String::new(),
+            }
+        ));
+    }
+
+    Ok(LocateResult { pc, opcode, locations })
+}
+
+/// A statement in the decompiled source, and the function it's in.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Statement {
+    pub function: Option<String>,
+    pub line: Option<usize>,
+    pub code: Option<String>,
+    pub pcs: Option<(u128, u128)>,
+}
+
+/// Find the statement emitted from the narrowest pc range containing `pc` in each function of
+/// the decompiled source, keyed by selector. The source must have been decompiled with pc
+/// comments. Functions which don't contain `pc` only have their name.
+pub fn find_statements(source: &str, pc: u128) -> HashMap<String, Statement> {
+    let mut statements: HashMap<String, Statement> = HashMap::new();
+    let mut selector: Option<String> = None;
+
+    for (index, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if let Some(tag) = line.strip_prefix("/// @custom:selector") {
+            selector = Some(tag.trim().to_string());
+            continue
+        }
+        let Some(selector) = &selector else { continue };
+
+        // the function's name and arguments, without its modifiers
+        if let Some(header) = line.strip_prefix("function ") {
+            let name = header.find(')').map_or(header, |end| &header[..=end]);
+            statements.entry(selector.clone()).or_default().function = Some(name.to_string());
+            continue
+        }
+
+        let (code, comment) = split_pc_comment(line);
+        let pcs = match parse_pc_comment(comment) {
+            Some(pcs) if pcs.0 <= pc && pc <= pcs.1 => pcs,
+            _ => continue,
+        };
+
+        let statement = statements.entry(selector.clone()).or_default();
+        if statement.pcs.is_none_or(|narrowest| pcs.1 - pcs.0 < narrowest.1 - narrowest.0) {
+            statement.line = Some(index + 1);
+            statement.code = Some(code.trim().to_string());
+            statement.pcs = Some(pcs);
+        }
+    }
+
+    statements
+}
+
+/// Parse a pc comment, e.g. ` // pc 0x1f-0x26` or ` // pc 0x1f`, into its inclusive range.
+fn parse_pc_comment(comment: &str) -> Option<(u128, u128)> {
+    let range = comment.trim().strip_prefix("// pc ")?;
+    let parse = |pc: &str| u128::from_str_radix(pc.trim_start_matches("0x"), 16).ok();
+    match range.split_once('-') {
+        Some((start, end)) => Some((parse(start)?, parse(end)?)),
+        None => parse(range).map(|pc| (pc, pc)),
+    }
+}
+
+/// The name of the instruction at `pc`, which must not be inside a PUSH's immediate data.
+fn opcode_at(bytecode: &[u8], pc: u128) -> Result<String, Error> {
+    let mut program_counter = 0;
+    while program_counter < bytecode.len() {
+        let operation = Opcode::new(bytecode[program_counter]);
+        let next = program_counter +
+            1 +
+            operation
+                .name
+                .strip_prefix("PUSH")
+                .and_then(|count| count.parse::<usize>().ok())
+                .unwrap_or(0);
+
+        if program_counter as u128 == pc {
+            return Ok(operation.name.to_string())
+        }
+        if (pc as usize) < next {
+            return Err(Error::GenericError(format!(
+                "pc {pc:#x} is in the data pushed by the {} at {program_counter:#x}, not an \
+                 instruction",
+                operation.name
+            )))
+        }
+        program_counter = next;
+    }
+
+    Err(Error::GenericError(format!("pc {pc:#x} is past the end of the bytecode")))
+}
+
+/// What the instruction at `pc` computes the first time it's executed in the trace, or the
+/// operation it performs if it doesn't push a value.
+fn find_expression(trace: &VMTrace, pc: u128) -> Option<String> {
+    // the vm counts instructions from 1, rather than from pc 0
+    for operation in &trace.operations {
+        let instruction = &operation.last_instruction;
+        if instruction.instruction != pc + 1 {
+            continue
+        }
+
+        return Some(match instruction.output_operations.first() {
+            Some(output) => output.solidify(),
+            None => format!(
+                "{}({})",
+                instruction.opcode_details.as_ref().map_or("UNKNOWN", |opcode| opcode.name),
+                instruction
+                    .input_operations
+                    .iter()
+                    .map(|input| input.solidify())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+        })
+    }
+
+    trace.children.iter().find_map(|child| find_expression(child, pc))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::locate::*;
+
+    #[test]
+    fn test_find_statements() {
+        let source = [
+            "contract DecompiledContract {",
+            "    /// @custom:selector    0xaaaaaaaa",
+            "    function Unresolved_aaaaaaaa(uint256 arg0) public payable {",
+            "        if (arg0) { // pc 0x10-0x30",
+            "            stor_a = arg0; // pc 0x20-0x24",
+            "        }",
+            "    }",
+            "    /// @custom:selector    0xbbbbbbbb",
+            "    function Unresolved_bbbbbbbb() public view returns (bytes32) {",
+            "        return stor_a; // pc 0x40",
+            "    }",
+            "}",
+        ]
+        .join("\n");
+
+        let statements = find_statements(&source, 0x22);
+        assert_eq!(
+            statements.get("0xaaaaaaaa"),
+            Some(&Statement {
+                function: Some(String::from("Unresolved_aaaaaaaa(uint256 arg0)")),
+                line: Some(5),
+                code: Some(String::from("stor_a = arg0;")),
+                pcs: Some((0x20, 0x24)),
+            })
+        );
+        assert_eq!(statements.get("0xbbbbbbbb").and_then(|statement| statement.line), None);
+
+        assert_eq!(find_statements(&source, 0x40)["0xbbbbbbbb"].line, Some(10));
+    }
+
+    #[test]
+    fn test_opcode_at() {
+        // PUSH2 0x0102 PUSH0 STOP
+        let bytecode = [0x61, 0x01, 0x02, 0x5f, 0x00];
+
+        assert_eq!(opcode_at(&bytecode, 0).unwrap(), "PUSH2");
+        assert_eq!(opcode_at(&bytecode, 3).unwrap(), "PUSH0");
+        assert!(opcode_at(&bytecode, 2).is_err());
+        assert!(opcode_at(&bytecode, 5).is_err());
+        assert_eq!(parse_pc("0x1f").unwrap(), parse_pc("31").unwrap());
+    }
+}
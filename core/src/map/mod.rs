@@ -0,0 +1,333 @@
+pub mod output;
+pub mod util;
+
+use std::{
+    collections::{BTreeSet, HashSet, VecDeque},
+    time::Duration,
+};
+
+use clap::{AppSettings, Parser};
+use derive_builder::Builder;
+use ethers::types::{Address, H256, U256};
+use heimdall_common::{
+    constants::ADDRESS_REGEX,
+    ether::{
+        batch::{get_code_batch, get_storage_batch},
+        bytecode::get_delegation_target,
+        evm::core::vm::VM,
+        rpc::{get_block_number, get_code_at_block},
+        selectors::find_function_selectors,
+    },
+    resources::labels::get_address_label,
+    utils::{
+        io::{
+            crash::enter_pass,
+            exit::{record_failure, record_partial_analysis, ExitStatus},
+            logging::{set_logger_env, Logger},
+        },
+        strings::decode_hex,
+        threading::run_with_timeout,
+    },
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    disassemble::{disassemble, DisassemblerArgs},
+    error::Error,
+};
+
+use self::util::{
+    as_address, find_hardcoded_addresses, CallGraphBuilder, Reference, ReferenceKind, VM_ADDRESSES,
+};
+
+#[derive(Debug, Clone, Parser, Builder)]
+#[clap(
+    about = "Map the system of contracts reachable from a contract, and how they call each other",
+    after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    global_setting = AppSettings::DeriveDisplayOrder,
+    override_usage = "heimdall map <TARGET> [OPTIONS]"
+)]
+pub struct MapArgs {
+    /// The address of the contract to start mapping from.
+    #[clap(required = true)]
+    pub target: String,
+
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// The RPC provider to use for fetching each contract's bytecode and storage.
+    #[clap(long = "rpc-url", short, default_value = "", hide_default_value = true)]
+    pub rpc_url: String,
+
+    /// How many references to follow away from the target. Contracts beyond this depth are
+    /// shown, but not crawled.
+    #[clap(long, short, default_value = "2")]
+    pub depth: u64,
+
+    /// The format to generate, one of 'dot', 'mermaid', or 'json'.
+    #[clap(long, short, default_value = "dot")]
+    pub format: String,
+
+    /// The block number to snapshot each contract's bytecode and storage at. Defaults to the
+    /// latest block, so every contract is read at the same block.
+    #[clap(long)]
+    pub block: Option<u64>,
+
+    /// The output directory to write the output to or 'print' to print to the console
+    #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
+    pub output: String,
+
+    /// The name for the output file
+    #[clap(long, short, default_value = "", hide_default_value = true)]
+    pub name: String,
+
+    /// The timeout for each function's symbolic execution in milliseconds.
+    #[clap(long, short, default_value = "10000", hide_default_value = true)]
+    pub timeout: u64,
+}
+
+impl MapArgsBuilder {
+    pub fn new() -> Self {
+        Self {
+            target: Some(String::new()),
+            verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
+            rpc_url: Some(String::new()),
+            depth: Some(2),
+            format: Some(String::from("dot")),
+            block: Some(None),
+            output: Some(String::new()),
+            name: Some(String::new()),
+            timeout: Some(10000),
+        }
+    }
+}
+
+/// A contract in the system, as it was at the snapshot block.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MappedContract {
+    pub address: Address,
+    /// How many references away from the target the contract is
+    pub depth: u64,
+    /// The contract's label, if it's a known address
+    pub label: Option<String>,
+    /// Whether any code is deployed at the address. An EOA with an EIP-7702 delegation counts
+    /// as a contract, since it runs its delegate's code.
+    pub is_contract: bool,
+    pub code_size: usize,
+    pub selectors: usize,
+    pub references: Vec<Reference>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MapResult {
+    pub root: Address,
+    pub block: u64,
+    /// The crawled contracts, in the order they were reached
+    pub contracts: Vec<MappedContract>,
+}
+
+/// Map the system of contracts reachable from the target. Starting from the target, each
+/// contract's bytecode is fetched and symbolically executed to find the addresses it calls,
+/// whether hardcoded or read from storage, which are crawled in turn up to `--depth` references
+/// away. Every contract is read at the same block, so the map is a consistent snapshot.
+pub async fn map(args: MapArgs) -> Result<MapResult, Error> {
+    set_logger_env(&args.verbose);
+
+    // get a new logger
+    let (logger, _) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    // check the format before crawling, so an invalid one fails fast
+    if !["dot", "mermaid", "json"].contains(&args.format.as_str()) {
+        return Err(Error::GenericError(format!(
+            "invalid format '{}' . expected one of: dot, mermaid, json.",
+            args.format
+        )))
+    }
+
+    // references are followed on-chain, so only an address can be mapped
+    let root = match ADDRESS_REGEX.is_match(&args.target).unwrap_or(false) {
+        true => args.target.parse::<Address>().map_err(|e| {
+            record_failure(ExitStatus::InvalidArguments);
+            Error::GenericError(format!("invalid target address: {e}"))
+        })?,
+        false => {
+            record_failure(ExitStatus::InvalidArguments);
+            return Err(Error::GenericError(format!(
+                "invalid target '{}' . mapping requires a contract address.",
+                args.target
+            )))
+        }
+    };
+
+    let block = match args.block {
+        Some(block) => block,
+        None => {
+            get_block_number(&args.rpc_url).await.map_err(|e| Error::GenericError(e.to_string()))?
+        }
+    };
+    logger.info(&format!("mapping from {root:#x} at block {block}."));
+
+    let mut contracts = Vec::new();
+    let mut visited = HashSet::from([root]);
+    let mut queue = VecDeque::from([(root, 0)]);
+    let mut prefetched = None;
+    while let Some((address, depth)) = queue.pop_front() {
+        // the bytecode of each level of the crawl is fetched in a single batch
+        if prefetched != Some(depth) {
+            let addresses = std::iter::once(address)
+                .chain(queue.iter().filter(|(_, d)| *d == depth).map(|(a, _)| *a))
+                .map(|address| format!("{address:#x}"))
+                .collect::<Vec<_>>();
+            get_code_batch(&addresses, Some(block), &args.rpc_url)
+                .await
+                .map_err(|e| Error::GenericError(e.to_string()))?;
+            prefetched = Some(depth);
+        }
+
+        enter_pass(&format!("crawl ({address:#x})"));
+        let contract = map_contract(address, depth, block, &args).await?;
+
+        if depth < args.depth {
+            for reference in &contract.references {
+                if visited.insert(reference.to) {
+                    queue.push_back((reference.to, depth + 1));
+                }
+            }
+        }
+        contracts.push(contract);
+    }
+
+    logger.info(&format!("mapped {} contracts.", contracts.len()));
+    Ok(MapResult { root, block, contracts })
+}
+
+/// Snapshot a single contract, and find every address it refers to.
+async fn map_contract(
+    address: Address,
+    depth: u64,
+    block: u64,
+    args: &MapArgs,
+) -> Result<MappedContract, Error> {
+    let target = format!("{address:#x}");
+    let bytecode = get_code_at_block(&target, &args.rpc_url, Some(block))
+        .await
+        .map_err(|e| Error::GenericError(e.to_string()))?;
+
+    let mut contract = MappedContract {
+        address,
+        depth,
+        label: get_address_label(&target),
+        is_contract: !bytecode.is_empty(),
+        code_size: bytecode.len() / 2,
+        selectors: 0,
+        references: Vec::new(),
+    };
+
+    // the EOA runs its delegate's code, which is mapped as its own contract
+    if let Some(delegate) = get_delegation_target(&bytecode) {
+        let delegate = delegate
+            .parse::<Address>()
+            .map_err(|e| Error::GenericError(format!("invalid delegate address: {e}")))?;
+        contract.references.push(Reference {
+            to: delegate,
+            kind: ReferenceKind::Delegation,
+            slot: None,
+        });
+        return Ok(contract)
+    }
+    if bytecode.is_empty() {
+        return Ok(contract)
+    }
+
+    let disassembled_bytecode = disassemble(DisassemblerArgs {
+        target: bytecode.clone(),
+        verbose: args.verbose.clone(),
+        rpc_url: args.rpc_url.clone(),
+        decimal_counter: false,
+        format: String::from("asm"),
+        verify: false,
+        name: String::new(),
+        output: String::new(),
+    })
+    .await
+    .map_err(|e| Error::GenericError(format!("failed to disassemble bytecode: {e}")))?;
+
+    let evm = VM::new(
+        bytecode.clone(),
+        String::from("0x"),
+        String::from(VM_ADDRESSES[0]),
+        String::from(VM_ADDRESSES[1]),
+        String::from(VM_ADDRESSES[2]),
+        0,
+        u128::MAX,
+    );
+
+    let mut selectors = find_function_selectors(&evm, &disassembled_bytecode)
+        .into_iter()
+        .collect::<Vec<(String, u128)>>();
+    selectors.sort();
+    contract.selectors = selectors.len();
+
+    let mut builder = CallGraphBuilder::new();
+    for (selector, entry_point) in selectors {
+        let mut evm_clone = evm.clone();
+        match run_with_timeout(
+            move || evm_clone.symbolic_exec_selector(&selector, entry_point),
+            Duration::from_millis(args.timeout),
+        ) {
+            Some((trace, _)) => builder.add_trace(&trace),
+            None => record_partial_analysis(),
+        }
+    }
+
+    // calls made outside of any function, such as a proxy's fallback, are only found by
+    // executing the contract as a whole
+    let evm_clone = evm.clone();
+    match run_with_timeout(move || evm_clone.symbolic_exec(), Duration::from_millis(args.timeout)) {
+        Some((trace, _)) => builder.add_trace(&trace),
+        None => record_partial_analysis(),
+    }
+
+    let (calls, storage_calls) = builder.build();
+    let mut references = calls
+        .into_iter()
+        .map(|(to, kind)| Reference { to, kind, slot: None })
+        .collect::<BTreeSet<Reference>>();
+
+    // symbolic execution starts with empty storage, so addresses read from storage are looked up
+    // in the snapshot
+    let slots = storage_calls
+        .iter()
+        .map(|(slot, _)| {
+            let mut slot_bytes = [0u8; 32];
+            slot.to_big_endian(&mut slot_bytes);
+            H256::from(slot_bytes)
+        })
+        .collect::<Vec<_>>();
+    let values = get_storage_batch(&target, &slots, block, &args.rpc_url)
+        .await
+        .map_err(|e| Error::GenericError(e.to_string()))?;
+    for ((slot, kind), value) in storage_calls.into_iter().zip(values) {
+        if let Some(to) = as_address(U256::from_big_endian(value.as_bytes())) {
+            references.insert(Reference { to, kind, slot: Some(slot) });
+        }
+    }
+
+    // hardcoded addresses which weren't seen being called may still be part of the system, e.g.
+    // a call which the symbolic execution didn't reach
+    let bytecode = decode_hex(&bytecode)
+        .map_err(|e| Error::GenericError(format!("failed to decode bytecode: {e}")))?;
+    for to in find_hardcoded_addresses(&bytecode) {
+        if to != address && !references.iter().any(|reference| reference.to == to) {
+            references.insert(Reference { to, kind: ReferenceKind::Constant, slot: None });
+        }
+    }
+
+    contract.references =
+        references.into_iter().filter(|reference| reference.to != address).collect();
+    Ok(contract)
+}
@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+
+use ethers::types::Address;
+use heimdall_common::resources::labels::get_address_label;
+use petgraph::{dot::Dot, graph::Graph};
+
+use super::{util::Reference, MapResult};
+
+/// The label of a contract's node, i.e. its address and, if known, its label. Addresses which
+/// weren't crawled are only known by their label.
+fn node_label(result: &MapResult, address: &Address) -> String {
+    let contract = result.contracts.iter().find(|contract| &contract.address == address);
+    let label = match contract {
+        Some(contract) => contract.label.clone(),
+        None => get_address_label(&format!("{address:#x}")),
+    };
+    let mut lines = vec![format!("{address:#x}")];
+    if let Some(label) = label {
+        lines.insert(0, label);
+    }
+    match contract {
+        Some(contract) if !contract.is_contract => lines.push(String::from("(EOA)")),
+        Some(contract) => lines.push(format!("{} selectors", contract.selectors)),
+        None => lines.push(String::from("(not crawled)")),
+    }
+    lines.join("\n")
+}
+
+/// The label of a reference's edge, e.g. `delegatecall (slot 0x0)`.
+fn edge_label(reference: &Reference) -> String {
+    match reference.slot {
+        Some(slot) => format!("{} (slot {slot:#x})", reference.kind.name()),
+        None => reference.kind.name().to_string(),
+    }
+}
+
+/// Every address in the map, crawled or not, in the order they were reached.
+fn addresses(result: &MapResult) -> Vec<Address> {
+    let mut addresses =
+        result.contracts.iter().map(|contract| contract.address).collect::<Vec<_>>();
+    for contract in &result.contracts {
+        for reference in &contract.references {
+            if !addresses.contains(&reference.to) {
+                addresses.push(reference.to);
+            }
+        }
+    }
+    addresses
+}
+
+/// Write the map in the `dot` graphviz format. Each contract points to the addresses it refers to.
+pub fn build_dot(result: &MapResult) -> String {
+    let mut graph: Graph<String, String> = Graph::new();
+    let nodes = addresses(result)
+        .into_iter()
+        .map(|address| (address, graph.add_node(node_label(result, &address))))
+        .collect::<HashMap<_, _>>();
+
+    for contract in &result.contracts {
+        for reference in &contract.references {
+            graph.add_edge(nodes[&contract.address], nodes[&reference.to], edge_label(reference));
+        }
+    }
+
+    let output = format!("{}", Dot::with_config(&graph, &[]));
+    output.replace(
+        "digraph {",
+        "digraph G {\n    node [shape=box, style=\"rounded\", fontname=\"Helvetica\"];\n    edge [fontname=\"Helvetica\"];",
+    )
+}
+
+/// Write the map as a Mermaid flowchart, which renders inline in markdown.
+pub fn build_mermaid(result: &MapResult) -> String {
+    let addresses = addresses(result);
+    let mut output = vec![String::from("graph TD")];
+
+    for (i, address) in addresses.iter().enumerate() {
+        let label = node_label(result, address).replace('\n', "<br/>").replace('"', "#quot;");
+        output.push(format!("    c{i}[\"{label}\"]"));
+    }
+    for contract in &result.contracts {
+        let from = addresses.iter().position(|address| address == &contract.address);
+        for reference in &contract.references {
+            let to = addresses.iter().position(|address| address == &reference.to);
+            if let (Some(from), Some(to)) = (from, to) {
+                output.push(format!("    c{from} -->|{}| c{to}", edge_label(reference)));
+            }
+        }
+    }
+
+    output.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::types::U256;
+
+    use crate::map::{output::*, util::ReferenceKind, MappedContract};
+
+    fn result() -> MapResult {
+        MapResult {
+            root: Address::repeat_byte(0x11),
+            block: 1,
+            contracts: vec![MappedContract {
+                address: Address::repeat_byte(0x11),
+                depth: 0,
+                label: None,
+                is_contract: true,
+                code_size: 100,
+                selectors: 2,
+                references: vec![Reference {
+                    to: Address::repeat_byte(0x22),
+                    kind: ReferenceKind::DelegateCall,
+                    slot: Some(U256::zero()),
+                }],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_build_mermaid() {
+        assert_eq!(
+            build_mermaid(&result()),
+            [
+                "graph TD",
+                "    c0[\"0x1111111111111111111111111111111111111111<br/>2 selectors\"]",
+                "    c1[\"0x2222222222222222222222222222222222222222<br/>(not crawled)\"]",
+                "    c0 -->|delegatecall (slot 0x0)| c1",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_build_dot() {
+        let dot = build_dot(&result());
+        assert!(dot.starts_with("digraph G {"));
+        assert!(dot.contains("label = \"delegatecall (slot 0x0)\""));
+        assert!(dot.contains("(not crawled)"));
+    }
+}
@@ -0,0 +1,255 @@
+use std::collections::{BTreeSet, HashMap};
+
+use ethers::types::{Address, U256};
+use heimdall_common::ether::{
+    bytecode::split_sections,
+    evm::{
+        core::opcodes::{Opcode, WrappedInput, WrappedOpcode},
+        ext::exec::VMTrace,
+    },
+};
+use serde::{Deserialize, Serialize};
+
+/// The address, origin, and caller of every symbolically executed function. These aren't real
+/// contracts, so calls to them aren't part of the map.
+pub const VM_ADDRESSES: [&str; 3] = [
+    "0x6865696d64616c6c000000000061646472657373",
+    "0x6865696d64616c6c0000000000006f726967696e",
+    "0x6865696d64616c6c00000000000063616c6c6572",
+];
+
+/// How a contract refers to another address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReferenceKind {
+    Call,
+    CallCode,
+    DelegateCall,
+    StaticCall,
+    /// The address is an EOA which delegates to the referenced code under EIP-7702
+    Delegation,
+    /// The address is hardcoded, but no call to it was traced
+    Constant,
+}
+
+impl ReferenceKind {
+    /// The kind of call made by a CALL-family opcode, if it is one.
+    pub fn from_opcode(opcode: u8) -> Option<ReferenceKind> {
+        match opcode {
+            0xf1 => Some(ReferenceKind::Call),
+            0xf2 => Some(ReferenceKind::CallCode),
+            0xf4 => Some(ReferenceKind::DelegateCall),
+            0xfa => Some(ReferenceKind::StaticCall),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ReferenceKind::Call => "call",
+            ReferenceKind::CallCode => "callcode",
+            ReferenceKind::DelegateCall => "delegatecall",
+            ReferenceKind::StaticCall => "staticcall",
+            ReferenceKind::Delegation => "delegation",
+            ReferenceKind::Constant => "constant",
+        }
+    }
+}
+
+/// A reference from a contract to another address.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Reference {
+    pub to: Address,
+    pub kind: ReferenceKind,
+    /// The storage slot the address was read from, if it isn't hardcoded
+    pub slot: Option<U256>,
+}
+
+/// Accumulates the calls a contract makes from its traces.
+#[derive(Debug, Clone, Default)]
+pub struct CallGraphBuilder {
+    /// The key read by each SLOAD, by the operation it pushed
+    loads: HashMap<WrappedOpcode, U256>,
+    /// Calls to a hardcoded address
+    calls: BTreeSet<(Address, ReferenceKind)>,
+    /// Calls to an address read from storage, by slot
+    storage_calls: BTreeSet<(U256, ReferenceKind)>,
+}
+
+impl CallGraphBuilder {
+    pub fn new() -> CallGraphBuilder {
+        CallGraphBuilder::default()
+    }
+
+    /// Record every call in the trace, and where its target address came from.
+    pub fn add_trace(&mut self, trace: &VMTrace) {
+        for operation in &trace.operations {
+            let instruction = &operation.last_instruction;
+
+            // SLOAD
+            if instruction.opcode == 0x54 {
+                if let (Some(key), Some(key_operation)) =
+                    (instruction.inputs.first(), instruction.input_operations.first())
+                {
+                    self.loads.insert(
+                        WrappedOpcode::new(0x54, vec![WrappedInput::Opcode(key_operation.clone())]),
+                        *key,
+                    );
+                }
+                continue
+            }
+
+            let Some(kind) = ReferenceKind::from_opcode(instruction.opcode) else { continue };
+            let (Some(address), Some(address_operation)) =
+                (instruction.inputs.get(1), instruction.input_operations.get(1))
+            else {
+                continue
+            };
+
+            // symbolic execution starts with empty storage, so an address read from storage is
+            // only known by its slot
+            match self.find_load(address_operation) {
+                Some(slot) => {
+                    self.storage_calls.insert((slot, kind));
+                }
+                None => {
+                    if let Some(address) = as_address(*address) {
+                        self.calls.insert((address, kind));
+                    }
+                }
+            }
+        }
+
+        for child in &trace.children {
+            self.add_trace(child);
+        }
+    }
+
+    /// The slot of the SLOAD the operation's value was derived from, if any.
+    fn find_load(&self, operation: &WrappedOpcode) -> Option<U256> {
+        if let Some(slot) = self.loads.get(operation) {
+            return Some(*slot)
+        }
+
+        operation.inputs.iter().find_map(|input| match input {
+            WrappedInput::Opcode(operation) => self.find_load(operation),
+            WrappedInput::Raw(_) | WrappedInput::Variable(_) => None,
+        })
+    }
+
+    /// The calls to hardcoded addresses, and the storage slots of the addresses called through
+    /// storage.
+    #[allow(clippy::type_complexity)]
+    pub fn build(self) -> (BTreeSet<(Address, ReferenceKind)>, BTreeSet<(U256, ReferenceKind)>) {
+        (self.calls, self.storage_calls)
+    }
+}
+
+/// The address held by a word, if it fits in 160 bits and isn't a precompile or one of the
+/// addresses used by symbolic execution.
+pub fn as_address(value: U256) -> Option<Address> {
+    if value.bits() > 160 || value <= U256::from(0xffff) {
+        return None
+    }
+
+    let mut bytes = [0u8; 32];
+    value.to_big_endian(&mut bytes);
+    let address = Address::from_slice(&bytes[12..]);
+    match VM_ADDRESSES.iter().any(|vm_address| vm_address.parse::<Address>().ok() == Some(address))
+    {
+        true => None,
+        false => Some(address),
+    }
+}
+
+/// The addresses hardcoded in the bytecode, either as a `PUSH20` or as an immutable, which
+/// `solc` pushes as a full word. Smaller constants are too ambiguous to be treated as addresses.
+pub fn find_hardcoded_addresses(bytecode: &[u8]) -> BTreeSet<Address> {
+    let end = split_sections(bytecode).code;
+    let mut addresses = BTreeSet::new();
+
+    let mut program_counter = 0;
+    while program_counter < end {
+        let operation = Opcode::new(bytecode[program_counter]);
+        let byte_count = operation
+            .name
+            .strip_prefix("PUSH")
+            .and_then(|count| count.parse::<usize>().ok())
+            .unwrap_or(0);
+        let next = (program_counter + 1 + byte_count).min(end);
+        let value = U256::from_big_endian(&bytecode[program_counter + 1..next]);
+
+        // a PUSH20 of all ones is an address mask
+        let is_address = match byte_count {
+            20 => value != (U256::one() << 160) - 1,
+            32 => value.bits() > 128,
+            _ => false,
+        };
+        if is_address {
+            addresses.extend(as_address(value));
+        }
+        program_counter = next;
+    }
+
+    addresses
+}
+
+#[cfg(test)]
+mod tests {
+    use heimdall_common::ether::evm::core::vm::VM;
+
+    use crate::map::util::*;
+
+    #[test]
+    fn test_find_hardcoded_addresses() {
+        // PUSH20 address, PUSH20 mask, PUSH32 immutable, PUSH32 non-address
+        let bytecode = [
+            vec![0x73],
+            vec![0x11; 20],
+            vec![0x73],
+            vec![0xff; 20],
+            vec![0x7f],
+            vec![0x00; 12],
+            vec![0x22; 20],
+            vec![0x7f],
+            vec![0x33; 32],
+            vec![0x00],
+        ]
+        .concat();
+
+        assert_eq!(
+            find_hardcoded_addresses(&bytecode),
+            BTreeSet::from([Address::repeat_byte(0x11), Address::repeat_byte(0x22)])
+        );
+    }
+
+    #[test]
+    fn test_calls_through_storage_and_constants() {
+        // STATICCALL(gas, sload(1), 0, 0, 0, 0), then DELEGATECALL(gas, 0x1111..11, 0, 0, 0, 0)
+        let bytecode = [
+            "600060006000600060015461fffffa50",
+            "60006000600060007311111111111111111111111111111111111111115af450",
+            "00",
+        ]
+        .concat();
+        let vm = VM::new(
+            bytecode,
+            String::from("0x"),
+            String::from(VM_ADDRESSES[0]),
+            String::from(VM_ADDRESSES[1]),
+            String::from(VM_ADDRESSES[2]),
+            0,
+            u128::MAX,
+        );
+
+        let mut builder = CallGraphBuilder::new();
+        builder.add_trace(&vm.symbolic_exec().0);
+        let (calls, storage_calls) = builder.build();
+
+        assert_eq!(
+            calls,
+            BTreeSet::from([(Address::repeat_byte(0x11), ReferenceKind::DelegateCall)])
+        );
+        assert_eq!(storage_calls, BTreeSet::from([(U256::one(), ReferenceKind::StaticCall)]));
+    }
+}
@@ -0,0 +1,166 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::Range,
+};
+
+use heimdall_common::ether::evm::core::opcodes::Opcode;
+use serde::{Deserialize, Serialize};
+
+use crate::minimize::util::basic_blocks;
+
+/// Runs of blocks smaller than this many bytes aren't reported, since short sequences such as
+/// `JUMPDEST POP JUMP` repeat in every contract.
+const MIN_CLONE_SIZE: usize = 24;
+
+/// A sequence of basic blocks which appears more than once in a contract, such as inlined
+/// library code.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CloneGroup {
+    /// The offset of each copy of the sequence
+    pub offsets: Vec<usize>,
+    /// The number of basic blocks in the sequence
+    pub blocks: usize,
+    /// The size of a single copy, in bytes
+    pub size: usize,
+    /// Whether every copy is byte-for-byte identical. Otherwise the copies only differ in
+    /// their PUSH data, which usually means jump targets.
+    pub exact: bool,
+}
+
+/// The opcodes of a block, ignoring PUSH data, so that blocks which only differ in their jump
+/// targets compare equal.
+pub fn normalize(code: &[u8], block: &Range<usize>) -> Vec<u8> {
+    let mut opcodes = Vec::new();
+    let mut program_counter = block.start;
+
+    while program_counter < block.end {
+        let operation = Opcode::new(code[program_counter]);
+        opcodes.push(operation.code);
+
+        if let Some(byte_count) = operation.name.strip_prefix("PUSH") {
+            program_counter += byte_count.parse::<usize>().unwrap_or(0);
+        }
+        program_counter += 1;
+    }
+
+    opcodes
+}
+
+/// Find every maximal sequence of consecutive basic blocks which appears more than once in the
+/// code, ignoring PUSH data. Copies of a sequence never overlap.
+pub fn find_clones(code: &[u8]) -> Vec<CloneGroup> {
+    let blocks = basic_blocks(code);
+    let keys = blocks.iter().map(|block| normalize(code, block)).collect::<Vec<Vec<u8>>>();
+
+    // map the contents of each maximal run to the start index of every copy
+    let mut runs: BTreeMap<&[Vec<u8>], BTreeSet<usize>> = BTreeMap::new();
+    for first in 0..blocks.len() {
+        for second in (first + 1)..blocks.len() {
+            // only start at the beginning of a run, so each run is found once
+            if keys[first] != keys[second] || (first > 0 && keys[first - 1] == keys[second - 1]) {
+                continue
+            }
+
+            let mut length = 0;
+            while second + length < blocks.len() &&
+                first + length < second &&
+                keys[first + length] == keys[second + length]
+            {
+                length += 1;
+            }
+
+            let copies = runs.entry(&keys[first..first + length]).or_default();
+            copies.insert(first);
+            copies.insert(second);
+        }
+    }
+
+    // drop runs whose every copy lies within a copy of a longer run
+    let spans = runs
+        .iter()
+        .flat_map(|(run, copies)| copies.iter().map(|start| *start..start + run.len()))
+        .collect::<Vec<Range<usize>>>();
+    let mut groups = runs
+        .iter()
+        .filter(|(run, copies)| {
+            !copies.iter().all(|start| {
+                spans.iter().any(|span| {
+                    span.len() > run.len() && span.start <= *start && start + run.len() <= span.end
+                })
+            })
+        })
+        .filter_map(|(run, copies)| {
+            let instances = copies
+                .iter()
+                .map(|start| &code[blocks[*start].start..blocks[start + run.len() - 1].end])
+                .collect::<Vec<&[u8]>>();
+            if instances[0].len() < MIN_CLONE_SIZE {
+                return None
+            }
+
+            Some(CloneGroup {
+                offsets: copies.iter().map(|start| blocks[*start].start).collect(),
+                blocks: run.len(),
+                size: instances[0].len(),
+                exact: instances.windows(2).all(|pair| pair[0] == pair[1]),
+            })
+        })
+        .collect::<Vec<CloneGroup>>();
+
+    groups.sort_by_key(|group| {
+        (std::cmp::Reverse(group.size * group.offsets.len()), group.offsets[0])
+    });
+    groups
+}
+
+/// The number of bytes which could be saved by keeping a single copy of each clone.
+pub fn duplicated_bytes(groups: &[CloneGroup]) -> usize {
+    groups.iter().map(|group| group.size * (group.offsets.len() - 1)).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::metrics::clones::*;
+
+    /// A block of 20 `ADD`s after a `JUMPDEST`, ending with a jump to `target`.
+    fn block(target: u8) -> Vec<u8> {
+        let mut block = vec![0x5b];
+        block.extend([0x01; 20]);
+        block.extend([0x60, target, 0x56]);
+        block
+    }
+
+    #[test]
+    fn test_find_exact_clones() {
+        let mut code = block(0x00);
+        code.extend([0x5b, 0x00]);
+        code.extend(block(0x00));
+
+        assert_eq!(
+            find_clones(&code),
+            vec![CloneGroup { offsets: vec![0, 26], blocks: 1, size: 24, exact: true }]
+        );
+    }
+
+    #[test]
+    fn test_find_near_clones() {
+        let mut code = block(0x00);
+        code.extend(block(0x01));
+        code.extend([0x5b, 0x00]);
+        code.extend(block(0x02));
+        code.extend(block(0x03));
+
+        let groups = find_clones(&code);
+
+        assert_eq!(
+            groups,
+            vec![CloneGroup { offsets: vec![0, 50], blocks: 2, size: 48, exact: false }]
+        );
+        assert_eq!(duplicated_bytes(&groups), 48);
+    }
+
+    #[test]
+    fn test_no_clones() {
+        assert!(find_clones(&block(0x00)).is_empty());
+    }
+}
@@ -0,0 +1,205 @@
+use std::collections::{BTreeMap, HashSet};
+
+use heimdall_common::{
+    ether::{
+        bytecode::split_sections,
+        evm::{
+            core::{opcodes::Opcode, vm::VM},
+            ext::exec::VMTrace,
+        },
+    },
+    utils::strings::decode_hex,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::minimize::util::basic_blocks;
+
+/// How a contract's bytes are spent, attributed by the owner of each basic block.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CodesizeBreakdown {
+    pub total: usize,
+    /// Blocks executed while dispatching any selector to its entry point
+    pub dispatcher: usize,
+    /// Blocks only executed by a single function, keyed by selector
+    pub functions: BTreeMap<String, usize>,
+    /// Blocks executed by more than one function, such as shared internal functions
+    pub shared: usize,
+    /// Blocks no function or the dispatcher reached, such as unreachable code
+    pub unattributed: usize,
+    /// PUSH data which is printable ASCII, which is how solc embeds revert strings. These bytes
+    /// are also counted in the blocks which contain them.
+    pub revert_strings: usize,
+    /// Embedded bytecode, such as the creation code of contracts deployed with `new`. Any bytes
+    /// between it and the metadata are counted as part of it.
+    pub embedded_bytecode: usize,
+    /// The offset of each contract preamble found within the embedded bytecode. A child's
+    /// runtime code appears within its creation code, so it's listed separately.
+    pub embedded_offsets: Vec<usize>,
+    /// Unreachable bytes after the code, such as a data section or padding
+    #[serde(default)]
+    pub data: usize,
+    /// The CBOR-encoded compiler metadata at the end of the bytecode
+    pub metadata: usize,
+}
+
+/// The offset of each contract preamble (`PUSH1 0x80 PUSH1 0x40 MSTORE`, or `0x60` for older
+/// compilers) which follows an `INVALID` separator, before `code_end`.
+pub fn embedded_offsets(bytecode: &[u8], code_end: usize) -> Vec<usize> {
+    (1..code_end)
+        .filter(|offset| {
+            bytecode[offset - 1] == 0xfe &&
+                (bytecode[*offset..code_end].starts_with(&[0x60, 0x80, 0x60, 0x40, 0x52]) ||
+                    bytecode[*offset..code_end].starts_with(&[0x60, 0x60, 0x60, 0x40, 0x52]))
+        })
+        .collect()
+}
+
+/// The number of bytes of PUSH data in the code which are printable ASCII strings, allowing for
+/// the trailing zero padding of a left-aligned string.
+pub fn revert_string_bytes(code: &[u8]) -> usize {
+    let mut total = 0;
+    let mut program_counter = 0;
+
+    while program_counter < code.len() {
+        let operation = Opcode::new(code[program_counter]);
+        let byte_count = operation
+            .name
+            .strip_prefix("PUSH")
+            .and_then(|count| count.parse::<usize>().ok())
+            .unwrap_or(0);
+
+        let data = &code[(program_counter + 1).min(code.len())..
+            (program_counter + 1 + byte_count).min(code.len())];
+        let text_length = data.iter().position(|byte| *byte == 0).unwrap_or(data.len());
+        if text_length >= 4 &&
+            data[..text_length].iter().all(|byte| byte.is_ascii_graphic() || *byte == b' ') &&
+            data[text_length..].iter().all(|byte| *byte == 0)
+        {
+            total += text_length;
+        }
+
+        program_counter += 1 + byte_count;
+    }
+
+    total
+}
+
+/// Add the program counter of every instruction in the trace to `pcs`.
+pub fn executed_pcs(trace: &VMTrace, pcs: &mut HashSet<usize>) {
+    for operation in &trace.operations {
+        pcs.insert(operation.last_instruction.instruction.saturating_sub(1) as usize);
+    }
+    for child in &trace.children {
+        executed_pcs(child, pcs);
+    }
+}
+
+/// The program counter of every instruction executed while dispatching `selector` to its entry
+/// point, mirroring the way symbolic execution reaches it.
+pub fn dispatcher_pcs(evm: &VM, selector: &str, entry_point: u128) -> HashSet<usize> {
+    let mut vm = evm.clone();
+    let mut pcs = HashSet::new();
+
+    vm.calldata = decode_hex(selector).unwrap_or_default();
+    while vm.bytecode.len() >= vm.instruction as usize && vm.instruction != entry_point + 1 {
+        pcs.insert(vm.instruction.saturating_sub(1) as usize);
+        vm.step();
+
+        if vm.exitcode != 255 || !vm.returndata.is_empty() {
+            break
+        }
+    }
+
+    pcs
+}
+
+/// Attribute each basic block of the bytecode to the dispatcher, a single function, or shared
+/// code, based on which of them executed an instruction within it.
+pub fn build_breakdown(
+    bytecode: &[u8],
+    dispatcher: &HashSet<usize>,
+    functions: &BTreeMap<String, HashSet<usize>>,
+) -> CodesizeBreakdown {
+    let sections = split_sections(bytecode);
+    let offsets = embedded_offsets(bytecode, sections.metadata_start());
+    let (code_end, data) = match offsets.first() {
+        Some(offset) => (*offset, 0),
+        None => (sections.code, sections.data),
+    };
+
+    let mut breakdown = CodesizeBreakdown {
+        total: bytecode.len(),
+        revert_strings: revert_string_bytes(&bytecode[..code_end]),
+        embedded_bytecode: sections.metadata_start() - code_end - data,
+        embedded_offsets: offsets,
+        data,
+        metadata: sections.metadata,
+        ..Default::default()
+    };
+
+    for block in basic_blocks(&bytecode[..code_end]) {
+        let size = block.len();
+        if block.clone().any(|pc| dispatcher.contains(&pc)) {
+            breakdown.dispatcher += size;
+            continue
+        }
+
+        let owners = functions
+            .iter()
+            .filter(|(_, pcs)| block.clone().any(|pc| pcs.contains(&pc)))
+            .map(|(selector, _)| selector)
+            .collect::<Vec<&String>>();
+        match owners.as_slice() {
+            [] => breakdown.unattributed += size,
+            [owner] => *breakdown.functions.entry(owner.to_string()).or_insert(0) += size,
+            _ => breakdown.shared += size,
+        }
+    }
+
+    breakdown
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::metrics::codesize::*;
+
+    #[test]
+    fn test_embedded_offsets() {
+        // STOP INVALID PUSH1 0x80 PUSH1 0x40 MSTORE
+        let bytecode = [0x00, 0xfe, 0x60, 0x80, 0x60, 0x40, 0x52, 0x00];
+
+        assert_eq!(embedded_offsets(&bytecode, bytecode.len()), vec![2]);
+    }
+
+    #[test]
+    fn test_revert_string_bytes() {
+        // PUSH32 "Ownable: caller" padded with zeros, PUSH4 0x08c379a0
+        let mut code = vec![0x7f];
+        code.extend(b"Ownable: caller");
+        code.extend([0u8; 17]);
+        code.extend([0x63, 0x08, 0xc3, 0x79, 0xa0]);
+
+        assert_eq!(revert_string_bytes(&code), 15);
+    }
+
+    #[test]
+    fn test_build_breakdown() {
+        // block 0: PUSH1 0x04 JUMP, block 1: JUMPDEST STOP, block 2: JUMPDEST STOP
+        let bytecode = [0x60, 0x04, 0x56, 0x5b, 0x00, 0x5b, 0x00];
+        let dispatcher = HashSet::from([0, 2]);
+        let functions = BTreeMap::from([("0x01".to_string(), HashSet::from([3, 4]))]);
+
+        let breakdown = build_breakdown(&bytecode, &dispatcher, &functions);
+
+        assert_eq!(breakdown.dispatcher, 3);
+        assert_eq!(breakdown.functions.get("0x01"), Some(&2));
+        assert_eq!(breakdown.unattributed, 2);
+        assert_eq!(breakdown.metadata, 0);
+
+        // the same code, followed by a trailing data section
+        let bytecode = [0x60, 0x04, 0x56, 0x5b, 0x00, 0x5b, 0x00, 0x7f, 0x01];
+        let breakdown = build_breakdown(&bytecode, &dispatcher, &functions);
+        assert_eq!(breakdown.data, 2);
+        assert_eq!(breakdown.unattributed, 2);
+    }
+}
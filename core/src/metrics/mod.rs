@@ -0,0 +1,241 @@
+pub mod clones;
+pub mod codesize;
+pub mod util;
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    time::Duration,
+};
+
+use clap::{AppSettings, Parser};
+use derive_builder::Builder;
+use heimdall_common::{
+    ether::{
+        bytecode::{get_bytecode_from_target, split_sections},
+        evm::core::vm::VM,
+        selectors::find_function_selectors,
+    },
+    utils::{
+        io::{
+            crash::enter_pass,
+            exit::record_partial_analysis,
+            logging::{set_logger_env, Logger},
+        },
+        strings::decode_hex,
+        threading::run_with_timeout,
+    },
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    disassemble::{disassemble, DisassemblerArgs},
+    error::Error,
+    minimize::util::basic_blocks,
+};
+
+use self::{
+    clones::{duplicated_bytes, find_clones, CloneGroup},
+    codesize::{build_breakdown, dispatcher_pcs, executed_pcs, CodesizeBreakdown},
+    util::{count_instructions, TraceShape},
+};
+
+#[derive(Debug, Clone, Parser, Builder)]
+#[clap(
+    about = "Report instruction, control flow, and stack metrics for EVM bytecode",
+    after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    global_setting = AppSettings::DeriveDisplayOrder,
+    override_usage = "heimdall metrics <TARGET> [OPTIONS]"
+)]
+pub struct MetricsArgs {
+    /// The target to measure, either a file, bytecode, contract address, or ENS name.
+    #[clap(required = true)]
+    pub target: String,
+
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// The RPC provider to use for fetching target bytecode.
+    #[clap(long = "rpc-url", short, default_value = "", hide_default_value = true)]
+    pub rpc_url: String,
+
+    /// The output directory to write the output to or 'print' to print to the console
+    #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
+    pub output: String,
+
+    /// The name for the output file
+    #[clap(long, short, default_value = "", hide_default_value = true)]
+    pub name: String,
+
+    /// The timeout for each function's symbolic execution in milliseconds.
+    #[clap(long, short, default_value = "10000", hide_default_value = true)]
+    pub timeout: u64,
+}
+
+impl MetricsArgsBuilder {
+    pub fn new() -> Self {
+        Self {
+            target: Some(String::new()),
+            verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
+            rpc_url: Some(String::new()),
+            output: Some(String::new()),
+            name: Some(String::new()),
+            timeout: Some(10000),
+        }
+    }
+}
+
+/// Control flow and stack metrics for a single function.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionMetrics {
+    pub selector: String,
+    pub entry_point: u128,
+    /// The number of distinct blocks reached by symbolic execution
+    pub basic_blocks: usize,
+    pub cyclomatic_complexity: usize,
+    pub max_stack_depth: usize,
+    /// Whether symbolic execution timed out, in which case the metrics above are empty
+    pub timed_out: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsResult {
+    pub size: usize,
+    pub instructions: usize,
+    /// The number of instructions of each opcode class, e.g. `arithmetic` or `storage`
+    pub opcode_classes: BTreeMap<String, usize>,
+    /// The number of instructions of each opcode
+    pub opcodes: BTreeMap<String, usize>,
+    /// The number of basic blocks in the bytecode, found statically
+    pub basic_blocks: usize,
+    pub functions: Vec<FunctionMetrics>,
+    /// The number of instructions executed at each stack depth, across every function
+    pub stack_depth_profile: BTreeMap<usize, usize>,
+    /// How the bytecode's size is split between the dispatcher, functions, and data
+    pub codesize: CodesizeBreakdown,
+    /// Sequences of basic blocks which appear more than once, such as inlined library code
+    pub clones: Vec<CloneGroup>,
+    /// The number of bytes which could be saved by keeping a single copy of each clone
+    pub duplicated_bytes: usize,
+}
+
+/// Measure the given target's bytecode. Instruction and block counts are found statically,
+/// while per-function metrics and the codesize breakdown come from symbolic execution of each
+/// selector.
+pub async fn metrics(args: MetricsArgs) -> Result<MetricsResult, Error> {
+    set_logger_env(&args.verbose);
+
+    // get a new logger
+    let (logger, _) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    let contract_bytecode = get_bytecode_from_target(&args.target, &args.rpc_url)
+        .await
+        .map_err(|e| Error::GenericError(e.to_string()))?;
+    let bytecode = decode_hex(&contract_bytecode.replacen("0x", "", 1))
+        .map_err(|e| Error::GenericError(format!("failed to decode bytecode: {e}")))?;
+
+    // trailing data and metadata aren't instructions
+    let code = &bytecode[..split_sections(&bytecode).code];
+    let (opcodes, opcode_classes) = count_instructions(code);
+
+    enter_pass("disassemble");
+    let disassembled_bytecode = disassemble(DisassemblerArgs {
+        target: contract_bytecode.clone(),
+        verbose: args.verbose.clone(),
+        rpc_url: args.rpc_url.clone(),
+        decimal_counter: false,
+        format: String::from("asm"),
+        verify: false,
+        name: String::new(),
+        output: String::new(),
+    })
+    .await
+    .map_err(|e| Error::GenericError(format!("failed to disassemble bytecode: {e}")))?;
+
+    let evm = VM::new(
+        contract_bytecode.clone(),
+        String::from("0x"),
+        String::from("0x6865696d64616c6c000000000061646472657373"),
+        String::from("0x6865696d64616c6c0000000000006f726967696e"),
+        String::from("0x6865696d64616c6c00000000000063616c6c6572"),
+        0,
+        u128::MAX,
+    );
+
+    let mut selectors = find_function_selectors(&evm, &disassembled_bytecode)
+        .into_iter()
+        .collect::<Vec<(String, u128)>>();
+    selectors.sort();
+    logger.info(&format!("found {} possible function selectors.", selectors.len()));
+
+    let mut functions = Vec::new();
+    let mut stack_depth_profile = BTreeMap::new();
+    let mut dispatcher = HashSet::new();
+    let mut function_pcs = BTreeMap::new();
+    for (selector, entry_point) in selectors {
+        enter_pass(&format!("symbolic execution (0x{selector})"));
+        let mut evm_clone = evm.clone();
+        let selector_clone = selector.clone();
+        let trace = run_with_timeout(
+            move || evm_clone.symbolic_exec_selector(&selector_clone, entry_point),
+            Duration::from_millis(args.timeout),
+        );
+
+        dispatcher.extend(dispatcher_pcs(&evm, &selector, entry_point));
+        let shape = trace.map(|(trace, _)| {
+            let mut pcs = HashSet::new();
+            executed_pcs(&trace, &mut pcs);
+            function_pcs.insert(format!("0x{selector}"), pcs);
+
+            TraceShape::from_trace(&trace)
+        });
+
+        let timed_out = shape.is_none();
+        if timed_out {
+            logger.warn(&format!("symbolic execution of '0x{selector}' timed out."));
+            record_partial_analysis();
+        }
+        let shape = shape.unwrap_or_default();
+
+        for (depth, count) in &shape.stack_depths {
+            *stack_depth_profile.entry(*depth).or_insert(0) += count;
+        }
+        functions.push(FunctionMetrics {
+            selector: format!("0x{selector}"),
+            entry_point,
+            basic_blocks: shape.blocks.len(),
+            cyclomatic_complexity: shape.cyclomatic_complexity(),
+            max_stack_depth: shape.max_stack_depth(),
+            timed_out,
+        });
+    }
+
+    let codesize = build_breakdown(&bytecode, &dispatcher, &function_pcs);
+    let clones = find_clones(
+        &bytecode
+            [..codesize.total - codesize.metadata - codesize.data - codesize.embedded_bytecode],
+    );
+    if !clones.is_empty() {
+        logger.info(&format!(
+            "found {} repeated block sequences, duplicating {} bytes.",
+            clones.len(),
+            duplicated_bytes(&clones)
+        ));
+    }
+
+    Ok(MetricsResult {
+        size: bytecode.len(),
+        instructions: opcodes.values().sum(),
+        opcode_classes,
+        opcodes,
+        basic_blocks: basic_blocks(code).len(),
+        functions,
+        stack_depth_profile,
+        codesize,
+        duplicated_bytes: duplicated_bytes(&clones),
+        clones,
+    })
+}
@@ -0,0 +1,132 @@
+use std::collections::{BTreeMap, HashSet};
+
+use heimdall_common::ether::evm::{core::opcodes::Opcode, ext::exec::VMTrace};
+
+/// The class an opcode belongs to, used to group instruction counts.
+pub fn opcode_class(opcode: &Opcode) -> &'static str {
+    match opcode.code {
+        _ if opcode.name == "unknown" => "invalid",
+        0x01..=0x0b => "arithmetic",
+        0x10..=0x15 => "comparison",
+        0x16..=0x1d => "bitwise",
+        0x20 => "keccak",
+        0x30..=0x3f => "environment",
+        0x40..=0x4a => "block",
+        0x50 => "stack",
+        0x80..=0x8f => "dup",
+        0x51..=0x53 | 0x59 => "memory",
+        0x54 | 0x55 => "storage",
+        0x00 | 0x56..=0x58 | 0x5a | 0x5b => "flow",
+        0x5f..=0x7f => "push",
+        0x90..=0x9f => "swap",
+        0xa0..=0xa4 => "log",
+        _ => "system",
+    }
+}
+
+/// Count each instruction in the bytecode, both by opcode name and by [`opcode_class`]. Push
+/// data is skipped.
+pub fn count_instructions(bytecode: &[u8]) -> (BTreeMap<String, usize>, BTreeMap<String, usize>) {
+    let mut opcodes = BTreeMap::new();
+    let mut classes = BTreeMap::new();
+    let mut program_counter = 0;
+
+    while program_counter < bytecode.len() {
+        let operation = Opcode::new(bytecode[program_counter]);
+        *opcodes.entry(operation.name.to_string()).or_insert(0) += 1;
+        *classes.entry(opcode_class(&operation).to_string()).or_insert(0) += 1;
+
+        // skip over any pushed bytes
+        if let Some(byte_count) = operation.name.strip_prefix("PUSH") {
+            program_counter += byte_count.parse::<usize>().unwrap_or(0);
+        }
+        program_counter += 1;
+    }
+
+    (opcodes, classes)
+}
+
+/// The shape of a function's symbolic execution trace.
+#[derive(Debug, Clone, Default)]
+pub struct TraceShape {
+    /// The first instruction of each distinct block which was executed
+    pub blocks: HashSet<u128>,
+    /// Each distinct transition between two blocks
+    pub edges: HashSet<(u128, u128)>,
+    /// The number of executed instructions at each stack depth
+    pub stack_depths: BTreeMap<usize, usize>,
+}
+
+impl TraceShape {
+    /// Walk the given trace, recording its blocks, edges, and stack depths.
+    pub fn from_trace(trace: &VMTrace) -> TraceShape {
+        let mut shape = TraceShape::default();
+        shape.walk(trace, None);
+        shape
+    }
+
+    fn walk(&mut self, trace: &VMTrace, parent: Option<u128>) {
+        let block = trace
+            .operations
+            .first()
+            .map(|operation| operation.last_instruction.instruction)
+            .unwrap_or(trace.instruction);
+
+        // a block which was already walked is only connected to again
+        let visited = !self.blocks.insert(block);
+        if let Some(parent) = parent {
+            self.edges.insert((parent, block));
+        }
+        if visited {
+            return
+        }
+
+        for operation in &trace.operations {
+            *self.stack_depths.entry(operation.stack.size()).or_insert(0) += 1;
+        }
+        for child in &trace.children {
+            self.walk(child, Some(block));
+        }
+    }
+
+    /// The cyclomatic complexity `E - N + 2` of the walked control flow graph.
+    pub fn cyclomatic_complexity(&self) -> usize {
+        (self.edges.len() + 2).saturating_sub(self.blocks.len()).max(1)
+    }
+
+    /// The deepest stack seen while executing the trace.
+    pub fn max_stack_depth(&self) -> usize {
+        self.stack_depths.keys().last().copied().unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::metrics::util::*;
+
+    #[test]
+    fn test_count_instructions() {
+        // PUSH1 0x01 PUSH1 0x02 ADD PUSH1 0x00 SSTORE STOP
+        let (opcodes, classes) =
+            count_instructions(&[0x60, 0x01, 0x60, 0x02, 0x01, 0x60, 0x00, 0x55, 0x00]);
+
+        assert_eq!(opcodes.get("PUSH1"), Some(&3));
+        assert_eq!(opcodes.get("ADD"), Some(&1));
+        assert_eq!(classes.get("push"), Some(&3));
+        assert_eq!(classes.get("storage"), Some(&1));
+        assert_eq!(classes.get("flow"), Some(&1));
+    }
+
+    #[test]
+    fn test_cyclomatic_complexity_of_branch() {
+        // a diamond: 0 -> {1, 2} -> 3
+        let shape = TraceShape {
+            blocks: HashSet::from([0, 1, 2, 3]),
+            edges: HashSet::from([(0, 1), (0, 2), (1, 3), (2, 3)]),
+            stack_depths: BTreeMap::from([(0, 1), (4, 2)]),
+        };
+
+        assert_eq!(shape.cyclomatic_complexity(), 2);
+        assert_eq!(shape.max_stack_depth(), 4);
+    }
+}
@@ -0,0 +1,265 @@
+pub mod util;
+
+use std::{
+    panic,
+    sync::{Arc, Mutex},
+};
+
+use clap::{AppSettings, Parser};
+use derive_builder::Builder;
+use fancy_regex::Regex;
+use heimdall_common::{
+    ether::bytecode::get_bytecode_from_target,
+    utils::{
+        io::logging::{set_logger_env, Logger},
+        strings::{decode_hex, encode_hex},
+    },
+};
+
+use crate::{
+    decompile::{decompile, DecompilerArgs},
+    error::Error,
+};
+
+use self::util::{basic_blocks, build_candidate, trim_trailing_stops};
+
+#[derive(Debug, Clone, Parser, Builder)]
+#[clap(
+    about = "Shrink bytecode which crashes or mis-decompiles down to a minimal reproducer",
+    after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    global_setting = AppSettings::DeriveDisplayOrder,
+    override_usage = "heimdall minimize <TARGET> --until-error <PATTERN> [OPTIONS]"
+)]
+pub struct MinimizeArgs {
+    /// The target to minimize, either a file, bytecode, contract address, or ENS name.
+    #[clap(required = true)]
+    pub target: String,
+
+    /// A regex which must match the panic message, error, or decompiled output for a candidate
+    /// to be kept.
+    #[clap(long = "until-error", short = 'u', required = true)]
+    pub until_error: String,
+
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// The RPC provider to use for fetching target bytecode.
+    #[clap(long = "rpc-url", short, default_value = "", hide_default_value = true)]
+    pub rpc_url: String,
+
+    /// The output directory to write the output to or 'print' to print to the console
+    #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
+    pub output: String,
+
+    /// The name for the output file
+    #[clap(long, short, default_value = "", hide_default_value = true)]
+    pub name: String,
+
+    /// The timeout for each function's symbolic execution in milliseconds.
+    #[clap(long, short, default_value = "10000", hide_default_value = true)]
+    pub timeout: u64,
+}
+
+impl MinimizeArgsBuilder {
+    pub fn new() -> Self {
+        Self {
+            target: Some(String::new()),
+            until_error: Some(String::new()),
+            verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
+            rpc_url: Some(String::new()),
+            output: Some(String::new()),
+            name: Some(String::new()),
+            timeout: Some(10000),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MinimizeResult {
+    /// The minimized bytecode, hex encoded
+    pub bytecode: String,
+    /// The number of bytes in the original and minimized bytecode
+    pub original_size: usize,
+    pub minimized_size: usize,
+    /// The number of candidates which were decompiled
+    pub attempts: usize,
+}
+
+/// Minimize the given target's bytecode using delta-debugging over its basic blocks. Blocks are
+/// removed for as long as decompiling the remaining bytecode still matches `--until-error`.
+pub async fn minimize(args: MinimizeArgs) -> Result<MinimizeResult, Error> {
+    use std::time::Instant;
+    let now = Instant::now();
+
+    set_logger_env(&args.verbose);
+
+    // get a new logger
+    let (logger, _) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    let pattern = Regex::new(&args.until_error)
+        .map_err(|e| Error::GenericError(format!("invalid --until-error pattern: {}", e)))?;
+
+    let contract_bytecode = get_bytecode_from_target(&args.target, &args.rpc_url)
+        .await
+        .map_err(|e| Error::GenericError(e.to_string()))?;
+    let bytecode = decode_hex(&contract_bytecode.replacen("0x", "", 1))
+        .map_err(|e| Error::GenericError(format!("failed to decode bytecode: {}", e)))?;
+
+    // panics may happen on symbolic execution threads, so they're collected by a hook rather
+    // than through join handles. the original hook is restored once minimization finishes.
+    let panics = Arc::new(Mutex::new(Vec::new()));
+    let original_hook = panic::take_hook();
+    let hook_panics = panics.clone();
+    panic::set_hook(Box::new(move |panic_info| {
+        if let Ok(mut panics) = hook_panics.lock() {
+            panics.push(panic_info.to_string());
+        }
+    }));
+
+    let mut oracle = Oracle { pattern, panics, timeout: args.timeout, attempts: 0 };
+    let result = delta_debug(&bytecode, &mut oracle, &logger);
+
+    panic::set_hook(original_hook);
+    let minimized = result?;
+
+    logger.info(&format!(
+        "minimized {} bytes to {} bytes in {} attempts.",
+        bytecode.len(),
+        minimized.len(),
+        oracle.attempts
+    ));
+    logger.debug(&format!("minimization completed in {} ms.", now.elapsed().as_millis()));
+
+    Ok(MinimizeResult {
+        bytecode: encode_hex(minimized.clone()),
+        original_size: bytecode.len(),
+        minimized_size: minimized.len(),
+        attempts: oracle.attempts,
+    })
+}
+
+/// Runs the ddmin algorithm over the basic blocks of `bytecode`, returning the smallest candidate
+/// which still reproduces.
+fn delta_debug(bytecode: &[u8], oracle: &mut Oracle, logger: &Logger) -> Result<Vec<u8>, Error> {
+    let blocks = basic_blocks(bytecode);
+    let mut kept: Vec<usize> = (0..blocks.len()).collect();
+
+    if !oracle.reproduces(bytecode) {
+        return Err(Error::GenericError(
+            "the target does not reproduce the given --until-error pattern.".to_string(),
+        ))
+    }
+    logger.debug(&format!("found {} basic blocks to minimize.", blocks.len()));
+
+    let mut granularity = 2;
+    while kept.len() >= 2 {
+        let chunk_size = kept.len().div_ceil(granularity);
+        let chunks: Vec<Vec<usize>> = kept.chunks(chunk_size).map(|c| c.to_vec()).collect();
+        let mut reduced = false;
+
+        // first try keeping a single chunk, then try removing a single chunk
+        for chunk in &chunks {
+            if oracle.reproduces(&build_candidate(bytecode, &blocks, chunk)) {
+                kept = chunk.clone();
+                granularity = 2;
+                reduced = true;
+                break
+            }
+        }
+        if !reduced {
+            for chunk in &chunks {
+                let complement: Vec<usize> =
+                    kept.iter().filter(|index| !chunk.contains(index)).cloned().collect();
+                if oracle.reproduces(&build_candidate(bytecode, &blocks, &complement)) {
+                    kept = complement;
+                    granularity = (granularity - 1).max(2);
+                    reduced = true;
+                    break
+                }
+            }
+        }
+
+        if reduced {
+            logger.debug(&format!("reduced to {} basic blocks.", kept.len()));
+        } else if granularity >= kept.len() {
+            break
+        } else {
+            granularity = (granularity * 2).min(kept.len());
+        }
+    }
+
+    let minimized = build_candidate(bytecode, &blocks, &kept);
+    let trimmed = trim_trailing_stops(&minimized);
+    if trimmed.len() < minimized.len() && oracle.reproduces(&trimmed) {
+        return Ok(trimmed)
+    }
+
+    Ok(minimized)
+}
+
+/// Decides whether a candidate bytecode still reproduces the failure.
+struct Oracle {
+    pattern: Regex,
+    panics: Arc<Mutex<Vec<String>>>,
+    timeout: u64,
+    attempts: usize,
+}
+
+impl Oracle {
+    fn reproduces(&mut self, candidate: &[u8]) -> bool {
+        self.attempts += 1;
+        if let Ok(mut panics) = self.panics.lock() {
+            panics.clear();
+        }
+
+        let args = DecompilerArgs {
+            target: encode_hex(candidate.to_vec()),
+            verbose: clap_verbosity_flag::Verbosity::new(0, 1),
+            rpc_url: String::new(),
+            default: true,
+            skip_resolving: true,
+            include_solidity: true,
+            include_yul: false,
+            include_interface: false,
+            output: String::new(),
+            name: String::new(),
+            timeout: self.timeout,
+            stream: false,
+            chain: String::from("ethereum"),
+            summarizer: String::new(),
+            usage: false,
+            artifact: String::new(),
+            include_pcs: false,
+            fragment: false,
+            entry_pc: String::from("0"),
+            no_follow_proxy: false,
+        };
+
+        // decompile on a separate thread with its own runtime, so a panic can't take down ours
+        let outcome = std::thread::spawn(move || -> Result<String, String> {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .map_err(|e| e.to_string())?;
+            runtime
+                .block_on(async {
+                    decompile(args).await.map(|result| result.source.unwrap_or_default())
+                })
+                .map_err(|e| e.to_string())
+        })
+        .join();
+
+        let mut messages = self.panics.lock().map(|panics| panics.clone()).unwrap_or_default();
+        match outcome {
+            Ok(Ok(source)) => messages.push(source),
+            Ok(Err(error)) => messages.push(error),
+            Err(_) => {}
+        }
+
+        messages.iter().any(|message| self.pattern.is_match(message).unwrap_or(false))
+    }
+}
@@ -0,0 +1,98 @@
+use std::ops::Range;
+
+use heimdall_common::ether::evm::core::opcodes::Opcode;
+
+/// Split the given bytecode into basic blocks. A block ends after a terminating or jumping
+/// instruction, and a new block begins at every `JUMPDEST`. Push data is never split.
+pub fn basic_blocks(bytecode: &[u8]) -> Vec<Range<usize>> {
+    let mut blocks = Vec::new();
+    let mut block_start = 0;
+    let mut program_counter = 0;
+
+    while program_counter < bytecode.len() {
+        let operation = Opcode::new(bytecode[program_counter]);
+
+        // a JUMPDEST always begins a new block
+        if operation.name == "JUMPDEST" && program_counter > block_start {
+            blocks.push(block_start..program_counter);
+            block_start = program_counter;
+        }
+
+        // skip over any pushed bytes
+        if let Some(byte_count) = operation.name.strip_prefix("PUSH") {
+            program_counter += byte_count.parse::<usize>().unwrap_or(0);
+        }
+        program_counter = (program_counter + 1).min(bytecode.len());
+
+        if matches!(
+            operation.name,
+            "STOP" | "JUMP" | "JUMPI" | "RETURN" | "REVERT" | "INVALID" | "SELFDESTRUCT"
+        ) {
+            blocks.push(block_start..program_counter);
+            block_start = program_counter;
+        }
+    }
+
+    if block_start < bytecode.len() {
+        blocks.push(block_start..bytecode.len());
+    }
+
+    blocks
+}
+
+/// Build a candidate bytecode which only keeps the given blocks. Removed blocks are overwritten
+/// with `STOP` so that the offsets of the remaining blocks, and therefore all jump targets, are
+/// preserved.
+pub fn build_candidate(bytecode: &[u8], blocks: &[Range<usize>], kept: &[usize]) -> Vec<u8> {
+    let mut candidate = vec![0u8; bytecode.len()];
+
+    for index in kept {
+        let block = blocks[*index].clone();
+        candidate[block.clone()].copy_from_slice(&bytecode[block]);
+    }
+
+    candidate
+}
+
+/// Remove trailing `STOP` bytes, which are equivalent to running off the end of the code.
+pub fn trim_trailing_stops(bytecode: &[u8]) -> Vec<u8> {
+    let length = bytecode.iter().rposition(|byte| *byte != 0).map(|i| i + 1).unwrap_or(0);
+    bytecode[..length].to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::minimize::util::*;
+
+    #[test]
+    fn test_basic_blocks_split_on_jumps_and_jumpdests() {
+        // PUSH1 0x04 JUMP INVALID JUMPDEST PUSH1 0x00 DUP1 REVERT
+        let bytecode = vec![0x60, 0x04, 0x56, 0xfe, 0x5b, 0x60, 0x00, 0x80, 0xfd];
+
+        assert_eq!(basic_blocks(&bytecode), vec![0..3, 3..4, 4..9]);
+    }
+
+    #[test]
+    fn test_basic_blocks_does_not_split_push_data() {
+        // PUSH2 0x5b56 STOP
+        let bytecode = vec![0x61, 0x5b, 0x56, 0x00];
+
+        assert_eq!(basic_blocks(&bytecode), vec![0..4]);
+    }
+
+    #[test]
+    fn test_build_candidate_preserves_offsets() {
+        let bytecode = vec![0x60, 0x04, 0x56, 0xfe, 0x5b, 0x60, 0x00, 0x80, 0xfd];
+        let blocks = basic_blocks(&bytecode);
+
+        let candidate = build_candidate(&bytecode, &blocks, &[0, 2]);
+
+        assert_eq!(candidate, vec![0x60, 0x04, 0x56, 0x00, 0x5b, 0x60, 0x00, 0x80, 0xfd]);
+    }
+
+    #[test]
+    fn test_trim_trailing_stops() {
+        assert_eq!(trim_trailing_stops(&[0x30, 0x31, 0x00, 0x00]), vec![0x30, 0x31]);
+        assert_eq!(trim_trailing_stops(&[0x00, 0x00]), Vec::<u8>::new());
+    }
+}
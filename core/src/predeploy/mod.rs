@@ -0,0 +1,318 @@
+use std::collections::BTreeMap;
+
+use clap::{AppSettings, Parser};
+use derive_builder::Builder;
+use ethers::{
+    types::{H160, U256},
+    utils::keccak256,
+};
+use heimdall_common::{
+    constants::{ADDRESS_REGEX, TRANSACTION_HASH_REGEX},
+    ether::{
+        bytecode::get_bytecode_from_target, create::create_address, evm::core::vm::VM,
+        rpc::get_transaction,
+    },
+    utils::{
+        io::logging::{set_logger_env, Logger},
+        strings::{decode_hex, encode_hex},
+    },
+};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::Error,
+    scan::{initializers::find_unprotected_initializers, rules::RuleSet, scan_bytecode, Finding},
+    simulate::SimulatedLog,
+};
+
+#[derive(Debug, Clone, Parser, Builder)]
+#[clap(
+    about = "Predict what a contract-creation transaction will deploy",
+    after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    global_setting = AppSettings::DeriveDisplayOrder,
+    override_usage = "heimdall predeploy <TARGET> [OPTIONS]"
+)]
+pub struct PredeployArgs {
+    /// The contract-creation transaction to analyze, either a file or hex of its calldata, i.e.
+    /// the init code and constructor arguments, or the hash of a pending transaction.
+    #[clap(required = true)]
+    pub target: String,
+
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// The RPC provider to use for fetching the pending transaction.
+    #[clap(long = "rpc-url", short, default_value = "", hide_default_value = true)]
+    pub rpc_url: String,
+
+    /// The address deploying the contract. Taken from the transaction if the target is a
+    /// transaction hash.
+    #[clap(long, default_value = "", hide_default_value = true)]
+    pub deployer: String,
+
+    /// The deployer's nonce, used with --deployer to predict the deployed contract's address.
+    /// Taken from the transaction if the target is a transaction hash.
+    #[clap(long)]
+    pub nonce: Option<u64>,
+
+    /// The value, in wei, sent with the deployment. Taken from the transaction if the target is a
+    /// transaction hash.
+    #[clap(long, default_value = "0", hide_default_value = true)]
+    pub value: u128,
+
+    /// The output directory to write the output to or 'print' to print to the console
+    #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
+    pub output: String,
+
+    /// The name for the output file
+    #[clap(long, short, default_value = "", hide_default_value = true)]
+    pub name: String,
+
+    /// The timeout for each function's symbolic execution in milliseconds.
+    #[clap(long, short, default_value = "10000", hide_default_value = true)]
+    pub timeout: u64,
+}
+
+impl PredeployArgsBuilder {
+    pub fn new() -> Self {
+        Self {
+            target: Some(String::new()),
+            verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
+            rpc_url: Some(String::new()),
+            deployer: Some(String::new()),
+            nonce: Some(None),
+            value: Some(0),
+            output: Some(String::new()),
+            name: Some(String::new()),
+            timeout: Some(10000),
+        }
+    }
+}
+
+lazy_static! {
+    /// The topic of `OwnershipTransferred(address,address)`, which OpenZeppelin's `Ownable` emits
+    /// from its constructor.
+    static ref OWNERSHIP_TRANSFERRED_TOPIC: U256 =
+        U256::from(keccak256("OwnershipTransferred(address,address)"));
+}
+
+/// Who will own the deployed contract.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PredictedOwner {
+    pub address: String,
+    /// The storage slot the owner is written to, if the constructor writes it
+    pub slot: Option<U256>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PredeployResult {
+    /// Whether the constructor exited with `STOP` or `RETURN`
+    pub success: bool,
+    pub exitcode: u128,
+    pub gas_used: u128,
+    pub deployer: String,
+    /// The address the contract will be deployed to, if the deployer's nonce is known
+    pub address: Option<String>,
+    /// The runtime code the constructor returns, which is what will be deployed
+    pub runtime_bytecode: String,
+    pub runtime_code_hash: String,
+    /// Every storage slot the constructor writes
+    pub storage: BTreeMap<U256, U256>,
+    pub events: Vec<SimulatedLog>,
+    pub owner: Option<PredictedOwner>,
+    /// Builtin scan rules which match the runtime code, and initializers anyone can call
+    pub findings: Vec<Finding>,
+}
+
+/// The init code and deployment context of a contract-creation transaction.
+struct Deployment {
+    init_code: String,
+    deployer: H160,
+    nonce: Option<u64>,
+    value: u128,
+}
+
+/// Run the init code of a contract-creation transaction in heimdall's VM to predict what it will
+/// deploy: the runtime code, the storage the constructor sets, and who will own the contract.
+/// The runtime code is then checked against the builtin scan rules, so unverified launches can be
+/// assessed before they land.
+pub async fn predeploy(args: PredeployArgs) -> Result<PredeployResult, Error> {
+    set_logger_env(&args.verbose);
+
+    // get a new logger
+    let (logger, _) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    let deployment = get_deployment(&args).await?;
+    let address = deployment.nonce.map(|nonce| create_address(deployment.deployer, nonce));
+    let deployer = format!("0x{}", encode_hex(deployment.deployer.as_bytes().to_vec()));
+
+    let mut vm = VM::new(
+        deployment.init_code,
+        String::from("0x"),
+        match address {
+            Some(address) => format!("0x{}", encode_hex(address.as_bytes().to_vec())),
+            None => String::from("0x6865696d64616c6c000000000061646472657373"),
+        },
+        deployer.clone(),
+        deployer.clone(),
+        deployment.value,
+        u128::MAX,
+    );
+    let execution = vm.execute();
+    let success = execution.exitcode == 0 || execution.exitcode == 10;
+
+    let runtime_bytecode = match success {
+        true => execution.returndata.clone(),
+        false => {
+            logger.warn(&format!(
+                "constructor failed with exit code {}, so nothing will be deployed.",
+                execution.exitcode
+            ));
+            Vec::new()
+        }
+    };
+    let storage: BTreeMap<U256, U256> = vm
+        .storage
+        .storage
+        .iter()
+        .map(|(slot, value)| (U256::from(slot), U256::from(value)))
+        .collect();
+    let events: Vec<SimulatedLog> = execution
+        .events
+        .iter()
+        .map(|log| SimulatedLog {
+            topics: log.topics.clone(),
+            data: format!("0x{}", encode_hex(log.data.clone())),
+        })
+        .collect();
+    let owner = find_owner(deployment.deployer, &storage, &events);
+
+    let mut findings = Vec::new();
+    if !runtime_bytecode.is_empty() {
+        findings.extend(scan_bytecode("runtime", &runtime_bytecode, &RuleSet::builtin()));
+        findings.extend(
+            find_unprotected_initializers(
+                "runtime",
+                &encode_hex(runtime_bytecode.clone()),
+                args.timeout,
+            )
+            .await?,
+        );
+        findings.sort_by(|a, b| b.severity.cmp(&a.severity).then(a.rule.cmp(&b.rule)));
+    }
+
+    logger.info(&format!(
+        "constructor deploys {} bytes of runtime code, writes {} storage slots, and emits {} events.",
+        runtime_bytecode.len(),
+        storage.len(),
+        events.len()
+    ));
+    if let Some(owner) = &owner {
+        logger.info(&format!("the deployed contract will be owned by {} .", owner.address));
+    }
+
+    Ok(PredeployResult {
+        success,
+        exitcode: execution.exitcode,
+        gas_used: execution.gas_used,
+        deployer,
+        address: address.map(|address| format!("0x{}", encode_hex(address.as_bytes().to_vec()))),
+        runtime_code_hash: format!("0x{}", encode_hex(keccak256(&runtime_bytecode).to_vec())),
+        runtime_bytecode: format!("0x{}", encode_hex(runtime_bytecode)),
+        storage,
+        events,
+        owner,
+        findings,
+    })
+}
+
+/// Read the init code and deployment context from the target, fetching the transaction if the
+/// target is a transaction hash. Values given as arguments are used for anything the target
+/// doesn't specify.
+async fn get_deployment(args: &PredeployArgs) -> Result<Deployment, Error> {
+    if ADDRESS_REGEX.is_match(&args.target).unwrap_or(false) {
+        return Err(Error::GenericError(format!(
+            "'{}' is an address, but predeploy analyzes a contract-creation transaction before it \
+             lands. Pass its calldata or transaction hash instead.",
+            args.target
+        )))
+    }
+
+    if TRANSACTION_HASH_REGEX.is_match(&args.target).unwrap_or(false) {
+        let transaction = get_transaction(&args.target, &args.rpc_url)
+            .await
+            .map_err(|e| Error::GenericError(e.to_string()))?;
+
+        if let Some(to) = transaction.to {
+            return Err(Error::GenericError(format!(
+                "transaction '{}' calls 0x{} rather than creating a contract.",
+                args.target,
+                encode_hex(to.as_bytes().to_vec())
+            )))
+        }
+
+        return Ok(Deployment {
+            init_code: encode_hex(transaction.input.to_vec()),
+            deployer: transaction.from,
+            nonce: Some(transaction.nonce.as_u64()),
+            value: transaction.value.try_into().map_err(|_| {
+                Error::GenericError(format!("deployment value {} is too large.", transaction.value))
+            })?,
+        })
+    }
+
+    let init_code = get_bytecode_from_target(&args.target, &args.rpc_url)
+        .await
+        .map_err(|e| Error::GenericError(e.to_string()))?;
+    let deployer = match args.deployer.is_empty() {
+        true => "0x6865696d64616c6c00000000000063616c6c6572",
+        false => args.deployer.as_str(),
+    };
+    let deployer = decode_hex(&deployer.replacen("0x", "", 1))
+        .ok()
+        .filter(|deployer| deployer.len() == 20)
+        .ok_or_else(|| Error::GenericError(format!("invalid deployer '{deployer}'")))?;
+
+    Ok(Deployment {
+        init_code,
+        deployer: H160::from_slice(&deployer),
+        // the default deployer is made up, so there's no address to predict for it
+        nonce: args.nonce.filter(|_| !args.deployer.is_empty()),
+        value: args.value,
+    })
+}
+
+/// Who will own the deployed contract. An `OwnershipTransferred` event from the constructor names
+/// the owner outright. Otherwise, the deployer is the owner if the constructor writes its address
+/// to storage.
+fn find_owner(
+    deployer: H160,
+    storage: &BTreeMap<U256, U256>,
+    events: &[SimulatedLog],
+) -> Option<PredictedOwner> {
+    let transferred_to = events
+        .iter()
+        .rev()
+        .find(|log| log.topics.len() == 3 && log.topics[0] == *OWNERSHIP_TRANSFERRED_TOPIC)
+        .map(|log| log.topics[2]);
+    let owner = transferred_to.unwrap_or_else(|| U256::from_big_endian(deployer.as_bytes()));
+
+    // the owner is often packed into a slot with other values, so only its low 20 bytes are
+    // compared
+    let mask = (U256::one() << 160) - 1;
+    let slot = storage.iter().find(|(_, value)| **value & mask == owner).map(|(slot, _)| *slot);
+
+    // without an event, a deployer which isn't written anywhere isn't known to own anything
+    if slot.is_none() && transferred_to.is_none() {
+        return None
+    }
+
+    let mut word = [0u8; 32];
+    owner.to_big_endian(&mut word);
+    Some(PredictedOwner { address: format!("0x{}", encode_hex(word[12..].to_vec())), slot })
+}
@@ -0,0 +1,478 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::error::Error;
+
+/// The facts a query runs over, as the tuples of each relation by name.
+pub type Database = BTreeMap<String, BTreeSet<Vec<String>>>;
+
+/// A variable, such as `F`, or a constant, such as `"view"` or `0x01`. `_` matches anything.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Term {
+    Variable(String),
+    Constant(String),
+    Wildcard,
+}
+
+/// A relation applied to terms, i.e. `guard(F, C)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Atom {
+    pub relation: String,
+    pub terms: Vec<Term>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    /// The left side contains the right, ignoring case
+    Contains,
+}
+
+/// A single condition in a rule's or query's body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Literal {
+    Atom(Atom),
+    /// Holds when no fact matches the atom
+    Not(Atom),
+    Compare(Term, Comparison, Term),
+}
+
+/// `head :- body.`, deriving a fact for the head from each way the body holds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub head: Atom,
+    pub body: Vec<Literal>,
+}
+
+/// Rules, followed by the body to query for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Program {
+    pub rules: Vec<Rule>,
+    pub query: Vec<Literal>,
+}
+
+/// The variables a query binds, and each distinct way it holds.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Identifier(String),
+    String(String),
+    Symbol(&'static str),
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, Error> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            // comments run to the end of the line
+            '%' => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break
+                    }
+                }
+            }
+            '"' => {
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => value.extend(chars.next()),
+                        Some(c) => value.push(c),
+                        None => {
+                            return Err(Error::GenericError("unterminated string in query".into()))
+                        }
+                    }
+                }
+                tokens.push(Token::String(value));
+            }
+            ':' if chars.next_if_eq(&'-').is_some() => tokens.push(Token::Symbol(":-")),
+            '?' if chars.next_if_eq(&'-').is_some() => tokens.push(Token::Symbol("?-")),
+            '!' if chars.next_if_eq(&'=').is_some() => tokens.push(Token::Symbol("!=")),
+            '!' => tokens.push(Token::Symbol("not")),
+            '(' => tokens.push(Token::Symbol("(")),
+            ')' => tokens.push(Token::Symbol(")")),
+            ',' => tokens.push(Token::Symbol(",")),
+            '.' => tokens.push(Token::Symbol(".")),
+            '=' => tokens.push(Token::Symbol("=")),
+            '~' => tokens.push(Token::Symbol("~")),
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut identifier = String::from(c);
+                while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || *c == '_') {
+                    identifier.push(c);
+                }
+                tokens.push(match identifier.as_str() {
+                    "not" => Token::Symbol("not"),
+                    _ => Token::Identifier(identifier),
+                });
+            }
+            c => return Err(Error::GenericError(format!("unexpected '{c}' in query"))),
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn eat(&mut self, symbol: &str) -> bool {
+        match self.peek() {
+            Some(Token::Symbol(s)) if *s == symbol => {
+                self.position += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn expect(&mut self, symbol: &str) -> Result<(), Error> {
+        match self.eat(symbol) {
+            true => Ok(()),
+            false => Err(Error::GenericError(format!(
+                "expected '{symbol}' in query, found {}",
+                self.peek().map(|token| format!("{token:?}")).unwrap_or("the end".to_string())
+            ))),
+        }
+    }
+
+    fn term(&mut self) -> Result<Term, Error> {
+        match self.next() {
+            Some(Token::String(value)) => Ok(Term::Constant(value)),
+            Some(Token::Identifier(name)) if name == "_" => Ok(Term::Wildcard),
+            Some(Token::Identifier(name))
+                if name.starts_with(|c: char| c.is_uppercase() || c == '_') =>
+            {
+                Ok(Term::Variable(name))
+            }
+            Some(Token::Identifier(value)) => Ok(Term::Constant(value)),
+            token => Err(Error::GenericError(format!("expected a term in query, found {token:?}"))),
+        }
+    }
+
+    fn atom(&mut self) -> Result<Atom, Error> {
+        let relation = match self.next() {
+            Some(Token::Identifier(name)) => name,
+            token => {
+                return Err(Error::GenericError(format!(
+                    "expected a relation in query, found {token:?}"
+                )))
+            }
+        };
+
+        let mut terms = Vec::new();
+        self.expect("(")?;
+        if !self.eat(")") {
+            loop {
+                terms.push(self.term()?);
+                if self.eat(")") {
+                    break
+                }
+                self.expect(",")?;
+            }
+        }
+        Ok(Atom { relation, terms })
+    }
+
+    fn literal(&mut self) -> Result<Literal, Error> {
+        if self.eat("not") {
+            return Ok(Literal::Not(self.atom()?))
+        }
+
+        // an identifier followed by '(' is an atom, anything else is a comparison
+        if matches!(self.tokens.get(self.position + 1), Some(Token::Symbol("("))) {
+            return Ok(Literal::Atom(self.atom()?))
+        }
+        let left = self.term()?;
+        let comparison = match self.next() {
+            Some(Token::Symbol("=")) => Comparison::Equal,
+            Some(Token::Symbol("!=")) => Comparison::NotEqual,
+            Some(Token::Symbol("~")) => Comparison::Contains,
+            token => {
+                return Err(Error::GenericError(format!(
+                    "expected '=', '!=', or '~' in query, found {token:?}"
+                )))
+            }
+        };
+        Ok(Literal::Compare(left, comparison, self.term()?))
+    }
+
+    fn body(&mut self) -> Result<Vec<Literal>, Error> {
+        let mut body = vec![self.literal()?];
+        while self.eat(",") {
+            body.push(self.literal()?);
+        }
+        Ok(body)
+    }
+}
+
+/// Parse a program of rules, i.e. `open(F) :- function(F, _, _), not guard(F, _).`, followed by
+/// a query, i.e. `?- open(F), call(F, C), C ~ "23b872dd".`. The `?-` and final `.` are optional.
+pub fn parse(source: &str) -> Result<Program, Error> {
+    let mut parser = Parser { tokens: tokenize(source)?, position: 0 };
+    let mut rules = Vec::new();
+
+    loop {
+        if parser.peek().is_none() {
+            return Err(Error::GenericError("query is empty".to_string()))
+        }
+        if parser.eat("?-") {
+            break
+        }
+
+        let body = parser.body()?;
+        if !parser.eat(":-") {
+            // a body which isn't a rule's head is the query
+            parser.eat(".");
+            if parser.peek().is_some() {
+                return Err(Error::GenericError("the query must come after every rule".into()))
+            }
+            return Ok(Program { rules, query: body })
+        }
+
+        let head = match body.as_slice() {
+            [Literal::Atom(head)] => head.clone(),
+            _ => return Err(Error::GenericError("a rule's head must be a single atom".into())),
+        };
+        let body = parser.body()?;
+        parser.expect(".")?;
+        rules.push(Rule { head, body });
+    }
+
+    let query = parser.body()?;
+    parser.eat(".");
+    if parser.peek().is_some() {
+        return Err(Error::GenericError("the query must come after every rule".into()))
+    }
+    Ok(Program { rules, query })
+}
+
+type Binding = HashMap<String, String>;
+
+/// The value a term has under a binding, if it's bound.
+fn resolve(term: &Term, binding: &Binding) -> Option<String> {
+    match term {
+        Term::Constant(value) => Some(value.clone()),
+        Term::Variable(name) => binding.get(name).cloned(),
+        Term::Wildcard => None,
+    }
+}
+
+/// Extend a binding so the atom's terms match a fact, if they can.
+fn unify(atom: &Atom, fact: &[String], binding: &Binding) -> Option<Binding> {
+    if atom.terms.len() != fact.len() {
+        return None
+    }
+
+    let mut binding = binding.clone();
+    for (term, value) in atom.terms.iter().zip(fact) {
+        match term {
+            Term::Wildcard => {}
+            Term::Constant(constant) if constant != value => return None,
+            Term::Constant(_) => {}
+            Term::Variable(name) => match binding.get(name) {
+                Some(bound) if bound != value => return None,
+                Some(_) => {}
+                None => {
+                    binding.insert(name.clone(), value.clone());
+                }
+            },
+        }
+    }
+    Some(binding)
+}
+
+/// Each binding under which every literal in the body holds. Atoms are matched first, in order,
+/// so negations and comparisons can use any variable the atoms bind.
+fn solve(body: &[Literal], database: &Database) -> Result<Vec<Binding>, Error> {
+    let empty = BTreeSet::new();
+    let mut bindings = vec![Binding::new()];
+
+    for literal in body.iter().filter(|literal| matches!(literal, Literal::Atom(_))) {
+        let Literal::Atom(atom) = literal else { continue };
+        let facts = database.get(&atom.relation).unwrap_or(&empty);
+        bindings = bindings
+            .iter()
+            .flat_map(|binding| facts.iter().filter_map(|fact| unify(atom, fact, binding)))
+            .collect();
+    }
+
+    for literal in body {
+        match literal {
+            Literal::Atom(_) => {}
+            Literal::Not(atom) => {
+                let facts = database.get(&atom.relation).unwrap_or(&empty);
+                bindings.retain(|binding| {
+                    !facts.iter().any(|fact| unify(atom, fact, binding).is_some())
+                });
+            }
+            Literal::Compare(left, comparison, right) => {
+                let mut kept = Vec::new();
+                for binding in bindings {
+                    let (Some(left), Some(right)) =
+                        (resolve(left, &binding), resolve(right, &binding))
+                    else {
+                        return Err(Error::GenericError(
+                            "comparisons may only use variables which a relation binds".into(),
+                        ))
+                    };
+                    let holds = match comparison {
+                        Comparison::Equal => left == right,
+                        Comparison::NotEqual => left != right,
+                        Comparison::Contains => left.to_lowercase().contains(&right.to_lowercase()),
+                    };
+                    if holds {
+                        kept.push(binding);
+                    }
+                }
+                bindings = kept;
+            }
+        }
+    }
+
+    Ok(bindings)
+}
+
+/// Run a program over the database. Rules are applied in order, each until it derives nothing
+/// new, so a rule may be recursive, and may negate any relation defined before it.
+pub fn evaluate(program: &Program, database: &Database) -> Result<QueryResult, Error> {
+    let mut database = database.clone();
+
+    for rule in &program.rules {
+        loop {
+            let derived = solve(&rule.body, &database)?
+                .iter()
+                .map(|binding| {
+                    rule.head
+                        .terms
+                        .iter()
+                        .map(|term| {
+                            resolve(term, binding).ok_or_else(|| {
+                                Error::GenericError(format!(
+                                    "every variable in the head of '{}' must be bound by its body",
+                                    rule.head.relation
+                                ))
+                            })
+                        })
+                        .collect::<Result<Vec<String>, Error>>()
+                })
+                .collect::<Result<Vec<Vec<String>>, Error>>()?;
+
+            let facts = database.entry(rule.head.relation.clone()).or_default();
+            let count = facts.len();
+            facts.extend(derived);
+            if facts.len() == count {
+                break
+            }
+        }
+    }
+
+    // the columns are the query's variables, in the order they first appear
+    let mut columns = Vec::new();
+    for literal in &program.query {
+        let terms = match literal {
+            Literal::Atom(atom) => atom.terms.clone(),
+            Literal::Not(_) => Vec::new(),
+            Literal::Compare(left, _, right) => vec![left.clone(), right.clone()],
+        };
+        for term in terms {
+            if let Term::Variable(name) = term {
+                if !columns.contains(&name) {
+                    columns.push(name);
+                }
+            }
+        }
+    }
+
+    let rows = solve(&program.query, &database)?
+        .iter()
+        .map(|binding| {
+            columns.iter().map(|column| binding.get(column).cloned().unwrap_or_default()).collect()
+        })
+        .collect::<BTreeSet<Vec<String>>>()
+        .into_iter()
+        .collect();
+
+    Ok(QueryResult { columns, rows })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::query::datalog::*;
+
+    fn database() -> Database {
+        let mut database = Database::new();
+        let mut insert = |relation: &str, fact: &[&str]| {
+            database
+                .entry(relation.to_string())
+                .or_default()
+                .insert(fact.iter().map(|value| value.to_string()).collect());
+        };
+        insert("function", &["0x01", "transfer(address,uint256)", "nonpayable"]);
+        insert("function", &["0x02", "sweep(address)", "nonpayable"]);
+        insert("function", &["0x03", "balanceOf(address)", "view"]);
+        insert("guard", &["0x02", "if (msg.sender == storage[0]) { .. }"]);
+        insert("call", &["0x01", "address(arg0).call(0x23b872dd)"]);
+        insert("call", &["0x02", "address(arg0).call(0x23b872dd)"]);
+        insert("edge", &["a", "b"]);
+        insert("edge", &["b", "c"]);
+        database
+    }
+
+    #[test]
+    fn test_query() {
+        let program = parse(
+            "function(F, S, M), M != \"view\", not guard(F, _), call(F, C), C ~ \"23B872DD\"",
+        )
+        .unwrap();
+        let result = evaluate(&program, &database()).unwrap();
+
+        assert_eq!(result.columns, vec!["F", "S", "M", "C"]);
+        assert_eq!(result.rows.len(), 1);
+        assert_eq!(result.rows[0][1], "transfer(address,uint256)");
+    }
+
+    #[test]
+    fn test_recursive_rules() {
+        let program = parse(
+            "path(X, Y) :- edge(X, Y).
+             path(X, Z) :- path(X, Y), edge(Y, Z).
+             ?- path(a, Z).",
+        )
+        .unwrap();
+        let result = evaluate(&program, &database()).unwrap();
+
+        assert_eq!(result.rows, vec![vec!["b".to_string()], vec!["c".to_string()]]);
+    }
+
+    #[test]
+    fn test_invalid_queries() {
+        assert!(parse("").is_err());
+        assert!(parse("function(F, S").is_err());
+        assert!(parse("guard(F, _), edge(a, b) :- edge(a, b).").is_err());
+
+        let unbound = parse("function(F, _, _), X = \"view\"").unwrap();
+        assert!(evaluate(&unbound, &database()).is_err());
+    }
+}
@@ -0,0 +1,82 @@
+use crate::snapshot::diff::SnapshotSummary;
+
+use super::datalog::Database;
+
+/// Each relation a query can use, with its columns.
+pub const RELATIONS: &[(&str, &str)] = &[
+    ("function", "selector, signature, mutability"),
+    ("guard", "selector, condition"),
+    ("call", "selector, call"),
+    ("address", "selector, address"),
+    ("storage", "selector, slot"),
+    ("writes", "selector, slot"),
+    ("event", "selector, topic"),
+    ("error", "selector, error"),
+    ("revert", "selector, reason"),
+    ("delay", "selector, condition"),
+    ("self_destruct", "selector"),
+    ("admin", "selector, caller"),
+    ("risk", "selector, kind"),
+];
+
+/// The facts a snapshot recovered, as a database to query. A function's signature is its
+/// selector when it wasn't resolved, and conditions are given without their `if (..) { .. }`.
+pub fn snapshot_facts(summary: &SnapshotSummary) -> Database {
+    let mut database = Database::new();
+    for (relation, _) in RELATIONS {
+        database.insert(relation.to_string(), Default::default());
+    }
+    let mut insert = |relation: &str, fact: Vec<String>| {
+        database.entry(relation.to_string()).or_default().insert(fact);
+    };
+
+    for (selector, function) in &summary.functions {
+        insert(
+            "function",
+            vec![
+                selector.clone(),
+                function.signature.clone().unwrap_or_else(|| selector.clone()),
+                function.mutability().to_string(),
+            ],
+        );
+
+        let relations = [
+            ("guard", &function.guards),
+            ("call", &function.external_calls),
+            ("address", &function.addresses),
+            ("storage", &function.storage),
+            ("writes", &function.writes),
+            ("event", &function.events),
+            ("error", &function.errors),
+            ("revert", &function.reverts),
+            ("delay", &function.delays),
+        ];
+        for (relation, values) in relations {
+            for value in values {
+                let value = value
+                    .strip_prefix("if (")
+                    .and_then(|value| value.strip_suffix(") { .. }"))
+                    .unwrap_or(value);
+                insert(relation, vec![selector.clone(), value.to_string()]);
+            }
+        }
+
+        if function.self_destructs {
+            insert("self_destruct", vec![selector.clone()]);
+        }
+    }
+
+    for function in &summary.governance.functions {
+        for caller in &function.callers {
+            insert("admin", vec![function.selector.clone(), caller.clone()]);
+        }
+    }
+    for risk in &summary.governance.centralization_risks {
+        // the kind's name, i.e. `self_destruct`, rather than its label, which may be translated
+        if let Ok(serde_json::Value::String(kind)) = serde_json::to_value(risk.kind) {
+            insert("risk", vec![risk.selector.clone(), kind]);
+        }
+    }
+
+    database
+}
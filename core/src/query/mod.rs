@@ -0,0 +1,154 @@
+pub mod datalog;
+pub mod facts;
+
+use clap::{AppSettings, Parser};
+use derive_builder::Builder;
+use heimdall_common::utils::io::logging::{set_logger_env, Logger};
+
+use crate::{
+    error::Error,
+    snapshot::{diff::SnapshotSummary, snapshot, SnapshotArgsBuilder},
+};
+
+use self::{
+    datalog::{evaluate, parse, Literal, QueryResult},
+    facts::{snapshot_facts, RELATIONS},
+};
+
+#[derive(Debug, Clone, Parser, Builder)]
+#[clap(
+    about = "Query the functions, calls, storage, and guards recovered from a contract",
+    after_help = "Relations: function(selector, signature, mutability), guard(selector, condition), \
+                  call(selector, call), address(selector, address), storage(selector, slot), \
+                  writes(selector, slot), event(selector, topic), error(selector, error), \
+                  revert(selector, reason), delay(selector, condition), \
+                  self_destruct(selector), admin(selector, caller), risk(selector, kind)\n\n\
+                  For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    global_setting = AppSettings::DeriveDisplayOrder,
+    override_usage = "heimdall query <TARGET> <QUERY> [OPTIONS]"
+)]
+pub struct QueryArgs {
+    /// The target to query, either a file, bytecode, contract address, or ENS name. With
+    /// --from-snapshot, a `snapshot.json` written by `heimdall snapshot`.
+    #[clap(required = true)]
+    pub target: String,
+
+    /// The query, in datalog. Rules such as `open(F) :- function(F, _, _), not guard(F, _).` may
+    /// come before it, and comparisons are written `X = "..."`, `X != "..."`, or `X ~ "..."` for
+    /// a case-insensitive substring.
+    #[clap(required = true)]
+    pub query: String,
+
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// The RPC provider to use for fetching target bytecode.
+    #[clap(long = "rpc-url", short, default_value = "", hide_default_value = true)]
+    pub rpc_url: String,
+
+    /// Read facts from a `snapshot.json` written by `heimdall snapshot`, rather than analyzing
+    /// the target.
+    #[clap(long = "from-snapshot")]
+    pub from_snapshot: bool,
+
+    /// Whether to skip resolving function selectors.
+    #[clap(long = "skip-resolving")]
+    pub skip_resolving: bool,
+
+    /// The output directory to write the output to or 'print' to print to the console
+    #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
+    pub output: String,
+
+    /// The name for the output file
+    #[clap(long, short, default_value = "", hide_default_value = true)]
+    pub name: String,
+
+    /// The timeout for each function's symbolic execution in milliseconds.
+    #[clap(long, short, default_value = "10000", hide_default_value = true)]
+    pub timeout: u64,
+}
+
+impl QueryArgsBuilder {
+    pub fn new() -> Self {
+        Self {
+            target: Some(String::new()),
+            query: Some(String::new()),
+            verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
+            rpc_url: Some(String::new()),
+            from_snapshot: Some(false),
+            skip_resolving: Some(false),
+            output: Some(String::new()),
+            name: Some(String::new()),
+            timeout: Some(10000),
+        }
+    }
+}
+
+/// Answer a datalog query over the facts a snapshot of the target recovers, i.e. which
+/// functions anyone can call that call `transferFrom`:
+///
+/// `function(F, S, M), M != "view", not guard(F, _), call(F, C), C ~ "23b872dd"`
+pub async fn query(args: QueryArgs) -> Result<QueryResult, Error> {
+    set_logger_env(&args.verbose);
+
+    // get a new logger
+    let (logger, _) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    // check the query before spending time on analysis
+    let program = parse(&args.query)?;
+    let defined = program.rules.iter().map(|rule| rule.head.relation.as_str()).collect::<Vec<_>>();
+    for literal in program.rules.iter().flat_map(|rule| &rule.body).chain(&program.query) {
+        let (Literal::Atom(atom) | Literal::Not(atom)) = literal else { continue };
+        if !defined.contains(&atom.relation.as_str()) &&
+            !RELATIONS.iter().any(|(relation, _)| *relation == atom.relation)
+        {
+            return Err(Error::GenericError(format!(
+                "unknown relation '{}', expected one of: {}",
+                atom.relation,
+                RELATIONS.iter().map(|(relation, _)| *relation).collect::<Vec<_>>().join(", ")
+            )))
+        }
+    }
+
+    let summary = match args.from_snapshot {
+        true => {
+            let contents = std::fs::read_to_string(&args.target).map_err(|e| {
+                Error::GenericError(format!("failed to read '{}': {e}", args.target))
+            })?;
+            serde_json::from_str::<SnapshotSummary>(&contents).map_err(|e| {
+                Error::GenericError(format!("failed to parse '{}': {e}", args.target))
+            })?
+        }
+        false => {
+            let result = snapshot(
+                SnapshotArgsBuilder::new()
+                    .target(args.target.clone())
+                    .verbose(args.verbose.clone())
+                    .rpc_url(args.rpc_url.clone())
+                    .skip_resolving(args.skip_resolving)
+                    .timeout(args.timeout)
+                    .build()
+                    .map_err(|e| Error::GenericError(e.to_string()))?,
+            )
+            .await
+            .map_err(|e| Error::GenericError(format!("failed to snapshot contract: {e}")))?;
+            SnapshotSummary::from_snapshots(&result.snapshots)
+        }
+    };
+
+    let database = snapshot_facts(&summary);
+    logger.debug(&format!(
+        "querying {} facts about {} functions.",
+        database.values().map(|facts| facts.len()).sum::<usize>(),
+        summary.functions.len()
+    ));
+
+    let result = evaluate(&program, &database)?;
+    logger.info(&format!("query matched {} rows.", result.rows.len()));
+
+    Ok(result)
+}
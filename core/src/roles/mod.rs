@@ -0,0 +1,212 @@
+pub mod output;
+pub mod util;
+
+use std::{collections::BTreeSet, time::Duration};
+
+use clap::{AppSettings, Parser};
+use derive_builder::Builder;
+use ethers::types::U256;
+use heimdall_common::{
+    constants::ADDRESS_REGEX,
+    ether::{
+        bytecode::get_bytecode_from_target, evm::core::vm::VM, rpc::get_storage_at,
+        selectors::find_function_selectors,
+    },
+    utils::{
+        io::{
+            crash::enter_pass,
+            exit::record_partial_analysis,
+            logging::{set_logger_env, Logger},
+        },
+        threading::run_with_timeout,
+    },
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    disassemble::{disassemble, DisassemblerArgs},
+    error::Error,
+};
+
+use self::util::{admin_slot, role_name, Role, RoleGraphBuilder, CALLER};
+
+#[derive(Debug, Clone, Parser, Builder)]
+#[clap(
+    about = "Recover an AccessControl contract's roles, their admins, and the functions they gate",
+    after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    global_setting = AppSettings::DeriveDisplayOrder,
+    override_usage = "heimdall roles <TARGET> [OPTIONS]"
+)]
+pub struct RolesArgs {
+    /// The target to recover the roles of, either a file, bytecode, contract address, or ENS
+    /// name.
+    #[clap(required = true)]
+    pub target: String,
+
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// The RPC provider to use for fetching target bytecode.
+    #[clap(long = "rpc-url", short, default_value = "", hide_default_value = true)]
+    pub rpc_url: String,
+
+    /// The format to generate, one of 'json' or 'dot'.
+    #[clap(long, short, default_value = "json")]
+    pub format: String,
+
+    /// The block number to read each role's admin from the target's storage at. Admins are set
+    /// at runtime, so they're only recovered for a contract address.
+    #[clap(long)]
+    pub block: Option<u64>,
+
+    /// The output directory to write the output to or 'print' to print to the console
+    #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
+    pub output: String,
+
+    /// The name for the output file
+    #[clap(long, short, default_value = "", hide_default_value = true)]
+    pub name: String,
+
+    /// The timeout for each function's symbolic execution in milliseconds.
+    #[clap(long, short, default_value = "10000", hide_default_value = true)]
+    pub timeout: u64,
+}
+
+impl RolesArgsBuilder {
+    pub fn new() -> Self {
+        Self {
+            target: Some(String::new()),
+            verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
+            rpc_url: Some(String::new()),
+            format: Some(String::from("json")),
+            block: Some(None),
+            output: Some(String::new()),
+            name: Some(String::new()),
+            timeout: Some(10000),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RolesResult {
+    /// The base slot of the role mapping, if any role checks were found
+    pub slot: Option<U256>,
+    /// The roles which gate a function, and their admins, ordered by id
+    pub roles: Vec<Role>,
+    /// Functions which only the admin of the role they're passed may call, such as `grantRole`
+    /// and `revokeRole`
+    pub admin_functions: BTreeSet<String>,
+}
+
+/// Recover the role graph of an AccessControl contract. Each function is symbolically executed,
+/// and every `hasRole(role, msg.sender)` check it makes gates it behind that role. Admin roles
+/// are stored rather than compiled in, so with `--block` they're read from the target's storage,
+/// following each admin's own admin in turn.
+pub async fn roles(args: RolesArgs) -> Result<RolesResult, Error> {
+    set_logger_env(&args.verbose);
+
+    // get a new logger
+    let (logger, _) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    // check the format before analyzing, so an invalid one fails fast
+    if !["json", "dot"].contains(&args.format.as_str()) {
+        return Err(Error::GenericError(format!(
+            "invalid format '{}' . expected one of: json, dot.",
+            args.format
+        )))
+    }
+
+    let contract_bytecode = get_bytecode_from_target(&args.target, &args.rpc_url)
+        .await
+        .map_err(|e| Error::GenericError(e.to_string()))?;
+
+    enter_pass("disassemble");
+    let disassembled_bytecode = disassemble(DisassemblerArgs {
+        target: contract_bytecode.clone(),
+        verbose: args.verbose.clone(),
+        rpc_url: args.rpc_url.clone(),
+        decimal_counter: false,
+        format: String::from("asm"),
+        verify: false,
+        name: String::new(),
+        output: String::new(),
+    })
+    .await
+    .map_err(|e| Error::GenericError(format!("failed to disassemble bytecode: {e}")))?;
+
+    let evm = VM::new(
+        contract_bytecode,
+        String::from("0x"),
+        String::from("0x6865696d64616c6c000000000061646472657373"),
+        String::from("0x6865696d64616c6c0000000000006f726967696e"),
+        String::from(CALLER),
+        0,
+        u128::MAX,
+    );
+
+    let mut selectors = find_function_selectors(&evm, &disassembled_bytecode)
+        .into_iter()
+        .collect::<Vec<(String, u128)>>();
+    selectors.sort();
+
+    let mut builder = RoleGraphBuilder::new();
+    for (selector, entry_point) in selectors {
+        enter_pass(&format!("symbolic execution (0x{selector})"));
+        let mut evm_clone = evm.clone();
+        let selector_clone = selector.clone();
+        match run_with_timeout(
+            move || evm_clone.symbolic_exec_selector(&selector_clone, entry_point),
+            Duration::from_millis(args.timeout),
+        ) {
+            Some((trace, _)) => builder.add_trace(&trace, &format!("0x{selector}")),
+            None => record_partial_analysis(),
+        }
+    }
+
+    let slot = builder.slot();
+    let admin_functions = builder.admin_functions();
+    let mut roles = builder.build();
+    logger.info(&format!("recovered {} roles.", roles.len()));
+
+    let is_address = ADDRESS_REGEX.is_match(&args.target).unwrap_or(false);
+    match (args.block, slot) {
+        (Some(block), Some(slot)) if is_address => {
+            enter_pass("admin resolution");
+
+            // an admin may itself be a role which gates no function, so it's added to the graph
+            // and its own admin is read in turn
+            let mut i = 0;
+            while i < roles.len() {
+                let admin = get_storage_at(
+                    &args.target,
+                    admin_slot(&roles[i].role, slot),
+                    block,
+                    &args.rpc_url,
+                )
+                .await
+                .map_err(|e| Error::GenericError(e.to_string()))?;
+                roles[i].admin = Some(admin);
+                if !roles.iter().any(|role| role.role == admin) {
+                    roles.push(Role {
+                        role: admin,
+                        name: role_name(&admin),
+                        admin: None,
+                        functions: BTreeSet::new(),
+                    });
+                }
+                i += 1;
+            }
+            roles.sort_by_key(|role| role.role);
+        }
+        (Some(_), _) if !is_address => {
+            logger.warn("admin roles are only read from storage for a contract address.");
+        }
+        _ => {}
+    }
+
+    Ok(RolesResult { slot, roles, admin_functions })
+}
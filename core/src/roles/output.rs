@@ -0,0 +1,52 @@
+use std::collections::HashMap;
+
+use heimdall_common::resources::labels::get_selector_label;
+use petgraph::{dot::Dot, graph::Graph};
+
+use super::RolesResult;
+
+/// Write the role graph in the `dot` graphviz format. Each role points to the roles it
+/// administers and the functions it gates.
+pub fn build_dot(result: &RolesResult) -> String {
+    let mut graph: Graph<String, String> = Graph::new();
+    let mut function_nodes = HashMap::new();
+    let mut function_node = |graph: &mut Graph<String, String>, function: &str| {
+        *function_nodes.entry(function.to_string()).or_insert_with(|| {
+            graph.add_node(match get_selector_label(function) {
+                Some(label) => format!("{label} ({function})"),
+                None => function.to_string(),
+            })
+        })
+    };
+
+    let role_nodes =
+        result.roles.iter().map(|role| graph.add_node(role.label())).collect::<Vec<_>>();
+    for (role, node) in result.roles.iter().zip(&role_nodes) {
+        for function in &role.functions {
+            let function = function_node(&mut graph, function);
+            graph.add_edge(*node, function, String::new());
+        }
+        if let Some(admin) = role.admin {
+            if let Some(admin_node) = result.roles.iter().position(|role| role.role == admin) {
+                graph.add_edge(role_nodes[admin_node], *node, String::from("admin"));
+            }
+        }
+    }
+
+    // the admin of whichever role is passed may call these
+    if !result.admin_functions.is_empty() {
+        let admin_node = graph.add_node(String::from("admin of the given role"));
+        for function in &result.admin_functions {
+            let function = function_node(&mut graph, function);
+            graph.add_edge(admin_node, function, String::new());
+        }
+    }
+
+    let output = format!("{}", Dot::with_config(&graph, &[]));
+    output
+        .replace(
+            "digraph {",
+            "digraph G {\n    node [shape=box, style=\"rounded\", fontname=\"Helvetica\"];\n    edge [fontname=\"Helvetica\"];",
+        )
+        .replace("[ label = \"\" ]", "[]")
+}
@@ -0,0 +1,246 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use ethers::{
+    types::{H256, U256},
+    utils::keccak256,
+};
+use heimdall_common::ether::evm::{core::opcodes::WrappedOpcode, ext::exec::VMTrace};
+use serde::{Deserialize, Serialize};
+
+/// The caller of every symbolically executed function. Role checks are `hasRole(role, msg.sender)`
+/// lookups, so they're the storage reads keyed by this address.
+pub const CALLER: &str = "0x6865696d64616c6c00000000000063616c6c6572";
+
+/// Role names commonly used by AccessControl contracts. A role is named by matching its id against
+/// `keccak256(name)`.
+const ROLE_NAMES: [&str; 40] = [
+    "ADMIN_ROLE",
+    "MINTER_ROLE",
+    "BURNER_ROLE",
+    "PAUSER_ROLE",
+    "PAUSE_ROLE",
+    "UNPAUSER_ROLE",
+    "UPGRADER_ROLE",
+    "UPGRADE_ROLE",
+    "OPERATOR_ROLE",
+    "MANAGER_ROLE",
+    "GOVERNOR_ROLE",
+    "GOVERNANCE_ROLE",
+    "GUARDIAN_ROLE",
+    "KEEPER_ROLE",
+    "EXECUTOR_ROLE",
+    "PROPOSER_ROLE",
+    "CANCELLER_ROLE",
+    "TIMELOCK_ADMIN_ROLE",
+    "SNAPSHOT_ROLE",
+    "URI_SETTER_ROLE",
+    "TRANSFER_ROLE",
+    "WHITELIST_ROLE",
+    "BLACKLIST_ROLE",
+    "ORACLE_ROLE",
+    "RELAYER_ROLE",
+    "BRIDGE_ROLE",
+    "STRATEGIST_ROLE",
+    "HARVESTER_ROLE",
+    "FEE_MANAGER_ROLE",
+    "TREASURY_ROLE",
+    "VAULT_ROLE",
+    "LIQUIDATOR_ROLE",
+    "EMERGENCY_ROLE",
+    "SIGNER_ROLE",
+    "VALIDATOR_ROLE",
+    "DEPOSITOR_ROLE",
+    "WITHDRAWER_ROLE",
+    "RESCUER_ROLE",
+    "CONFIGURATOR_ROLE",
+    "DISTRIBUTOR_ROLE",
+];
+
+/// A role, and the functions which only its members may call.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Role {
+    pub role: H256,
+    /// The role's name, if its id is a known `keccak256(name)`
+    pub name: Option<String>,
+    /// The role whose members may grant and revoke this role, if it was read from storage
+    pub admin: Option<H256>,
+    pub functions: BTreeSet<String>,
+}
+
+impl Role {
+    /// The role's name, or its id if it isn't known.
+    pub fn label(&self) -> String {
+        self.name.clone().unwrap_or_else(|| format!("{:#x}", self.role))
+    }
+}
+
+/// Accumulates the role checks made by a contract from its traces.
+#[derive(Debug, Clone, Default)]
+pub struct RoleGraphBuilder {
+    /// The preimage of every hash computed by SHA3
+    preimages: HashMap<U256, Vec<u8>>,
+    /// The operation which produced each word stored to memory, by offset
+    memory_sources: HashMap<U256, WrappedOpcode>,
+    /// Hashes of a role which was read from storage, i.e. `keccak256(getRoleAdmin(role) . slot)`
+    admin_hashes: HashSet<U256>,
+    /// The base slot of the role mapping
+    slot: Option<U256>,
+    roles: BTreeMap<H256, BTreeSet<String>>,
+    admin_functions: BTreeSet<String>,
+}
+
+impl RoleGraphBuilder {
+    pub fn new() -> RoleGraphBuilder {
+        RoleGraphBuilder::default()
+    }
+
+    /// Record every role check in the trace as being made by `function`. A role check is a read
+    /// of `_roles[role].members[msg.sender]`, i.e. `keccak256(msg.sender . keccak256(role .
+    /// slot))`.
+    pub fn add_trace(&mut self, trace: &VMTrace, function: &str) {
+        let caller = U256::from_str_radix(CALLER.trim_start_matches("0x"), 16)
+            .expect("CALLER is a valid address");
+
+        for operation in &trace.operations {
+            let instruction = &operation.last_instruction;
+            match instruction.opcode {
+                // MSTORE
+                0x52 => {
+                    if let (Some(offset), Some(source)) =
+                        (instruction.inputs.first(), instruction.input_operations.get(1))
+                    {
+                        self.memory_sources.insert(*offset, source.clone());
+                    }
+                }
+                // SHA3
+                0x20 => {
+                    let (Some(offset), Some(size), Some(hash)) = (
+                        instruction.inputs.first(),
+                        instruction.inputs.get(1),
+                        instruction.outputs.first(),
+                    ) else {
+                        continue
+                    };
+                    if let (Ok(offset), Ok(size)) =
+                        (usize::try_from(*offset), usize::try_from(*size))
+                    {
+                        self.preimages.insert(*hash, operation.memory.read(offset, size));
+                    }
+
+                    // the hashed role was read from storage, so this is the admin of some role
+                    if self
+                        .memory_sources
+                        .get(&instruction.inputs[0])
+                        .is_some_and(|source| source.opcode.code == 0x54)
+                    {
+                        self.admin_hashes.insert(*hash);
+                    }
+                }
+                // SLOAD
+                0x54 => {
+                    if let Some(key) = instruction.inputs.first() {
+                        self.add_read(*key, caller, function);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for child in &trace.children {
+            self.add_trace(child, function);
+        }
+    }
+
+    fn add_read(&mut self, key: U256, caller: U256, function: &str) {
+        let Some(preimage) = self.preimages.get(&key).filter(|preimage| preimage.len() == 64)
+        else {
+            return
+        };
+        if U256::from_big_endian(&preimage[..32]) != caller {
+            return
+        }
+
+        let role_hash = U256::from_big_endian(&preimage[32..]);
+        let Some(role_preimage) =
+            self.preimages.get(&role_hash).filter(|preimage| preimage.len() == 64)
+        else {
+            return
+        };
+
+        self.slot = Some(U256::from_big_endian(&role_preimage[32..]));
+        if self.admin_hashes.contains(&role_hash) {
+            self.admin_functions.insert(function.to_string());
+        } else {
+            self.roles
+                .entry(H256::from_slice(&role_preimage[..32]))
+                .or_default()
+                .insert(function.to_string());
+        }
+    }
+
+    /// The base slot of the role mapping, if any role checks were found.
+    pub fn slot(&self) -> Option<U256> {
+        self.slot
+    }
+
+    /// The functions which are only callable by the admin of the role they're passed, e.g.
+    /// `grantRole(bytes32,address)`.
+    pub fn admin_functions(&self) -> BTreeSet<String> {
+        self.admin_functions.clone()
+    }
+
+    /// The roles which were checked, ordered by id. Their admins aren't known from the bytecode
+    /// alone.
+    pub fn build(self) -> Vec<Role> {
+        self.roles
+            .into_iter()
+            .map(|(role, functions)| Role { role, name: role_name(&role), admin: None, functions })
+            .collect()
+    }
+}
+
+/// The name of a role, if its id is `DEFAULT_ADMIN_ROLE` or a known `keccak256(name)`.
+pub fn role_name(role: &H256) -> Option<String> {
+    if role.is_zero() {
+        return Some(String::from("DEFAULT_ADMIN_ROLE"))
+    }
+
+    ROLE_NAMES.iter().find(|name| keccak256(name.as_bytes()) == role.0).map(|name| name.to_string())
+}
+
+/// The storage slot holding a role's admin, i.e. `keccak256(role . slot) + 1`.
+pub fn admin_slot(role: &H256, slot: U256) -> H256 {
+    let mut preimage = role.as_bytes().to_vec();
+    let mut slot_bytes = [0u8; 32];
+    slot.to_big_endian(&mut slot_bytes);
+    preimage.extend(slot_bytes);
+
+    let mut admin_slot = [0u8; 32];
+    (U256::from_big_endian(&keccak256(preimage)) + 1).to_big_endian(&mut admin_slot);
+    H256::from(admin_slot)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::roles::util::*;
+
+    #[test]
+    fn test_role_names() {
+        assert_eq!(role_name(&H256::zero()), Some(String::from("DEFAULT_ADMIN_ROLE")));
+        assert_eq!(
+            role_name(&H256::from(keccak256("MINTER_ROLE"))),
+            Some(String::from("MINTER_ROLE"))
+        );
+        assert_eq!(role_name(&H256::repeat_byte(0x11)), None);
+    }
+
+    #[test]
+    fn test_admin_slot_follows_role_data() {
+        let mut preimage = vec![0u8; 64];
+        preimage[31] = 1;
+        let role_data = U256::from_big_endian(&keccak256(preimage));
+
+        let admin_slot = admin_slot(&H256::from_low_u64_be(1), U256::zero());
+        assert_eq!(U256::from_big_endian(admin_slot.as_bytes()), role_data + 1);
+    }
+}
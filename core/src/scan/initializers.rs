@@ -0,0 +1,277 @@
+use std::{collections::HashSet, time::Duration};
+
+use ethers::types::U256;
+use heimdall_common::{
+    ether::{
+        evm::{
+            core::{
+                opcodes::{WrappedInput, WrappedOpcode},
+                vm::VM,
+            },
+            ext::exec::VMTrace,
+        },
+        selectors::find_function_selectors,
+    },
+    resources::labels::get_selector_label,
+    utils::{io::exit::record_partial_analysis, threading::run_with_timeout},
+};
+
+use crate::{
+    disassemble::{disassemble, DisassemblerArgs},
+    error::Error,
+};
+
+use super::{rules::Severity, Finding};
+
+/// Selectors of common initializer signatures, i.e. `initialize()` and `initialize(address)`.
+const INITIALIZER_SELECTORS: [&str; 13] = [
+    "8129fc1c", "c4d66de8", "485cc955", "c0c53b8b", "cd6dc687", "4cd88b76", "439fab91", "90657147",
+    "077f224a", "e1c7392a", "19ab453c", "e46dcfeb", "c57c5f60",
+];
+
+/// How a single function's execution depends on and changes storage.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StorageAccess {
+    /// Constant storage slots which are read by a branch condition
+    pub checked_slots: HashSet<U256>,
+    /// Storage slots which are written on any path
+    pub written_slots: HashSet<U256>,
+    /// Whether a branch condition depends on `msg.sender` or `tx.origin`
+    pub caller_checked: bool,
+}
+
+impl StorageAccess {
+    /// Record the storage accesses of every branch of a trace.
+    pub fn from_trace(trace: &VMTrace) -> StorageAccess {
+        let mut access = StorageAccess::default();
+        access.add_trace(trace);
+        access
+    }
+
+    fn add_trace(&mut self, trace: &VMTrace) {
+        for operation in &trace.operations {
+            let instruction = &operation.last_instruction;
+            match instruction.opcode {
+                // JUMPI
+                0x57 => {
+                    if let Some(condition) = instruction.input_operations.get(1) {
+                        self.add_condition(condition);
+                    }
+                }
+                // SSTORE
+                0x55 => {
+                    if let Some(slot) = instruction.inputs.first() {
+                        self.written_slots.insert(*slot);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for child in &trace.children {
+            self.add_trace(child);
+        }
+    }
+
+    fn add_condition(&mut self, condition: &WrappedOpcode) {
+        match condition.opcode.code {
+            // CALLER, ORIGIN
+            0x33 | 0x32 => self.caller_checked = true,
+            // SLOAD
+            0x54 => {
+                if let Some(slot) = condition.inputs.first().and_then(constant) {
+                    self.checked_slots.insert(slot);
+                }
+            }
+            _ => {}
+        }
+
+        for input in &condition.inputs {
+            if let WrappedInput::Opcode(operation) = input {
+                self.add_condition(operation);
+            }
+        }
+    }
+}
+
+/// The value of an input, if it's a constant.
+pub(super) fn constant(input: &WrappedInput) -> Option<U256> {
+    match input {
+        WrappedInput::Raw(value) => Some(*value),
+        WrappedInput::Opcode(operation) => match operation.opcode.code {
+            // PUSH0
+            0x5f => Some(U256::zero()),
+            // PUSH1 -> PUSH32
+            0x60..=0x7f => operation.inputs.first().and_then(constant),
+            _ => None,
+        },
+        WrappedInput::Variable(_) => None,
+    }
+}
+
+/// Why an initializer can be called again by anyone, or `None` if it's protected. An initializer
+/// is protected when it checks the caller, or checks a slot which some function in the contract
+/// writes, such as the `_initialized` flag set by an `initializer` modifier. Initializers which
+/// don't write storage have nothing to re-initialize.
+pub fn unprotected_reason(
+    access: &StorageAccess,
+    written_anywhere: &HashSet<U256>,
+) -> Option<String> {
+    if access.written_slots.is_empty() || access.caller_checked {
+        return None
+    }
+    if access.checked_slots.iter().any(|slot| written_anywhere.contains(slot)) {
+        return None
+    }
+
+    Some(match access.checked_slots.is_empty() {
+        true => "writes storage without checking an initialized flag or the caller".to_string(),
+        false => format!(
+            "checks storage slots {} before writing, but no function sets them",
+            format_slots(&access.checked_slots)
+        ),
+    })
+}
+
+fn format_slots(slots: &HashSet<U256>) -> String {
+    let mut slots = slots.iter().collect::<Vec<&U256>>();
+    slots.sort();
+    slots.iter().map(|slot| format!("{slot:#x}")).collect::<Vec<String>>().join(", ")
+}
+
+/// Whether a selector belongs to an initializer, either by its signature or its label.
+fn is_initializer(selector: &str) -> bool {
+    INITIALIZER_SELECTORS.contains(&selector) ||
+        get_selector_label(selector)
+            .map(|label| label.to_lowercase().starts_with("init"))
+            .unwrap_or(false)
+}
+
+/// Find initializers in the given bytecode which anyone can call again once the contract is
+/// initialized. Every function is symbolically executed, since the flag an initializer checks
+/// may be set by another function.
+pub async fn find_unprotected_initializers(
+    contract: &str,
+    contract_bytecode: &str,
+    timeout: u64,
+) -> Result<Vec<Finding>, Error> {
+    let contract_bytecode = contract_bytecode.replacen("0x", "", 1);
+    let disassembled_bytecode = disassemble(DisassemblerArgs {
+        target: contract_bytecode.clone(),
+        verbose: clap_verbosity_flag::Verbosity::new(0, 1),
+        rpc_url: String::new(),
+        decimal_counter: false,
+        format: String::from("asm"),
+        verify: false,
+        name: String::new(),
+        output: String::new(),
+    })
+    .await
+    .map_err(|e| Error::GenericError(format!("failed to disassemble bytecode: {e}")))?;
+
+    let evm = VM::new(
+        contract_bytecode,
+        String::from("0x"),
+        String::from("0x6865696d64616c6c000000000061646472657373"),
+        String::from("0x6865696d64616c6c0000000000006f726967696e"),
+        String::from("0x6865696d64616c6c00000000000063616c6c6572"),
+        0,
+        u128::MAX,
+    );
+
+    let mut selectors = find_function_selectors(&evm, &disassembled_bytecode)
+        .into_iter()
+        .collect::<Vec<(String, u128)>>();
+    if !selectors.iter().any(|(selector, _)| is_initializer(selector)) {
+        return Ok(Vec::new())
+    }
+    selectors.sort();
+
+    let mut accesses = Vec::new();
+    for (selector, entry_point) in selectors {
+        let mut evm_clone = evm.clone();
+        let selector_clone = selector.clone();
+        match run_with_timeout(
+            move || evm_clone.symbolic_exec_selector(&selector_clone, entry_point),
+            Duration::from_millis(timeout),
+        ) {
+            Some((trace, _)) => accesses.push((selector, StorageAccess::from_trace(&trace))),
+            None => record_partial_analysis(),
+        }
+    }
+
+    let written_anywhere = accesses
+        .iter()
+        .flat_map(|(_, access)| access.written_slots.iter().copied())
+        .collect::<HashSet<U256>>();
+
+    Ok(accesses
+        .iter()
+        .filter(|(selector, _)| is_initializer(selector))
+        .filter_map(|(selector, access)| {
+            let reason = unprotected_reason(access, &written_anywhere)?;
+            Some(Finding {
+                contract: contract.to_string(),
+                rule: String::from("unprotected-initializer"),
+                name: String::from("Unprotected initializer"),
+                severity: Severity::High,
+                description: format!(
+                    "The initializer 0x{selector} can be called again by anyone: it {reason}. \
+                     If this is an implementation or proxy, anyone can re-initialize it and take \
+                     over whatever the initializer sets, such as its owner."
+                ),
+                references: vec![String::from(
+                    "https://docs.openzeppelin.com/upgrades-plugins/writing-upgradeable#initializers",
+                )],
+                evidence: vec![
+                    format!("0x{selector} writes storage slots {}", format_slots(&access.written_slots)),
+                ],
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scan::initializers::*;
+
+    fn slots(slots: &[u64]) -> HashSet<U256> {
+        slots.iter().map(|slot| U256::from(*slot)).collect()
+    }
+
+    #[test]
+    fn test_initializer_modifier_is_protected() {
+        let access = StorageAccess {
+            checked_slots: slots(&[0]),
+            written_slots: slots(&[0, 1]),
+            caller_checked: false,
+        };
+
+        assert_eq!(unprotected_reason(&access, &access.written_slots), None);
+    }
+
+    #[test]
+    fn test_only_initializing_flag_set_elsewhere_is_protected() {
+        let access = StorageAccess {
+            checked_slots: slots(&[0]),
+            written_slots: slots(&[1]),
+            caller_checked: false,
+        };
+
+        assert_eq!(unprotected_reason(&access, &slots(&[0, 1])), None);
+        assert!(unprotected_reason(&access, &slots(&[1])).is_some());
+    }
+
+    #[test]
+    fn test_unchecked_initializer_is_unprotected() {
+        let access = StorageAccess {
+            checked_slots: slots(&[]),
+            written_slots: slots(&[1]),
+            caller_checked: false,
+        };
+        assert!(unprotected_reason(&access, &slots(&[1])).is_some());
+
+        let access = StorageAccess { caller_checked: true, ..access };
+        assert_eq!(unprotected_reason(&access, &slots(&[1])), None);
+    }
+}
@@ -0,0 +1,307 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    time::Duration,
+};
+
+use ethers::types::U256;
+use heimdall_common::{
+    ether::{
+        evm::{
+            core::{
+                opcodes::{WrappedInput, WrappedOpcode},
+                vm::{State, VM},
+            },
+            ext::exec::VMTrace,
+        },
+        selectors::find_function_selectors,
+    },
+    utils::{io::exit::record_partial_analysis, threading::run_with_timeout},
+};
+
+use crate::{
+    disassemble::{disassemble, DisassemblerArgs},
+    error::Error,
+};
+
+use super::{initializers::constant, rules::Severity, Finding};
+
+/// The block gas limit a single call must fit in.
+pub const BLOCK_GAS_LIMIT: u128 = 30_000_000;
+
+/// A loop whose exit condition reads storage, such as the length of a storage array.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageBoundedLoop {
+    /// The pc of the JUMPDEST each iteration starts at
+    pub header: u128,
+    /// The slot the bound is read from, or `None` if it's read from a mapping or nested array
+    pub slot: Option<U256>,
+    /// The gas used by the first iteration, which pays for any cold storage it accesses
+    pub iteration_gas: u128,
+    /// The gas used before the loop is entered, including the intrinsic gas
+    pub entry_gas: u128,
+}
+
+impl StorageBoundedLoop {
+    /// The number of iterations after which a call no longer fits in the block gas limit.
+    pub fn max_iterations(&self) -> u128 {
+        BLOCK_GAS_LIMIT.saturating_sub(self.entry_gas) / self.iteration_gas.max(1)
+    }
+
+    /// Where the loop's bound is read from, e.g. `storage slot 0x2`.
+    pub fn bound(&self) -> String {
+        match self.slot {
+            Some(slot) => format!("storage slot {slot:#x}"),
+            None => String::from("a mapping or nested array"),
+        }
+    }
+}
+
+/// Find the loops in every path of a trace whose exit condition reads storage. A loop is a
+/// backward jump to a JUMPDEST which the path already passed through without returning from it
+/// in between, so an internal function called twice isn't mistaken for one. A loop found on several
+/// paths is reported once, with the most expensive iteration seen.
+pub fn find_storage_bounded_loops(trace: &VMTrace) -> Vec<StorageBoundedLoop> {
+    let mut loops = BTreeMap::new();
+    add_trace(trace, &mut Vec::new(), &mut loops);
+    loops.into_values().collect()
+}
+
+fn add_trace<'a>(
+    trace: &'a VMTrace,
+    path: &mut Vec<&'a State>,
+    loops: &mut BTreeMap<u128, StorageBoundedLoop>,
+) {
+    let depth = path.len();
+    for state in &trace.operations {
+        path.push(state);
+        if let Some(found) = storage_bounded_loop(path) {
+            loops
+                .entry(found.header)
+                .and_modify(|existing: &mut StorageBoundedLoop| {
+                    if found.iteration_gas > existing.iteration_gas {
+                        *existing = found.clone();
+                    }
+                })
+                .or_insert(found);
+        }
+    }
+
+    for child in &trace.children {
+        add_trace(child, path, loops);
+    }
+    path.truncate(depth);
+}
+
+/// The loop closed by the last state of the path, if it's a storage-bounded loop's header.
+fn storage_bounded_loop(path: &[&State]) -> Option<StorageBoundedLoop> {
+    let (header, previous) = match path {
+        [.., previous, header] => (header, previous),
+        _ => return None,
+    };
+
+    // JUMPDEST, reached by a backward JUMP(I)
+    let pc = header.last_instruction.instruction;
+    if header.last_instruction.opcode != 0x5b ||
+        !matches!(previous.last_instruction.opcode, 0x56 | 0x57) ||
+        previous.last_instruction.instruction <= pc
+    {
+        return None
+    }
+
+    let first = path[..path.len() - 1]
+        .iter()
+        .rposition(|state| state.last_instruction.instruction == pc)?;
+    if returns_between(path[first], &path[first + 1..path.len() - 1]) {
+        return None
+    }
+
+    // the loop's exit condition is the first branch after its header
+    let condition = path[first + 1..]
+        .iter()
+        .find(|state| state.last_instruction.opcode == 0x57)?
+        .last_instruction
+        .input_operations
+        .get(1)?;
+    let key = find_load(condition)?;
+
+    Some(StorageBoundedLoop {
+        header: pc - 1,
+        slot: key.and_then(|key| constant(&key)),
+        iteration_gas: header.gas_used - path[first].gas_used,
+        entry_gas: path[first].gas_used,
+    })
+}
+
+/// Whether any JUMP in the states goes to an address which was on the stack at the header, i.e.
+/// the path returned from the function the header is in before reaching it again. A loop only
+/// jumps to those addresses once it exits.
+fn returns_between(header: &State, states: &[&State]) -> bool {
+    let addresses = header.stack.stack.iter().map(|frame| frame.value).collect::<HashSet<U256>>();
+    states.iter().any(|state| {
+        state.last_instruction.opcode == 0x56 &&
+            state
+                .last_instruction
+                .inputs
+                .first()
+                .is_some_and(|target| addresses.contains(target))
+    })
+}
+
+/// The key of the first SLOAD in the operation's tree, if it reads storage at all.
+fn find_load(operation: &WrappedOpcode) -> Option<Option<WrappedInput>> {
+    // SLOAD
+    if operation.opcode.code == 0x54 {
+        return Some(operation.inputs.first().cloned())
+    }
+
+    operation.inputs.iter().find_map(|input| match input {
+        WrappedInput::Opcode(operation) => find_load(operation),
+        WrappedInput::Raw(_) | WrappedInput::Variable(_) => None,
+    })
+}
+
+/// Find functions in the given bytecode with loops bounded by storage, such as an iteration over
+/// a storage array. Anyone who can grow the array can make these functions too expensive to call,
+/// so each is reported with the number of iterations at which it exceeds the block gas limit.
+pub async fn find_unbounded_loops(
+    contract: &str,
+    contract_bytecode: &str,
+    timeout: u64,
+) -> Result<Vec<Finding>, Error> {
+    let contract_bytecode = contract_bytecode.replacen("0x", "", 1);
+    let disassembled_bytecode = disassemble(DisassemblerArgs {
+        target: contract_bytecode.clone(),
+        verbose: clap_verbosity_flag::Verbosity::new(0, 1),
+        rpc_url: String::new(),
+        decimal_counter: false,
+        format: String::from("asm"),
+        verify: false,
+        name: String::new(),
+        output: String::new(),
+    })
+    .await
+    .map_err(|e| Error::GenericError(format!("failed to disassemble bytecode: {e}")))?;
+
+    let evm = VM::new(
+        contract_bytecode,
+        String::from("0x"),
+        String::from("0x6865696d64616c6c000000000061646472657373"),
+        String::from("0x6865696d64616c6c0000000000006f726967696e"),
+        String::from("0x6865696d64616c6c00000000000063616c6c6572"),
+        0,
+        u128::MAX,
+    );
+
+    let mut selectors = find_function_selectors(&evm, &disassembled_bytecode)
+        .into_iter()
+        .collect::<Vec<(String, u128)>>();
+    selectors.sort();
+
+    let mut findings = Vec::new();
+    for (selector, entry_point) in selectors {
+        let mut evm_clone = evm.clone();
+        let selector_clone = selector.clone();
+        let loops = match run_with_timeout(
+            move || evm_clone.symbolic_exec_selector(&selector_clone, entry_point),
+            Duration::from_millis(timeout),
+        ) {
+            Some((trace, _)) => find_storage_bounded_loops(&trace),
+            None => {
+                record_partial_analysis();
+                continue
+            }
+        };
+        let Some(max_iterations) = loops.iter().map(|found| found.max_iterations()).min() else {
+            continue
+        };
+
+        let bounds = loops
+            .iter()
+            .map(|found| found.bound())
+            .collect::<BTreeSet<String>>()
+            .into_iter()
+            .collect::<Vec<String>>();
+        findings.push(Finding {
+            contract: contract.to_string(),
+            rule: String::from("unbounded-loop"),
+            name: String::from("Loop bounded by storage"),
+            severity: Severity::Medium,
+            description: format!(
+                "0x{selector} loops over a collection whose length is read from {}. Once it \
+                 holds about {max_iterations} elements, a call exceeds the {BLOCK_GAS_LIMIT} \
+                 block gas limit and the function can no longer be called. If anyone can add \
+                 elements, they can lock it.",
+                bounds.join(" and ")
+            ),
+            references: vec![String::from("https://swcregistry.io/docs/SWC-128")],
+            evidence: loops
+                .iter()
+                .map(|found| {
+                    format!(
+                        "the loop at pc {:#x} is bounded by {} and uses {} gas per iteration",
+                        found.header,
+                        found.bound(),
+                        found.iteration_gas
+                    )
+                })
+                .collect(),
+        });
+    }
+
+    Ok(findings)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{map::util::VM_ADDRESSES, scan::loops::*};
+
+    fn find(bytecode: &str) -> Vec<StorageBoundedLoop> {
+        let vm = VM::new(
+            bytecode.to_string(),
+            String::from("0x"),
+            String::from(VM_ADDRESSES[0]),
+            String::from(VM_ADDRESSES[1]),
+            String::from(VM_ADDRESSES[2]),
+            0,
+            u128::MAX,
+        );
+
+        find_storage_bounded_loops(&vm.symbolic_exec().0)
+    }
+
+    #[test]
+    fn test_loop_over_storage_array() {
+        // for (i = 0; i < sload(0); i++) sload(i + 1)
+        let loops = find("60005b6000548110156018578060010154506001016002565b00");
+
+        assert_eq!(loops.len(), 1);
+        assert_eq!(loops[0].header, 2);
+        assert_eq!(loops[0].slot, Some(U256::zero()));
+        assert!(loops[0].iteration_gas > 2_100);
+        assert_eq!(
+            loops[0].max_iterations(),
+            (BLOCK_GAS_LIMIT - loops[0].entry_gas) / loops[0].iteration_gas
+        );
+    }
+
+    #[test]
+    fn test_internal_function_called_twice() {
+        // a function which branches on sload(0), called from two places after it
+        assert!(find("600c565b600054600a575b565b60126003565b60186003565b00").is_empty());
+    }
+
+    #[test]
+    fn test_loop_with_constant_bound() {
+        // for (i = 0; i < 10; i++) sload(i + 1)
+        assert!(find("60005b600a8110156017578060010154506001016002565b00").is_empty());
+    }
+
+    #[test]
+    fn test_max_iterations() {
+        let found =
+            StorageBoundedLoop { header: 0, slot: None, iteration_gas: 5_000, entry_gas: 30_000 };
+
+        assert_eq!(found.max_iterations(), 5_994);
+        assert_eq!(found.bound(), "a mapping or nested array");
+    }
+}
@@ -0,0 +1,238 @@
+pub mod initializers;
+pub mod loops;
+pub mod rules;
+
+use clap::{AppSettings, Parser};
+use derive_builder::Builder;
+use heimdall_common::{
+    constants::{ADDRESS_REGEX, BYTECODE_REGEX},
+    ether::{
+        bytecode::{get_bytecode_from_target, split_sections},
+        corpus::Corpus,
+    },
+    utils::{
+        io::logging::{set_logger_env, Logger},
+        strings::{decode_hex, encode_hex},
+    },
+};
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+
+use self::{
+    initializers::find_unprotected_initializers,
+    loops::find_unbounded_loops,
+    rules::{pushed_selectors, RuleSet, Severity},
+};
+
+#[derive(Debug, Clone, Parser, Builder)]
+#[clap(
+    about = "Scan bytecode for signatures of known vulnerabilities",
+    after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    global_setting = AppSettings::DeriveDisplayOrder,
+    override_usage = "heimdall scan <TARGET> [OPTIONS]"
+)]
+pub struct ScanArgs {
+    /// The target to scan, either a file, bytecode, contract address, or ENS name. With
+    /// --corpus, a directory or file of contracts.
+    #[clap(required = true)]
+    pub target: String,
+
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// The RPC provider to use for fetching target bytecode.
+    #[clap(long = "rpc-url", short, default_value = "", hide_default_value = true)]
+    pub rpc_url: String,
+
+    /// Treat the target as a corpus, either a directory with one contract per file, or a file
+    /// with one contract per line.
+    #[clap(long)]
+    pub corpus: bool,
+
+    /// A TOML file of additional rules. Rules with the same id as a builtin rule replace it.
+    #[clap(long, default_value = "", hide_default_value = true)]
+    pub rules: String,
+
+    /// Only report findings of at least this severity, one of 'info', 'low', 'medium', 'high',
+    /// or 'critical'.
+    #[clap(long, default_value = "info")]
+    pub min_severity: String,
+
+    /// The output directory to write the output to or 'print' to print to the console
+    #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
+    pub output: String,
+
+    /// The name for the output file
+    #[clap(long, short, default_value = "", hide_default_value = true)]
+    pub name: String,
+
+    /// The timeout for each function's symbolic execution in milliseconds.
+    #[clap(long, short, default_value = "10000", hide_default_value = true)]
+    pub timeout: u64,
+}
+
+impl ScanArgsBuilder {
+    pub fn new() -> Self {
+        Self {
+            target: Some(String::new()),
+            verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
+            rpc_url: Some(String::new()),
+            corpus: Some(false),
+            rules: Some(String::new()),
+            min_severity: Some(String::from("info")),
+            output: Some(String::new()),
+            name: Some(String::new()),
+            timeout: Some(10000),
+        }
+    }
+}
+
+/// A rule which matched a contract.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Finding {
+    /// The target, or the name of the corpus contract
+    pub contract: String,
+    pub rule: String,
+    pub name: String,
+    pub severity: Severity,
+    pub description: String,
+    pub references: Vec<String>,
+    /// What matched each of the rule's conditions
+    pub evidence: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanResult {
+    /// The number of rules checked
+    pub rules: usize,
+    /// The number of contracts scanned
+    pub contracts: usize,
+    /// Each finding, most severe first
+    pub findings: Vec<Finding>,
+}
+
+/// Check the target, or each contract in a corpus, against the builtin rules and any rules
+/// given with --rules, for initializers which anyone can call again, and for loops over storage
+/// which can grow past the block gas limit.
+pub async fn scan(args: ScanArgs) -> Result<ScanResult, Error> {
+    set_logger_env(&args.verbose);
+
+    // get a new logger
+    let (logger, _) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    let mut rules = RuleSet::builtin();
+    if !args.rules.is_empty() {
+        rules.extend(RuleSet::from_file(&args.rules)?);
+    }
+    let min_severity: Severity =
+        serde_json::from_value(serde_json::Value::String(args.min_severity.to_lowercase()))
+            .map_err(|_| {
+                Error::GenericError(format!("unknown severity '{}'", args.min_severity))
+            })?;
+    rules.rules.retain(|rule| rule.severity >= min_severity);
+    let check_initializers = Severity::High >= min_severity;
+    let check_loops = Severity::Medium >= min_severity;
+    logger.debug(&format!("checking {} rules.", rules.rules.len()));
+
+    let mut findings = Vec::new();
+    let contracts = match args.corpus {
+        true => {
+            let corpus =
+                Corpus::open(&args.target).map_err(|e| Error::GenericError(e.to_string()))?;
+            for entry in corpus.iter() {
+                let (name, bytecode) =
+                    match entry.and_then(|entry| Ok((entry.bytes()?, entry.name))) {
+                        Ok((bytecode, name)) => (name, bytecode),
+                        Err(e) => {
+                            logger.warn(&format!("skipping corpus contract: {e}"));
+                            continue
+                        }
+                    };
+                findings.extend(scan_bytecode(&name, &bytecode, &rules));
+                let contract_bytecode = encode_hex(bytecode);
+                if check_initializers {
+                    match find_unprotected_initializers(&name, &contract_bytecode, args.timeout)
+                        .await
+                    {
+                        Ok(found) => findings.extend(found),
+                        Err(e) => {
+                            logger.warn(&format!("failed to check '{name}' initializers: {e}"))
+                        }
+                    }
+                }
+                if check_loops {
+                    match find_unbounded_loops(&name, &contract_bytecode, args.timeout).await {
+                        Ok(found) => findings.extend(found),
+                        Err(e) => logger.warn(&format!("failed to check '{name}' loops: {e}")),
+                    }
+                }
+            }
+            corpus.len()
+        }
+        false => {
+            let contract_bytecode = get_bytecode_from_target(&args.target, &args.rpc_url)
+                .await
+                .map_err(|e| Error::GenericError(e.to_string()))?;
+            let bytecode = decode_hex(&contract_bytecode.replacen("0x", "", 1))
+                .map_err(|e| Error::GenericError(format!("failed to decode bytecode: {e}")))?;
+
+            let name = target_name(&args.target);
+            findings.extend(scan_bytecode(&name, &bytecode, &rules));
+            if check_initializers {
+                findings.extend(
+                    find_unprotected_initializers(&name, &contract_bytecode, args.timeout).await?,
+                );
+            }
+            if check_loops {
+                findings
+                    .extend(find_unbounded_loops(&name, &contract_bytecode, args.timeout).await?);
+            }
+            1
+        }
+    };
+
+    findings.sort_by(|a, b| {
+        b.severity.cmp(&a.severity).then(a.contract.cmp(&b.contract)).then(a.rule.cmp(&b.rule))
+    });
+    logger.info(&format!("found {} findings in {} contracts.", findings.len(), contracts));
+
+    Ok(ScanResult { rules: rules.rules.len(), contracts, findings })
+}
+
+/// Check a single contract's bytecode against each rule. Selectors are only taken from the code,
+/// so constants in a trailing data section or metadata aren't mistaken for them.
+pub fn scan_bytecode(contract: &str, bytecode: &[u8], rules: &RuleSet) -> Vec<Finding> {
+    let selectors = pushed_selectors(&bytecode[..split_sections(bytecode).code]);
+
+    rules
+        .rules
+        .iter()
+        .filter_map(|rule| {
+            rule.check(bytecode, &selectors).map(|evidence| Finding {
+                contract: contract.to_string(),
+                rule: rule.id.clone(),
+                name: rule.name.clone(),
+                severity: rule.severity,
+                description: rule.description.clone(),
+                references: rule.references.clone(),
+                evidence,
+            })
+        })
+        .collect()
+}
+
+/// The name findings for the target are reported under. Raw bytecode is too long to repeat in
+/// each finding.
+fn target_name(target: &str) -> String {
+    match !ADDRESS_REGEX.is_match(target).unwrap_or(false) &&
+        BYTECODE_REGEX.is_match(target).unwrap_or(false)
+    {
+        true => String::from("bytecode"),
+        false => target.to_string(),
+    }
+}
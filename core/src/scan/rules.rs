@@ -0,0 +1,239 @@
+use std::collections::HashSet;
+
+use heimdall_common::{
+    ether::evm::core::opcodes::Opcode,
+    utils::{io::file::read_file, strings::decode_hex},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+
+/// The rules shipped with heimdall.
+const BUILTIN_RULES: &str = include_str!("rules.toml");
+
+/// How serious a rule's finding is, from least to most.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+/// A bytecode-level signature of a historically exploited pattern, read from TOML:
+///
+/// ```toml
+/// [[rules]]
+/// id = "batch-transfer-overflow"
+/// name = "batchTransfer multiplication overflow"
+/// severity = "high"
+/// description = "Exposes batchTransfer(address[],uint256) ..."
+/// references = ["https://nvd.nist.gov/vuln/detail/CVE-2018-10299"]
+/// selectors = ["0x83f12fec"]
+/// patterns = ["63??????????"]
+/// ```
+///
+/// A rule matches when every selector is pushed as a PUSH4 constant, and every pattern appears
+/// in the bytecode. In patterns, `??` matches any byte.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rule {
+    pub id: String,
+    pub name: String,
+    pub severity: Severity,
+    pub description: String,
+    #[serde(default)]
+    pub references: Vec<String>,
+    #[serde(default)]
+    pub selectors: Vec<String>,
+    #[serde(default)]
+    pub patterns: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RuleSet {
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+}
+
+impl RuleSet {
+    /// The rules shipped with heimdall.
+    pub fn builtin() -> RuleSet {
+        RuleSet::parse(BUILTIN_RULES).expect("builtin rules are valid")
+    }
+
+    /// Read rules from the TOML file at `path`.
+    pub fn from_file(path: &str) -> Result<RuleSet, Error> {
+        RuleSet::parse(&read_file(path))
+    }
+
+    /// Parse and validate rules from TOML.
+    pub fn parse(contents: &str) -> Result<RuleSet, Error> {
+        let rules: RuleSet = toml::from_str(contents)
+            .map_err(|e| Error::GenericError(format!("failed to parse rules: {}", e)))?;
+
+        for rule in &rules.rules {
+            if rule.selectors.is_empty() && rule.patterns.is_empty() {
+                return Err(Error::GenericError(format!(
+                    "rule '{}' has no selectors or patterns",
+                    rule.id
+                )))
+            }
+            for selector in &rule.selectors {
+                parse_selector(selector).map_err(|e| {
+                    Error::GenericError(format!("invalid selector in rule '{}': {e}", rule.id))
+                })?;
+            }
+            for pattern in &rule.patterns {
+                parse_pattern(pattern).map_err(|e| {
+                    Error::GenericError(format!("invalid pattern in rule '{}': {e}", rule.id))
+                })?;
+            }
+        }
+
+        Ok(rules)
+    }
+
+    /// Add the given rules, replacing any existing rules with the same id.
+    pub fn extend(&mut self, other: RuleSet) {
+        for rule in other.rules {
+            self.rules.retain(|existing| existing.id != rule.id);
+            self.rules.push(rule);
+        }
+    }
+}
+
+impl Rule {
+    /// Check the rule against the given bytecode. If it matches, the evidence for each of its
+    /// conditions is returned.
+    pub fn check(
+        &self,
+        bytecode: &[u8],
+        pushed_selectors: &HashSet<[u8; 4]>,
+    ) -> Option<Vec<String>> {
+        let mut evidence = Vec::new();
+
+        for selector in &self.selectors {
+            let bytes = parse_selector(selector).ok()?;
+            if !pushed_selectors.contains(&bytes) {
+                return None
+            }
+            evidence.push(format!("PUSH4 {selector}"));
+        }
+
+        for pattern in &self.patterns {
+            let pattern_bytes = parse_pattern(pattern).ok()?;
+            let offset = find_pattern(bytecode, &pattern_bytes)?;
+            evidence.push(format!("pattern {pattern} at {offset:#x}"));
+        }
+
+        Some(evidence)
+    }
+}
+
+fn parse_selector(selector: &str) -> Result<[u8; 4], String> {
+    decode_hex(selector.trim_start_matches("0x"))
+        .map_err(|e| e.to_string())?
+        .try_into()
+        .map_err(|_| format!("'{selector}' isn't 4 bytes"))
+}
+
+/// Parse a hex pattern, where `??` matches any byte.
+fn parse_pattern(pattern: &str) -> Result<Vec<Option<u8>>, String> {
+    let pattern = pattern.trim_start_matches("0x");
+    if pattern.is_empty() || !pattern.len().is_multiple_of(2) {
+        return Err(format!("'{pattern}' isn't a whole number of bytes"))
+    }
+
+    (0..pattern.len())
+        .step_by(2)
+        .map(|i| match &pattern[i..i + 2] {
+            "??" => Ok(None),
+            byte => u8::from_str_radix(byte, 16)
+                .map(Some)
+                .map_err(|_| format!("'{byte}' isn't a hex byte or '??'")),
+        })
+        .collect()
+}
+
+/// The offset of the first occurrence of the pattern in the bytecode.
+fn find_pattern(bytecode: &[u8], pattern: &[Option<u8>]) -> Option<usize> {
+    bytecode.windows(pattern.len()).position(|window| {
+        window.iter().zip(pattern).all(|(byte, expected)| expected.is_none_or(|e| e == *byte))
+    })
+}
+
+/// Every constant pushed by a PUSH4 instruction. Push data is skipped, so bytes which only look
+/// like a PUSH4 aren't counted.
+pub fn pushed_selectors(bytecode: &[u8]) -> HashSet<[u8; 4]> {
+    let mut selectors = HashSet::new();
+    let mut program_counter = 0;
+
+    while program_counter < bytecode.len() {
+        let operation = Opcode::new(bytecode[program_counter]);
+        let byte_count = operation
+            .name
+            .strip_prefix("PUSH")
+            .and_then(|count| count.parse::<usize>().ok())
+            .unwrap_or(0);
+
+        if byte_count == 4 {
+            if let Some(Ok(selector)) =
+                bytecode.get(program_counter + 1..program_counter + 5).map(|bytes| bytes.try_into())
+            {
+                selectors.insert(selector);
+            }
+        }
+        program_counter += byte_count + 1;
+    }
+
+    selectors
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scan::rules::*;
+
+    #[test]
+    fn test_builtin_rules_are_valid() {
+        let rules = RuleSet::builtin();
+
+        assert!(!rules.rules.is_empty());
+        assert!(rules.rules.iter().all(|rule| !rule.references.is_empty()));
+    }
+
+    #[test]
+    fn test_pattern_wildcards() {
+        let pattern = parse_pattern("60??56").unwrap();
+
+        assert_eq!(find_pattern(&[0x00, 0x60, 0x2a, 0x56], &pattern), Some(1));
+        assert_eq!(find_pattern(&[0x60, 0x2a, 0x57], &pattern), None);
+        assert!(parse_pattern("60?").is_err());
+    }
+
+    #[test]
+    fn test_pushed_selectors_skip_push_data() {
+        // PUSH5 0x6383f12fec, which contains a PUSH4 opcode and selector as data
+        let selectors = pushed_selectors(&[0x64, 0x63, 0x83, 0xf1, 0x2f, 0xec]);
+        assert!(selectors.is_empty());
+
+        let selectors = pushed_selectors(&[0x63, 0x83, 0xf1, 0x2f, 0xec, 0x14]);
+        assert!(selectors.contains(&[0x83, 0xf1, 0x2f, 0xec]));
+    }
+
+    #[test]
+    fn test_rule_replaced_by_id() {
+        let mut rules = RuleSet::builtin();
+        let count = rules.rules.len();
+        rules.extend(
+            RuleSet::parse(
+                "[[rules]]\nid = \"exposed-initializer\"\nname = \"a\"\nseverity = \"low\"\ndescription = \"b\"\nselectors = [\"0x8129fc1c\"]\n",
+            )
+            .unwrap(),
+        );
+
+        assert_eq!(rules.rules.len(), count);
+        assert_eq!(rules.rules.last().unwrap().severity, Severity::Low);
+    }
+}
@@ -0,0 +1,193 @@
+use std::collections::BTreeMap;
+
+use heimdall_common::utils::io::file::read_file;
+use serde::Deserialize;
+
+use crate::error::Error;
+
+use super::{parse_word, SimulateResult, SimulatedLog};
+
+/// Post-conditions which must hold after a simulation, read from TOML:
+///
+/// ```toml
+/// success = true
+/// returndata = "0x"
+/// balance = "1000"
+///
+/// [storage]
+/// "0x0" = "0x1"
+///
+/// [[events]]
+/// topics = ["0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"]
+/// data = "0x"
+/// ```
+///
+/// Every field is optional. Events must appear in the given order, but other events may be
+/// emitted between them.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Assertions {
+    pub success: Option<bool>,
+    pub returndata: Option<String>,
+    /// The contract's balance after the call, or after the last call if `--bundle` is given
+    pub balance: Option<String>,
+    #[serde(default)]
+    pub storage: BTreeMap<String, String>,
+    #[serde(default)]
+    pub events: Vec<ExpectedLog>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExpectedLog {
+    pub topics: Vec<String>,
+    pub data: Option<String>,
+}
+
+impl Assertions {
+    /// Read assertions from the TOML file at `path`.
+    pub fn from_file(path: &str) -> Result<Assertions, Error> {
+        toml::from_str(&read_file(path))
+            .map_err(|e| Error::GenericError(format!("failed to parse assertions: {}", e)))
+    }
+
+    /// Check each assertion against the given result, returning a description of every one
+    /// which doesn't hold.
+    pub fn check(&self, result: &SimulateResult) -> Vec<String> {
+        let mut failures = Vec::new();
+
+        if let Some(success) = self.success {
+            if success != result.success {
+                failures.push(format!(
+                    "expected success = {success}, found {} (exit code {})",
+                    result.success, result.exitcode
+                ));
+            }
+        }
+
+        if let Some(returndata) = &self.returndata {
+            if normalize_hex(returndata) != normalize_hex(&result.returndata) {
+                failures
+                    .push(format!("expected returndata {returndata}, found {}", result.returndata));
+            }
+        }
+
+        if let Some(balance) = &self.balance {
+            match parse_word(balance) {
+                Some(expected) if expected != result.balance => {
+                    failures.push(format!("expected balance {expected}, found {}", result.balance))
+                }
+                Some(_) => {}
+                None => failures.push(format!("invalid balance assertion '{balance}'")),
+            }
+        }
+
+        for (slot, expected) in &self.storage {
+            let (Some(slot), Some(expected)) = (parse_word(slot), parse_word(expected)) else {
+                failures.push(format!("invalid storage assertion '{slot}' = '{expected}'"));
+                continue
+            };
+            let found = result.storage.get(&slot).copied().unwrap_or_default();
+            if found != expected {
+                failures.push(format!(
+                    "expected storage slot {slot:#x} = {expected:#x}, found {found:#x}"
+                ));
+            }
+        }
+
+        let mut remaining = result.events.iter();
+        for expected in &self.events {
+            if !remaining.any(|log| expected.matches(log)) {
+                failures.push(format!(
+                    "expected event with topics [{}] was not emitted",
+                    expected.topics.join(", ")
+                ));
+            }
+        }
+
+        failures
+    }
+}
+
+impl ExpectedLog {
+    fn matches(&self, log: &SimulatedLog) -> bool {
+        let topics_match = self.topics.len() <= log.topics.len() &&
+            self.topics.iter().zip(&log.topics).all(|(expected, found)| {
+                parse_word(expected).map(|expected| expected == *found).unwrap_or(false)
+            });
+        let data_match =
+            self.data.as_ref().map(|data| normalize_hex(data) == normalize_hex(&log.data));
+
+        topics_match && data_match.unwrap_or(true)
+    }
+}
+
+fn normalize_hex(value: &str) -> String {
+    value.trim_start_matches("0x").to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use ethers::types::{transaction::eip2930::AccessList, U256};
+
+    use crate::simulate::{assertions::Assertions, SimulateResult, SimulatedLog};
+
+    fn result() -> SimulateResult {
+        SimulateResult {
+            success: true,
+            exitcode: 10,
+            gas_used: 43106,
+            returndata: "0x".to_string(),
+            balance: U256::from(1000),
+            storage: BTreeMap::from([(U256::zero(), U256::one())]),
+            events: vec![SimulatedLog { topics: vec![U256::from(0xff)], data: "0x".to_string() }],
+            access_list: AccessList::default(),
+            bundle: Vec::new(),
+            failed_assertions: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_assertions_hold() {
+        let assertions: Assertions = toml::from_str(
+            r#"
+            success = true
+            balance = "0x3e8"
+
+            [storage]
+            "0x0" = "1"
+
+            [[events]]
+            topics = ["0xff"]
+            "#,
+        )
+        .unwrap();
+
+        assert!(assertions.check(&result()).is_empty());
+    }
+
+    #[test]
+    fn test_assertions_report_diff() {
+        let assertions: Assertions = toml::from_str(
+            r#"
+            success = false
+            returndata = "0x01"
+            balance = "999"
+
+            [storage]
+            "0x0" = "0x2"
+            "0x1" = "0x0"
+
+            [[events]]
+            topics = ["0xee"]
+            "#,
+        )
+        .unwrap();
+
+        let failures = assertions.check(&result());
+
+        assert_eq!(failures.len(), 5);
+        assert!(failures.contains(&"expected balance 999, found 1000".to_string()));
+        assert!(failures.contains(&"expected storage slot 0x0 = 0x2, found 0x1".to_string()));
+    }
+}
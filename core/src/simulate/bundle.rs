@@ -0,0 +1,183 @@
+use std::collections::BTreeMap;
+
+use ethers::types::U256;
+use heimdall_common::utils::io::file::read_file;
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+
+use super::{parse_word, SimulatedLog};
+
+/// An ordered bundle of calls which share evolving state, read from TOML:
+///
+/// ```toml
+/// [pre]
+/// balance = "1000000000000000000"
+///
+/// [pre.storage]
+/// "0x0" = "0x1"
+///
+/// [[calls]]
+/// calldata = "0xd0e30db0"
+/// value = "1000"
+///
+/// [[calls]]
+/// calldata = "0x2e1a7d4d00000000000000000000000000000000000000000000000000000000000003e8"
+/// caller = "0x0000000000000000000000000000000000000001"
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Bundle {
+    #[serde(default)]
+    pub pre: PreState,
+    pub calls: Vec<BundleCall>,
+}
+
+/// The state of the contract before the first call of a [`Bundle`].
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PreState {
+    pub balance: Option<String>,
+    #[serde(default)]
+    pub storage: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct BundleCall {
+    #[serde(default)]
+    pub calldata: String,
+    pub value: Option<String>,
+    pub caller: Option<String>,
+}
+
+/// The result of a single call within a bundle, and the state after it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleStep {
+    pub calldata: String,
+    pub value: U256,
+    pub success: bool,
+    pub exitcode: u128,
+    pub gas_used: u128,
+    pub returndata: String,
+    /// The contract's balance after the call. Only value sent with each call is counted, since
+    /// outgoing transfers aren't modelled by the VM.
+    pub balance: U256,
+    pub storage: BTreeMap<U256, U256>,
+    pub events: Vec<SimulatedLog>,
+}
+
+impl Bundle {
+    /// Read a bundle from the TOML file at `path`.
+    pub fn from_file(path: &str) -> Result<Bundle, Error> {
+        let bundle: Bundle = toml::from_str(&read_file(path))
+            .map_err(|e| Error::GenericError(format!("failed to parse bundle: {}", e)))?;
+
+        // every call is checked up front, so a malformed one doesn't fail the bundle partway
+        for call in &bundle.calls {
+            call.calldata()?;
+        }
+        Ok(bundle)
+    }
+
+    /// The pre-state storage, parsed into words.
+    pub fn pre_storage(&self) -> Result<BTreeMap<U256, U256>, Error> {
+        self.pre
+            .storage
+            .iter()
+            .map(|(slot, value)| match (parse_word(slot), parse_word(value)) {
+                (Some(slot), Some(value)) => Ok((slot, value)),
+                _ => Err(Error::GenericError(format!(
+                    "invalid pre-state storage '{slot}' = '{value}' ."
+                ))),
+            })
+            .collect()
+    }
+
+    /// The pre-state balance, parsed into a word.
+    pub fn pre_balance(&self) -> Result<U256, Error> {
+        match &self.pre.balance {
+            Some(balance) => parse_word(balance)
+                .ok_or(Error::GenericError(format!("invalid pre-state balance '{balance}' ."))),
+            None => Ok(U256::zero()),
+        }
+    }
+}
+
+impl BundleCall {
+    /// The calldata of this call, prefixed with `0x`.
+    pub fn calldata(&self) -> Result<String, Error> {
+        let calldata = self.calldata.replacen("0x", "", 1);
+        if !calldata.len().is_multiple_of(2) || !calldata.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(Error::GenericError(format!("invalid calldata '{}' .", self.calldata)))
+        }
+        Ok(format!("0x{calldata}"))
+    }
+
+    /// The value sent with this call, parsed into a word.
+    pub fn value(&self) -> Result<U256, Error> {
+        match &self.value {
+            Some(value) => parse_word(value)
+                .ok_or(Error::GenericError(format!("invalid call value '{value}' ."))),
+            None => Ok(U256::zero()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::types::U256;
+
+    use crate::simulate::bundle::Bundle;
+
+    #[test]
+    fn test_parse_bundle() {
+        let bundle: Bundle = toml::from_str(
+            r#"
+            [pre]
+            balance = "0x10"
+
+            [pre.storage]
+            "0x0" = "1"
+
+            [[calls]]
+            calldata = "0x01"
+            value = "5"
+
+            [[calls]]
+            caller = "0x0000000000000000000000000000000000000001"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(bundle.pre_balance().unwrap(), U256::from(16));
+        assert_eq!(bundle.pre_storage().unwrap().get(&U256::zero()), Some(&U256::one()));
+        assert_eq!(bundle.calls.len(), 2);
+        assert_eq!(bundle.calls[0].value().unwrap(), U256::from(5));
+        assert_eq!(bundle.calls[1].value().unwrap(), U256::zero());
+        assert_eq!(bundle.calls[1].calldata().unwrap(), "0x");
+    }
+
+    #[test]
+    fn test_invalid_pre_state() {
+        let bundle: Bundle = toml::from_str(
+            r#"
+            [pre.storage]
+            "slot" = "1"
+
+            [[calls]]
+            "#,
+        )
+        .unwrap();
+
+        assert!(bundle.pre_storage().is_err());
+    }
+
+    #[test]
+    fn test_invalid_calldata() {
+        let path = std::env::temp_dir().join("heimdall-test-invalid-calldata-bundle.toml");
+        std::fs::write(&path, "[[calls]]\ncalldata = \"0xabc\"\n").unwrap();
+
+        let bundle = Bundle::from_file(&path.to_string_lossy());
+        std::fs::remove_file(path).unwrap();
+
+        assert!(bundle.is_err());
+    }
+}
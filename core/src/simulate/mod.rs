@@ -0,0 +1,412 @@
+pub mod assertions;
+pub mod bundle;
+
+use std::collections::BTreeMap;
+
+use clap::{AppSettings, Parser};
+use derive_builder::Builder;
+use ethers::types::{
+    transaction::eip2930::{AccessList, AccessListItem},
+    H160, H256, U256,
+};
+use heimdall_common::{
+    constants::ADDRESS_REGEX,
+    ether::{
+        batch::{get_balance_batch, get_storage_batch},
+        bytecode::get_bytecode_from_target,
+        evm::core::vm::{ExecutionResult, VM},
+        rpc::{get_block_environment, get_code_at_block},
+    },
+    utils::{
+        io::logging::{set_logger_env, Logger},
+        strings::{decode_hex, encode_hex},
+    },
+};
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+
+use self::{
+    assertions::Assertions,
+    bundle::{Bundle, BundleStep},
+};
+
+#[derive(Debug, Clone, Parser, Builder)]
+#[clap(
+    about = "Simulate a call to EVM bytecode and report the resulting state",
+    after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    global_setting = AppSettings::DeriveDisplayOrder,
+    override_usage = "heimdall simulate <TARGET> [OPTIONS]"
+)]
+pub struct SimulateArgs {
+    /// The target to simulate, either a file, bytecode, contract address, or ENS name.
+    #[clap(required = true)]
+    pub target: String,
+
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// The RPC provider to use for fetching target bytecode.
+    #[clap(long = "rpc-url", short, default_value = "", hide_default_value = true)]
+    pub rpc_url: String,
+
+    /// The calldata to simulate the call with.
+    #[clap(long, short, default_value = "", hide_default_value = true)]
+    pub calldata: String,
+
+    /// The value, in wei, to simulate the call with.
+    #[clap(long, default_value = "0", hide_default_value = true)]
+    pub value: u128,
+
+    /// The block number to pin the simulation to. Bytecode, storage, and block values such as
+    /// `TIMESTAMP` and `NUMBER` are read as of this block. Requires an archive RPC provider.
+    #[clap(long)]
+    pub block: Option<u64>,
+
+    /// Path to a TOML file of post-conditions which must hold after the simulation.
+    #[clap(long = "assert", short = 'a', default_value = "", hide_default_value = true)]
+    pub assertions: String,
+
+    /// Path to a TOML file describing a pre-state and an ordered bundle of calls to simulate
+    /// instead of a single call.
+    #[clap(long = "bundle", short = 'b', default_value = "", hide_default_value = true)]
+    pub bundle: String,
+
+    /// The output directory to write the output to or 'print' to print to the console
+    #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
+    pub output: String,
+
+    /// The name for the output file
+    #[clap(long, short, default_value = "", hide_default_value = true)]
+    pub name: String,
+}
+
+impl SimulateArgsBuilder {
+    pub fn new() -> Self {
+        Self {
+            target: Some(String::new()),
+            verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
+            rpc_url: Some(String::new()),
+            calldata: Some(String::new()),
+            value: Some(0),
+            block: Some(None),
+            assertions: Some(String::new()),
+            bundle: Some(String::new()),
+            output: Some(String::new()),
+            name: Some(String::new()),
+        }
+    }
+}
+
+/// A log emitted during simulation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SimulatedLog {
+    pub topics: Vec<U256>,
+    pub data: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulateResult {
+    /// Whether the call exited with `STOP` or `RETURN`
+    pub success: bool,
+    pub exitcode: u128,
+    pub gas_used: u128,
+    pub returndata: String,
+    /// The contract's balance after the call, which includes the value sent with it if it
+    /// succeeded. Outgoing transfers aren't modelled by the VM.
+    pub balance: U256,
+    /// The post-state of every storage slot written during the call
+    pub storage: BTreeMap<U256, U256>,
+    pub events: Vec<SimulatedLog>,
+    /// The EIP-2930 access list of every address and storage key touched during the call
+    pub access_list: AccessList,
+    /// The result of each call and the state after it, if `--bundle` was given. The fields above
+    /// then describe the final call.
+    pub bundle: Vec<BundleStep>,
+    /// A description of each assertion which didn't hold, if `--assert` was given
+    pub failed_assertions: Vec<String>,
+}
+
+/// The maximum number of times a simulation pinned to a block is re-run to fetch storage slots
+/// it reads for the first time.
+const MAX_STORAGE_FETCH_ROUNDS: usize = 8;
+
+/// Simulate a call to the given target's bytecode in heimdall's VM. The contract starts with
+/// empty storage, unless `--block` is given for a deployed contract, in which case every slot
+/// the call reads is fetched as of that block.
+pub async fn simulate(args: SimulateArgs) -> Result<SimulateResult, Error> {
+    set_logger_env(&args.verbose);
+
+    // get a new logger
+    let (logger, _) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    // read assertions before simulating, so a malformed file fails fast
+    let assertions = if args.assertions.is_empty() {
+        None
+    } else {
+        Some(Assertions::from_file(&args.assertions)?)
+    };
+
+    let bundle = if args.bundle.is_empty() { None } else { Some(Bundle::from_file(&args.bundle)?) };
+
+    let is_address = ADDRESS_REGEX.is_match(&args.target).unwrap_or(false);
+    let contract_bytecode = match args.block {
+        Some(block) if is_address => {
+            logger.debug(&format!("pinning simulation to block {block} ."));
+            get_code_at_block(&args.target, &args.rpc_url, Some(block)).await
+        }
+        _ => get_bytecode_from_target(&args.target, &args.rpc_url).await,
+    }
+    .map_err(|e| Error::GenericError(e.to_string()))?;
+
+    // when simulating a deployed contract, use its address so the access list can be used as-is
+    let address = if is_address {
+        format!("0x{}", args.target.replacen("0x", "", 1))
+    } else {
+        String::from("0x6865696d64616c6c000000000061646472657373")
+    };
+
+    let mut vm = VM::new(
+        contract_bytecode,
+        format!("0x{}", args.calldata.replacen("0x", "", 1)),
+        address.clone(),
+        String::from("0x6865696d64616c6c0000000000006f726967696e"),
+        String::from("0x6865696d64616c6c00000000000063616c6c6572"),
+        args.value,
+        u128::MAX,
+    );
+
+    // a deployed contract starts with its balance at the pinned block, and otherwise with none
+    let pre_balance = match args.block {
+        Some(block) if is_address => {
+            get_balance_batch(std::slice::from_ref(&address), block, &args.rpc_url)
+                .await
+                .map_err(|e| Error::GenericError(e.to_string()))?
+                .pop()
+                .unwrap_or_default()
+        }
+        _ => U256::zero(),
+    };
+    vm.balance = Some(pre_balance.saturating_add(U256::from(args.value)));
+
+    if let Some(block) = args.block {
+        vm.block = Some(
+            get_block_environment(block, &args.rpc_url)
+                .await
+                .map_err(|e| Error::GenericError(e.to_string()))?,
+        );
+    }
+
+    // storage fetched as of the pinned block, which the bundle's pre-state takes precedence over
+    let mut fetched: BTreeMap<U256, U256> = BTreeMap::new();
+    let mut rounds = 0;
+    let mut result = loop {
+        let mut run = vm.clone();
+        for (slot, value) in &fetched {
+            run.storage.storage.insert((*slot).into(), (*value).into());
+        }
+
+        let result = match &bundle {
+            Some(bundle) => simulate_bundle(&mut run, bundle, pre_balance, &logger)?,
+            None => {
+                let execution = run.execute();
+                let mut result = build_result(&run, &execution);
+                result.balance = match result.success {
+                    true => run.balance.unwrap_or_default(),
+                    false => pre_balance,
+                };
+                result
+            }
+        };
+
+        let Some(block) = args.block.filter(|_| is_address) else { break result };
+
+        // re-run the simulation until every slot it reads has been fetched
+        let missing = run
+            .storage
+            .accessed_keys()
+            .iter()
+            .map(U256::from)
+            .filter(|slot| !fetched.contains_key(slot))
+            .collect::<Vec<U256>>();
+        if missing.is_empty() || rounds == MAX_STORAGE_FETCH_ROUNDS {
+            if !missing.is_empty() {
+                logger.warn(&format!(
+                    "{} storage slots were read without being fetched from block {block} .",
+                    missing.len()
+                ));
+            }
+            break result
+        }
+
+        let slots =
+            missing.iter().map(|slot| H256::from(<[u8; 32]>::from(*slot))).collect::<Vec<_>>();
+        let values = get_storage_batch(&address, &slots, block, &args.rpc_url)
+            .await
+            .map_err(|e| Error::GenericError(e.to_string()))?;
+        for (slot, value) in missing.into_iter().zip(values) {
+            fetched.insert(slot, U256::from(value.as_bytes()));
+        }
+        rounds += 1;
+    };
+
+    logger.info(&format!(
+        "simulation {} with exit code {} after using {} gas.",
+        if result.success { "succeeded" } else { "failed" },
+        result.exitcode,
+        result.gas_used
+    ));
+
+    if let Some(assertions) = assertions {
+        result.failed_assertions = assertions.check(&result);
+
+        if result.failed_assertions.is_empty() {
+            logger.success("all assertions passed.");
+        }
+        for failure in &result.failed_assertions {
+            logger.error(&format!("assertion failed: {failure}"));
+        }
+    }
+
+    Ok(result)
+}
+
+/// Build a [`SimulateResult`] from the VM's state after the given execution.
+fn build_result(vm: &VM, execution: &ExecutionResult) -> SimulateResult {
+    SimulateResult {
+        success: execution.exitcode == 0 || execution.exitcode == 10,
+        exitcode: execution.exitcode,
+        gas_used: execution.gas_used,
+        returndata: format!("0x{}", encode_hex(execution.returndata.clone())),
+        balance: U256::zero(),
+        storage: vm
+            .storage
+            .storage
+            .iter()
+            .map(|(slot, value)| (U256::from(slot), U256::from(value)))
+            .collect(),
+        events: execution
+            .events
+            .iter()
+            .map(|log| SimulatedLog {
+                topics: log.topics.clone(),
+                data: format!("0x{}", encode_hex(log.data.clone())),
+            })
+            .collect(),
+        access_list: build_access_list(vm),
+        bundle: Vec::new(),
+        failed_assertions: Vec::new(),
+    }
+}
+
+/// Simulate each call of the bundle in order. Storage carries over from one call to the next,
+/// and the contract's balance, which it reads with `SELFBALANCE`, grows by the value sent with
+/// each successful call. It starts at the bundle's pre-state balance, or `pre_balance` if the
+/// bundle doesn't set one.
+fn simulate_bundle(
+    vm: &mut VM,
+    bundle: &Bundle,
+    pre_balance: U256,
+    logger: &Logger,
+) -> Result<SimulateResult, Error> {
+    if bundle.calls.is_empty() {
+        return Err(Error::GenericError("bundle must contain at least one call.".to_string()))
+    }
+
+    // the pre-state is written directly, so it doesn't appear in the access list
+    for (slot, value) in bundle.pre_storage()? {
+        vm.storage.storage.insert(slot.into(), value.into());
+    }
+    let mut balance = match bundle.pre.balance {
+        Some(_) => bundle.pre_balance()?,
+        None => pre_balance,
+    };
+
+    let mut steps = Vec::new();
+    let mut result = None;
+    for (index, call) in bundle.calls.iter().enumerate() {
+        let value = call.value()?;
+        if let Some(caller) = &call.caller {
+            vm.caller = decode_hex(&caller.replacen("0x", "", 1))
+                .map_err(|e| Error::GenericError(format!("invalid caller '{caller}': {e}")))?;
+        }
+
+        // the value sent is credited before the call runs, and kept if it succeeds
+        let calldata = call.calldata()?;
+        vm.balance = Some(balance.saturating_add(value));
+        let execution = vm.call(
+            &calldata,
+            value
+                .try_into()
+                .map_err(|_| Error::GenericError(format!("call value {value} is too large.")))?,
+        );
+        let call_result = build_result(vm, &execution);
+
+        if call_result.success {
+            balance = balance.saturating_add(value);
+        }
+        logger.debug(&format!(
+            "bundle call {} {} with exit code {}.",
+            index,
+            if call_result.success { "succeeded" } else { "failed" },
+            call_result.exitcode
+        ));
+
+        steps.push(BundleStep {
+            calldata,
+            value,
+            success: call_result.success,
+            exitcode: call_result.exitcode,
+            gas_used: call_result.gas_used,
+            returndata: call_result.returndata.clone(),
+            balance,
+            storage: call_result.storage.clone(),
+            events: call_result.events.clone(),
+        });
+        result = Some(call_result);
+    }
+
+    let mut result = result.expect("bundle has at least one call");
+    result.balance = balance;
+    result.bundle = steps;
+    Ok(result)
+}
+
+/// Build the EIP-2930 access list for the given VM after execution. The contract itself is
+/// always listed first, with every storage key it accessed.
+pub fn build_access_list(vm: &VM) -> AccessList {
+    let mut storage_keys =
+        vm.storage.accessed_keys().iter().map(|key| H256::from(*key)).collect::<Vec<H256>>();
+    storage_keys.sort();
+
+    let mut addresses = vm
+        .address_access_set
+        .iter()
+        .map(|address| {
+            let mut word = [0u8; 32];
+            address.to_big_endian(&mut word);
+            H160::from_slice(&word[12..])
+        })
+        .filter(|address| address.as_bytes() != vm.address.as_slice())
+        .collect::<Vec<H160>>();
+    addresses.sort();
+
+    let mut items = vec![AccessListItem { address: H160::from_slice(&vm.address), storage_keys }];
+    items.extend(
+        addresses.into_iter().map(|address| AccessListItem { address, storage_keys: Vec::new() }),
+    );
+
+    AccessList(items)
+}
+
+/// Parse a 32-byte word from either `0x` prefixed hex or decimal.
+pub(crate) fn parse_word(value: &str) -> Option<U256> {
+    match value.strip_prefix("0x") {
+        Some(hex) => U256::from_str_radix(hex, 16).ok(),
+        None => U256::from_dec_str(value).ok(),
+    }
+}
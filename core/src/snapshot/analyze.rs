@@ -1,4 +1,4 @@
-use crate::decompile::constants::AND_BITMASK_REGEX;
+use crate::{decompile::constants::AND_BITMASK_REGEX, usage::reverts::decode_revert};
 
 use super::{
     constants::VARIABLE_SIZE_CHECK_REGEX,
@@ -79,6 +79,8 @@ pub fn snapshot_trace(
                 "BASEFEE",
                 "SLOAD",
                 "SSTORE",
+                "TLOAD",
+                "TSTORE",
                 "CREATE",
                 "SELFDESTRUCT",
                 "CALL",
@@ -104,6 +106,7 @@ pub fn snapshot_trace(
         if snapshot.view &&
             [
                 "SSTORE",
+                "TSTORE",
                 "CREATE",
                 "SELFDESTRUCT",
                 "CALL",
@@ -147,6 +150,10 @@ pub fn snapshot_trace(
             // this is an if conditional for the children branches
             let conditional = instruction.input_operations[1].solidify().cleanup();
 
+            if conditional.contains("block.timestamp") {
+                snapshot.timestamp_checks.insert(conditional.clone());
+            }
+
             // remove non-payable check and mark function as non-payable
             if conditional == "!msg.value" {
                 // this is marking the start of a non-payable function
@@ -181,6 +188,18 @@ pub fn snapshot_trace(
             let size: usize = instruction.inputs[1].try_into().unwrap_or(0);
             let revert_data = memory.read(offset, size);
 
+            // messages and panics are kept as they're decoded, and custom errors are resolved
+            // later
+            match decode_revert(&revert_data) {
+                (Some(_), _) => {
+                    snapshot.errors.insert(U256::from(&revert_data[..4]), None);
+                }
+                (None, reason) if revert_data.len() >= 4 && !reason.contains('\0') => {
+                    snapshot.revert_reasons.insert(reason);
+                }
+                _ => {}
+            }
+
             if let Some(hex_data) = revert_data.get(4..) {
                 if let Ok(reverts_with) = decode(&[ParamType::String], hex_data) {
                     if !reverts_with[0].to_string().is_empty() &&
@@ -247,8 +266,14 @@ pub fn snapshot_trace(
                     };
                 }
             }
+        } else if opcode_name == "SELFDESTRUCT" {
+            snapshot.self_destructs = true;
         } else if opcode_name == "SSTORE" || opcode_name == "SLOAD" {
-            snapshot.storage.insert(instruction.input_operations[0].solidify().cleanup());
+            let slot = instruction.input_operations[0].solidify().cleanup();
+            if opcode_name == "SSTORE" {
+                snapshot.storage_writes.insert(slot.clone());
+            }
+            snapshot.storage.insert(slot);
         } else if opcode_name == "CALLDATALOAD" {
             let slot_as_usize: usize = instruction.inputs[0].try_into().unwrap_or(usize::MAX);
             let calldata_slot = (slot_as_usize.saturating_sub(4)) / 32;
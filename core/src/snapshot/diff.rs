@@ -0,0 +1,309 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use heimdall_common::utils::strings::encode_hex_reduced;
+use serde::{Deserialize, Serialize};
+
+use crate::snapshot::{
+    governance::GovernanceSummary,
+    reverts::RevertTable,
+    structures::snapshot::{GasUsed, Snapshot},
+};
+
+/// The parts of a function's snapshot which are compared between runs. Unlike [`Snapshot`],
+/// this can be stored and read back.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FunctionSummary {
+    pub selector: String,
+    pub signature: Option<String>,
+    pub payable: bool,
+    pub view: bool,
+    pub pure: bool,
+    /// Control statements, such as access control guards
+    pub guards: BTreeSet<String>,
+    pub external_calls: BTreeSet<String>,
+    pub addresses: BTreeSet<String>,
+    pub storage: BTreeSet<String>,
+    pub events: BTreeSet<String>,
+    pub errors: BTreeSet<String>,
+    /// Storage slots the function writes
+    #[serde(default)]
+    pub writes: BTreeSet<String>,
+    /// Conditions which compare against `block.timestamp`
+    #[serde(default)]
+    pub delays: BTreeSet<String>,
+    #[serde(default)]
+    pub self_destructs: bool,
+    /// The messages, panics, and custom errors the function reverts with
+    #[serde(default)]
+    pub reverts: BTreeSet<String>,
+    /// The least, most, and average gas used across the paths symbolic execution took. Gas
+    /// changes with any change to the code, so it isn't compared between snapshots.
+    #[serde(default)]
+    pub gas: GasUsed,
+}
+
+/// A stored snapshot, keyed by selector.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotSummary {
+    pub functions: BTreeMap<String, FunctionSummary>,
+    /// Who can change what, and after what delay
+    #[serde(default)]
+    pub governance: GovernanceSummary,
+    /// Each revert reason, and the functions which revert with it
+    #[serde(default)]
+    pub reverts: RevertTable,
+}
+
+/// The semantic changes to a single function between two snapshots.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FunctionChanges {
+    pub selector: String,
+    pub signature: Option<String>,
+    pub changes: Vec<String>,
+}
+
+/// The semantic changes between two snapshots of a contract.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<FunctionChanges>,
+    /// External dependencies which no function in the old snapshot had
+    pub new_dependencies: Vec<String>,
+}
+
+impl SnapshotDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl FunctionSummary {
+    fn from_snapshot(snapshot: &Snapshot) -> FunctionSummary {
+        FunctionSummary {
+            selector: format!("0x{}", snapshot.selector),
+            signature: snapshot
+                .resolved_function
+                .as_ref()
+                .map(|function| function.signature.clone()),
+            payable: snapshot.payable,
+            view: snapshot.view,
+            pure: snapshot.pure,
+            guards: snapshot.control_statements.iter().cloned().collect(),
+            external_calls: snapshot.external_calls.iter().cloned().collect(),
+            addresses: snapshot.addresses.iter().cloned().collect(),
+            storage: snapshot.storage.iter().cloned().collect(),
+            events: snapshot.events.keys().map(|event| encode_hex_reduced(*event)).collect(),
+            errors: snapshot.errors.keys().map(|error| encode_hex_reduced(*error)).collect(),
+            writes: snapshot.storage_writes.iter().cloned().collect(),
+            delays: snapshot.timestamp_checks.iter().cloned().collect(),
+            self_destructs: snapshot.self_destructs,
+            reverts: snapshot
+                .revert_reasons
+                .iter()
+                .cloned()
+                .chain(snapshot.errors.iter().map(
+                    |(selector, resolved_error)| match resolved_error {
+                        Some(resolved_error) => resolved_error.signature.clone(),
+                        None => encode_hex_reduced(*selector),
+                    },
+                ))
+                .collect(),
+            gas: snapshot.gas_used.clone(),
+        }
+    }
+
+    /// The function's state mutability, as it would be written in Solidity.
+    pub fn mutability(&self) -> &'static str {
+        match (self.pure, self.view, self.payable) {
+            (true, ..) => "pure",
+            (_, true, _) => "view",
+            (_, _, true) => "payable",
+            _ => "nonpayable",
+        }
+    }
+
+    /// The external contracts this function depends on: hardcoded addresses and call targets.
+    fn dependencies(&self) -> BTreeSet<String> {
+        self.addresses
+            .iter()
+            .cloned()
+            .chain(self.external_calls.iter().filter_map(|call| {
+                call.strip_prefix("address(")
+                    .and_then(|call| call.split_once(')'))
+                    .map(|(target, _)| target.to_string())
+            }))
+            .collect()
+    }
+}
+
+impl SnapshotSummary {
+    pub fn from_snapshots(snapshots: &[Snapshot]) -> SnapshotSummary {
+        let functions = snapshots
+            .iter()
+            .map(FunctionSummary::from_snapshot)
+            .map(|function| (function.selector.clone(), function))
+            .collect();
+
+        SnapshotSummary {
+            governance: GovernanceSummary::from_functions(&functions),
+            reverts: RevertTable::from_functions(&functions),
+            functions,
+        }
+    }
+
+    fn dependencies(&self) -> BTreeSet<String> {
+        self.functions.values().flat_map(|function| function.dependencies()).collect()
+    }
+}
+
+/// Describe the items which were added to or removed from a set, e.g. `guard added: ..`.
+fn describe_set_changes(
+    name: &str,
+    old: &BTreeSet<String>,
+    new: &BTreeSet<String>,
+    changes: &mut Vec<String>,
+) {
+    changes.extend(new.difference(old).map(|item| format!("{name} added: {item}")));
+    changes.extend(old.difference(new).map(|item| format!("{name} removed: {item}")));
+}
+
+/// Compare two snapshots of a contract, reporting new and removed selectors, and changes to the
+/// guards, mutability, external calls, storage, events, errors, and reverts of each remaining
+/// function.
+pub fn diff_snapshots(old: &SnapshotSummary, new: &SnapshotSummary) -> SnapshotDiff {
+    let mut diff = SnapshotDiff {
+        added: new
+            .functions
+            .keys()
+            .filter(|selector| !old.functions.contains_key(*selector))
+            .cloned()
+            .collect(),
+        removed: old
+            .functions
+            .keys()
+            .filter(|selector| !new.functions.contains_key(*selector))
+            .cloned()
+            .collect(),
+        new_dependencies: new.dependencies().difference(&old.dependencies()).cloned().collect(),
+        ..Default::default()
+    };
+
+    for (selector, new_function) in &new.functions {
+        let old_function = match old.functions.get(selector) {
+            Some(old_function) => old_function,
+            None => continue,
+        };
+
+        let mut changes = Vec::new();
+        if old_function.mutability() != new_function.mutability() {
+            changes.push(format!(
+                "mutability changed: {} -> {}",
+                old_function.mutability(),
+                new_function.mutability()
+            ));
+        }
+        describe_set_changes("guard", &old_function.guards, &new_function.guards, &mut changes);
+        describe_set_changes(
+            "external call",
+            &old_function.external_calls,
+            &new_function.external_calls,
+            &mut changes,
+        );
+        describe_set_changes(
+            "hardcoded address",
+            &old_function.addresses,
+            &new_function.addresses,
+            &mut changes,
+        );
+        describe_set_changes("storage", &old_function.storage, &new_function.storage, &mut changes);
+        describe_set_changes("event", &old_function.events, &new_function.events, &mut changes);
+        describe_set_changes("error", &old_function.errors, &new_function.errors, &mut changes);
+        describe_set_changes("revert", &old_function.reverts, &new_function.reverts, &mut changes);
+
+        if !changes.is_empty() {
+            diff.changed.push(FunctionChanges {
+                selector: selector.clone(),
+                signature: new_function.signature.clone(),
+                changes,
+            });
+        }
+    }
+
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::snapshot::diff::*;
+
+    fn function(selector: &str) -> FunctionSummary {
+        FunctionSummary { selector: selector.to_string(), payable: true, ..Default::default() }
+    }
+
+    fn summary(functions: Vec<FunctionSummary>) -> SnapshotSummary {
+        SnapshotSummary {
+            functions: functions
+                .into_iter()
+                .map(|function| (function.selector.clone(), function))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_diff_selectors() {
+        let old = summary(vec![function("0x01"), function("0x02")]);
+        let new = summary(vec![function("0x02"), function("0x03")]);
+
+        let diff = diff_snapshots(&old, &new);
+        assert_eq!(diff.added, vec!["0x03".to_string()]);
+        assert_eq!(diff.removed, vec!["0x01".to_string()]);
+        assert!(diff.changed.is_empty());
+    }
+
+    #[test]
+    fn test_diff_guards_and_dependencies() {
+        let old = summary(vec![function("0x01")]);
+        let mut changed = function("0x01");
+        changed.view = true;
+        changed.guards.insert("if (msg.sender == storage[0]) { .. }".to_string());
+        changed.external_calls.insert("address(0x1234).staticcall(0x01);".to_string());
+        let new = summary(vec![changed]);
+
+        let diff = diff_snapshots(&old, &new);
+        assert_eq!(
+            diff.changed[0].changes,
+            vec![
+                "mutability changed: payable -> view".to_string(),
+                "guard added: if (msg.sender == storage[0]) { .. }".to_string(),
+                "external call added: address(0x1234).staticcall(0x01);".to_string(),
+            ]
+        );
+        assert_eq!(diff.new_dependencies, vec!["0x1234".to_string()]);
+    }
+
+    #[test]
+    fn test_diff_unchanged() {
+        let old = summary(vec![function("0x01")]);
+
+        assert!(diff_snapshots(&old, &old.clone()).is_empty());
+    }
+
+    #[test]
+    fn test_diff_ignores_gas() {
+        let old = summary(vec![function("0x01")]);
+        let mut cheaper = function("0x01");
+        cheaper.gas = GasUsed { min: 21000, max: 30000, avg: 25000 };
+        let new = summary(vec![cheaper]);
+
+        assert!(diff_snapshots(&old, &new).is_empty());
+
+        // snapshots stored before gas was summarized can still be compared against
+        let stored = serde_json::to_value(&old).unwrap();
+        let mut function = stored["functions"]["0x01"].clone();
+        function.as_object_mut().unwrap().remove("gas");
+        let read: FunctionSummary = serde_json::from_value(function).unwrap();
+        assert_eq!(read.gas, GasUsed::default());
+    }
+}
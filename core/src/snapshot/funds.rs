@@ -0,0 +1,279 @@
+use ethers::{
+    abi::{decode, encode, ParamType, Token},
+    types::{Address, U256},
+    utils::format_units,
+};
+use heimdall_common::{
+    error::Error,
+    ether::{batch::get_balance_batch, rpc::call},
+    utils::io::messages::{format_message, message},
+};
+use serde::{Deserialize, Serialize};
+
+/// Multicall3, which is deployed at the same address on nearly every chain.
+const MULTICALL3: &str = "0xcA11bde05977b3631167028862bE2a173976CA11";
+
+/// `aggregate3((address,bool,bytes)[])`
+const AGGREGATE3_SELECTOR: [u8; 4] = [0x82, 0xad, 0x56, 0xcb];
+
+/// `balanceOf(address)`
+const BALANCE_OF_SELECTOR: [u8; 4] = [0x70, 0xa0, 0x82, 0x31];
+
+/// The number of `balanceOf` calls batched into each multicall.
+const BATCH_SIZE: usize = 100;
+
+/// Widely held Ethereum mainnet tokens, which are checked when no token list is given.
+const MAINNET_TOKENS: [(&str, &str, u8); 10] = [
+    ("0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2", "WETH", 18),
+    ("0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48", "USDC", 6),
+    ("0xdac17f958d2ee523a2206206994597c13d831ec7", "USDT", 6),
+    ("0x6b175474e89094c44da98b954eedeac495271d0f", "DAI", 18),
+    ("0x2260fac5e5542a773aa44fbcfedf7c193bc2c599", "WBTC", 8),
+    ("0xae7ab96520de3a18e5e111b5eaab095312d7fe84", "stETH", 18),
+    ("0x7f39c581f595b53c5cb19bd0b3f8da6c935e2ca0", "wstETH", 18),
+    ("0x514910771af9ca656af840dff83e8264ecf986ca", "LINK", 18),
+    ("0x1f9840a85d5af5bf1d1762f925bdaddc4201f984", "UNI", 18),
+    ("0x9f8f72aa9304c8b593d555f12ef6589cc3a579a2", "MKR", 18),
+];
+
+/// A token which may be held by the target.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ListedToken {
+    #[serde(rename = "chainId", default)]
+    pub chain_id: Option<u64>,
+    pub address: String,
+    pub symbol: String,
+    pub decimals: u8,
+}
+
+/// A balance of a token held by the target.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenHolding {
+    pub token: String,
+    pub symbol: String,
+    pub decimals: u8,
+    pub balance: U256,
+}
+
+/// What the target holds, and so what's at stake if it's malicious or vulnerable.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FundsAtRisk {
+    pub block: u64,
+    pub ether: U256,
+    /// Tokens with a non-zero balance, in the order of the token list
+    pub tokens: Vec<TokenHolding>,
+    /// The number of tokens which were checked
+    pub tokens_checked: usize,
+}
+
+/// The tokens to check, from a token list in the Uniswap format, i.e. a `{"tokens": [..]}`
+/// object whose tokens have a `chainId`, `address`, `symbol`, and `decimals`. Tokens on other
+/// chains are skipped.
+pub fn parse_token_list(contents: &str, chain_id: u64) -> Result<Vec<ListedToken>, Error> {
+    #[derive(Deserialize)]
+    struct TokenList {
+        tokens: Vec<ListedToken>,
+    }
+
+    let list: TokenList = serde_json::from_str(contents)
+        .map_err(|e| Error::Generic(format!("failed to parse token list: {e}")))?;
+    Ok(list
+        .tokens
+        .into_iter()
+        .filter(|token| token.chain_id.is_none_or(|token_chain_id| token_chain_id == chain_id))
+        .collect())
+}
+
+/// The tokens checked when no token list is given. Only mainnet has a default list.
+pub fn default_tokens(chain_id: u64) -> Vec<ListedToken> {
+    match chain_id {
+        1 => MAINNET_TOKENS
+            .iter()
+            .map(|(address, symbol, decimals)| ListedToken {
+                chain_id: Some(1),
+                address: address.to_string(),
+                symbol: symbol.to_string(),
+                decimals: *decimals,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Encode an `aggregate3` call which gets `holder`'s balance of each token. Failed calls are
+/// allowed, so a token which isn't deployed on this chain doesn't fail the batch.
+fn encode_balance_calls(holder: Address, tokens: &[Address]) -> Vec<u8> {
+    let balance_of = [BALANCE_OF_SELECTOR.to_vec(), encode(&[Token::Address(holder)])].concat();
+    let calls = tokens
+        .iter()
+        .map(|token| {
+            Token::Tuple(vec![
+                Token::Address(*token),
+                Token::Bool(true),
+                Token::Bytes(balance_of.clone()),
+            ])
+        })
+        .collect();
+
+    [AGGREGATE3_SELECTOR.to_vec(), encode(&[Token::Array(calls)])].concat()
+}
+
+/// Decode the balances returned by an `aggregate3` call. A failed or malformed call's balance is
+/// `None`.
+fn decode_balances(output: &[u8]) -> Result<Vec<Option<U256>>, Error> {
+    let results = decode(
+        &[ParamType::Array(Box::new(ParamType::Tuple(vec![ParamType::Bool, ParamType::Bytes])))],
+        output,
+    )
+    .map_err(|e| Error::Generic(format!("failed to decode multicall output: {e}")))?;
+
+    Ok(results
+        .into_iter()
+        .next()
+        .and_then(|results| results.into_array())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|result| match result.into_tuple().as_deref() {
+            Some([Token::Bool(true), Token::Bytes(data)]) if data.len() == 32 => {
+                Some(U256::from_big_endian(data))
+            }
+            _ => None,
+        })
+        .collect())
+}
+
+/// Read the target's ether balance, and its balance of each token, at the given block. Token
+/// balances are read with batched `balanceOf` calls through Multicall3.
+pub async fn get_funds_at_risk(
+    target: &str,
+    tokens: &[ListedToken],
+    block: u64,
+    rpc_url: &str,
+) -> Result<FundsAtRisk, Error> {
+    let holder = target
+        .parse::<Address>()
+        .map_err(|e| Error::Generic(format!("invalid target address: {e}")))?;
+    let ether =
+        get_balance_batch(&[target.to_string()], block, rpc_url).await?.pop().unwrap_or_default();
+
+    let tokens = tokens
+        .iter()
+        .filter_map(|token| Some((token.address.parse::<Address>().ok()?, token)))
+        .collect::<Vec<_>>();
+    let mut holdings = Vec::new();
+    for batch in tokens.chunks(BATCH_SIZE) {
+        let addresses = batch.iter().map(|(address, _)| *address).collect::<Vec<_>>();
+        let output =
+            call(MULTICALL3, encode_balance_calls(holder, &addresses), block, rpc_url).await?;
+
+        for ((address, token), balance) in batch.iter().zip(decode_balances(&output)?) {
+            match balance {
+                Some(balance) if !balance.is_zero() => holdings.push(TokenHolding {
+                    token: format!("{address:#x}"),
+                    symbol: token.symbol.clone(),
+                    decimals: token.decimals,
+                    balance,
+                }),
+                _ => {}
+            }
+        }
+    }
+
+    Ok(FundsAtRisk { block, ether, tokens: holdings, tokens_checked: tokens.len() })
+}
+
+impl FundsAtRisk {
+    /// Render the holdings as a markdown report.
+    pub fn to_markdown(&self) -> String {
+        let mut lines = vec![format!("# {}", message("funds.title")), String::new()];
+        lines.push(format_message(
+            "funds.summary",
+            &[("block", &self.block.to_string()), ("tokens", &self.tokens_checked.to_string())],
+        ));
+        lines.push(String::new());
+        lines.push(format!(
+            "| {} | {} | {} |",
+            message("funds.asset"),
+            message("funds.address"),
+            message("funds.balance")
+        ));
+        lines.push("| --- | --- | --- |".to_string());
+        lines.push(format!("| ETH | | {} |", format_balance(self.ether, 18)));
+        for holding in &self.tokens {
+            lines.push(format!(
+                "| {} | `{}` | {} |",
+                holding.symbol,
+                holding.token,
+                format_balance(holding.balance, holding.decimals)
+            ));
+        }
+
+        lines.join("\n")
+    }
+}
+
+/// Format a balance in whole units, without trailing zeros, e.g. `1.5`.
+fn format_balance(balance: U256, decimals: u8) -> String {
+    match format_units(balance, decimals as u32) {
+        Ok(formatted) if formatted.contains('.') => {
+            formatted.trim_end_matches('0').trim_end_matches('.').to_string()
+        }
+        Ok(formatted) => formatted,
+        Err(_) => balance.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::snapshot::funds::*;
+
+    #[test]
+    fn test_balance_calls_round_trip() {
+        let calldata =
+            encode_balance_calls(Address::repeat_byte(0x11), &[Address::repeat_byte(0x22)]);
+        assert_eq!(calldata[..4], AGGREGATE3_SELECTOR);
+
+        // one successful call returning 5, and one failed call
+        let output = encode(&[Token::Array(vec![
+            Token::Tuple(vec![Token::Bool(true), Token::Bytes(encode(&[Token::Uint(5.into())]))]),
+            Token::Tuple(vec![Token::Bool(false), Token::Bytes(Vec::new())]),
+        ])]);
+        assert_eq!(decode_balances(&output).unwrap(), vec![Some(U256::from(5)), None]);
+    }
+
+    #[test]
+    fn test_parse_token_list() {
+        let tokens = parse_token_list(
+            r#"{"tokens": [
+                {"chainId": 1, "address": "0x01", "symbol": "A", "decimals": 18},
+                {"chainId": 10, "address": "0x02", "symbol": "B", "decimals": 6}
+            ]}"#,
+            1,
+        )
+        .unwrap();
+
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].symbol, "A");
+        assert_eq!(default_tokens(1).len(), MAINNET_TOKENS.len());
+        assert!(default_tokens(10).is_empty());
+    }
+
+    #[test]
+    fn test_funds_markdown() {
+        let funds = FundsAtRisk {
+            block: 100,
+            ether: U256::exp10(18) * 3 / 2,
+            tokens: vec![TokenHolding {
+                token: String::from("0x01"),
+                symbol: String::from("USDC"),
+                decimals: 6,
+                balance: U256::from(2_000_000),
+            }],
+            tokens_checked: 10,
+        };
+
+        let markdown = funds.to_markdown();
+        assert!(markdown.contains("| ETH | | 1.5 |"));
+        assert!(markdown.contains("| USDC | `0x01` | 2 |"));
+    }
+}
@@ -0,0 +1,495 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use heimdall_common::utils::io::messages::{format_message, message};
+use serde::{Deserialize, Serialize};
+
+use crate::snapshot::diff::FunctionSummary;
+
+/// A state-changing function which only some callers can use.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GovernedFunction {
+    pub selector: String,
+    pub signature: Option<String>,
+    /// The conditions on the caller, i.e. `msg.sender == storage[0]`
+    pub callers: Vec<String>,
+    /// The storage slots the function writes
+    pub writes: Vec<String>,
+    /// Conditions which compare against `block.timestamp` before the change is made
+    pub delays: Vec<String>,
+    /// Whether only the contract itself can call the function, i.e. through its own timelock
+    pub self_governed: bool,
+}
+
+/// The functions which queue and execute delayed transactions, identified by the hashes they
+/// store and the timestamp comparisons they make.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Timelock {
+    /// Functions which store a hash and check a delay, without making external calls
+    pub queue: Vec<String>,
+    /// Functions which check a stored hash and a delay, then make external calls
+    pub execute: Vec<String>,
+    /// Each distinct delay comparison made by the queue and execute functions
+    pub delays: Vec<String>,
+}
+
+/// The kinds of power an admin-controlled function gives its callers over users.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskKind {
+    /// Writes storage which other, public functions check before running
+    Pause,
+    /// Moves ether or tokens out of the contract, i.e. `sweep` or `rescue`
+    Withdrawal,
+    /// Can destroy the contract
+    SelfDestruct,
+    /// Sets a fee without checking it against an upper bound
+    UnboundedFee,
+}
+
+impl RiskKind {
+    /// The key of the risk's messages in the message catalog, i.e. `risk.pause`.
+    fn message_key(&self) -> &'static str {
+        match self {
+            RiskKind::Pause => "risk.pause",
+            RiskKind::Withdrawal => "risk.withdrawal",
+            RiskKind::SelfDestruct => "risk.self_destruct",
+            RiskKind::UnboundedFee => "risk.unbounded_fee",
+        }
+    }
+
+    /// The risk's label in reports.
+    pub fn label(&self) -> String {
+        message(&format!("{}.label", self.message_key()))
+    }
+
+    /// A description of the risk, with `{functions}` filled in by the functions it affects.
+    fn description(&self, values: &[(&str, &str)]) -> String {
+        format_message(&format!("{}.description", self.message_key()), values)
+    }
+}
+
+/// An admin-controlled function which users of the contract have to trust its admin with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CentralizationRisk {
+    pub kind: RiskKind,
+    pub selector: String,
+    pub signature: Option<String>,
+    pub description: String,
+    /// The storage the caller is checked against, i.e. `storage[0]` for an owner
+    pub controlled_by: Vec<String>,
+}
+
+/// The contract's governance surface: who can change what, and after what delay.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GovernanceSummary {
+    pub timelock: Option<Timelock>,
+    pub functions: Vec<GovernedFunction>,
+    #[serde(default)]
+    pub centralization_risks: Vec<CentralizationRisk>,
+}
+
+/// The condition of a control statement, i.e. `msg.sender == storage[0]` for
+/// `if (msg.sender == storage[0]) { .. }`.
+fn condition(guard: &str) -> &str {
+    guard.strip_prefix("if (").and_then(|guard| guard.strip_suffix(") { .. }")).unwrap_or(guard)
+}
+
+/// A function's name in the summary: its signature if it was resolved, otherwise its selector.
+fn display_name(function: &FunctionSummary) -> String {
+    function.signature.clone().unwrap_or_else(|| function.selector.clone())
+}
+
+/// Each `storage[..]` expression in a condition, i.e. `storage[0]` in
+/// `msg.sender == storage[0]`.
+fn storage_references(condition: &str) -> Vec<String> {
+    let mut references = Vec::new();
+    let mut rest = condition;
+
+    while let Some(start) = rest.find("storage[") {
+        let mut depth = 0;
+        let mut end = None;
+        for (index, c) in rest[start..].char_indices() {
+            match c {
+                '[' => depth += 1,
+                ']' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        end = Some(start + index + 1);
+                        break
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        match end {
+            Some(end) => {
+                references.push(rest[start..end].to_string());
+                rest = &rest[end..];
+            }
+            None => break,
+        }
+    }
+
+    references
+}
+
+/// The name of a resolved function, lowercased, i.e. `setfee` for `setFee(uint256)`.
+fn function_name(function: &FunctionSummary) -> Option<String> {
+    function
+        .signature
+        .as_ref()
+        .map(|signature| signature.split('(').next().unwrap_or_default().to_lowercase())
+}
+
+/// Whether an external call moves ether or tokens: it sends a value, or calls a token's
+/// `transfer` or `transferFrom`.
+fn moves_funds(call: &str) -> bool {
+    let sends_value =
+        call.split_once("value: ").map(|(_, value)| !value.starts_with("0 ")).unwrap_or(false);
+
+    sends_value || call.contains("0xa9059cbb") || call.contains("0x23b872dd")
+}
+
+/// The centralization risks of a single admin-controlled function.
+fn function_risks(
+    function: &FunctionSummary,
+    functions: &BTreeMap<String, FunctionSummary>,
+    governed: &BTreeSet<String>,
+) -> Vec<(RiskKind, String)> {
+    let mut risks = Vec::new();
+    let name = function_name(function).unwrap_or_default();
+
+    // public functions which check a slot this function writes can be blocked by it
+    let blocked = functions
+        .values()
+        .filter(|other| {
+            !governed.contains(&other.selector) &&
+                !other.view &&
+                !other.pure &&
+                other.guards.iter().any(|guard| {
+                    function.writes.iter().any(|slot| guard.contains(&format!("storage[{slot}]")))
+                })
+        })
+        .map(display_name)
+        .collect::<Vec<String>>();
+    if !blocked.is_empty() {
+        risks.push((
+            RiskKind::Pause,
+            RiskKind::Pause.description(&[("functions", &code_list(&blocked))]),
+        ));
+    }
+
+    if function.external_calls.iter().any(|call| moves_funds(call)) ||
+        ["sweep", "rescue", "recover", "emergency", "drain"]
+            .iter()
+            .any(|prefix| name.contains(prefix))
+    {
+        risks.push((RiskKind::Withdrawal, RiskKind::Withdrawal.description(&[])));
+    }
+
+    if function.self_destructs {
+        risks.push((RiskKind::SelfDestruct, RiskKind::SelfDestruct.description(&[])));
+    }
+
+    let bounds_argument = function
+        .guards
+        .iter()
+        .any(|guard| guard.contains("arg") && (guard.contains('<') || guard.contains('>')));
+    if (name.contains("fee") || name.contains("tax")) &&
+        !function.writes.is_empty() &&
+        !bounds_argument
+    {
+        risks.push((RiskKind::UnboundedFee, RiskKind::UnboundedFee.description(&[])));
+    }
+
+    risks
+}
+
+impl GovernanceSummary {
+    /// Summarize the governance surface of a contract's functions, keyed by selector.
+    pub fn from_functions(functions: &BTreeMap<String, FunctionSummary>) -> GovernanceSummary {
+        let mut timelock = Timelock::default();
+        let mut delays = BTreeSet::new();
+        let mut governed = Vec::new();
+
+        for function in functions.values() {
+            let stores_hash = function.writes.iter().any(|slot| slot.contains("keccak256"));
+            let checks_hash =
+                function.guards.iter().any(|guard| guard.contains("storage[keccak256"));
+            if !function.delays.is_empty() {
+                if stores_hash && function.external_calls.is_empty() {
+                    timelock.queue.push(display_name(function));
+                    delays.extend(function.delays.iter().cloned());
+                } else if checks_hash && !function.external_calls.is_empty() {
+                    timelock.execute.push(display_name(function));
+                    delays.extend(function.delays.iter().cloned());
+                }
+            }
+
+            let callers = function
+                .guards
+                .iter()
+                .map(|guard| condition(guard))
+                .filter(|guard| guard.contains("msg.sender") || guard.contains("tx.origin"))
+                .map(|guard| guard.to_string())
+                .collect::<Vec<String>>();
+            let changes_state = !function.view &&
+                !function.pure &&
+                (!function.writes.is_empty() || !function.external_calls.is_empty());
+            if callers.is_empty() || !changes_state {
+                continue
+            }
+
+            governed.push(GovernedFunction {
+                selector: function.selector.clone(),
+                signature: function.signature.clone(),
+                self_governed: callers.iter().any(|caller| caller.contains("address(this)")),
+                callers,
+                writes: function.writes.iter().cloned().collect(),
+                delays: function.delays.iter().cloned().collect(),
+            });
+        }
+
+        let governed_selectors =
+            governed.iter().map(|function| function.selector.clone()).collect::<BTreeSet<_>>();
+        let mut centralization_risks = Vec::new();
+        for governed_function in &governed {
+            let function = &functions[&governed_function.selector];
+            let controlled_by = governed_function
+                .callers
+                .iter()
+                .flat_map(|caller| storage_references(caller))
+                .collect::<BTreeSet<String>>()
+                .into_iter()
+                .collect::<Vec<String>>();
+
+            for (kind, description) in function_risks(function, functions, &governed_selectors) {
+                centralization_risks.push(CentralizationRisk {
+                    kind,
+                    selector: function.selector.clone(),
+                    signature: function.signature.clone(),
+                    description,
+                    controlled_by: controlled_by.clone(),
+                });
+            }
+        }
+        centralization_risks.sort_by(|a, b| a.kind.cmp(&b.kind).then(a.selector.cmp(&b.selector)));
+
+        timelock.delays = delays.into_iter().collect();
+        GovernanceSummary {
+            timelock: match timelock.queue.is_empty() && timelock.execute.is_empty() {
+                true => None,
+                false => Some(timelock),
+            },
+            functions: governed,
+            centralization_risks,
+        }
+    }
+
+    /// Render the summary as a markdown report.
+    pub fn to_markdown(&self) -> String {
+        let mut lines = vec![format!("# {}", message("governance.title")), String::new()];
+
+        lines.push(format!("## {}", message("governance.timelock.title")));
+        lines.push(String::new());
+        match &self.timelock {
+            Some(timelock) => {
+                for (name, functions) in [
+                    (message("governance.timelock.queued_by"), &timelock.queue),
+                    (message("governance.timelock.executed_by"), &timelock.execute),
+                ] {
+                    if !functions.is_empty() {
+                        lines.push(format!("- {name}: {}", code_list(functions)));
+                    }
+                }
+                if !timelock.delays.is_empty() {
+                    lines.push(format!(
+                        "- {}: {}",
+                        message("governance.timelock.delays"),
+                        code_list(&timelock.delays)
+                    ));
+                }
+            }
+            None => lines.push(message("governance.timelock.none")),
+        }
+        lines.push(String::new());
+
+        lines.push(format!("## {}", message("governance.risks.title")));
+        lines.push(String::new());
+        if self.centralization_risks.is_empty() {
+            lines.push(message("governance.risks.none"));
+        }
+        for risk in &self.centralization_risks {
+            let controller = match risk.controlled_by.is_empty() {
+                true => String::new(),
+                false => format!(
+                    " {}",
+                    format_message(
+                        "governance.risks.controlled_by",
+                        &[("controllers", &code_list(&risk.controlled_by))]
+                    )
+                ),
+            };
+            lines.push(format!(
+                "- **{}** `{}` {}.{}",
+                risk.kind.label(),
+                risk.signature.as_ref().unwrap_or(&risk.selector),
+                risk.description,
+                controller
+            ));
+        }
+        lines.push(String::new());
+
+        lines.push(format!("## {}", message("governance.functions.title")));
+        lines.push(String::new());
+        if self.functions.is_empty() {
+            lines.push(message("governance.functions.none"));
+            return lines.join("\n")
+        }
+
+        lines.push(format!(
+            "| {} | {} | {} | {} |",
+            message("governance.functions.function"),
+            message("governance.functions.callers"),
+            message("governance.functions.writes"),
+            message("governance.functions.delay")
+        ));
+        lines.push("| --- | --- | --- | --- |".to_string());
+        for function in &self.functions {
+            let delay = match (function.delays.is_empty(), function.self_governed) {
+                (false, _) => code_list(&function.delays),
+                (true, true) if self.timelock.is_some() => message("governance.delay.timelock"),
+                (true, true) => message("governance.delay.self"),
+                (true, false) => message("governance.delay.none"),
+            };
+            lines.push(format!(
+                "| `{}` | {} | {} | {} |",
+                function.signature.as_ref().unwrap_or(&function.selector),
+                code_list(&function.callers),
+                match function.writes.is_empty() {
+                    true => message("governance.functions.external_calls_only"),
+                    false => code_list(&function.writes),
+                },
+                delay
+            ));
+        }
+
+        lines.join("\n")
+    }
+}
+
+/// A comma separated list of inline code, escaped for use in a markdown table.
+pub(crate) fn code_list(items: &[String]) -> String {
+    items
+        .iter()
+        .map(|item| format!("`{}`", item.replace('|', "\\|")))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::snapshot::governance::*;
+
+    fn function(selector: &str) -> FunctionSummary {
+        FunctionSummary { selector: selector.to_string(), ..Default::default() }
+    }
+
+    fn functions(functions: Vec<FunctionSummary>) -> BTreeMap<String, FunctionSummary> {
+        functions.into_iter().map(|function| (function.selector.clone(), function)).collect()
+    }
+
+    #[test]
+    fn test_admin_only_functions() {
+        let mut set_owner = function("0x13af4035");
+        set_owner.guards.insert("if (msg.sender == storage[0]) { .. }".to_string());
+        set_owner.writes.insert("0".to_string());
+        let mut public = function("0xd09de08a");
+        public.writes.insert("1".to_string());
+
+        let governance = GovernanceSummary::from_functions(&functions(vec![set_owner, public]));
+
+        assert_eq!(governance.timelock, None);
+        assert_eq!(governance.functions.len(), 1);
+        assert_eq!(governance.functions[0].callers, vec!["msg.sender == storage[0]".to_string()]);
+        assert!(governance.to_markdown().contains("| `0x13af4035` | `msg.sender == storage[0]` |"));
+    }
+
+    #[test]
+    fn test_timelock() {
+        let mut queue = function("0x3a66f901");
+        queue.guards.insert("if (msg.sender == storage[0]) { .. }".to_string());
+        queue.writes.insert("keccak256(memory[0:64])".to_string());
+        queue.delays.insert("arg4 >= block.timestamp + storage[2]".to_string());
+        let mut execute = function("0x0825f38f");
+        execute.guards.insert("if (storage[keccak256(memory[0:64])]) { .. }".to_string());
+        execute.delays.insert("block.timestamp >= arg4".to_string());
+        execute.external_calls.insert("address(arg0).call(arg3);".to_string());
+        let mut set_delay = function("0xe177246e");
+        set_delay.guards.insert("if (msg.sender == address(this)) { .. }".to_string());
+        set_delay.writes.insert("2".to_string());
+
+        let governance =
+            GovernanceSummary::from_functions(&functions(vec![queue, execute, set_delay]));
+        let timelock = governance.timelock.clone().unwrap();
+
+        assert_eq!(timelock.queue, vec!["0x3a66f901".to_string()]);
+        assert_eq!(timelock.execute, vec!["0x0825f38f".to_string()]);
+        assert_eq!(timelock.delays.len(), 2);
+        assert!(governance.functions.iter().any(|f| f.selector == "0xe177246e" && f.self_governed));
+        assert!(governance
+            .to_markdown()
+            .contains("| `0xe177246e` | `msg.sender == address(this)` | `2` | timelock |"));
+    }
+
+    #[test]
+    fn test_centralization_risks() {
+        let mut pause = function("0x8456cb59");
+        pause.signature = Some("pause()".to_string());
+        pause.guards.insert("if (msg.sender == storage[0]) { .. }".to_string());
+        pause.writes.insert("1".to_string());
+        let mut transfer = function("0xa9059cbb");
+        transfer.guards.insert("if (!storage[1]) { .. }".to_string());
+        transfer.writes.insert("keccak256(memory[0:64])".to_string());
+        let mut set_fee = function("0x69fe0e2d");
+        set_fee.signature = Some("setFee(uint256)".to_string());
+        set_fee.guards.insert("if (msg.sender == storage[0]) { .. }".to_string());
+        set_fee.writes.insert("2".to_string());
+        let mut sweep = function("0x01681a62");
+        sweep.guards.insert("if (msg.sender == storage[0]) { .. }".to_string());
+        sweep
+            .external_calls
+            .insert("address(msg.sender).call{ value: address(this).balance }();".to_string());
+
+        let governance =
+            GovernanceSummary::from_functions(&functions(vec![pause, transfer, set_fee, sweep]));
+        let kinds = governance
+            .centralization_risks
+            .iter()
+            .map(|risk| (risk.kind, risk.selector.as_str()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            kinds,
+            vec![
+                (RiskKind::Pause, "0x8456cb59"),
+                (RiskKind::Withdrawal, "0x01681a62"),
+                (RiskKind::UnboundedFee, "0x69fe0e2d"),
+            ]
+        );
+        assert_eq!(
+            governance.centralization_risks[0].controlled_by,
+            vec!["storage[0]".to_string()]
+        );
+        assert!(governance.to_markdown().contains("- **pause** `pause()` can block `0xa9059cbb`."));
+    }
+
+    #[test]
+    fn test_storage_references() {
+        assert_eq!(
+            storage_references("msg.sender == storage[0] || storage[keccak256(memory[0:64])]"),
+            vec!["storage[0]".to_string(), "storage[keccak256(memory[0:64])]".to_string()]
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use heimdall_common::utils::strings::encode_hex_reduced;
+use heimdall_common::ether::signatures::{error_selector, event_selector};
 use tui::{
     backend::Backend,
     layout::{Alignment, Constraint, Direction, Layout},
@@ -146,7 +146,7 @@ pub fn render_tui_command_palette<B: Backend>(f: &mut Frame<B>, state: &mut Stat
                 .events
                 .iter()
                 .map(|x| {
-                    let key = encode_hex_reduced(*x.0).replacen("0x", "", 1);
+                    let key = event_selector(x.0);
                     match state.resolved_events.get(&key) {
                         Some(event) => {
                             Spans::from(format!(" {}({})", event.name, event.inputs.join(",")))
@@ -172,7 +172,7 @@ pub fn render_tui_command_palette<B: Backend>(f: &mut Frame<B>, state: &mut Stat
                 .errors
                 .iter()
                 .map(|x| {
-                    let key = encode_hex_reduced(*x.0).replacen("0x", "", 1);
+                    let key = error_selector(x.0);
                     match state.resolved_errors.get(&key) {
                         Some(error) => {
                             Spans::from(format!(" {}({})", error.name, error.inputs.join(",")))
@@ -1,4 +1,7 @@
-use heimdall_common::utils::strings::encode_hex_reduced;
+use heimdall_common::{
+    ether::signatures::{error_selector, event_selector},
+    utils::io::address::format_addresses,
+};
 use tui::{
     backend::Backend,
     layout::{Alignment, Constraint, Direction, Layout},
@@ -163,7 +166,7 @@ pub fn render_tui_view_main<B: Backend>(f: &mut Frame<B>, state: &mut State) {
                 .events
                 .iter()
                 .map(|x| {
-                    let key = encode_hex_reduced(*x.0).replacen("0x", "", 1);
+                    let key = event_selector(x.0);
                     match state.resolved_events.get(&key) {
                         Some(event) => {
                             Spans::from(format!(" {}({})", event.name, event.inputs.join(",")))
@@ -189,7 +192,7 @@ pub fn render_tui_view_main<B: Backend>(f: &mut Frame<B>, state: &mut State) {
                 .errors
                 .iter()
                 .map(|x| {
-                    let key = encode_hex_reduced(*x.0).replacen("0x", "", 1);
+                    let key = error_selector(x.0);
                     match state.resolved_errors.get(&key) {
                         Some(error) => {
                             Spans::from(format!(" {}({})", error.name, error.inputs.join(",")))
@@ -250,7 +253,7 @@ pub fn render_tui_view_main<B: Backend>(f: &mut Frame<B>, state: &mut State) {
             &mut snapshot
                 .addresses
                 .iter()
-                .map(|x| Spans::from(format!(" {}", x)))
+                .map(|x| Spans::from(format!(" {}", format_addresses(x))))
                 .collect::<Vec<_>>(),
         );
     }
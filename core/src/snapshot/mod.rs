@@ -1,10 +1,20 @@
 pub mod analyze;
 pub mod constants;
+pub mod diff;
+pub mod funds;
+pub mod governance;
 pub mod menus;
 pub mod resolve;
+pub mod reverts;
 pub mod structures;
 pub mod util;
-use heimdall_common::{debug_max, utils::threading::run_with_timeout};
+use heimdall_common::{
+    debug_max,
+    utils::{
+        io::{crash::enter_pass, exit::record_partial_analysis},
+        threading::run_with_timeout,
+    },
+};
 
 use std::{
     collections::{HashMap, HashSet},
@@ -14,15 +24,18 @@ use std::{
 use clap::{AppSettings, Parser};
 use derive_builder::Builder;
 use heimdall_common::{
+    constants::ADDRESS_REGEX,
     ether::{
         bytecode::get_bytecode_from_target,
         compiler::detect_compiler,
+        endpoints::set_signature_target,
         evm::core::vm::VM,
+        rpc::{chain_id, get_block_number},
         selectors::get_resolved_selectors,
         signatures::{ResolvedError, ResolvedFunction, ResolvedLog},
     },
     utils::{
-        io::logging::*,
+        io::{file::read_file, logging::*},
         strings::{decode_hex, get_shortned_target},
     },
 };
@@ -32,6 +45,8 @@ use crate::{
     disassemble::{disassemble, DisassemblerArgs},
     snapshot::{
         analyze::snapshot_trace,
+        diff::{diff_snapshots, SnapshotDiff, SnapshotSummary},
+        funds::{default_tokens, get_funds_at_risk, parse_token_list, FundsAtRisk},
         resolve::resolve_signatures,
         structures::snapshot::{GasUsed, Snapshot},
         util::tui,
@@ -80,6 +95,16 @@ pub struct SnapshotArgs {
     /// The timeout for each function's symbolic execution in milliseconds.
     #[clap(long, short, default_value = "10000", hide_default_value = true)]
     pub timeout: u64,
+
+    /// A stored `snapshot.json` to compare this snapshot against, reporting new selectors,
+    /// changed guards, and new external dependencies.
+    #[clap(long, default_value = "", hide_default_value = true)]
+    pub diff: String,
+
+    /// A token list in the Uniswap format, whose tokens are checked for a balance held by the
+    /// target. Defaults to a list of widely held tokens on mainnet.
+    #[clap(long = "token-list", default_value = "", hide_default_value = true)]
+    pub token_list: String,
 }
 
 impl SnapshotArgsBuilder {
@@ -94,6 +119,8 @@ impl SnapshotArgsBuilder {
             name: Some(String::new()),
             output: Some(String::new()),
             timeout: Some(10000),
+            diff: Some(String::new()),
+            token_list: Some(String::new()),
         }
     }
 }
@@ -103,6 +130,10 @@ pub struct SnapshotResult {
     pub snapshots: Vec<Snapshot>,
     pub resolved_errors: HashMap<String, ResolvedError>,
     pub resolved_events: HashMap<String, ResolvedLog>,
+    /// The changes since the snapshot passed with `--diff`, if any
+    pub diff: Option<SnapshotDiff>,
+    /// The ether and tokens held by the target, if it's a contract address
+    pub funds: Option<FundsAtRisk>,
 }
 
 /// The main snapshot function, which will be called from the main thread. This module is
@@ -130,6 +161,11 @@ pub async fn snapshot(args: SnapshotArgs) -> Result<SnapshotResult, Box<dyn std:
 
     let contract_bytecode = get_bytecode_from_target(&args.target, &args.rpc_url).await?;
 
+    // a deployed contract's selectors are resolved from its verified ABI first, when it has one
+    if ADDRESS_REGEX.is_match(&args.target).unwrap_or(false) {
+        set_signature_target(&args.target, &args.rpc_url);
+    }
+
     // perform versioning and compiler heuristics
     let (compiler, version) = detect_compiler(&contract_bytecode);
     trace.add_call(
@@ -175,12 +211,15 @@ pub async fn snapshot(args: SnapshotArgs) -> Result<SnapshotResult, Box<dyn std:
         "()".to_string(),
     );
 
+    enter_pass("disassemble");
     let disassembled_bytecode = disassemble(DisassemblerArgs {
         rpc_url: args.rpc_url.clone(),
         verbose: args.verbose.clone(),
         target: args.target.clone(),
         name: args.name.clone(),
         decimal_counter: false,
+        format: String::from("asm"),
+        verify: false,
         output: String::new(),
     })
     .await?;
@@ -203,6 +242,61 @@ pub async fn snapshot(args: SnapshotArgs) -> Result<SnapshotResult, Box<dyn std:
     logger.info("symbolic execution completed.");
     logger.debug(&format!("snapshot completed in {:?}.", now.elapsed()));
 
+    for risk in SnapshotSummary::from_snapshots(&snapshots).governance.centralization_risks {
+        logger.warn(&format!(
+            "centralization risk: '{}' {}.",
+            risk.signature.as_ref().unwrap_or(&risk.selector),
+            risk.description
+        ));
+    }
+
+    // compare against the stored snapshot
+    let diff = match args.diff.is_empty() {
+        true => None,
+        false => {
+            let old: SnapshotSummary = serde_json::from_str(&std::fs::read_to_string(&args.diff)?)?;
+            let diff = diff_snapshots(&old, &SnapshotSummary::from_snapshots(&snapshots));
+
+            if diff.is_empty() {
+                logger.info(&format!("no semantic changes since '{}'.", &args.diff));
+            } else {
+                logger.warn(&format!(
+                    "{} selectors added, {} removed, and {} changed since '{}'.",
+                    diff.added.len(),
+                    diff.removed.len(),
+                    diff.changed.len(),
+                    &args.diff
+                ));
+                for dependency in &diff.new_dependencies {
+                    logger.warn(&format!("new external dependency: {dependency}"));
+                }
+            }
+            Some(diff)
+        }
+    };
+
+    // balances are only meaningful for a deployed contract
+    let funds = match ADDRESS_REGEX.is_match(&args.target).unwrap_or(false) {
+        true => {
+            enter_pass("funds at risk");
+            match read_funds_at_risk(&args).await {
+                Ok(funds) => {
+                    logger.info(&format!(
+                        "target holds {} wei and {} tokens.",
+                        funds.ether,
+                        funds.tokens.len()
+                    ));
+                    Some(funds)
+                }
+                Err(e) => {
+                    logger.warn(&format!("failed to read the target's balances: {e}"));
+                    None
+                }
+            }
+        }
+        false => None,
+    };
+
     // open the tui
     if !args.no_tui {
         tui::handle(
@@ -219,9 +313,26 @@ pub async fn snapshot(args: SnapshotArgs) -> Result<SnapshotResult, Box<dyn std:
         snapshots,
         resolved_errors: all_resolved_errors,
         resolved_events: all_resolved_events,
+        diff,
+        funds,
     })
 }
 
+/// Read the target's balances at the latest block, checking the tokens in `--token-list`, or
+/// the default list for the chain.
+async fn read_funds_at_risk(
+    args: &SnapshotArgs,
+) -> Result<FundsAtRisk, Box<dyn std::error::Error>> {
+    let chain_id = chain_id(&args.rpc_url).await?;
+    let tokens = match args.token_list.is_empty() {
+        true => default_tokens(chain_id),
+        false => parse_token_list(&read_file(&args.token_list), chain_id)?,
+    };
+    let block = get_block_number(&args.rpc_url).await?;
+
+    Ok(get_funds_at_risk(&args.target, &tokens, block, &args.rpc_url).await?)
+}
+
 async fn get_snapshots(
     selectors: HashMap<String, u128>,
     resolved_selectors: HashMap<String, Vec<ResolvedFunction>>,
@@ -262,6 +373,7 @@ async fn get_snapshots(
         );
 
         // get a map of possible jump destinations
+        enter_pass(&format!("symbolic execution (0x{selector})"));
         let mut evm_clone = evm.clone();
         let selector_clone = selector.clone();
         let (map, jumpdest_count) = match run_with_timeout(
@@ -275,6 +387,7 @@ async fn get_snapshots(
                     line!(),
                     "symbolic execution timed out, skipping snapshotting.",
                 );
+                record_partial_analysis();
                 continue
             }
         };
@@ -294,6 +407,7 @@ async fn get_snapshots(
         );
 
         debug_max!("building snapshot for selector {} from symbolic execution trace", selector);
+        enter_pass(&format!("snapshot (0x{selector})"));
         let mut snapshot = snapshot_trace(
             &map,
             Snapshot {
@@ -311,11 +425,15 @@ async fn get_snapshots(
                 view: true,
                 payable: true,
                 strings: HashSet::new(),
+                revert_reasons: HashSet::new(),
                 external_calls: Vec::new(),
                 gas_used: GasUsed { min: u128::MAX, max: 0, avg: 0 },
                 addresses: HashSet::new(),
                 branch_count: jumpdest_count,
                 control_statements: HashSet::new(),
+                storage_writes: HashSet::new(),
+                timestamp_checks: HashSet::new(),
+                self_destructs: false,
             },
             trace,
             func_analysis_trace,
@@ -1,16 +1,14 @@
 use std::collections::HashMap;
 
 use super::structures::snapshot::Snapshot;
+use ethers::types::U256;
 use heimdall_common::{
     debug_max,
     ether::{
         selectors::resolve_selectors,
-        signatures::{score_signature, ResolvedError, ResolvedFunction, ResolvedLog},
-    },
-    utils::{
-        io::logging::{Logger, TraceFactory},
-        strings::encode_hex_reduced,
+        signatures::{self, score_signature, ResolvedError, ResolvedFunction, ResolvedLog},
     },
+    utils::io::logging::{Logger, TraceFactory},
 };
 use indicatif::ProgressBar;
 
@@ -256,16 +254,10 @@ async fn resolve_error_signatures(
 ) -> Result<(), Box<dyn std::error::Error>> {
     let (logger, _) = Logger::new("");
 
-    let resolved_errors: HashMap<String, Vec<ResolvedError>> = resolve_selectors(
-        snapshot
-            .errors
-            .keys()
-            .map(|error_selector| encode_hex_reduced(*error_selector).replacen("0x", "", 1))
-            .collect(),
-    )
-    .await;
+    let resolved_errors: HashMap<String, Vec<ResolvedError>> =
+        resolve_selectors(snapshot.errors.keys().map(signatures::error_selector).collect()).await;
     for (error_selector, _) in snapshot.errors.clone() {
-        let error_selector_str = encode_hex_reduced(error_selector).replacen("0x", "", 1);
+        let error_selector_str = signatures::error_selector(&error_selector);
         let mut selected_error_index: u8 = 0;
         let mut resolved_error_selectors = match resolved_errors.get(&error_selector_str) {
             Some(func) => func.clone(),
@@ -314,19 +306,15 @@ async fn resolve_event_signatures(
 ) -> Result<(), Box<dyn std::error::Error>> {
     let (logger, _) = Logger::new("");
 
-    let resolved_events: HashMap<String, Vec<ResolvedLog>> = resolve_selectors(
-        snapshot
-            .events
-            .keys()
-            .map(|event_selector| encode_hex_reduced(*event_selector).replacen("0x", "", 1))
-            .collect(),
+    let resolved_events: HashMap<U256, Vec<ResolvedLog>> = signatures::resolve_event_signatures(
+        &snapshot.events.keys().copied().collect::<Vec<U256>>(),
     )
     .await;
 
     for (event_selector, (_, raw_event)) in snapshot.events.clone() {
         let mut selected_event_index: u8 = 0;
-        let event_selector_str = encode_hex_reduced(event_selector).replacen("0x", "", 1);
-        let mut resolved_event_selectors = match resolved_events.get(&event_selector_str) {
+        let event_selector_str = signatures::event_selector(&event_selector);
+        let mut resolved_event_selectors = match resolved_events.get(&event_selector) {
             Some(func) => func.clone(),
             None => Vec::new(),
         };
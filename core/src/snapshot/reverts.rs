@@ -0,0 +1,91 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use heimdall_common::utils::io::messages::message;
+use serde::{Deserialize, Serialize};
+
+use crate::snapshot::{diff::FunctionSummary, governance::code_list};
+
+/// Every message, panic, and custom error the contract reverts with, and the functions which
+/// revert with each. Reading it gives a quick overview of what the contract checks.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct RevertTable {
+    pub reasons: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl RevertTable {
+    pub fn from_functions(functions: &BTreeMap<String, FunctionSummary>) -> RevertTable {
+        let mut reasons: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        for function in functions.values() {
+            for reason in &function.reverts {
+                reasons.entry(reason.clone()).or_default().insert(
+                    function.signature.clone().unwrap_or_else(|| function.selector.clone()),
+                );
+            }
+        }
+
+        RevertTable { reasons }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.reasons.is_empty()
+    }
+
+    /// Render the table as markdown.
+    pub fn to_markdown(&self) -> String {
+        let mut lines = vec![format!("# {}", message("reverts.title")), String::new()];
+        if self.reasons.is_empty() {
+            lines.push(message("reverts.none"));
+            return lines.join("\n")
+        }
+
+        lines.push(format!("| {} | {} |", message("reverts.reason"), message("reverts.functions")));
+        lines.push("| --- | --- |".to_string());
+        for (reason, functions) in &self.reasons {
+            lines.push(format!(
+                "| {} | {} |",
+                code_list(std::slice::from_ref(reason)),
+                code_list(&functions.iter().cloned().collect::<Vec<String>>())
+            ));
+        }
+
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::snapshot::reverts::*;
+
+    #[test]
+    fn test_revert_table() {
+        let mut transfer = FunctionSummary {
+            selector: "0xa9059cbb".to_string(),
+            signature: Some("transfer(address,uint256)".to_string()),
+            ..Default::default()
+        };
+        transfer.reverts.insert("\"ERC20: insufficient balance\"".to_string());
+        transfer.reverts.insert("Paused()".to_string());
+        let mut mint = FunctionSummary { selector: "0x40c10f19".to_string(), ..Default::default() };
+        mint.reverts.insert("Paused()".to_string());
+
+        let table = RevertTable::from_functions(
+            &[transfer, mint]
+                .into_iter()
+                .map(|function| (function.selector.clone(), function))
+                .collect(),
+        );
+        assert_eq!(
+            table.reasons.get("Paused()"),
+            Some(&BTreeSet::from([
+                "0x40c10f19".to_string(),
+                "transfer(address,uint256)".to_string()
+            ]))
+        );
+
+        let markdown = table.to_markdown();
+        assert!(markdown
+            .contains("| `\"ERC20: insufficient balance\"` | `transfer(address,uint256)` |"));
+        assert!(RevertTable::default().to_markdown().contains("No revert messages"));
+    }
+}
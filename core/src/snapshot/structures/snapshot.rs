@@ -5,6 +5,7 @@ use heimdall_common::ether::{
     evm::core::{log::Log, opcodes::WrappedOpcode},
     signatures::{ResolvedError, ResolvedFunction, ResolvedLog},
 };
+use serde::{Deserialize, Serialize};
 
 /// A snapshot of a contract's state at a given point in time. Will be built over the process of
 /// symbolic-execution analysis.
@@ -55,6 +56,9 @@ pub struct Snapshot {
     // stores strings found within the function
     pub strings: HashSet<String>,
 
+    // the messages and panics the function reverts with. custom errors are kept in `errors`
+    pub revert_reasons: HashSet<String>,
+
     // store external calls made by the function
     pub external_calls: Vec<String>,
 
@@ -69,9 +73,18 @@ pub struct Snapshot {
 
     // control statements, such as access control
     pub control_statements: HashSet<String>,
+
+    // storage slots written by the function
+    pub storage_writes: HashSet<String>,
+
+    // conditions which compare against block.timestamp, such as a timelock's delay
+    pub timestamp_checks: HashSet<String>,
+
+    // whether the function can self-destruct the contract
+    pub self_destructs: bool,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct GasUsed {
     pub min: u128,
     pub max: u128,
@@ -1,8 +1,7 @@
 use std::collections::HashMap;
 
-use heimdall_common::{
-    ether::signatures::{ResolvedError, ResolvedLog},
-    utils::strings::encode_hex_reduced,
+use heimdall_common::ether::signatures::{
+    error_selector, event_selector, ResolvedError, ResolvedLog,
 };
 
 use crate::snapshot::structures::snapshot::Snapshot;
@@ -65,7 +64,7 @@ pub fn generate_csv(
             .events
             .iter()
             .map(|x| {
-                let key = encode_hex_reduced(*x.0).replacen("0x", "", 1);
+                let key = event_selector(x.0);
                 match resolved_events.get(&key) {
                     Some(event) => format!(" {}({})", event.name, event.inputs.join(",")),
                     None => format!(" Event_{}()", key[0..8].to_owned()),
@@ -79,7 +78,7 @@ pub fn generate_csv(
             .errors
             .iter()
             .map(|x| {
-                let key = encode_hex_reduced(*x.0).replacen("0x", "", 1);
+                let key = error_selector(x.0);
                 match resolved_errors.get(&key) {
                     Some(errors) => format!(" {}({})", errors.name, errors.inputs.join(",")),
                     None => format!(" Error_{}()", key[0..8].to_owned()),
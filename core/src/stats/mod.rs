@@ -0,0 +1,197 @@
+pub mod util;
+
+use std::{collections::HashMap, sync::Arc};
+
+use clap::{AppSettings, Parser};
+use derive_builder::Builder;
+use heimdall_common::{
+    ether::{
+        corpus::Corpus,
+        selectors::resolve_selectors,
+        signatures::{score_signature, ResolvedFunction},
+    },
+    utils::{
+        io::logging::{set_logger_env, Logger},
+        threading::task_pool,
+    },
+};
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+
+use self::util::{contract_selectors, SelectorCluster, SelectorFrequency, SelectorIndex};
+
+#[derive(Debug, Clone, Parser, Builder)]
+#[clap(
+    about = "Report selector frequencies, clusters, and unresolved selectors across a corpus",
+    after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    global_setting = AppSettings::DeriveDisplayOrder,
+    override_usage = "heimdall stats <CORPUS> [OPTIONS]"
+)]
+pub struct StatsArgs {
+    /// The corpus to report on, either a directory with one contract per file, or a file with
+    /// one contract per line.
+    #[clap(required = true)]
+    pub target: String,
+
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// The number of selectors to list as most frequent, and as unresolved.
+    #[clap(long, short, default_value = "25")]
+    pub limit: usize,
+
+    /// The minimum number of contracts a selector must be dispatched by to be clustered.
+    #[clap(long, default_value = "2")]
+    pub min_contracts: usize,
+
+    /// The minimum share, from 0 to 1, of the contracts dispatching either of two selectors
+    /// which must dispatch both for them to be clustered.
+    #[clap(long, default_value = "0.8")]
+    pub cluster_threshold: f64,
+
+    /// The number of threads to find corpus contracts' selectors with.
+    #[clap(long, default_value = "8")]
+    pub threads: usize,
+
+    /// Whether to skip resolving selectors to signatures. No unresolved selectors are listed.
+    #[clap(long = "skip-resolving")]
+    pub skip_resolving: bool,
+
+    /// The output directory to write the output to or 'print' to print to the console
+    #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
+    pub output: String,
+
+    /// The name for the output file
+    #[clap(long, short, default_value = "", hide_default_value = true)]
+    pub name: String,
+}
+
+impl StatsArgsBuilder {
+    pub fn new() -> Self {
+        Self {
+            target: Some(String::new()),
+            verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
+            limit: Some(25),
+            min_contracts: Some(2),
+            cluster_threshold: Some(0.8),
+            threads: Some(8),
+            skip_resolving: Some(false),
+            output: Some(String::new()),
+            name: Some(String::new()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatsResult {
+    /// The number of contracts whose selectors were found
+    pub contracts: usize,
+    /// The number of contracts which couldn't be read or decoded
+    pub skipped: usize,
+    /// The number of distinct selectors in the corpus
+    pub distinct_selectors: usize,
+    /// The most frequent selectors, most frequent first
+    pub selectors: Vec<SelectorFrequency>,
+    /// Selectors which are usually dispatched together, most common first
+    pub clusters: Vec<SelectorCluster>,
+    /// The most frequent selectors which no signature database or label resolves, most
+    /// frequent first. These are the selectors most worth brute forcing or labeling.
+    pub unresolved: Vec<SelectorFrequency>,
+}
+
+/// Find the selectors every contract in a corpus dispatches, and report how often each occurs,
+/// which are usually dispatched together, and which of the most frequent can't be resolved.
+pub async fn stats(args: StatsArgs) -> Result<StatsResult, Error> {
+    set_logger_env(&args.verbose);
+
+    // get a new logger
+    let (logger, _) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    let corpus =
+        Arc::new(Corpus::open(&args.target).map_err(|e| Error::GenericError(e.to_string()))?);
+    logger.info(&format!("finding the selectors of {} contracts.", corpus.len()));
+
+    let task_corpus = corpus.clone();
+    let results = task_pool((0..corpus.len()).collect(), args.threads.max(1), move |index| {
+        let entry = task_corpus.get(index).map_err(|e| e.to_string())?;
+        contract_selectors(&entry.bytecode()).map_err(|e| format!("'{}': {e}", entry.name))
+    });
+
+    let mut index = SelectorIndex::default();
+    let mut skipped = 0;
+    for result in results {
+        match result {
+            Ok(selectors) => index.add_contract(&selectors),
+            Err(e) => {
+                logger.warn(&format!("skipping corpus contract {e}"));
+                skipped += 1;
+            }
+        }
+    }
+
+    let frequencies = index.frequencies();
+    let clusters = index.clusters(args.min_contracts, args.cluster_threshold);
+    logger.info(&format!(
+        "found {} distinct selectors and {} clusters in {} contracts.",
+        frequencies.len(),
+        clusters.len(),
+        index.contracts()
+    ));
+
+    // resolve selectors in order of frequency, until enough unresolved selectors are found
+    let mut signatures: HashMap<String, Option<String>> = HashMap::new();
+    let mut unresolved = Vec::new();
+    if !args.skip_resolving {
+        for batch in frequencies.chunks(args.limit.max(1)) {
+            if unresolved.len() >= args.limit && signatures.len() >= args.limit {
+                break
+            }
+
+            let resolved = resolve_selectors::<ResolvedFunction>(
+                batch.iter().map(|(selector, _)| selector.clone()).collect(),
+            )
+            .await;
+            for (selector, _) in batch {
+                let signature = resolved.get(selector).and_then(|functions| {
+                    functions
+                        .iter()
+                        .max_by_key(|function| score_signature(&function.signature))
+                        .map(|function| function.signature.clone())
+                });
+                if signature.is_none() && unresolved.len() < args.limit {
+                    unresolved.push(selector.clone());
+                }
+                signatures.insert(selector.clone(), signature);
+            }
+        }
+    }
+
+    let frequency = |selector: &str, contracts: usize| SelectorFrequency {
+        selector: selector.to_string(),
+        contracts,
+        share: contracts as f64 / index.contracts().max(1) as f64,
+        signature: signatures.get(selector).cloned().flatten(),
+    };
+    let counts = frequencies.iter().cloned().collect::<HashMap<String, usize>>();
+
+    Ok(StatsResult {
+        contracts: index.contracts(),
+        skipped,
+        distinct_selectors: frequencies.len(),
+        selectors: frequencies
+            .iter()
+            .take(args.limit)
+            .map(|(selector, contracts)| frequency(selector, *contracts))
+            .collect(),
+        clusters,
+        unresolved: unresolved
+            .iter()
+            .map(|selector| frequency(selector, counts[selector]))
+            .collect(),
+    })
+}
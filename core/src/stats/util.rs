@@ -0,0 +1,241 @@
+use std::collections::{HashMap, HashSet};
+
+use heimdall_common::ether::{evm::core::vm::VM, selectors::find_function_selectors};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    disassemble::{disassemble, DisassemblerArgs},
+    error::Error,
+};
+
+/// How often a selector occurs in the corpus.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SelectorFrequency {
+    pub selector: String,
+    /// The number of contracts which dispatch the selector
+    pub contracts: usize,
+    /// The share of the corpus's contracts which dispatch the selector, from 0 to 1
+    pub share: f64,
+    /// The most likely signature of the selector, if it was resolved
+    pub signature: Option<String>,
+}
+
+/// Selectors which are usually dispatched by the same contracts, i.e. the functions of a
+/// standard interface.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SelectorCluster {
+    /// The cluster's selectors, most frequent first
+    pub selectors: Vec<String>,
+    /// The number of contracts which dispatch every selector in the cluster
+    pub contracts: usize,
+}
+
+/// The selectors of each contract in a corpus. Selectors are interned, so a corpus of millions
+/// of contracts only keeps each distinct selector once.
+#[derive(Debug, Clone, Default)]
+pub struct SelectorIndex {
+    ids: HashMap<String, usize>,
+    selectors: Vec<String>,
+    counts: Vec<usize>,
+    contracts: Vec<Vec<usize>>,
+}
+
+impl SelectorIndex {
+    /// Add a contract which dispatches the given selectors. Repeated selectors are counted once.
+    pub fn add_contract(&mut self, selectors: &[String]) {
+        let mut ids = selectors
+            .iter()
+            .map(|selector| match self.ids.get(selector) {
+                Some(id) => *id,
+                None => {
+                    self.ids.insert(selector.clone(), self.selectors.len());
+                    self.selectors.push(selector.clone());
+                    self.counts.push(0);
+                    self.selectors.len() - 1
+                }
+            })
+            .collect::<Vec<usize>>();
+        ids.sort_unstable();
+        ids.dedup();
+
+        for id in &ids {
+            self.counts[*id] += 1;
+        }
+        self.contracts.push(ids);
+    }
+
+    /// The number of contracts added.
+    pub fn contracts(&self) -> usize {
+        self.contracts.len()
+    }
+
+    /// Each selector and the number of contracts which dispatch it, most frequent first.
+    pub fn frequencies(&self) -> Vec<(String, usize)> {
+        let mut frequencies = self
+            .selectors
+            .iter()
+            .cloned()
+            .zip(self.counts.iter().copied())
+            .collect::<Vec<(String, usize)>>();
+        frequencies.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        frequencies
+    }
+
+    /// Group selectors which are dispatched by at least `min_contracts` contracts into clusters.
+    /// Two selectors are clustered when the Jaccard similarity of the contracts dispatching them,
+    /// i.e. the contracts with both over the contracts with either, is at least `threshold`.
+    /// Clusters are linked transitively, and ordered by the number of contracts with every
+    /// selector in them.
+    pub fn clusters(&self, min_contracts: usize, threshold: f64) -> Vec<SelectorCluster> {
+        // count how many contracts dispatch each pair of frequent selectors
+        let mut pairs: HashMap<(usize, usize), usize> = HashMap::new();
+        for contract in &self.contracts {
+            let frequent = contract
+                .iter()
+                .copied()
+                .filter(|id| self.counts[*id] >= min_contracts)
+                .collect::<Vec<usize>>();
+            for (i, a) in frequent.iter().enumerate() {
+                for b in &frequent[i + 1..] {
+                    *pairs.entry((*a, *b)).or_default() += 1;
+                }
+            }
+        }
+
+        // union each pair which is dispatched together often enough
+        let mut parents = (0..self.selectors.len()).collect::<Vec<usize>>();
+        fn root(parents: &mut [usize], mut id: usize) -> usize {
+            while parents[id] != id {
+                parents[id] = parents[parents[id]];
+                id = parents[id];
+            }
+            id
+        }
+        for ((a, b), both) in pairs {
+            let either = self.counts[a] + self.counts[b] - both;
+            if both as f64 / either as f64 >= threshold {
+                let (a, b) = (root(&mut parents, a), root(&mut parents, b));
+                parents[a.max(b)] = a.min(b);
+            }
+        }
+
+        let mut members: HashMap<usize, Vec<usize>> = HashMap::new();
+        for id in 0..self.selectors.len() {
+            let parent = root(&mut parents, id);
+            members.entry(parent).or_default().push(id);
+        }
+
+        let mut clusters = members
+            .into_values()
+            .filter(|ids| ids.len() > 1)
+            .map(|mut ids| {
+                ids.sort_by(|a, b| {
+                    self.counts[*b]
+                        .cmp(&self.counts[*a])
+                        .then(self.selectors[*a].cmp(&self.selectors[*b]))
+                });
+                let cluster = ids.iter().copied().collect::<HashSet<usize>>();
+                SelectorCluster {
+                    contracts: self
+                        .contracts
+                        .iter()
+                        .filter(|contract| {
+                            contract.iter().filter(|id| cluster.contains(id)).count() ==
+                                cluster.len()
+                        })
+                        .count(),
+                    selectors: ids.into_iter().map(|id| self.selectors[id].clone()).collect(),
+                }
+            })
+            .collect::<Vec<SelectorCluster>>();
+        clusters.sort_by(|a, b| {
+            b.contracts
+                .cmp(&a.contracts)
+                .then(b.selectors.len().cmp(&a.selectors.len()))
+                .then(a.selectors.cmp(&b.selectors))
+        });
+        clusters
+    }
+}
+
+/// The selectors a contract's dispatcher compares calldata to, sorted.
+pub fn contract_selectors(contract_bytecode: &str) -> Result<Vec<String>, Error> {
+    let contract_bytecode = contract_bytecode.replacen("0x", "", 1);
+
+    // disassembling bytecode never waits on the network, so it can be run to completion here
+    let disassembled_bytecode = futures::executor::block_on(disassemble(DisassemblerArgs {
+        target: contract_bytecode.clone(),
+        verbose: clap_verbosity_flag::Verbosity::new(0, 1),
+        rpc_url: String::new(),
+        decimal_counter: false,
+        format: String::from("asm"),
+        verify: false,
+        name: String::new(),
+        output: String::new(),
+    }))
+    .map_err(|e| Error::GenericError(format!("failed to disassemble bytecode: {e}")))?;
+
+    let evm = VM::new(
+        contract_bytecode,
+        String::from("0x"),
+        String::from("0x6865696d64616c6c000000000061646472657373"),
+        String::from("0x6865696d64616c6c0000000000006f726967696e"),
+        String::from("0x6865696d64616c6c00000000000063616c6c6572"),
+        0,
+        u128::MAX,
+    );
+
+    let mut selectors = find_function_selectors(&evm, &disassembled_bytecode)
+        .into_keys()
+        .map(|selector| format!("0x{}", selector.trim_start_matches("0x")))
+        .collect::<Vec<String>>();
+    selectors.sort();
+    Ok(selectors)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::stats::util::*;
+
+    fn index(contracts: &[&[&str]]) -> SelectorIndex {
+        let mut index = SelectorIndex::default();
+        for contract in contracts {
+            index.add_contract(&contract.iter().map(|s| s.to_string()).collect::<Vec<String>>());
+        }
+        index
+    }
+
+    #[test]
+    fn test_frequencies() {
+        let index = index(&[&["0x01", "0x02", "0x02"], &["0x02"], &["0x03"]]);
+
+        assert_eq!(index.contracts(), 3);
+        assert_eq!(
+            index.frequencies(),
+            vec![("0x02".to_string(), 2), ("0x01".to_string(), 1), ("0x03".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_clusters() {
+        let index = index(&[
+            &["0xa9059cbb", "0x095ea7b3", "0x70a08231", "0xdeadbeef"],
+            &["0xa9059cbb", "0x095ea7b3", "0x70a08231"],
+            &["0xa9059cbb", "0x095ea7b3", "0x70a08231", "0x12345678"],
+            &["0x12345678", "0x87654321"],
+            &["0x12345678", "0x87654321"],
+        ]);
+
+        let clusters = index.clusters(2, 0.8);
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].selectors, vec!["0x095ea7b3", "0x70a08231", "0xa9059cbb"]);
+        assert_eq!(clusters[0].contracts, 3);
+
+        // 0x12345678 is dispatched by one contract 0x87654321 isn't, so the pair's similarity is
+        // 2 / 3
+        let clusters = index.clusters(2, 0.5);
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(clusters[1].selectors, vec!["0x12345678", "0x87654321"]);
+        assert_eq!(clusters[1].contracts, 2);
+    }
+}
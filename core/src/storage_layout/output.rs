@@ -0,0 +1,75 @@
+use super::util::StorageLayout;
+
+/// Write the layout as a markdown table, with a row for each variable in slot order.
+pub fn build_table(layout: &StorageLayout) -> String {
+    let mut output = vec![String::from("# Storage layout"), String::new()];
+    if layout.storage.is_empty() {
+        output.push(String::from("The contract doesn't use storage."));
+        return output.join("\n")
+    }
+
+    output.push(String::from("| slot | offset | bytes | type | name |"));
+    output.push(String::from("| --- | --- | --- | --- | --- |"));
+    for entry in &layout.storage {
+        let (label, size) = match layout.types.get(&entry.type_id) {
+            Some(storage_type) => {
+                (storage_type.label.as_str(), storage_type.number_of_bytes.as_str())
+            }
+            None => (entry.type_id.as_str(), "?"),
+        };
+        output.push(format!(
+            "| {} | {} | {size} | `{label}` | `{}` |",
+            entry.slot, entry.offset, entry.label
+        ));
+    }
+
+    output.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use ethers::types::U256;
+
+    use crate::{
+        layout::util::{SlotKind, SlotUsage},
+        storage_layout::{
+            output::*,
+            util::{build_storage_layout, PackingBuilder},
+        },
+    };
+
+    #[test]
+    fn test_build_table() {
+        let layout = vec![SlotUsage {
+            slot: U256::from(1),
+            kind: SlotKind::Mapping,
+            label: None,
+            reads: BTreeSet::new(),
+            writes: BTreeSet::new(),
+        }];
+        let storage_layout =
+            build_storage_layout("DecompiledContract", &layout, &PackingBuilder::new());
+
+        assert_eq!(
+            build_table(&storage_layout),
+            [
+                "# Storage layout",
+                "",
+                "| slot | offset | bytes | type | name |",
+                "| --- | --- | --- | --- | --- |",
+                "| 1 | 0 | 32 | `mapping(bytes32 => uint256)` | `stor_map_a` |",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_build_table_without_storage() {
+        assert_eq!(
+            build_table(&StorageLayout::default()),
+            "# Storage layout\n\nThe contract doesn't use storage."
+        );
+    }
+}
@@ -0,0 +1,476 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use ethers::{
+    types::{H256, U256},
+    utils::keccak256,
+};
+use heimdall_common::{
+    ether::evm::{
+        core::opcodes::{WrappedInput, WrappedOpcode},
+        ext::exec::VMTrace,
+    },
+    utils::strings::base26_encode,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::layout::util::{SlotKind, SlotUsage};
+
+/// A state variable, in the format of solc's `storageLayout` output.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageEntry {
+    pub ast_id: usize,
+    pub contract: String,
+    pub label: String,
+    /// The byte offset of the variable within its slot
+    pub offset: usize,
+    /// The slot, in decimal
+    pub slot: String,
+    /// The type's key in [`StorageLayout::types`]
+    #[serde(rename = "type")]
+    pub type_id: String,
+}
+
+/// A type used by a state variable, in the format of solc's `storageLayout` output.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageType {
+    /// One of `inplace`, `mapping`, or `dynamic_array`
+    pub encoding: String,
+    pub label: String,
+    pub number_of_bytes: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base: Option<String>,
+}
+
+/// A storage layout, in the format of solc's `storageLayout` output.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageLayout {
+    pub storage: Vec<StorageEntry>,
+    pub types: BTreeMap<String, StorageType>,
+}
+
+impl StorageLayout {
+    /// A label for a storage slot: the variables packed into it, i.e. `stor_a (address), stor_b
+    /// (uint8)`, or the element of a dynamic array it holds, i.e. `stor_array_c[2]`. Mapping
+    /// values are keyed by a hash, so their slots can't be labeled.
+    pub fn slot_label(&self, slot: &H256) -> Option<String> {
+        let slot = U256::from_big_endian(slot.as_bytes());
+
+        let variables = self
+            .storage
+            .iter()
+            .filter(|entry| entry.slot == slot.to_string())
+            .map(|entry| match self.types.get(&entry.type_id) {
+                Some(storage_type) => format!("{} ({})", entry.label, storage_type.label),
+                None => entry.label.clone(),
+            })
+            .collect::<Vec<String>>();
+        if !variables.is_empty() {
+            return Some(variables.join(", "))
+        }
+
+        // a dynamic array's elements start at the hash of its slot
+        self.storage
+            .iter()
+            .filter(|entry| {
+                self.types
+                    .get(&entry.type_id)
+                    .is_some_and(|storage_type| storage_type.encoding == "dynamic_array")
+            })
+            .find_map(|entry| {
+                let base_slot = U256::from_dec_str(&entry.slot).ok()?;
+                let mut base_slot_bytes = [0u8; 32];
+                base_slot.to_big_endian(&mut base_slot_bytes);
+                let index = slot.checked_sub(U256::from(keccak256(base_slot_bytes)))?;
+
+                // arrays this long can't have been written to
+                (index < U256::from(u32::MAX)).then(|| format!("{}[{}]", entry.label, index))
+            })
+    }
+}
+
+/// Accumulates the byte ranges of each slot which are read or written on their own, i.e. the
+/// variables packed into it.
+#[derive(Debug, Clone, Default)]
+pub struct PackingBuilder {
+    /// The `(offset, size)` of each field, by slot
+    fields: BTreeMap<U256, BTreeSet<(usize, usize)>>,
+}
+
+impl PackingBuilder {
+    pub fn new() -> PackingBuilder {
+        PackingBuilder::default()
+    }
+
+    /// Record the fields of every slot read in the trace.
+    pub fn add_trace(&mut self, trace: &VMTrace) {
+        for operation in &trace.operations {
+            for input in &operation.last_instruction.input_operations {
+                self.add_operation(input);
+            }
+        }
+
+        for child in &trace.children {
+            self.add_trace(child);
+        }
+    }
+
+    fn add_operation(&mut self, operation: &WrappedOpcode) {
+        if let Some((slot, field)) = masked_field(operation) {
+            self.fields.entry(slot).or_default().insert(field);
+            return
+        }
+
+        for input in &operation.inputs {
+            if let WrappedInput::Opcode(input) = input {
+                self.add_operation(input);
+            }
+        }
+    }
+
+    /// The fields packed into a slot as `(offset, size)`, ordered by offset. Where two fields
+    /// overlap, the one which starts first, or is narrower, is kept. A slot with no fields holds
+    /// a single 32 byte value.
+    pub fn fields(&self, slot: &U256) -> Vec<(usize, usize)> {
+        let mut fields: Vec<(usize, usize)> = Vec::new();
+        for (offset, size) in self.fields.get(slot).into_iter().flatten() {
+            if fields
+                .last()
+                .is_none_or(|(last_offset, last_size)| *offset >= last_offset + last_size)
+            {
+                fields.push((*offset, *size));
+            }
+        }
+
+        match fields.is_empty() {
+            true => vec![(0, 32)],
+            false => fields,
+        }
+    }
+}
+
+/// The slot and field an operation isolates, i.e. a masked or shifted SLOAD of a constant slot.
+fn masked_field(operation: &WrappedOpcode) -> Option<(U256, (usize, usize))> {
+    // AND
+    if operation.opcode.code == 0x16 {
+        let inputs = [operation.inputs.first()?, operation.inputs.get(1)?];
+        for (mask, value) in [(inputs[0], inputs[1]), (inputs[1], inputs[0])] {
+            let (Some(mask), WrappedInput::Opcode(value)) = (constant(mask), value) else {
+                continue
+            };
+            if let Some((slot, shift)) = shifted_load(value) {
+                return mask_field(mask, shift).map(|field| (slot, field))
+            }
+        }
+        return None
+    }
+
+    // a shift without a mask leaves the field at the top of the slot
+    match shifted_load(operation)? {
+        (slot, shift) if shift > 0 => Some((slot, (shift, 32 - shift))),
+        _ => None,
+    }
+}
+
+/// The slot an operation loads and the number of bytes it shifts the value right by, for an SLOAD
+/// of a constant slot optionally followed by a SHR or DIV by a constant.
+fn shifted_load(operation: &WrappedOpcode) -> Option<(U256, usize)> {
+    let load = |input: &WrappedInput| match input {
+        // SLOAD
+        WrappedInput::Opcode(operation) if operation.opcode.code == 0x54 => {
+            operation.inputs.first().and_then(constant)
+        }
+        _ => None,
+    };
+
+    match operation.opcode.code {
+        // SLOAD
+        0x54 => Some((operation.inputs.first().and_then(constant)?, 0)),
+        // SHR
+        0x1c => {
+            let shift = constant(operation.inputs.first()?)?;
+            let slot = load(operation.inputs.get(1)?)?;
+            (shift < U256::from(256) && shift.as_usize().is_multiple_of(8))
+                .then(|| (slot, shift.as_usize() / 8))
+        }
+        // DIV
+        0x04 => {
+            let slot = load(operation.inputs.first()?)?;
+            let divisor = constant(operation.inputs.get(1)?)?;
+            let bits = divisor.trailing_zeros() as usize;
+            (!divisor.is_zero() && divisor == U256::one() << bits && bits.is_multiple_of(8))
+                .then_some((slot, bits / 8))
+        }
+        _ => None,
+    }
+}
+
+/// The field a mask selects from a value shifted right by `shift` bytes. Reads mask the low bytes
+/// of a shifted value, while writes clear the field's bytes before setting them, so a single run
+/// of zero bytes is the field being written.
+fn mask_field(mask: U256, shift: usize) -> Option<(usize, usize)> {
+    let mut bytes = [0u8; 32];
+    mask.to_little_endian(&mut bytes);
+    if bytes.iter().any(|byte| *byte != 0 && *byte != 0xff) {
+        return None
+    }
+
+    let ones = runs(&bytes, 0xff);
+    let zeros = runs(&bytes, 0);
+    match (ones.as_slice(), zeros.as_slice()) {
+        // the low bytes of the value
+        ([(0, size)], _) if shift + size <= 32 && (shift > 0 || *size < 32) => Some((shift, *size)),
+        (_, [field]) if shift == 0 => Some(*field),
+        ([field], _) if shift == 0 && field.1 < 32 => Some(*field),
+        _ => None,
+    }
+}
+
+/// The `(start, length)` of each run of bytes equal to `value`.
+fn runs(bytes: &[u8], value: u8) -> Vec<(usize, usize)> {
+    let mut runs: Vec<(usize, usize)> = Vec::new();
+    for (i, byte) in bytes.iter().enumerate() {
+        if *byte != value {
+            continue
+        }
+        match runs.last_mut() {
+            Some((start, length)) if *start + *length == i => *length += 1,
+            _ => runs.push((i, 1)),
+        }
+    }
+    runs
+}
+
+/// The value of an input, if it's a constant.
+pub(crate) fn constant(input: &WrappedInput) -> Option<U256> {
+    match input {
+        WrappedInput::Raw(value) => Some(*value),
+        WrappedInput::Opcode(operation) => match operation.opcode.code {
+            // PUSH0
+            0x5f => Some(U256::zero()),
+            // PUSH1 -> PUSH32
+            0x60..=0x7f => operation.inputs.first().and_then(constant),
+            _ => None,
+        },
+        WrappedInput::Variable(_) => None,
+    }
+}
+
+/// The solidity type of a value `size` bytes wide. Twenty byte values are almost always addresses.
+fn value_type(size: usize) -> (String, String) {
+    match size {
+        20 => (String::from("t_address"), String::from("address")),
+        _ => (format!("t_uint{}", size * 8), format!("uint{}", size * 8)),
+    }
+}
+
+/// Build a solc-style storage layout from the base slots and the fields packed into them. Mapping
+/// keys and values, and array elements, can't be told apart from a 32 byte word, so they're typed
+/// as `bytes32` keys and `uint256` values. Unlabeled variables are named like the decompiler names
+/// them, e.g. `stor_a` and `stor_map_b`.
+pub fn build_storage_layout(
+    contract: &str,
+    layout: &[SlotUsage],
+    packing: &PackingBuilder,
+) -> StorageLayout {
+    let mut storage_layout = StorageLayout::default();
+    let mut add_type = |id: &str, storage_type: StorageType| {
+        storage_layout.types.entry(id.to_string()).or_insert(storage_type);
+    };
+
+    let mut entries = Vec::new();
+    for usage in layout {
+        let fields = match usage.kind {
+            SlotKind::Value => packing.fields(&usage.slot),
+            _ => vec![(0, 32)],
+        };
+        for (offset, size) in fields {
+            let name = base26_encode(entries.len() + 1);
+            let (type_id, label) = match usage.kind {
+                SlotKind::Value => {
+                    let (type_id, label) = value_type(size);
+                    add_type(
+                        &type_id,
+                        StorageType {
+                            encoding: String::from("inplace"),
+                            label,
+                            number_of_bytes: size.to_string(),
+                            key: None,
+                            value: None,
+                            base: None,
+                        },
+                    );
+                    (type_id, format!("stor_{name}"))
+                }
+                SlotKind::Mapping => {
+                    add_type("t_bytes32", inplace("bytes32"));
+                    add_type("t_uint256", inplace("uint256"));
+                    let type_id = String::from("t_mapping(t_bytes32,t_uint256)");
+                    add_type(
+                        &type_id,
+                        StorageType {
+                            encoding: String::from("mapping"),
+                            label: String::from("mapping(bytes32 => uint256)"),
+                            number_of_bytes: String::from("32"),
+                            key: Some(String::from("t_bytes32")),
+                            value: Some(String::from("t_uint256")),
+                            base: None,
+                        },
+                    );
+                    (type_id, format!("stor_map_{name}"))
+                }
+                SlotKind::Array => {
+                    add_type("t_uint256", inplace("uint256"));
+                    let type_id = String::from("t_array(t_uint256)dyn_storage");
+                    add_type(
+                        &type_id,
+                        StorageType {
+                            encoding: String::from("dynamic_array"),
+                            label: String::from("uint256[]"),
+                            number_of_bytes: String::from("32"),
+                            key: None,
+                            value: None,
+                            base: Some(String::from("t_uint256")),
+                        },
+                    );
+                    (type_id, format!("stor_array_{name}"))
+                }
+            };
+
+            entries.push(StorageEntry {
+                ast_id: entries.len(),
+                contract: contract.to_string(),
+                label: usage.label.clone().unwrap_or(label),
+                offset,
+                slot: usage.slot.to_string(),
+                type_id,
+            });
+        }
+    }
+
+    storage_layout.storage = entries;
+    storage_layout
+}
+
+/// A 32 byte value type.
+fn inplace(label: &str) -> StorageType {
+    StorageType {
+        encoding: String::from("inplace"),
+        label: label.to_string(),
+        number_of_bytes: String::from("32"),
+        key: None,
+        value: None,
+        base: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::storage_layout::util::*;
+
+    fn usage(slot: u64, kind: SlotKind) -> SlotUsage {
+        SlotUsage {
+            slot: U256::from(slot),
+            kind,
+            label: None,
+            reads: BTreeSet::new(),
+            writes: BTreeSet::new(),
+        }
+    }
+
+    #[test]
+    fn test_mask_field() {
+        // and(sload(0), 0xff)
+        assert_eq!(mask_field(U256::from(0xff), 0), Some((0, 1)));
+        // and(shr(160, sload(0)), 0xff)
+        assert_eq!(mask_field(U256::from(0xff), 20), Some((20, 1)));
+        // and(sload(0), not(0xff << 160)), clearing the field before it's written
+        assert_eq!(mask_field(!(U256::from(0xff) << 160), 0), Some((20, 1)));
+        // and(sload(0), not(2^160 - 1))
+        assert_eq!(mask_field(!((U256::one() << 160) - 1), 0), Some((0, 20)));
+        assert_eq!(mask_field(U256::MAX, 0), None);
+        assert_eq!(mask_field(U256::from(0x0f), 0), None);
+    }
+
+    #[test]
+    fn test_overlapping_fields() {
+        let mut packing = PackingBuilder::new();
+        packing.fields.insert(U256::zero(), BTreeSet::from([(0, 20), (0, 32), (20, 1), (20, 12)]));
+
+        assert_eq!(packing.fields(&U256::zero()), vec![(0, 20), (20, 1)]);
+        assert_eq!(packing.fields(&U256::one()), vec![(0, 32)]);
+    }
+
+    #[test]
+    fn test_build_storage_layout() {
+        let mut packing = PackingBuilder::new();
+        packing.fields.insert(U256::zero(), BTreeSet::from([(0, 20), (20, 1)]));
+        let layout =
+            vec![usage(0, SlotKind::Value), usage(1, SlotKind::Mapping), usage(2, SlotKind::Array)];
+
+        let storage_layout = build_storage_layout("DecompiledContract", &layout, &packing);
+
+        let labels = storage_layout
+            .storage
+            .iter()
+            .map(|entry| (entry.label.as_str(), entry.offset, entry.slot.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            labels,
+            vec![
+                ("stor_a", 0, "0"),
+                ("stor_b", 20, "0"),
+                ("stor_map_c", 0, "1"),
+                ("stor_array_d", 0, "2")
+            ]
+        );
+        assert_eq!(storage_layout.storage[0].type_id, "t_address");
+        assert_eq!(storage_layout.storage[1].type_id, "t_uint8");
+        assert_eq!(storage_layout.types["t_uint8"].number_of_bytes, "1");
+        assert_eq!(
+            storage_layout.types["t_mapping(t_bytes32,t_uint256)"].value.as_deref(),
+            Some("t_uint256")
+        );
+        assert_eq!(
+            storage_layout.types["t_array(t_uint256)dyn_storage"].base.as_deref(),
+            Some("t_uint256")
+        );
+    }
+
+    #[test]
+    fn test_slot_label() {
+        let mut packing = PackingBuilder::new();
+        packing.fields.insert(U256::zero(), BTreeSet::from([(0, 20), (20, 1)]));
+        let layout =
+            vec![usage(0, SlotKind::Value), usage(1, SlotKind::Mapping), usage(2, SlotKind::Array)];
+        let storage_layout = build_storage_layout("DecompiledContract", &layout, &packing);
+
+        let slot = |slot: U256| {
+            let mut bytes = [0u8; 32];
+            slot.to_big_endian(&mut bytes);
+            H256::from(bytes)
+        };
+        let array_start = U256::from(keccak256(slot(U256::from(2)).to_fixed_bytes()));
+
+        assert_eq!(
+            storage_layout.slot_label(&slot(U256::zero())).as_deref(),
+            Some("stor_a (address), stor_b (uint8)")
+        );
+        assert_eq!(
+            storage_layout.slot_label(&slot(U256::one())).as_deref(),
+            Some("stor_map_c (mapping(bytes32 => uint256))")
+        );
+        assert_eq!(
+            storage_layout.slot_label(&slot(array_start + 3)).as_deref(),
+            Some("stor_array_d[3]")
+        );
+        assert_eq!(storage_layout.slot_label(&slot(U256::from(3))), None);
+    }
+}
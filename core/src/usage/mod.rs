@@ -0,0 +1,251 @@
+pub mod output;
+pub mod reverts;
+pub mod util;
+
+use clap::{AppSettings, Parser};
+use derive_builder::Builder;
+use ethers::types::{Address, TransactionRequest, U256};
+use heimdall_common::{
+    constants::ADDRESS_REGEX,
+    ether::{
+        explorer::ExplorerClient,
+        rpc::get_revert_data,
+        selectors::resolve_selectors,
+        signatures::{score_signature, ResolvedError, ResolvedFunction},
+    },
+    utils::{
+        io::{
+            crash::enter_pass,
+            exit::{record_failure, ExitStatus},
+            logging::{set_logger_env, Logger},
+        },
+        strings::decode_hex,
+    },
+};
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+
+use self::{
+    reverts::error_selectors,
+    util::{mine_usage, selectors, FunctionUsage, ObservedCall},
+};
+
+/// The most failed transactions replayed, starting from the most recent.
+const MAX_REPLAYS: usize = 100;
+
+#[derive(Debug, Clone, Parser, Builder)]
+#[clap(
+    about = "Report how a contract's functions are called in practice, from its transaction history",
+    after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    global_setting = AppSettings::DeriveDisplayOrder,
+    override_usage = "heimdall usage <TARGET> [OPTIONS]"
+)]
+pub struct UsageArgs {
+    /// The address of the contract to report the usage of.
+    #[clap(required = true)]
+    pub target: String,
+
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// The RPC provider to use. The block explorer for its chain is used to find transactions.
+    #[clap(long = "rpc-url", short, default_value = "", hide_default_value = true)]
+    pub rpc_url: String,
+
+    /// Whether to skip resolving function selectors.
+    #[clap(long = "skip-resolving")]
+    pub skip_resolving: bool,
+
+    /// Whether to replay recent failed transactions, and report what they reverted with.
+    #[clap(long)]
+    pub reverts: bool,
+
+    /// The format to generate, one of 'json' or 'markdown'.
+    #[clap(long, short, default_value = "markdown")]
+    pub format: String,
+
+    /// The output directory to write the output to or 'print' to print to the console
+    #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
+    pub output: String,
+
+    /// The name for the output file
+    #[clap(long, short, default_value = "", hide_default_value = true)]
+    pub name: String,
+}
+
+impl UsageArgsBuilder {
+    pub fn new() -> Self {
+        Self {
+            target: Some(String::new()),
+            verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
+            rpc_url: Some(String::new()),
+            skip_resolving: Some(false),
+            reverts: Some(false),
+            format: Some(String::from("markdown")),
+            output: Some(String::new()),
+            name: Some(String::new()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageResult {
+    pub target: String,
+    /// The number of transactions which called the target
+    pub transactions: usize,
+    /// Each function which was called, most called first
+    pub functions: Vec<FunctionUsage>,
+}
+
+/// Report how the target's functions are called in practice. The target's transactions are
+/// pulled from the block explorer for the RPC provider's chain and clustered by selector, then
+/// each function's callers and arguments are searched for patterns.
+pub async fn usage(args: UsageArgs) -> Result<UsageResult, Error> {
+    set_logger_env(&args.verbose);
+
+    // get a new logger
+    let (logger, _) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    // check the format before fetching, so an invalid one fails fast
+    if !["json", "markdown"].contains(&args.format.as_str()) {
+        return Err(Error::GenericError(format!(
+            "invalid format '{}' . expected one of: json, markdown.",
+            args.format
+        )))
+    }
+
+    enter_pass("transaction history");
+    let calls = get_observed_calls(&args.target, &args.rpc_url, args.reverts).await?;
+    logger.info(&format!("found {} calls to '{}' .", calls.len(), args.target));
+    if args.reverts {
+        let (failed, replayed) = calls.iter().fold((0, 0), |(failed, replayed), call| {
+            (failed + call.failed as usize, replayed + call.revert.is_some() as usize)
+        });
+        logger.info(&format!("{replayed} of {failed} failed calls reverted when replayed."));
+    }
+
+    let mut functions = mine_usage(&calls);
+    if !args.skip_resolving {
+        enter_pass("resolve selectors");
+        let resolved =
+            resolve_selectors::<ResolvedFunction>(selectors(&functions).into_iter().collect())
+                .await;
+        for function in functions.iter_mut() {
+            function.signature = resolved
+                .get(function.selector.trim_start_matches("0x"))
+                .and_then(|matches| {
+                    matches.iter().max_by_key(|resolved| score_signature(&resolved.signature))
+                })
+                .map(|resolved| resolved.signature.clone());
+        }
+
+        // custom errors are only known by their selector until they're resolved
+        let errors = functions
+            .iter()
+            .flat_map(|function| error_selectors(&function.reverts))
+            .collect::<Vec<String>>();
+        if !errors.is_empty() {
+            let resolved = resolve_selectors::<ResolvedError>(errors).await;
+            for revert in functions.iter_mut().flat_map(|function| function.reverts.iter_mut()) {
+                if let Some(signature) = revert
+                    .selector
+                    .as_ref()
+                    .and_then(|selector| resolved.get(selector.trim_start_matches("0x")))
+                    .and_then(|matches| {
+                        matches.iter().max_by_key(|resolved| score_signature(&resolved.signature))
+                    })
+                {
+                    revert.reason = signature.signature.clone();
+                }
+            }
+        }
+    }
+
+    Ok(UsageResult { target: args.target.to_lowercase(), transactions: calls.len(), functions })
+}
+
+/// The calls made to the target by its transaction history. Only direct calls are found, since
+/// explorers don't report the calldata of internal calls.
+///
+/// When `replay_failures` is set, the most recent failed calls are replayed against the state
+/// before their block, with their original gas limit, to recover what they reverted with. A
+/// failure which depended on an earlier transaction in the same block may not revert again, and
+/// is left without a reason.
+pub async fn get_observed_calls(
+    target: &str,
+    rpc_url: &str,
+    replay_failures: bool,
+) -> Result<Vec<ObservedCall>, Error> {
+    // transactions are indexed by address, so only an address has a history
+    if !ADDRESS_REGEX.is_match(target).unwrap_or(false) || target.parse::<Address>().is_err() {
+        record_failure(ExitStatus::InvalidArguments);
+        return Err(Error::GenericError(format!(
+            "invalid target '{target}' . usage requires a contract address."
+        )))
+    }
+
+    let explorer = match rpc_url.is_empty() {
+        true => None,
+        false => ExplorerClient::from_rpc_url(rpc_url).await,
+    }
+    .ok_or_else(|| {
+        Error::GenericError(String::from(
+            "reading transaction history requires a block explorer for the RPC provider's chain.",
+        ))
+    })?;
+
+    let target = target.to_lowercase();
+    let transactions = explorer
+        .get_transactions(&target)
+        .await
+        .map_err(|e| Error::GenericError(e.to_string()))?
+        .into_iter()
+        .filter(|transaction| transaction.to == target)
+        .collect::<Vec<_>>();
+
+    let mut calls = Vec::new();
+    let mut replays = 0;
+    for transaction in transactions.iter() {
+        let calldata = match decode_hex(transaction.input.trim_start_matches("0x")) {
+            Ok(calldata) => calldata,
+            Err(_) => continue,
+        };
+        let value = U256::from_dec_str(&transaction.value).unwrap_or_default();
+
+        let mut revert = None;
+        if replay_failures && transaction.failed && replays < MAX_REPLAYS {
+            if let Some(block_number) = transaction.block_number.filter(|block| *block > 0) {
+                replays += 1;
+                let mut request = TransactionRequest::new()
+                    .from(transaction.from.parse::<Address>().unwrap_or_default())
+                    .to(target.parse::<Address>().unwrap_or_default())
+                    .data(calldata.clone())
+                    .value(value);
+                if let Some(gas) = transaction.gas {
+                    request = request.gas(gas);
+                }
+                revert = get_revert_data(request, block_number - 1, rpc_url)
+                    .await
+                    .map_err(|e| Error::GenericError(e.to_string()))?;
+            }
+        }
+
+        calls.push(ObservedCall {
+            caller: transaction.from.clone(),
+            calldata,
+            value,
+            failed: transaction.failed,
+            revert,
+        });
+    }
+
+    // the explorer returns transactions newest first, so the most recent failures are the ones
+    // replayed
+    calls.reverse();
+    Ok(calls)
+}
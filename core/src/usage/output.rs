@@ -0,0 +1,77 @@
+use super::UsageResult;
+
+/// Write the usage report as markdown, with a section for each function.
+pub fn build_markdown(result: &UsageResult) -> String {
+    let mut output = vec![
+        format!("# Usage of `{}`", result.target),
+        String::new(),
+        format!(
+            "{} transactions called {} functions.",
+            result.transactions,
+            result.functions.len()
+        ),
+    ];
+
+    for function in &result.functions {
+        output.push(String::new());
+        output.push(match &function.signature {
+            Some(signature) => format!("## `{signature}` ({})", function.selector),
+            None => format!("## `{}`", function.selector),
+        });
+        output.push(String::new());
+        output.extend(function.notes().into_iter().map(|note| format!("- {note}")));
+
+        if !function.top_callers.is_empty() {
+            output.push(format!(
+                "- top callers: {}",
+                function
+                    .top_callers
+                    .iter()
+                    .map(|(caller, count)| format!("`{caller}` ({count})"))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ));
+        }
+    }
+
+    output.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::usage::{output::*, util::FunctionUsage};
+
+    #[test]
+    fn test_build_markdown() {
+        let result = UsageResult {
+            target: String::from("0xaa"),
+            transactions: 2,
+            functions: vec![FunctionUsage {
+                selector: String::from("0xd0e30db0"),
+                signature: Some(String::from("deposit()")),
+                calls: 2,
+                failed: 0,
+                with_value: 2,
+                callers: 1,
+                top_callers: vec![(String::from("0xbb"), 2)],
+                arguments: Vec::new(),
+                reverts: Vec::new(),
+            }],
+        };
+
+        assert_eq!(
+            build_markdown(&result),
+            [
+                "# Usage of `0xaa`",
+                "",
+                "2 transactions called 1 functions.",
+                "",
+                "## `deposit()` (0xd0e30db0)",
+                "",
+                "- called 2 times by 1 caller, 2 with ether",
+                "- top callers: `0xbb` (2)",
+            ]
+            .join("\n")
+        );
+    }
+}
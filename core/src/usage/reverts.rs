@@ -0,0 +1,124 @@
+use std::collections::{HashMap, HashSet};
+
+use ethers::{
+    abi::{decode, ParamType, Token},
+    types::U256,
+};
+use heimdall_common::{
+    ether::panics::{panic_description, PANIC_SELECTOR},
+    utils::strings::encode_hex,
+};
+use serde::{Deserialize, Serialize};
+
+/// `Error(string)`, which `require` and `revert` with a message revert with.
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+
+/// A reason a function reverted with, and how often it did.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RevertReason {
+    /// The custom error's selector, if the revert wasn't a message or a panic
+    pub selector: Option<String>,
+    /// The decoded reason, e.g. a quoted message, a panic, or a custom error's signature
+    pub reason: String,
+    pub count: usize,
+}
+
+/// Decode revert data into a readable reason. A custom error is described by its selector, which
+/// is returned alongside so it can be resolved.
+pub fn decode_revert(data: &[u8]) -> (Option<String>, String) {
+    if data.is_empty() {
+        return (None, String::from("empty revert"))
+    }
+    if data.len() < 4 {
+        return (None, format!("0x{}", encode_hex(data.to_vec())))
+    }
+
+    let selector = &data[..4];
+    if selector == ERROR_SELECTOR {
+        if let Ok(Some(Token::String(message))) =
+            decode(&[ParamType::String], &data[4..]).map(|tokens| tokens.into_iter().next())
+        {
+            return (None, format!("{message:?}"))
+        }
+    }
+    if selector == PANIC_SELECTOR && data.len() == 36 {
+        let code = U256::from_big_endian(&data[4..]);
+        return (
+            None,
+            match panic_description(code) {
+                Some(description) => format!("panic {code:#04x} ({description})"),
+                None => format!("panic {code:#x}"),
+            },
+        )
+    }
+
+    let selector = format!("0x{}", encode_hex(selector.to_vec()));
+    (Some(selector.clone()), selector)
+}
+
+/// Count the reasons a function reverted with, most common first.
+pub fn count_reverts<'a>(reverts: impl IntoIterator<Item = &'a [u8]>) -> Vec<RevertReason> {
+    let mut counts: HashMap<(Option<String>, String), usize> = HashMap::new();
+    for data in reverts {
+        *counts.entry(decode_revert(data)).or_default() += 1;
+    }
+
+    let mut reasons = counts
+        .into_iter()
+        .map(|((selector, reason), count)| RevertReason { selector, reason, count })
+        .collect::<Vec<RevertReason>>();
+    reasons.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.reason.cmp(&b.reason)));
+    reasons
+}
+
+/// The selectors of every custom error which was reverted with, for resolving their signatures.
+pub fn error_selectors(reasons: &[RevertReason]) -> HashSet<String> {
+    reasons
+        .iter()
+        .filter_map(|reason| Some(reason.selector.as_ref()?.trim_start_matches("0x").to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::abi::encode;
+
+    use crate::usage::reverts::*;
+
+    #[test]
+    fn test_decode_revert() {
+        let message = [
+            ERROR_SELECTOR.to_vec(),
+            encode(&[Token::String(String::from("Ownable: caller is not the owner"))]),
+        ]
+        .concat();
+        assert_eq!(
+            decode_revert(&message),
+            (None, String::from("\"Ownable: caller is not the owner\""))
+        );
+
+        let panic = [PANIC_SELECTOR.to_vec(), encode(&[Token::Uint(U256::from(0x11))])].concat();
+        assert_eq!(
+            decode_revert(&panic),
+            (None, String::from("panic 0x11 (arithmetic overflow or underflow)"))
+        );
+
+        assert_eq!(decode_revert(&[]), (None, String::from("empty revert")));
+        assert_eq!(
+            decode_revert(&[0x82, 0xb4, 0x29, 0x00]),
+            (Some(String::from("0x82b42900")), String::from("0x82b42900"))
+        );
+    }
+
+    #[test]
+    fn test_count_reverts() {
+        let unauthorized = [0x82, 0xb4, 0x29, 0x00];
+        let reverts: Vec<&[u8]> = vec![&unauthorized, &[], &unauthorized];
+
+        let reasons = count_reverts(reverts);
+        assert_eq!(reasons.len(), 2);
+        assert_eq!((reasons[0].reason.as_str(), reasons[0].count), ("0x82b42900", 2));
+        assert_eq!((reasons[1].reason.as_str(), reasons[1].count), ("empty revert", 1));
+        assert_eq!(error_selectors(&reasons), HashSet::from([String::from("82b42900")]));
+    }
+}
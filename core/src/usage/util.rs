@@ -0,0 +1,311 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use ethers::types::U256;
+use heimdall_common::utils::strings::encode_hex;
+use serde::{Deserialize, Serialize};
+
+use super::reverts::{count_reverts, RevertReason};
+
+/// The most argument words reported for each function.
+const MAX_ARGUMENTS: usize = 8;
+
+/// The most values or callers listed for each pattern.
+const MAX_LISTED: usize = 3;
+
+/// A call to one of the target's functions, taken from its transaction history.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObservedCall {
+    pub caller: String,
+    /// The calldata, including the selector
+    pub calldata: Vec<u8>,
+    pub value: U256,
+    pub failed: bool,
+    /// The data the call reverted with when it was replayed, if it was
+    pub revert: Option<Vec<u8>>,
+}
+
+/// The values passed as one word of a function's arguments.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArgumentUsage {
+    /// The index of the 32-byte word after the selector
+    pub index: usize,
+    /// The number of distinct values passed
+    pub distinct: usize,
+    /// The most common values, and how often each was passed
+    pub values: Vec<(String, usize)>,
+    /// What every value looks like, e.g. `address` or `boolean`, if they agree
+    pub looks_like: Option<String>,
+}
+
+/// How a function is used in practice.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FunctionUsage {
+    pub selector: String,
+    pub signature: Option<String>,
+    pub calls: usize,
+    pub failed: usize,
+    /// The number of calls which sent ether
+    pub with_value: usize,
+    /// The number of distinct callers
+    pub callers: usize,
+    /// The most frequent callers, and how often each called
+    pub top_callers: Vec<(String, usize)>,
+    /// The arguments passed by every call, by word
+    pub arguments: Vec<ArgumentUsage>,
+    /// The reasons replayed failures reverted with, most common first
+    #[serde(default)]
+    pub reverts: Vec<RevertReason>,
+}
+
+impl FunctionUsage {
+    /// The function's usage, as short notes for the decompiled output.
+    pub fn notes(&self) -> Vec<String> {
+        let mut notes = vec![format!(
+            "called {} {} by {} {}{}{}",
+            self.calls,
+            plural(self.calls, "time", "times"),
+            self.callers,
+            plural(self.callers, "caller", "callers"),
+            match self.failed {
+                0 => String::new(),
+                failed => format!(", {failed} failed"),
+            },
+            match self.with_value {
+                0 => String::new(),
+                with_value => format!(", {with_value} with ether"),
+            },
+        )];
+
+        // a caller making most of the calls is worth knowing, e.g. a keeper or an owner
+        if let Some((caller, count)) = self.top_callers.first() {
+            if self.callers > 1 && count * 2 > self.calls {
+                notes.push(format!("most calls ({count}) come from {caller}"));
+            }
+        }
+
+        // the guards which actually fire, which are hard to tell apart in a chain of requires
+        if !self.reverts.is_empty() {
+            let mut reasons = self
+                .reverts
+                .iter()
+                .take(MAX_LISTED)
+                .map(|revert| format!("{} ({})", revert.reason, revert.count))
+                .collect::<Vec<String>>();
+            if self.reverts.len() > MAX_LISTED {
+                reasons.push(format!("{} more", self.reverts.len() - MAX_LISTED));
+            }
+            notes.push(format!("reverted with {}", reasons.join(", ")));
+        }
+
+        for argument in &self.arguments {
+            let looks_like = match argument.looks_like.as_deref() {
+                Some("address") => String::from(", all addresses"),
+                Some(looks_like) => format!(", all {looks_like}s"),
+                None => String::new(),
+            };
+            notes.push(match argument.distinct {
+                1 => format!("word {} is always {}", argument.index, argument.values[0].0),
+                distinct if distinct <= MAX_LISTED => format!(
+                    "word {} is one of {}",
+                    argument.index,
+                    argument
+                        .values
+                        .iter()
+                        .map(|(value, count)| format!("{value} ({count})"))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                ),
+                distinct => {
+                    format!(
+                        "word {} varies ({distinct} distinct values{looks_like})",
+                        argument.index
+                    )
+                }
+            });
+        }
+
+        notes
+    }
+}
+
+fn plural<'a>(count: usize, singular: &'a str, plural: &'a str) -> &'a str {
+    if count == 1 {
+        singular
+    } else {
+        plural
+    }
+}
+
+/// A word formatted for a report: small values in decimal, anything else in hex.
+fn format_word(word: U256) -> String {
+    match word.bits() {
+        0..=64 => word.to_string(),
+        _ => format!("{word:#x}"),
+    }
+}
+
+/// What a set of words looks like, if they all agree.
+fn classify(words: &[U256]) -> Option<String> {
+    if words.iter().all(|word| word <= &U256::one()) {
+        Some(String::from("boolean"))
+    } else if words.iter().all(|word| word.bits() > 64 && word.bits() <= 160) {
+        Some(String::from("address"))
+    } else if words.iter().all(|word| word.bits() <= 64) {
+        Some(String::from("small integer"))
+    } else {
+        None
+    }
+}
+
+/// The values counted, most common first. Ties are broken by value, so the order is stable.
+fn most_common<T: Ord + Clone>(counts: HashMap<T, usize>) -> Vec<(T, usize)> {
+    let mut counts = counts.into_iter().collect::<Vec<(T, usize)>>();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
+
+/// Cluster the calls by selector, and find the patterns in each function's callers and
+/// arguments. Arguments are compared word by word, since their types aren't known, and only the
+/// words every call passed are reported. Functions are ordered by how often they're called.
+pub fn mine_usage(calls: &[ObservedCall]) -> Vec<FunctionUsage> {
+    let mut clusters: BTreeMap<String, Vec<&ObservedCall>> = BTreeMap::new();
+    for call in calls.iter().filter(|call| call.calldata.len() >= 4) {
+        clusters.entry(encode_hex(call.calldata[..4].to_vec())).or_default().push(call);
+    }
+
+    let mut functions = clusters
+        .into_iter()
+        .map(|(selector, calls)| {
+            let mut callers: HashMap<String, usize> = HashMap::new();
+            for call in &calls {
+                *callers.entry(call.caller.clone()).or_default() += 1;
+            }
+
+            let word_count =
+                calls.iter().map(|call| (call.calldata.len() - 4) / 32).min().unwrap_or(0);
+            let arguments = (0..word_count.min(MAX_ARGUMENTS))
+                .map(|index| {
+                    let words = calls
+                        .iter()
+                        .map(|call| {
+                            U256::from_big_endian(
+                                &call.calldata[4 + index * 32..4 + (index + 1) * 32],
+                            )
+                        })
+                        .collect::<Vec<U256>>();
+                    let mut counts: HashMap<U256, usize> = HashMap::new();
+                    for word in &words {
+                        *counts.entry(*word).or_default() += 1;
+                    }
+
+                    ArgumentUsage {
+                        index,
+                        distinct: counts.len(),
+                        values: most_common(counts)
+                            .into_iter()
+                            .take(MAX_LISTED)
+                            .map(|(word, count)| (format_word(word), count))
+                            .collect(),
+                        looks_like: classify(&words),
+                    }
+                })
+                .collect();
+
+            FunctionUsage {
+                selector: format!("0x{selector}"),
+                signature: None,
+                calls: calls.len(),
+                failed: calls.iter().filter(|call| call.failed).count(),
+                with_value: calls.iter().filter(|call| !call.value.is_zero()).count(),
+                callers: callers.len(),
+                top_callers: most_common(callers).into_iter().take(MAX_LISTED).collect(),
+                arguments,
+                reverts: count_reverts(calls.iter().filter_map(|call| call.revert.as_deref())),
+            }
+        })
+        .collect::<Vec<FunctionUsage>>();
+
+    functions.sort_by(|a, b| b.calls.cmp(&a.calls).then_with(|| a.selector.cmp(&b.selector)));
+    functions
+}
+
+/// The selectors of every function which was called, for resolving their signatures.
+pub fn selectors(functions: &[FunctionUsage]) -> HashSet<String> {
+    functions
+        .iter()
+        .map(|function| function.selector.trim_start_matches("0x").to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use heimdall_common::utils::strings::decode_hex;
+
+    use crate::usage::util::*;
+
+    fn call(caller: &str, calldata: &str, failed: bool) -> ObservedCall {
+        ObservedCall {
+            caller: caller.to_string(),
+            calldata: decode_hex(calldata).unwrap(),
+            value: U256::zero(),
+            failed,
+            revert: None,
+        }
+    }
+
+    #[test]
+    fn test_mine_usage() {
+        let address = "0000000000000000000000001111111111111111111111111111111111111111";
+        let calls = vec![
+            call("0xaa", &format!("a9059cbb{address}{:064x}", 1), false),
+            call("0xaa", &format!("a9059cbb{address}{:064x}", 2), false),
+            call("0xbb", &format!("a9059cbb{address}{:064x}", 3), true),
+            call("0xbb", "095ea7b3", false),
+        ];
+
+        let functions = mine_usage(&calls);
+        assert_eq!(functions.len(), 2);
+
+        let transfer = &functions[0];
+        assert_eq!(transfer.selector, "0xa9059cbb");
+        assert_eq!((transfer.calls, transfer.failed, transfer.callers), (3, 1, 2));
+        assert_eq!(transfer.arguments.len(), 2);
+        assert_eq!(transfer.arguments[0].distinct, 1);
+        assert_eq!(transfer.arguments[0].looks_like.as_deref(), Some("address"));
+        assert_eq!(transfer.arguments[1].distinct, 3);
+
+        let notes = transfer.notes();
+        assert_eq!(notes[0], "called 3 times by 2 callers, 1 failed");
+        assert!(notes.contains(&format!(
+            "word 0 is always {:#x}",
+            U256::from_str_radix("1111111111111111111111111111111111111111", 16).unwrap()
+        )));
+        assert!(notes.contains(&String::from("word 1 is one of 1 (1), 2 (1), 3 (1)")));
+    }
+
+    #[test]
+    fn test_varying_arguments() {
+        let calls =
+            (0..5).map(|i| call("0xaa", &format!("12345678{i:064x}"), false)).collect::<Vec<_>>();
+
+        let notes = mine_usage(&calls)[0].notes();
+        assert_eq!(
+            notes,
+            vec![
+                String::from("called 5 times by 1 caller"),
+                String::from("word 0 varies (5 distinct values, all small integers)"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_revert_notes() {
+        let mut calls = (0..3).map(|_| call("0xaa", "12345678", true)).collect::<Vec<_>>();
+        calls[0].revert = Some(Vec::new());
+        calls[1].revert = Some(vec![0x82, 0xb4, 0x29, 0x00]);
+        calls[2].revert = Some(vec![0x82, 0xb4, 0x29, 0x00]);
+
+        let notes = mine_usage(&calls)[0].notes();
+        assert_eq!(notes[1], "reverted with 0x82b42900 (2), empty revert (1)");
+    }
+}
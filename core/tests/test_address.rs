@@ -0,0 +1,53 @@
+#[cfg(test)]
+mod integration_tests {
+    use heimdall_core::address::{address, AddressArgsBuilder};
+
+    #[tokio::test]
+    async fn test_address_create() {
+        let result = address(
+            AddressArgsBuilder::new()
+                .deployer(String::from("0x6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0"))
+                .nonce(Some(1))
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result.scheme, "CREATE");
+        assert_eq!(result.address, "0x343c43a37d37dff08ae8c4a11544c718abb4fcf8");
+    }
+
+    #[tokio::test]
+    async fn test_address_create2() {
+        // from EIP-1014's examples
+        let result = address(
+            AddressArgsBuilder::new()
+                .deployer(String::from("0xdeadbeef00000000000000000000000000000000"))
+                .salt(String::from("0x00"))
+                .init_code(String::from("0x00"))
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result.scheme, "CREATE2");
+        assert_eq!(result.address, "0xb928f69bb1d91cd65274e3c79d8986362984fda3");
+    }
+
+    #[tokio::test]
+    async fn test_address_requires_one_scheme() {
+        let result = address(
+            AddressArgsBuilder::new()
+                .deployer(String::from("0xdeadbeef00000000000000000000000000000000"))
+                .nonce(Some(1))
+                .salt(String::from("0x00"))
+                .build()
+                .unwrap(),
+        )
+        .await;
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,57 @@
+#[cfg(test)]
+mod integration_tests {
+    use clap_verbosity_flag::Verbosity;
+
+    use heimdall_core::{
+        asm::{asm, AsmArgsBuilder},
+        disassemble::{disassemble, DisassemblerArgs},
+    };
+
+    const RECOVER: &str = "731bf797219482a29013d804ad96d1c6f84fba4c453014608060405260043610610058576000357c0100000000000000000000000000000000000000000000000000000000900463ffffffff16806319045a251461005d575b600080fd5b6100c56004803603810190808035600019169060200190929190803590602001908201803590602001908080601f0160208091040260200160405190810160405280939291908181526020018383808284378201915050505050509192919290505050610107565b604051808273ffffffffffffffffffffffffffffffffffffffff1673ffffffffffffffffffffffffffffffffffffffff16815260200191505060405180910390f35b6000806000806041855114151561012157600093506101f6565b6020850151925060408501519150606085015160001a9050601b8160ff16101561014c57601b810190505b601b8160ff16141580156101645750601c8160ff1614155b1561017257600093506101f6565b600186828585604051600081526020016040526040518085600019166000191681526020018460ff1660ff1681526020018360001916600019168152602001826000191660001916815260200194505050505060206040516020810390808403906000865af11580156101e9573d6000803e3d6000fd5b5050506020604051035193505b505050929150505600a165627a7a72305820aacffa0494cd3f043493eee9c720bca9d5ef505ae7230ffc3d88c49ceeb7441e0029";
+
+    #[tokio::test]
+    async fn test_asm_disassembly() {
+        for format in ["asm", "annotated"] {
+            let assembly = disassemble(DisassemblerArgs {
+                target: String::from(RECOVER),
+                verbose: Verbosity::new(0, 0),
+                rpc_url: String::from(""),
+                decimal_counter: false,
+                format: String::from(format),
+                verify: true,
+                name: String::from(""),
+                output: String::from(""),
+            })
+            .await
+            .unwrap();
+
+            let args = AsmArgsBuilder::new()
+                .target(assembly)
+                .verbose(Verbosity::new(0, 0))
+                .build()
+                .unwrap();
+            let result = asm(args).await.unwrap();
+            assert_eq!(result.bytecode, RECOVER, "{format}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_asm_patch() {
+        // the dispatcher's fallback `PUSH1 0x00 DUP1 REVERT` at 0x58 becomes
+        // `JUMPDEST PUSH1 0x00 DUP1 DUP1 REVERT`, shifting every jump past it by one byte
+        let args = AsmArgsBuilder::new()
+            .target(String::from("JUMPDEST\nPUSH1 00\nDUP1\nDUP1\nREVERT"))
+            .verbose(Verbosity::new(0, 0))
+            .patch(String::from(RECOVER))
+            .block(String::from("0x59"))
+            .build()
+            .unwrap();
+        let result = asm(args).await.unwrap();
+
+        assert_eq!(result.block, Some(0x58..0x5d));
+        assert!(result.fixed_jumps > 0);
+        assert!(result.bytecode.contains("5b60008080fd5b6100c6"));
+        assert!(result.bytecode.ends_with("0029"));
+        assert_eq!(result.bytecode.len(), RECOVER.len() + 2);
+    }
+}
@@ -0,0 +1,56 @@
+#[cfg(test)]
+mod integration_tests {
+    use clap_verbosity_flag::Verbosity;
+    use ethers::types::U256;
+
+    use heimdall_core::{
+        calls::{
+            calls,
+            util::{CallTarget, CallValue},
+            CallsArgsBuilder,
+        },
+        map::util::ReferenceKind,
+    };
+
+    /// `0xaaaaaaaa` delegatecalls its first argument, and `0xbbbbbbbb` forwards its value to the
+    /// address in slot 0.
+    const CALLS: &str = "60003560e01c8063aaaaaaaa14601a5763bbbbbbbb14602957005b60006000600060006004355af4005b6000600060006000346000545af100";
+
+    #[tokio::test]
+    async fn test_calls_per_function() {
+        let args = CallsArgsBuilder::new()
+            .target(String::from(CALLS))
+            .verbose(Verbosity::new(0, 0))
+            .build()
+            .unwrap();
+        let result = calls(args).await.unwrap();
+
+        let delegatecalls = &result.functions["0xaaaaaaaa"];
+        assert_eq!(delegatecalls.len(), 1);
+        assert_eq!(delegatecalls[0].kind, ReferenceKind::DelegateCall);
+        assert_eq!(delegatecalls[0].target, CallTarget::Calldata { argument: Some(0) });
+        assert!(delegatecalls[0].is_dangerous());
+
+        let value_calls = &result.functions["0xbbbbbbbb"];
+        assert_eq!(value_calls.len(), 1);
+        assert_eq!(value_calls[0].kind, ReferenceKind::Call);
+        assert_eq!(value_calls[0].target, CallTarget::Storage { slot: U256::zero() });
+        assert_eq!(value_calls[0].value, CallValue::CallValue);
+
+        // every call is made by a function, so the fallback makes none
+        assert!(!result.functions.contains_key("fallback"));
+    }
+
+    #[tokio::test]
+    async fn test_calls_invalid_format() {
+        let args = CallsArgsBuilder::new()
+            .target(String::from(CALLS))
+            .verbose(Verbosity::new(0, 0))
+            .format(String::from("dot"))
+            .build()
+            .unwrap();
+        let result = calls(args).await;
+
+        assert!(result.is_err());
+    }
+}
@@ -17,6 +17,7 @@ mod benchmark {
                 output: String::from(""),
                 name: String::from(""),
                 timeout: 10000,
+                format: String::from("dot"),
             };
             let _ = heimdall_core::cfg::cfg(args).await;
         }
@@ -36,6 +37,7 @@ mod benchmark {
                 output: String::from(""),
                 name: String::from(""),
                 timeout: 10000,
+                format: String::from("dot"),
             };
             let _ = heimdall_core::cfg::cfg(args).await;
         }
@@ -47,7 +49,7 @@ mod benchmark {
 #[cfg(test)]
 mod integration_tests {
     use clap_verbosity_flag::Verbosity;
-    use heimdall_core::cfg::CFGArgs;
+    use heimdall_core::cfg::{output::build_cfg_json, CFGArgs, CFGArgsBuilder};
     use petgraph::dot::Dot;
 
     #[tokio::test]
@@ -61,6 +63,7 @@ mod integration_tests {
             output: String::from(""),
             name: String::from(""),
             timeout: 10000,
+            format: String::from("dot"),
         })
         .await
         .unwrap();
@@ -86,6 +89,7 @@ mod integration_tests {
             output: String::from(""),
             name: String::from(""),
             timeout: 10000,
+            format: String::from("dot"),
         })
         .await
         .unwrap();
@@ -96,4 +100,50 @@ mod integration_tests {
             assert!(output.contains(line))
         }
     }
+
+    #[tokio::test]
+    async fn test_cfg_json() {
+        // jumps to 0x07 if the first word of calldata is non-zero, and stops at 0x06 otherwise
+        let result = heimdall_core::cfg::cfg(
+            CFGArgsBuilder::new()
+                .target(String::from("600035600757005b600160005500"))
+                .verbose(Verbosity::new(0, 0))
+                .format(String::from("json"))
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+        let json = build_cfg_json(&result);
+
+        let blocks = json.blocks.iter().map(|block| (block.start, block.end)).collect::<Vec<_>>();
+        assert_eq!(blocks, vec![(0x00, 0x05), (0x06, 0x06), (0x07, 0x0d)]);
+        assert_eq!(json.blocks[0].instructions[1].opcode, "CALLDATALOAD");
+        assert_eq!(json.blocks[0].instructions[2].operand.as_deref(), Some("0x07"));
+
+        let edges = json
+            .edges
+            .iter()
+            .map(|edge| (edge.from, edge.to, edge.edge.jump_taken))
+            .collect::<Vec<_>>();
+        assert_eq!(edges, vec![(0x00, 0x06, false), (0x00, 0x07, true)]);
+        assert!(json
+            .edges
+            .iter()
+            .all(|edge| edge.edge.condition.as_deref() == Some("msg.data[0]")));
+    }
+
+    #[tokio::test]
+    async fn test_cfg_invalid_format() {
+        let result = heimdall_core::cfg::cfg(
+            CFGArgsBuilder::new()
+                .target(String::from("600035600757005b600160005500"))
+                .format(String::from("svg"))
+                .build()
+                .unwrap(),
+        )
+        .await;
+
+        assert!(result.is_err());
+    }
 }
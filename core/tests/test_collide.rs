@@ -0,0 +1,66 @@
+#[cfg(test)]
+mod integration_tests {
+    use clap_verbosity_flag::Verbosity;
+
+    use heimdall_common::utils::strings::encode_hex;
+    use heimdall_core::collide::{
+        collide,
+        search::{expand_pattern, selector_of},
+        CollideArgsBuilder,
+    };
+
+    #[tokio::test]
+    async fn test_collide_dictionary() {
+        let result = collide(
+            CollideArgsBuilder::new()
+                .selector(String::from("0x095ea7b3"))
+                .verbose(Verbosity::new(0, 0))
+                .limit(1)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result.signatures, vec!["approve(address,uint256)".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_collide_pattern() {
+        let signature = expand_pattern("clash_*(uint256)", 4242);
+        let selector = encode_hex(selector_of(&signature).to_vec());
+
+        let result = collide(
+            CollideArgsBuilder::new()
+                .selector(selector)
+                .verbose(Verbosity::new(0, 0))
+                .pattern(String::from("clash_*(uint256)"))
+                .max_attempts(10000)
+                .limit(1)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result.signatures, vec![signature]);
+    }
+
+    #[tokio::test]
+    async fn test_collide_invalid_selector() {
+        let args = CollideArgsBuilder::new()
+            .selector(String::from("0x1234"))
+            .verbose(Verbosity::new(0, 0))
+            .build()
+            .unwrap();
+        assert!(collide(args).await.is_err());
+
+        let args = CollideArgsBuilder::new()
+            .selector(String::from("0x12345678"))
+            .verbose(Verbosity::new(0, 0))
+            .pattern(String::from("clash()"))
+            .build()
+            .unwrap();
+        assert!(collide(args).await.is_err());
+    }
+}
@@ -16,9 +16,19 @@ mod benchmark {
                 skip_resolving: true,
                 include_solidity: true,
                 include_yul: false,
+                include_interface: false,
                 output: String::from(""),
                 name: String::from(""),
                 timeout: 10000,
+                stream: false,
+                chain: String::from("ethereum"),
+                summarizer: String::new(),
+                usage: false,
+                artifact: String::new(),
+                include_pcs: false,
+                fragment: false,
+                entry_pc: String::from("0"),
+                no_follow_proxy: false,
             };
             let _ = heimdall_core::decompile::decompile(args).await;
         }
@@ -37,9 +47,19 @@ mod benchmark {
                 skip_resolving: true,
                 include_solidity: true,
                 include_yul: false,
+                include_interface: false,
                 output: String::from(""),
                 name: String::from(""),
                 timeout: 10000,
+                stream: false,
+                chain: String::from("ethereum"),
+                summarizer: String::new(),
+                usage: false,
+                artifact: String::new(),
+                include_pcs: false,
+                fragment: false,
+                entry_pc: String::from("0"),
+                no_follow_proxy: false,
             };
             let _ = heimdall_core::decompile::decompile(args).await;
         }
@@ -58,9 +78,19 @@ mod benchmark {
                 skip_resolving: true,
                 include_solidity: false,
                 include_yul: true,
+                include_interface: false,
                 output: String::from(""),
                 name: String::from(""),
                 timeout: 10000,
+                stream: false,
+                chain: String::from("ethereum"),
+                summarizer: String::new(),
+                usage: false,
+                artifact: String::new(),
+                include_pcs: false,
+                fragment: false,
+                entry_pc: String::from("0"),
+                no_follow_proxy: false,
             };
             let _ = heimdall_core::decompile::decompile(args).await;
         }
@@ -79,9 +109,19 @@ mod benchmark {
                 skip_resolving: true,
                 include_solidity: false,
                 include_yul: true,
+                include_interface: false,
                 output: String::from(""),
                 name: String::from(""),
                 timeout: 10000,
+                stream: false,
+                chain: String::from("ethereum"),
+                summarizer: String::new(),
+                usage: false,
+                artifact: String::new(),
+                include_pcs: false,
+                fragment: false,
+                entry_pc: String::from("0"),
+                no_follow_proxy: false,
             };
             let _ = heimdall_core::decompile::decompile(args).await;
         }
@@ -100,9 +140,19 @@ mod benchmark {
                 skip_resolving: true,
                 include_solidity: false,
                 include_yul: false,
+                include_interface: false,
                 output: String::from(""),
                 name: String::from(""),
                 timeout: 10000,
+                stream: false,
+                chain: String::from("ethereum"),
+                summarizer: String::new(),
+                usage: false,
+                artifact: String::new(),
+                include_pcs: false,
+                fragment: false,
+                entry_pc: String::from("0"),
+                no_follow_proxy: false,
             };
             let _ = heimdall_core::decompile::decompile(args).await;
         }
@@ -121,9 +171,19 @@ mod benchmark {
                 skip_resolving: true,
                 include_solidity: false,
                 include_yul: false,
+                include_interface: false,
                 output: String::from(""),
                 name: String::from(""),
                 timeout: 10000,
+                stream: false,
+                chain: String::from("ethereum"),
+                summarizer: String::new(),
+                usage: false,
+                artifact: String::new(),
+                include_pcs: false,
+                fragment: false,
+                entry_pc: String::from("0"),
+                no_follow_proxy: false,
             };
             let _ = heimdall_core::decompile::decompile(args).await;
         }
@@ -136,7 +196,461 @@ mod benchmark {
 mod integration_tests {
     use clap_verbosity_flag::Verbosity;
     use heimdall_common::utils::io::file::delete_path;
-    use heimdall_core::decompile::DecompilerArgs;
+    use heimdall_core::decompile::{out::abi::ABIStructure, DecompilerArgs};
+
+    /// Dispatches both 0xaaaaaaaa and 0xbbbbbbbb to the same body, which writes storage.
+    const ALIASED_BYTECODE: &str =
+        "60003560e01c8063aaaaaaaa14601f578063bbbbbbbb14601f5760006000fd5b600160005500";
+
+    /// `0xaaaaaaaa` calls an internal function with its argument `n`, which stores `n` and calls
+    /// itself with `n - 1` until `n` is zero.
+    const RECURSIVE_BYTECODE: &str =
+        "60003560e01c63aaaaaaaa14601057005b6019600435601b565b005b8015602e57808055602d60018203601b565b5b5056";
+
+    /// `0xaaaaaaaa` loops over `i` from zero to its first argument, and within that over `j` from
+    /// zero to its second, storing `j` at slot `i`, then stores 5 at slot 7.
+    const LOOP_BYTECODE: &str =
+        "60003560e01c63aaaaaaaa14601057005b60005b806004351115603a5760005b806024351115603257808255600101601f565b506001016013565b600560075500";
+
+    /// Initializes the free memory pointer, then `0xaaaaaaaa` creates a contract from memory at
+    /// 0x80, and `0xbbbbbbbb` from the free memory pointer and zero slot at 0x40.
+    const CREATE_BYTECODE: &str = "608060405260003560e01c8063aaaaaaaa14601f5763bbbbbbbb14602857005b602060806000f0005b604060406000f000";
+
+    /// `0xaaaaaaaa` calls `arbBlockNumber()` on address 0x64, which is Arbitrum's `ArbSys`.
+    const ARBSYS_BYTECODE: &str = "60003560e01c63aaaaaaaa14601057005b7fa3b1b31d00000000000000000000000000000000000000000000000000000000600052602060006004600060645afa5060206000f3";
+
+    /// `0xaaaaaaaa` reverts if the EIP-1153 lock in transient slot 0 is set, and otherwise sets it,
+    /// stores the caller in slot 1, and clears it.
+    const TRANSIENT_BYTECODE: &str =
+        "60003560e01c63aaaaaaaa14601057005b60005c602657600160005d33600155600060005d005b60006000fd";
+
+    /// `0xaaaaaaaa` returns storage slot 0, dispatched with PUSH0 as solc 0.8.20 and later do.
+    const PUSH0_BYTECODE: &str = "5f3560e01c63aaaaaaaa146011575f80fd5b5f545f5260205ff3";
+
+    /// `0xaaaaaaaa` panics with a division by zero if its argument is zero, and otherwise
+    /// returns 1.
+    // 0xaaaaaaaa DUP3s with only one item on the stack, then stores the sum
+    const STACK_UNDERFLOW_BYTECODE: &str =
+        "60003560e01c63aaaaaaaa1460145760006000fd5b6001820160005500";
+
+    /// `0xaaaaaaaa` deploys the single byte `0x00` with CREATE2 and a salt of zero.
+    const CREATE2_BYTECODE: &str =
+        "60003560e01c63aaaaaaaa1460145760006000fd5b600060005260006001601f6000f500";
+
+    /// `0xaaaaaaaa` reverts with `0x12345678(msg.sender)` unless its first argument is set.
+    const CUSTOM_ERROR_BYTECODE: &str = "60003560e01c63aaaaaaaa1460145760006000fd5b600435602f57631234567860e01b6000523360045260246000fd5b00";
+
+    /// `0xaaaaaaaa` always reverts with `0x12345678()`.
+    const UNCONDITIONAL_CUSTOM_ERROR_BYTECODE: &str =
+        "60003560e01c63aaaaaaaa1460145760006000fd5b631234567860e01b60005260046000fd";
+
+    const PANIC_BYTECODE: &str = "60003560e01c63aaaaaaaa14601357600080fd5b600435602f57634e487b7160e01b600052601260045260246000fd5b600160005260206000f3";
+
+    fn bytecode_args(target: &str, include_yul: bool) -> DecompilerArgs {
+        DecompilerArgs {
+            target: String::from(target),
+            verbose: Verbosity::new(0, 0),
+            rpc_url: String::from(""),
+            default: true,
+            skip_resolving: true,
+            include_solidity: !include_yul,
+            include_yul,
+            include_interface: false,
+            output: String::from(""),
+            name: String::from(""),
+            timeout: 10000,
+            stream: false,
+            chain: String::from("ethereum"),
+            summarizer: String::new(),
+            usage: false,
+            artifact: String::new(),
+            include_pcs: false,
+            fragment: false,
+            entry_pc: String::from("0"),
+            no_follow_proxy: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_decompile_pc_comments() {
+        for include_yul in [false, true] {
+            let result = heimdall_core::decompile::decompile(DecompilerArgs {
+                include_pcs: true,
+                ..bytecode_args(CREATE_BYTECODE, include_yul)
+            })
+            .await
+            .unwrap();
+            let source = result.source.unwrap();
+
+            // each create is annotated with the instructions from its function's JUMPDEST
+            for (create, pcs) in [
+                ("create(0, 0x80, 0x20)", "// pc 0x1f-0x26"),
+                ("create(0, 0x40, 0x40)", "// pc 0x28-0x2f"),
+            ] {
+                assert!(source.lines().any(|line| line.contains(create) && line.ends_with(pcs)));
+            }
+        }
+
+        let result = heimdall_core::decompile::decompile(bytecode_args(CREATE_BYTECODE, false))
+            .await
+            .unwrap();
+        assert!(!result.source.unwrap().contains("// pc"));
+    }
+
+    #[tokio::test]
+    async fn test_decompile_transient_storage() {
+        let result = heimdall_core::decompile::decompile(bytecode_args(TRANSIENT_BYTECODE, false))
+            .await
+            .unwrap();
+        let source = result.source.unwrap();
+
+        // the lock is a transient variable, named and declared apart from the stored caller
+        assert!(source.contains("bytes32 transient tstor_a;"));
+        assert!(source.contains("bytes32 public stor_a;"));
+        assert!(source.contains("tstor_a = 0x01;"));
+        assert!(source.contains("stor_a = msg.sender;"));
+
+        let result = heimdall_core::decompile::decompile(bytecode_args(TRANSIENT_BYTECODE, true))
+            .await
+            .unwrap();
+        let source = result.source.unwrap();
+        // the branch jumped to reverts, so the guard isn't negated
+        assert!(source.contains("if tload(0) {\n                    revert(0, 0)"));
+        assert!(source.contains("tstore(0, 0x01)"));
+        assert!(source.contains("sstore(0x01, caller())"));
+    }
+
+    #[tokio::test]
+    async fn test_decompile_push0() {
+        let result = heimdall_core::decompile::decompile(bytecode_args(PUSH0_BYTECODE, false))
+            .await
+            .unwrap();
+        let source = result.source.unwrap();
+
+        // the selector is found behind the PUSH0 calldata offset, and the slot read through it
+        assert!(source.contains("function Unresolved_aaaaaaaa()"));
+        assert!(source.contains("return stor_a;"));
+    }
+
+    #[tokio::test]
+    async fn test_decompile_panic() {
+        let result = heimdall_core::decompile::decompile(bytecode_args(PANIC_BYTECODE, false))
+            .await
+            .unwrap();
+        let source = result.source.unwrap();
+        assert!(source.contains("\"Panic(0x12): division or modulo by zero\");"));
+        assert!(!source.contains("CustomError_4e487b71"));
+
+        let result =
+            heimdall_core::decompile::decompile(bytecode_args(PANIC_BYTECODE, true)).await.unwrap();
+        let source = result.source.unwrap();
+        assert!(source.contains("/* Panic(0x12): division or modulo by zero */ revert(0, 0x24)\n"));
+    }
+
+    #[tokio::test]
+    async fn test_decompile_stack_underflow() {
+        let result =
+            heimdall_core::decompile::decompile(bytecode_args(STACK_UNDERFLOW_BYTECODE, false))
+                .await
+                .unwrap();
+        let source = result.source.unwrap();
+
+        // the underflow is reported, and the rest of the function is still decompiled
+        assert!(source.contains(
+            "/// @notice             stack underflow at pc 0x17 (DUP3): expected at least 3 items, found 1"
+        ));
+        assert!(source.contains("stor_a = unknown + 0x01;"));
+    }
+
+    #[tokio::test]
+    async fn test_decompile_create2_site() {
+        let result = heimdall_core::decompile::decompile(bytecode_args(CREATE2_BYTECODE, false))
+            .await
+            .unwrap();
+        let source = result.source.unwrap();
+
+        // the deployer isn't known for raw bytecode, so only the salt and init code hash are given
+        assert!(source.contains(&format!(
+            "CREATE2 at pc 0x22 deploys with salt 0x{}, init code hash \
+             0xbc36789e7a1e281436464229828f817d6612f7b477d66591ff96a9e064bcc98a",
+            "0".repeat(64)
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_decompile_custom_error_arguments() {
+        let result =
+            heimdall_core::decompile::decompile(bytecode_args(CUSTOM_ERROR_BYTECODE, false))
+                .await
+                .unwrap();
+        let source = result.source.unwrap();
+
+        assert!(source.contains("error CustomError_12345678(bytes32 arg0);"));
+        assert!(source.contains("require(arg0, CustomError_12345678(msg.sender));"));
+    }
+
+    #[tokio::test]
+    async fn test_decompile_unconditional_custom_error() {
+        let result = heimdall_core::decompile::decompile(bytecode_args(
+            UNCONDITIONAL_CUSTOM_ERROR_BYTECODE,
+            false,
+        ))
+        .await
+        .unwrap();
+        let source = result.source.unwrap();
+
+        assert!(source.contains("revert CustomError_12345678();"));
+    }
+
+    #[tokio::test]
+    async fn test_decompile_interface() {
+        let result = heimdall_core::decompile::decompile(DecompilerArgs {
+            include_solidity: false,
+            include_interface: true,
+            ..bytecode_args(CUSTOM_ERROR_BYTECODE, false)
+        })
+        .await
+        .unwrap();
+        let interface = result.interface.unwrap();
+
+        // only the interface is built, not the function bodies
+        assert!(result.source.is_none());
+        assert!(interface.contains("interface IContract {"));
+        assert!(interface.contains("// function 0xaaaaaaaa could not be resolved"));
+        assert!(interface.contains("// error 0x12345678 could not be resolved"));
+    }
+
+    #[tokio::test]
+    async fn test_decompile_time_bounds() {
+        // 0xaaaaaaaa requires block.timestamp <= arg0, and 0xbbbbbbbb reverts while
+        // block.timestamp < storage[0x02]
+        let bytecode = "60003560e01c8063aaaaaaaa14601a5763bbbbbbbb14602a57005b600435421115602857600080fd5b005b6002544210603457005b600080fd";
+        let source = heimdall_core::decompile::decompile(bytecode_args(bytecode, false))
+            .await
+            .unwrap()
+            .source
+            .unwrap();
+
+        assert!(source.contains(
+            "/// @notice             arg0 is a deadline, the call reverts once block.timestamp passes it"
+        ));
+        assert!(source.contains(
+            "/// @notice             storage slot 0x02 is an unlock time, the call reverts until block.timestamp reaches it"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_decompile_minimal_proxy() {
+        let bytecode = "363d3d373d3d3d363d73bebebebebebebebebebebebebebebebebebebebe5af43d82803e903d91602b57fd5bf3";
+        let result = heimdall_core::decompile::decompile(DecompilerArgs {
+            no_follow_proxy: true,
+            ..bytecode_args(bytecode, false)
+        })
+        .await
+        .unwrap();
+
+        let proxy = result.proxy.unwrap();
+        assert_eq!(proxy.implementation, "0xbebebebebebebebebebebebebebebebebebebebe");
+        assert!(result.source.unwrap().contains(
+            "/// @custom:proxy EIP-1167 minimal proxy for \
+             0xbebebebebebebebebebebebebebebebebebebebe, the code below is the proxy's own\n\
+             contract DecompiledContract {"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_decompile_yul_reverts() {
+        let result =
+            heimdall_core::decompile::decompile(bytecode_args(CUSTOM_ERROR_BYTECODE, true))
+                .await
+                .unwrap();
+        let source = result.source.unwrap();
+
+        // the revert data is written inside the negated guard, before it's reverted with
+        assert!(source
+            .contains("if iszero(calldataload(0x04)) {\n                    mstore(0, 0x12345678"));
+        assert!(source.contains(
+            "mstore(0x04, caller())\n                    revert(0, 0x24)\n                }\n"
+        ));
+        assert!(!source.contains("else"));
+
+        // the helpers are defined as written, rather than cleaned up like the logic
+        assert!(source.contains("a := and(x, 0xffffffffffffffffffffffffffffffffffffffff)"));
+
+        let result = heimdall_core::decompile::decompile(bytecode_args(
+            UNCONDITIONAL_CUSTOM_ERROR_BYTECODE,
+            true,
+        ))
+        .await
+        .unwrap();
+        assert!(result.source.unwrap().contains("revert(0, 0x04)"));
+    }
+
+    #[tokio::test]
+    async fn test_decompile_fragment() {
+        // two bytes of other code, then a fragment which jumps out to 0x1234 if its first
+        // argument is set, stores the caller, and jumps out to 0xabcd
+        let result = heimdall_core::decompile::decompile(DecompilerArgs {
+            fragment: true,
+            entry_pc: String::from("0x02"),
+            no_follow_proxy: false,
+            ..bytecode_args("fefe600435611234573360005561abcd56", false)
+        })
+        .await
+        .unwrap();
+        let source = result.source.unwrap();
+
+        assert!(source.contains("function Unresolved_00000002("));
+        assert!(source.contains("// jumps to 0x1234, outside the fragment"));
+        assert!(source.contains("// jumps to 0xabcd, outside the fragment"));
+        assert!(source.contains("decompiled from a code fragment, starting at pc 0x2"));
+    }
+
+    #[tokio::test]
+    async fn test_decompile_shared_body_solidity() {
+        let result = heimdall_core::decompile::decompile(bytecode_args(ALIASED_BYTECODE, false))
+            .await
+            .unwrap();
+        let source = result.source.unwrap();
+
+        // the body is written once, with the other selector attached to it
+        assert_eq!(source.matches("function Unresolved_").count(), 1);
+        assert!(source.contains("/// @custom:alias       0xbbbbbbbb Unresolved_bbbbbbbb"));
+        assert!(source.contains("0xbbbbbbbb is dispatched to the same entry point"));
+
+        // both selectors are still in the ABI
+        let functions = result
+            .abi
+            .unwrap()
+            .into_iter()
+            .filter(|entry| matches!(entry, ABIStructure::Function(_)))
+            .count();
+        assert_eq!(functions, 2);
+    }
+
+    #[tokio::test]
+    async fn test_decompile_shared_body_yul() {
+        let result = heimdall_core::decompile::decompile(bytecode_args(ALIASED_BYTECODE, true))
+            .await
+            .unwrap();
+        let source = result.source.unwrap();
+
+        assert!(source.contains("case 0xaaaaaaaa"));
+        assert!(source.contains("case 0xbbbbbbbb"));
+        assert!(source.contains("shares its body with 0xaaaaaaaa"));
+        assert_eq!(source.matches("sstore(").count(), 1);
+    }
+
+    /// `0xaaaaaaaa` requires that the caller is stored in slot 0, then writes slot 1.
+    const GUARDED_BYTECODE: &str =
+        "60003560e01c63aaaaaaaa14601057005b6000543314601e5760006000fd5b600160015500";
+
+    #[tokio::test]
+    async fn test_decompile_natspec_evidence() {
+        let result = heimdall_core::decompile::decompile(bytecode_args(GUARDED_BYTECODE, false))
+            .await
+            .unwrap();
+        let source = result.source.unwrap();
+
+        assert!(source.contains("/// @notice             Accepts ether and writes storage."));
+        assert!(source.contains(
+            "/// @dev                selector 0xaaaaaaaa, confidence medium (signature unresolved"
+        ));
+        assert!(source.contains("/// @dev                guards: 1 require check, on the caller"));
+        assert!(source.contains("/// @dev                gas: ~"));
+    }
+
+    #[tokio::test]
+    async fn test_decompile_summarizer_suggestions() {
+        let summarizer = "cat > /dev/null; echo '{\"name\": \"onlyOwnerSet\", \"summary\": \"Lets the owner   set a flag.\"}'";
+        let result = heimdall_core::decompile::decompile(DecompilerArgs {
+            summarizer: String::from(summarizer),
+            usage: false,
+            artifact: String::new(),
+            include_pcs: false,
+            fragment: false,
+            entry_pc: String::from("0"),
+            no_follow_proxy: false,
+            ..bytecode_args(GUARDED_BYTECODE, false)
+        })
+        .await
+        .unwrap();
+        let source = result.source.unwrap();
+
+        assert!(source.contains(&format!(
+            "/// @custom:suggested   onlyOwnerSet (proposed by `{summarizer}`, unverified)"
+        )));
+        assert!(source.contains("/// @custom:summary     Lets the owner set a flag. (proposed by"));
+    }
+
+    #[tokio::test]
+    async fn test_decompile_recursive_solidity() {
+        let result = heimdall_core::decompile::decompile(bytecode_args(RECURSIVE_BYTECODE, false))
+            .await
+            .unwrap();
+        let source = result.source.unwrap();
+
+        // the recursion is emitted as a call instead of being unrolled
+        assert!(source.contains("/// @custom:recursive   internal_0x001b"));
+        assert!(source.contains("internal_0x001b(arg0 - 0x01);"));
+        assert_eq!(source.matches(" = arg0;").count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_decompile_recursive_yul() {
+        let result = heimdall_core::decompile::decompile(bytecode_args(RECURSIVE_BYTECODE, true))
+            .await
+            .unwrap();
+        let source = result.source.unwrap();
+
+        assert!(source.contains("// internal_0x001b calls itself"));
+        assert!(source.contains("internal_0x001b(sub(calldataload(0x04), 0x01))"));
+        assert_eq!(source.matches("sstore(").count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_decompile_loops() {
+        let result =
+            heimdall_core::decompile::decompile(bytecode_args(LOOP_BYTECODE, false)).await.unwrap();
+        let source = result.source.unwrap();
+
+        // each loop is reconstructed over its loop-carried variable, rather than unrolled
+        assert!(source.contains("for (uint256 i = 0; arg0 > i; i++) {"));
+        assert!(source.contains("for (uint256 j = 0; arg1 > j; j++) {"));
+        assert_eq!(source.matches("for (").count(), 2);
+        assert!(!source.contains("if ("));
+        assert!(source.contains("stor_b = 0x05;"));
+    }
+
+    #[tokio::test]
+    async fn test_decompile_memory_safe_assembly() {
+        let result = heimdall_core::decompile::decompile(bytecode_args(CREATE_BYTECODE, false))
+            .await
+            .unwrap();
+        let source = result.source.unwrap();
+
+        assert!(source.contains("assembly (\"memory-safe\") { addr := create(0, 0x80, 0x20) }"));
+        assert!(source.contains("assembly { addr := create(0, 0x40, 0x40) }"));
+    }
+
+    #[tokio::test]
+    async fn test_decompile_chain_predeploy() {
+        let result = heimdall_core::decompile::decompile(bytecode_args(ARBSYS_BYTECODE, false))
+            .await
+            .unwrap();
+        assert!(result.source.unwrap().contains("address(0x64).staticcall"));
+
+        let result = heimdall_core::decompile::decompile(DecompilerArgs {
+            chain: String::from("arbitrum"),
+            ..bytecode_args(ARBSYS_BYTECODE, false)
+        })
+        .await
+        .unwrap();
+        let source = result.source.unwrap();
+
+        assert!(source.contains("uint256 var_a = ArbSys.arbBlockNumber();"));
+        assert!(!source.contains("staticcall"));
+    }
 
     #[tokio::test]
     async fn test_decompile_precompile() {
@@ -148,9 +662,19 @@ mod integration_tests {
             skip_resolving: true,
             include_solidity: true,
             include_yul: false,
+            include_interface: false,
             output: String::from(""),
             name: String::from(""),
             timeout: 10000,
+            stream: false,
+            chain: String::from("ethereum"),
+            summarizer: String::new(),
+            usage: false,
+            artifact: String::new(),
+            include_pcs: false,
+            fragment: false,
+            entry_pc: String::from("0"),
+            no_follow_proxy: false,
         })
         .await
         .unwrap();
@@ -178,9 +702,19 @@ mod integration_tests {
             skip_resolving: true,
             include_solidity: true,
             include_yul: false,
+            include_interface: false,
             output: String::from(""),
             name: String::from(""),
             timeout: 10000,
+            stream: false,
+            chain: String::from("ethereum"),
+            summarizer: String::new(),
+            usage: false,
+            artifact: String::new(),
+            include_pcs: false,
+            fragment: false,
+            entry_pc: String::from("0"),
+            no_follow_proxy: false,
         })
         .await
         .unwrap();
@@ -215,9 +749,19 @@ mod integration_tests {
             skip_resolving: true,
             include_solidity: true,
             include_yul: false,
+            include_interface: false,
             output: String::from(""),
             name: String::from(""),
             timeout: 10000,
+            stream: false,
+            chain: String::from("ethereum"),
+            summarizer: String::new(),
+            usage: false,
+            artifact: String::new(),
+            include_pcs: false,
+            fragment: false,
+            entry_pc: String::from("0"),
+            no_follow_proxy: false,
         })
         .await
         .unwrap();
@@ -329,9 +873,19 @@ mod integration_tests {
                 skip_resolving: true,
                 include_solidity: true,
                 include_yul: false,
+                include_interface: false,
                 output: String::from(""),
                 name: String::from(""),
                 timeout: 10000,
+                stream: false,
+                chain: String::from("ethereum"),
+                summarizer: String::new(),
+                usage: false,
+                artifact: String::new(),
+                include_pcs: false,
+                fragment: false,
+                entry_pc: String::from("0"),
+                no_follow_proxy: false,
             })
             .await
             .unwrap();
@@ -374,4 +928,39 @@ mod integration_tests {
 
         delete_path(&String::from("./output/tests/decompile/integration"));
     }
+
+    #[tokio::test]
+    async fn test_decompile_stream() {
+        use futures::StreamExt;
+
+        let functions = heimdall_core::decompile::stream::decompile_stream(DecompilerArgs {
+            target: String::from("731bf797219482a29013d804ad96d1c6f84fba4c453014608060405260043610610058576000357c0100000000000000000000000000000000000000000000000000000000900463ffffffff16806319045a251461005d575b600080fd5b6100c56004803603810190808035600019169060200190929190803590602001908201803590602001908080601f0160208091040260200160405190810160405280939291908181526020018383808284378201915050505050509192919290505050610107565b604051808273ffffffffffffffffffffffffffffffffffffffff1673ffffffffffffffffffffffffffffffffffffffff16815260200191505060405180910390f35b6000806000806041855114151561012157600093506101f6565b6020850151925060408501519150606085015160001a9050601b8160ff16101561014c57601b810190505b601b8160ff16141580156101645750601c8160ff1614155b1561017257600093506101f6565b600186828585604051600081526020016040526040518085600019166000191681526020018460ff1660ff1681526020018360001916600019168152602001826000191660001916815260200194505050505060206040516020810390808403906000865af11580156101e9573d6000803e3d6000fd5b5050506020604051035193505b505050929150505600a165627a7a72305820aacffa0494cd3f043493eee9c720bca9d5ef505ae7230ffc3d88c49ceeb7441e0029"),
+            verbose: Verbosity::new(0, 0),
+            rpc_url: String::from(""),
+            default: true,
+            skip_resolving: true,
+            include_solidity: false,
+            include_yul: false,
+            include_interface: false,
+            output: String::from(""),
+            name: String::from(""),
+            timeout: 10000,
+            stream: true,
+            chain: String::from("ethereum"),
+            summarizer: String::new(),
+            usage: false,
+            artifact: String::new(),
+            include_pcs: false,
+            fragment: false,
+            entry_pc: String::from("0"),
+            no_follow_proxy: false,
+        })
+        .collect::<Vec<_>>()
+        .await;
+
+        assert_eq!(functions.len(), 1);
+        let function = functions[0].as_ref().unwrap();
+        assert_eq!(function.selector, "0x19045a25");
+        assert_eq!(function.abi.len(), 1);
+    }
 }
@@ -13,6 +13,8 @@ mod benchmarks {
                 verbose: Verbosity::new(0, 0),
                 rpc_url: String::from(""),
                 decimal_counter: true,
+                format: String::from("asm"),
+                verify: false,
                 name:String::from(""),
                 output: String::from(""),
             }).await.unwrap();
@@ -28,18 +30,41 @@ mod integration_tests {
 
     use clap_verbosity_flag::Verbosity;
 
-    use heimdall_core::disassemble::{disassemble, DisassemblerArgs};
+    use heimdall_core::disassemble::{disassemble, DisassemblerArgs, Instruction};
 
     #[tokio::test]
     async fn test_disassemble_nominal() {
         let bytecode = "366000600037611000600036600073";
-        let expected = String::from("000000 CALLDATASIZE \n000002 PUSH1 00\n000004 PUSH1 00\n000005 CALLDATACOPY \n000008 PUSH2 1000\n00000a PUSH1 00\n00000b CALLDATASIZE \n00000d PUSH1 00\n");
+        let expected = String::from("000000 CALLDATASIZE \n000002 PUSH1 00\n000004 PUSH1 00\n000005 CALLDATACOPY \n000008 PUSH2 1000\n00000a PUSH1 00\n00000b CALLDATASIZE \n00000d PUSH1 00\n00000e DATA 73\n");
 
         let assembly = disassemble(DisassemblerArgs {
             target: bytecode.to_owned(),
             verbose: Verbosity::new(0, 0),
             rpc_url: String::from(""),
             decimal_counter: false,
+            format: String::from("asm"),
+            verify: false,
+            name: String::from(""),
+            output: String::from(""),
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(expected, assembly);
+    }
+
+    #[tokio::test]
+    async fn test_disassemble_push0() {
+        let bytecode = "5f3560e01c5f5f";
+        let expected = String::from("000000 PUSH0 \n000001 CALLDATALOAD \n000003 PUSH1 e0\n000004 SHR \n000005 PUSH0 \n000006 PUSH0 \n");
+
+        let assembly = disassemble(DisassemblerArgs {
+            target: bytecode.to_owned(),
+            verbose: Verbosity::new(0, 0),
+            rpc_url: String::from(""),
+            decimal_counter: false,
+            format: String::from("asm"),
+            verify: false,
             name: String::from(""),
             output: String::from(""),
         })
@@ -52,13 +77,15 @@ mod integration_tests {
     #[tokio::test]
     async fn test_disassemble_decimal_counter_nominal() {
         let bytecode = "366000600037611000600036600073";
-        let expected = String::from("0 CALLDATASIZE \n2 PUSH1 00\n4 PUSH1 00\n5 CALLDATACOPY \n8 PUSH2 1000\n10 PUSH1 00\n11 CALLDATASIZE \n13 PUSH1 00\n");
+        let expected = String::from("0 CALLDATASIZE \n2 PUSH1 00\n4 PUSH1 00\n5 CALLDATACOPY \n8 PUSH2 1000\n10 PUSH1 00\n11 CALLDATASIZE \n13 PUSH1 00\n14 DATA 73\n");
 
         let assembly = disassemble(DisassemblerArgs {
             target: bytecode.to_owned(),
             verbose: Verbosity::new(0, 0),
             rpc_url: String::from(""),
             decimal_counter: true,
+            format: String::from("asm"),
+            verify: false,
             name: String::from(""),
             output: String::from(""),
         })
@@ -71,13 +98,15 @@ mod integration_tests {
     #[tokio::test]
     async fn test_disassemble_with_custom_output() {
         let bytecode = "366000600037611000600036600073";
-        let expected = String::from("0 CALLDATASIZE \n2 PUSH1 00\n4 PUSH1 00\n5 CALLDATACOPY \n8 PUSH2 1000\n10 PUSH1 00\n11 CALLDATASIZE \n13 PUSH1 00\n");
+        let expected = String::from("0 CALLDATASIZE \n2 PUSH1 00\n4 PUSH1 00\n5 CALLDATACOPY \n8 PUSH2 1000\n10 PUSH1 00\n11 CALLDATASIZE \n13 PUSH1 00\n14 DATA 73\n");
 
         let assembly = disassemble(DisassemblerArgs {
             target: bytecode.to_owned(),
             verbose: Verbosity::new(0, 0),
             rpc_url: String::from(""),
             decimal_counter: true,
+            format: String::from("asm"),
+            verify: false,
             name: String::from(""),
             output: String::from(""),
         })
@@ -90,13 +119,15 @@ mod integration_tests {
     #[tokio::test]
     async fn test_disassemble_silent() {
         let bytecode = "366000600037611000600036600073";
-        let expected = String::from("0 CALLDATASIZE \n2 PUSH1 00\n4 PUSH1 00\n5 CALLDATACOPY \n8 PUSH2 1000\n10 PUSH1 00\n11 CALLDATASIZE \n13 PUSH1 00\n");
+        let expected = String::from("0 CALLDATASIZE \n2 PUSH1 00\n4 PUSH1 00\n5 CALLDATACOPY \n8 PUSH2 1000\n10 PUSH1 00\n11 CALLDATASIZE \n13 PUSH1 00\n14 DATA 73\n");
 
         let assembly = disassemble(DisassemblerArgs {
             target: bytecode.to_owned(),
             verbose: Verbosity::new(0, 1),
             rpc_url: String::from(""),
             decimal_counter: true,
+            format: String::from("asm"),
+            verify: false,
             name: String::from(""),
             output: String::from(""),
         })
@@ -109,16 +140,19 @@ mod integration_tests {
     #[tokio::test]
     async fn test_disassemble_from_file() {
         let bytecode = "366000600037611000600036600073";
-        let expected = String::from("0 CALLDATASIZE \n2 PUSH1 00\n4 PUSH1 00\n5 CALLDATACOPY \n8 PUSH2 1000\n10 PUSH1 00\n11 CALLDATASIZE \n13 PUSH1 00\n");
+        let expected = String::from("0 CALLDATASIZE \n2 PUSH1 00\n4 PUSH1 00\n5 CALLDATACOPY \n8 PUSH2 1000\n10 PUSH1 00\n11 CALLDATASIZE \n13 PUSH1 00\n14 DATA 73\n");
 
-        // write bytecode to file at the cwd
-        let mut file = std::fs::File::create("test_disassemble_from_file").unwrap();
+        // write bytecode to a temporary file
+        let path = std::env::temp_dir().join("heimdall-test-disassemble-from-file");
+        let mut file = std::fs::File::create(&path).unwrap();
         file.write_all(bytecode.as_bytes()).unwrap();
         let assembly = disassemble(DisassemblerArgs {
-            target: String::from("test_disassemble_from_file"),
+            target: path.to_string_lossy().to_string(),
             verbose: Verbosity::new(0, 0),
             rpc_url: String::from(""),
             decimal_counter: true,
+            format: String::from("asm"),
+            verify: false,
             name: String::from(""),
             output: String::from(""),
         })
@@ -128,18 +162,100 @@ mod integration_tests {
         assert_eq!(expected, assembly);
 
         // delete the file
-        std::fs::remove_file("test_disassemble_from_file").unwrap();
+        std::fs::remove_file(path).unwrap();
     }
 
     #[tokio::test]
     async fn test_disassemble_from_rpc() {
-        let expected = String::from("1 PUSH1 80\n3 PUSH1 40\n4 MSTORE \n25 PUSH20 ffffffffffffffffffffffffffffffffffffffff\n27 PUSH1 00\n28 SLOAD \n29 AND \n30 CALLDATASIZE \n32 PUSH1 00\n33 DUP1 \n34 CALLDATACOPY \n36 PUSH1 00\n37 DUP1 \n38 CALLDATASIZE \n40 PUSH1 00\n41 DUP5 \n42 GAS \n43 DELEGATECALL \n44 RETURNDATASIZE \n46 PUSH1 00\n47 DUP1 \n48 RETURNDATACOPY \n50 PUSH1 00\n51 DUP2 \n52 EQ \n53 ISZERO \n55 PUSH1 3d\n56 JUMPI \n57 RETURNDATASIZE \n59 PUSH1 00\n60 REVERT \n61 JUMPDEST \n62 RETURNDATASIZE \n64 PUSH1 00\n65 RETURN \n66 INVALID \n67 LOG1 \n74 PUSH6 627a7a723058\n75 SHA3 \n76 unknown \n107 PUSH30 648b83cfac072cbccefc2ffc62a6999d4a050ee87a721942de1da9670db8\n108 STOP \n109 unknown \n");
+        let expected = String::from("1 PUSH1 80\n3 PUSH1 40\n4 MSTORE \n25 PUSH20 ffffffffffffffffffffffffffffffffffffffff\n27 PUSH1 00\n28 SLOAD \n29 AND \n30 CALLDATASIZE \n32 PUSH1 00\n33 DUP1 \n34 CALLDATACOPY \n36 PUSH1 00\n37 DUP1 \n38 CALLDATASIZE \n40 PUSH1 00\n41 DUP5 \n42 GAS \n43 DELEGATECALL \n44 RETURNDATASIZE \n46 PUSH1 00\n47 DUP1 \n48 RETURNDATACOPY \n50 PUSH1 00\n51 DUP2 \n52 EQ \n53 ISZERO \n55 PUSH1 3d\n56 JUMPI \n57 RETURNDATASIZE \n59 PUSH1 00\n60 REVERT \n61 JUMPDEST \n62 RETURNDATASIZE \n64 PUSH1 00\n65 RETURN \n");
 
         let assembly = disassemble(DisassemblerArgs {
             target: String::from("0xafc2f2d803479a2af3a72022d54cc0901a0ec0d6"),
             verbose: Verbosity::new(0, 0),
             rpc_url: String::from("https://eth.llamarpc.com"),
             decimal_counter: true,
+            format: String::from("asm"),
+            verify: false,
+            name: String::from(""),
+            output: String::from(""),
+        })
+        .await
+        .unwrap();
+
+        // the unreachable INVALID and bzzr metadata aren't disassembled
+        assert!(assembly.starts_with(&expected));
+        assert!(
+            assembly[expected.len()..].starts_with("66 DATA fe\n67 METADATA a165627a7a72305820")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_disassemble_trailing_data() {
+        // JUMPDEST PUSH1 0x00 JUMP, followed by data which looks like a truncated PUSH32, and
+        // metadata whose length suffix doesn't match
+        let bytecode = "5b6000567f0102a26469706673ffff";
+        let expected =
+            String::from("0 JUMPDEST \n2 PUSH1 00\n3 JUMP \n4 DATA 7f0102a26469706673ffff\n");
+
+        let assembly = disassemble(DisassemblerArgs {
+            target: bytecode.to_owned(),
+            verbose: Verbosity::new(0, 0),
+            rpc_url: String::from(""),
+            decimal_counter: true,
+            format: String::from("asm"),
+            verify: false,
+            name: String::from(""),
+            output: String::from(""),
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(expected, assembly);
+    }
+
+    #[tokio::test]
+    async fn test_disassemble_json() {
+        let bytecode = "5b6000567f0102a26469706673ffff";
+
+        let assembly = disassemble(DisassemblerArgs {
+            target: bytecode.to_owned(),
+            verbose: Verbosity::new(0, 0),
+            rpc_url: String::from(""),
+            decimal_counter: false,
+            format: String::from("json"),
+            verify: false,
+            name: String::from(""),
+            output: String::from(""),
+        })
+        .await
+        .unwrap();
+        let instructions: Vec<Instruction> = serde_json::from_str(&assembly).unwrap();
+
+        assert_eq!(
+            instructions.iter().map(|instruction| instruction.op.as_str()).collect::<Vec<_>>(),
+            vec!["JUMPDEST", "PUSH1", "JUMP", "DATA"]
+        );
+        assert_eq!(instructions[1].pc, 1);
+        assert_eq!(instructions[1].pushed, Some(String::from("00")));
+        assert_eq!(instructions[1].offsets, 1..3);
+        assert_eq!(instructions[3].offsets, 4..15);
+    }
+
+    #[tokio::test]
+    async fn test_disassemble_annotated() {
+        let bytecode = "5b6000567f0102a26469706673ffff";
+        let expected = String::from(
+            "; block 0, 000000 - 000003\nlabel_000000:\n000000 JUMPDEST\n000001 PUSH1 00 ; -> \
+             label_000000\n000003 JUMP\n\n; data\n000004 DATA 7f0102a26469706673ffff\n",
+        );
+
+        let assembly = disassemble(DisassemblerArgs {
+            target: bytecode.to_owned(),
+            verbose: Verbosity::new(0, 0),
+            rpc_url: String::from(""),
+            decimal_counter: false,
+            format: String::from("annotated"),
+            verify: false,
             name: String::from(""),
             output: String::from(""),
         })
@@ -148,4 +264,45 @@ mod integration_tests {
 
         assert_eq!(expected, assembly);
     }
+
+    #[tokio::test]
+    async fn test_disassemble_invalid_format() {
+        let result = disassemble(DisassemblerArgs {
+            target: String::from("5b"),
+            verbose: Verbosity::new(0, 0),
+            rpc_url: String::from(""),
+            decimal_counter: false,
+            format: String::from("hex"),
+            verify: false,
+            name: String::from(""),
+            output: String::from(""),
+        })
+        .await;
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_disassemble_verify() {
+        // a truncated PUSH32 and an undefined opcode both reassemble to the original bytes
+        let path = std::env::temp_dir().join("heimdall-test-disassemble-verify");
+        std::fs::write(&path, "600c0c60016002017f0102").unwrap();
+        for format in ["asm", "json", "annotated"] {
+            let result = disassemble(DisassemblerArgs {
+                target: path.to_string_lossy().to_string(),
+                verbose: Verbosity::new(0, 0),
+                rpc_url: String::from(""),
+                decimal_counter: false,
+                format: String::from(format),
+                verify: true,
+                name: String::from(""),
+                output: String::from(""),
+            })
+            .await;
+
+            assert!(result.is_ok(), "{format}");
+        }
+
+        std::fs::remove_file(path).unwrap();
+    }
 }
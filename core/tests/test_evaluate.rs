@@ -0,0 +1,124 @@
+#[cfg(test)]
+mod integration_tests {
+    use std::fs;
+
+    use clap_verbosity_flag::Verbosity;
+    use ethers::types::U256;
+
+    use heimdall_core::evaluate::{evaluate, EvaluateArgs};
+
+    /// The deployed bytecode of `Implementation`, whose ABI and storage layout are below.
+    fn bytecode() -> String {
+        fs::read_to_string("./tests/testdata/implementation.hex").unwrap().trim().to_string()
+    }
+
+    /// A foundry artifact for `Implementation`. `initialize()` stores `msg.sender` in slot 0 and
+    /// sets a mapping at slot 1, and `upgradeTo(address)` writes the EIP-1967 implementation slot.
+    fn artifact() -> String {
+        ARTIFACT.replace("{bytecode}", &bytecode())
+    }
+
+    const ARTIFACT: &str = r#"{
+        "abi": [
+            {"type": "function", "name": "initialize", "stateMutability": "nonpayable",
+             "inputs": [], "outputs": []},
+            {"type": "function", "name": "upgradeTo", "stateMutability": "nonpayable",
+             "inputs": [{"name": "implementation", "type": "address"}], "outputs": []}
+        ],
+        "deployedBytecode": {"object": "0x{bytecode}"},
+        "storageLayout": {
+            "storage": [
+                {"label": "owner", "slot": "0", "offset": 0, "type": "t_address"},
+                {"label": "initialized", "slot": "1", "offset": 0, "type": "t_mapping(t_address,t_bool)"}
+            ],
+            "types": {
+                "t_address": {"encoding": "inplace", "label": "address", "numberOfBytes": "20"},
+                "t_mapping(t_address,t_bool)": {"encoding": "mapping", "label": "mapping(address => bool)", "numberOfBytes": "32"}
+            }
+        },
+        "metadata": {"settings": {"compilationTarget": {"src/Implementation.sol": "Implementation"}}}
+    }"#;
+
+    #[tokio::test]
+    async fn test_evaluate_foundry_project() {
+        let out = std::env::temp_dir().join("heimdall-test-evaluate-out");
+        let _ = fs::remove_dir_all(&out);
+        fs::create_dir_all(out.join("Implementation.sol")).unwrap();
+        fs::create_dir_all(out.join("IERC20.sol")).unwrap();
+        fs::create_dir_all(out.join("build-info")).unwrap();
+        fs::write(out.join("Implementation.sol/Implementation.json"), artifact()).unwrap();
+        fs::write(out.join("IERC20.sol/IERC20.json"), r#"{"abi": [], "deployedBytecode": "0x"}"#)
+            .unwrap();
+        fs::write(out.join("build-info/0.json"), artifact()).unwrap();
+
+        let result = evaluate(EvaluateArgs {
+            target: out.to_string_lossy().to_string(),
+            verbose: Verbosity::new(0, 0),
+            timeout: 10000,
+            format: String::from("json"),
+            output: String::from(""),
+            name: String::from(""),
+        })
+        .await
+        .unwrap();
+        fs::remove_dir_all(&out).unwrap();
+
+        // the interface has no bytecode, and build info isn't an artifact
+        assert_eq!(result.contracts.len(), 1);
+        let contract = &result.contracts[0];
+        assert_eq!(contract.name, "Implementation");
+        assert_eq!(contract.functions.selector_recall(), 1.0);
+        assert!(contract.functions.unexpected.is_empty());
+
+        let storage = contract.storage.as_ref().unwrap();
+        assert_eq!((storage.expected, storage.found, storage.kinds_correct), (2, 2, 2));
+        assert_eq!(
+            storage.unexpected,
+            vec![U256::from_str_radix(
+                "360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc",
+                16
+            )
+            .unwrap()]
+        );
+
+        assert_eq!(result.metrics.contracts, 1);
+        assert_eq!(result.metrics.functions, 2);
+        // `upgradeTo` stores its argument without masking it, so it isn't known to be an address
+        assert_eq!(result.metrics.type_accuracy, 0.0);
+        assert_eq!(result.metrics.storage_recall, Some(1.0));
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_solc_combined_json() {
+        let path = std::env::temp_dir().join("heimdall-test-evaluate-combined.json");
+        fs::write(
+            &path,
+            format!(
+                r#"{{"contracts": {{
+                    "src/Implementation.sol:Implementation": {{"abi": "[]", "bin-runtime": "{}"}},
+                    "src/IProxy.sol:IProxy": {{"abi": [], "bin-runtime": ""}}
+                }}}}"#,
+                bytecode()
+            ),
+        )
+        .unwrap();
+
+        let result = evaluate(EvaluateArgs {
+            target: path.to_string_lossy().to_string(),
+            verbose: Verbosity::new(0, 0),
+            timeout: 10000,
+            format: String::from("json"),
+            output: String::from(""),
+            name: String::from(""),
+        })
+        .await
+        .unwrap();
+        fs::remove_file(&path).unwrap();
+
+        // both selectors are missing from the empty ABI, and there's no layout to compare
+        assert_eq!(result.contracts.len(), 1);
+        assert_eq!(result.contracts[0].name, "Implementation");
+        assert_eq!(result.contracts[0].functions.unexpected.len(), 2);
+        assert_eq!(result.metrics.storage_recall, None);
+    }
+}
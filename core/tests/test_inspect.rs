@@ -24,6 +24,7 @@ mod integration_tests {
             name: String::from(""),
             output: String::from("output"),
             skip_resolving: true,
+            max_depth: None,
         };
 
         let _ = heimdall_core::inspect::inspect(args).await.unwrap();
@@ -42,6 +43,7 @@ mod integration_tests {
             name: String::from(""),
             output: String::from("output"),
             skip_resolving: true,
+            max_depth: None,
         };
 
         let _ = heimdall_core::inspect::inspect(args).await.unwrap();
@@ -0,0 +1,73 @@
+#[cfg(test)]
+mod integration_tests {
+    use clap_verbosity_flag::Verbosity;
+    use ethers::types::U256;
+
+    use heimdall_core::layout::{layout, util::SlotKind, LayoutArgsBuilder};
+
+    /// Stores `msg.sender` in slot 0 from its fallback, and reads the EIP-1967 implementation
+    /// slot.
+    const PROXY: &str =
+        "336000557f360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc545000";
+
+    /// `initialize()` stores `msg.sender` in slot 0 and sets a mapping at slot 1, and
+    /// `upgradeTo(address)` writes the EIP-1967 implementation slot.
+    const IMPLEMENTATION: &str = "./tests/testdata/implementation.hex";
+
+    #[tokio::test]
+    async fn test_layout_recovers_mappings() {
+        let args = LayoutArgsBuilder::new()
+            .target(String::from(IMPLEMENTATION))
+            .verbose(Verbosity::new(0, 0))
+            .build()
+            .unwrap();
+        let result = layout(args).await.unwrap();
+
+        assert!(result.implementation.is_none());
+        assert_eq!(result.layout.len(), 3);
+        assert_eq!(result.layout[0].slot, U256::zero());
+        assert!(result.layout[0].writes.contains("0x8129fc1c"));
+        assert_eq!(result.layout[1].slot, U256::from(1));
+        assert_eq!(result.layout[1].kind, SlotKind::Mapping);
+    }
+
+    #[tokio::test]
+    async fn test_layout_write_set() {
+        let args = LayoutArgsBuilder::new()
+            .target(String::from(IMPLEMENTATION))
+            .verbose(Verbosity::new(0, 0))
+            .build()
+            .unwrap();
+        let result = layout(args).await.unwrap();
+
+        // initialize() writes slot 0 and the mapping family at slot 1
+        let initialize = &result.write_set["0x8129fc1c"];
+        assert_eq!(initialize.len(), 2);
+        assert_eq!(initialize[0].slot, U256::zero());
+        assert_eq!(initialize[1].slot, U256::from(1));
+        assert_eq!(initialize[1].kind, SlotKind::Mapping);
+
+        // upgradeTo(address) writes only the EIP-1967 implementation slot
+        assert_eq!(result.write_set["0x3659cfe6"].len(), 1);
+        assert!(!result.write_set.contains_key("fallback"));
+    }
+
+    #[tokio::test]
+    async fn test_layout_proxy_collisions() {
+        let args = LayoutArgsBuilder::new()
+            .target(String::from(PROXY))
+            .verbose(Verbosity::new(0, 0))
+            .implementation(String::from(IMPLEMENTATION))
+            .build()
+            .unwrap();
+        let result = layout(args).await.unwrap();
+
+        assert_eq!(result.layout.len(), 2);
+        assert!(result.layout[0].writes.contains("fallback"));
+
+        // the implementation slot is shared by design, so only slot 0 collides
+        assert_eq!(result.collisions.len(), 1);
+        assert_eq!(result.collisions[0].slot, U256::zero());
+        assert!(result.collisions[0].implementation.writes.contains("0x8129fc1c"));
+    }
+}
@@ -0,0 +1,29 @@
+#[cfg(test)]
+mod integration_tests {
+    use clap_verbosity_flag::Verbosity;
+
+    use heimdall_core::lineage::{lineage, LineageArgsBuilder};
+
+    #[tokio::test]
+    async fn test_lineage_requires_address() {
+        let args = LineageArgsBuilder::new()
+            .target(String::from("0x6000600055"))
+            .verbose(Verbosity::new(0, 0))
+            .build()
+            .unwrap();
+        assert!(lineage(args).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_lineage_requires_explorer_or_index() {
+        for format in ["svg", "markdown"] {
+            let args = LineageArgsBuilder::new()
+                .target(String::from("0x1111111111111111111111111111111111111111"))
+                .verbose(Verbosity::new(0, 0))
+                .format(String::from(format))
+                .build()
+                .unwrap();
+            assert!(lineage(args).await.is_err());
+        }
+    }
+}
@@ -0,0 +1,69 @@
+#[cfg(test)]
+mod integration_tests {
+    use clap_verbosity_flag::Verbosity;
+
+    use heimdall_core::locate::{locate, LocateArgsBuilder};
+
+    /// `initialize()` stores `msg.sender` in slot 0 from a single statement, which the SSTORE at
+    /// 0x1e belongs to.
+    const IMPLEMENTATION: &str = "./tests/testdata/implementation.hex";
+
+    #[tokio::test]
+    async fn test_locate_sstore() {
+        let args = LocateArgsBuilder::new()
+            .target(String::from(IMPLEMENTATION))
+            .pc(String::from("0x1e"))
+            .verbose(Verbosity::new(0, 0))
+            .skip_resolving(true)
+            .build()
+            .unwrap();
+        let result = locate(args).await.unwrap();
+
+        assert_eq!(result.opcode, "SSTORE");
+        assert_eq!(result.locations.len(), 1);
+        let location = &result.locations[0];
+        assert_eq!(location.selector, "0x8129fc1c");
+        assert_eq!(location.function.as_deref(), Some("Unresolved_8129fc1c()"));
+        assert_eq!(location.statement.as_deref(), Some("stor_b = msg.sender;"));
+        assert_eq!(location.statement_pcs, Some((0x1a, 0x1e)));
+        assert_eq!(location.expression, "SSTORE(0, msg.sender)");
+
+        // the pc may be given in decimal, too
+        let args = LocateArgsBuilder::new()
+            .target(String::from(IMPLEMENTATION))
+            .pc(String::from("30"))
+            .verbose(Verbosity::new(0, 0))
+            .skip_resolving(true)
+            .build()
+            .unwrap();
+        assert_eq!(locate(args).await.unwrap().locations, result.locations);
+    }
+
+    #[tokio::test]
+    async fn test_locate_dispatcher() {
+        // the dispatcher's CALLDATALOAD isn't executed by any function
+        let args = LocateArgsBuilder::new()
+            .target(String::from(IMPLEMENTATION))
+            .pc(String::from("0x2"))
+            .verbose(Verbosity::new(0, 0))
+            .skip_resolving(true)
+            .build()
+            .unwrap();
+        let result = locate(args).await.unwrap();
+
+        assert_eq!(result.opcode, "CALLDATALOAD");
+        assert!(result.locations.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_locate_push_data() {
+        let args = LocateArgsBuilder::new()
+            .target(String::from(IMPLEMENTATION))
+            .pc(String::from("0x1"))
+            .verbose(Verbosity::new(0, 0))
+            .skip_resolving(true)
+            .build()
+            .unwrap();
+        assert!(locate(args).await.is_err());
+    }
+}
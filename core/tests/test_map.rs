@@ -0,0 +1,29 @@
+#[cfg(test)]
+mod integration_tests {
+    use clap_verbosity_flag::Verbosity;
+
+    use heimdall_core::map::{map, MapArgsBuilder};
+
+    #[tokio::test]
+    async fn test_map_requires_address() {
+        let args = MapArgsBuilder::new()
+            .target(String::from("0x6000600055"))
+            .verbose(Verbosity::new(0, 0))
+            .block(Some(1))
+            .build()
+            .unwrap();
+        assert!(map(args).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_map_invalid_format() {
+        let args = MapArgsBuilder::new()
+            .target(String::from("0x1111111111111111111111111111111111111111"))
+            .verbose(Verbosity::new(0, 0))
+            .format(String::from("svg"))
+            .block(Some(1))
+            .build()
+            .unwrap();
+        assert!(map(args).await.is_err());
+    }
+}
@@ -0,0 +1,64 @@
+#[cfg(test)]
+mod integration_tests {
+    use clap_verbosity_flag::Verbosity;
+
+    use heimdall_core::metrics::{metrics, MetricsArgs};
+
+    #[tokio::test]
+    async fn test_metrics_simple() {
+        let result = metrics(MetricsArgs {
+            target: String::from("731bf797219482a29013d804ad96d1c6f84fba4c453014608060405260043610610058576000357c0100000000000000000000000000000000000000000000000000000000900463ffffffff16806319045a251461005d575b600080fd5b6100c56004803603810190808035600019169060200190929190803590602001908201803590602001908080601f0160208091040260200160405190810160405280939291908181526020018383808284378201915050505050509192919290505050610107565b604051808273ffffffffffffffffffffffffffffffffffffffff1673ffffffffffffffffffffffffffffffffffffffff16815260200191505060405180910390f35b6000806000806041855114151561012157600093506101f6565b6020850151925060408501519150606085015160001a9050601b8160ff16101561014c57601b810190505b601b8160ff16141580156101645750601c8160ff1614155b1561017257600093506101f6565b600186828585604051600081526020016040526040518085600019166000191681526020018460ff1660ff1681526020018360001916600019168152602001826000191660001916815260200194505050505060206040516020810390808403906000865af11580156101e9573d6000803e3d6000fd5b5050506020604051035193505b505050929150505600a165627a7a72305820aacffa0494cd3f043493eee9c720bca9d5ef505ae7230ffc3d88c49ceeb7441e0029"),
+            verbose: Verbosity::new(0, 0),
+            rpc_url: String::from(""),
+            output: String::from(""),
+            name: String::from(""),
+            timeout: 10000,
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(result.instructions, result.opcode_classes.values().sum::<usize>());
+        assert!(result.basic_blocks > 1);
+        assert_eq!(result.functions.len(), 1);
+        assert_eq!(result.functions[0].selector, "0x19045a25");
+        assert!(!result.functions[0].timed_out);
+        assert!(result.functions[0].cyclomatic_complexity > 1);
+        assert!(result.functions[0].max_stack_depth > 0);
+
+        // the contract ends with bzzr metadata, and each block has a single owner
+        assert_eq!(result.codesize.metadata, 43);
+        assert!(result.codesize.dispatcher > 0);
+        assert!(result.codesize.functions.contains_key("0x19045a25"));
+        assert_eq!(
+            result.codesize.dispatcher +
+                result.codesize.functions.values().sum::<usize>() +
+                result.codesize.shared +
+                result.codesize.unattributed +
+                result.codesize.embedded_bytecode +
+                result.codesize.data +
+                result.codesize.metadata,
+            result.size
+        );
+    }
+
+    #[tokio::test]
+    async fn test_metrics_jump_table_dispatcher() {
+        // copies the entry point for selector % 2 from a jump table at the end of the code
+        let result = metrics(MetricsArgs {
+            target: String::from("60003560e01c8060029006602002604501602090600039600051565b60006000fd5b8063aaaaaaaa1415601b576001600055005b8063bbbbbbbb1415601b5760026000550000000000000000000000000000000000000000000000000000000000000000210000000000000000000000000000000000000000000000000000000000000033"),
+            verbose: Verbosity::new(0, 0),
+            rpc_url: String::from(""),
+            output: String::from(""),
+            name: String::from(""),
+            timeout: 10000,
+        })
+        .await
+        .unwrap();
+
+        let selectors =
+            result.functions.iter().map(|function| function.selector.as_str()).collect::<Vec<_>>();
+        assert_eq!(selectors, vec!["0xaaaaaaaa", "0xbbbbbbbb"]);
+        assert_eq!(result.functions[1].entry_point, 0x33);
+        assert_eq!(result.codesize.data, 64);
+    }
+}
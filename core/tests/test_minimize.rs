@@ -0,0 +1,42 @@
+#[cfg(test)]
+mod integration_tests {
+    use clap_verbosity_flag::Verbosity;
+
+    use heimdall_core::minimize::{minimize, MinimizeArgs};
+
+    #[tokio::test]
+    async fn test_minimize_precompile() {
+        let bytecode = "731bf797219482a29013d804ad96d1c6f84fba4c453014608060405260043610610058576000357c0100000000000000000000000000000000000000000000000000000000900463ffffffff16806319045a251461005d575b600080fd5b6100c56004803603810190808035600019169060200190929190803590602001908201803590602001908080601f0160208091040260200160405190810160405280939291908181526020018383808284378201915050505050509192919290505050610107565b604051808273ffffffffffffffffffffffffffffffffffffffff1673ffffffffffffffffffffffffffffffffffffffff16815260200191505060405180910390f35b6000806000806041855114151561012157600093506101f6565b6020850151925060408501519150606085015160001a9050601b8160ff16101561014c57601b810190505b601b8160ff16141580156101645750601c8160ff1614155b1561017257600093506101f6565b600186828585604051600081526020016040526040518085600019166000191681526020018460ff1660ff1681526020018360001916600019168152602001826000191660001916815260200194505050505060206040516020810390808403906000865af11580156101e9573d6000803e3d6000fd5b5050506020604051035193505b505050929150505600a165627a7a72305820aacffa0494cd3f043493eee9c720bca9d5ef505ae7230ffc3d88c49ceeb7441e0029";
+
+        let result = minimize(MinimizeArgs {
+            target: String::from(bytecode),
+            until_error: String::from("ecrecover"),
+            verbose: Verbosity::new(0, 0),
+            rpc_url: String::from(""),
+            output: String::from(""),
+            name: String::from(""),
+            timeout: 10000,
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(result.original_size, bytecode.len() / 2);
+        assert!(result.minimized_size < result.original_size);
+    }
+
+    #[tokio::test]
+    async fn test_minimize_does_not_reproduce() {
+        let result = minimize(MinimizeArgs {
+            target: String::from("6080604052348015600f57600080fd5b50"),
+            until_error: String::from("this pattern never matches"),
+            verbose: Verbosity::new(0, 0),
+            rpc_url: String::from(""),
+            output: String::from(""),
+            name: String::from(""),
+            timeout: 10000,
+        })
+        .await;
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,58 @@
+#[cfg(test)]
+mod integration_tests {
+    use clap_verbosity_flag::Verbosity;
+    use ethers::{
+        types::{H160, U256},
+        utils::get_contract_address,
+    };
+
+    use heimdall_core::predeploy::{predeploy, PredeployArgs};
+
+    #[tokio::test]
+    async fn test_predeploy_runtime_and_owner() {
+        // constructor: CALLER PUSH1 0x00 SSTORE, then CODECOPY and RETURN the 6 bytes of runtime
+        // code at offset 0x10, which is PUSH1 0x01 PUSH1 0x00 SSTORE STOP
+        let result = predeploy(PredeployArgs {
+            target: String::from("336000556006601060003960066000f3600160005500"),
+            verbose: Verbosity::new(0, 0),
+            rpc_url: String::from(""),
+            deployer: String::from("0x1111111111111111111111111111111111111111"),
+            nonce: Some(7),
+            value: 0,
+            output: String::from(""),
+            name: String::from(""),
+            timeout: 10000,
+        })
+        .await
+        .unwrap();
+
+        assert!(result.success);
+        assert_eq!(result.runtime_bytecode, "0x600160005500");
+        assert_eq!(
+            result.address,
+            Some(format!("{:?}", get_contract_address(H160::from_slice(&[0x11; 20]), 7)))
+        );
+
+        let owner = result.owner.unwrap();
+        assert_eq!(owner.address, "0x1111111111111111111111111111111111111111");
+        assert_eq!(owner.slot, Some(U256::zero()));
+    }
+
+    #[tokio::test]
+    async fn test_predeploy_rejects_addresses() {
+        let result = predeploy(PredeployArgs {
+            target: String::from("0x1111111111111111111111111111111111111111"),
+            verbose: Verbosity::new(0, 0),
+            rpc_url: String::from(""),
+            deployer: String::from(""),
+            nonce: None,
+            value: 0,
+            output: String::from(""),
+            name: String::from(""),
+            timeout: 10000,
+        })
+        .await;
+
+        assert!(result.is_err());
+    }
+}
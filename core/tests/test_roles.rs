@@ -0,0 +1,43 @@
+#[cfg(test)]
+mod integration_tests {
+    use clap_verbosity_flag::Verbosity;
+    use ethers::types::{H256, U256};
+
+    use heimdall_core::roles::{output::build_dot, roles, RolesArgsBuilder};
+
+    /// `0xaaaaaaaa` is gated by `MINTER_ROLE`, and `0xbbbbbbbb` by the admin of the role it's
+    /// passed, read from `keccak256(role . 0) + 1`.
+    const ACCESS_CONTROL: &str = "60003560e01c8063aaaaaaaa14601a5763bbbbbbbb14606057005b7f9f2df0fed2c77648de5860a4cc508cd0818c85b8b8a1ab4ceeef8d981c8956a66000526000602052604060002033600052602052604060002054605e5760006000fd5b005b600435600052600060205260406000206001015460005260406000203360005260205260406000205460925760006000fd5b00";
+
+    #[tokio::test]
+    async fn test_roles_gate_functions() {
+        let args = RolesArgsBuilder::new()
+            .target(String::from(ACCESS_CONTROL))
+            .verbose(Verbosity::new(0, 0))
+            .build()
+            .unwrap();
+        let result = roles(args).await.unwrap();
+
+        assert_eq!(result.slot, Some(U256::zero()));
+        assert_eq!(result.roles.len(), 1);
+        assert_eq!(result.roles[0].name.as_deref(), Some("MINTER_ROLE"));
+        assert_eq!(result.roles[0].admin, None::<H256>);
+        assert!(result.roles[0].functions.contains("0xaaaaaaaa"));
+        assert!(result.admin_functions.contains("0xbbbbbbbb"));
+
+        let dot = build_dot(&result);
+        assert!(dot.contains("MINTER_ROLE"));
+        assert!(dot.contains("admin of the given role"));
+    }
+
+    #[tokio::test]
+    async fn test_roles_invalid_format() {
+        let args = RolesArgsBuilder::new()
+            .target(String::from(ACCESS_CONTROL))
+            .verbose(Verbosity::new(0, 0))
+            .format(String::from("svg"))
+            .build()
+            .unwrap();
+        assert!(roles(args).await.is_err());
+    }
+}
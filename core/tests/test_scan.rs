@@ -0,0 +1,131 @@
+#[cfg(test)]
+mod integration_tests {
+    use clap_verbosity_flag::Verbosity;
+
+    use heimdall_core::scan::{rules::Severity, scan, ScanArgsBuilder};
+
+    // 0x12345678 loops until i reaches sload(0), 0x87654321 until i reaches 10
+    const LOOPS: &str = "60003560e01c80631234567814601a57638765432114603557005b60005b600054811015603357806001015450600101601d565b005b60005b600a8110156047576001016038565b00";
+
+    #[tokio::test]
+    async fn test_scan_batch_transfer() {
+        // a dispatcher for batchTransfer(address[],uint256) and initialize()
+        let bytecode = "60003560e01c806383f12fec14601a57638129fc1c14601a57005b00";
+
+        let args = ScanArgsBuilder::new()
+            .target(String::from(bytecode))
+            .verbose(Verbosity::new(0, 0))
+            .build()
+            .unwrap();
+        let result = scan(args).await.unwrap();
+
+        assert_eq!(result.contracts, 1);
+        assert_eq!(result.findings.len(), 2);
+        assert_eq!(result.findings[0].rule, "batch-transfer-overflow");
+        assert_eq!(result.findings[0].severity, Severity::High);
+        assert_eq!(result.findings[0].contract, "bytecode");
+        assert!(!result.findings[0].references.is_empty());
+        assert_eq!(result.findings[1].rule, "exposed-initializer");
+    }
+
+    #[tokio::test]
+    async fn test_scan_min_severity() {
+        let args = ScanArgsBuilder::new()
+            .target(String::from("60003560e01c638129fc1c14601057005b00"))
+            .verbose(Verbosity::new(0, 0))
+            .min_severity(String::from("low"))
+            .build()
+            .unwrap();
+
+        let result = scan(args).await.unwrap();
+
+        assert!(result.findings.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_scan_corpus() {
+        let corpus = std::env::temp_dir().join("heimdall-scan-corpus.txt");
+        std::fs::write(
+            &corpus,
+            "token,60003560e01c806383f12fec14601157005b00\nclean,6080604052\nbroken,0xzz\n",
+        )
+        .unwrap();
+
+        let args = ScanArgsBuilder::new()
+            .target(corpus.to_string_lossy().to_string())
+            .verbose(Verbosity::new(0, 0))
+            .corpus(true)
+            .build()
+            .unwrap();
+        let result = scan(args).await.unwrap();
+        std::fs::remove_file(&corpus).unwrap();
+
+        assert_eq!(result.contracts, 3);
+        assert_eq!(result.findings.len(), 1);
+        assert_eq!(result.findings[0].contract, "token");
+    }
+
+    #[tokio::test]
+    async fn test_scan_unprotected_initializer() {
+        // initialize() stores msg.sender in slot 0 without any checks
+        let args = ScanArgsBuilder::new()
+            .target(String::from("60003560e01c638129fc1c14601057005b3360005500"))
+            .verbose(Verbosity::new(0, 0))
+            .build()
+            .unwrap();
+        let result = scan(args).await.unwrap();
+
+        assert!(result.findings.iter().any(|finding| finding.rule == "unprotected-initializer" &&
+            finding.severity == Severity::High));
+    }
+
+    #[tokio::test]
+    async fn test_scan_initializer_with_flag() {
+        // initialize() reverts if slot 0 is set, then sets it
+        let args = ScanArgsBuilder::new()
+            .target(String::from(
+                "60003560e01c638129fc1c14601057005b60005415601c57600080fd5b600160005500",
+            ))
+            .verbose(Verbosity::new(0, 0))
+            .build()
+            .unwrap();
+        let result = scan(args).await.unwrap();
+
+        assert!(!result.findings.iter().any(|finding| finding.rule == "unprotected-initializer"));
+        assert!(result.findings.iter().any(|finding| finding.rule == "exposed-initializer"));
+    }
+
+    #[tokio::test]
+    async fn test_scan_unbounded_loop() {
+        let args = ScanArgsBuilder::new()
+            .target(String::from(LOOPS))
+            .verbose(Verbosity::new(0, 0))
+            .build()
+            .unwrap();
+        let result = scan(args).await.unwrap();
+
+        let loops = result
+            .findings
+            .iter()
+            .filter(|finding| finding.rule == "unbounded-loop")
+            .collect::<Vec<_>>();
+        assert_eq!(loops.len(), 1);
+        assert_eq!(loops[0].severity, Severity::Medium);
+        assert!(loops[0].description.starts_with("0x12345678 loops over"));
+        assert!(loops[0].evidence[0].contains("storage slot 0x0"));
+    }
+
+    #[tokio::test]
+    async fn test_scan_unbounded_loop_min_severity() {
+        let args = ScanArgsBuilder::new()
+            .target(String::from(LOOPS))
+            .verbose(Verbosity::new(0, 0))
+            .min_severity(String::from("high"))
+            .build()
+            .unwrap();
+
+        let result = scan(args).await.unwrap();
+
+        assert!(!result.findings.iter().any(|finding| finding.rule == "unbounded-loop"));
+    }
+}
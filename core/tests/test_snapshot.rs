@@ -18,6 +18,8 @@ mod benchmark {
                 name: String::from(""),
                 output: String::from(""),
                 timeout: 10000,
+                diff: String::new(),
+                token_list: String::new(),
             };
             let _ = heimdall_core::snapshot::snapshot(args).await.unwrap();
         }
@@ -38,6 +40,8 @@ mod benchmark {
                 name: String::from(""),
                 output: String::from(""),
                 timeout: 10000,
+                diff: String::new(),
+                token_list: String::new(),
             };
             let _ = heimdall_core::snapshot::snapshot(args).await.unwrap();
         }
@@ -50,7 +54,8 @@ mod benchmark {
 mod integration_tests {
     use clap_verbosity_flag::Verbosity;
     use heimdall_common::utils::io::file::delete_path;
-    use heimdall_core::snapshot::SnapshotArgs;
+    use heimdall_core::snapshot::{diff::SnapshotSummary, SnapshotArgs};
+    use std::collections::{BTreeMap, BTreeSet};
 
     #[tokio::test]
     async fn test_snapshot_weth() {
@@ -64,6 +69,8 @@ mod integration_tests {
             name: String::from(""),
             output: String::from(""),
             timeout: 10000,
+            diff: String::new(),
+            token_list: String::new(),
         };
 
         let _ = heimdall_core::snapshot::snapshot(args).await.unwrap();
@@ -81,11 +88,43 @@ mod integration_tests {
             name: String::from(""),
             output: String::from(""),
             timeout: 10000,
+            diff: String::new(),
+            token_list: String::new(),
         };
 
         let _ = heimdall_core::snapshot::snapshot(args).await.unwrap();
     }
 
+    #[tokio::test]
+    async fn test_snapshot_revert_reasons() {
+        // `0xaaaaaaaa` reverts with "no" unless the caller is stored in slot 0, and `0xbbbbbbbb`
+        // reverts with `Unauthorized()` unless its argument is set
+        let args = SnapshotArgs {
+            target: String::from("60003560e01c8063aaaaaaaa14601d5763bbbbbbbb14604b57600080fd5b33600054146049576308c379a060e01b60005260206004526002602452616e6f60f01b60445260646000fd5b005b6004356062576382b4290060e01b60005260046000fd5b00"),
+            verbose: Verbosity::new(0, 0),
+            rpc_url: String::from(""),
+            default: true,
+            skip_resolving: true,
+            no_tui: true,
+            name: String::from(""),
+            output: String::from(""),
+            timeout: 10000,
+            diff: String::new(),
+            token_list: String::new(),
+        };
+
+        let result = heimdall_core::snapshot::snapshot(args).await.unwrap();
+        let summary = SnapshotSummary::from_snapshots(&result.snapshots);
+
+        assert_eq!(
+            summary.reverts.reasons,
+            BTreeMap::from([
+                (String::from("\"no\""), BTreeSet::from([String::from("0xaaaaaaaa")])),
+                (String::from("0x82b42900"), BTreeSet::from([String::from("0xbbbbbbbb")])),
+            ])
+        );
+    }
+
     /// Thorough testing for snapshot across a large number of contracts
     /// Runs on the top 100 contracts for 2023-06-26
     ///
@@ -172,6 +211,8 @@ mod integration_tests {
                 name: String::from(""),
                 output: String::from(""),
                 timeout: 10000,
+                diff: String::new(),
+                token_list: String::new(),
             };
             let _ = heimdall_core::snapshot::snapshot(args).await.unwrap();
         }
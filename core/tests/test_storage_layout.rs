@@ -0,0 +1,49 @@
+#[cfg(test)]
+mod integration_tests {
+    use clap_verbosity_flag::Verbosity;
+
+    use heimdall_core::storage_layout::{storage_layout, StorageLayoutArgsBuilder};
+
+    /// `0x11111111` returns the address in the low 20 bytes of slot 0, `0x22222222` the byte
+    /// above it, `0x33333333` sets a mapping at slot 1, and `0x44444444` returns slot 2.
+    const PACKED: &str = "60003560e01c80631111111114602e578063222222221460505780633333333314606257634444444414607757005b60005473ffffffffffffffffffffffffffffffffffffffff1660005260206000f35b60005460a01c60ff1660005260206000f35b60043560005260016020526001604060002055005b60025460005260206000f3";
+
+    #[tokio::test]
+    async fn test_storage_layout_packed_slot() {
+        let args = StorageLayoutArgsBuilder::new()
+            .target(String::from(PACKED))
+            .verbose(Verbosity::new(0, 0))
+            .build()
+            .unwrap();
+        let result = storage_layout(args).await.unwrap();
+
+        let storage = result
+            .storage
+            .iter()
+            .map(|entry| (entry.slot.as_str(), entry.offset, entry.type_id.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            storage,
+            vec![
+                ("0", 0, "t_address"),
+                ("0", 20, "t_uint8"),
+                ("1", 0, "t_mapping(t_bytes32,t_uint256)"),
+                ("2", 0, "t_uint256"),
+            ]
+        );
+        assert_eq!(result.types["t_mapping(t_bytes32,t_uint256)"].encoding, "mapping");
+    }
+
+    #[tokio::test]
+    async fn test_storage_layout_without_storage() {
+        let args = StorageLayoutArgsBuilder::new()
+            .target(String::from("60016000f3"))
+            .verbose(Verbosity::new(0, 0))
+            .build()
+            .unwrap();
+        let result = storage_layout(args).await.unwrap();
+
+        assert!(result.storage.is_empty());
+        assert!(result.types.is_empty());
+    }
+}
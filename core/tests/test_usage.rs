@@ -0,0 +1,24 @@
+#[cfg(test)]
+mod integration_tests {
+    use clap_verbosity_flag::Verbosity;
+
+    use heimdall_core::usage::{usage, UsageArgsBuilder};
+
+    #[tokio::test]
+    async fn test_usage_requires_address_and_explorer() {
+        for (target, format) in [
+            ("0x6000600055", "markdown"),
+            ("0x1111111111111111111111111111111111111111", "svg"),
+            ("0x1111111111111111111111111111111111111111", "markdown"),
+        ] {
+            let args = UsageArgsBuilder::new()
+                .target(String::from(target))
+                .verbose(Verbosity::new(0, 0))
+                .skip_resolving(true)
+                .format(String::from(format))
+                .build()
+                .unwrap();
+            assert!(usage(args).await.is_err());
+        }
+    }
+}